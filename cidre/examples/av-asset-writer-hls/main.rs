@@ -1,10 +1,8 @@
 #[cfg(target_os = "macos")]
 mod macos {
-    use std::{fs, io::Write, path::PathBuf};
-
     use cidre::{
-        arc, av, av::AssetWriterDelegate, cm, define_obj_type, dispatch, ns, objc, objc::Obj, sc,
-        sc::StreamOutput, ut,
+        arc, av, av::hls, av::AssetWriterDelegate, cm, define_obj_type, dispatch, ns, objc,
+        objc::Obj, sc, sc::StreamOutput, ut,
     };
 
     #[repr(C)]
@@ -49,67 +47,7 @@ mod macos {
         }
     }
 
-    struct SegmentWriter {
-        n: u32,
-        dir: PathBuf,
-        base_name: String,
-        target_dur: u32,
-    }
-
-    impl SegmentWriter {
-        fn init_path(&self) -> PathBuf {
-            self.dir.join(format!("{}.mp4", self.base_name))
-        }
-
-        fn playlist_path(&self) -> PathBuf {
-            self.dir.join(format!("{}.m3u8", self.base_name))
-        }
-
-        fn segment_path(&self) -> PathBuf {
-            self.dir.join(self.segment_name())
-        }
-
-        fn segment_name(&self) -> String {
-            format!("{}{}.m4s", self.base_name, self.n)
-        }
-
-        fn write_init(&mut self, data: &[u8]) {
-            fs::write(self.init_path(), data).unwrap();
-
-            let target_dur = self.target_dur;
-            let base_name = &self.base_name;
-            fs::write(
-                self.playlist_path(),
-                format!(
-                    "#EXTM3U
-#EXT-X-VERSION:7
-#EXT-X-TARGETDURATION:{target_dur}
-#EXT-X-MEDIA-SEQUENCE:0
-#EXT-X-MAP:URI=\"{base_name}.mp4\"
-"
-                ),
-            )
-            .unwrap();
-        }
-
-        fn write_segment(&mut self, data: &[u8], duration: f64) {
-            fs::write(self.segment_path(), data).unwrap();
-            let mut file = fs::File::options()
-                .append(true)
-                .open(self.playlist_path())
-                .unwrap();
-            let segment_name = self.segment_name();
-            writeln!(file, "\n#EXTINF:{duration},\n{segment_name}").unwrap();
-        }
-
-        fn write_end(&mut self) {
-            let mut file = fs::File::options()
-                .append(true)
-                .open(self.playlist_path())
-                .unwrap();
-            writeln!(file, "\n#EXT-X-ENDLIST").unwrap();
-        }
-    }
+    type SegmentWriter = hls::SegmentManager<hls::DirStorage>;
 
     define_obj_type!(
         WriterDelegate + av::AssetWriterDelegateImpl,
@@ -130,22 +68,13 @@ mod macos {
             segment_report: Option<&av::AssetSegmentReport>,
         ) {
             let ctx = self.inner_mut();
-            match segment_type {
-                av::AssetSegmentType::Initialization => {
-                    ctx.write_init(segment_data.as_slice());
-                }
-                av::AssetSegmentType::Separable => {
-                    let duration = segment_report
-                        .unwrap()
-                        .track_reports()
-                        .get(0)
-                        .unwrap()
-                        .duration()
-                        .as_secs();
-                    ctx.write_segment(segment_data.as_slice(), duration);
-                    eprintln!("[{}]{duration}", ctx.n);
-                    ctx.n += 1;
-                }
+            // panics must not unwind into the writer
+            if let Err(e) = ctx.handle_segment(segment_data, segment_type, segment_report) {
+                eprintln!("failed to handle segment: {e}");
+                return;
+            }
+            if let Some(s) = ctx.playlist().segments.last() {
+                eprintln!("[{}]{}", s.uri, s.duration);
             }
         }
     }
@@ -177,12 +106,13 @@ mod macos {
         const FPS: i32 = 30;
         const TARGET_DUR: u32 = 6;
 
-        let mut delegate = WriterDelegate::with(SegmentWriter {
-            n: 0,
-            dir: "/tmp/".into(),
+        let cfg = hls::SegmentManagerCfg {
             base_name: "hls".into(),
-            target_dur: TARGET_DUR,
-        });
+            target_duration: TARGET_DUR,
+            ..Default::default()
+        };
+        let mut delegate =
+            WriterDelegate::with(hls::SegmentManager::new(cfg, hls::DirStorage::new("/tmp/")));
 
         let mut input = av::AssetWriterInput::with_media_type_and_output_settings(
             av::MediaType::video(),
@@ -227,7 +157,7 @@ mod macos {
             stream.stop().await.unwrap();
 
             writer.finish_writing();
-            delegate.inner_mut().write_end();
+            delegate.inner_mut().finish().unwrap();
         } else {
             eprintln!("failed? {:?}", writer.error());
         }
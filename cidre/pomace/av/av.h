@@ -24,6 +24,7 @@ Class AV_CAPTURE_PHOTO_OUTPUT;
 Class AV_CAPTURE_VIDEO_PREVIEW_LAYER;
 
 Class AV_AUDIO_PLAYER_NODE;
+Class AV_AUDIO_SOURCE_NODE;
 Class AV_AUDIO_PLAYER;
 
 Class AV_AUDIO_ENGINE;
@@ -37,8 +38,17 @@ Class AV_ASSET_READER;
 Class AV_ASSET_WRITER_INPUT;
 Class AV_ASSET_WRITER_INPUT_PIXEL_BUFFER_ADAPTOR;
 Class AV_ASSET_READER_TRACK_OUTPUT;
+Class AV_ASSET_READER_AUDIO_MIX_OUTPUT;
+Class AV_ASSET_READER_VIDEO_COMPOSITION_OUTPUT;
 Class AV_ASSET_IMAGE_GENERATOR;
 
+Class AV_MUTABLE_COMPOSITION;
+Class AV_MUTABLE_VIDEO_COMPOSITION;
+Class AV_MUTABLE_VIDEO_COMPOSITION_INSTRUCTION;
+Class AV_MUTABLE_VIDEO_COMPOSITION_LAYER_INSTRUCTION;
+Class AV_MUTABLE_AUDIO_MIX;
+Class AV_MUTABLE_AUDIO_MIX_INPUT_PARAMETERS;
+
 Class AV_OUTPUT_SETTINGS_ASSISTANT;
 
 Class AV_AUDIO_TIME;
@@ -55,6 +65,7 @@ Class AV_AUDIO_FILE;
 Class AV_AUDIO_CHANNEL_LAYOUT;
 
 Class AV_PLAYER;
+Class AV_PLAYER_ITEM;
 
 Class AV_SAMPLE_BUFFER_DISPLAY_LAYER;
 Class AV_SAMPLE_BUFFER_VIDEO_RENDERER;
@@ -142,6 +153,7 @@ static void av_initializer(void)
 #endif
         
         AV_AUDIO_PLAYER_NODE = [AVAudioPlayerNode class];
+        AV_AUDIO_SOURCE_NODE = [AVAudioSourceNode class];
         AV_AUDIO_PLAYER = [AVAudioPlayer class];
         
         AV_AUDIO_ENGINE = [AVAudioEngine class];
@@ -157,6 +169,9 @@ static void av_initializer(void)
         
         
         AV_URL_ASSET = [AVURLAsset class];
+        AV_MUTABLE_COMPOSITION = [AVMutableComposition class];
+        AV_MUTABLE_AUDIO_MIX = [AVMutableAudioMix class];
+        AV_MUTABLE_AUDIO_MIX_INPUT_PARAMETERS = [AVMutableAudioMixInputParameters class];
         
         
 #if TARGET_OS_WATCH
@@ -166,6 +181,12 @@ static void av_initializer(void)
         AV_ASSET_WRITER_INPUT_PIXEL_BUFFER_ADAPTOR = [AVAssetWriterInputPixelBufferAdaptor class];
         AV_ASSET_READER_TRACK_OUTPUT = [AVAssetReaderTrackOutput class];
         AV_ASSET_READER = [AVAssetReader class];
+        AV_ASSET_READER_AUDIO_MIX_OUTPUT = [AVAssetReaderAudioMixOutput class];
+        AV_ASSET_READER_VIDEO_COMPOSITION_OUTPUT = [AVAssetReaderVideoCompositionOutput class];
+        
+        AV_MUTABLE_VIDEO_COMPOSITION = [AVMutableVideoComposition class];
+        AV_MUTABLE_VIDEO_COMPOSITION_INSTRUCTION = [AVMutableVideoCompositionInstruction class];
+        AV_MUTABLE_VIDEO_COMPOSITION_LAYER_INSTRUCTION = [AVMutableVideoCompositionLayerInstruction class];
         
         AV_SAMPLE_BUFFER_DISPLAY_LAYER = [AVSampleBufferDisplayLayer class];
         
@@ -181,6 +202,7 @@ static void av_initializer(void)
         AV_AUDIO_CHANNEL_LAYOUT = [AVAudioChannelLayout class];
         
         AV_PLAYER = [AVPlayer class];
+        AV_PLAYER_ITEM = [AVPlayerItem class];
 
         AV_SPEECH_SYNTHESIS_VOICE = [AVSpeechSynthesisVoice class];
         AV_SPEECH_SYNTHESIZER = [AVSpeechSynthesizer class];
@@ -1,4 +1,9 @@
-use std::{ffi::c_void, intrinsics::transmute, ops::Deref};
+use std::{
+    ffi::{c_int, c_void},
+    intrinsics::transmute,
+    ops::Deref,
+    os::{fd::FromRawFd, unix::net::UnixStream},
+};
 pub mod base;
 pub mod development;
 pub mod discovery;
@@ -9,7 +14,7 @@ pub mod log;
 pub use base::{Device, Error, Notification};
 pub use discovery::{Action, IfaceConnectionType, QueryBuilder, Speed};
 
-use crate::{arc, cf, os};
+use crate::{arc, cf, os, usbmux};
 
 use self::base::ServiceConnection;
 
@@ -22,6 +27,17 @@ impl Device {
         unsafe { AMDeviceCopyDeviceIdentifier(self) }
     }
 
+    /// Opens usbmux tunnel to `port` of the device.
+    pub fn connect_port(&self, port: u16) -> usbmux::Result<UnixStream> {
+        let mut fd = -1;
+        let res =
+            unsafe { USBMuxConnectByPort(self.connection_id(), port.to_be() as u32, &mut fd) };
+        if res != 0 {
+            return Err(usbmux::Error::Mux(res as i64));
+        }
+        Ok(unsafe { UnixStream::from_raw_fd(fd) })
+    }
+
     /// Connect to the mobile device.
     ///
     /// If you are already connected, this function will attempt to
@@ -360,9 +376,42 @@ impl<'a> Deref for Session<'a> {
     }
 }
 
+impl From<Error> for usbmux::Error {
+    fn from(value: Error) -> Self {
+        usbmux::Error::Backend(value.0)
+    }
+}
+
+/// MobileDevice backend of [`usbmux::Lockdown`].
+impl<'a> usbmux::Lockdown for Session<'a> {
+    type Service = arc::R<ServiceConnection>;
+
+    fn query_type(&mut self) -> usbmux::Result<String> {
+        // MobileDevice has no call for it, ask lockdownd over a separate tunnel
+        let stream = self.connect_port(usbmux::lockdown::PORT)?;
+        usbmux::LockdownClient::new(stream).query_type()
+    }
+
+    fn value(&mut self, domain: Option<&str>, key: Option<&str>) -> usbmux::Result<usbmux::Value> {
+        let domain = domain.map(cf::String::from_str);
+        let key = key.map(cf::String::from_str);
+        let value = self.try_value(domain.as_deref(), key.as_deref())?;
+        // plist types are converted through XML representation
+        let xml = value
+            .to_cf_data(cf::PlistFormat::XmlV1_0)
+            .map_err(|_| usbmux::Error::Plist)?;
+        usbmux::Value::from_xml(xml.as_slice())
+    }
+
+    fn start_service(&mut self, name: &str) -> usbmux::Result<Self::Service> {
+        Ok(self.secure_start_service(&cf::String::from_str(name))?)
+    }
+}
+
 #[link(name = "MobileDevice", kind = "framework")]
 extern "C" {
     fn AMDeviceGetConnectionID(device: &Device) -> u32;
+    fn USBMuxConnectByPort(connection_id: u32, port: u32, handle: &mut c_int) -> c_int;
     fn AMDeviceCopyDeviceIdentifier(device: &Device) -> arc::R<cf::String>;
     fn AMDeviceCopyValue(
         device: &Device,
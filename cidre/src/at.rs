@@ -3,18 +3,25 @@ pub use audio::BalanceFade as AudioBalanceFade;
 pub use audio::Buf as AudioBuf;
 pub use audio::BufList as AudioBufList;
 pub use audio::BufListN as AudioBufListN;
+pub use audio::Bufs as AudioBufs;
 pub use audio::ChannelBitmap as AudioChannelBitmap;
 pub use audio::ChannelCoordinateIndex as AudioChannelCoordinateIndex;
 pub use audio::ChannelDesc as AudioChannelDesc;
 pub use audio::ChannelFlags as AudioChannelFlags;
 pub use audio::ChannelLabel as AudioChannelLabel;
 pub use audio::ChannelLayout as AudioChannelLayout;
+pub use audio::ChannelLayoutBuf as AudioChannelLayoutBuf;
 pub use audio::ChannelLayoutTag as AudioChannelLayoutTag;
 pub use audio::ClassDesc as AudioClassDesc;
 pub use audio::Converter as AudioConverter;
 pub use audio::ConverterRef as AudioConverterRef;
+pub use audio::Dither as AudioDither;
 pub use audio::FileId as AudioFileId;
 pub use audio::FormatPropId as AudioFormatPropId;
+pub use audio::MixMatrix as AudioMixMatrix;
+pub use audio::PcmConverter as AudioPcmConverter;
+pub use audio::RingBuf as AudioRingBuf;
+pub use audio::Sample as AudioSample;
 
 pub use audio::TimeStamp as AudioTimeStamp;
 
@@ -287,6 +287,56 @@ impl FileId {
         }
     }
 
+    /// Fails with [`err::UNSUPPORTED_DATA_FORMAT`] unless `bufs` are of the file data format.
+    fn check_bufs(&self, bufs: &audio::Bufs) -> os::Result {
+        if bufs.buf_count() != 1 {
+            return Err(audio::err::PARAM);
+        }
+        if self.data_format()? != *bufs.asbd() {
+            return Err(err::UNSUPPORTED_DATA_FORMAT);
+        }
+        Ok(())
+    }
+
+    /// Reads LPCM packets into interleaved `bufs` up to its capacity.
+    ///
+    /// Returns number of frames read, `bufs` frames are updated accordingly.
+    /// `bufs` have to be of the file data format.
+    #[doc(alias = "AudioFileReadPacketData")]
+    pub fn read_into(&mut self, starting_packet: isize, bufs: &mut audio::Bufs) -> os::Result<u32> {
+        self.check_bufs(bufs)?;
+        bufs.set_frames(bufs.capacity())?;
+        let mut num_packets = bufs.capacity();
+        let mut num_bytes = bufs.buf_bytes_size() as u32;
+        let ptr = bufs.bytes_mut(0).unwrap().as_mut_ptr();
+        self.read_packets(
+            false,
+            &mut num_bytes,
+            std::ptr::null_mut(),
+            starting_packet,
+            &mut num_packets,
+            ptr,
+        )?;
+        bufs.set_frames(num_packets)?;
+        Ok(num_packets)
+    }
+
+    /// Writes valid frames of interleaved LPCM `bufs` of the file data format.
+    #[doc(alias = "AudioFileWritePackets")]
+    pub fn write_from(&mut self, starting_packet: isize, bufs: &audio::Bufs) -> os::Result<u32> {
+        self.check_bufs(bufs)?;
+        let mut num_packets = bufs.frames();
+        self.write_packets(
+            false,
+            bufs.buf_bytes_size() as u32,
+            std::ptr::null(),
+            starting_packet,
+            &mut num_packets,
+            bufs.bytes(0).unwrap().as_ptr(),
+        )?;
+        Ok(num_packets)
+    }
+
     #[doc(alias = "AudioFileGetPropertyInfo")]
     #[inline]
     pub fn property_info(&self, property_id: PropId) -> os::Result<(usize, bool)> {
@@ -708,6 +758,33 @@ extern "C" {
 mod tests {
     use crate::{at::audio, cf};
 
+    #[test]
+    fn bufs_format() {
+        let path = cf::Url::from_str("file:///tmp/cidre_bufs_format.wav").unwrap();
+        let asbd = audio::StreamBasicDesc::common_f32(48_000.0, 1, true);
+        let mut file = audio::FileId::create(
+            &path,
+            audio::FileTypeId::WAVE,
+            &asbd,
+            audio::FileFlags::ERASE_FILE,
+        )
+        .unwrap();
+
+        let mut bufs = audio::Bufs::new(&asbd, 16).unwrap();
+        bufs.set_frames(16).unwrap();
+        assert_eq!(file.write_from(0, &bufs).unwrap(), 16);
+        assert_eq!(file.read_into(0, &mut bufs).unwrap(), 16);
+
+        let mut other = asbd;
+        other.sample_rate = 44_100.0;
+        let mut bufs = audio::Bufs::new(&other, 16).unwrap();
+        assert_eq!(
+            file.read_into(0, &mut bufs).unwrap_err(),
+            audio::file_err::UNSUPPORTED_DATA_FORMAT
+        );
+        file.close().unwrap();
+    }
+
     #[test]
     fn basics() {
         let path = cf::Url::from_str("file:///tmp/m4a.m4a").unwrap();
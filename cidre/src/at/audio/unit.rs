@@ -56,6 +56,14 @@ pub use properties::VoiceIoMutedSpeechActivityEventListener;
 pub use properties::VoiceIoOtherAudioDuckingCfg;
 pub use properties::VoiceIoSpeechActivityEvent;
 
+pub mod property;
+pub use property::ParamDesc;
+pub use property::Property;
+
+mod offline;
+pub use offline::OfflineRender;
+pub use offline::Source as OfflineSource;
+
 mod parameters;
 pub use parameters::NBandEQFilterType;
 #[cfg(target_os = "macos")]
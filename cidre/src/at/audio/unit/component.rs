@@ -947,12 +947,26 @@ impl Unit {
         }
     }
 
+    pub fn set_prop_slice<T: Sized>(
+        &mut self,
+        prop_id: PropId,
+        scope: Scope,
+        element: Element,
+        val: &[T],
+    ) -> os::Result {
+        let size = std::mem::size_of_val(val) as u32;
+        unsafe {
+            AudioUnitSetProperty(self, prop_id, scope, element, val.as_ptr().cast(), size).result()
+        }
+    }
+
     pub fn params_list(&self, scope: Scope) -> os::Result<Vec<ParamId>> {
         self.prop_vec(PropId::PARAM_LIST, scope, Element::OUTPUT)
     }
 
     pub fn param_info(&self, param_id: ParamId) -> os::Result<ParamInfo> {
-        self.prop(PropId::PARAM_INFO, Scope::GLOBAL, Element(param_id.0))
+        use super::property::Get;
+        ParamInfo::get(self, PropId::PARAM_INFO, Scope::GLOBAL, Element(param_id.0))
     }
 
     pub fn param(
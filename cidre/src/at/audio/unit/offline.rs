@@ -0,0 +1,283 @@
+//! Offline rendering of audio unit chains.
+//!
+//! [`OfflineRender`] feeds the first unit from a Rust [`Source`], connects units
+//! one after another and pulls the last one with synthetic sample time stamps.
+//! Rendered output is deterministic, so effect chains can be compared against golden files.
+
+use crate::{
+    at::{
+        au::{self, Connection, PropId, RenderActionFlags, Scope, Unit},
+        audio,
+    },
+    os,
+};
+
+/// Rust implemented input of [`OfflineRender`].
+pub trait Source {
+    /// Fills [`audio::Bufs::frames`] frames of `bufs` for time stamp `ts`.
+    ///
+    /// `bufs` has the input stream format of the first unit.
+    fn render(&mut self, ts: &audio::TimeStamp, bufs: &mut audio::Bufs) -> os::Result;
+}
+
+impl<F> Source for F
+where
+    F: FnMut(&audio::TimeStamp, &mut audio::Bufs) -> os::Result,
+{
+    fn render(&mut self, ts: &audio::TimeStamp, bufs: &mut audio::Bufs) -> os::Result {
+        self(ts, bufs)
+    }
+}
+
+struct SourceState<S> {
+    source: S,
+    bufs: audio::Bufs,
+}
+
+/// Buffers of the list passed to the render callback.
+unsafe fn list_bufs<'a>(list: *mut audio::BufList<1>) -> &'a mut [audio::Buf] {
+    let n = (*list).number_buffers as usize;
+    std::slice::from_raw_parts_mut((*list).buffers.as_mut_ptr(), n)
+}
+
+extern "C-unwind" fn input_cb<S: Source>(
+    ref_con: *mut SourceState<S>,
+    _io_action_flags: &mut RenderActionFlags,
+    ts: &audio::TimeStamp,
+    _bus_num: u32,
+    frames: u32,
+    io_data: *mut audio::BufList<1>,
+) -> os::Status {
+    let Some(state) = (unsafe { ref_con.as_mut() }) else {
+        return audio::err::PARAM.status();
+    };
+    if io_data.is_null() {
+        return audio::err::PARAM.status();
+    }
+    if frames > state.bufs.capacity() {
+        // converters may pull more than max frames per slice
+        match audio::Bufs::new(state.bufs.asbd(), frames) {
+            Ok(bufs) => state.bufs = bufs,
+            Err(e) => return e.status(),
+        }
+    }
+    if let Err(e) = state.bufs.set_frames(frames) {
+        return e.status();
+    }
+    if let Err(e) = state.source.render(ts, &mut state.bufs) {
+        return e.status();
+    }
+
+    let bufs = unsafe { list_bufs(io_data) };
+    if bufs.len() != state.bufs.buf_count() {
+        return audio::err::PARAM.status();
+    }
+    let size = state.bufs.buf_bytes_size();
+    for (i, buf) in bufs.iter_mut().enumerate() {
+        let src = state.bufs.bytes_mut(i).unwrap();
+        if buf.data.is_null() {
+            buf.data = src.as_mut_ptr();
+        } else if (buf.data_bytes_size as usize) < size {
+            return audio::err::PARAM.status();
+        } else {
+            let dst = unsafe { std::slice::from_raw_parts_mut(buf.data, size) };
+            dst.copy_from_slice(src);
+        }
+        buf.data_bytes_size = size as u32;
+    }
+    os::Status::NO_ERR
+}
+
+/// Renders a chain of initialized audio units without an output device.
+///
+/// Output bus 0 of each unit is connected to input bus 0 of the next one,
+/// the first unit pulls input bus 0 from the [`Source`].
+/// Stream formats and max frames per slice are read on creation.
+/// Connections and the input callback are removed on drop.
+pub struct OfflineRender<'a, S: Source> {
+    units: Vec<&'a mut Unit>,
+    state: Box<SourceState<S>>,
+    out: audio::Bufs,
+    sample_time: f64,
+}
+
+impl<'a, S: Source> OfflineRender<'a, S> {
+    pub fn new(mut units: Vec<&'a mut Unit>, source: S) -> os::Result<Self> {
+        let (Some(first), Some(last)) = (units.first(), units.last()) else {
+            return Err(audio::err::PARAM);
+        };
+        let in_asbd = first.stream_format(Scope::INPUT, 0)?;
+        let in_bufs = audio::Bufs::new(&in_asbd, first.max_frames_per_slice()?)?;
+        let out_asbd = last.stream_format(Scope::OUTPUT, 0)?;
+        let out = audio::Bufs::new(&out_asbd, last.max_frames_per_slice()?)?;
+
+        let mut state = Box::new(SourceState {
+            source,
+            bufs: in_bufs,
+        });
+        let ref_con: *mut SourceState<S> = state.as_mut();
+        units[0].set_input_cb::<1, _>(Scope::INPUT, 0, input_cb::<S>, ref_con)?;
+
+        let mut res = Self {
+            units,
+            state,
+            out,
+            sample_time: 0.0,
+        };
+        for i in 1..res.units.len() {
+            let conn = Connection {
+                src_au: &*res.units[i - 1],
+                src_output_num: 0,
+                dst_input_num: 0,
+            };
+            res.units[i].set_prop(PropId::MAKE_CONNECTION, Scope::INPUT, au::Element(0), &conn)?;
+        }
+        Ok(res)
+    }
+
+    /// Stream format of rendered buffers.
+    #[inline]
+    pub fn asbd(&self) -> &audio::StreamBasicDesc {
+        self.out.asbd()
+    }
+
+    /// Max number of frames [`Self::render_slice`] can render.
+    #[inline]
+    pub fn max_frames_per_slice(&self) -> u32 {
+        self.out.capacity()
+    }
+
+    /// Sample time of the next slice.
+    #[inline]
+    pub fn sample_time(&self) -> f64 {
+        self.sample_time
+    }
+
+    #[inline]
+    pub fn source(&self) -> &S {
+        &self.state.source
+    }
+
+    #[inline]
+    pub fn source_mut(&mut self) -> &mut S {
+        &mut self.state.source
+    }
+
+    /// Renders single slice of at most [`Self::max_frames_per_slice`] frames.
+    pub fn render_slice(&mut self, frames: u32) -> os::Result<&audio::Bufs> {
+        self.out.set_frames(frames)?;
+        let ts = audio::TimeStamp::with_sample_time(self.sample_time);
+        let list = self.out.as_list_ptr_mut();
+        let last = self.units.last_mut().unwrap();
+        last.render(&ts, 0, frames, unsafe { &mut *list })?;
+
+        // unit may return its own buffers instead of filling ours
+        let rendered: Vec<_> = unsafe { list_bufs(list) }
+            .iter()
+            .map(|b| (b.data, b.data_bytes_size as usize))
+            .collect();
+        for (i, (data, size)) in rendered.into_iter().enumerate() {
+            let dst = self.out.bytes_mut(i).unwrap();
+            if data != dst.as_mut_ptr() && !data.is_null() {
+                let size = size.min(dst.len());
+                dst[..size].copy_from_slice(unsafe { std::slice::from_raw_parts(data, size) });
+            }
+        }
+        // short slices advance time only by what was rendered
+        let rendered = self.out.sync_frames();
+        self.sample_time += rendered as f64;
+        Ok(&self.out)
+    }
+
+    /// Renders `frames` frames slice by slice into new buffers.
+    ///
+    /// Result is shorter if the last unit renders less than requested.
+    pub fn render(&mut self, frames: u32) -> os::Result<audio::Bufs> {
+        let mut res = audio::Bufs::new(self.out.asbd(), frames)?;
+        let mut offset = 0;
+        let mut left = frames;
+        while left > 0 {
+            let n = left.min(self.max_frames_per_slice());
+            let slice = self.render_slice(n)?;
+            let size = slice.buf_bytes_size();
+            for i in 0..slice.buf_count() {
+                res.bytes_mut(i).unwrap()[offset..offset + size]
+                    .copy_from_slice(slice.bytes(i).unwrap());
+            }
+            offset += size;
+            if slice.frames() < n {
+                // unit rendered less than asked, keep what we have
+                res.set_frames(frames - left + slice.frames())?;
+                break;
+            }
+            left -= n;
+        }
+        Ok(res)
+    }
+}
+
+impl<'a, S: Source> Drop for OfflineRender<'a, S> {
+    fn drop(&mut self) {
+        for i in 1..self.units.len() {
+            let conn = Connection {
+                src_au: std::ptr::null(),
+                src_output_num: 0,
+                dst_input_num: 0,
+            };
+            let res = self.units[i].set_prop(
+                PropId::MAKE_CONNECTION,
+                Scope::INPUT,
+                au::Element(0),
+                &conn,
+            );
+            debug_assert!(res.is_ok());
+        }
+        let res = self.units[0].remove_input_cb(Scope::INPUT, 0);
+        debug_assert!(res.is_ok());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        at::{au, audio},
+        os,
+    };
+
+    #[test]
+    fn converters_chain() {
+        let mut a = au::FormatConverter::new_apple().unwrap();
+        a.set_max_frames_per_slice(512).unwrap();
+        let mut a = a.allocate_resources().unwrap();
+        let mut b = au::FormatConverter::new_apple().unwrap();
+        b.set_max_frames_per_slice(512).unwrap();
+        let mut b = b.allocate_resources().unwrap();
+
+        let mut pos = 0u32;
+        let source = |_ts: &audio::TimeStamp, bufs: &mut audio::Bufs| -> os::Result {
+            let frames = bufs.frames();
+            for buf in bufs.bufs_mut::<f32>().unwrap() {
+                for (i, s) in buf.iter_mut().enumerate() {
+                    *s = ((pos + i as u32) % 100) as f32 / 100.0;
+                }
+            }
+            pos += frames;
+            Ok(())
+        };
+
+        let mut render = au::OfflineRender::new(vec![a.unit_mut(), b.unit_mut()], source).unwrap();
+        assert_eq!(render.max_frames_per_slice(), 512);
+
+        let bufs = render.render(1200).unwrap();
+        assert_eq!(render.sample_time(), 1200.0);
+        assert_eq!(bufs.frames(), 1200);
+        let ch = bufs.planar::<f32>(1).unwrap();
+        assert_eq!(ch[0], 0.0);
+        assert_eq!(ch[1], 0.01);
+        assert_eq!(ch[1199], 0.99);
+
+        assert!(render.render_slice(513).is_err());
+        assert_eq!(render.render_slice(10).unwrap().frames(), 10);
+        assert_eq!(render.sample_time(), 1210.0);
+    }
+}
@@ -246,6 +246,24 @@ pub struct ParamInfo {
     pub flags: ParamFlags,
 }
 
+impl ParamInfo {
+    /// `name_string` if flagged as present, C string name otherwise.
+    pub fn name(&self) -> String {
+        if self.flags.contains(ParamFlags::HAS_CF_NAME_STRING) {
+            if let Some(name) = &self.name_string {
+                return name.to_string();
+            }
+        }
+        let bytes: Vec<u8> = self
+            .name
+            .iter()
+            .take_while(|c| **c != 0)
+            .map(|c| *c as u8)
+            .collect();
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+}
+
 impl Default for ParamInfo {
     fn default() -> Self {
         Self {
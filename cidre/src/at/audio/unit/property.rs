@@ -0,0 +1,460 @@
+//! Typed audio unit properties.
+//!
+//! [`Property`] carries value type, scopes and access of the property,
+//! so mismatched types or scopes don't compile.
+//!
+//! ```no_run
+//! use cidre::at::au::{self, property as prop};
+//!
+//! let mut mixer = au::MultiChannelMixer::new_apple().unwrap();
+//! let unit = mixer.unit_mut();
+//! let asbd = unit.get(prop::STREAM_FORMAT, prop::Input, au::Element(0)).unwrap();
+//! unit.set(prop::MAX_FRAMES_PER_SLICE, prop::Global, au::Element(0), &512).unwrap();
+//! let latency = unit.get(prop::LATENCY, prop::Global, au::Element(0)).unwrap();
+//! ```
+
+use std::{ffi::c_void, marker::PhantomData};
+
+use crate::{
+    arc,
+    at::{
+        au::{
+            ParamFlags, ParamId, ParamInfo, ParamUnit, ParamValue, Preset, PropId, RenderCbStruct,
+            Scope, Unit,
+        },
+        audio::{self, ChannelLayoutBuf, StreamBasicDesc},
+    },
+    cf, os,
+};
+
+use super::Element;
+
+/// Scope marker.
+pub trait ScopeMarker: Copy {
+    const SCOPE: Scope;
+}
+
+/// Implemented by scope markers the property with scopes `S` is defined on.
+pub trait AllowedIn<S>: ScopeMarker {}
+
+#[derive(Debug, Default, Copy, Clone)]
+pub struct Global;
+
+#[derive(Debug, Default, Copy, Clone)]
+pub struct Input;
+
+#[derive(Debug, Default, Copy, Clone)]
+pub struct Output;
+
+/// Property is defined on input and output scopes.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct InputOutput;
+
+/// Property is defined on any scope.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct AnyScope;
+
+impl ScopeMarker for Global {
+    const SCOPE: Scope = Scope::GLOBAL;
+}
+
+impl ScopeMarker for Input {
+    const SCOPE: Scope = Scope::INPUT;
+}
+
+impl ScopeMarker for Output {
+    const SCOPE: Scope = Scope::OUTPUT;
+}
+
+impl AllowedIn<Global> for Global {}
+impl AllowedIn<Input> for Input {}
+impl AllowedIn<Output> for Output {}
+impl AllowedIn<InputOutput> for Input {}
+impl AllowedIn<InputOutput> for Output {}
+impl AllowedIn<AnyScope> for Global {}
+impl AllowedIn<AnyScope> for Input {}
+impl AllowedIn<AnyScope> for Output {}
+
+#[derive(Debug, Copy, Clone)]
+pub struct Read;
+
+#[derive(Debug, Copy, Clone)]
+pub struct Write;
+
+#[derive(Debug, Copy, Clone)]
+pub struct ReadWrite;
+
+pub trait Readable {}
+pub trait Writable {}
+
+impl Readable for Read {}
+impl Readable for ReadWrite {}
+impl Writable for Write {}
+impl Writable for ReadWrite {}
+
+/// Property value that can be read from audio unit.
+pub trait Get: Sized {
+    fn get(unit: &Unit, id: PropId, scope: Scope, element: Element) -> os::Result<Self>;
+}
+
+/// Property value that can be written to audio unit.
+pub trait Set {
+    fn set(&self, unit: &mut Unit, id: PropId, scope: Scope, element: Element) -> os::Result;
+}
+
+macro_rules! plain {
+    ($($t:ty),*) => {
+        $(
+            impl Get for $t {
+                #[inline]
+                fn get(unit: &Unit, id: PropId, scope: Scope, element: Element) -> os::Result<Self> {
+                    unit.prop(id, scope, element)
+                }
+            }
+
+            impl Set for $t {
+                #[inline]
+                fn set(&self, unit: &mut Unit, id: PropId, scope: Scope, element: Element) -> os::Result {
+                    unit.set_prop(id, scope, element, self)
+                }
+            }
+        )*
+    };
+}
+
+plain!(u32, f64, StreamBasicDesc, Preset);
+
+impl Get for bool {
+    #[inline]
+    fn get(unit: &Unit, id: PropId, scope: Scope, element: Element) -> os::Result<Self> {
+        u32::get(unit, id, scope, element).map(|v| v != 0)
+    }
+}
+
+impl Set for bool {
+    #[inline]
+    fn set(&self, unit: &mut Unit, id: PropId, scope: Scope, element: Element) -> os::Result {
+        (*self as u32).set(unit, id, scope, element)
+    }
+}
+
+impl Get for os::Status {
+    #[inline]
+    fn get(unit: &Unit, id: PropId, scope: Scope, element: Element) -> os::Result<Self> {
+        unit.prop(id, scope, element)
+    }
+}
+
+impl Get for ParamInfo {
+    fn get(unit: &Unit, id: PropId, scope: Scope, element: Element) -> os::Result<Self> {
+        // units only fill strings flagged as present, others stay null
+        let mut buf = [ParamInfo::default()];
+        unit.prop_fill(id, scope, element, &mut buf)?;
+        let [mut info] = buf;
+        if !info.flags.contains(ParamFlags::HAS_CF_NAME_STRING) {
+            std::mem::forget(info.name_string.take());
+        }
+        if info.unit != ParamUnit::CustomUnit {
+            std::mem::forget(info.unit_name.take());
+        }
+        if !info.flags.contains(ParamFlags::CF_NAME_RELEASE) {
+            // strings are owned by the unit, retain them for our drop
+            std::mem::forget(info.name_string.clone());
+            std::mem::forget(info.unit_name.clone());
+        }
+        Ok(info)
+    }
+}
+
+impl Get for Vec<ParamId> {
+    #[inline]
+    fn get(unit: &Unit, id: PropId, scope: Scope, element: Element) -> os::Result<Self> {
+        unit.prop_vec(id, scope, element)
+    }
+}
+
+impl Get for Vec<Preset> {
+    fn get(unit: &Unit, id: PropId, scope: Scope, element: Element) -> os::Result<Self> {
+        let arr: Option<arc::R<cf::Array>> = unit.prop(id, scope, element)?;
+        let Some(arr) = arr else {
+            return Ok(vec![]);
+        };
+        // elements are AUPreset pointers owned by the unit
+        let res = (0..arr.len())
+            .map(|i| {
+                let preset = unsafe { &*(&arr[i] as *const cf::Type as *const Preset) };
+                Preset {
+                    number: preset.number,
+                    name: preset.name.clone(),
+                }
+            })
+            .collect();
+        Ok(res)
+    }
+}
+
+/// Native `AudioChannelLayout` is sequence of 32 bit fields.
+impl Get for ChannelLayoutBuf {
+    fn get(unit: &Unit, id: PropId, scope: Scope, element: Element) -> os::Result<Self> {
+        let words: Vec<u32> = unit.prop_vec(id, scope, element)?;
+        if words.len() < 3 {
+            return Err(audio::err::PARAM);
+        }
+        let n = (words[2] as usize).min((words.len() - 3) / 5);
+        let descs = words[3..3 + n * 5]
+            .chunks_exact(5)
+            .map(|w| audio::ChannelDesc {
+                channel_label: audio::ChannelLabel(w[0]),
+                channel_flags: audio::ChannelFlags(w[1]),
+                coordinates: [
+                    f32::from_bits(w[2]),
+                    f32::from_bits(w[3]),
+                    f32::from_bits(w[4]),
+                ],
+            })
+            .collect();
+        Ok(Self {
+            tag: audio::ChannelLayoutTag(words[0]),
+            bitmap: audio::ChannelBitmap(words[1]),
+            descs,
+        })
+    }
+}
+
+impl Set for ChannelLayoutBuf {
+    fn set(&self, unit: &mut Unit, id: PropId, scope: Scope, element: Element) -> os::Result {
+        let mut words = vec![self.tag.0, self.bitmap.0, self.descs.len() as u32];
+        for d in &self.descs {
+            words.extend_from_slice(&[
+                d.channel_label.0,
+                d.channel_flags.0,
+                d.coordinates[0].to_bits(),
+                d.coordinates[1].to_bits(),
+                d.coordinates[2].to_bits(),
+            ]);
+        }
+        // struct has at least one channel description
+        words.resize(words.len().max(8), 0);
+        unit.set_prop_slice(id, scope, element, &words)
+    }
+}
+
+impl<const N: usize, T> Set for RenderCbStruct<N, T> {
+    #[inline]
+    fn set(&self, unit: &mut Unit, id: PropId, scope: Scope, element: Element) -> os::Result {
+        unit.set_prop(id, scope, element, self)
+    }
+}
+
+/// Audio unit property with value type `T`, scopes `S` and access `A`.
+pub struct Property<T, S, A = ReadWrite> {
+    id: PropId,
+    _marker: PhantomData<fn() -> (T, S, A)>,
+}
+
+impl<T, S, A> Property<T, S, A> {
+    #[inline]
+    pub const fn new(id: PropId) -> Self {
+        Self {
+            id,
+            _marker: PhantomData,
+        }
+    }
+
+    #[inline]
+    pub const fn id(&self) -> PropId {
+        self.id
+    }
+}
+
+impl<T, S, A> Clone for Property<T, S, A> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T, S, A> Copy for Property<T, S, A> {}
+
+impl<T, S, A> std::fmt::Debug for Property<T, S, A> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Property").field(&self.id).finish()
+    }
+}
+
+/// Element is bus.
+#[doc(alias = "kAudioUnitProperty_StreamFormat")]
+pub const STREAM_FORMAT: Property<StreamBasicDesc, InputOutput> =
+    Property::new(PropId::STREAM_FORMAT);
+
+#[doc(alias = "kAudioUnitProperty_SampleRate")]
+pub const SAMPLE_RATE: Property<f64, InputOutput> = Property::new(PropId::SAMPLE_RATE);
+
+#[doc(alias = "kAudioUnitProperty_MaximumFramesPerSlice")]
+pub const MAX_FRAMES_PER_SLICE: Property<u32, Global> = Property::new(PropId::MAX_FRAMES_PER_SLICE);
+
+/// Element is bus.
+#[doc(alias = "kAudioUnitProperty_AudioChannelLayout")]
+pub const AUDIO_CHANNEL_LAYOUT: Property<ChannelLayoutBuf, InputOutput> =
+    Property::new(PropId::AUDIO_CHANNEL_LAYOUT);
+
+#[doc(alias = "kAudioUnitProperty_ElementCount")]
+pub const ELEMENT_COUNT: Property<u32, AnyScope> = Property::new(PropId::ELEMENT_COUNT);
+
+/// Seconds.
+#[doc(alias = "kAudioUnitProperty_Latency")]
+pub const LATENCY: Property<f64, Global, Read> = Property::new(PropId::LATENCY);
+
+/// Seconds.
+#[doc(alias = "kAudioUnitProperty_TailTime")]
+pub const TAIL_TIME: Property<f64, Global, Read> = Property::new(PropId::TAIL_TIME);
+
+#[doc(alias = "kAudioUnitProperty_BypassEffect")]
+pub const BYPASS_EFFECT: Property<bool, Global> = Property::new(PropId::BYPASS_EFFECT);
+
+#[doc(alias = "kAudioUnitProperty_OfflineRender")]
+pub const OFFLINE_RENDER: Property<bool, Global> = Property::new(PropId::OFFLINE_RENDER);
+
+#[doc(alias = "kAudioUnitProperty_LastRenderError")]
+pub const LAST_RENDER_ERROR: Property<os::Status, Global, Read> =
+    Property::new(PropId::LAST_RENDER_ERROR);
+
+#[doc(alias = "kAudioUnitProperty_RenderQuality")]
+pub const RENDER_QUALITY: Property<u32, Global> = Property::new(PropId::RENDER_QUALITY);
+
+#[doc(alias = "kAudioUnitProperty_ParameterList")]
+pub const PARAM_LIST: Property<Vec<ParamId>, AnyScope, Read> = Property::new(PropId::PARAM_LIST);
+
+/// Element is parameter id.
+#[doc(alias = "kAudioUnitProperty_ParameterInfo")]
+pub const PARAM_INFO: Property<ParamInfo, AnyScope, Read> = Property::new(PropId::PARAM_INFO);
+
+#[doc(alias = "kAudioUnitProperty_FactoryPresets")]
+pub const FACTORY_PRESETS: Property<Vec<Preset>, Global, Read> =
+    Property::new(PropId::FACTORY_PRESETS);
+
+#[doc(alias = "kAudioUnitProperty_PresentPreset")]
+pub const PRESENT_PRESET: Property<Preset, Global> = Property::new(PropId::PRESENT_PRESET);
+
+/// Element is input bus.
+#[doc(alias = "kAudioUnitProperty_SetRenderCallback")]
+pub const SET_RENDER_CB: Property<RenderCbStruct<1, c_void>, Input, Write> =
+    Property::new(PropId::SET_RENDER_CB);
+
+/// Parameter description with owned strings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParamDesc {
+    pub id: ParamId,
+    pub name: String,
+    pub unit: ParamUnit,
+    /// Name of custom unit.
+    pub unit_name: Option<String>,
+    pub min_value: ParamValue,
+    pub max_value: ParamValue,
+    pub default_value: ParamValue,
+    pub flags: ParamFlags,
+    pub clump_id: Option<u32>,
+    /// Names of indexed parameter values.
+    pub value_strings: Vec<String>,
+}
+
+impl Unit {
+    /// Reads typed property.
+    #[inline]
+    pub fn get<T: Get, S, A: Readable, M: AllowedIn<S>>(
+        &self,
+        prop: Property<T, S, A>,
+        _scope: M,
+        element: Element,
+    ) -> os::Result<T> {
+        T::get(self, prop.id, M::SCOPE, element)
+    }
+
+    /// Writes typed property.
+    #[inline]
+    pub fn set<T: Set, S, A: Writable, M: AllowedIn<S>>(
+        &mut self,
+        prop: Property<T, S, A>,
+        _scope: M,
+        element: Element,
+        val: &T,
+    ) -> os::Result {
+        val.set(self, prop.id, M::SCOPE, element)
+    }
+
+    /// Descriptions of all parameters in `scope`.
+    pub fn param_descs(&self, scope: Scope) -> os::Result<Vec<ParamDesc>> {
+        let ids = self.params_list(scope)?;
+        let mut res = Vec::with_capacity(ids.len());
+        for id in ids {
+            let info = ParamInfo::get(self, PropId::PARAM_INFO, scope, Element(id.0))?;
+            let name = info.name();
+            let unit_name = info.unit_name.as_ref().map(|s| s.to_string());
+            let value_strings = if info.unit == ParamUnit::Indexed {
+                let strings: os::Result<Option<arc::R<cf::ArrayOf<cf::String>>>> =
+                    self.prop(PropId::PARAM_VALUE_STRINGS, scope, Element(id.0));
+                match strings {
+                    Ok(Some(arr)) => arr.iter().map(|s| s.to_string()).collect(),
+                    _ => vec![],
+                }
+            } else {
+                vec![]
+            };
+            res.push(ParamDesc {
+                id,
+                name,
+                unit: info.unit,
+                unit_name,
+                min_value: info.min_value,
+                max_value: info.max_value,
+                default_value: info.default_value,
+                flags: info.flags,
+                clump_id: info
+                    .flags
+                    .contains(ParamFlags::HAS_CLUMP)
+                    .then_some(info.clump_id),
+                value_strings,
+            });
+        }
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::at::{
+        au::{self, property as prop},
+        audio,
+    };
+
+    #[test]
+    fn basics() {
+        let mut mixer = au::MultiChannelMixer::new_apple().unwrap();
+        let unit = mixer.unit_mut();
+
+        unit.set(
+            prop::MAX_FRAMES_PER_SLICE,
+            prop::Global,
+            au::Element(0),
+            &512,
+        )
+        .unwrap();
+        let frames = unit
+            .get(prop::MAX_FRAMES_PER_SLICE, prop::Global, au::Element(0))
+            .unwrap();
+        assert_eq!(frames, 512);
+
+        let asbd = unit
+            .get(prop::STREAM_FORMAT, prop::Input, au::Element(0))
+            .unwrap();
+        assert_eq!(asbd.format, audio::Format::LINEAR_PCM);
+        unit.get(prop::LATENCY, prop::Global, au::Element(0))
+            .unwrap();
+
+        let params = unit.param_descs(au::Scope::INPUT).unwrap();
+        assert!(!params.is_empty());
+        for p in params {
+            assert!(!p.name.is_empty());
+            assert!(p.min_value <= p.max_value);
+        }
+    }
+}
@@ -122,6 +122,31 @@ pub use asset::AssetImageGenerator;
 pub use asset::AssetImageGeneratorCh;
 pub use asset::AssetImageGeneratorResult;
 
+pub mod composition;
+pub use composition::Composition;
+pub use composition::CompositionMut;
+pub use composition::Track as CompositionTrack;
+pub use composition::TrackMut as CompositionTrackMut;
+
+pub mod video_composition;
+pub use video_composition::Instruction as VideoCompositionInstruction;
+pub use video_composition::InstructionMut as VideoCompositionInstructionMut;
+pub use video_composition::LayerInstruction as VideoCompositionLayerInstruction;
+pub use video_composition::LayerInstructionMut as VideoCompositionLayerInstructionMut;
+pub use video_composition::VideoComposition;
+pub use video_composition::VideoCompositionMut;
+
+pub mod audio_mix;
+pub use audio_mix::AudioMix;
+pub use audio_mix::AudioMixMut;
+pub use audio_mix::InputParams as AudioMixInputParams;
+pub use audio_mix::InputParamsMut as AudioMixInputParamsMut;
+
+pub mod timeline;
+pub use timeline::Timeline;
+
+pub mod hls;
+
 pub mod audio;
 pub use audio::Buf as AudioBuf;
 pub use audio::ChannelCount as AudioChannelCount;
@@ -149,6 +174,8 @@ pub use audio::PcmBuf as AudioPcmBuf;
 pub use audio::Player as AudioPlayer;
 pub use audio::PlayerDelegate as AudioPlayerDelegate;
 pub use audio::PlayerNode as AudioPlayerNode;
+pub use audio::SourceNode as AudioSourceNode;
+pub use audio::SourceNodeRenderBlock as AudioSourceNodeRenderBlock;
 pub use audio::Time as AudioTime;
 pub use audio::VpOtherAudioDuckingCfg as AudioVpOtherAudioDuckingCfg;
 pub use audio::VpOtherAudioDuckingLevel as AudioVpOtherAudioDuckingLevel;
@@ -128,7 +128,44 @@ impl ReaderTrackOutput {
     pub fn reset_for_reading_time_ranges(&mut self, ranges: &ns::Array<ns::Value>);
 }
 
+impl ReaderAudioMixOutput {
+    define_cls!(AV_ASSET_READER_AUDIO_MIX_OUTPUT);
+
+    /// Mixes the audio tracks, all tracks must be of the reader's asset.
+    #[objc::msg_send(assetReaderAudioMixOutputWithAudioTracks:audioSettings:)]
+    pub fn with_tracks(
+        tracks: &ns::Array<av::asset::Track>,
+        audio_settings: Option<&ns::Dictionary<ns::String, ns::Id>>,
+    ) -> arc::R<Self>;
+
+    #[objc::msg_send(audioMix)]
+    pub fn audio_mix(&self) -> Option<arc::R<av::AudioMix>>;
+
+    #[objc::msg_send(setAudioMix:)]
+    pub fn set_audio_mix(&mut self, val: Option<&av::AudioMix>);
+}
+
+impl ReaderVideoCompositionOutput {
+    define_cls!(AV_ASSET_READER_VIDEO_COMPOSITION_OUTPUT);
+
+    /// Composites the video tracks, all tracks must be of the reader's asset.
+    #[objc::msg_send(assetReaderVideoCompositionOutputWithVideoTracks:videoSettings:)]
+    pub fn with_tracks(
+        tracks: &ns::Array<av::asset::Track>,
+        video_settings: Option<&ns::Dictionary<ns::String, ns::Id>>,
+    ) -> arc::R<Self>;
+
+    #[objc::msg_send(videoComposition)]
+    pub fn video_composition(&self) -> Option<arc::R<av::VideoComposition>>;
+
+    #[objc::msg_send(setVideoComposition:)]
+    pub fn set_video_composition(&mut self, val: Option<&av::VideoComposition>);
+}
+
 #[link(name = "av", kind = "static")]
 extern "C" {
     static AV_ASSET_READER_TRACK_OUTPUT: &'static objc::Class<ReaderTrackOutput>;
+    static AV_ASSET_READER_AUDIO_MIX_OUTPUT: &'static objc::Class<ReaderAudioMixOutput>;
+    static AV_ASSET_READER_VIDEO_COMPOSITION_OUTPUT:
+        &'static objc::Class<ReaderVideoCompositionOutput>;
 }
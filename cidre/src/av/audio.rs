@@ -52,6 +52,10 @@ mod player;
 pub use player::Delegate as PlayerDelegate;
 pub use player::Player;
 
+mod source_node;
+pub use source_node::RenderBlock as SourceNodeRenderBlock;
+pub use source_node::SourceNode;
+
 mod player_node;
 pub use player_node::BufOpts as PlayerNodeBufOpts;
 pub use player_node::CompletionCbType as PlayerNodeCompletionCbType;
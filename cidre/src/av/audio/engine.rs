@@ -256,6 +256,36 @@ impl Engine {
         Ok(status)
     }
 
+    /// Renders `frames` frames in chunks of [`Self::manual_rendering_max_frame_count`].
+    ///
+    /// Engine must be started in offline manual rendering mode. Rendering stops early
+    /// if input nodes run out of data.
+    pub fn render_offline_bufs<'ar>(
+        &mut self,
+        frames: av::AudioFrameCount,
+    ) -> Result<Vec<arc::R<av::AudioPcmBuf>>, &'ar ns::Error> {
+        let format = self.manual_rendering_format();
+        let max = self.manual_rendering_max_frame_count();
+        let mut res = Vec::new();
+        let mut left = frames;
+        while left > 0 && max > 0 {
+            let n = left.min(max);
+            let Some(mut buf) = av::AudioPcmBuf::with_format(&format, n) else {
+                break;
+            };
+            let status = self.render_offline(n, &mut buf)?;
+            let rendered = buf.frame_len();
+            if rendered > 0 {
+                res.push(buf);
+            }
+            if status != ManualRenderingStatus::Success || rendered < n {
+                break;
+            }
+            left -= n;
+        }
+        Ok(res)
+    }
+
     #[objc::msg_send(isInManualRenderingMode)]
     pub fn is_in_manual_rendering_mode(&self) -> bool;
 
@@ -0,0 +1,112 @@
+use crate::{
+    arc, at,
+    av::{self, audio},
+    blocks, define_cls, define_obj_type, objc, os,
+};
+
+/// Block called on the realtime thread to supply output data.
+///
+/// Set `is_silence` to `true` and zero the buffers if there is nothing to render.
+#[doc(alias = "AVAudioSourceNodeRenderBlock")]
+pub type RenderBlock<Attr> = blocks::Block<
+    fn(&mut bool, &at::AudioTimeStamp, av::AudioFrameCount, *mut at::AudioBufList) -> os::Status,
+    Attr,
+>;
+
+define_obj_type!(
+    /// A node that supplies audio data from a render block.
+    #[doc(alias = "AVAudioSourceNode")]
+    pub SourceNode(audio::Node)
+);
+
+impl arc::A<SourceNode> {
+    #[objc::msg_send(initWithRenderBlock:)]
+    pub fn init_with_render_block(
+        self,
+        block: &mut RenderBlock<blocks::Send>,
+    ) -> arc::R<SourceNode>;
+
+    #[objc::msg_send(initWithFormat:renderBlock:)]
+    pub fn init_with_format_render_block(
+        self,
+        format: &av::AudioFormat,
+        block: &mut RenderBlock<blocks::Send>,
+    ) -> arc::R<SourceNode>;
+}
+
+impl SourceNode {
+    define_cls!(AV_AUDIO_SOURCE_NODE);
+
+    /// Output format is the format of the connection.
+    pub fn with_render_block(block: &mut RenderBlock<blocks::Send>) -> arc::R<Self> {
+        Self::alloc().init_with_render_block(block)
+    }
+
+    /// `format` is the format of the buffers passed to the block,
+    /// the node converts it to the output format of the connection.
+    pub fn with_format_render_block(
+        format: &av::AudioFormat,
+        block: &mut RenderBlock<blocks::Send>,
+    ) -> arc::R<Self> {
+        Self::alloc().init_with_format_render_block(format, block)
+    }
+
+    pub fn with_format_render(
+        format: &av::AudioFormat,
+        render: impl FnMut(
+                &mut bool,
+                &at::AudioTimeStamp,
+                av::AudioFrameCount,
+                *mut at::AudioBufList,
+            ) -> os::Status
+            + 'static
+            + std::marker::Send,
+    ) -> arc::R<Self> {
+        let mut block = RenderBlock::<blocks::Send>::new4(render);
+        Self::with_format_render_block(format, &mut block)
+    }
+}
+
+#[link(name = "av", kind = "static")]
+extern "C" {
+    static AV_AUDIO_SOURCE_NODE: &'static objc::Class<SourceNode>;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{av, os};
+
+    #[test]
+    fn offline() {
+        let format = av::AudioFormat::standard_with_sample_rate_and_channels(48_000.0, 1).unwrap();
+        let mut engine = av::AudioEngine::new();
+        engine
+            .enable_manual_rendering_mode(
+                av::audio::EngineManualRenderingMode::Offline,
+                &format,
+                512,
+            )
+            .unwrap();
+
+        let source =
+            av::AudioSourceNode::with_format_render(&format, |_is_silence, _ts, frames, list| {
+                let list = unsafe { &mut *list };
+                let len = frames as usize;
+                let buf = &mut list.buffers[0];
+                let samples = unsafe { std::slice::from_raw_parts_mut(buf.data as *mut f32, len) };
+                samples.fill(0.5);
+                os::Status::NO_ERR
+            });
+        engine.attach_node(&source);
+        let mixer = engine.main_mixer_node().retained();
+        engine.connect_node_to_node(&source, &mixer, Some(&format));
+        engine.start().unwrap();
+
+        let bufs = engine.render_offline_bufs(1200).unwrap();
+        assert_eq!(bufs.len(), 3);
+        let frames: u32 = bufs.iter().map(|b| b.frame_len()).sum();
+        assert_eq!(frames, 1200);
+        assert!((bufs[2].data_f32_at(0).unwrap()[0] - 0.5).abs() < 1e-3);
+        engine.stop();
+    }
+}
@@ -0,0 +1,78 @@
+use crate::{arc, av, cm, define_obj_type, ns, objc};
+
+define_obj_type!(
+    #[doc(alias = "AVAudioMix")]
+    pub AudioMix(ns::Id)
+);
+
+define_obj_type!(
+    #[doc(alias = "AVMutableAudioMix")]
+    pub AudioMixMut(AudioMix),
+    AV_MUTABLE_AUDIO_MIX
+);
+
+define_obj_type!(
+    #[doc(alias = "AVAudioMixInputParameters")]
+    pub InputParams(ns::Id)
+);
+
+define_obj_type!(
+    #[doc(alias = "AVMutableAudioMixInputParameters")]
+    pub InputParamsMut(InputParams),
+    AV_MUTABLE_AUDIO_MIX_INPUT_PARAMETERS
+);
+
+impl AudioMix {
+    #[objc::msg_send(inputParameters)]
+    pub fn input_params(&self) -> arc::R<ns::Array<InputParams>>;
+}
+
+impl AudioMixMut {
+    #[objc::msg_send(setInputParameters:)]
+    pub fn set_input_params(&mut self, val: &ns::Array<InputParams>);
+}
+
+impl InputParams {
+    #[objc::msg_send(trackID)]
+    pub fn track_id(&self) -> cm::PersistentTrackId;
+}
+
+impl InputParamsMut {
+    #[objc::msg_send(audioMixInputParametersWithTrack:)]
+    pub fn with_track(track: Option<&av::asset::Track>) -> arc::R<Self>;
+
+    #[objc::msg_send(setTrackID:)]
+    pub fn set_track_id(&mut self, val: cm::PersistentTrackId);
+
+    #[objc::msg_send(setVolume:atTime:)]
+    pub fn set_volume_at(&mut self, volume: f32, time: cm::Time);
+
+    /// Linear volume change over the time range, ramps of the track must not overlap.
+    #[objc::msg_send(setVolumeRampFromStartVolume:toEndVolume:timeRange:)]
+    pub fn set_volume_ramp(&mut self, start: f32, end: f32, time_range: cm::TimeRange);
+}
+
+#[link(name = "av", kind = "static")]
+extern "C" {
+    static AV_MUTABLE_AUDIO_MIX: &'static objc::Class<AudioMixMut>;
+    static AV_MUTABLE_AUDIO_MIX_INPUT_PARAMETERS: &'static objc::Class<InputParamsMut>;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{av, cm, ns};
+
+    #[test]
+    fn basics() {
+        let mut params = av::AudioMixInputParamsMut::with_track(None);
+        params.set_track_id(cm::PersistentTrackId(2));
+        params.set_volume_at(0.5, cm::Time::zero());
+        assert_eq!(params.track_id(), cm::PersistentTrackId(2));
+
+        let mut mix = av::AudioMixMut::new();
+        mix.set_input_params(&ns::Array::<av::AudioMixInputParams>::from_slice(&[
+            &params,
+        ]));
+        assert_eq!(mix.input_params().len(), 1);
+    }
+}
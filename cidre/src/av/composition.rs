@@ -0,0 +1,150 @@
+use crate::{arc, av, cg, cm, define_obj_type, ns, objc};
+
+define_obj_type!(
+    #[doc(alias = "AVComposition")]
+    pub Composition(av::Asset)
+);
+
+define_obj_type!(
+    #[doc(alias = "AVMutableComposition")]
+    pub CompositionMut(Composition),
+    AV_MUTABLE_COMPOSITION
+);
+
+define_obj_type!(
+    #[doc(alias = "AVCompositionTrack")]
+    pub Track(av::asset::Track)
+);
+
+define_obj_type!(
+    #[doc(alias = "AVMutableCompositionTrack")]
+    pub TrackMut(Track)
+);
+
+impl Composition {
+    #[objc::msg_send(tracks)]
+    pub fn tracks(&self) -> arc::R<ns::Array<Track>>;
+
+    #[objc::msg_send(naturalSize)]
+    pub fn natural_size(&self) -> cg::Size;
+
+    #[objc::msg_send(trackWithTrackID:)]
+    pub fn track_with_id(&self, track_id: cm::PersistentTrackId) -> Option<arc::R<Track>>;
+}
+
+/// Editing of the composition as a whole, changes apply to all tracks.
+impl CompositionMut {
+    #[objc::msg_send(setNaturalSize:)]
+    pub fn set_natural_size(&mut self, val: cg::Size);
+
+    #[objc::msg_send(insertTimeRange:ofAsset:atTime:error:)]
+    pub unsafe fn insert_time_range_of_asset_err<'ear>(
+        &mut self,
+        time_range: cm::TimeRange,
+        asset: &av::Asset,
+        start_time: cm::Time,
+        err: *mut Option<&'ear ns::Error>,
+    ) -> bool;
+
+    /// Inserts all the tracks within a given time range of a specified asset into the composition.
+    pub fn insert_time_range_of_asset<'ear>(
+        &mut self,
+        time_range: cm::TimeRange,
+        asset: &av::Asset,
+        start_time: cm::Time,
+    ) -> ns::Result<'ear> {
+        ns::if_false(|err| unsafe {
+            self.insert_time_range_of_asset_err(time_range, asset, start_time, err)
+        })
+    }
+
+    #[objc::msg_send(insertEmptyTimeRange:)]
+    pub fn insert_empty_time_range(&mut self, time_range: cm::TimeRange);
+
+    #[objc::msg_send(removeTimeRange:)]
+    pub fn remove_time_range(&mut self, time_range: cm::TimeRange);
+
+    /// Changes the duration of all tracks in the time range.
+    #[objc::msg_send(scaleTimeRange:toDuration:)]
+    pub fn scale_time_range(&mut self, time_range: cm::TimeRange, duration: cm::Time);
+
+    /// Adds an empty track, pass [`cm::PersistentTrackId::INVALID`] to generate unique id.
+    #[objc::msg_send(addMutableTrackWithMediaType:preferredTrackID:)]
+    pub fn add_track(
+        &mut self,
+        media_type: &av::MediaType,
+        preferred_track_id: cm::PersistentTrackId,
+    ) -> Option<arc::R<TrackMut>>;
+
+    #[objc::msg_send(removeTrack:)]
+    pub fn remove_track(&mut self, track: &Track);
+}
+
+impl TrackMut {
+    #[objc::msg_send(insertTimeRange:ofTrack:atTime:error:)]
+    pub unsafe fn insert_time_range_of_track_err<'ear>(
+        &mut self,
+        time_range: cm::TimeRange,
+        track: &av::asset::Track,
+        start_time: cm::Time,
+        err: *mut Option<&'ear ns::Error>,
+    ) -> bool;
+
+    /// Inserts a time range of a source track at `start_time`.
+    pub fn insert_time_range_of_track<'ear>(
+        &mut self,
+        time_range: cm::TimeRange,
+        track: &av::asset::Track,
+        start_time: cm::Time,
+    ) -> ns::Result<'ear> {
+        ns::if_false(|err| unsafe {
+            self.insert_time_range_of_track_err(time_range, track, start_time, err)
+        })
+    }
+
+    #[objc::msg_send(insertEmptyTimeRange:)]
+    pub fn insert_empty_time_range(&mut self, time_range: cm::TimeRange);
+
+    #[objc::msg_send(removeTimeRange:)]
+    pub fn remove_time_range(&mut self, time_range: cm::TimeRange);
+
+    #[objc::msg_send(scaleTimeRange:toDuration:)]
+    pub fn scale_time_range(&mut self, time_range: cm::TimeRange, duration: cm::Time);
+
+    #[objc::msg_send(naturalTimeScale)]
+    pub fn natural_time_scale(&self) -> cm::TimeScale;
+
+    #[objc::msg_send(setNaturalTimeScale:)]
+    pub fn set_natural_time_scale(&mut self, val: cm::TimeScale);
+
+    #[objc::msg_send(setPreferredTransform:)]
+    pub fn set_preferred_transform(&mut self, val: cg::AffineTransform);
+
+    #[objc::msg_send(setPreferredVolume:)]
+    pub fn set_preferred_volume(&mut self, val: f32);
+
+    #[objc::msg_send(setEnabled:)]
+    pub fn set_enabled(&mut self, val: bool);
+}
+
+#[link(name = "av", kind = "static")]
+extern "C" {
+    static AV_MUTABLE_COMPOSITION: &'static objc::Class<CompositionMut>;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{av, cm};
+
+    #[test]
+    fn basics() {
+        let mut comp = av::CompositionMut::new();
+        let track = comp
+            .add_track(av::MediaType::video(), cm::PersistentTrackId::INVALID)
+            .unwrap();
+        assert_eq!(comp.tracks().len(), 1);
+        assert!(comp.track_with_id(track.track_id()).is_some());
+        comp.remove_track(&track);
+        assert!(comp.tracks().is_empty());
+    }
+}
@@ -0,0 +1,24 @@
+//! HTTP Live Streaming playlists and segmenting on top of `av::AssetWriter` segment output.
+
+mod m3u8;
+pub use m3u8::ByteRange;
+pub use m3u8::Map;
+pub use m3u8::MediaPlaylist;
+pub use m3u8::MultivariantPlaylist;
+pub use m3u8::ParseError;
+pub use m3u8::ParseErrorKind;
+pub use m3u8::Part;
+pub use m3u8::Playlist;
+pub use m3u8::PlaylistType;
+pub use m3u8::PreloadHint;
+pub use m3u8::Rendition;
+pub use m3u8::RenditionType;
+pub use m3u8::Segment;
+pub use m3u8::ServerControl;
+pub use m3u8::Variant;
+
+mod segment_manager;
+pub use segment_manager::DirStorage;
+pub use segment_manager::SegmentManager;
+pub use segment_manager::SegmentManagerCfg;
+pub use segment_manager::Storage;
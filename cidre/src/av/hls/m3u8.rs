@@ -0,0 +1,924 @@
+use std::fmt::{self, Write};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct ByteRange {
+    pub len: u64,
+    pub offset: Option<u64>,
+}
+
+#[doc(alias = "EXT-X-MAP")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Map {
+    pub uri: String,
+    pub byte_range: Option<ByteRange>,
+}
+
+impl Map {
+    pub fn new(uri: impl Into<String>) -> Self {
+        Self {
+            uri: uri.into(),
+            byte_range: None,
+        }
+    }
+}
+
+#[doc(alias = "EXT-X-PLAYLIST-TYPE")]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum PlaylistType {
+    Event,
+    Vod,
+}
+
+/// LL-HLS partial segment
+#[doc(alias = "EXT-X-PART")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Part {
+    pub uri: String,
+    pub duration: f64,
+    pub independent: bool,
+    pub byte_range: Option<ByteRange>,
+}
+
+#[doc(alias = "EXT-X-SERVER-CONTROL")]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ServerControl {
+    pub can_block_reload: bool,
+    pub can_skip_until: Option<f64>,
+    pub hold_back: Option<f64>,
+    pub part_hold_back: Option<f64>,
+}
+
+#[doc(alias = "EXT-X-PRELOAD-HINT")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct PreloadHint {
+    pub uri: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Segment {
+    pub uri: String,
+    /// `EXTINF` duration in seconds
+    pub duration: f64,
+    pub title: Option<String>,
+    pub discontinuity: bool,
+    pub program_date_time: Option<String>,
+    pub byte_range: Option<ByteRange>,
+    /// `EXT-X-MAP` change before this segment
+    pub map: Option<Map>,
+    /// Partial segments this segment consists of
+    pub parts: Vec<Part>,
+}
+
+impl Segment {
+    pub fn new(uri: impl Into<String>, duration: f64) -> Self {
+        Self {
+            uri: uri.into(),
+            duration,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MediaPlaylist {
+    pub version: u32,
+    pub target_duration: u32,
+    pub media_sequence: u64,
+    pub discontinuity_sequence: u64,
+    pub playlist_type: Option<PlaylistType>,
+    pub independent_segments: bool,
+    pub server_control: Option<ServerControl>,
+    /// `EXT-X-PART-INF:PART-TARGET`
+    pub part_target: Option<f64>,
+    /// Initialization section for the first segment
+    pub map: Option<Map>,
+    pub segments: Vec<Segment>,
+    /// Parts of the segment which is not complete yet
+    pub pending_parts: Vec<Part>,
+    pub preload_hint: Option<PreloadHint>,
+    pub end_list: bool,
+}
+
+impl Default for MediaPlaylist {
+    fn default() -> Self {
+        Self {
+            version: 7,
+            target_duration: 0,
+            media_sequence: 0,
+            discontinuity_sequence: 0,
+            playlist_type: None,
+            independent_segments: false,
+            server_control: None,
+            part_target: None,
+            map: None,
+            segments: Vec::new(),
+            pending_parts: Vec::new(),
+            preload_hint: None,
+            end_list: false,
+        }
+    }
+}
+
+impl MediaPlaylist {
+    /// Total duration of complete segments.
+    pub fn duration(&self) -> f64 {
+        self.segments.iter().map(|s| s.duration).sum()
+    }
+
+    /// Minimal target duration satisfying all segments
+    /// (`EXTINF` rounded to the nearest integer must not exceed it).
+    pub fn min_target_duration(&self) -> u32 {
+        self.segments
+            .iter()
+            .map(|s| s.duration.round() as u32)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+#[doc(alias = "EXT-X-MEDIA")]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum RenditionType {
+    Audio,
+    Video,
+    Subtitles,
+    ClosedCaptions,
+}
+
+impl RenditionType {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Audio => "AUDIO",
+            Self::Video => "VIDEO",
+            Self::Subtitles => "SUBTITLES",
+            Self::ClosedCaptions => "CLOSED-CAPTIONS",
+        }
+    }
+
+    fn from_str(s: &str) -> Option<Self> {
+        Some(match s {
+            "AUDIO" => Self::Audio,
+            "VIDEO" => Self::Video,
+            "SUBTITLES" => Self::Subtitles,
+            "CLOSED-CAPTIONS" => Self::ClosedCaptions,
+            _ => return None,
+        })
+    }
+}
+
+#[doc(alias = "EXT-X-MEDIA")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rendition {
+    pub type_: RenditionType,
+    pub group_id: String,
+    pub name: String,
+    pub language: Option<String>,
+    pub uri: Option<String>,
+    pub default: bool,
+    pub autoselect: bool,
+    pub channels: Option<String>,
+}
+
+#[doc(alias = "EXT-X-STREAM-INF")]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Variant {
+    pub uri: String,
+    pub bandwidth: u64,
+    pub average_bandwidth: Option<u64>,
+    pub codecs: Option<String>,
+    pub resolution: Option<(u32, u32)>,
+    pub frame_rate: Option<f64>,
+    pub audio: Option<String>,
+    pub video: Option<String>,
+    pub subtitles: Option<String>,
+}
+
+/// Top level playlist listing renditions of the same content
+/// (a.k.a. master playlist)
+#[derive(Debug, Clone, PartialEq)]
+pub struct MultivariantPlaylist {
+    pub version: u32,
+    pub independent_segments: bool,
+    pub renditions: Vec<Rendition>,
+    pub variants: Vec<Variant>,
+}
+
+impl Default for MultivariantPlaylist {
+    fn default() -> Self {
+        Self {
+            version: 7,
+            independent_segments: false,
+            renditions: Vec::new(),
+            variants: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Playlist {
+    Media(MediaPlaylist),
+    Multivariant(MultivariantPlaylist),
+}
+
+/// Decimal with fixed precision, `EXTINF:6.00000`
+struct Dec(f64);
+
+impl fmt::Display for Dec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.5}", self.0)
+    }
+}
+
+struct Attrs<'a, 'b> {
+    f: &'a mut fmt::Formatter<'b>,
+    first: bool,
+}
+
+impl<'a, 'b> Attrs<'a, 'b> {
+    fn new(f: &'a mut fmt::Formatter<'b>, tag: &str) -> Result<Self, fmt::Error> {
+        f.write_str(tag)?;
+        f.write_char(':')?;
+        Ok(Self { f, first: true })
+    }
+
+    fn raw(&mut self, key: &str, val: impl fmt::Display) -> fmt::Result {
+        if !self.first {
+            self.f.write_char(',')?;
+        }
+        self.first = false;
+        write!(self.f, "{key}={val}")
+    }
+
+    fn quoted(&mut self, key: &str, val: &str) -> fmt::Result {
+        self.raw(key, format_args!("\"{val}\""))
+    }
+
+    fn yes(&mut self, key: &str, val: bool) -> fmt::Result {
+        if val {
+            self.raw(key, "YES")
+        } else {
+            Ok(())
+        }
+    }
+
+    fn end(self) -> fmt::Result {
+        self.f.write_char('\n')
+    }
+}
+
+impl fmt::Display for ByteRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.offset {
+            Some(o) => write!(f, "{}@{}", self.len, o),
+            None => write!(f, "{}", self.len),
+        }
+    }
+}
+
+fn write_map(f: &mut fmt::Formatter<'_>, map: &Map) -> fmt::Result {
+    let mut a = Attrs::new(f, "#EXT-X-MAP")?;
+    a.quoted("URI", &map.uri)?;
+    if let Some(br) = map.byte_range {
+        a.quoted("BYTERANGE", &br.to_string())?;
+    }
+    a.end()
+}
+
+fn write_part(f: &mut fmt::Formatter<'_>, part: &Part) -> fmt::Result {
+    let mut a = Attrs::new(f, "#EXT-X-PART")?;
+    a.raw("DURATION", Dec(part.duration))?;
+    a.quoted("URI", &part.uri)?;
+    a.yes("INDEPENDENT", part.independent)?;
+    if let Some(br) = part.byte_range {
+        a.quoted("BYTERANGE", &br.to_string())?;
+    }
+    a.end()
+}
+
+impl fmt::Display for MediaPlaylist {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("#EXTM3U\n")?;
+        writeln!(f, "#EXT-X-VERSION:{}", self.version)?;
+        writeln!(f, "#EXT-X-TARGETDURATION:{}", self.target_duration)?;
+        if let Some(sc) = &self.server_control {
+            let mut a = Attrs::new(f, "#EXT-X-SERVER-CONTROL")?;
+            a.yes("CAN-BLOCK-RELOAD", sc.can_block_reload)?;
+            if let Some(v) = sc.can_skip_until {
+                a.raw("CAN-SKIP-UNTIL", Dec(v))?;
+            }
+            if let Some(v) = sc.hold_back {
+                a.raw("HOLD-BACK", Dec(v))?;
+            }
+            if let Some(v) = sc.part_hold_back {
+                a.raw("PART-HOLD-BACK", Dec(v))?;
+            }
+            a.end()?;
+        }
+        if let Some(pt) = self.part_target {
+            writeln!(f, "#EXT-X-PART-INF:PART-TARGET={}", Dec(pt))?;
+        }
+        writeln!(f, "#EXT-X-MEDIA-SEQUENCE:{}", self.media_sequence)?;
+        if self.discontinuity_sequence != 0 {
+            writeln!(
+                f,
+                "#EXT-X-DISCONTINUITY-SEQUENCE:{}",
+                self.discontinuity_sequence
+            )?;
+        }
+        match self.playlist_type {
+            Some(PlaylistType::Event) => f.write_str("#EXT-X-PLAYLIST-TYPE:EVENT\n")?,
+            Some(PlaylistType::Vod) => f.write_str("#EXT-X-PLAYLIST-TYPE:VOD\n")?,
+            None => {}
+        }
+        if self.independent_segments {
+            f.write_str("#EXT-X-INDEPENDENT-SEGMENTS\n")?;
+        }
+        if let Some(map) = &self.map {
+            write_map(f, map)?;
+        }
+        for s in self.segments.iter() {
+            if s.discontinuity {
+                f.write_str("#EXT-X-DISCONTINUITY\n")?;
+            }
+            if let Some(map) = &s.map {
+                write_map(f, map)?;
+            }
+            if let Some(pdt) = &s.program_date_time {
+                writeln!(f, "#EXT-X-PROGRAM-DATE-TIME:{pdt}")?;
+            }
+            for p in s.parts.iter() {
+                write_part(f, p)?;
+            }
+            writeln!(
+                f,
+                "#EXTINF:{},{}",
+                Dec(s.duration),
+                s.title.as_deref().unwrap_or("")
+            )?;
+            if let Some(br) = s.byte_range {
+                writeln!(f, "#EXT-X-BYTERANGE:{br}")?;
+            }
+            writeln!(f, "{}", s.uri)?;
+        }
+        for p in self.pending_parts.iter() {
+            write_part(f, p)?;
+        }
+        if let Some(hint) = &self.preload_hint {
+            let mut a = Attrs::new(f, "#EXT-X-PRELOAD-HINT")?;
+            a.raw("TYPE", "PART")?;
+            a.quoted("URI", &hint.uri)?;
+            a.end()?;
+        }
+        if self.end_list {
+            f.write_str("#EXT-X-ENDLIST\n")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for MultivariantPlaylist {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("#EXTM3U\n")?;
+        writeln!(f, "#EXT-X-VERSION:{}", self.version)?;
+        if self.independent_segments {
+            f.write_str("#EXT-X-INDEPENDENT-SEGMENTS\n")?;
+        }
+        for r in self.renditions.iter() {
+            let mut a = Attrs::new(f, "#EXT-X-MEDIA")?;
+            a.raw("TYPE", r.type_.as_str())?;
+            a.quoted("GROUP-ID", &r.group_id)?;
+            a.quoted("NAME", &r.name)?;
+            if let Some(lang) = &r.language {
+                a.quoted("LANGUAGE", lang)?;
+            }
+            a.yes("DEFAULT", r.default)?;
+            a.yes("AUTOSELECT", r.autoselect)?;
+            if let Some(ch) = &r.channels {
+                a.quoted("CHANNELS", ch)?;
+            }
+            if let Some(uri) = &r.uri {
+                a.quoted("URI", uri)?;
+            }
+            a.end()?;
+        }
+        for v in self.variants.iter() {
+            let mut a = Attrs::new(f, "#EXT-X-STREAM-INF")?;
+            a.raw("BANDWIDTH", v.bandwidth)?;
+            if let Some(ab) = v.average_bandwidth {
+                a.raw("AVERAGE-BANDWIDTH", ab)?;
+            }
+            if let Some(codecs) = &v.codecs {
+                a.quoted("CODECS", codecs)?;
+            }
+            if let Some((w, h)) = v.resolution {
+                a.raw("RESOLUTION", format_args!("{w}x{h}"))?;
+            }
+            if let Some(fr) = v.frame_rate {
+                a.raw("FRAME-RATE", format_args!("{fr:.3}"))?;
+            }
+            if let Some(g) = &v.audio {
+                a.quoted("AUDIO", g)?;
+            }
+            if let Some(g) = &v.video {
+                a.quoted("VIDEO", g)?;
+            }
+            if let Some(g) = &v.subtitles {
+                a.quoted("SUBTITLES", g)?;
+            }
+            a.end()?;
+            writeln!(f, "{}", v.uri)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Playlist {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Media(p) => p.fmt(f),
+            Self::Multivariant(p) => p.fmt(f),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ParseErrorKind {
+    /// First line is not `#EXTM3U`
+    MissingHeader,
+    /// Tag value can't be parsed
+    InvalidTag(String),
+    /// `EXTINF` or `EXT-X-STREAM-INF` is not followed by URI
+    MissingUri,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ParseError {
+    /// 1-based line number
+    pub line: usize,
+    pub kind: ParseErrorKind,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ParseErrorKind::MissingHeader => write!(f, "line {}: missing #EXTM3U", self.line),
+            ParseErrorKind::InvalidTag(tag) => write!(f, "line {}: invalid {tag}", self.line),
+            ParseErrorKind::MissingUri => write!(f, "line {}: missing uri", self.line),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Splits attribute list respecting quoted strings.
+fn attrs(s: &str) -> impl Iterator<Item = (&str, &str)> {
+    let mut rest = s;
+    std::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+        let mut quoted = false;
+        let mut end = rest.len();
+        for (i, c) in rest.char_indices() {
+            match c {
+                '"' => quoted = !quoted,
+                ',' if !quoted => {
+                    end = i;
+                    break;
+                }
+                _ => {}
+            }
+        }
+        let item = &rest[..end];
+        rest = rest.get(end + 1..).unwrap_or("");
+        let (k, v) = item.split_once('=').unwrap_or((item, ""));
+        let v = v.trim();
+        let v = v
+            .strip_prefix('"')
+            .and_then(|v| v.strip_suffix('"'))
+            .unwrap_or(v);
+        Some((k.trim(), v))
+    })
+}
+
+fn parse_byte_range(s: &str) -> Option<ByteRange> {
+    match s.split_once('@') {
+        Some((len, off)) => Some(ByteRange {
+            len: len.parse().ok()?,
+            offset: Some(off.parse().ok()?),
+        }),
+        None => Some(ByteRange {
+            len: s.parse().ok()?,
+            offset: None,
+        }),
+    }
+}
+
+fn parse_map(s: &str) -> Option<Map> {
+    let mut uri = None;
+    let mut byte_range = None;
+    for (k, v) in attrs(s) {
+        match k {
+            "URI" => uri = Some(v.to_string()),
+            "BYTERANGE" => byte_range = Some(parse_byte_range(v)?),
+            _ => {}
+        }
+    }
+    Some(Map {
+        uri: uri?,
+        byte_range,
+    })
+}
+
+fn parse_part(s: &str) -> Option<Part> {
+    let mut uri = None;
+    let mut duration = None;
+    let mut independent = false;
+    let mut byte_range = None;
+    for (k, v) in attrs(s) {
+        match k {
+            "URI" => uri = Some(v.to_string()),
+            "DURATION" => duration = Some(v.parse().ok()?),
+            "INDEPENDENT" => independent = v == "YES",
+            "BYTERANGE" => byte_range = Some(parse_byte_range(v)?),
+            _ => {}
+        }
+    }
+    Some(Part {
+        uri: uri?,
+        duration: duration?,
+        independent,
+        byte_range,
+    })
+}
+
+fn parse_server_control(s: &str) -> Option<ServerControl> {
+    let mut res = ServerControl::default();
+    for (k, v) in attrs(s) {
+        match k {
+            "CAN-BLOCK-RELOAD" => res.can_block_reload = v == "YES",
+            "CAN-SKIP-UNTIL" => res.can_skip_until = Some(v.parse().ok()?),
+            "HOLD-BACK" => res.hold_back = Some(v.parse().ok()?),
+            "PART-HOLD-BACK" => res.part_hold_back = Some(v.parse().ok()?),
+            _ => {}
+        }
+    }
+    Some(res)
+}
+
+fn parse_rendition(s: &str) -> Option<Rendition> {
+    let mut type_ = None;
+    let mut group_id = None;
+    let mut name = None;
+    let mut res = Rendition {
+        type_: RenditionType::Audio,
+        group_id: String::new(),
+        name: String::new(),
+        language: None,
+        uri: None,
+        default: false,
+        autoselect: false,
+        channels: None,
+    };
+    for (k, v) in attrs(s) {
+        match k {
+            "TYPE" => type_ = RenditionType::from_str(v),
+            "GROUP-ID" => group_id = Some(v.to_string()),
+            "NAME" => name = Some(v.to_string()),
+            "LANGUAGE" => res.language = Some(v.to_string()),
+            "URI" => res.uri = Some(v.to_string()),
+            "DEFAULT" => res.default = v == "YES",
+            "AUTOSELECT" => res.autoselect = v == "YES",
+            "CHANNELS" => res.channels = Some(v.to_string()),
+            _ => {}
+        }
+    }
+    res.type_ = type_?;
+    res.group_id = group_id?;
+    res.name = name?;
+    Some(res)
+}
+
+fn parse_stream_inf(s: &str) -> Option<Variant> {
+    let mut bandwidth = None;
+    let mut res = Variant::default();
+    for (k, v) in attrs(s) {
+        match k {
+            "BANDWIDTH" => bandwidth = Some(v.parse().ok()?),
+            "AVERAGE-BANDWIDTH" => res.average_bandwidth = Some(v.parse().ok()?),
+            "CODECS" => res.codecs = Some(v.to_string()),
+            "RESOLUTION" => {
+                let (w, h) = v.split_once('x')?;
+                res.resolution = Some((w.parse().ok()?, h.parse().ok()?));
+            }
+            "FRAME-RATE" => res.frame_rate = Some(v.parse().ok()?),
+            "AUDIO" => res.audio = Some(v.to_string()),
+            "VIDEO" => res.video = Some(v.to_string()),
+            "SUBTITLES" => res.subtitles = Some(v.to_string()),
+            _ => {}
+        }
+    }
+    res.bandwidth = bandwidth?;
+    Some(res)
+}
+
+impl Playlist {
+    /// Parses media or multivariant playlist. Unknown tags and comments are ignored.
+    pub fn parse(s: &str) -> Result<Self, ParseError> {
+        let mut lines = s
+            .lines()
+            .enumerate()
+            .map(|(i, l)| (i + 1, l.trim()))
+            .filter(|(_, l)| !l.is_empty());
+
+        match lines.next() {
+            Some((_, "#EXTM3U")) => {}
+            Some((line, _)) => {
+                return Err(ParseError {
+                    line,
+                    kind: ParseErrorKind::MissingHeader,
+                })
+            }
+            None => {
+                return Err(ParseError {
+                    line: 1,
+                    kind: ParseErrorKind::MissingHeader,
+                })
+            }
+        }
+
+        let mut media = MediaPlaylist::default();
+        let mut multi = MultivariantPlaylist::default();
+        let mut is_multi = false;
+
+        let mut seg = Segment::default();
+        let mut has_inf = false;
+        let mut variant: Option<Variant> = None;
+
+        for (line, l) in lines {
+            let invalid = |tag: &str| ParseError {
+                line,
+                kind: ParseErrorKind::InvalidTag(tag.to_string()),
+            };
+
+            if !l.starts_with('#') {
+                if let Some(mut v) = variant.take() {
+                    v.uri = l.to_string();
+                    multi.variants.push(v);
+                } else if has_inf {
+                    seg.uri = l.to_string();
+                    media.segments.push(std::mem::take(&mut seg));
+                    has_inf = false;
+                }
+                // uri without tags is ignored
+                continue;
+            }
+            if !l.starts_with("#EXT") {
+                // comment
+                continue;
+            }
+            if variant.is_some() {
+                return Err(ParseError {
+                    line,
+                    kind: ParseErrorKind::MissingUri,
+                });
+            }
+
+            let (tag, val) = l.split_once(':').unwrap_or((l, ""));
+            match tag {
+                "#EXT-X-VERSION" => {
+                    let v = val.parse().map_err(|_| invalid(tag))?;
+                    media.version = v;
+                    multi.version = v;
+                }
+                "#EXT-X-INDEPENDENT-SEGMENTS" => {
+                    media.independent_segments = true;
+                    multi.independent_segments = true;
+                }
+                "#EXT-X-TARGETDURATION" => {
+                    media.target_duration = val.parse().map_err(|_| invalid(tag))?
+                }
+                "#EXT-X-MEDIA-SEQUENCE" => {
+                    media.media_sequence = val.parse().map_err(|_| invalid(tag))?
+                }
+                "#EXT-X-DISCONTINUITY-SEQUENCE" => {
+                    media.discontinuity_sequence = val.parse().map_err(|_| invalid(tag))?
+                }
+                "#EXT-X-PLAYLIST-TYPE" => {
+                    media.playlist_type = Some(match val {
+                        "EVENT" => PlaylistType::Event,
+                        "VOD" => PlaylistType::Vod,
+                        _ => return Err(invalid(tag)),
+                    })
+                }
+                "#EXT-X-SERVER-CONTROL" => {
+                    media.server_control = Some(parse_server_control(val).ok_or(invalid(tag))?)
+                }
+                "#EXT-X-PART-INF" => {
+                    let pt = attrs(val)
+                        .find(|(k, _)| *k == "PART-TARGET")
+                        .and_then(|(_, v)| v.parse().ok())
+                        .ok_or(invalid(tag))?;
+                    media.part_target = Some(pt);
+                }
+                "#EXT-X-MAP" => {
+                    let map = parse_map(val).ok_or(invalid(tag))?;
+                    if media.map.is_none() && media.segments.is_empty() {
+                        media.map = Some(map);
+                    } else {
+                        seg.map = Some(map);
+                    }
+                }
+                "#EXT-X-DISCONTINUITY" => seg.discontinuity = true,
+                "#EXT-X-PROGRAM-DATE-TIME" => seg.program_date_time = Some(val.to_string()),
+                "#EXT-X-BYTERANGE" => {
+                    seg.byte_range = Some(parse_byte_range(val).ok_or(invalid(tag))?)
+                }
+                "#EXT-X-PART" => seg.parts.push(parse_part(val).ok_or(invalid(tag))?),
+                "#EXT-X-PRELOAD-HINT" => {
+                    let uri = attrs(val).find(|(k, _)| *k == "URI").ok_or(invalid(tag))?.1;
+                    media.preload_hint = Some(PreloadHint {
+                        uri: uri.to_string(),
+                    });
+                }
+                "#EXTINF" => {
+                    let (dur, title) = val.split_once(',').unwrap_or((val, ""));
+                    seg.duration = dur.trim().parse().map_err(|_| invalid(tag))?;
+                    seg.title = if title.is_empty() {
+                        None
+                    } else {
+                        Some(title.to_string())
+                    };
+                    has_inf = true;
+                }
+                "#EXT-X-ENDLIST" => media.end_list = true,
+                "#EXT-X-MEDIA" => {
+                    is_multi = true;
+                    multi
+                        .renditions
+                        .push(parse_rendition(val).ok_or(invalid(tag))?);
+                }
+                "#EXT-X-STREAM-INF" => {
+                    is_multi = true;
+                    variant = Some(parse_stream_inf(val).ok_or(invalid(tag))?);
+                }
+                _ => {}
+            }
+        }
+
+        if variant.is_some() || has_inf {
+            return Err(ParseError {
+                line: s.lines().count(),
+                kind: ParseErrorKind::MissingUri,
+            });
+        }
+
+        if is_multi {
+            Ok(Self::Multivariant(multi))
+        } else {
+            media.pending_parts = seg.parts;
+            Ok(Self::Media(media))
+        }
+    }
+}
+
+impl std::str::FromStr for Playlist {
+    type Err = ParseError;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::av::hls;
+
+    #[test]
+    fn media_roundtrip() {
+        let mut pl = hls::MediaPlaylist {
+            target_duration: 6,
+            map: Some(hls::Map::new("init.mp4")),
+            independent_segments: true,
+            ..Default::default()
+        };
+        pl.segments.push(hls::Segment::new("seg0.m4s", 6.0));
+        pl.segments.push(hls::Segment::new("seg1.m4s", 5.5));
+        pl.end_list = true;
+
+        let s = pl.to_string();
+        assert_eq!(
+            s,
+            "#EXTM3U
+#EXT-X-VERSION:7
+#EXT-X-TARGETDURATION:6
+#EXT-X-MEDIA-SEQUENCE:0
+#EXT-X-INDEPENDENT-SEGMENTS
+#EXT-X-MAP:URI=\"init.mp4\"
+#EXTINF:6.00000,
+seg0.m4s
+#EXTINF:5.50000,
+seg1.m4s
+#EXT-X-ENDLIST
+"
+        );
+        assert_eq!(hls::Playlist::parse(&s).unwrap(), hls::Playlist::Media(pl));
+    }
+
+    #[test]
+    fn low_latency_roundtrip() {
+        let part = |uri: &str, independent| hls::Part {
+            uri: uri.to_string(),
+            duration: 0.5,
+            independent,
+            byte_range: None,
+        };
+        let mut seg = hls::Segment::new("s10.m4s", 1.0);
+        seg.parts = vec![part("s10.0.m4s", true), part("s10.1.m4s", false)];
+        let pl = hls::MediaPlaylist {
+            version: 9,
+            target_duration: 1,
+            media_sequence: 10,
+            server_control: Some(hls::ServerControl {
+                can_block_reload: true,
+                part_hold_back: Some(1.5),
+                ..Default::default()
+            }),
+            part_target: Some(0.5),
+            map: Some(hls::Map {
+                uri: "init.mp4".to_string(),
+                byte_range: Some(hls::ByteRange {
+                    len: 720,
+                    offset: Some(0),
+                }),
+            }),
+            segments: vec![seg],
+            pending_parts: vec![part("s11.0.m4s", true)],
+            preload_hint: Some(hls::PreloadHint {
+                uri: "s11.1.m4s".to_string(),
+            }),
+            ..Default::default()
+        };
+        let s = pl.to_string();
+        assert!(s.contains("#EXT-X-SERVER-CONTROL:CAN-BLOCK-RELOAD=YES,PART-HOLD-BACK=1.50000\n"));
+        assert!(s.contains("#EXT-X-PART:DURATION=0.50000,URI=\"s10.0.m4s\",INDEPENDENT=YES\n"));
+        assert!(s.ends_with("#EXT-X-PRELOAD-HINT:TYPE=PART,URI=\"s11.1.m4s\"\n"));
+        assert_eq!(hls::Playlist::parse(&s).unwrap(), hls::Playlist::Media(pl));
+    }
+
+    #[test]
+    fn multivariant_roundtrip() {
+        let pl = hls::MultivariantPlaylist {
+            independent_segments: true,
+            renditions: vec![hls::Rendition {
+                type_: hls::RenditionType::Audio,
+                group_id: "aac".to_string(),
+                name: "English".to_string(),
+                language: Some("en".to_string()),
+                uri: Some("audio/index.m3u8".to_string()),
+                default: true,
+                autoselect: true,
+                channels: Some("2".to_string()),
+            }],
+            variants: vec![hls::Variant {
+                uri: "1080p/index.m3u8".to_string(),
+                bandwidth: 6_000_000,
+                average_bandwidth: Some(5_000_000),
+                codecs: Some("hvc1.2.4.L123.B0,mp4a.40.2".to_string()),
+                resolution: Some((1920, 1080)),
+                frame_rate: Some(30.0),
+                audio: Some("aac".to_string()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let s = pl.to_string();
+        assert!(s.contains("CODECS=\"hvc1.2.4.L123.B0,mp4a.40.2\",RESOLUTION=1920x1080,"));
+        assert_eq!(
+            hls::Playlist::parse(&s).unwrap(),
+            hls::Playlist::Multivariant(pl)
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        let err = hls::Playlist::parse("#EXTINF:1,\nfoo").unwrap_err();
+        assert_eq!(err.kind, hls::ParseErrorKind::MissingHeader);
+
+        let err = hls::Playlist::parse("#EXTM3U\n#EXT-X-TARGETDURATION:x\n").unwrap_err();
+        assert_eq!(err.line, 2);
+
+        let err = hls::Playlist::parse("#EXTM3U\n#EXTINF:1,\n").unwrap_err();
+        assert_eq!(err.kind, hls::ParseErrorKind::MissingUri);
+
+        let pl =
+            hls::Playlist::parse("#EXTM3U\n# comment\n#EXT-X-UNKNOWN:1\n#EXTINF:2,title\na.ts\n")
+                .unwrap();
+        let hls::Playlist::Media(pl) = pl else {
+            panic!("media playlist expected")
+        };
+        assert_eq!(pl.segments[0].title.as_deref(), Some("title"));
+        assert_eq!(pl.min_target_duration(), 2);
+    }
+}
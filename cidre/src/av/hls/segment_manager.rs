@@ -0,0 +1,482 @@
+use std::{collections::HashMap, fs, io, path::PathBuf};
+
+use crate::{av, ns};
+
+use super::{Map, MediaPlaylist, Part, PlaylistType, PreloadHint, Segment, ServerControl};
+
+/// Destination for playlists and segments produced by [`SegmentManager`].
+pub trait Storage {
+    fn write(&mut self, name: &str, data: &[u8]) -> io::Result<()>;
+    fn remove(&mut self, name: &str) -> io::Result<()>;
+}
+
+/// In memory storage, handy for serving segments directly from the process.
+impl Storage for HashMap<String, Vec<u8>> {
+    fn write(&mut self, name: &str, data: &[u8]) -> io::Result<()> {
+        self.insert(name.to_string(), data.to_vec());
+        Ok(())
+    }
+
+    fn remove(&mut self, name: &str) -> io::Result<()> {
+        HashMap::remove(self, name);
+        Ok(())
+    }
+}
+
+/// Writes files into directory. Files are replaced atomically
+/// so clients never see partially written playlist.
+#[derive(Debug, Clone)]
+pub struct DirStorage {
+    dir: PathBuf,
+}
+
+impl DirStorage {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &PathBuf {
+        &self.dir
+    }
+}
+
+impl Storage for DirStorage {
+    fn write(&mut self, name: &str, data: &[u8]) -> io::Result<()> {
+        let path = self.dir.join(name);
+        let tmp = self.dir.join(format!(".{name}.tmp"));
+        fs::write(&tmp, data)?;
+        fs::rename(tmp, path)
+    }
+
+    fn remove(&mut self, name: &str) -> io::Result<()> {
+        match fs::remove_file(self.dir.join(name)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SegmentManagerCfg {
+    /// Prefix for all file names: `{base}.m3u8`, `{base}.mp4`, `{base}{n}.m4s`
+    /// and `{base}part{n}.m4s` for parts.
+    pub base_name: String,
+
+    /// Should match `av::AssetWriter::preferred_output_segment_interval`
+    /// (or segment duration when parts are enabled).
+    /// Longer segments, e.g. with late key frames, are kept and raise
+    /// `EXT-X-TARGETDURATION` of the playlist.
+    pub target_duration: u32,
+
+    /// Number of segments to keep in live playlist. `None` keeps everything.
+    pub window: Option<usize>,
+
+    pub playlist_type: Option<PlaylistType>,
+
+    /// Enables LL-HLS. Each segment from the writer becomes a partial segment
+    /// and parts are grouped into segments of `target_duration`.
+    /// Writer's segment interval should be set to this value.
+    pub part_target: Option<f64>,
+}
+
+impl Default for SegmentManagerCfg {
+    fn default() -> Self {
+        Self {
+            base_name: "hls".to_string(),
+            target_duration: 6,
+            window: None,
+            playlist_type: None,
+            part_target: None,
+        }
+    }
+}
+
+/// Builds HLS media playlist from fMP4 segments emitted by `av::AssetWriter` delegate.
+pub struct SegmentManager<S: Storage> {
+    cfg: SegmentManagerCfg,
+    storage: S,
+    playlist: MediaPlaylist,
+    /// Number of the next segment
+    seq: u64,
+    /// Number of the next part, parts are numbered across segments
+    /// so preload hint is known before the segment boundary is.
+    part_seq: u64,
+    /// Collected data of the current segment in parts mode
+    parts_data: Vec<u8>,
+    total_bytes: u64,
+    total_duration: f64,
+    peak_bandwidth: u64,
+}
+
+impl<S: Storage> SegmentManager<S> {
+    /// Parts are dropped from the playlist when older than this number of target durations.
+    pub const PARTS_WINDOW: u32 = 3;
+
+    pub fn new(cfg: SegmentManagerCfg, storage: S) -> Self {
+        let mut playlist = MediaPlaylist {
+            target_duration: cfg.target_duration,
+            playlist_type: cfg.playlist_type,
+            independent_segments: cfg.part_target.is_none(),
+            ..Default::default()
+        };
+        if let Some(pt) = cfg.part_target {
+            playlist.part_target = Some(pt);
+            playlist.server_control = Some(ServerControl {
+                part_hold_back: Some(pt * 3.0),
+                ..Default::default()
+            });
+        }
+        Self {
+            cfg,
+            storage,
+            playlist,
+            seq: 0,
+            part_seq: 0,
+            parts_data: Vec::new(),
+            total_bytes: 0,
+            total_duration: 0.0,
+            peak_bandwidth: 0,
+        }
+    }
+
+    #[inline]
+    pub fn cfg(&self) -> &SegmentManagerCfg {
+        &self.cfg
+    }
+
+    #[inline]
+    pub fn playlist(&self) -> &MediaPlaylist {
+        &self.playlist
+    }
+
+    #[inline]
+    pub fn storage(&self) -> &S {
+        &self.storage
+    }
+
+    #[inline]
+    pub fn storage_mut(&mut self) -> &mut S {
+        &mut self.storage
+    }
+
+    pub fn playlist_name(&self) -> String {
+        format!("{}.m3u8", self.cfg.base_name)
+    }
+
+    pub fn init_name(&self) -> String {
+        format!("{}.mp4", self.cfg.base_name)
+    }
+
+    fn segment_name(&self, seq: u64) -> String {
+        format!("{}{}.m4s", self.cfg.base_name, seq)
+    }
+
+    fn part_name(&self, seq: u64) -> String {
+        format!("{}part{}.m4s", self.cfg.base_name, seq)
+    }
+
+    /// Peak segment bit rate, suitable for `BANDWIDTH` of multivariant playlist.
+    #[inline]
+    pub fn peak_bandwidth(&self) -> u64 {
+        self.peak_bandwidth
+    }
+
+    /// Suitable for `AVERAGE-BANDWIDTH` of multivariant playlist.
+    pub fn average_bandwidth(&self) -> u64 {
+        if self.total_duration > 0.0 {
+            (self.total_bytes as f64 * 8.0 / self.total_duration) as u64
+        } else {
+            0
+        }
+    }
+
+    /// Handles `av::AssetSegmentType::Initialization` output.
+    pub fn on_init(&mut self, data: &[u8]) -> io::Result<()> {
+        let name = self.init_name();
+        self.storage.write(&name, data)?;
+        self.playlist.map = Some(Map::new(name));
+        self.write_playlist()
+    }
+
+    /// Handles `av::AssetSegmentType::Separable` output.
+    ///
+    /// `independent` is `true` if segment starts with a sync sample.
+    /// Segments longer than target duration raise the playlist target duration.
+    pub fn on_segment(&mut self, data: &[u8], duration: f64, independent: bool) -> io::Result<()> {
+        let target = self.cfg.target_duration as f64;
+        if !duration.is_finite() || duration < 0.0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid segment duration",
+            ));
+        }
+        if self.cfg.part_target.is_none() {
+            let name = self.segment_name(self.seq);
+            self.storage.write(&name, data)?;
+            self.push_segment(Segment::new(name, duration), data.len());
+            self.trim()?;
+            return self.write_playlist();
+        }
+
+        let pending_dur: f64 = self.playlist.pending_parts.iter().map(|p| p.duration).sum();
+        let dur = pending_dur + duration;
+        // segments are cut at independent parts, or at any part
+        // before they outgrow target duration
+        if !self.playlist.pending_parts.is_empty()
+            && ((independent && dur > target + 0.001) || dur.round() > target)
+        {
+            self.close_parts()?;
+        }
+
+        let name = self.part_name(self.part_seq);
+        self.part_seq += 1;
+        self.storage.write(&name, data)?;
+        self.parts_data.extend_from_slice(data);
+        self.playlist.pending_parts.push(Part {
+            uri: name,
+            duration,
+            independent,
+            byte_range: None,
+        });
+        let hint = self.part_name(self.part_seq);
+        self.playlist.preload_hint = Some(PreloadHint { uri: hint });
+        self.trim()?;
+        self.write_playlist()
+    }
+
+    /// Handles delegate callback of `av::AssetWriter`.
+    pub fn handle_segment(
+        &mut self,
+        data: &ns::Data,
+        segment_type: av::AssetSegmentType,
+        report: Option<&av::AssetSegmentReport>,
+    ) -> io::Result<()> {
+        match segment_type {
+            av::AssetSegmentType::Initialization => self.on_init(data.as_slice()),
+            av::AssetSegmentType::Separable => {
+                let Some(report) = report else {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "segment report is required",
+                    ));
+                };
+                let mut duration = 0.0f64;
+                let mut independent = true;
+                for track in report.track_reports().iter() {
+                    let dur = track.duration();
+                    if !dur.is_valid() {
+                        continue;
+                    }
+                    if track.media_type().is_equal(av::MediaType::video()) {
+                        duration = dur.as_secs();
+                        independent = track.first_sample_info().is_sync();
+                        break;
+                    }
+                    duration = duration.max(dur.as_secs());
+                }
+                self.on_segment(data.as_slice(), duration, independent)
+            }
+        }
+    }
+
+    /// Completes pending parts and marks playlist with `EXT-X-ENDLIST`.
+    pub fn finish(&mut self) -> io::Result<()> {
+        if !self.playlist.pending_parts.is_empty() {
+            self.close_parts()?;
+        }
+        self.playlist.preload_hint = None;
+        self.playlist.end_list = true;
+        self.write_playlist()
+    }
+
+    fn close_parts(&mut self) -> io::Result<()> {
+        let name = self.segment_name(self.seq);
+        self.storage.write(&name, &self.parts_data)?;
+        let parts = std::mem::take(&mut self.playlist.pending_parts);
+        let duration = parts.iter().map(|p| p.duration).sum();
+        let len = self.parts_data.len();
+        self.parts_data.clear();
+        let mut segment = Segment::new(name, duration);
+        segment.parts = parts;
+        self.push_segment(segment, len);
+        Ok(())
+    }
+
+    fn push_segment(&mut self, segment: Segment, len: usize) {
+        // EXTINF durations rounded to integer must not exceed target duration
+        let rounded = segment.duration.round() as u32;
+        self.playlist.target_duration = self.playlist.target_duration.max(rounded);
+        if segment.duration > 0.0 {
+            let bw = (len as f64 * 8.0 / segment.duration) as u64;
+            self.peak_bandwidth = self.peak_bandwidth.max(bw);
+        }
+        self.total_bytes += len as u64;
+        self.total_duration += segment.duration;
+        self.playlist.segments.push(segment);
+        self.seq += 1;
+    }
+
+    fn trim(&mut self) -> io::Result<()> {
+        if let Some(window) = self.cfg.window {
+            while self.playlist.segments.len() > window {
+                let s = self.playlist.segments.remove(0);
+                self.storage.remove(&s.uri)?;
+                for p in s.parts.iter() {
+                    self.storage.remove(&p.uri)?;
+                }
+                if s.discontinuity {
+                    self.playlist.discontinuity_sequence += 1;
+                }
+                self.playlist.media_sequence += 1;
+            }
+        }
+
+        let limit = (self.playlist.target_duration * Self::PARTS_WINDOW) as f64;
+        let mut age: f64 = self.playlist.pending_parts.iter().map(|p| p.duration).sum();
+        for i in (0..self.playlist.segments.len()).rev() {
+            if age > limit {
+                for p in std::mem::take(&mut self.playlist.segments[i].parts) {
+                    self.storage.remove(&p.uri)?;
+                }
+            }
+            age += self.playlist.segments[i].duration;
+        }
+        Ok(())
+    }
+
+    fn write_playlist(&mut self) -> io::Result<()> {
+        let name = self.playlist_name();
+        let data = self.playlist.to_string();
+        self.storage.write(&name, data.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::av::hls;
+
+    #[test]
+    fn sliding_window() {
+        let cfg = hls::SegmentManagerCfg {
+            target_duration: 2,
+            window: Some(3),
+            ..Default::default()
+        };
+        let mut m = hls::SegmentManager::new(cfg, HashMap::new());
+        m.on_init(b"init").unwrap();
+        for _ in 0..5 {
+            m.on_segment(&[0u8; 500], 2.0, true).unwrap();
+        }
+        m.on_segment(&[0u8; 1000], 2.4, true).unwrap();
+        m.finish().unwrap();
+
+        let pl = m.playlist();
+        assert_eq!(pl.media_sequence, 3);
+        assert_eq!(pl.target_duration, 2);
+        let uris: Vec<_> = pl.segments.iter().map(|s| s.uri.as_str()).collect();
+        assert_eq!(uris, ["hls3.m4s", "hls4.m4s", "hls5.m4s"]);
+
+        let files = m.storage();
+        assert!(!files.contains_key("hls0.m4s"));
+        assert_eq!(files["hls.mp4"], b"init");
+        let text = std::str::from_utf8(&files["hls.m3u8"]).unwrap();
+        assert!(text.starts_with("#EXTM3U\n"));
+        assert!(text.contains("#EXT-X-MAP:URI=\"hls.mp4\"\n"));
+        assert!(text.ends_with("#EXTINF:2.40000,\nhls5.m4s\n#EXT-X-ENDLIST\n"));
+
+        assert_eq!(
+            hls::Playlist::parse(text).unwrap(),
+            hls::Playlist::Media(pl.clone())
+        );
+
+        assert_eq!(m.peak_bandwidth(), 1000 * 8 * 10 / 24);
+        assert_eq!(m.average_bandwidth(), (3500.0 * 8.0 / 12.4) as u64);
+    }
+
+    #[test]
+    fn long_segment() {
+        let mut m = hls::SegmentManager::new(Default::default(), HashMap::new());
+        m.on_segment(b"a", 6.4, true).unwrap();
+        assert_eq!(m.playlist().target_duration, 6);
+        m.on_segment(b"b", 7.6, true).unwrap();
+        assert_eq!(m.playlist().target_duration, 8);
+        assert_eq!(m.playlist().segments[1].duration, 7.6);
+        assert_eq!(m.storage()["hls1.m4s"], b"b");
+        let text = std::str::from_utf8(&m.storage()["hls.m3u8"]).unwrap();
+        assert!(text.contains("#EXT-X-TARGETDURATION:8\n"));
+        assert!(m.on_segment(b"c", f64::NAN, true).is_err());
+    }
+
+    #[test]
+    fn parts() {
+        let cfg = hls::SegmentManagerCfg {
+            target_duration: 1,
+            part_target: Some(0.5),
+            ..Default::default()
+        };
+        let mut m = hls::SegmentManager::new(cfg, HashMap::new());
+        m.on_init(b"init").unwrap();
+        m.on_segment(b"aa", 0.5, true).unwrap();
+        m.on_segment(b"bb", 0.5, false).unwrap();
+        // not independent, but the segment would outgrow target duration
+        m.on_segment(b"cc", 0.5, false).unwrap();
+        // independent, fits into the segment
+        m.on_segment(b"dd", 0.5, true).unwrap();
+
+        let pl = m.playlist();
+        assert_eq!(pl.segments.len(), 1);
+        assert_eq!(pl.segments[0].uri, "hls0.m4s");
+        assert_eq!(pl.segments[0].duration, 1.0);
+        assert_eq!(pl.segments[0].parts.len(), 2);
+        assert_eq!(pl.pending_parts.len(), 2);
+        assert_eq!(pl.pending_parts[0].uri, "hlspart2.m4s");
+        assert_eq!(pl.preload_hint.as_ref().unwrap().uri, "hlspart4.m4s");
+        assert_eq!(pl.target_duration, 1);
+        assert_eq!(m.storage()["hls0.m4s"], b"aabb");
+
+        let text = std::str::from_utf8(&m.storage()["hls.m3u8"]).unwrap();
+        assert!(text.contains("#EXT-X-PART-INF:PART-TARGET=0.50000\n"));
+        assert!(text.contains("PART-HOLD-BACK=1.50000"));
+        assert_eq!(
+            hls::Playlist::parse(text).unwrap(),
+            hls::Playlist::Media(pl.clone())
+        );
+
+        for _ in 0..20 {
+            m.on_segment(b"ee", 0.5, true).unwrap();
+        }
+        m.finish().unwrap();
+        let pl = m.playlist();
+        assert!(pl.segments[0].parts.is_empty());
+        assert!(!m.storage().contains_key("hlspart0.m4s"));
+        assert!(pl.segments.iter().all(|s| s.duration <= 1.0));
+        assert!(!pl.segments.last().unwrap().parts.is_empty());
+        assert!(pl.pending_parts.is_empty());
+        assert!(pl.preload_hint.is_none());
+        assert!(pl.end_list);
+    }
+
+    #[test]
+    fn preload_hint_across_segments() {
+        let cfg = hls::SegmentManagerCfg {
+            target_duration: 1,
+            part_target: Some(0.5),
+            ..Default::default()
+        };
+        let mut m = hls::SegmentManager::new(cfg, HashMap::new());
+        m.on_segment(b"aa", 0.5, true).unwrap();
+        m.on_segment(b"bb", 0.5, false).unwrap();
+        let hint = m.playlist().preload_hint.clone().unwrap().uri;
+
+        // closes the first segment
+        m.on_segment(b"cc", 0.5, true).unwrap();
+        let pl = m.playlist();
+        assert_eq!(pl.segments.len(), 1);
+        assert_eq!(pl.pending_parts.len(), 1);
+        assert_eq!(pl.pending_parts[0].uri, hint);
+        assert_eq!(m.storage()[&hint], b"cc");
+        assert_ne!(pl.preload_hint.as_ref().unwrap().uri, hint);
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{define_obj_type, ns};
+use crate::{arc, av, define_cls, define_obj_type, ns, objc};
 
 #[doc(alias = "AVPlayerItemStatus")]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -14,6 +14,31 @@ define_obj_type!(
     pub Item(ns::Id)
 );
 
+impl Item {
+    define_cls!(AV_PLAYER_ITEM);
+
+    #[objc::msg_send(playerItemWithAsset:)]
+    pub fn with_asset(asset: &av::Asset) -> arc::R<Self>;
+
+    #[objc::msg_send(asset)]
+    pub fn asset(&self) -> arc::R<av::Asset>;
+
+    #[objc::msg_send(status)]
+    pub fn status(&self) -> Status;
+
+    #[objc::msg_send(videoComposition)]
+    pub fn video_composition(&self) -> Option<arc::R<av::VideoComposition>>;
+
+    #[objc::msg_send(setVideoComposition:)]
+    pub fn set_video_composition(&mut self, val: Option<&av::VideoComposition>);
+
+    #[objc::msg_send(audioMix)]
+    pub fn audio_mix(&self) -> Option<arc::R<av::AudioMix>>;
+
+    #[objc::msg_send(setAudioMix:)]
+    pub fn set_audio_mix(&mut self, val: Option<&av::AudioMix>);
+}
+
 impl ns::NotificationName {
     /// A notification the system posts when a player item’s time changes discontinuously.
     ///
@@ -114,3 +139,8 @@ extern "C" {
         &'static ns::NotificationName;
     static AVPlayerItemMediaSelectionDidChangeNotification: &'static ns::NotificationName;
 }
+
+#[link(name = "av", kind = "static")]
+extern "C" {
+    static AV_PLAYER_ITEM: &'static objc::Class<Item>;
+}
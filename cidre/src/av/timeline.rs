@@ -0,0 +1,757 @@
+//! Edit decision timeline.
+//!
+//! [`Timeline`] is a stack of tracks with clips cut from source tracks,
+//! speed changes, transitions and volume ramps. [`Timeline::plan`] lays it out
+//! onto composition tracks, video composition instructions and audio mix ramps
+//! which [`Timeline::build`] turns into AVFoundation objects for playback and export.
+
+use std::{cmp::Ordering, fmt};
+
+use crate::cm;
+
+mod build;
+pub use build::Built;
+pub use build::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaKind {
+    Video,
+    Audio,
+}
+
+/// Transition from the previous clip, audio crossfades for both kinds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transition {
+    Dissolve,
+    /// Outgoing clip fades out during the first half, incoming fades in during the second.
+    DipToBlack,
+}
+
+/// Linear change of opacity or volume, the end value holds after the range.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ramp {
+    pub range: cm::TimeRange,
+    pub start: f32,
+    pub end: f32,
+}
+
+impl Ramp {
+    pub fn new(range: cm::TimeRange, start: f32, end: f32) -> Self {
+        Self { range, start, end }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Clip {
+    /// Index of the source track passed to [`Timeline::build`].
+    pub source: usize,
+    pub src_range: cm::TimeRange,
+    /// Position on the timeline, duration differs from the source one for speed changes.
+    pub range: cm::TimeRange,
+    /// Transition from the previous clip, the clips overlap by its duration.
+    pub transition_in: Option<(Transition, cm::Time)>,
+}
+
+impl Clip {
+    pub fn new(source: usize, src_range: cm::TimeRange, start: cm::Time) -> Self {
+        Self {
+            source,
+            src_range,
+            range: cm::TimeRange::new(start, src_range.duration),
+            transition_in: None,
+        }
+    }
+
+    /// Plays the source `speed` times faster.
+    pub fn with_speed(mut self, speed: f64) -> Self {
+        self.range.duration = self.src_range.duration.mul_f64(1.0 / speed);
+        self
+    }
+
+    pub fn with_transition(mut self, transition: Transition, duration: cm::Time) -> Self {
+        self.transition_in = Some((transition, duration));
+        self
+    }
+
+    pub fn speed(&self) -> f64 {
+        self.src_range.duration.as_secs() / self.range.duration.as_secs()
+    }
+
+    pub fn end(&self) -> cm::Time {
+        self.range.start.add(self.range.duration)
+    }
+
+    pub fn time_mapping(&self) -> cm::TimeMapping {
+        cm::TimeMapping {
+            source: self.src_range,
+            target: self.range,
+        }
+    }
+
+    fn is_valid(&self) -> bool {
+        let zero = cm::Time::zero();
+        [self.src_range, self.range]
+            .iter()
+            .all(|r| r.start.is_numeric() && r.duration.is_numeric() && r.duration > zero)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Track {
+    pub kind: MediaKind,
+    /// Clips ordered by start.
+    pub clips: Vec<Clip>,
+    /// Audio only, must not overlap each other and crossfades.
+    pub volume_ramps: Vec<Ramp>,
+    /// Gaps render as black or silence, otherwise they are reported by [`Timeline::validate`].
+    pub allow_gaps: bool,
+}
+
+impl Track {
+    pub fn new(kind: MediaKind) -> Self {
+        Self {
+            kind,
+            clips: Vec::new(),
+            volume_ramps: Vec::new(),
+            allow_gaps: false,
+        }
+    }
+
+    pub fn video() -> Self {
+        Self::new(MediaKind::Video)
+    }
+
+    pub fn audio() -> Self {
+        Self::new(MediaKind::Audio)
+    }
+
+    pub fn push(&mut self, clip: Clip) -> &mut Self {
+        self.clips.push(clip);
+        self
+    }
+
+    /// Lowers volume to `volume` during `range`, fading in and out within it.
+    pub fn duck(&mut self, range: cm::TimeRange, volume: f32, fade: cm::Time) -> &mut Self {
+        let end = range.start.add(range.duration);
+        self.volume_ramps.push(Ramp::new(
+            cm::TimeRange::new(range.start, fade),
+            1.0,
+            volume,
+        ));
+        self.volume_ramps.push(Ramp::new(
+            cm::TimeRange::new(end.sub(fade), fade),
+            volume,
+            1.0,
+        ));
+        self
+    }
+
+    pub fn end(&self) -> cm::Time {
+        self.clips
+            .iter()
+            .map(Clip::end)
+            .fold(cm::Time::zero(), cm::Time::max)
+    }
+
+    /// Volume set by ramps at `time`.
+    pub fn volume_at(&self, time: cm::Time) -> f32 {
+        let mut ramps: Vec<_> = self.volume_ramps.iter().collect();
+        ramps.sort_by(|a, b| cmp(&a.range.start, &b.range.start));
+        let mut volume = 1.0;
+        for ramp in ramps {
+            let end = ramp.range.start.add(ramp.range.duration);
+            if time < ramp.range.start {
+                break;
+            }
+            if time >= end {
+                volume = ramp.end;
+            } else {
+                let pos = time.sub(ramp.range.start).as_secs() / ramp.range.duration.as_secs();
+                volume = ramp.start + (ramp.end - ramp.start) * pos as f32;
+            }
+        }
+        volume
+    }
+
+    /// Overlaps of clips with their previous ones.
+    pub fn crossfades(&self) -> impl Iterator<Item = cm::TimeRange> + '_ {
+        self.clips.iter().skip(1).filter_map(|c| {
+            c.transition_in
+                .map(|(_, d)| cm::TimeRange::new(c.range.start, d))
+        })
+    }
+
+    fn check(&self, track: usize, issues: &mut Vec<Issue>) {
+        let zero = cm::Time::zero();
+        let mut covered = zero;
+        for (i, clip) in self.clips.iter().enumerate() {
+            if !clip.is_valid() {
+                issues.push(Issue::InvalidRange { track, clip: i });
+                continue;
+            }
+            if i > 0 && clip.range.start < self.clips[i - 1].range.start {
+                issues.push(Issue::Unordered { track, clip: i });
+                continue;
+            }
+            if clip.range.start > covered && !self.allow_gaps {
+                issues.push(Issue::Gap {
+                    track,
+                    range: cm::TimeRange::new(covered, clip.range.start.sub(covered)),
+                });
+            }
+            // only the previous clip may overlap
+            let earlier_end = self.clips[..i.saturating_sub(1)]
+                .iter()
+                .map(Clip::end)
+                .fold(zero, cm::Time::max);
+            if earlier_end > clip.range.start {
+                issues.push(Issue::Overlap {
+                    track,
+                    clip: i,
+                    range: cm::TimeRange::new(clip.range.start, earlier_end.sub(clip.range.start)),
+                });
+            }
+            // speed changes round durations to their timescale
+            let (overlap, tolerance) = match i.checked_sub(1) {
+                Some(prev) => {
+                    let prev = &self.clips[prev];
+                    (
+                        cm::Time::max(zero, prev.end().sub(clip.range.start)),
+                        cm::Time::new(1, prev.range.duration.scale),
+                    )
+                }
+                None => (zero, zero),
+            };
+            match clip.transition_in {
+                Some((_, duration))
+                    if i == 0
+                        || duration <= zero
+                        || overlap <= zero
+                        || duration.sub(overlap).abs() > tolerance
+                        || duration > clip.range.duration =>
+                {
+                    issues.push(Issue::TransitionMismatch { track, clip: i });
+                }
+                Some(_) => {}
+                None if overlap > zero => issues.push(Issue::Overlap {
+                    track,
+                    clip: i,
+                    range: cm::TimeRange::new(clip.range.start, overlap),
+                }),
+                None => {}
+            }
+            covered = cm::Time::max(covered, clip.end());
+        }
+
+        if self.kind == MediaKind::Video && !self.volume_ramps.is_empty() {
+            issues.push(Issue::RampOnVideo { track });
+            return;
+        }
+        let unit = 0.0..=1.0;
+        for (r, ramp) in self.volume_ramps.iter().enumerate() {
+            if !unit.contains(&ramp.start) || !unit.contains(&ramp.end) {
+                issues.push(Issue::VolumeOutOfRange { track, ramp: r });
+            }
+            let overlaps = self.volume_ramps[..r]
+                .iter()
+                .map(|o| o.range)
+                .chain(self.crossfades())
+                .any(|o| intersects(&o, &ramp.range));
+            if overlaps {
+                issues.push(Issue::RampOverlap { track, ramp: r });
+            }
+        }
+    }
+
+    fn opacity_ramps(&self, i: usize) -> Vec<Ramp> {
+        let mut ramps = Vec::new();
+        let clip = &self.clips[i];
+        if let Some((Transition::DipToBlack, duration)) = clip.transition_in {
+            let half = duration.mul_f64(0.5);
+            let mid = clip.range.start.add(half);
+            ramps.push(Ramp::new(
+                cm::TimeRange::new(clip.range.start, half),
+                0.0,
+                0.0,
+            ));
+            ramps.push(Ramp::new(
+                cm::TimeRange::new(mid, duration.sub(half)),
+                0.0,
+                1.0,
+            ));
+        }
+        let Some(next) = self.clips.get(i + 1) else {
+            return ramps;
+        };
+        if let Some((transition, duration)) = next.transition_in {
+            let start = next.range.start;
+            let duration = match transition {
+                Transition::Dissolve => duration,
+                Transition::DipToBlack => duration.mul_f64(0.5),
+            };
+            ramps.push(Ramp::new(cm::TimeRange::new(start, duration), 1.0, 0.0));
+        }
+        ramps
+    }
+}
+
+/// Reason of timeline rejection.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Issue {
+    /// Source or timeline range of the clip is invalid or empty.
+    InvalidRange {
+        track: usize,
+        clip: usize,
+    },
+    /// Clip starts before the previous one.
+    Unordered {
+        track: usize,
+        clip: usize,
+    },
+    /// Clip overlaps earlier clips without transition.
+    Overlap {
+        track: usize,
+        clip: usize,
+        range: cm::TimeRange,
+    },
+    Gap {
+        track: usize,
+        range: cm::TimeRange,
+    },
+    /// Transition duration doesn't match overlap with the previous clip.
+    TransitionMismatch {
+        track: usize,
+        clip: usize,
+    },
+    RampOnVideo {
+        track: usize,
+    },
+    /// Volume ramp overlaps another ramp or a crossfade.
+    RampOverlap {
+        track: usize,
+        ramp: usize,
+    },
+    VolumeOutOfRange {
+        track: usize,
+        ramp: usize,
+    },
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let secs = |r: &cm::TimeRange| (r.start.as_secs(), r.start.add(r.duration).as_secs());
+        match self {
+            Self::InvalidRange { track, clip } => {
+                write!(f, "track {track} clip {clip}: invalid or empty time range")
+            }
+            Self::Unordered { track, clip } => {
+                write!(f, "track {track} clip {clip}: starts before previous clip")
+            }
+            Self::Overlap { track, clip, range } => {
+                let (start, end) = secs(range);
+                write!(
+                    f,
+                    "track {track} clip {clip}: overlaps earlier clip at {start}s..{end}s without transition"
+                )
+            }
+            Self::Gap { track, range } => {
+                let (start, end) = secs(range);
+                write!(f, "track {track}: gap at {start}s..{end}s")
+            }
+            Self::TransitionMismatch { track, clip } => write!(
+                f,
+                "track {track} clip {clip}: transition duration doesn't match overlap with previous clip"
+            ),
+            Self::RampOnVideo { track } => {
+                write!(f, "track {track}: volume ramps on video track")
+            }
+            Self::RampOverlap { track, ramp } => {
+                write!(f, "track {track} ramp {ramp}: overlaps another ramp or crossfade")
+            }
+            Self::VolumeOutOfRange { track, ramp } => {
+                write!(f, "track {track} ramp {ramp}: volume is out of 0..=1")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Issue {}
+
+/// Composition track with `(track, clip)` indices of timeline clips in time order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlanTrack {
+    pub kind: MediaKind,
+    pub clips: Vec<(usize, usize)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layer {
+    /// Index of composition track in [`Plan::tracks`].
+    pub track: usize,
+    pub opacity: Vec<Ramp>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instruction {
+    pub range: cm::TimeRange,
+    /// Top to bottom, empty for black.
+    pub layers: Vec<Layer>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioParams {
+    /// Index of composition track in [`Plan::tracks`].
+    pub track: usize,
+    /// Zero duration ramps set volume at their start.
+    pub volume: Vec<Ramp>,
+}
+
+/// Timeline laid out onto composition tracks.
+///
+/// Tracks with transitions take two composition tracks with alternating clips.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plan {
+    pub tracks: Vec<PlanTrack>,
+    /// Cover the whole duration, empty without video.
+    pub instructions: Vec<Instruction>,
+    pub audio: Vec<AudioParams>,
+    pub duration: cm::Time,
+}
+
+/// Stack of tracks, later video tracks are composited on top.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Timeline {
+    pub tracks: Vec<Track>,
+}
+
+impl Timeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, track: Track) -> &mut Self {
+        self.tracks.push(track);
+        self
+    }
+
+    pub fn duration(&self) -> cm::Time {
+        self.tracks
+            .iter()
+            .map(Track::end)
+            .fold(cm::Time::zero(), cm::Time::max)
+    }
+
+    /// Checks overlaps, gaps, transitions and ramps of all tracks.
+    pub fn validate(&self) -> Result<(), Vec<Issue>> {
+        let mut issues = Vec::new();
+        for (t, track) in self.tracks.iter().enumerate() {
+            track.check(t, &mut issues);
+        }
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(issues)
+        }
+    }
+
+    pub fn plan(&self) -> Result<Plan, Vec<Issue>> {
+        self.validate()?;
+        let mut tracks = Vec::new();
+        // composition track of each clip
+        let mut slots = Vec::with_capacity(self.tracks.len());
+        for (t, track) in self.tracks.iter().enumerate() {
+            let n = if track.clips.is_empty() {
+                0
+            } else if track.crossfades().next().is_some() {
+                2
+            } else {
+                1
+            };
+            let first = tracks.len();
+            tracks.extend((0..n).map(|_| PlanTrack {
+                kind: track.kind,
+                clips: Vec::new(),
+            }));
+            let track_slots: Vec<_> = (0..track.clips.len()).map(|i| first + i % n).collect();
+            for (i, &slot) in track_slots.iter().enumerate() {
+                tracks[slot].clips.push((t, i));
+            }
+            slots.push(track_slots);
+        }
+        let duration = self.duration();
+        Ok(Plan {
+            tracks,
+            instructions: self.instructions(&slots, duration),
+            audio: self.audio_params(&slots),
+            duration,
+        })
+    }
+
+    fn instructions(&self, slots: &[Vec<usize>], duration: cm::Time) -> Vec<Instruction> {
+        let video: Vec<_> = self
+            .tracks
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| t.kind == MediaKind::Video && !t.clips.is_empty())
+            .collect();
+        if video.is_empty() {
+            return Vec::new();
+        }
+        let mut times = vec![cm::Time::zero(), duration];
+        for (_, track) in &video {
+            for clip in &track.clips {
+                times.push(clip.range.start);
+                times.push(clip.end());
+            }
+        }
+        times.sort_by(cmp);
+        times.dedup();
+
+        times
+            .windows(2)
+            .map(|w| {
+                let mut layers = Vec::new();
+                for (t, track) in video.iter().rev() {
+                    for (i, clip) in track.clips.iter().enumerate() {
+                        if clip.range.start <= w[0] && clip.end() >= w[1] {
+                            layers.push(Layer {
+                                track: slots[*t][i],
+                                opacity: track.opacity_ramps(i),
+                            });
+                        }
+                    }
+                }
+                Instruction {
+                    range: cm::TimeRange::new(w[0], w[1].sub(w[0])),
+                    layers,
+                }
+            })
+            .collect()
+    }
+
+    fn audio_params(&self, slots: &[Vec<usize>]) -> Vec<AudioParams> {
+        let mut res = Vec::new();
+        for (t, track) in self.tracks.iter().enumerate() {
+            if track.kind != MediaKind::Audio {
+                continue;
+            }
+            let mut track_slots = slots[t].clone();
+            track_slots.sort_unstable();
+            track_slots.dedup();
+            for slot in track_slots {
+                let mut volume = track.volume_ramps.clone();
+                let mut faded_out = false;
+                for (i, clip) in track.clips.iter().enumerate() {
+                    if slots[t][i] != slot {
+                        continue;
+                    }
+                    let start = clip.range.start;
+                    let level = track.volume_at(start);
+                    match clip.transition_in {
+                        Some((_, d)) => {
+                            volume.push(Ramp::new(cm::TimeRange::new(start, d), 0.0, level))
+                        }
+                        None if faded_out => volume.push(Ramp::new(
+                            cm::TimeRange::new(start, cm::Time::zero()),
+                            level,
+                            level,
+                        )),
+                        None => {}
+                    }
+                    faded_out = false;
+                    let Some(next) = track.clips.get(i + 1) else {
+                        continue;
+                    };
+                    if let Some((_, d)) = next.transition_in {
+                        let start = next.range.start;
+                        let level = track.volume_at(start);
+                        volume.push(Ramp::new(cm::TimeRange::new(start, d), level, 0.0));
+                        faded_out = true;
+                    }
+                }
+                if !volume.is_empty() {
+                    volume.sort_by(|a, b| cmp(&a.range.start, &b.range.start));
+                    res.push(AudioParams {
+                        track: slot,
+                        volume,
+                    });
+                }
+            }
+        }
+        res
+    }
+}
+
+fn cmp(a: &cm::Time, b: &cm::Time) -> Ordering {
+    a.partial_cmp(b).unwrap_or(Ordering::Equal)
+}
+
+fn intersects(a: &cm::TimeRange, b: &cm::TimeRange) -> bool {
+    a.start < b.start.add(b.duration) && b.start < a.start.add(a.duration)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        av::timeline::{self, Clip, Issue, Ramp, Track, Transition},
+        cm,
+    };
+
+    fn t(secs: i64) -> cm::Time {
+        cm::Time::new(secs, 1)
+    }
+
+    fn range(start: i64, duration: i64) -> cm::TimeRange {
+        cm::TimeRange::new(t(start), t(duration))
+    }
+
+    #[test]
+    fn validate() {
+        let mut video = Track::video();
+        video
+            .push(Clip::new(0, range(0, 4), t(0)))
+            .push(Clip::new(0, range(10, 4), t(3)))
+            .push(Clip::new(1, range(0, 2), t(9)).with_transition(Transition::Dissolve, t(1)));
+        video.volume_ramps.push(Ramp::new(range(0, 1), 1.0, 0.0));
+        let mut timeline = timeline::Timeline::new();
+        timeline.push(video);
+        assert_eq!(
+            timeline.validate().unwrap_err(),
+            [
+                Issue::Overlap {
+                    track: 0,
+                    clip: 1,
+                    range: range(3, 1)
+                },
+                Issue::Gap {
+                    track: 0,
+                    range: range(7, 2)
+                },
+                Issue::TransitionMismatch { track: 0, clip: 2 },
+                Issue::RampOnVideo { track: 0 },
+            ]
+        );
+
+        let mut audio = Track::audio();
+        audio
+            .push(Clip::new(0, range(0, 4), t(0)))
+            .push(Clip::new(0, range(4, 4), t(3)).with_transition(Transition::Dissolve, t(1)))
+            .duck(range(3, 3), 0.25, t(1));
+        audio.volume_ramps.push(Ramp::new(range(6, 1), 1.0, 1.5));
+        let timeline = timeline::Timeline {
+            tracks: vec![audio],
+        };
+        let issues = timeline.validate().unwrap_err();
+        assert_eq!(
+            issues,
+            [
+                Issue::RampOverlap { track: 0, ramp: 0 },
+                Issue::VolumeOutOfRange { track: 0, ramp: 2 },
+            ]
+        );
+        assert_eq!(
+            issues[0].to_string(),
+            "track 0 ramp 0: overlaps another ramp or crossfade"
+        );
+
+        // 7x speed rounds the first clip to 857/600 s, overlap is 601/600 s
+        let mut video = Track::video();
+        video
+            .push(
+                Clip::new(0, cm::TimeRange::new(t(0), cm::Time::new(6000, 600)), t(0))
+                    .with_speed(7.0),
+            )
+            .push(
+                Clip::new(0, range(10, 4), cm::Time::new(256, 600))
+                    .with_transition(Transition::Dissolve, t(1)),
+            );
+        assert_eq!(video.clips[0].range.duration, cm::Time::new(857, 600));
+        let mut timeline = timeline::Timeline::new();
+        timeline.push(video);
+        assert_eq!(timeline.validate(), Ok(()));
+
+        timeline.tracks[0].clips[1].range.start = cm::Time::new(255, 600);
+        assert_eq!(
+            timeline.validate().unwrap_err(),
+            [Issue::TransitionMismatch { track: 0, clip: 1 }]
+        );
+    }
+
+    #[test]
+    fn video_plan() {
+        let mut main = Track::video();
+        main.push(Clip::new(0, range(0, 4), t(0)))
+            .push(Clip::new(1, range(0, 4), t(3)).with_transition(Transition::Dissolve, t(1)));
+        let mut overlay = Track::video();
+        overlay.allow_gaps = true;
+        overlay.push(Clip::new(2, range(0, 4), t(2)).with_speed(2.0));
+        let mut timeline = timeline::Timeline::new();
+        timeline.push(main).push(overlay);
+
+        assert_eq!(timeline.tracks[1].clips[0].range, range(2, 2));
+        assert_eq!(timeline.tracks[1].clips[0].speed(), 2.0);
+
+        let plan = timeline.plan().unwrap();
+        assert_eq!(plan.duration, t(7));
+        assert_eq!(plan.tracks.len(), 3);
+        assert_eq!(plan.tracks[0].clips, [(0, 0)]);
+        assert_eq!(plan.tracks[1].clips, [(0, 1)]);
+        assert_eq!(plan.tracks[2].clips, [(1, 0)]);
+
+        let ranges: Vec<_> = plan.instructions.iter().map(|i| i.range).collect();
+        assert_eq!(ranges, [range(0, 2), range(2, 1), range(3, 1), range(4, 3)]);
+        let layers: Vec<Vec<_>> = plan
+            .instructions
+            .iter()
+            .map(|i| i.layers.iter().map(|l| l.track).collect())
+            .collect();
+        assert_eq!(layers, [vec![0], vec![2, 0], vec![2, 0, 1], vec![1]]);
+        assert_eq!(
+            plan.instructions[2].layers[1].opacity,
+            [Ramp::new(range(3, 1), 1.0, 0.0)]
+        );
+        assert!(plan.instructions[2].layers[2].opacity.is_empty());
+        assert!(plan.audio.is_empty());
+    }
+
+    #[test]
+    fn audio_plan() {
+        let mut music = Track::audio();
+        music
+            .push(Clip::new(0, range(0, 4), t(0)))
+            .push(Clip::new(0, range(10, 4), t(2)).with_transition(Transition::Dissolve, t(2)))
+            .push(Clip::new(0, range(20, 2), t(6)))
+            .duck(range(7, 1), 0.5, cm::Time::new(1, 2));
+        let mut timeline = timeline::Timeline::new();
+        timeline.push(music);
+        assert_eq!(timeline.tracks[0].volume_at(cm::Time::new(15, 2)), 0.5);
+
+        let plan = timeline.plan().unwrap();
+        assert!(plan.instructions.is_empty());
+        assert_eq!(plan.tracks[0].clips, [(0, 0), (0, 2)]);
+        assert_eq!(plan.tracks[1].clips, [(0, 1)]);
+
+        let duck_in = Ramp::new(cm::TimeRange::new(t(7), cm::Time::new(1, 2)), 1.0, 0.5);
+        let duck_out = Ramp::new(
+            cm::TimeRange::new(cm::Time::new(15, 2), cm::Time::new(1, 2)),
+            0.5,
+            1.0,
+        );
+        assert_eq!(plan.audio.len(), 2);
+        assert_eq!(plan.audio[0].track, 0);
+        assert_eq!(
+            plan.audio[0].volume,
+            [
+                Ramp::new(range(2, 2), 1.0, 0.0),
+                Ramp::new(range(6, 0), 1.0, 1.0),
+                duck_in,
+                duck_out,
+            ]
+        );
+        assert_eq!(plan.audio[1].track, 1);
+        assert_eq!(
+            plan.audio[1].volume,
+            [Ramp::new(range(2, 2), 0.0, 1.0), duck_in, duck_out]
+        );
+    }
+}
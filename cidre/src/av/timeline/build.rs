@@ -0,0 +1,194 @@
+use std::fmt;
+
+use crate::{arc, av, cg, cm, ns};
+
+use super::{Issue, MediaKind, Timeline};
+
+#[derive(Debug)]
+pub enum Error {
+    Invalid(Vec<Issue>),
+    /// Clip refers to a missing source track.
+    UnknownSource(usize),
+    /// Clip's source track has other media type than the track it is placed on.
+    KindMismatch {
+        track: usize,
+        clip: usize,
+    },
+    TrackNotAdded,
+    Ns(arc::R<ns::Error>),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Invalid(issues) => {
+                f.write_str("invalid timeline")?;
+                for issue in issues {
+                    write!(f, "; {issue}")?;
+                }
+                Ok(())
+            }
+            Self::UnknownSource(source) => write!(f, "unknown source track {source}"),
+            Self::KindMismatch { track, clip } => {
+                write!(
+                    f,
+                    "track {track} clip {clip}: source track has other media type"
+                )
+            }
+            Self::TrackNotAdded => f.write_str("composition track can't be added"),
+            Self::Ns(e) => write!(f, "{}", &**e),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<Vec<Issue>> for Error {
+    fn from(value: Vec<Issue>) -> Self {
+        Self::Invalid(value)
+    }
+}
+
+impl From<&ns::Error> for Error {
+    fn from(value: &ns::Error) -> Self {
+        Self::Ns(value.retained())
+    }
+}
+
+/// Composition with video composition and audio mix to use together,
+/// e.g. with [`av::PlayerItem`] or [`av::AssetReaderVideoCompositionOutput`].
+pub struct Built {
+    pub composition: arc::R<av::CompositionMut>,
+    /// `None` without video tracks.
+    pub video_composition: Option<arc::R<av::VideoCompositionMut>>,
+    /// `None` without volume changes.
+    pub audio_mix: Option<arc::R<av::AudioMixMut>>,
+}
+
+impl MediaKind {
+    fn media_type(self) -> &'static av::MediaType {
+        match self {
+            Self::Video => av::MediaType::video(),
+            Self::Audio => av::MediaType::audio(),
+        }
+    }
+}
+
+impl Timeline {
+    /// Materializes the timeline, clip sources index `sources`.
+    pub fn build(
+        &self,
+        sources: &[&av::asset::Track],
+        render_size: cg::Size,
+        frame_duration: cm::Time,
+    ) -> Result<Built, Error> {
+        let plan = self.plan()?;
+        for (t, track) in self.tracks.iter().enumerate() {
+            for (i, clip) in track.clips.iter().enumerate() {
+                let Some(src) = sources.get(clip.source) else {
+                    return Err(Error::UnknownSource(clip.source));
+                };
+                if !src.media_type().is_equal(track.kind.media_type()) {
+                    return Err(Error::KindMismatch { track: t, clip: i });
+                }
+            }
+        }
+
+        let mut composition = av::CompositionMut::new();
+        composition.set_natural_size(render_size);
+        let mut tracks = Vec::with_capacity(plan.tracks.len());
+        for plan_track in &plan.tracks {
+            let mut track = composition
+                .add_track(plan_track.kind.media_type(), cm::PersistentTrackId::INVALID)
+                .ok_or(Error::TrackNotAdded)?;
+            let mut end = cm::Time::zero();
+            for &(t, i) in &plan_track.clips {
+                let clip = &self.tracks[t].clips[i];
+                let start = clip.range.start;
+                if start > end {
+                    track.insert_empty_time_range(cm::TimeRange::new(end, start.sub(end)));
+                }
+                track.insert_time_range_of_track(clip.src_range, sources[clip.source], start)?;
+                if clip.src_range.duration != clip.range.duration {
+                    track.scale_time_range(
+                        cm::TimeRange::new(start, clip.src_range.duration),
+                        clip.range.duration,
+                    );
+                }
+                end = clip.end();
+            }
+            tracks.push(track);
+        }
+
+        let video_composition = (!plan.instructions.is_empty()).then(|| {
+            let instructions: Vec<_> = plan
+                .instructions
+                .iter()
+                .map(|instruction| {
+                    let layers: Vec<_> = instruction
+                        .layers
+                        .iter()
+                        .map(|layer| {
+                            let mut res = av::VideoCompositionLayerInstructionMut::with_track(
+                                &tracks[layer.track],
+                            );
+                            for ramp in &layer.opacity {
+                                res.set_opacity_ramp(ramp.start, ramp.end, ramp.range);
+                            }
+                            res
+                        })
+                        .collect();
+                    let layers: Vec<&av::VideoCompositionLayerInstruction> = layers
+                        .iter()
+                        .map(|l| -> &av::VideoCompositionLayerInstruction { l })
+                        .collect();
+                    let mut res = av::VideoCompositionInstructionMut::new();
+                    res.set_time_range(instruction.range);
+                    res.set_layer_instructions(&ns::Array::from_slice(&layers));
+                    res
+                })
+                .collect();
+            let instructions: Vec<&av::VideoCompositionInstruction> = instructions
+                .iter()
+                .map(|i| -> &av::VideoCompositionInstruction { i })
+                .collect();
+            let mut res = av::VideoCompositionMut::new();
+            res.set_frame_duration(frame_duration);
+            res.set_render_size(render_size);
+            res.set_instructions(&ns::Array::from_slice(&instructions));
+            res
+        });
+
+        let audio_mix = (!plan.audio.is_empty()).then(|| {
+            let params: Vec<_> = plan
+                .audio
+                .iter()
+                .map(|audio| {
+                    let mut res =
+                        av::AudioMixInputParamsMut::with_track(Some(&tracks[audio.track]));
+                    for ramp in &audio.volume {
+                        if ramp.range.duration == cm::Time::zero() {
+                            res.set_volume_at(ramp.end, ramp.range.start);
+                        } else {
+                            res.set_volume_ramp(ramp.start, ramp.end, ramp.range);
+                        }
+                    }
+                    res
+                })
+                .collect();
+            let params: Vec<&av::AudioMixInputParams> = params
+                .iter()
+                .map(|p| -> &av::AudioMixInputParams { p })
+                .collect();
+            let mut res = av::AudioMixMut::new();
+            res.set_input_params(&ns::Array::from_slice(&params));
+            res
+        });
+
+        Ok(Built {
+            composition,
+            video_composition,
+            audio_mix,
+        })
+    }
+}
@@ -0,0 +1,159 @@
+use crate::{arc, av, cg, cm, define_obj_type, ns, objc};
+
+define_obj_type!(
+    #[doc(alias = "AVVideoComposition")]
+    pub VideoComposition(ns::Id)
+);
+
+define_obj_type!(
+    #[doc(alias = "AVMutableVideoComposition")]
+    pub VideoCompositionMut(VideoComposition),
+    AV_MUTABLE_VIDEO_COMPOSITION
+);
+
+define_obj_type!(
+    #[doc(alias = "AVVideoCompositionInstruction")]
+    pub Instruction(ns::Id)
+);
+
+define_obj_type!(
+    #[doc(alias = "AVMutableVideoCompositionInstruction")]
+    pub InstructionMut(Instruction),
+    AV_MUTABLE_VIDEO_COMPOSITION_INSTRUCTION
+);
+
+define_obj_type!(
+    #[doc(alias = "AVVideoCompositionLayerInstruction")]
+    pub LayerInstruction(ns::Id)
+);
+
+define_obj_type!(
+    #[doc(alias = "AVMutableVideoCompositionLayerInstruction")]
+    pub LayerInstructionMut(LayerInstruction),
+    AV_MUTABLE_VIDEO_COMPOSITION_LAYER_INSTRUCTION
+);
+
+impl VideoComposition {
+    /// The interval which the video composition should render composed video frames.
+    #[objc::msg_send(frameDuration)]
+    pub fn frame_duration(&self) -> cm::Time;
+
+    #[objc::msg_send(renderSize)]
+    pub fn render_size(&self) -> cg::Size;
+
+    #[objc::msg_send(instructions)]
+    pub fn instructions(&self) -> arc::R<ns::Array<Instruction>>;
+}
+
+impl VideoCompositionMut {
+    #[objc::msg_send(setFrameDuration:)]
+    pub fn set_frame_duration(&mut self, val: cm::Time);
+
+    #[objc::msg_send(setRenderSize:)]
+    pub fn set_render_size(&mut self, val: cg::Size);
+
+    /// Instructions must cover the composition duration without gaps and overlaps.
+    #[objc::msg_send(setInstructions:)]
+    pub fn set_instructions(&mut self, val: &ns::Array<Instruction>);
+}
+
+impl Instruction {
+    #[objc::msg_send(timeRange)]
+    pub fn time_range(&self) -> cm::TimeRange;
+
+    /// Layer instructions from top to bottom.
+    #[objc::msg_send(layerInstructions)]
+    pub fn layer_instructions(&self) -> arc::R<ns::Array<LayerInstruction>>;
+
+    #[objc::msg_send(enablePostProcessing)]
+    pub fn post_processing_enabled(&self) -> bool;
+}
+
+impl InstructionMut {
+    #[objc::msg_send(setTimeRange:)]
+    pub fn set_time_range(&mut self, val: cm::TimeRange);
+
+    #[objc::msg_send(setLayerInstructions:)]
+    pub fn set_layer_instructions(&mut self, val: &ns::Array<LayerInstruction>);
+
+    #[objc::msg_send(setBackgroundColor:)]
+    pub fn set_background_color(&mut self, val: Option<&cg::Color>);
+
+    #[objc::msg_send(setEnablePostProcessing:)]
+    pub fn set_post_processing_enabled(&mut self, val: bool);
+}
+
+impl LayerInstruction {
+    #[objc::msg_send(trackID)]
+    pub fn track_id(&self) -> cm::PersistentTrackId;
+}
+
+impl LayerInstructionMut {
+    #[objc::msg_send(videoCompositionLayerInstructionWithAssetTrack:)]
+    pub fn with_track(track: &av::asset::Track) -> arc::R<Self>;
+
+    #[objc::msg_send(setTrackID:)]
+    pub fn set_track_id(&mut self, val: cm::PersistentTrackId);
+
+    #[objc::msg_send(setOpacity:atTime:)]
+    pub fn set_opacity_at(&mut self, opacity: f32, time: cm::Time);
+
+    /// Linear opacity change over the time range, the end opacity holds after it.
+    #[objc::msg_send(setOpacityRampFromStartOpacity:toEndOpacity:timeRange:)]
+    pub fn set_opacity_ramp(&mut self, start: f32, end: f32, time_range: cm::TimeRange);
+
+    #[objc::msg_send(setTransform:atTime:)]
+    pub fn set_transform_at(&mut self, transform: cg::AffineTransform, time: cm::Time);
+
+    #[objc::msg_send(setTransformRampFromStartTransform:toEndTransform:timeRange:)]
+    pub fn set_transform_ramp(
+        &mut self,
+        start: cg::AffineTransform,
+        end: cg::AffineTransform,
+        time_range: cm::TimeRange,
+    );
+
+    #[objc::msg_send(setCropRectangle:atTime:)]
+    pub fn set_crop_rect_at(&mut self, rect: cg::Rect, time: cm::Time);
+}
+
+#[link(name = "av", kind = "static")]
+extern "C" {
+    static AV_MUTABLE_VIDEO_COMPOSITION: &'static objc::Class<VideoCompositionMut>;
+    static AV_MUTABLE_VIDEO_COMPOSITION_INSTRUCTION: &'static objc::Class<InstructionMut>;
+    static AV_MUTABLE_VIDEO_COMPOSITION_LAYER_INSTRUCTION:
+        &'static objc::Class<LayerInstructionMut>;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{av, cg, cm, ns};
+
+    #[test]
+    fn basics() {
+        let mut layer = av::VideoCompositionLayerInstructionMut::new();
+        layer.set_track_id(cm::PersistentTrackId(1));
+        layer.set_opacity_ramp(1.0, 0.0, cm::TimeRange::zero());
+
+        let range = cm::TimeRange {
+            start: cm::Time::zero(),
+            duration: cm::Time::new(1, 1),
+        };
+        let mut instruction = av::VideoCompositionInstructionMut::new();
+        instruction.set_time_range(range);
+        instruction.set_layer_instructions(
+            &ns::Array::<av::VideoCompositionLayerInstruction>::from_slice(&[&layer]),
+        );
+        assert_eq!(instruction.time_range(), range);
+        assert_eq!(instruction.layer_instructions().len(), 1);
+
+        let mut comp = av::VideoCompositionMut::new();
+        comp.set_frame_duration(cm::Time::new(1, 30));
+        comp.set_render_size(cg::Size::new(1920.0, 1080.0));
+        comp.set_instructions(&ns::Array::<av::VideoCompositionInstruction>::from_slice(
+            &[&instruction],
+        ));
+        assert_eq!(comp.frame_duration(), cm::Time::new(1, 30));
+        assert_eq!(comp.instructions().len(), 1);
+    }
+}
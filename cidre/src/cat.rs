@@ -3,16 +3,23 @@ pub mod audio;
 pub use audio::Buf as AudioBuf;
 pub use audio::BufList as AudioBufList;
 pub use audio::BufListN as AudioBufListN;
+pub use audio::Bufs as AudioBufs;
 pub use audio::ChannelBitmap as AudioChannelBitmap;
 pub use audio::ChannelCoordinateIndex;
 pub use audio::ChannelDesc as AudioChannelDesc;
 pub use audio::ChannelFlags as AudioChannelFlags;
 pub use audio::ChannelLabel as AudioChannelLabel;
 pub use audio::ChannelLayout as AudioChannelLayout;
+pub use audio::ChannelLayoutBuf as AudioChannelLayoutBuf;
 pub use audio::ChannelLayoutTag as AudioChannelLayoutTag;
 pub use audio::ClassDesc as AudioClassDesc;
+pub use audio::Dither as AudioDither;
 pub use audio::Format as AudioFormat;
 pub use audio::FormatFlags as AudioFormatFlags;
+pub use audio::MixMatrix as AudioMixMatrix;
+pub use audio::PcmConverter as AudioPcmConverter;
+pub use audio::RingBuf as AudioRingBuf;
+pub use audio::Sample as AudioSample;
 pub use audio::TimeStamp as AudioTimeStamp;
 pub use audio::TimeStampFlags as AudioTimeStampFlags;
 
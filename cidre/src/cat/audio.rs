@@ -1,6 +1,27 @@
+pub mod aac;
+
 mod base_types;
 pub use base_types::*;
 
+mod bufs;
+pub use bufs::Bufs;
+pub use bufs::Sample;
+
+pub mod container;
+pub use container::ChannelLayoutBuf;
+
+mod convert;
+pub use convert::Dither;
+pub use convert::MixMatrix;
+pub use convert::PcmConverter;
+
+mod ring;
+pub use ring::Consumer as RingConsumer;
+pub use ring::Producer as RingProducer;
+pub use ring::ReadStatus as RingReadStatus;
+pub use ring::RingBuf;
+pub use ring::WriteStatus as RingWriteStatus;
+
 mod session_types;
 pub use session_types::ErrorCode as SessionErrorCode;
 pub use session_types::SessionId;
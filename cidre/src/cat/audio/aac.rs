@@ -0,0 +1,864 @@
+//! AAC AudioSpecificConfig, ESDS magic cookie and ADTS framing (ISO/IEC 14496-3, 14496-1).
+
+use std::ffi::c_long;
+
+use super::{Format, FormatFlags, Mpeg4Object, StreamBasicDesc, StreamPacketDesc};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Error {
+    /// Not enough data.
+    Truncated,
+    /// Malformed descriptor or header.
+    Invalid,
+    /// Valid, but not supported configuration.
+    Unsupported,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::Truncated => "aac config is truncated",
+            Self::Invalid => "aac config is invalid",
+            Self::Unsupported => "aac config is not supported",
+        };
+        f.write_str(s)
+    }
+}
+
+impl std::error::Error for Error {}
+
+pub type Result<T = ()> = std::result::Result<T, Error>;
+
+const SAMPLE_RATES: [u32; 13] = [
+    96000, 88200, 64000, 48000, 44100, 32000, 24000, 22050, 16000, 12000, 11025, 8000, 7350,
+];
+
+/// Parametric stereo audio object type.
+const OBJECT_PS: u32 = 29;
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn remaining(&self) -> usize {
+        self.data.len() * 8 - self.pos
+    }
+
+    fn read(&mut self, n: usize) -> Result<u32> {
+        if n > self.remaining() {
+            return Err(Error::Truncated);
+        }
+        let mut res = 0u32;
+        for _ in 0..n {
+            let bit = (self.data[self.pos / 8] >> (7 - self.pos % 8)) & 1;
+            res = (res << 1) | bit as u32;
+            self.pos += 1;
+        }
+        Ok(res)
+    }
+
+    fn read_object(&mut self) -> Result<u32> {
+        match self.read(5)? {
+            31 => Ok(32 + self.read(6)?),
+            object => Ok(object),
+        }
+    }
+
+    fn read_sample_rate(&mut self) -> Result<u32> {
+        match self.read(4)? {
+            15 => self.read(24),
+            i => SAMPLE_RATES.get(i as usize).copied().ok_or(Error::Invalid),
+        }
+    }
+}
+
+#[derive(Default)]
+struct BitWriter {
+    data: Vec<u8>,
+    pos: usize,
+}
+
+impl BitWriter {
+    fn write(&mut self, n: usize, val: u32) {
+        for i in (0..n).rev() {
+            if self.pos % 8 == 0 {
+                self.data.push(0);
+            }
+            let bit = ((val >> i) & 1) as u8;
+            *self.data.last_mut().unwrap() |= bit << (7 - self.pos % 8);
+            self.pos += 1;
+        }
+    }
+
+    /// 31 is the escape value, so it and types over 95 can't be written.
+    fn write_object(&mut self, object: u32) -> Result {
+        match object {
+            0..=30 => self.write(5, object),
+            32..=95 => {
+                self.write(5, 31);
+                self.write(6, object - 32);
+            }
+            _ => return Err(Error::Unsupported),
+        }
+        Ok(())
+    }
+
+    fn write_sample_rate(&mut self, rate: u32) {
+        match SAMPLE_RATES.iter().position(|r| *r == rate) {
+            Some(i) => self.write(4, i as u32),
+            None => {
+                self.write(4, 15);
+                self.write(24, rate);
+            }
+        }
+    }
+}
+
+#[inline]
+fn is_ga(object: u32) -> bool {
+    matches!(object, 1..=4 | 6 | 7 | 17 | 19..=23)
+}
+
+#[inline]
+fn is_er(object: u32) -> bool {
+    matches!(object, 17 | 19..=27 | 39)
+}
+
+/// MPEG-4 AudioSpecificConfig of AAC family codecs.
+///
+/// ```
+/// use cidre::cat::audio::aac;
+///
+/// let asc = aac::AudioSpecificConfig::parse(&[0x12, 0x10]).unwrap();
+/// assert_eq!(asc.sample_rate, 44_100);
+/// assert_eq!(asc.channels(), 2);
+/// assert_eq!(asc.to_bytes().unwrap(), [0x12, 0x10]);
+/// ```
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct AudioSpecificConfig {
+    /// Core audio object, `AAC_LC` for HE-AAC.
+    pub object: Mpeg4Object,
+    /// Core sample rate.
+    pub sample_rate: u32,
+    pub channel_config: u8,
+    pub frame_length_960: bool,
+    /// Output sample rate of spectral band replication (HE-AAC).
+    pub sbr_sample_rate: Option<u32>,
+    /// Parametric stereo (HE-AAC v2).
+    pub ps: bool,
+}
+
+impl AudioSpecificConfig {
+    /// AAC LC config.
+    pub fn lc(sample_rate: u32, channel_config: u8) -> Self {
+        Self {
+            object: Mpeg4Object::AAC_LC,
+            sample_rate,
+            channel_config,
+            frame_length_960: false,
+            sbr_sample_rate: None,
+            ps: false,
+        }
+    }
+
+    /// Builds config from output format of AAC encoder.
+    pub fn with_asbd(asbd: &StreamBasicDesc) -> Result<Self> {
+        let channel_config = match asbd.channels_per_frame {
+            ch @ 1..=6 => ch as u8,
+            8 => 7,
+            _ => return Err(Error::Unsupported),
+        };
+        let rate = asbd.sample_rate as u32;
+        if rate as f64 != asbd.sample_rate {
+            return Err(Error::Unsupported);
+        }
+        let mut res = Self::lc(rate, channel_config);
+        match asbd.format {
+            Format::MPEG4_AAC => {}
+            Format::MPEG4_AAC_HE => {
+                res.sample_rate = rate / 2;
+                res.sbr_sample_rate = Some(rate);
+            }
+            Format::MPEG4_AAC_HE_V2 if channel_config == 2 => {
+                res.sample_rate = rate / 2;
+                res.channel_config = 1;
+                res.sbr_sample_rate = Some(rate);
+                res.ps = true;
+            }
+            _ => return Err(Error::Unsupported),
+        }
+        Ok(res)
+    }
+
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        let mut r = BitReader::new(data);
+        let mut object = r.read_object()?;
+        let sample_rate = r.read_sample_rate()?;
+        let channel_config = r.read(4)? as u8;
+        let mut sbr_sample_rate = None;
+        let mut ps = false;
+
+        // explicit hierarchical signaling
+        if object == Mpeg4Object::AAC_SBR.0 as u32 || object == OBJECT_PS {
+            ps = object == OBJECT_PS;
+            sbr_sample_rate = Some(r.read_sample_rate()?);
+            object = r.read_object()?;
+        }
+        if !is_ga(object) {
+            return Err(Error::Unsupported);
+        }
+
+        // GASpecificConfig
+        let frame_length_960 = r.read(1)? == 1;
+        if r.read(1)? == 1 {
+            // core coder delay
+            r.read(14)?;
+        }
+        let extension = r.read(1)? == 1;
+        if channel_config == 0 {
+            // program config element
+            return Err(Error::Unsupported);
+        }
+        if object == 6 || object == 20 {
+            // layer number
+            r.read(3)?;
+        }
+        if extension {
+            if object == 22 {
+                // number of sub frames and layer length
+                r.read(16)?;
+            }
+            if matches!(object, 17 | 19 | 20 | 23) {
+                // resilience flags
+                r.read(3)?;
+            }
+            // extension flag 3
+            r.read(1)?;
+        }
+        if is_er(object) {
+            // ep config
+            r.read(2)?;
+        }
+
+        // explicit backward compatible signaling
+        if sbr_sample_rate.is_none() && r.remaining() >= 16 && r.read(11)? == 0x2b7 {
+            let ext = r.read_object()?;
+            if ext == Mpeg4Object::AAC_SBR.0 as u32 && r.read(1)? == 1 {
+                sbr_sample_rate = Some(r.read_sample_rate()?);
+                if r.remaining() >= 12 && r.read(11)? == 0x548 {
+                    ps = r.read(1)? == 1;
+                }
+            }
+        }
+
+        Ok(Self {
+            object: Mpeg4Object(object as c_long),
+            sample_rate,
+            channel_config,
+            frame_length_960,
+            sbr_sample_rate,
+            ps,
+        })
+    }
+
+    /// Parses magic cookie of `at::AudioConverter` or `at::AudioFile`,
+    /// which is either ESDS or raw AudioSpecificConfig.
+    pub fn with_magic_cookie(cookie: &[u8]) -> Result<Self> {
+        match Esds::parse(cookie) {
+            Ok(esds) => Self::parse(&esds.asc),
+            Err(_) => Self::parse(cookie),
+        }
+    }
+
+    /// Writes config with explicit hierarchical signaling of SBR and PS.
+    ///
+    /// Fails with [`Error::Unsupported`] if object type can't be written.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let object = self.object.0 as u32;
+        let mut w = BitWriter::default();
+        match self.sbr_sample_rate {
+            Some(sbr_sample_rate) => {
+                w.write_object(if self.ps {
+                    OBJECT_PS
+                } else {
+                    Mpeg4Object::AAC_SBR.0 as u32
+                })?;
+                w.write_sample_rate(self.sample_rate);
+                w.write(4, self.channel_config as u32);
+                w.write_sample_rate(sbr_sample_rate);
+                w.write_object(object)?;
+            }
+            None => {
+                w.write_object(object)?;
+                w.write_sample_rate(self.sample_rate);
+                w.write(4, self.channel_config as u32);
+            }
+        }
+        w.write(1, self.frame_length_960 as u32);
+        // depends on core coder and extension flag
+        w.write(2, 0);
+        if object == 6 || object == 20 {
+            w.write(3, 0);
+        }
+        if is_er(object) {
+            w.write(2, 0);
+        }
+        Ok(w.data)
+    }
+
+    /// Number of output channels, 0 for program config element.
+    pub fn channels(&self) -> u32 {
+        let ch = match self.channel_config {
+            1..=6 => self.channel_config as u32,
+            7 | 12 | 14 => 8,
+            11 => 7,
+            13 => 24,
+            _ => 0,
+        };
+        if self.ps && ch == 1 {
+            2
+        } else {
+            ch
+        }
+    }
+
+    #[inline]
+    pub fn output_sample_rate(&self) -> u32 {
+        self.sbr_sample_rate.unwrap_or(self.sample_rate)
+    }
+
+    /// Output frames per packet.
+    pub fn frames_per_packet(&self) -> u32 {
+        let frames = if self.frame_length_960 { 960 } else { 1024 };
+        match self.sbr_sample_rate {
+            Some(rate) if rate != self.sample_rate => frames * 2,
+            _ => frames,
+        }
+    }
+
+    /// Format to use with `at::AudioConverter` together with this config as magic cookie.
+    pub fn to_asbd(&self) -> StreamBasicDesc {
+        let format = match (self.sbr_sample_rate, self.ps) {
+            (None, _) => Format::MPEG4_AAC,
+            (Some(_), false) => Format::MPEG4_AAC_HE,
+            (Some(_), true) => Format::MPEG4_AAC_HE_V2,
+        };
+        StreamBasicDesc {
+            sample_rate: self.output_sample_rate() as f64,
+            format,
+            format_flags: FormatFlags(self.object.0 as u32),
+            bytes_per_packet: 0,
+            frames_per_packet: self.frames_per_packet(),
+            bytes_per_frame: 0,
+            channels_per_frame: self.channels(),
+            bits_per_channel: 0,
+            reserved: 0,
+        }
+    }
+}
+
+const ES_DESCR_TAG: u8 = 0x03;
+const DECODER_CONFIG_DESCR_TAG: u8 = 0x04;
+const DEC_SPECIFIC_DESCR_TAG: u8 = 0x05;
+const SL_CONFIG_DESCR_TAG: u8 = 0x06;
+
+/// `objectTypeIndication` of MPEG-4 audio.
+const OBJECT_TYPE_MPEG4_AUDIO: u8 = 0x40;
+/// Audio stream type with reserved bit set.
+const STREAM_TYPE_AUDIO: u8 = (0x05 << 2) | 1;
+
+/// Reads descriptor tag and size, returns body.
+fn read_descr(data: &[u8]) -> Result<(u8, &[u8], &[u8])> {
+    let (&tag, mut rest) = data.split_first().ok_or(Error::Truncated)?;
+    let mut len = 0usize;
+    for i in 0..4 {
+        let (&b, r) = rest.split_first().ok_or(Error::Truncated)?;
+        rest = r;
+        len = (len << 7) | (b & 0x7f) as usize;
+        if b & 0x80 == 0 {
+            break;
+        }
+        if i == 3 {
+            return Err(Error::Invalid);
+        }
+    }
+    if rest.len() < len {
+        return Err(Error::Truncated);
+    }
+    Ok((tag, &rest[..len], &rest[len..]))
+}
+
+fn write_descr(buf: &mut Vec<u8>, tag: u8, body: &[u8]) {
+    buf.push(tag);
+    let len = body.len();
+    for shift in [21, 14, 7] {
+        if len >> shift != 0 {
+            buf.push(0x80 | ((len >> shift) & 0x7f) as u8);
+        }
+    }
+    buf.push((len & 0x7f) as u8);
+    buf.extend_from_slice(body);
+}
+
+/// Elementary stream descriptor, the magic cookie format of AAC in Apple frameworks.
+#[doc(alias = "esds")]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Esds {
+    pub es_id: u16,
+    pub object_type_indication: u8,
+    pub stream_type: u8,
+    pub buffer_size: u32,
+    pub max_bitrate: u32,
+    pub avg_bitrate: u32,
+    /// Raw AudioSpecificConfig.
+    pub asc: Vec<u8>,
+}
+
+impl Esds {
+    pub fn with_asc(asc: &AudioSpecificConfig) -> Result<Self> {
+        Ok(Self {
+            es_id: 0,
+            object_type_indication: OBJECT_TYPE_MPEG4_AUDIO,
+            stream_type: STREAM_TYPE_AUDIO,
+            buffer_size: 0,
+            max_bitrate: 0,
+            avg_bitrate: 0,
+            asc: asc.to_bytes()?,
+        })
+    }
+
+    /// Parses ES descriptor with or without version and flags of `esds` box.
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        let data = match data {
+            [0, 0, 0, 0, ES_DESCR_TAG, ..] => &data[4..],
+            _ => data,
+        };
+        let (tag, body, _) = read_descr(data)?;
+        if tag != ES_DESCR_TAG || body.len() < 3 {
+            return Err(Error::Invalid);
+        }
+        let es_id = u16::from_be_bytes([body[0], body[1]]);
+        let flags = body[2];
+        let mut at = 3;
+        if flags & 0x80 != 0 {
+            // depends on es id
+            at += 2;
+        }
+        if flags & 0x40 != 0 {
+            // url
+            at += 1 + *body.get(at).ok_or(Error::Truncated)? as usize;
+        }
+        if flags & 0x20 != 0 {
+            // ocr es id
+            at += 2;
+        }
+        let mut rest = body.get(at..).ok_or(Error::Truncated)?;
+        while !rest.is_empty() {
+            let (tag, body, next) = read_descr(rest)?;
+            rest = next;
+            if tag != DECODER_CONFIG_DESCR_TAG {
+                continue;
+            }
+            if body.len() < 13 {
+                return Err(Error::Truncated);
+            }
+            let u32_at = |at: usize| u32::from_be_bytes(body[at..at + 4].try_into().unwrap());
+            let mut specific = &body[13..];
+            while !specific.is_empty() {
+                let (tag, asc, next) = read_descr(specific)?;
+                specific = next;
+                if tag == DEC_SPECIFIC_DESCR_TAG {
+                    return Ok(Self {
+                        es_id,
+                        object_type_indication: body[0],
+                        stream_type: body[1],
+                        buffer_size: u32_at(1) & 0x00ff_ffff,
+                        max_bitrate: u32_at(5),
+                        avg_bitrate: u32_at(9),
+                        asc: asc.to_vec(),
+                    });
+                }
+            }
+        }
+        Err(Error::Invalid)
+    }
+
+    /// ES descriptor without version and flags of `esds` box.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut config = Vec::with_capacity(15 + self.asc.len());
+        config.push(self.object_type_indication);
+        config.extend_from_slice(
+            &(((self.stream_type as u32) << 24) | (self.buffer_size & 0x00ff_ffff)).to_be_bytes(),
+        );
+        config.extend_from_slice(&self.max_bitrate.to_be_bytes());
+        config.extend_from_slice(&self.avg_bitrate.to_be_bytes());
+        write_descr(&mut config, DEC_SPECIFIC_DESCR_TAG, &self.asc);
+
+        let mut es = Vec::with_capacity(config.len() + 10);
+        es.extend_from_slice(&self.es_id.to_be_bytes());
+        es.push(0);
+        write_descr(&mut es, DECODER_CONFIG_DESCR_TAG, &config);
+        // predefined SL config for MP4 files
+        write_descr(&mut es, SL_CONFIG_DESCR_TAG, &[0x02]);
+
+        let mut res = Vec::with_capacity(es.len() + 5);
+        write_descr(&mut res, ES_DESCR_TAG, &es);
+        res
+    }
+}
+
+/// ADTS frame header.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct AdtsHeader {
+    pub object: Mpeg4Object,
+    pub sample_rate: u32,
+    pub channel_config: u8,
+    /// Frame length including header.
+    pub frame_len: u16,
+    /// 0x7FF for variable bit rate.
+    pub buffer_fullness: u16,
+    /// Number of raw data blocks minus one.
+    pub raw_blocks: u8,
+    pub has_crc: bool,
+}
+
+impl AdtsHeader {
+    pub const LEN: usize = 7;
+    pub const MAX_FRAME_LEN: usize = (1 << 13) - 1;
+
+    /// Header of frame without CRC with payload of `payload_len` bytes.
+    pub fn with_asc(asc: &AudioSpecificConfig, payload_len: usize) -> Result<Self> {
+        let object = asc.object.0;
+        if !(1..=4).contains(&object)
+            || asc.channel_config > 7
+            || asc.frame_length_960
+            || !SAMPLE_RATES.contains(&asc.sample_rate)
+        {
+            return Err(Error::Unsupported);
+        }
+        if payload_len + Self::LEN > Self::MAX_FRAME_LEN {
+            return Err(Error::Unsupported);
+        }
+        Ok(Self {
+            object: asc.object,
+            sample_rate: asc.sample_rate,
+            channel_config: asc.channel_config,
+            frame_len: (payload_len + Self::LEN) as u16,
+            buffer_fullness: 0x7ff,
+            raw_blocks: 0,
+            has_crc: false,
+        })
+    }
+
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        if data.len() < Self::LEN {
+            return Err(Error::Truncated);
+        }
+        let mut r = BitReader::new(&data[..Self::LEN]);
+        // sync word, id and layer
+        if r.read(12)? != 0xfff {
+            return Err(Error::Invalid);
+        }
+        r.read(3)?;
+        let has_crc = r.read(1)? == 0;
+        let object = r.read(2)? + 1;
+        let sample_rate = SAMPLE_RATES
+            .get(r.read(4)? as usize)
+            .copied()
+            .ok_or(Error::Invalid)?;
+        r.read(1)?;
+        let channel_config = r.read(3)? as u8;
+        // original, home, copyright bits
+        r.read(4)?;
+        let frame_len = r.read(13)? as u16;
+        let buffer_fullness = r.read(11)? as u16;
+        let raw_blocks = r.read(2)? as u8;
+        let res = Self {
+            object: Mpeg4Object(object as c_long),
+            sample_rate,
+            channel_config,
+            frame_len,
+            buffer_fullness,
+            raw_blocks,
+            has_crc,
+        };
+        if (frame_len as usize) < res.header_len() {
+            return Err(Error::Invalid);
+        }
+        Ok(res)
+    }
+
+    #[inline]
+    pub fn header_len(&self) -> usize {
+        if self.has_crc {
+            Self::LEN + 2
+        } else {
+            Self::LEN
+        }
+    }
+
+    #[inline]
+    pub fn payload_len(&self) -> usize {
+        self.frame_len as usize - self.header_len()
+    }
+
+    /// Header without CRC.
+    pub fn to_bytes(&self) -> [u8; 7] {
+        let index = SAMPLE_RATES
+            .iter()
+            .position(|r| *r == self.sample_rate)
+            .unwrap_or(15);
+        let mut w = BitWriter::default();
+        w.write(12, 0xfff);
+        // mpeg-4, layer 0, no crc
+        w.write(4, 0b0001);
+        w.write(2, (self.object.0 as u32).saturating_sub(1));
+        w.write(4, index as u32);
+        w.write(1, 0);
+        w.write(3, self.channel_config as u32);
+        w.write(4, 0);
+        w.write(13, self.frame_len as u32);
+        w.write(11, self.buffer_fullness as u32);
+        w.write(2, self.raw_blocks as u32);
+        w.data.try_into().unwrap()
+    }
+
+    pub fn to_asc(&self) -> AudioSpecificConfig {
+        AudioSpecificConfig {
+            object: self.object,
+            sample_rate: self.sample_rate,
+            channel_config: self.channel_config,
+            frame_length_960: false,
+            sbr_sample_rate: None,
+            ps: false,
+        }
+    }
+}
+
+/// Appends each packet of `data` described by `packets` with ADTS header to `out`.
+pub fn add_adts(
+    asc: &AudioSpecificConfig,
+    data: &[u8],
+    packets: &[StreamPacketDesc],
+    out: &mut Vec<u8>,
+) -> Result {
+    for p in packets {
+        let start = p.start_offset as usize;
+        let end = start
+            .checked_add(p.data_byte_size as usize)
+            .ok_or(Error::Truncated)?;
+        let payload = data.get(start..end).ok_or(Error::Truncated)?;
+        let header = AdtsHeader::with_asc(asc, payload.len())?;
+        out.extend_from_slice(&header.to_bytes());
+        out.extend_from_slice(payload);
+    }
+    Ok(())
+}
+
+/// Appends payloads of complete ADTS frames of `data` to `out` with packet descriptions
+/// relative to the start of `out`. Returns number of consumed bytes, the rest of `data`
+/// is incomplete frame.
+///
+/// Frames carrying more than one raw data block fail with [`Error::Unsupported`].
+pub fn strip_adts(
+    data: &[u8],
+    out: &mut Vec<u8>,
+    packets: &mut Vec<StreamPacketDesc>,
+) -> Result<usize> {
+    let mut pos = 0;
+    while data.len() - pos >= AdtsHeader::LEN {
+        let header = AdtsHeader::parse(&data[pos..])?;
+        if header.raw_blocks != 0 {
+            return Err(Error::Unsupported);
+        }
+        let frame_len = header.frame_len as usize;
+        if data.len() - pos < frame_len {
+            break;
+        }
+        packets.push(StreamPacketDesc {
+            start_offset: out.len() as i64,
+            variable_frames_in_packet: 0,
+            data_byte_size: header.payload_len() as u32,
+        });
+        out.extend_from_slice(&data[pos + header.header_len()..pos + frame_len]);
+        pos += frame_len;
+    }
+    Ok(pos)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cat::audio::{self, aac};
+
+    #[test]
+    fn asc() {
+        let lc = aac::AudioSpecificConfig::parse(&[0x11, 0x90]).unwrap();
+        assert_eq!(lc, aac::AudioSpecificConfig::lc(48_000, 2));
+        assert_eq!(lc.frames_per_packet(), 1024);
+
+        let he = aac::AudioSpecificConfig::parse(&[0x2b, 0x11, 0x88, 0x00]).unwrap();
+        assert_eq!(he.object, audio::Mpeg4Object::AAC_LC);
+        assert_eq!(he.sample_rate, 24_000);
+        assert_eq!(he.sbr_sample_rate, Some(48_000));
+        assert_eq!(he.frames_per_packet(), 2048);
+        assert_eq!(he.to_bytes().unwrap(), [0x2b, 0x11, 0x88, 0x00]);
+
+        // backward compatible signaling: LC 22050 stereo, SBR 44100, PS
+        let he_v2 =
+            aac::AudioSpecificConfig::parse(&[0x13, 0x90, 0x56, 0xe5, 0xa5, 0x48, 0x80]).unwrap();
+        assert_eq!(he_v2.sample_rate, 22_050);
+        assert_eq!(he_v2.sbr_sample_rate, Some(44_100));
+        assert!(he_v2.ps);
+
+        let asbd = audio::StreamBasicDesc {
+            sample_rate: 44_100.0,
+            format: audio::Format::MPEG4_AAC_HE_V2,
+            channels_per_frame: 2,
+            ..Default::default()
+        };
+        let asc = aac::AudioSpecificConfig::with_asbd(&asbd).unwrap();
+        assert_eq!(asc.channel_config, 1);
+        assert_eq!(asc.channels(), 2);
+        let parsed = aac::AudioSpecificConfig::parse(&asc.to_bytes().unwrap()).unwrap();
+        assert_eq!(parsed, asc);
+        let asbd = parsed.to_asbd();
+        assert_eq!(asbd.format, audio::Format::MPEG4_AAC_HE_V2);
+        assert_eq!(asbd.sample_rate, 44_100.0);
+        assert_eq!(asbd.frames_per_packet, 2048);
+
+        // explicit sample rate
+        let mut odd = aac::AudioSpecificConfig::lc(44_000, 1);
+        odd.frame_length_960 = true;
+        assert_eq!(
+            aac::AudioSpecificConfig::parse(&odd.to_bytes().unwrap()),
+            Ok(odd)
+        );
+
+        assert_eq!(
+            aac::AudioSpecificConfig::parse(&[0x12]),
+            Err(aac::Error::Truncated)
+        );
+    }
+
+    #[test]
+    fn object_types() {
+        for object in (0..=30).chain(32..=95) {
+            let mut w = aac::BitWriter::default();
+            w.write_object(object).unwrap();
+            w.write(3, 0b101);
+            let mut r = aac::BitReader::new(&w.data);
+            assert_eq!(r.read_object(), Ok(object));
+            assert_eq!(r.read(3), Ok(0b101));
+        }
+        let mut w = aac::BitWriter::default();
+        assert_eq!(w.write_object(31), Err(aac::Error::Unsupported));
+        assert_eq!(w.write_object(96), Err(aac::Error::Unsupported));
+
+        let mut asc = aac::AudioSpecificConfig::lc(48_000, 2);
+        asc.object = audio::Mpeg4Object(31);
+        assert_eq!(asc.to_bytes(), Err(aac::Error::Unsupported));
+    }
+
+    #[test]
+    fn esds() {
+        // cookie of AAC LC 44100 stereo from AudioConverter
+        let cookie = [
+            0x03, 0x80, 0x80, 0x80, 0x22, 0x00, 0x00, 0x00, 0x04, 0x80, 0x80, 0x80, 0x14, 0x40,
+            0x15, 0x00, 0x18, 0x00, 0x00, 0x01, 0xf4, 0x00, 0x00, 0x01, 0xf4, 0x00, 0x05, 0x80,
+            0x80, 0x80, 0x02, 0x12, 0x10, 0x06, 0x80, 0x80, 0x80, 0x01, 0x02,
+        ];
+        let esds = aac::Esds::parse(&cookie).unwrap();
+        assert_eq!(esds.asc, [0x12, 0x10]);
+        assert_eq!(esds.buffer_size, 0x1800);
+        assert_eq!(esds.avg_bitrate, 128_000);
+        let asc = aac::AudioSpecificConfig::with_magic_cookie(&cookie).unwrap();
+        assert_eq!(asc, aac::AudioSpecificConfig::lc(44_100, 2));
+
+        let bytes = esds.to_bytes();
+        assert_eq!(bytes[0], 0x03);
+        assert_eq!(aac::Esds::parse(&bytes).unwrap(), esds);
+
+        let mut boxed = vec![0, 0, 0, 0];
+        boxed.extend_from_slice(&bytes);
+        assert_eq!(aac::Esds::parse(&boxed).unwrap(), esds);
+
+        let esds = aac::Esds::with_asc(&asc).unwrap();
+        assert_eq!(
+            aac::AudioSpecificConfig::with_magic_cookie(&esds.to_bytes()),
+            Ok(asc)
+        );
+        assert_eq!(
+            aac::AudioSpecificConfig::with_magic_cookie(&[0x12, 0x10]),
+            Ok(asc)
+        );
+    }
+
+    #[test]
+    fn adts() {
+        let asc = aac::AudioSpecificConfig::lc(44_100, 2);
+        let header = aac::AdtsHeader::with_asc(&asc, 0x100).unwrap();
+        assert_eq!(
+            header.to_bytes(),
+            [0xff, 0xf1, 0x50, 0x80, 0x20, 0xff, 0xfc]
+        );
+        assert_eq!(aac::AdtsHeader::parse(&header.to_bytes()), Ok(header));
+        assert_eq!(header.to_asc(), asc);
+
+        let data: Vec<u8> = (0..30).collect();
+        let packets = [
+            audio::StreamPacketDesc {
+                start_offset: 0,
+                variable_frames_in_packet: 0,
+                data_byte_size: 10,
+            },
+            audio::StreamPacketDesc {
+                start_offset: 10,
+                variable_frames_in_packet: 0,
+                data_byte_size: 20,
+            },
+        ];
+        let mut stream = Vec::new();
+        aac::add_adts(&asc, &data, &packets, &mut stream).unwrap();
+        assert_eq!(stream.len(), 44);
+
+        let mut payloads = Vec::new();
+        let mut parsed = Vec::new();
+        // incomplete second frame
+        let n = aac::strip_adts(&stream[..40], &mut payloads, &mut parsed).unwrap();
+        assert_eq!(n, 17);
+        let n = aac::strip_adts(&stream[n..], &mut payloads, &mut parsed).unwrap();
+        assert_eq!(n, 27);
+        assert_eq!(payloads, data);
+        assert_eq!(parsed, packets);
+
+        assert_eq!(
+            aac::strip_adts(&[0u8; 8], &mut payloads, &mut parsed),
+            Err(aac::Error::Invalid)
+        );
+
+        let mut header = aac::AdtsHeader::with_asc(&asc, 10).unwrap();
+        header.raw_blocks = 1;
+        let mut multi = header.to_bytes().to_vec();
+        multi.extend_from_slice(&data[..10]);
+        assert_eq!(
+            aac::strip_adts(&multi, &mut payloads, &mut parsed),
+            Err(aac::Error::Unsupported)
+        );
+
+        let huge = [audio::StreamPacketDesc {
+            start_offset: -1,
+            variable_frames_in_packet: 0,
+            data_byte_size: 2,
+        }];
+        assert_eq!(
+            aac::add_adts(&asc, &data, &huge, &mut stream),
+            Err(aac::Error::Truncated)
+        );
+    }
+}
@@ -1052,7 +1052,7 @@ impl ChannelLabel {
 /// These constants are for use in the mChannelBitmap field of an
 /// AudioChannelLayout structure
 #[doc(alias = "AudioChannelBitmap")]
-#[derive(Debug, PartialEq, Eq, Default)]
+#[derive(Debug, PartialEq, Eq, Default, Copy, Clone)]
 #[repr(transparent)]
 pub struct ChannelBitmap(pub u32);
 
@@ -1597,7 +1597,7 @@ impl ChannelLayoutTag {
 
 /// This structure describes a single channel.
 #[doc(alias = "AudioChannelDescription")]
-#[derive(Debug, Default, Copy, Clone)]
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
 #[repr(C)]
 pub struct ChannelDesc {
     /// The AudioChannelLabel that describes the channel.
@@ -1629,7 +1629,7 @@ pub struct FormatListItem {
 }
 
 #[doc(alias = "MPEG4ObjectID")]
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
 #[repr(transparent)]
 pub struct Mpeg4Object(pub c_long);
 
@@ -0,0 +1,468 @@
+use crate::{cat::audio, os};
+
+use super::{Buf, BufList, Format, FormatFlags, StreamBasicDesc};
+
+/// Sample type which can be viewed in LPCM buffers.
+///
+/// # Safety
+///
+/// Any bit pattern of `size_of::<Self>()` bytes must be a valid value.
+pub unsafe trait Sample: Copy + Default + 'static {
+    const IS_FLOAT: bool;
+    const IS_SIGNED: bool;
+
+    const BITS: u32 = std::mem::size_of::<Self>() as u32 * 8;
+
+    /// Returns `true` if samples of the `asbd` can be viewed as `Self`.
+    fn matches(asbd: &StreamBasicDesc) -> bool {
+        let flags = asbd.format_flags;
+        asbd.format == Format::LINEAR_PCM
+            && asbd.bits_per_channel == Self::BITS
+            && flags.contains(FormatFlags::IS_FLOAT) == Self::IS_FLOAT
+            && flags.contains(FormatFlags::IS_SIGNED_INTEGER)
+                == (Self::IS_SIGNED && !Self::IS_FLOAT)
+            && (Self::BITS == 8 || asbd.is_native_endian())
+            && asbd.bytes_per_frame == asbd.interleaved_channels_num() * (Self::BITS / 8)
+    }
+}
+
+unsafe impl Sample for f32 {
+    const IS_FLOAT: bool = true;
+    const IS_SIGNED: bool = true;
+}
+
+unsafe impl Sample for f64 {
+    const IS_FLOAT: bool = true;
+    const IS_SIGNED: bool = true;
+}
+
+unsafe impl Sample for i16 {
+    const IS_FLOAT: bool = false;
+    const IS_SIGNED: bool = true;
+}
+
+unsafe impl Sample for i32 {
+    const IS_FLOAT: bool = false;
+    const IS_SIGNED: bool = true;
+}
+
+unsafe impl Sample for u8 {
+    const IS_FLOAT: bool = false;
+    const IS_SIGNED: bool = false;
+}
+
+unsafe impl Sample for i8 {
+    const IS_FLOAT: bool = false;
+    const IS_SIGNED: bool = true;
+}
+
+/// Owned LPCM audio buffers with `AudioBufferList` header for FFI calls.
+///
+/// Interleaved formats have single buffer, non-interleaved formats have buffer per channel.
+///
+/// ```
+/// use cidre::at::audio;
+///
+/// let asbd = audio::StreamBasicDesc::common_f32(48_000.0, 2, false);
+/// let mut bufs = audio::Bufs::new(&asbd, 512).unwrap();
+/// bufs.buf_mut::<f32>(1).unwrap()[0] = 1.0;
+///
+/// let list = bufs.as_list::<2>().unwrap();
+/// assert_eq!(list.buffers[1].data_bytes_size, 512 * 4);
+/// ```
+pub struct Bufs {
+    asbd: StreamBasicDesc,
+    capacity: u32,
+    frames: u32,
+    /// bytes between buffers in `data`
+    stride: usize,
+    /// u64 for 8 bytes alignment of samples
+    data: Vec<u64>,
+    /// `AudioBufferList` storage: `number_buffers` and padding followed by `Buf`s
+    list: Vec<u64>,
+}
+
+unsafe impl Send for Bufs {}
+unsafe impl Sync for Bufs {}
+
+impl Bufs {
+    /// Allocates zeroed buffers for `capacity` frames of LPCM `asbd`.
+    pub fn new(asbd: &StreamBasicDesc, capacity: u32) -> os::Result<Self> {
+        if asbd.format != Format::LINEAR_PCM
+            || asbd.frames_per_packet != 1
+            || asbd.channels_per_frame == 0
+            || asbd.bytes_per_frame == 0
+            || asbd.bytes_per_packet != asbd.bytes_per_frame
+            || asbd.bits_per_channel > asbd.bytes_per_frame * 8
+        {
+            return Err(audio::err::PARAM);
+        }
+
+        let buf_count = if asbd.is_interleaved() {
+            1
+        } else {
+            asbd.channels_per_frame as usize
+        };
+        let buf_size = capacity as usize * asbd.bytes_per_frame as usize;
+        // align every buffer to 16 bytes
+        let stride = (buf_size + 15) & !15;
+        let data = vec![0u64; stride * buf_count / 8];
+
+        // header: u32 number_buffers padded to 8 bytes, Buf is 16 bytes
+        let mut list = vec![0u64; 1 + buf_count * 2];
+        unsafe { *(list.as_mut_ptr() as *mut u32) = buf_count as u32 };
+
+        let mut res = Self {
+            asbd: *asbd,
+            capacity,
+            frames: capacity,
+            stride,
+            data,
+            list,
+        };
+        res.update_list();
+        Ok(res)
+    }
+
+    #[inline]
+    pub fn asbd(&self) -> &StreamBasicDesc {
+        &self.asbd
+    }
+
+    /// Number of valid frames.
+    #[inline]
+    pub fn frames(&self) -> u32 {
+        self.frames
+    }
+
+    /// Sets number of valid frames. Updates `data_bytes_size` of the list.
+    pub fn set_frames(&mut self, val: u32) -> os::Result {
+        if val > self.capacity {
+            return Err(audio::err::PARAM);
+        }
+        self.frames = val;
+        self.update_list();
+        Ok(())
+    }
+
+    #[inline]
+    pub fn capacity(&self) -> u32 {
+        self.capacity
+    }
+
+    #[inline]
+    pub fn buf_count(&self) -> usize {
+        self.list.len() / 2
+    }
+
+    #[inline]
+    pub fn is_interleaved(&self) -> bool {
+        self.asbd.is_interleaved()
+    }
+
+    /// Number of valid bytes in each buffer.
+    #[inline]
+    pub fn buf_bytes_size(&self) -> usize {
+        self.frames as usize * self.asbd.bytes_per_frame as usize
+    }
+
+    fn data_ptr(&self) -> *const u8 {
+        self.data.as_ptr() as *const u8
+    }
+
+    fn data_ptr_mut(&mut self) -> *mut u8 {
+        self.data.as_mut_ptr() as *mut u8
+    }
+
+    fn bufs_raw_mut(&mut self) -> &mut [Buf] {
+        let n = self.buf_count();
+        unsafe { std::slice::from_raw_parts_mut(self.list.as_mut_ptr().add(1) as *mut Buf, n) }
+    }
+
+    fn update_list(&mut self) {
+        let channels = self.asbd.interleaved_channels_num();
+        let size = self.buf_bytes_size() as u32;
+        let stride = self.stride;
+        let data = self.data_ptr_mut();
+        for (i, buf) in self.bufs_raw_mut().iter_mut().enumerate() {
+            buf.number_channels = channels;
+            buf.data_bytes_size = size;
+            buf.data = unsafe { data.add(i * stride) };
+        }
+    }
+
+    /// Valid bytes of buffer `i`.
+    pub fn bytes(&self, i: usize) -> Option<&[u8]> {
+        if i >= self.buf_count() {
+            return None;
+        }
+        let ptr = unsafe { self.data_ptr().add(i * self.stride) };
+        Some(unsafe { std::slice::from_raw_parts(ptr, self.buf_bytes_size()) })
+    }
+
+    pub fn bytes_mut(&mut self, i: usize) -> Option<&mut [u8]> {
+        if i >= self.buf_count() {
+            return None;
+        }
+        let ptr = unsafe { self.data_ptr_mut().add(i * self.stride) };
+        Some(unsafe { std::slice::from_raw_parts_mut(ptr, self.buf_bytes_size()) })
+    }
+
+    /// Typed samples of buffer `i`. `None` if `T` doesn't match the format.
+    ///
+    /// For interleaved formats samples of all channels are returned.
+    pub fn buf<T: Sample>(&self, i: usize) -> Option<&[T]> {
+        if !T::matches(&self.asbd) {
+            return None;
+        }
+        let bytes = self.bytes(i)?;
+        let len = bytes.len() / std::mem::size_of::<T>();
+        Some(unsafe { std::slice::from_raw_parts(bytes.as_ptr() as *const T, len) })
+    }
+
+    pub fn buf_mut<T: Sample>(&mut self, i: usize) -> Option<&mut [T]> {
+        if !T::matches(&self.asbd) {
+            return None;
+        }
+        let bytes = self.bytes_mut(i)?;
+        let len = bytes.len() / std::mem::size_of::<T>();
+        Some(unsafe { std::slice::from_raw_parts_mut(bytes.as_mut_ptr() as *mut T, len) })
+    }
+
+    /// All buffers at once, handy for writing planar data.
+    pub fn bufs_mut<T: Sample>(&mut self) -> Option<Vec<&mut [T]>> {
+        if !T::matches(&self.asbd) {
+            return None;
+        }
+        let len = self.buf_bytes_size() / std::mem::size_of::<T>();
+        let stride = self.stride;
+        let data = self.data_ptr_mut();
+        Some(
+            (0..self.buf_count())
+                .map(|i| unsafe {
+                    std::slice::from_raw_parts_mut(data.add(i * stride) as *mut T, len)
+                })
+                .collect(),
+        )
+    }
+
+    /// Samples of interleaved format.
+    #[inline]
+    pub fn interleaved<T: Sample>(&self) -> Option<&[T]> {
+        if self.is_interleaved() {
+            self.buf(0)
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    pub fn interleaved_mut<T: Sample>(&mut self) -> Option<&mut [T]> {
+        if self.is_interleaved() {
+            self.buf_mut(0)
+        } else {
+            None
+        }
+    }
+
+    /// Samples of channel `ch` of non-interleaved format.
+    #[inline]
+    pub fn planar<T: Sample>(&self, ch: usize) -> Option<&[T]> {
+        if self.is_interleaved() && self.asbd.channels_per_frame > 1 {
+            None
+        } else {
+            self.buf(ch)
+        }
+    }
+
+    #[inline]
+    pub fn planar_mut<T: Sample>(&mut self, ch: usize) -> Option<&mut [T]> {
+        if self.is_interleaved() && self.asbd.channels_per_frame > 1 {
+            None
+        } else {
+            self.buf_mut(ch)
+        }
+    }
+
+    /// Zeroes all samples.
+    pub fn clear(&mut self) {
+        self.data.fill(0);
+    }
+
+    /// `AudioBufferList` for FFI calls. `None` if `N` is not equal to [`Self::buf_count`].
+    pub fn as_list<const N: usize>(&self) -> Option<&BufList<N>> {
+        if N == self.buf_count() {
+            Some(unsafe { &*(self.list.as_ptr() as *const BufList<N>) })
+        } else {
+            None
+        }
+    }
+
+    /// Mutable `AudioBufferList` for FFI calls which fill buffers.
+    ///
+    /// Callee may shrink `data_bytes_size`, call [`Self::sync_frames`] afterwards.
+    pub fn as_list_mut<const N: usize>(&mut self) -> Option<&mut BufList<N>> {
+        if N == self.buf_count() {
+            self.update_list();
+            Some(unsafe { &mut *(self.list.as_mut_ptr() as *mut BufList<N>) })
+        } else {
+            None
+        }
+    }
+
+    /// Raw `AudioBufferList` when number of buffers is known at runtime only.
+    ///
+    /// Points to [`Self::buf_count`] buffers, call [`Self::sync_frames`] after callee fills them.
+    pub fn as_list_ptr_mut(&mut self) -> *mut BufList<1> {
+        self.update_list();
+        self.list.as_mut_ptr() as *mut BufList<1>
+    }
+
+    /// Updates number of valid frames from `data_bytes_size` of the first buffer
+    /// and restores list pointers.
+    pub fn sync_frames(&mut self) -> u32 {
+        let bytes = self.bufs_raw_mut()[0].data_bytes_size;
+        let frames = bytes / self.asbd.bytes_per_frame;
+        self.frames = frames.min(self.capacity);
+        self.update_list();
+        self.frames
+    }
+
+    /// Copies data from foreign list (e.g. `io_data` of render callback).
+    ///
+    /// List must match the format and fit into capacity.
+    pub fn copy_from_list<const N: usize>(&mut self, list: &BufList<N>) -> os::Result {
+        let bufs = list.as_slice();
+        if bufs.len() != self.buf_count() {
+            return Err(audio::err::PARAM);
+        }
+        let size = bufs[0].data_bytes_size;
+        if size % self.asbd.bytes_per_frame != 0
+            || size / self.asbd.bytes_per_frame > self.capacity
+            || bufs
+                .iter()
+                .any(|b| b.data_bytes_size != size || b.data.is_null())
+        {
+            return Err(audio::err::PARAM);
+        }
+        self.set_frames(size / self.asbd.bytes_per_frame)?;
+        for (i, b) in bufs.iter().enumerate() {
+            let src = unsafe { std::slice::from_raw_parts(b.data, size as usize) };
+            self.bytes_mut(i).unwrap().copy_from_slice(src);
+        }
+        Ok(())
+    }
+
+    /// Copies valid frames into foreign list (e.g. `io_data` of render callback).
+    ///
+    /// Each list buffer must be at least [`Self::buf_bytes_size`] bytes.
+    pub fn copy_to_list<const N: usize>(&self, list: &mut BufList<N>) -> os::Result {
+        let size = self.buf_bytes_size();
+        if N != self.buf_count() {
+            return Err(audio::err::PARAM);
+        }
+        for (i, b) in list.as_mut_slice().iter_mut().enumerate() {
+            if b.data.is_null() || (b.data_bytes_size as usize) < size {
+                return Err(audio::err::PARAM);
+            }
+            let dst = unsafe { std::slice::from_raw_parts_mut(b.data, size) };
+            dst.copy_from_slice(self.bytes(i).unwrap());
+            b.data_bytes_size = size as u32;
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for Bufs {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Bufs")
+            .field("asbd", &self.asbd)
+            .field("frames", &self.frames)
+            .field("capacity", &self.capacity)
+            .field("buf_count", &self.buf_count())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cat::audio;
+
+    #[test]
+    fn planar() {
+        let asbd = audio::StreamBasicDesc::common_f32(48_000.0, 2, false);
+        let mut bufs = audio::Bufs::new(&asbd, 100).unwrap();
+        assert_eq!(bufs.buf_count(), 2);
+        assert!(bufs.interleaved::<f32>().is_none());
+        assert!(bufs.planar::<i16>(0).is_none());
+
+        let mut planes = bufs.bufs_mut::<f32>().unwrap();
+        planes[0].fill(1.0);
+        planes[1].fill(-1.0);
+
+        assert_eq!(bufs.planar::<f32>(1).unwrap()[99], -1.0);
+        assert!(bufs.planar::<f32>(2).is_none());
+
+        bufs.set_frames(10).unwrap();
+        assert!(bufs.set_frames(101).is_err());
+        assert_eq!(bufs.planar::<f32>(0).unwrap().len(), 10);
+
+        assert!(bufs.as_list::<1>().is_none());
+        let list = bufs.as_list_mut::<2>().unwrap();
+        assert_eq!(list.number_buffers, 2);
+        assert_eq!(list.buffers[0].data_bytes_size, 40);
+        assert_eq!(list.buffers[1].number_channels, 1);
+        assert_eq!(unsafe { *(list.buffers[1].data as *const f32) }, -1.0);
+
+        list.buffers[0].data_bytes_size = 16;
+        list.buffers[1].data_bytes_size = 16;
+        assert_eq!(bufs.sync_frames(), 4);
+
+        let mut other = audio::Bufs::new(&asbd, 4).unwrap();
+        other.copy_from_list(bufs.as_list::<2>().unwrap()).unwrap();
+        assert_eq!(other.planar::<f32>(0).unwrap(), &[1.0; 4]);
+
+        let mut small = audio::Bufs::new(&asbd, 3).unwrap();
+        assert!(small.copy_from_list(bufs.as_list::<2>().unwrap()).is_err());
+    }
+
+    #[test]
+    fn interleaved() {
+        let mut asbd = audio::StreamBasicDesc::common_f32(44_100.0, 2, true);
+        asbd.format_flags = audio::FormatFlags::IS_SIGNED_INTEGER | audio::FormatFlags::IS_PACKED;
+        asbd.bits_per_channel = 16;
+        asbd.bytes_per_frame = 4;
+        asbd.bytes_per_packet = 4;
+
+        let mut bufs = audio::Bufs::new(&asbd, 8).unwrap();
+        assert_eq!(bufs.buf_count(), 1);
+        assert!(bufs.interleaved::<f32>().is_none());
+        let samples = bufs.interleaved_mut::<i16>().unwrap();
+        assert_eq!(samples.len(), 16);
+        samples[1] = i16::MIN;
+        assert!(bufs.planar::<i16>(0).is_none());
+
+        let mut dst = audio::Bufs::new(&asbd, 8).unwrap();
+        bufs.copy_to_list(dst.as_list_mut::<1>().unwrap()).unwrap();
+        assert_eq!(dst.interleaved::<i16>().unwrap()[1], i16::MIN);
+
+        // signedness has to match
+        asbd.bits_per_channel = 8;
+        asbd.bytes_per_frame = 2;
+        asbd.bytes_per_packet = 2;
+        let mut bufs = audio::Bufs::new(&asbd, 8).unwrap();
+        assert!(bufs.interleaved_mut::<i8>().is_some());
+        assert!(bufs.interleaved_mut::<u8>().is_none());
+        asbd.format_flags = audio::FormatFlags::IS_PACKED;
+        let mut bufs = audio::Bufs::new(&asbd, 8).unwrap();
+        assert!(bufs.interleaved_mut::<u8>().is_some());
+        assert!(bufs.interleaved_mut::<i8>().is_none());
+        let mut asbd = audio::StreamBasicDesc::common_f32(44_100.0, 2, true);
+        asbd.format_flags = asbd.format_flags | audio::FormatFlags::IS_SIGNED_INTEGER;
+        let bufs = audio::Bufs::new(&asbd, 8).unwrap();
+        assert!(bufs.interleaved::<f32>().is_none());
+
+        asbd.format = audio::Format::MPEG4_AAC;
+        assert!(audio::Bufs::new(&asbd, 8).is_err());
+    }
+}
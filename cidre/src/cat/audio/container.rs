@@ -0,0 +1,148 @@
+//! Pure Rust readers and writers of audio container files.
+//!
+//! Headers are mapped to [`StreamBasicDesc`], channel layouts and magic cookies,
+//! so the data can be passed to `at::AudioConverter` or `at::AudioFile` as is.
+
+use std::io::{self, Read};
+
+use super::{ChannelBitmap, ChannelDesc, ChannelLayout, ChannelLayoutTag};
+
+#[cfg(doc)]
+use super::StreamBasicDesc;
+
+pub mod caf;
+pub mod ogg;
+pub mod wav;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    /// File doesn't start with expected header.
+    InvalidHeader,
+    /// Chunk is malformed.
+    InvalidChunk([u8; 4]),
+    /// Required chunk is missing.
+    MissingChunk([u8; 4]),
+    /// Format can't be stored in the container.
+    Unsupported,
+    /// Header can't be changed after data was written.
+    HeaderWritten,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => e.fmt(f),
+            Self::InvalidHeader => f.write_str("invalid file header"),
+            Self::InvalidChunk(ty) => write!(f, "invalid '{}' chunk", ty.escape_ascii()),
+            Self::MissingChunk(ty) => write!(f, "missing '{}' chunk", ty.escape_ascii()),
+            Self::Unsupported => f.write_str("format is not supported by the container"),
+            Self::HeaderWritten => f.write_str("header is already written"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+pub type Result<T = ()> = std::result::Result<T, Error>;
+
+/// Owned variable length [`ChannelLayout`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChannelLayoutBuf {
+    pub tag: ChannelLayoutTag,
+    pub bitmap: ChannelBitmap,
+    pub descs: Vec<ChannelDesc>,
+}
+
+impl ChannelLayoutBuf {
+    pub fn with_tag(tag: ChannelLayoutTag) -> Self {
+        Self {
+            tag,
+            bitmap: ChannelBitmap(0),
+            descs: Vec::new(),
+        }
+    }
+
+    pub fn with_bitmap(bitmap: ChannelBitmap) -> Self {
+        Self {
+            tag: ChannelLayoutTag::USE_CHANNEL_BITMAP,
+            bitmap,
+            descs: Vec::new(),
+        }
+    }
+
+    pub fn with_descs(descs: Vec<ChannelDesc>) -> Self {
+        Self {
+            tag: ChannelLayoutTag::USE_CHANNEL_DESCRIPTIONS,
+            bitmap: ChannelBitmap(0),
+            descs,
+        }
+    }
+
+    pub fn with_layout<const N: usize>(layout: &ChannelLayout<N>) -> Self {
+        let n = (layout.number_channel_descriptions as usize).min(N);
+        Self {
+            tag: layout.channel_layout_tag,
+            bitmap: layout.channel_bitmap,
+            descs: layout.channel_descriptions[..n].to_vec(),
+        }
+    }
+
+    pub fn channels_num(&self) -> u32 {
+        match self.tag {
+            ChannelLayoutTag::USE_CHANNEL_DESCRIPTIONS => self.descs.len() as u32,
+            ChannelLayoutTag::USE_CHANNEL_BITMAP => self.bitmap.0.count_ones(),
+            tag => tag.number_of_channels(),
+        }
+    }
+
+    /// `None` if there are more than `N` channel descriptions.
+    pub fn to_layout<const N: usize>(&self) -> Option<ChannelLayout<N>> {
+        if self.descs.len() > N {
+            return None;
+        }
+        let mut channel_descriptions = [ChannelDesc::default(); N];
+        channel_descriptions[..self.descs.len()].copy_from_slice(&self.descs);
+        Some(ChannelLayout {
+            channel_layout_tag: self.tag,
+            channel_bitmap: self.bitmap,
+            number_channel_descriptions: self.descs.len() as u32,
+            channel_descriptions,
+        })
+    }
+}
+
+#[inline]
+fn read_array<const N: usize>(r: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut res = [0u8; N];
+    r.read_exact(&mut res)?;
+    Ok(res)
+}
+
+fn read_vec(r: &mut impl Read, len: u64) -> io::Result<Vec<u8>> {
+    let mut res = Vec::new();
+    r.take(len).read_to_end(&mut res)?;
+    if (res.len() as u64) < len {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(res)
+}
+
+/// Fixed size field of the chunk body.
+#[inline]
+fn field<const N: usize>(body: &[u8], at: usize) -> Option<[u8; N]> {
+    body.get(at..at + N)?.try_into().ok()
+}
@@ -0,0 +1,731 @@
+//! Core Audio Format.
+//!
+//! Supports `desc`, `chan`, `kuki`, `pakt`, `info` and `data` chunks. Other chunks are skipped.
+
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+use crate::cat::audio::{
+    ChannelBitmap, ChannelDesc, ChannelFlags, ChannelLabel, ChannelLayoutTag, Format, FormatFlags,
+    StreamBasicDesc, StreamPacketDesc,
+};
+
+use super::{field, read_array, read_vec, ChannelLayoutBuf, Error, Result};
+
+const DESC: [u8; 4] = *b"desc";
+const CHAN: [u8; 4] = *b"chan";
+const KUKI: [u8; 4] = *b"kuki";
+const PAKT: [u8; 4] = *b"pakt";
+const INFO: [u8; 4] = *b"info";
+const DATA: [u8; 4] = *b"data";
+
+/// `kCAFLinearPCMFormatFlagIsFloat`
+const LPCM_IS_FLOAT: u32 = 1 << 0;
+/// `kCAFLinearPCMFormatFlagIsLittleEndian`
+const LPCM_IS_LITTLE_ENDIAN: u32 = 1 << 1;
+
+/// Packet table of the file with variable packet sizes or durations.
+#[doc(alias = "CAFPacketTableHeader")]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PacketTable {
+    pub valid_frames: i64,
+    pub priming_frames: i32,
+    pub remainder_frames: i32,
+    /// Offsets are relative to the start of audio data.
+    pub packets: Vec<StreamPacketDesc>,
+}
+
+fn asbd_from_desc(body: &[u8]) -> Result<StreamBasicDesc> {
+    let err = || Error::InvalidChunk(DESC);
+    let u32_at = |at| field(body, at).map(u32::from_be_bytes).ok_or_else(err);
+    let sample_rate = field(body, 0).map(f64::from_be_bytes).ok_or_else(err)?;
+    let format = Format(u32_at(8)?);
+    let flags = u32_at(12)?;
+    let bytes_per_packet = u32_at(16)?;
+    let frames_per_packet = u32_at(20)?;
+    let channels_per_frame = u32_at(24)?;
+    let bits_per_channel = u32_at(28)?;
+
+    let (format_flags, bytes_per_frame) = if format == Format::LINEAR_PCM {
+        let mut ff = FormatFlags::IS_PACKED;
+        if flags & LPCM_IS_FLOAT != 0 {
+            ff |= FormatFlags::IS_FLOAT;
+        } else {
+            ff |= FormatFlags::IS_SIGNED_INTEGER;
+        }
+        if flags & LPCM_IS_LITTLE_ENDIAN == 0 {
+            ff |= FormatFlags::IS_BIG_ENDIAN;
+        }
+        (ff, bytes_per_packet)
+    } else {
+        (FormatFlags(flags), 0)
+    };
+    Ok(StreamBasicDesc {
+        sample_rate,
+        format,
+        format_flags,
+        bytes_per_packet,
+        frames_per_packet,
+        bytes_per_frame,
+        channels_per_frame,
+        bits_per_channel,
+        reserved: 0,
+    })
+}
+
+fn desc_from_asbd(asbd: &StreamBasicDesc) -> Result<[u8; 32]> {
+    let flags = if asbd.format == Format::LINEAR_PCM {
+        let ff = asbd.format_flags;
+        let bytes = asbd.bits_per_channel.div_ceil(8);
+        if !asbd.is_interleaved()
+            || asbd.frames_per_packet != 1
+            || asbd.bytes_per_frame != bytes * asbd.channels_per_frame
+            || (!ff.contains(FormatFlags::IS_FLOAT) && !ff.contains(FormatFlags::IS_SIGNED_INTEGER))
+        {
+            return Err(Error::Unsupported);
+        }
+        let mut flags = 0;
+        if ff.contains(FormatFlags::IS_FLOAT) {
+            flags |= LPCM_IS_FLOAT;
+        }
+        if !ff.contains(FormatFlags::IS_BIG_ENDIAN) {
+            flags |= LPCM_IS_LITTLE_ENDIAN;
+        }
+        flags
+    } else {
+        asbd.format_flags.0
+    };
+    let mut res = [0u8; 32];
+    res[0..8].copy_from_slice(&asbd.sample_rate.to_be_bytes());
+    res[8..12].copy_from_slice(&asbd.format.0.to_be_bytes());
+    res[12..16].copy_from_slice(&flags.to_be_bytes());
+    res[16..20].copy_from_slice(&asbd.bytes_per_packet.to_be_bytes());
+    res[20..24].copy_from_slice(&asbd.frames_per_packet.to_be_bytes());
+    res[24..28].copy_from_slice(&asbd.channels_per_frame.to_be_bytes());
+    res[28..32].copy_from_slice(&asbd.bits_per_channel.to_be_bytes());
+    Ok(res)
+}
+
+fn parse_chan(body: &[u8]) -> Result<ChannelLayoutBuf> {
+    let err = || Error::InvalidChunk(CHAN);
+    let u32_at = |at| field(body, at).map(u32::from_be_bytes).ok_or_else(err);
+    let n = u32_at(8)? as usize;
+    if body.len() < 12 + n * 20 {
+        return Err(err());
+    }
+    let mut descs = Vec::with_capacity(n);
+    for i in 0..n {
+        let at = 12 + i * 20;
+        let f32_at = |at| field(body, at).map(f32::from_be_bytes).ok_or_else(err);
+        descs.push(ChannelDesc {
+            channel_label: ChannelLabel(u32_at(at)?),
+            channel_flags: ChannelFlags(u32_at(at + 4)?),
+            coordinates: [f32_at(at + 8)?, f32_at(at + 12)?, f32_at(at + 16)?],
+        });
+    }
+    Ok(ChannelLayoutBuf {
+        tag: ChannelLayoutTag(u32_at(0)?),
+        bitmap: ChannelBitmap(u32_at(4)?),
+        descs,
+    })
+}
+
+fn chan_body(layout: &ChannelLayoutBuf) -> Vec<u8> {
+    let mut res = Vec::with_capacity(12 + layout.descs.len() * 20);
+    res.extend_from_slice(&layout.tag.0.to_be_bytes());
+    res.extend_from_slice(&layout.bitmap.0.to_be_bytes());
+    res.extend_from_slice(&(layout.descs.len() as u32).to_be_bytes());
+    for desc in &layout.descs {
+        res.extend_from_slice(&desc.channel_label.0.to_be_bytes());
+        res.extend_from_slice(&desc.channel_flags.0.to_be_bytes());
+        for c in desc.coordinates {
+            res.extend_from_slice(&c.to_be_bytes());
+        }
+    }
+    res
+}
+
+fn parse_info(body: &[u8]) -> Result<Vec<(String, String)>> {
+    let err = || Error::InvalidChunk(INFO);
+    let n = field(body, 0).map(u32::from_be_bytes).ok_or_else(err)?;
+    let mut strings = body[4..]
+        .split(|b| *b == 0)
+        .map(|s| String::from_utf8_lossy(s).into_owned());
+    let mut res = Vec::with_capacity((n as usize).min(64));
+    for _ in 0..n {
+        let (Some(key), Some(val)) = (strings.next(), strings.next()) else {
+            return Err(err());
+        };
+        res.push((key, val));
+    }
+    Ok(res)
+}
+
+/// Reads BER encoded integer of the packet table.
+fn read_var(body: &[u8], at: &mut usize) -> Option<u64> {
+    let mut res = 0u64;
+    loop {
+        let b = *body.get(*at)?;
+        *at += 1;
+        res = (res << 7) | (b & 0x7f) as u64;
+        if b & 0x80 == 0 {
+            return Some(res);
+        }
+    }
+}
+
+fn write_var(buf: &mut Vec<u8>, val: u64) {
+    let mut bytes = [0u8; 10];
+    let mut i = bytes.len();
+    let mut val = val;
+    loop {
+        i -= 1;
+        bytes[i] = (val & 0x7f) as u8 | if i == bytes.len() - 1 { 0 } else { 0x80 };
+        val >>= 7;
+        if val == 0 {
+            break;
+        }
+    }
+    buf.extend_from_slice(&bytes[i..]);
+}
+
+fn parse_pakt(body: &[u8], asbd: &StreamBasicDesc) -> Result<PacketTable> {
+    let err = || Error::InvalidChunk(PAKT);
+    let count = field(body, 0).map(i64::from_be_bytes).ok_or_else(err)?;
+    let valid_frames = field(body, 8).map(i64::from_be_bytes).ok_or_else(err)?;
+    let priming_frames = field(body, 16).map(i32::from_be_bytes).ok_or_else(err)?;
+    let remainder_frames = field(body, 20).map(i32::from_be_bytes).ok_or_else(err)?;
+    if count < 0 {
+        return Err(err());
+    }
+    let mut packets = Vec::with_capacity((count as usize).min(body.len()));
+    let mut at = 24;
+    let mut offset = 0i64;
+    for _ in 0..count {
+        let size = if asbd.bytes_per_packet == 0 {
+            read_var(body, &mut at).ok_or_else(err)? as u32
+        } else {
+            asbd.bytes_per_packet
+        };
+        let frames = if asbd.frames_per_packet == 0 {
+            read_var(body, &mut at).ok_or_else(err)? as u32
+        } else {
+            0
+        };
+        packets.push(StreamPacketDesc {
+            start_offset: offset,
+            variable_frames_in_packet: frames,
+            data_byte_size: size,
+        });
+        offset += size as i64;
+    }
+    Ok(PacketTable {
+        valid_frames,
+        priming_frames,
+        remainder_frames,
+        packets,
+    })
+}
+
+/// CAF file reader.
+pub struct Reader<R> {
+    inner: R,
+    asbd: StreamBasicDesc,
+    layout: Option<ChannelLayoutBuf>,
+    cookie: Option<Vec<u8>>,
+    info: Vec<(String, String)>,
+    packets: Option<PacketTable>,
+    data_offset: u64,
+    data_len: u64,
+    pos: u64,
+}
+
+impl<R: Read + Seek> Reader<R> {
+    pub fn new(mut inner: R) -> Result<Self> {
+        let len = inner.seek(SeekFrom::End(0))?;
+        inner.seek(SeekFrom::Start(0))?;
+        let header: [u8; 8] = read_array(&mut inner)?;
+        if header[..4] != *b"caff" || header[4..6] != [0, 1] {
+            return Err(Error::InvalidHeader);
+        }
+
+        let mut asbd = None;
+        let mut layout = None;
+        let mut cookie = None;
+        let mut info = Vec::new();
+        let mut pakt = None;
+        let mut data = None;
+        let mut pos = 8u64;
+        while pos + 12 <= len {
+            let ty: [u8; 4] = read_array(&mut inner)?;
+            let size = i64::from_be_bytes(read_array(&mut inner)?);
+            pos += 12;
+            if ty == DATA {
+                // -1 is for the last chunk with unknown size
+                let size = if size == -1 { len - pos } else { size as u64 };
+                if size < 4 || pos.checked_add(size).filter(|&end| end <= len).is_none() {
+                    return Err(Error::InvalidChunk(DATA));
+                }
+                data = Some((pos + 4, size - 4));
+                pos += size;
+                inner.seek(SeekFrom::Start(pos))?;
+                continue;
+            }
+            if size < 0
+                || pos
+                    .checked_add(size as u64)
+                    .filter(|&end| end <= len)
+                    .is_none()
+            {
+                return Err(Error::InvalidChunk(ty));
+            }
+            let size = size as u64;
+            match ty {
+                DESC => asbd = Some(asbd_from_desc(&read_vec(&mut inner, size)?)?),
+                CHAN => layout = Some(parse_chan(&read_vec(&mut inner, size)?)?),
+                KUKI => cookie = Some(read_vec(&mut inner, size)?),
+                INFO => info = parse_info(&read_vec(&mut inner, size)?)?,
+                PAKT => pakt = Some(read_vec(&mut inner, size)?),
+                _ => {
+                    inner.seek(SeekFrom::Current(size as i64))?;
+                }
+            }
+            pos += size;
+        }
+
+        let asbd = asbd.ok_or(Error::MissingChunk(DESC))?;
+        let (data_offset, data_len) = data.ok_or(Error::MissingChunk(DATA))?;
+        let packets = match pakt {
+            Some(body) => Some(parse_pakt(&body, &asbd)?),
+            None if asbd.bytes_per_packet == 0 || asbd.frames_per_packet == 0 => {
+                return Err(Error::MissingChunk(PAKT))
+            }
+            None => None,
+        };
+        Ok(Self {
+            inner,
+            asbd,
+            layout,
+            cookie,
+            info,
+            packets,
+            data_offset,
+            data_len,
+            pos: 0,
+        })
+    }
+
+    #[inline]
+    pub fn asbd(&self) -> &StreamBasicDesc {
+        &self.asbd
+    }
+
+    #[inline]
+    pub fn channel_layout(&self) -> Option<&ChannelLayoutBuf> {
+        self.layout.as_ref()
+    }
+
+    #[inline]
+    pub fn magic_cookie(&self) -> Option<&[u8]> {
+        self.cookie.as_deref()
+    }
+
+    #[inline]
+    pub fn info(&self) -> &[(String, String)] {
+        &self.info
+    }
+
+    #[inline]
+    pub fn packet_table(&self) -> Option<&PacketTable> {
+        self.packets.as_ref()
+    }
+
+    /// Size of audio data in bytes.
+    #[inline]
+    pub fn data_len(&self) -> u64 {
+        self.data_len
+    }
+
+    pub fn packet_count(&self) -> u64 {
+        match &self.packets {
+            Some(table) => table.packets.len() as u64,
+            None => self.data_len / self.asbd.bytes_per_packet as u64,
+        }
+    }
+
+    /// Number of valid frames, without priming and remainder frames.
+    pub fn frames(&self) -> u64 {
+        match &self.packets {
+            Some(table) => table.valid_frames.max(0) as u64,
+            None => self.packet_count() * self.asbd.frames_per_packet as u64,
+        }
+    }
+
+    pub fn packet_desc(&self, index: u64) -> Option<StreamPacketDesc> {
+        match &self.packets {
+            Some(table) => table.packets.get(index as usize).copied(),
+            None if index < self.packet_count() => Some(StreamPacketDesc {
+                start_offset: (index * self.asbd.bytes_per_packet as u64) as i64,
+                variable_frames_in_packet: 0,
+                data_byte_size: self.asbd.bytes_per_packet,
+            }),
+            None => None,
+        }
+    }
+
+    /// Reads packet at `index` into `buf`, `None` if there is no such packet.
+    pub fn read_packet(
+        &mut self,
+        index: u64,
+        buf: &mut Vec<u8>,
+    ) -> Result<Option<StreamPacketDesc>> {
+        let Some(desc) = self.packet_desc(index) else {
+            return Ok(None);
+        };
+        if desc.start_offset as u64 + desc.data_byte_size as u64 > self.data_len {
+            return Err(Error::InvalidChunk(PAKT));
+        }
+        self.inner
+            .seek(SeekFrom::Start(self.data_offset + desc.start_offset as u64))?;
+        buf.resize(desc.data_byte_size as usize, 0);
+        self.inner.read_exact(buf)?;
+        Ok(Some(desc))
+    }
+
+    /// Reads audio data sequentially.
+    pub fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let n = (buf.len() as u64).min(self.data_len - self.pos) as usize;
+        if n == 0 {
+            return Ok(0);
+        }
+        self.inner
+            .seek(SeekFrom::Start(self.data_offset + self.pos))?;
+        self.inner.read_exact(&mut buf[..n])?;
+        self.pos += n as u64;
+        Ok(n)
+    }
+
+    /// Moves position of [`Self::read`] to packet containing `frame`.
+    /// Works for constant bit rate formats only.
+    pub fn seek_frame(&mut self, frame: u64) -> Result {
+        if self.packets.is_some() {
+            return Err(Error::Unsupported);
+        }
+        let packet = frame / self.asbd.frames_per_packet as u64;
+        self.pos = (packet * self.asbd.bytes_per_packet as u64).min(self.data_len);
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+/// CAF file writer.
+///
+/// Data chunk is written with unknown size, so the file is readable
+/// even if [`Writer::finish`] is never called.
+pub struct Writer<W: Write + Seek> {
+    inner: W,
+    asbd: StreamBasicDesc,
+    layout: Option<ChannelLayoutBuf>,
+    cookie: Option<Vec<u8>>,
+    info: Vec<(String, String)>,
+    /// Offset of the data chunk size field.
+    data_size_offset: Option<u64>,
+    data_len: u64,
+    /// Packet sizes and frames for variable bit rate formats.
+    packets: Vec<(u32, u32)>,
+    priming_frames: i32,
+    remainder_frames: i32,
+}
+
+impl<W: Write + Seek> Writer<W> {
+    pub fn new(inner: W, asbd: &StreamBasicDesc) -> Result<Self> {
+        // validate early
+        desc_from_asbd(asbd)?;
+        Ok(Self {
+            inner,
+            asbd: *asbd,
+            layout: None,
+            cookie: None,
+            info: Vec::new(),
+            data_size_offset: None,
+            data_len: 0,
+            packets: Vec::new(),
+            priming_frames: 0,
+            remainder_frames: 0,
+        })
+    }
+
+    #[inline]
+    pub fn asbd(&self) -> &StreamBasicDesc {
+        &self.asbd
+    }
+
+    #[inline]
+    fn is_vbr(&self) -> bool {
+        self.asbd.bytes_per_packet == 0 || self.asbd.frames_per_packet == 0
+    }
+
+    #[inline]
+    fn check_header(&self) -> Result {
+        if self.data_size_offset.is_some() {
+            return Err(Error::HeaderWritten);
+        }
+        Ok(())
+    }
+
+    pub fn set_channel_layout(&mut self, val: ChannelLayoutBuf) -> Result {
+        self.check_header()?;
+        self.layout = Some(val);
+        Ok(())
+    }
+
+    pub fn set_magic_cookie(&mut self, val: &[u8]) -> Result {
+        self.check_header()?;
+        self.cookie = Some(val.to_vec());
+        Ok(())
+    }
+
+    /// Adds `info` entry, like `title` or `encoding application`.
+    pub fn add_info(&mut self, key: &str, val: &str) -> Result {
+        self.check_header()?;
+        self.info.push((key.to_string(), val.to_string()));
+        Ok(())
+    }
+
+    /// Sets priming and remainder frames of the packet table.
+    #[inline]
+    pub fn set_priming(&mut self, priming_frames: i32, remainder_frames: i32) {
+        self.priming_frames = priming_frames;
+        self.remainder_frames = remainder_frames;
+    }
+
+    fn write_chunk(&mut self, ty: [u8; 4], body: &[u8]) -> io::Result<()> {
+        self.inner.write_all(&ty)?;
+        self.inner.write_all(&(body.len() as i64).to_be_bytes())?;
+        self.inner.write_all(body)
+    }
+
+    fn write_header(&mut self) -> Result {
+        if self.data_size_offset.is_some() {
+            return Ok(());
+        }
+        self.inner.write_all(b"caff\x00\x01\x00\x00")?;
+        self.write_chunk(DESC, &desc_from_asbd(&self.asbd)?)?;
+        if let Some(layout) = self.layout.take() {
+            self.write_chunk(CHAN, &chan_body(&layout))?;
+            self.layout = Some(layout);
+        }
+        if let Some(cookie) = self.cookie.take() {
+            self.write_chunk(KUKI, &cookie)?;
+            self.cookie = Some(cookie);
+        }
+        if !self.info.is_empty() {
+            let mut body = (self.info.len() as u32).to_be_bytes().to_vec();
+            for (key, val) in &self.info {
+                body.extend_from_slice(key.as_bytes());
+                body.push(0);
+                body.extend_from_slice(val.as_bytes());
+                body.push(0);
+            }
+            self.write_chunk(INFO, &body)?;
+        }
+        self.inner.write_all(&DATA)?;
+        self.data_size_offset = Some(self.inner.stream_position()?);
+        // unknown size and zero edit count
+        self.inner.write_all(&(-1i64).to_be_bytes())?;
+        self.inner.write_all(&0u32.to_be_bytes())?;
+        Ok(())
+    }
+
+    /// Writes audio data of constant bit rate format.
+    pub fn write(&mut self, data: &[u8]) -> Result {
+        if self.is_vbr() {
+            return Err(Error::Unsupported);
+        }
+        self.write_header()?;
+        self.inner.write_all(data)?;
+        self.data_len += data.len() as u64;
+        Ok(())
+    }
+
+    /// Writes a packet of variable bit rate format. `frames` is ignored
+    /// if format has constant frames per packet.
+    pub fn write_packet(&mut self, data: &[u8], frames: u32) -> Result {
+        if !self.is_vbr() {
+            return self.write(data);
+        }
+        self.write_header()?;
+        self.inner.write_all(data)?;
+        self.data_len += data.len() as u64;
+        self.packets.push((data.len() as u32, frames));
+        Ok(())
+    }
+
+    /// Writes data size and packet table.
+    pub fn finish(mut self) -> Result<W> {
+        self.write_header()?;
+        let end = self.inner.stream_position()?;
+        self.inner
+            .seek(SeekFrom::Start(self.data_size_offset.unwrap()))?;
+        self.inner
+            .write_all(&(self.data_len as i64 + 4).to_be_bytes())?;
+        self.inner.seek(SeekFrom::Start(end))?;
+
+        if self.is_vbr() {
+            let fpp = self.asbd.frames_per_packet;
+            let frames: i64 = if fpp == 0 {
+                self.packets.iter().map(|p| p.1 as i64).sum()
+            } else {
+                self.packets.len() as i64 * fpp as i64
+            };
+            let valid = frames - self.priming_frames as i64 - self.remainder_frames as i64;
+            let mut body = Vec::with_capacity(24 + self.packets.len() * 3);
+            body.extend_from_slice(&(self.packets.len() as i64).to_be_bytes());
+            body.extend_from_slice(&valid.to_be_bytes());
+            body.extend_from_slice(&self.priming_frames.to_be_bytes());
+            body.extend_from_slice(&self.remainder_frames.to_be_bytes());
+            for (size, frames) in &self.packets {
+                if self.asbd.bytes_per_packet == 0 {
+                    write_var(&mut body, *size as u64);
+                }
+                if fpp == 0 {
+                    write_var(&mut body, *frames as u64);
+                }
+            }
+            self.write_chunk(PAKT, &body)?;
+        }
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::cat::audio::{
+        self,
+        container::{caf, Error},
+    };
+
+    #[test]
+    fn lpcm() {
+        let asbd = audio::StreamBasicDesc {
+            sample_rate: 44_100.0,
+            format: audio::Format::LINEAR_PCM,
+            format_flags: audio::FormatFlags(
+                audio::FormatFlags::IS_SIGNED_INTEGER.0 | audio::FormatFlags::IS_PACKED.0,
+            ),
+            bytes_per_packet: 4,
+            frames_per_packet: 1,
+            bytes_per_frame: 4,
+            channels_per_frame: 2,
+            bits_per_channel: 16,
+            reserved: 0,
+        };
+        let mut writer = caf::Writer::new(Cursor::new(Vec::new()), &asbd).unwrap();
+        writer
+            .set_channel_layout(audio::ChannelLayoutBuf::with_tag(
+                audio::ChannelLayoutTag::STEREO,
+            ))
+            .unwrap();
+        writer.add_info("title", "test").unwrap();
+        writer.write(&[1, 0, 2, 0, 3, 0, 4, 0]).unwrap();
+        assert!(writer.add_info("artist", "none").is_err());
+        let bytes = writer.finish().unwrap().into_inner();
+
+        assert_eq!(&bytes[..8], b"caff\x00\x01\x00\x00");
+        assert_eq!(&bytes[8..20], b"desc\x00\x00\x00\x00\x00\x00\x00\x20");
+        // 44100 as f64, 'lpcm', little endian int
+        assert_eq!(
+            &bytes[20..36],
+            b"\x40\xe5\x88\x80\x00\x00\x00\x00lpcm\x00\x00\x00\x02"
+        );
+
+        let mut reader = caf::Reader::new(Cursor::new(bytes)).unwrap();
+        assert_eq!(reader.asbd(), &asbd);
+        assert_eq!(
+            reader.channel_layout().unwrap().tag,
+            audio::ChannelLayoutTag::STEREO
+        );
+        assert_eq!(reader.channel_layout().unwrap().channels_num(), 2);
+        assert_eq!(reader.info(), &[("title".to_string(), "test".to_string())]);
+        assert_eq!(reader.frames(), 2);
+        assert_eq!(reader.packet_count(), 2);
+
+        let mut buf = [0u8; 16];
+        assert_eq!(reader.read(&mut buf).unwrap(), 8);
+        assert_eq!(&buf[..8], &[1, 0, 2, 0, 3, 0, 4, 0]);
+        reader.seek_frame(1).unwrap();
+        assert_eq!(reader.read(&mut buf).unwrap(), 4);
+        assert_eq!(&buf[..4], &[3, 0, 4, 0]);
+
+        // chunk sizes from the file can't overflow
+        let mut bytes = reader.into_inner().into_inner();
+        let data = bytes.windows(4).rposition(|w| w == b"data").unwrap();
+        bytes[data + 4..data + 12].copy_from_slice(&(-2i64).to_be_bytes());
+        assert!(matches!(
+            caf::Reader::new(Cursor::new(bytes)),
+            Err(Error::InvalidChunk(caf::DATA))
+        ));
+    }
+
+    #[test]
+    fn packets() {
+        let asbd = audio::StreamBasicDesc {
+            sample_rate: 48_000.0,
+            format: audio::Format::MPEG4_AAC,
+            format_flags: audio::FormatFlags(0),
+            bytes_per_packet: 0,
+            frames_per_packet: 1024,
+            bytes_per_frame: 0,
+            channels_per_frame: 2,
+            bits_per_channel: 0,
+            reserved: 0,
+        };
+        let cookie = [0x12, 0x10];
+        let mut writer = caf::Writer::new(Cursor::new(Vec::new()), &asbd).unwrap();
+        writer.set_magic_cookie(&cookie).unwrap();
+        writer.set_priming(2112, 960);
+        assert!(writer.write(&[0; 8]).is_err());
+        let sizes = [6usize, 200, 0x1234, 1];
+        for (i, size) in sizes.iter().enumerate() {
+            writer.write_packet(&vec![i as u8; *size], 0).unwrap();
+        }
+        let bytes = writer.finish().unwrap().into_inner();
+
+        let mut reader = caf::Reader::new(Cursor::new(bytes)).unwrap();
+        assert_eq!(reader.asbd(), &asbd);
+        assert_eq!(reader.magic_cookie(), Some(&cookie[..]));
+        let table = reader.packet_table().unwrap();
+        assert_eq!(table.priming_frames, 2112);
+        assert_eq!(table.remainder_frames, 960);
+        assert_eq!(table.valid_frames, 4 * 1024 - 2112 - 960);
+        assert_eq!(reader.frames(), 4 * 1024 - 2112 - 960);
+        assert_eq!(reader.packet_count(), 4);
+
+        let mut buf = Vec::new();
+        let desc = reader.read_packet(2, &mut buf).unwrap().unwrap();
+        assert_eq!(desc.start_offset, 206);
+        assert_eq!(desc.data_byte_size, 0x1234);
+        assert!(buf.iter().all(|b| *b == 2));
+        assert!(reader.read_packet(4, &mut buf).unwrap().is_none());
+        assert!(reader.seek_frame(0).is_err());
+    }
+
+    #[test]
+    fn var_ints() {
+        for val in [0u64, 1, 127, 128, 0x1234, u32::MAX as u64, u64::MAX] {
+            let mut buf = Vec::new();
+            super::write_var(&mut buf, val);
+            let mut at = 0;
+            assert_eq!(super::read_var(&buf, &mut at), Some(val));
+            assert_eq!(at, buf.len());
+        }
+        let mut buf = Vec::new();
+        super::write_var(&mut buf, 200);
+        assert_eq!(buf, [0x81, 0x48]);
+    }
+}
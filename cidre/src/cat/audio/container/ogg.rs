@@ -0,0 +1,855 @@
+//! Ogg encapsulation of Opus (RFC 7845).
+//!
+//! Only a single logical stream is supported. Pages of other streams are skipped.
+
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+
+use crate::cat::audio::{
+    ChannelDesc, ChannelLabel, ChannelLayoutTag, Format, FormatFlags, StreamBasicDesc,
+    StreamPacketDesc,
+};
+
+use super::{read_vec, ChannelLayoutBuf, Error, Result};
+
+const OGGS: [u8; 4] = *b"OggS";
+const OPUS_HEAD: &[u8; 8] = b"OpusHead";
+const OPUS_TAGS: &[u8; 8] = b"OpusTags";
+
+const FLAG_CONTINUED: u8 = 1;
+const FLAG_BOS: u8 = 2;
+const FLAG_EOS: u8 = 4;
+
+/// Page is flushed before the next packet once its body reaches this size.
+const PAGE_SIZE: usize = 4096;
+
+/// Opus always runs at 48 kHz in Ogg.
+const SAMPLE_RATE: u32 = 48_000;
+
+/// Stream and coupled stream counts of Vorbis channel order (RFC 7845 5.1.1.2).
+const VORBIS_STREAMS: [(u8, u8, &[u8]); 8] = [
+    (1, 0, &[0]),
+    (1, 1, &[0, 1]),
+    (2, 1, &[0, 2, 1]),
+    (2, 2, &[0, 1, 2, 3]),
+    (3, 2, &[0, 4, 1, 2, 3]),
+    (4, 2, &[0, 4, 1, 2, 3, 5]),
+    (4, 3, &[0, 4, 1, 2, 3, 5, 6]),
+    (5, 3, &[0, 6, 1, 2, 3, 4, 5, 7]),
+];
+
+fn crc(mut crc: u32, data: &[u8]) -> u32 {
+    const TABLE: [u32; 256] = {
+        let mut table = [0u32; 256];
+        let mut i = 0;
+        while i < 256 {
+            let mut r = (i as u32) << 24;
+            let mut j = 0;
+            while j < 8 {
+                r = if r & 0x8000_0000 != 0 {
+                    (r << 1) ^ 0x04c1_1db7
+                } else {
+                    r << 1
+                };
+                j += 1;
+            }
+            table[i] = r;
+            i += 1;
+        }
+        table
+    };
+    for b in data {
+        crc = (crc << 8) ^ TABLE[((crc >> 24) as u8 ^ b) as usize];
+    }
+    crc
+}
+
+/// Number of 48 kHz frames in Opus packet, from its TOC byte (RFC 6716 3.1).
+///
+/// `None` if packet is malformed.
+pub fn packet_frames(data: &[u8]) -> Option<u32> {
+    let toc = *data.first()?;
+    let config = toc >> 3;
+    let frame = match config {
+        0..=11 => [480, 960, 1920, 2880][config as usize % 4],
+        12..=15 => [480, 960][config as usize % 2],
+        _ => [120, 240, 480, 960][config as usize % 4],
+    };
+    let count = match toc & 3 {
+        0 => 1,
+        1 | 2 => 2,
+        _ => (*data.get(1)? & 0x3f) as u32,
+    };
+    let res = frame * count;
+    if res == 0 || res > 5760 {
+        return None;
+    }
+    Some(res)
+}
+
+/// Identification header of Ogg Opus stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpusHead {
+    pub channels: u8,
+    /// Frames to discard from decoder output at the start.
+    pub pre_skip: u16,
+    /// Informational sample rate of original input.
+    pub input_sample_rate: u32,
+    /// Gain in Q7.8 dB to apply to decoder output.
+    pub output_gain: i16,
+    /// 0 for mono and stereo, 1 for Vorbis channel order, 255 for discrete channels.
+    pub mapping_family: u8,
+    pub stream_count: u8,
+    pub coupled_count: u8,
+    /// Channel to stream mapping, empty for family 0.
+    pub mapping: Vec<u8>,
+}
+
+impl OpusHead {
+    /// Head for output format of Opus encoder.
+    ///
+    /// Encoder input is expected to be in Vorbis channel order for 3 to 8 channels.
+    /// Other layouts are reported as [`Error::Unsupported`].
+    /// `pre_skip` is zero, set it from encoder priming frames.
+    pub fn with_asbd(asbd: &StreamBasicDesc, layout: Option<&ChannelLayoutBuf>) -> Result<Self> {
+        if asbd.format != Format::OPUS {
+            return Err(Error::Unsupported);
+        }
+        let channels = match asbd.channels_per_frame {
+            ch @ 1..=8 => ch as u8,
+            _ => return Err(Error::Unsupported),
+        };
+        if let Some(layout) = layout {
+            let expected = vorbis_layout(channels);
+            let labels =
+                |l: &ChannelLayoutBuf| l.descs.iter().map(|d| d.channel_label).collect::<Vec<_>>();
+            let same = layout.tag == expected.tag
+                && (layout.tag != ChannelLayoutTag::USE_CHANNEL_DESCRIPTIONS
+                    || labels(layout) == labels(&expected));
+            if !same && channels > 2 {
+                return Err(Error::Unsupported);
+            }
+        }
+        let (stream_count, coupled_count, mapping) = VORBIS_STREAMS[channels as usize - 1];
+        let family = if channels > 2 { 1 } else { 0 };
+        Ok(Self {
+            channels,
+            pre_skip: 0,
+            input_sample_rate: asbd.sample_rate as u32,
+            output_gain: 0,
+            mapping_family: family,
+            stream_count,
+            coupled_count,
+            mapping: if family == 0 {
+                Vec::new()
+            } else {
+                mapping.to_vec()
+            },
+        })
+    }
+
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        if data.len() < 19 || &data[..8] != OPUS_HEAD || data[8] & 0xf0 != 0 {
+            return Err(Error::InvalidHeader);
+        }
+        let channels = data[9];
+        let mapping_family = data[18];
+        let (stream_count, coupled_count, mapping) = if mapping_family == 0 {
+            if !(1..=2).contains(&channels) {
+                return Err(Error::InvalidHeader);
+            }
+            (1, channels - 1, Vec::new())
+        } else {
+            let table = data
+                .get(19..21 + channels as usize)
+                .ok_or(Error::InvalidHeader)?;
+            (table[0], table[1], table[2..].to_vec())
+        };
+        let res = Self {
+            channels,
+            pre_skip: u16::from_le_bytes([data[10], data[11]]),
+            input_sample_rate: u32::from_le_bytes([data[12], data[13], data[14], data[15]]),
+            output_gain: i16::from_le_bytes([data[16], data[17]]),
+            mapping_family,
+            stream_count,
+            coupled_count,
+            mapping,
+        };
+        res.validate()?;
+        Ok(res)
+    }
+
+    fn validate(&self) -> Result {
+        let streams = self.stream_count as u32 + self.coupled_count as u32;
+        let valid = self.channels != 0
+            && self.stream_count != 0
+            && self.coupled_count <= self.stream_count
+            && streams <= 255
+            && if self.mapping_family == 0 {
+                self.channels <= 2 && self.mapping.is_empty()
+            } else {
+                self.mapping.len() == self.channels as usize
+                    && self
+                        .mapping
+                        .iter()
+                        .all(|m| *m == 255 || (*m as u32) < streams)
+            };
+        if valid {
+            Ok(())
+        } else {
+            Err(Error::InvalidHeader)
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut res = Vec::with_capacity(21 + self.mapping.len());
+        res.extend_from_slice(OPUS_HEAD);
+        res.push(1);
+        res.push(self.channels);
+        res.extend_from_slice(&self.pre_skip.to_le_bytes());
+        res.extend_from_slice(&self.input_sample_rate.to_le_bytes());
+        res.extend_from_slice(&self.output_gain.to_le_bytes());
+        res.push(self.mapping_family);
+        if self.mapping_family != 0 {
+            res.push(self.stream_count);
+            res.push(self.coupled_count);
+            res.extend_from_slice(&self.mapping);
+        }
+        res
+    }
+
+    /// Decoder output format.
+    pub fn asbd(&self, frames_per_packet: u32) -> StreamBasicDesc {
+        StreamBasicDesc {
+            sample_rate: SAMPLE_RATE as f64,
+            format: Format::OPUS,
+            format_flags: FormatFlags(0),
+            bytes_per_packet: 0,
+            frames_per_packet,
+            bytes_per_frame: 0,
+            channels_per_frame: self.channels as u32,
+            bits_per_channel: 0,
+            reserved: 0,
+        }
+    }
+
+    pub fn channel_layout(&self) -> ChannelLayoutBuf {
+        match self.mapping_family {
+            0 | 1 if self.channels <= 8 => vorbis_layout(self.channels),
+            _ => ChannelLayoutBuf::with_tag(ChannelLayoutTag(
+                ChannelLayoutTag::DISCRETE_IN_ORDER.0 | self.channels as u32,
+            )),
+        }
+    }
+}
+
+fn vorbis_layout(channels: u8) -> ChannelLayoutBuf {
+    let labels = |labels: &[ChannelLabel]| {
+        ChannelLayoutBuf::with_descs(
+            labels
+                .iter()
+                .map(|l| ChannelDesc {
+                    channel_label: *l,
+                    ..Default::default()
+                })
+                .collect(),
+        )
+    };
+    match channels {
+        1 => ChannelLayoutBuf::with_tag(ChannelLayoutTag::MONO),
+        2 => ChannelLayoutBuf::with_tag(ChannelLayoutTag::STEREO),
+        3 => ChannelLayoutBuf::with_tag(ChannelLayoutTag::AC3_3_0),
+        4 => ChannelLayoutBuf::with_tag(ChannelLayoutTag::QUADRAPHONIC),
+        5 => ChannelLayoutBuf::with_tag(ChannelLayoutTag::MPEG_5_0_C),
+        6 => ChannelLayoutBuf::with_tag(ChannelLayoutTag::MPEG_5_1_C),
+        7 => labels(&[
+            ChannelLabel::LEFT,
+            ChannelLabel::CENTER,
+            ChannelLabel::RIGHT,
+            ChannelLabel::LEFT_SURROUND,
+            ChannelLabel::RIGHT_SURROUND,
+            ChannelLabel::CENTER_SURROUND,
+            ChannelLabel::LFE_SCREEN,
+        ]),
+        _ => labels(&[
+            ChannelLabel::LEFT,
+            ChannelLabel::CENTER,
+            ChannelLabel::RIGHT,
+            ChannelLabel::LEFT_SURROUND,
+            ChannelLabel::RIGHT_SURROUND,
+            ChannelLabel::REAR_SURROUND_LEFT,
+            ChannelLabel::REAR_SURROUND_RIGHT,
+            ChannelLabel::LFE_SCREEN,
+        ]),
+    }
+}
+
+fn tags_body(vendor: &str, comments: &[(String, String)]) -> Vec<u8> {
+    let mut res = OPUS_TAGS.to_vec();
+    res.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+    res.extend_from_slice(vendor.as_bytes());
+    res.extend_from_slice(&(comments.len() as u32).to_le_bytes());
+    for (key, val) in comments {
+        res.extend_from_slice(&((key.len() + val.len() + 1) as u32).to_le_bytes());
+        res.extend_from_slice(key.as_bytes());
+        res.push(b'=');
+        res.extend_from_slice(val.as_bytes());
+    }
+    res
+}
+
+fn parse_tags(data: &[u8]) -> Result<(String, Vec<(String, String)>)> {
+    if data.len() < 8 || &data[..8] != OPUS_TAGS {
+        return Err(Error::InvalidHeader);
+    }
+    let mut at = 8;
+    let u32_next = |at: &mut usize| -> Result<u32> {
+        let b = data.get(*at..*at + 4).ok_or(Error::InvalidHeader)?;
+        *at += 4;
+        Ok(u32::from_le_bytes(b.try_into().unwrap()))
+    };
+    let string = |at: &mut usize| -> Result<String> {
+        let len = u32_next(at)? as usize;
+        let s = data.get(*at..*at + len).ok_or(Error::InvalidHeader)?;
+        *at += len;
+        Ok(String::from_utf8_lossy(s).into_owned())
+    };
+    let vendor = string(&mut at)?;
+    let count = u32_next(&mut at)?;
+    let mut comments = Vec::new();
+    for _ in 0..count {
+        let s = string(&mut at)?;
+        if let Some((key, val)) = s.split_once('=') {
+            comments.push((key.to_string(), val.to_string()));
+        }
+    }
+    Ok((vendor, comments))
+}
+
+/// Packets of a single logical stream.
+struct Pages<R> {
+    inner: R,
+    serial: Option<u32>,
+    seq: u32,
+    packets: VecDeque<Vec<u8>>,
+    partial: Vec<u8>,
+    eos: bool,
+    end_granule: Option<u64>,
+}
+
+impl<R: Read> Pages<R> {
+    fn next_packet(&mut self) -> Result<Option<Vec<u8>>> {
+        self.fill()?;
+        Ok(self.packets.pop_front())
+    }
+
+    fn fill(&mut self) -> Result {
+        while self.packets.is_empty() && !self.eos {
+            if !self.read_page()? {
+                self.eos = true;
+            }
+        }
+        Ok(())
+    }
+
+    /// `false` at the end of input.
+    fn read_page(&mut self) -> Result<bool> {
+        let mut header = [0u8; 27];
+        if self.inner.read(&mut header[..1])? == 0 {
+            return Ok(false);
+        }
+        self.inner.read_exact(&mut header[1..])?;
+        if header[..4] != OGGS || header[4] != 0 {
+            return Err(Error::InvalidChunk(OGGS));
+        }
+        let flags = header[5];
+        let granule = i64::from_le_bytes(header[6..14].try_into().unwrap());
+        let serial = u32::from_le_bytes(header[14..18].try_into().unwrap());
+        let seq = u32::from_le_bytes(header[18..22].try_into().unwrap());
+        let checksum = u32::from_le_bytes(header[22..26].try_into().unwrap());
+        let lacing = read_vec(&mut self.inner, header[26] as u64)?;
+        let body_len = lacing.iter().map(|l| *l as u64).sum();
+        let body = read_vec(&mut self.inner, body_len)?;
+
+        header[22..26].fill(0);
+        if crc(crc(crc(0, &header), &lacing), &body) != checksum {
+            return Err(Error::InvalidChunk(OGGS));
+        }
+        if *self.serial.get_or_insert(serial) != serial {
+            return Ok(true);
+        }
+
+        // lost page breaks the packet spanning it
+        let continued = flags & FLAG_CONTINUED != 0;
+        let mut skip = continued && (seq != self.seq || self.partial.is_empty());
+        if !continued || seq != self.seq {
+            self.partial.clear();
+        }
+        self.seq = seq.wrapping_add(1);
+
+        let mut pos = 0;
+        for l in lacing {
+            let end = pos + l as usize;
+            if !skip {
+                self.partial.extend_from_slice(&body[pos..end]);
+            }
+            pos = end;
+            if l < 255 {
+                if !skip {
+                    self.packets.push_back(std::mem::take(&mut self.partial));
+                }
+                skip = false;
+            }
+        }
+        if flags & FLAG_EOS != 0 {
+            self.eos = true;
+            self.end_granule = (granule >= 0).then_some(granule as u64);
+        }
+        Ok(true)
+    }
+}
+
+/// Ogg Opus stream reader.
+///
+/// Yields packets with descriptions for `at::AudioConverter` decoding [`Reader::asbd`].
+pub struct Reader<R> {
+    pages: Pages<R>,
+    head: OpusHead,
+    vendor: String,
+    comments: Vec<(String, String)>,
+    asbd: StreamBasicDesc,
+    frames_read: u64,
+}
+
+impl<R: Read> Reader<R> {
+    pub fn new(inner: R) -> Result<Self> {
+        let mut pages = Pages {
+            inner,
+            serial: None,
+            seq: 0,
+            packets: VecDeque::new(),
+            partial: Vec::new(),
+            eos: false,
+            end_granule: None,
+        };
+        let head = pages.next_packet()?.ok_or(Error::InvalidHeader)?;
+        let head = OpusHead::parse(&head)?;
+        let tags = pages.next_packet()?.ok_or(Error::InvalidHeader)?;
+        let (vendor, comments) = parse_tags(&tags)?;
+        pages.fill()?;
+        let frames_per_packet = pages
+            .packets
+            .front()
+            .and_then(|p| packet_frames(p))
+            .unwrap_or(960);
+        Ok(Self {
+            pages,
+            asbd: head.asbd(frames_per_packet),
+            head,
+            vendor,
+            comments,
+            frames_read: 0,
+        })
+    }
+
+    #[inline]
+    pub fn head(&self) -> &OpusHead {
+        &self.head
+    }
+
+    /// Opus format with frames per packet of the first packet.
+    #[inline]
+    pub fn asbd(&self) -> &StreamBasicDesc {
+        &self.asbd
+    }
+
+    #[inline]
+    pub fn channel_layout(&self) -> ChannelLayoutBuf {
+        self.head.channel_layout()
+    }
+
+    #[inline]
+    pub fn vendor(&self) -> &str {
+        &self.vendor
+    }
+
+    #[inline]
+    pub fn comments(&self) -> &[(String, String)] {
+        &self.comments
+    }
+
+    /// Frames in packets read so far, including pre-skip.
+    #[inline]
+    pub fn frames_read(&self) -> u64 {
+        self.frames_read
+    }
+
+    /// Frames to discard at the end of decoder output.
+    ///
+    /// Known after the last packet is read.
+    pub fn remainder_frames(&self) -> Option<u64> {
+        if !self.pages.eos || !self.pages.packets.is_empty() {
+            return None;
+        }
+        Some(
+            self.pages
+                .end_granule
+                .map_or(0, |g| self.frames_read.saturating_sub(g)),
+        )
+    }
+
+    /// Reads next packet into `buf`, `None` at the end of stream.
+    pub fn read_packet(&mut self, buf: &mut Vec<u8>) -> Result<Option<StreamPacketDesc>> {
+        let Some(packet) = self.pages.next_packet()? else {
+            return Ok(None);
+        };
+        let frames = packet_frames(&packet).ok_or(Error::InvalidChunk(OGGS))?;
+        self.frames_read += frames as u64;
+        let desc = StreamPacketDesc {
+            start_offset: 0,
+            variable_frames_in_packet: frames,
+            data_byte_size: packet.len() as u32,
+        };
+        *buf = packet;
+        Ok(Some(desc))
+    }
+
+    /// Reads up to `max` packets into contiguous `buf`, replacing its content.
+    pub fn read_packets(
+        &mut self,
+        max: usize,
+        buf: &mut Vec<u8>,
+        descs: &mut Vec<StreamPacketDesc>,
+    ) -> Result<usize> {
+        buf.clear();
+        descs.clear();
+        let mut packet = Vec::new();
+        while descs.len() < max {
+            let Some(mut desc) = self.read_packet(&mut packet)? else {
+                break;
+            };
+            desc.start_offset = buf.len() as i64;
+            buf.extend_from_slice(&packet);
+            descs.push(desc);
+        }
+        Ok(descs.len())
+    }
+
+    pub fn into_inner(self) -> R {
+        self.pages.inner
+    }
+}
+
+/// Ogg Opus stream writer.
+///
+/// Pages are written as packets arrive, so `W` doesn't need to be seekable.
+pub struct Writer<W: Write> {
+    inner: W,
+    head: OpusHead,
+    comments: Vec<(String, String)>,
+    serial: u32,
+    seq: u32,
+    header_written: bool,
+    lacing: Vec<u8>,
+    body: Vec<u8>,
+    /// Granule of the last packet finished on the current page.
+    page_granule: Option<u64>,
+    continued: bool,
+    granule: u64,
+    remainder_frames: u64,
+}
+
+impl<W: Write> Writer<W> {
+    /// `serial` identifies the logical stream, should be random.
+    pub fn new(inner: W, head: OpusHead, serial: u32) -> Result<Self> {
+        if head.validate().is_err() {
+            return Err(Error::Unsupported);
+        }
+        Ok(Self {
+            inner,
+            head,
+            comments: Vec::new(),
+            serial,
+            seq: 0,
+            header_written: false,
+            lacing: Vec::new(),
+            body: Vec::new(),
+            page_granule: None,
+            continued: false,
+            granule: 0,
+            remainder_frames: 0,
+        })
+    }
+
+    #[inline]
+    pub fn head(&self) -> &OpusHead {
+        &self.head
+    }
+
+    /// Adds comment like `TITLE` or `ARTIST`.
+    pub fn add_comment(&mut self, key: &str, val: &str) -> Result {
+        if self.header_written {
+            return Err(Error::HeaderWritten);
+        }
+        self.comments.push((key.to_string(), val.to_string()));
+        Ok(())
+    }
+
+    /// Frames of the last packets to trim at the end of playback.
+    #[inline]
+    pub fn set_remainder_frames(&mut self, val: u64) {
+        self.remainder_frames = val;
+    }
+
+    /// Frames written so far, including pre-skip.
+    #[inline]
+    pub fn frames_written(&self) -> u64 {
+        self.granule
+    }
+
+    fn flush_page(&mut self, eos: bool, continues: bool) -> io::Result<()> {
+        let mut flags = 0;
+        if self.continued {
+            flags |= FLAG_CONTINUED;
+        }
+        if self.seq == 0 {
+            flags |= FLAG_BOS;
+        }
+        if eos {
+            flags |= FLAG_EOS;
+        }
+        let granule = self.page_granule.map_or(-1, |g| g as i64);
+        let mut header = [0u8; 27];
+        header[..4].copy_from_slice(&OGGS);
+        header[5] = flags;
+        header[6..14].copy_from_slice(&granule.to_le_bytes());
+        header[14..18].copy_from_slice(&self.serial.to_le_bytes());
+        header[18..22].copy_from_slice(&self.seq.to_le_bytes());
+        header[26] = self.lacing.len() as u8;
+        let checksum = crc(crc(crc(0, &header), &self.lacing), &self.body);
+        header[22..26].copy_from_slice(&checksum.to_le_bytes());
+
+        self.inner.write_all(&header)?;
+        self.inner.write_all(&self.lacing)?;
+        self.inner.write_all(&self.body)?;
+        self.seq += 1;
+        self.lacing.clear();
+        self.body.clear();
+        self.page_granule = None;
+        self.continued = continues;
+        Ok(())
+    }
+
+    fn push_packet(&mut self, data: &[u8], granule: u64) -> io::Result<()> {
+        let mut pos = 0;
+        loop {
+            if self.lacing.len() == 255 {
+                self.flush_page(false, pos > 0)?;
+            }
+            let n = (data.len() - pos).min(255);
+            self.lacing.push(n as u8);
+            self.body.extend_from_slice(&data[pos..pos + n]);
+            pos += n;
+            if n < 255 {
+                break;
+            }
+        }
+        self.page_granule = Some(granule);
+        Ok(())
+    }
+
+    fn write_header(&mut self) -> io::Result<()> {
+        if self.header_written {
+            return Ok(());
+        }
+        self.header_written = true;
+        self.push_packet(&self.head.to_bytes(), 0)?;
+        self.flush_page(false, false)?;
+        let tags = tags_body(concat!("cidre ", env!("CARGO_PKG_VERSION")), &self.comments);
+        self.push_packet(&tags, 0)?;
+        self.flush_page(false, false)
+    }
+
+    pub fn write_packet(&mut self, data: &[u8]) -> Result {
+        let frames = packet_frames(data).ok_or(Error::Unsupported)?;
+        self.write_header()?;
+        // flushing before the packet keeps the last packet on the eos page
+        if self.body.len() >= PAGE_SIZE {
+            self.flush_page(false, false)?;
+        }
+        self.granule += frames as u64;
+        self.push_packet(data, self.granule)?;
+        Ok(())
+    }
+
+    /// Writes packets of encoder output described by `descs`.
+    pub fn write_packets(&mut self, data: &[u8], descs: &[StreamPacketDesc]) -> Result {
+        for desc in descs {
+            let start = desc.start_offset as usize;
+            let packet = data
+                .get(start..start + desc.data_byte_size as usize)
+                .ok_or(Error::Unsupported)?;
+            self.write_packet(packet)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the last page with end trimmed granule position.
+    pub fn finish(mut self) -> Result<W> {
+        self.write_header()?;
+        self.page_granule = Some(self.granule.saturating_sub(self.remainder_frames));
+        self.flush_page(true, false)?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::cat::audio::{self, container::ogg};
+
+    #[test]
+    fn crc() {
+        assert_eq!(super::crc(0, b"123456789"), 0x89a1_897f);
+    }
+
+    #[test]
+    fn packet_frames() {
+        // CELT 20 ms
+        assert_eq!(ogg::packet_frames(&[0xf8]), Some(960));
+        // SILK 20 ms, two frames
+        assert_eq!(ogg::packet_frames(&[0x09]), Some(1920));
+        // CELT 20 ms, three frames
+        assert_eq!(ogg::packet_frames(&[0xfb, 0x03]), Some(2880));
+        assert_eq!(ogg::packet_frames(&[]), None);
+        assert_eq!(ogg::packet_frames(&[0xfb]), None);
+        assert_eq!(ogg::packet_frames(&[0xfb, 0x00]), None);
+        assert_eq!(ogg::packet_frames(&[0xfb, 0x07]), None);
+    }
+
+    #[test]
+    fn head() {
+        let asbd = audio::StreamBasicDesc {
+            sample_rate: 48_000.0,
+            format: audio::Format::OPUS,
+            frames_per_packet: 960,
+            channels_per_frame: 6,
+            ..Default::default()
+        };
+        let layout = audio::ChannelLayoutBuf::with_tag(audio::ChannelLayoutTag::MPEG_5_1_C);
+        let head = ogg::OpusHead::with_asbd(&asbd, Some(&layout)).unwrap();
+        assert_eq!(head.mapping_family, 1);
+        assert_eq!(head.stream_count, 4);
+        assert_eq!(head.coupled_count, 2);
+        assert_eq!(head.mapping, [0, 4, 1, 2, 3, 5]);
+        assert_eq!(ogg::OpusHead::parse(&head.to_bytes()).unwrap(), head);
+        assert_eq!(head.channel_layout(), layout);
+
+        let layout = audio::ChannelLayoutBuf::with_tag(audio::ChannelLayoutTag::MPEG_5_1_A);
+        assert!(ogg::OpusHead::with_asbd(&asbd, Some(&layout)).is_err());
+
+        let asbd = audio::StreamBasicDesc {
+            channels_per_frame: 2,
+            ..asbd
+        };
+        let head = ogg::OpusHead::with_asbd(&asbd, None).unwrap();
+        assert_eq!(
+            head.to_bytes(),
+            b"OpusHead\x01\x02\x00\x00\x80\xbb\x00\x00\x00\x00\x00"
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let asbd = audio::StreamBasicDesc {
+            sample_rate: 48_000.0,
+            format: audio::Format::OPUS,
+            frames_per_packet: 960,
+            channels_per_frame: 2,
+            ..Default::default()
+        };
+        let mut head = ogg::OpusHead::with_asbd(&asbd, None).unwrap();
+        head.pre_skip = 312;
+
+        // 20 ms packets, one of them spans several pages
+        let packets: Vec<Vec<u8>> = [100usize, 0xff, 70_000, 3, 5000, 255 * 255]
+            .iter()
+            .enumerate()
+            .map(|(i, len)| {
+                let mut p = vec![i as u8; *len];
+                p[0] = 0xf8;
+                p
+            })
+            .collect();
+        let mut data = Vec::new();
+        let mut descs = Vec::new();
+        for p in &packets[1..] {
+            descs.push(audio::StreamPacketDesc {
+                start_offset: data.len() as i64,
+                variable_frames_in_packet: 0,
+                data_byte_size: p.len() as u32,
+            });
+            data.extend_from_slice(p);
+        }
+
+        let mut writer = ogg::Writer::new(Vec::new(), head.clone(), 0x1234).unwrap();
+        writer.add_comment("TITLE", "test").unwrap();
+        writer.write_packet(&packets[0]).unwrap();
+        assert!(writer.add_comment("ARTIST", "none").is_err());
+        writer.write_packets(&data, &descs).unwrap();
+        assert_eq!(writer.frames_written(), 6 * 960);
+        writer.set_remainder_frames(500);
+        let bytes = writer.finish().unwrap();
+
+        // bos page with head only
+        assert_eq!(&bytes[..6], b"OggS\x00\x02");
+        assert_eq!(bytes[26], 1);
+        assert_eq!(bytes[27], 19);
+
+        let mut reader = ogg::Reader::new(Cursor::new(&bytes)).unwrap();
+        assert_eq!(reader.head(), &head);
+        assert_eq!(reader.asbd(), &asbd);
+        assert_eq!(
+            reader.comments(),
+            &[("TITLE".to_string(), "test".to_string())]
+        );
+        assert!(reader.vendor().starts_with("cidre"));
+
+        let mut buf = Vec::new();
+        let desc = reader.read_packet(&mut buf).unwrap().unwrap();
+        assert_eq!(desc.variable_frames_in_packet, 960);
+        assert_eq!(buf, packets[0]);
+        assert_eq!(reader.remainder_frames(), None);
+
+        let mut read_descs = Vec::new();
+        assert_eq!(
+            reader.read_packets(16, &mut buf, &mut read_descs).unwrap(),
+            5
+        );
+        assert_eq!(buf, data);
+        for (a, b) in read_descs.iter().zip(descs.iter()) {
+            assert_eq!(a.start_offset, b.start_offset);
+            assert_eq!(a.data_byte_size, b.data_byte_size);
+            assert_eq!(a.variable_frames_in_packet, 960);
+        }
+        assert!(reader.read_packet(&mut buf).unwrap().is_none());
+        assert_eq!(reader.frames_read(), 6 * 960);
+        assert_eq!(reader.remainder_frames(), Some(500));
+
+        // corrupted page
+        let mut bytes = bytes;
+        let at = bytes.len() - 100;
+        bytes[at] ^= 1;
+        let mut reader = ogg::Reader::new(Cursor::new(&bytes)).unwrap();
+        let mut res = Ok(None);
+        for _ in 0..6 {
+            res = reader.read_packet(&mut buf);
+            if res.is_err() {
+                break;
+            }
+        }
+        assert!(res.is_err());
+    }
+}
@@ -0,0 +1,619 @@
+//! RIFF WAVE, RF64/BW64 and Sony Wave64 files with PCM and IEEE float data.
+
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use crate::cat::audio::{ChannelBitmap, ChannelLayoutTag, Format, FormatFlags, StreamBasicDesc};
+
+use super::{field, read_array, read_vec, ChannelLayoutBuf, Error, Result};
+
+const FMT: [u8; 4] = *b"fmt ";
+const DATA: [u8; 4] = *b"data";
+const DS64: [u8; 4] = *b"ds64";
+const JUNK: [u8; 4] = *b"JUNK";
+
+/// Size of `ds64` chunk body without table.
+const DS64_LEN: u32 = 28;
+
+const WAVE_FORMAT_PCM: u16 = 0x0001;
+const WAVE_FORMAT_IEEE_FLOAT: u16 = 0x0003;
+const WAVE_FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+/// Tail of `KSDATAFORMAT_SUBTYPE_*` GUIDs after the format tag.
+const SUBTYPE_TAIL: [u8; 14] = [
+    0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x80, 0x00, 0x00, 0xAA, 0x00, 0x38, 0x9B, 0x71,
+];
+
+/// Tail of Wave64 GUIDs after the four character code.
+const W64_TAIL: [u8; 12] = [
+    0xF3, 0xAC, 0xD3, 0x11, 0x8C, 0xD1, 0x00, 0xC0, 0x4F, 0x8E, 0xDB, 0x8A,
+];
+
+const W64_RIFF: [u8; 16] = [
+    b'r', b'i', b'f', b'f', 0x2E, 0x91, 0xCF, 0x11, 0xA5, 0xD6, 0x28, 0xDB, 0x04, 0xC1, 0x00, 0x00,
+];
+
+#[inline]
+fn w64_guid(ty: [u8; 4]) -> [u8; 16] {
+    let mut res = [0u8; 16];
+    res[..4].copy_from_slice(&ty);
+    res[4..].copy_from_slice(&W64_TAIL);
+    res
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Container {
+    /// RIFF WAVE, promoted to RF64 by the writer if data doesn't fit 4GB.
+    Wave,
+    /// RF64 or BW64.
+    Rf64,
+    /// Sony Wave64.
+    W64,
+}
+
+fn parse_fmt(body: &[u8]) -> Result<(StreamBasicDesc, Option<ChannelLayoutBuf>)> {
+    let err = || Error::InvalidChunk(FMT);
+    let u16_at = |at| field(body, at).map(u16::from_le_bytes).ok_or_else(err);
+    let mut tag = u16_at(0)?;
+    let channels = u16_at(2)? as u32;
+    let sample_rate = field(body, 4).map(u32::from_le_bytes).ok_or_else(err)?;
+    let block_align = u16_at(12)? as u32;
+    let bits = u16_at(14)? as u32;
+    let mut valid_bits = bits;
+    let mut layout = None;
+
+    if tag == WAVE_FORMAT_EXTENSIBLE {
+        valid_bits = u16_at(18)? as u32;
+        let mask = field(body, 20).map(u32::from_le_bytes).ok_or_else(err)?;
+        let subtype: [u8; 16] = field(body, 24).ok_or_else(err)?;
+        if subtype[2..] != SUBTYPE_TAIL {
+            return Err(Error::Unsupported);
+        }
+        tag = u16::from_le_bytes([subtype[0], subtype[1]]);
+        if valid_bits == 0 {
+            valid_bits = bits;
+        }
+        if mask != 0 {
+            layout = Some(ChannelLayoutBuf::with_bitmap(ChannelBitmap(mask)));
+        }
+    }
+
+    if channels == 0 || block_align == 0 || block_align % channels != 0 {
+        return Err(err());
+    }
+    let container_bits = block_align / channels * 8;
+    if valid_bits == 0 || valid_bits > container_bits {
+        return Err(err());
+    }
+    let mut flags = match tag {
+        WAVE_FORMAT_PCM if container_bits == 8 => FormatFlags(0),
+        WAVE_FORMAT_PCM => FormatFlags::IS_SIGNED_INTEGER,
+        WAVE_FORMAT_IEEE_FLOAT if valid_bits == container_bits => FormatFlags::IS_FLOAT,
+        _ => return Err(Error::Unsupported),
+    };
+    if valid_bits == container_bits {
+        flags |= FormatFlags::IS_PACKED;
+    } else {
+        flags |= FormatFlags::IS_ALIGNED_HIGH;
+    }
+    let asbd = StreamBasicDesc {
+        sample_rate: sample_rate as f64,
+        format: Format::LINEAR_PCM,
+        format_flags: flags,
+        bytes_per_packet: block_align,
+        frames_per_packet: 1,
+        bytes_per_frame: block_align,
+        channels_per_frame: channels,
+        bits_per_channel: valid_bits,
+        reserved: 0,
+    };
+    Ok((asbd, layout))
+}
+
+fn fmt_body(asbd: &StreamBasicDesc, mask: Option<u32>) -> Result<Vec<u8>> {
+    let ff = asbd.format_flags;
+    let channels = asbd.channels_per_frame;
+    if asbd.format != Format::LINEAR_PCM
+        || !asbd.is_interleaved()
+        || ff.contains(FormatFlags::IS_BIG_ENDIAN)
+        || asbd.frames_per_packet != 1
+        || channels == 0
+        || channels > u16::MAX as u32
+        || asbd.bytes_per_frame == 0
+        || asbd.bytes_per_frame % channels != 0
+        || asbd.bytes_per_frame > u16::MAX as u32
+        || asbd.sample_rate.fract() != 0.0
+        || asbd.sample_rate < 1.0
+        || asbd.sample_rate > u32::MAX as f64
+    {
+        return Err(Error::Unsupported);
+    }
+    let container_bits = asbd.bytes_per_frame / channels * 8;
+    let valid_bits = asbd.bits_per_channel;
+    let float = ff.contains(FormatFlags::IS_FLOAT);
+    let signed = ff.contains(FormatFlags::IS_SIGNED_INTEGER);
+    let supported = if float {
+        valid_bits == container_bits && (valid_bits == 32 || valid_bits == 64)
+    } else {
+        // 8 bit samples are unsigned in WAVE
+        signed == (container_bits > 8)
+            && valid_bits <= container_bits
+            && (valid_bits == container_bits || ff.contains(FormatFlags::IS_ALIGNED_HIGH))
+    };
+    if !supported {
+        return Err(Error::Unsupported);
+    }
+
+    let tag = if float {
+        WAVE_FORMAT_IEEE_FLOAT
+    } else {
+        WAVE_FORMAT_PCM
+    };
+    let extensible = channels > 2
+        || valid_bits != container_bits
+        || (!float && container_bits > 16)
+        || mask.is_some();
+    let sample_rate = asbd.sample_rate as u32;
+
+    let mut res = Vec::with_capacity(40);
+    res.extend_from_slice(
+        &if extensible {
+            WAVE_FORMAT_EXTENSIBLE
+        } else {
+            tag
+        }
+        .to_le_bytes(),
+    );
+    res.extend_from_slice(&(channels as u16).to_le_bytes());
+    res.extend_from_slice(&sample_rate.to_le_bytes());
+    res.extend_from_slice(&sample_rate.wrapping_mul(asbd.bytes_per_frame).to_le_bytes());
+    res.extend_from_slice(&(asbd.bytes_per_frame as u16).to_le_bytes());
+    res.extend_from_slice(&(container_bits as u16).to_le_bytes());
+    if extensible {
+        res.extend_from_slice(&22u16.to_le_bytes());
+        res.extend_from_slice(&(valid_bits as u16).to_le_bytes());
+        res.extend_from_slice(&mask.unwrap_or(0).to_le_bytes());
+        res.extend_from_slice(&tag.to_le_bytes());
+        res.extend_from_slice(&SUBTYPE_TAIL);
+    }
+    Ok(res)
+}
+
+/// WAVE file reader.
+pub struct Reader<R> {
+    inner: R,
+    container: Container,
+    asbd: StreamBasicDesc,
+    layout: Option<ChannelLayoutBuf>,
+    data_offset: u64,
+    data_len: u64,
+    pos: u64,
+}
+
+impl<R: Read + Seek> Reader<R> {
+    pub fn new(mut inner: R) -> Result<Self> {
+        let len = inner.seek(SeekFrom::End(0))?;
+        inner.seek(SeekFrom::Start(0))?;
+        let header: [u8; 12] = read_array(&mut inner)?;
+        let container = match (&header[..4], &header[8..]) {
+            (b"RIFF", b"WAVE") => Container::Wave,
+            (b"RF64" | b"BW64", b"WAVE") => Container::Rf64,
+            (b"riff", _) => {
+                let rest: [u8; 28] = read_array(&mut inner)?;
+                if header[4..] != W64_RIFF[4..12]
+                    || rest[..4] != W64_RIFF[12..]
+                    || rest[12..] != w64_guid(*b"wave")
+                {
+                    return Err(Error::InvalidHeader);
+                }
+                Container::W64
+            }
+            _ => return Err(Error::InvalidHeader),
+        };
+
+        let mut fmt = None;
+        let mut data = None;
+        let mut ds64_data_len = None;
+        let mut pos = inner.stream_position()?;
+        let header_len = if container == Container::W64 { 24 } else { 8 };
+        while pos + header_len <= len {
+            let (ty, size, next) = if container == Container::W64 {
+                let guid: [u8; 16] = read_array(&mut inner)?;
+                let size = u64::from_le_bytes(read_array(&mut inner)?);
+                // chunks we know about are four character codes with common tail
+                let ty = if guid[4..] == W64_TAIL {
+                    guid[..4].try_into().unwrap()
+                } else {
+                    [0u8; 4]
+                };
+                let next = pos
+                    .checked_add(size.max(24))
+                    .and_then(|end| end.checked_next_multiple_of(8))
+                    .ok_or(Error::InvalidChunk(ty))?;
+                (ty, size.saturating_sub(24), next)
+            } else {
+                let ty: [u8; 4] = read_array(&mut inner)?;
+                let mut size = u32::from_le_bytes(read_array(&mut inner)?) as u64;
+                if ty == DATA && size == u32::MAX as u64 {
+                    if let Some(ds64) = ds64_data_len {
+                        size = ds64;
+                    }
+                }
+                let next = (pos + 8)
+                    .checked_add(size)
+                    .and_then(|end| end.checked_add(size & 1))
+                    .ok_or(Error::InvalidChunk(ty))?;
+                (ty, size, next)
+            };
+            pos += header_len;
+            match ty {
+                DATA => {
+                    // unfinished files have zero or too large sizes
+                    let size = if size == 0 || pos + size > len {
+                        len - pos
+                    } else {
+                        size
+                    };
+                    data = Some((pos, size));
+                }
+                FMT if size <= 1024 => fmt = Some(parse_fmt(&read_vec(&mut inner, size)?)?),
+                DS64 if container == Container::Rf64 => {
+                    let body = read_vec(&mut inner, size.min(1024))?;
+                    ds64_data_len = field(&body, 8).map(u64::from_le_bytes);
+                }
+                _ => {}
+            }
+            pos = next;
+            inner.seek(SeekFrom::Start(pos.min(len)))?;
+        }
+
+        let (asbd, layout) = fmt.ok_or(Error::MissingChunk(FMT))?;
+        let (data_offset, data_len) = data.ok_or(Error::MissingChunk(DATA))?;
+        Ok(Self {
+            inner,
+            container,
+            asbd,
+            layout,
+            data_offset,
+            data_len,
+            pos: 0,
+        })
+    }
+
+    #[inline]
+    pub fn container(&self) -> Container {
+        self.container
+    }
+
+    #[inline]
+    pub fn asbd(&self) -> &StreamBasicDesc {
+        &self.asbd
+    }
+
+    /// Layout with channel bitmap from `WAVE_FORMAT_EXTENSIBLE` channel mask.
+    #[inline]
+    pub fn channel_layout(&self) -> Option<&ChannelLayoutBuf> {
+        self.layout.as_ref()
+    }
+
+    /// Size of audio data in bytes.
+    #[inline]
+    pub fn data_len(&self) -> u64 {
+        self.data_len
+    }
+
+    #[inline]
+    pub fn frames(&self) -> u64 {
+        self.data_len / self.asbd.bytes_per_frame as u64
+    }
+
+    /// Reads interleaved frames sequentially.
+    pub fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let n = (buf.len() as u64).min(self.data_len - self.pos) as usize;
+        if n == 0 {
+            return Ok(0);
+        }
+        self.inner
+            .seek(SeekFrom::Start(self.data_offset + self.pos))?;
+        self.inner.read_exact(&mut buf[..n])?;
+        self.pos += n as u64;
+        Ok(n)
+    }
+
+    /// Moves position of [`Self::read`] to `frame`.
+    pub fn seek_frame(&mut self, frame: u64) -> Result {
+        self.pos = frame
+            .saturating_mul(self.asbd.bytes_per_frame as u64)
+            .min(self.data_len);
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+/// WAVE file writer.
+pub struct Writer<W: Write + Seek> {
+    inner: W,
+    container: Container,
+    asbd: StreamBasicDesc,
+    mask: Option<u32>,
+    /// Offset of the data chunk header.
+    data_offset: Option<u64>,
+    data_len: u64,
+}
+
+impl<W: Write + Seek> Writer<W> {
+    pub fn new(inner: W, asbd: &StreamBasicDesc, container: Container) -> Result<Self> {
+        // validate early
+        fmt_body(asbd, None)?;
+        Ok(Self {
+            inner,
+            container,
+            asbd: *asbd,
+            mask: None,
+            data_offset: None,
+            data_len: 0,
+        })
+    }
+
+    #[inline]
+    pub fn asbd(&self) -> &StreamBasicDesc {
+        &self.asbd
+    }
+
+    #[inline]
+    pub fn container(&self) -> Container {
+        self.container
+    }
+
+    /// Sets channel mask of `WAVE_FORMAT_EXTENSIBLE` header.
+    ///
+    /// Only bitmap, mono and stereo layouts can be stored.
+    pub fn set_channel_layout(&mut self, val: &ChannelLayoutBuf) -> Result {
+        if self.data_offset.is_some() {
+            return Err(Error::HeaderWritten);
+        }
+        let mask = match val.tag {
+            ChannelLayoutTag::USE_CHANNEL_BITMAP => val.bitmap,
+            ChannelLayoutTag::MONO => ChannelBitmap::CENTER,
+            ChannelLayoutTag::STEREO => {
+                ChannelBitmap(ChannelBitmap::LEFT.0 | ChannelBitmap::RIGHT.0)
+            }
+            _ => return Err(Error::Unsupported),
+        };
+        if mask.0.count_ones() != self.asbd.channels_per_frame {
+            return Err(Error::Unsupported);
+        }
+        self.mask = Some(mask.0);
+        Ok(())
+    }
+
+    fn write_header(&mut self) -> Result {
+        if self.data_offset.is_some() {
+            return Ok(());
+        }
+        let fmt = fmt_body(&self.asbd, self.mask)?;
+        let w = &mut self.inner;
+        if self.container == Container::W64 {
+            w.write_all(&W64_RIFF)?;
+            w.write_all(&0u64.to_le_bytes())?;
+            w.write_all(&w64_guid(*b"wave"))?;
+            w.write_all(&w64_guid(FMT))?;
+            w.write_all(&(24 + fmt.len() as u64).to_le_bytes())?;
+            w.write_all(&fmt)?;
+            w.write_all(&[0u8; 8][..fmt.len().next_multiple_of(8) - fmt.len()])?;
+            self.data_offset = Some(w.stream_position()?);
+            w.write_all(&w64_guid(DATA))?;
+            w.write_all(&0u64.to_le_bytes())?;
+        } else {
+            // ds64 placeholder allows promotion to RF64
+            let (riff, ds64) = match self.container {
+                Container::Rf64 => (*b"RF64", DS64),
+                _ => (*b"RIFF", JUNK),
+            };
+            w.write_all(&riff)?;
+            w.write_all(&u32::MAX.to_le_bytes())?;
+            w.write_all(b"WAVE")?;
+            w.write_all(&ds64)?;
+            w.write_all(&DS64_LEN.to_le_bytes())?;
+            w.write_all(&[0u8; DS64_LEN as usize])?;
+            w.write_all(&FMT)?;
+            w.write_all(&(fmt.len() as u32).to_le_bytes())?;
+            w.write_all(&fmt)?;
+            self.data_offset = Some(w.stream_position()?);
+            w.write_all(&DATA)?;
+            w.write_all(&u32::MAX.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Writes interleaved frames.
+    pub fn write(&mut self, data: &[u8]) -> Result {
+        self.write_header()?;
+        self.inner.write_all(data)?;
+        self.data_len += data.len() as u64;
+        Ok(())
+    }
+
+    /// Pads data and writes chunk sizes.
+    pub fn finish(mut self) -> Result<W> {
+        self.write_header()?;
+        let data_offset = self.data_offset.unwrap();
+        let w = &mut self.inner;
+        if self.container == Container::W64 {
+            let pad = self.data_len.next_multiple_of(8) - self.data_len;
+            w.write_all(&[0u8; 8][..pad as usize])?;
+            let end = w.stream_position()?;
+            w.seek(SeekFrom::Start(16))?;
+            w.write_all(&end.to_le_bytes())?;
+            w.seek(SeekFrom::Start(data_offset + 16))?;
+            w.write_all(&(24 + self.data_len).to_le_bytes())?;
+            w.seek(SeekFrom::Start(end))?;
+        } else {
+            if self.data_len & 1 == 1 {
+                w.write_all(&[0])?;
+            }
+            let end = w.stream_position()?;
+            let riff_len = end - 8;
+            let rf64 = self.container == Container::Rf64 || riff_len > u32::MAX as u64;
+            if rf64 {
+                w.seek(SeekFrom::Start(0))?;
+                w.write_all(b"RF64")?;
+                w.write_all(&u32::MAX.to_le_bytes())?;
+                w.seek(SeekFrom::Start(12))?;
+                w.write_all(&DS64)?;
+                w.write_all(&DS64_LEN.to_le_bytes())?;
+                w.write_all(&riff_len.to_le_bytes())?;
+                w.write_all(&self.data_len.to_le_bytes())?;
+                let frames = self.data_len / self.asbd.bytes_per_frame as u64;
+                w.write_all(&frames.to_le_bytes())?;
+                w.write_all(&0u32.to_le_bytes())?;
+                w.seek(SeekFrom::Start(data_offset + 4))?;
+                w.write_all(&u32::MAX.to_le_bytes())?;
+            } else {
+                w.seek(SeekFrom::Start(4))?;
+                w.write_all(&(riff_len as u32).to_le_bytes())?;
+                w.seek(SeekFrom::Start(data_offset + 4))?;
+                w.write_all(&(self.data_len as u32).to_le_bytes())?;
+            }
+            w.seek(SeekFrom::Start(end))?;
+        }
+        w.flush()?;
+        Ok(self.inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::cat::audio::{
+        self,
+        container::{wav, Error},
+    };
+
+    fn int_asbd(channels: u32, bits: u32, bytes: u32) -> audio::StreamBasicDesc {
+        let mut flags = audio::FormatFlags::IS_SIGNED_INTEGER.0;
+        flags |= if bits == bytes * 8 {
+            audio::FormatFlags::IS_PACKED.0
+        } else {
+            audio::FormatFlags::IS_ALIGNED_HIGH.0
+        };
+        audio::StreamBasicDesc {
+            sample_rate: 48_000.0,
+            format: audio::Format::LINEAR_PCM,
+            format_flags: audio::FormatFlags(flags),
+            bytes_per_packet: bytes * channels,
+            frames_per_packet: 1,
+            bytes_per_frame: bytes * channels,
+            channels_per_frame: channels,
+            bits_per_channel: bits,
+            reserved: 0,
+        }
+    }
+
+    #[test]
+    fn canonical() {
+        // 8kHz mono 16 bit with 2 frames and unknown chunk
+        let mut bytes = b"RIFF\x34\x00\x00\x00WAVEfmt \x10\x00\x00\x00".to_vec();
+        bytes
+            .extend_from_slice(b"\x01\x00\x01\x00\x40\x1f\x00\x00\x80\x3e\x00\x00\x02\x00\x10\x00");
+        bytes.extend_from_slice(b"LIST\x04\x00\x00\x00INFO");
+        bytes.extend_from_slice(b"data\x04\x00\x00\x00\xff\x7f\x00\x80");
+
+        let mut reader = wav::Reader::new(Cursor::new(bytes)).unwrap();
+        assert_eq!(reader.container(), wav::Container::Wave);
+        let mut asbd = int_asbd(1, 16, 2);
+        asbd.sample_rate = 8_000.0;
+        assert_eq!(reader.asbd(), &asbd);
+        assert!(reader.channel_layout().is_none());
+        assert_eq!(reader.frames(), 2);
+        let mut buf = [0u8; 8];
+        assert_eq!(reader.read(&mut buf).unwrap(), 4);
+        assert_eq!(&buf[..4], b"\xff\x7f\x00\x80");
+
+        assert!(wav::Reader::new(Cursor::new(b"RIFX\x00\x00\x00\x00WAVE".to_vec())).is_err());
+
+        // ds64 data size can't overflow chunk end
+        let mut bytes = b"RF64\xff\xff\xff\xffWAVEds64\x1c\x00\x00\x00".to_vec();
+        bytes.extend_from_slice(&[0; 8]);
+        bytes.extend_from_slice(&u64::MAX.to_le_bytes());
+        bytes.extend_from_slice(&[0; 12]);
+        bytes.extend_from_slice(b"data\xff\xff\xff\xff\x00\x00");
+        assert!(matches!(
+            wav::Reader::new(Cursor::new(bytes)),
+            Err(Error::InvalidChunk(_))
+        ));
+    }
+
+    #[test]
+    fn round_trip() {
+        let data: Vec<u8> = (0..6 * 3 * 5).map(|i| i as u8).collect();
+        let asbd = int_asbd(6, 24, 3);
+        let bitmap = audio::ChannelBitmap(0x3f);
+        for container in [
+            wav::Container::Wave,
+            wav::Container::Rf64,
+            wav::Container::W64,
+        ] {
+            let mut writer = wav::Writer::new(Cursor::new(Vec::new()), &asbd, container).unwrap();
+            writer
+                .set_channel_layout(&audio::ChannelLayoutBuf::with_bitmap(bitmap))
+                .unwrap();
+            writer.write(&data[..45]).unwrap();
+            writer.write(&data[45..]).unwrap();
+            let bytes = writer.finish().unwrap().into_inner();
+            if container == wav::Container::W64 {
+                assert_eq!(bytes.len() % 8, 0);
+            }
+
+            let mut reader = wav::Reader::new(Cursor::new(bytes)).unwrap();
+            assert_eq!(reader.container(), container);
+            assert_eq!(reader.asbd(), &asbd);
+            assert_eq!(reader.channel_layout().unwrap().bitmap, bitmap);
+            assert_eq!(reader.channel_layout().unwrap().channels_num(), 6);
+            assert_eq!(reader.frames(), 5);
+            reader.seek_frame(4).unwrap();
+            let mut buf = [0u8; 32];
+            assert_eq!(reader.read(&mut buf).unwrap(), 18);
+            assert_eq!(&buf[..18], &data[72..]);
+        }
+    }
+
+    #[test]
+    fn formats() {
+        // 24 bits in 32 bit container
+        let asbd = int_asbd(2, 24, 4);
+        let mut writer =
+            wav::Writer::new(Cursor::new(Vec::new()), &asbd, wav::Container::Wave).unwrap();
+        writer.write(&[0; 8]).unwrap();
+        let bytes = writer.finish().unwrap().into_inner();
+        // RIFF size, fmt tag extensible after JUNK
+        assert_eq!(&bytes[4..8], &(bytes.len() as u32 - 8).to_le_bytes());
+        assert_eq!(&bytes[12..16], b"JUNK");
+        assert_eq!(&bytes[48..56], b"fmt \x28\x00\x00\x00");
+        assert_eq!(&bytes[56..58], b"\xfe\xff");
+        let reader = wav::Reader::new(Cursor::new(bytes)).unwrap();
+        assert_eq!(reader.asbd(), &asbd);
+
+        let asbd = audio::StreamBasicDesc::common_f32(44_100.0, 2, true);
+        let mut writer =
+            wav::Writer::new(Cursor::new(Vec::new()), &asbd, wav::Container::Wave).unwrap();
+        writer.write(&[0; 8]).unwrap();
+        let bytes = writer.finish().unwrap().into_inner();
+        assert_eq!(&bytes[56..58], b"\x03\x00");
+        let reader = wav::Reader::new(Cursor::new(bytes)).unwrap();
+        assert_eq!(reader.asbd(), &asbd);
+
+        // unsigned 8 bit
+        let mut asbd = int_asbd(1, 8, 1);
+        assert!(wav::Writer::new(Cursor::new(Vec::new()), &asbd, wav::Container::Wave).is_err());
+        asbd.format_flags = audio::FormatFlags::IS_PACKED;
+        assert!(wav::Writer::new(Cursor::new(Vec::new()), &asbd, wav::Container::Wave).is_ok());
+
+        // planar and big endian are not supported
+        let asbd = audio::StreamBasicDesc::common_f32(44_100.0, 2, false);
+        assert!(wav::Writer::new(Cursor::new(Vec::new()), &asbd, wav::Container::W64).is_err());
+    }
+}
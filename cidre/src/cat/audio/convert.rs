@@ -0,0 +1,753 @@
+use crate::{cat::audio, os};
+
+use super::{BufList, Bufs, ChannelLabel, Format, FormatFlags, Sample, StreamBasicDesc};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum SampleFmt {
+    U8,
+    I8,
+    I16,
+    /// packed 3 bytes
+    I24,
+    I32,
+    F32,
+    F64,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Layout {
+    fmt: SampleFmt,
+    big_endian: bool,
+    channels: usize,
+    interleaved: bool,
+    bytes_per_sample: usize,
+    bytes_per_frame: usize,
+}
+
+impl Layout {
+    fn with_asbd(asbd: &StreamBasicDesc) -> os::Result<Self> {
+        if asbd.format != Format::LINEAR_PCM
+            || asbd.frames_per_packet != 1
+            || asbd.channels_per_frame == 0
+            || asbd.channels_per_frame as usize > PcmConverter::MAX_CHANNELS
+        {
+            return Err(audio::err::PARAM);
+        }
+        let flags = asbd.format_flags;
+        let channels = asbd.channels_per_frame as usize;
+        let interleaved = asbd.is_interleaved();
+        let bytes_per_frame = asbd.bytes_per_frame as usize;
+        let bytes_per_sample = bytes_per_frame / asbd.interleaved_channels_num() as usize;
+        let signed = flags.contains(FormatFlags::IS_SIGNED_INTEGER);
+        let fmt = match (flags.contains(FormatFlags::IS_FLOAT), asbd.bits_per_channel) {
+            (true, 32) => SampleFmt::F32,
+            (true, 64) => SampleFmt::F64,
+            (false, 8) if signed => SampleFmt::I8,
+            (false, 8) => SampleFmt::U8,
+            // unsigned samples are only common at 8 bits
+            (false, _) if !signed => return Err(audio::err::UNIMPLEMENTED),
+            (false, 16) => SampleFmt::I16,
+            (false, 24) if bytes_per_sample == 3 => SampleFmt::I24,
+            // 24 bits in the high bytes of 32 bit container are just i32
+            (false, 24)
+                if bytes_per_sample == 4 && flags.contains(FormatFlags::IS_ALIGNED_HIGH) =>
+            {
+                SampleFmt::I32
+            }
+            (false, 32) => SampleFmt::I32,
+            _ => return Err(audio::err::UNIMPLEMENTED),
+        };
+        let expected = match fmt {
+            SampleFmt::U8 | SampleFmt::I8 => 1,
+            SampleFmt::I16 => 2,
+            SampleFmt::I24 => 3,
+            SampleFmt::I32 | SampleFmt::F32 => 4,
+            SampleFmt::F64 => 8,
+        };
+        if bytes_per_sample != expected || bytes_per_frame != asbd.bytes_per_packet as usize {
+            return Err(audio::err::PARAM);
+        }
+        Ok(Self {
+            fmt,
+            big_endian: flags.contains(FormatFlags::IS_BIG_ENDIAN),
+            channels,
+            interleaved,
+            bytes_per_sample,
+            bytes_per_frame,
+        })
+    }
+
+    fn buf_count(&self) -> usize {
+        if self.interleaved {
+            1
+        } else {
+            self.channels
+        }
+    }
+
+    fn is_int(&self) -> bool {
+        !matches!(self.fmt, SampleFmt::F32 | SampleFmt::F64)
+    }
+
+    fn bits(&self) -> u32 {
+        self.bytes_per_sample as u32 * 8
+    }
+
+    #[inline]
+    fn offset(&self, ch: usize, frame: usize) -> (usize, usize) {
+        if self.interleaved {
+            (0, (frame * self.channels + ch) * self.bytes_per_sample)
+        } else {
+            (ch, frame * self.bytes_per_sample)
+        }
+    }
+
+    #[inline]
+    unsafe fn read(&self, ptr: *const u8) -> f64 {
+        let mut b = [0u8; 8];
+        let n = self.bytes_per_sample;
+        std::ptr::copy_nonoverlapping(ptr, b.as_mut_ptr(), n);
+        if self.big_endian {
+            b[..n].reverse();
+        }
+        match self.fmt {
+            SampleFmt::U8 => (b[0] as f64 - 128.0) / 128.0,
+            SampleFmt::I8 => b[0] as i8 as f64 / 128.0,
+            SampleFmt::I16 => i16::from_le_bytes([b[0], b[1]]) as f64 / 32_768.0,
+            SampleFmt::I24 => {
+                // sign extend via shift
+                (i32::from_le_bytes([0, b[0], b[1], b[2]]) >> 8) as f64 / 8_388_608.0
+            }
+            SampleFmt::I32 => i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64 / 2_147_483_648.0,
+            SampleFmt::F32 => f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+            SampleFmt::F64 => f64::from_le_bytes(b),
+        }
+    }
+
+    #[inline]
+    unsafe fn write(&self, ptr: *mut u8, val: f64, dither: f64) {
+        #[inline]
+        fn quantize(val: f64, dither: f64, scale: f64) -> f64 {
+            (val * scale + dither)
+                .round_ties_even()
+                .clamp(-scale, scale - 1.0)
+        }
+
+        let mut b = [0u8; 8];
+        let n = self.bytes_per_sample;
+        match self.fmt {
+            SampleFmt::U8 => b[0] = (quantize(val, dither, 128.0) as i32 + 128) as u8,
+            SampleFmt::I8 => b[0] = quantize(val, dither, 128.0) as i8 as u8,
+            SampleFmt::I16 => {
+                b[..2].copy_from_slice(&(quantize(val, dither, 32_768.0) as i16).to_le_bytes())
+            }
+            SampleFmt::I24 => {
+                let v = quantize(val, dither, 8_388_608.0) as i32;
+                b[..3].copy_from_slice(&v.to_le_bytes()[..3]);
+            }
+            SampleFmt::I32 => b[..4]
+                .copy_from_slice(&(quantize(val, dither, 2_147_483_648.0) as i32).to_le_bytes()),
+            SampleFmt::F32 => b[..4].copy_from_slice(&(val as f32).to_le_bytes()),
+            SampleFmt::F64 => b.copy_from_slice(&val.to_le_bytes()),
+        }
+        if self.big_endian {
+            b[..n].reverse();
+        }
+        std::ptr::copy_nonoverlapping(b.as_ptr(), ptr, n);
+    }
+}
+
+/// Dither applied when reducing bit depth to integer samples.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+pub enum Dither {
+    #[default]
+    None,
+    /// Triangular probability density function noise of ±1 LSB.
+    Tpdf,
+}
+
+/// Channel gains, `dst_channels` rows of `src_channels` columns.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MixMatrix {
+    src_channels: usize,
+    dst_channels: usize,
+    gains: Vec<f32>,
+}
+
+impl MixMatrix {
+    /// -3 dB
+    const HALF_POWER: f32 = std::f32::consts::FRAC_1_SQRT_2;
+
+    /// Silent matrix
+    pub fn new(src_channels: usize, dst_channels: usize) -> Self {
+        Self {
+            src_channels,
+            dst_channels,
+            gains: vec![0.0; src_channels * dst_channels],
+        }
+    }
+
+    pub fn identity(channels: usize) -> Self {
+        let mut res = Self::new(channels, channels);
+        for i in 0..channels {
+            res.set(i, i, 1.0);
+        }
+        res
+    }
+
+    /// Mono is copied to the first two channels, downmix to mono averages all channels,
+    /// other combinations map channels one to one.
+    pub fn default_for(src_channels: usize, dst_channels: usize) -> Self {
+        let mut res = Self::new(src_channels, dst_channels);
+        if src_channels == 1 {
+            for d in 0..dst_channels.min(2) {
+                res.set(d, 0, 1.0);
+            }
+        } else if dst_channels == 1 {
+            let g = 1.0 / src_channels as f32;
+            for s in 0..src_channels {
+                res.set(0, s, g);
+            }
+        } else {
+            for i in 0..src_channels.min(dst_channels) {
+                res.set(i, i, 1.0);
+            }
+        }
+        res
+    }
+
+    /// Builds matrix from channel labels of source and destination layouts.
+    ///
+    /// Channels with the same label are copied, missing center and surrounds are folded
+    /// into left and right at -3 dB, LFE is dropped when there is no LFE in destination.
+    pub fn with_labels(src: &[ChannelLabel], dst: &[ChannelLabel]) -> Self {
+        let mut res = Self::new(src.len(), dst.len());
+        let find = |label: ChannelLabel| dst.iter().position(|l| *l == label);
+        let left = find(ChannelLabel::LEFT);
+        let right = find(ChannelLabel::RIGHT);
+        let mono = find(ChannelLabel::MONO).or(find(ChannelLabel::CENTER));
+
+        for (s, label) in src.iter().enumerate() {
+            if let Some(d) = find(*label) {
+                res.set(d, s, 1.0);
+                continue;
+            }
+            let (l, r) = match *label {
+                ChannelLabel::MONO => (1.0, 1.0),
+                ChannelLabel::CENTER => (Self::HALF_POWER, Self::HALF_POWER),
+                ChannelLabel::LEFT => (1.0, 0.0),
+                ChannelLabel::RIGHT => (0.0, 1.0),
+                ChannelLabel::LEFT_SURROUND
+                | ChannelLabel::LEFT_SURROUND_DIRECT
+                | ChannelLabel::REAR_SURROUND_LEFT
+                | ChannelLabel::LEFT_CENTER
+                | ChannelLabel::LEFT_WIDE => (Self::HALF_POWER, 0.0),
+                ChannelLabel::RIGHT_SURROUND
+                | ChannelLabel::RIGHT_SURROUND_DIRECT
+                | ChannelLabel::REAR_SURROUND_RIGHT
+                | ChannelLabel::RIGHT_CENTER
+                | ChannelLabel::RIGHT_WIDE => (0.0, Self::HALF_POWER),
+                ChannelLabel::CENTER_SURROUND => (0.5, 0.5),
+                _ => (0.0, 0.0),
+            };
+            match (left, right, mono) {
+                (Some(ld), Some(rd), _) => {
+                    res.set(ld, s, l);
+                    res.set(rd, s, r);
+                }
+                (_, _, Some(md)) => res.set(md, s, (l + r) * 0.5),
+                _ => {}
+            }
+        }
+        res
+    }
+
+    #[inline]
+    pub fn src_channels(&self) -> usize {
+        self.src_channels
+    }
+
+    #[inline]
+    pub fn dst_channels(&self) -> usize {
+        self.dst_channels
+    }
+
+    #[inline]
+    pub fn gain(&self, dst: usize, src: usize) -> f32 {
+        self.gains[dst * self.src_channels + src]
+    }
+
+    #[inline]
+    pub fn set(&mut self, dst: usize, src: usize, gain: f32) {
+        self.gains[dst * self.src_channels + src] = gain;
+    }
+
+    fn is_identity(&self) -> bool {
+        self.src_channels == self.dst_channels
+            && (0..self.dst_channels).all(|d| {
+                (0..self.src_channels).all(|s| self.gain(d, s) == if d == s { 1.0 } else { 0.0 })
+            })
+    }
+}
+
+/// LPCM converter without sample rate conversion.
+///
+/// Handles endianness, bit depth, interleaving and channel mixing.
+/// Conversion doesn't allocate, so it can be used in real-time callbacks.
+///
+/// ```
+/// use cidre::at::audio;
+///
+/// let src = audio::StreamBasicDesc::common_f32(48_000.0, 1, true);
+/// let dst = audio::StreamBasicDesc::common_f32(48_000.0, 2, false);
+/// let mut conv = audio::PcmConverter::new(&src, &dst).unwrap();
+///
+/// let mut out = audio::Bufs::new(&dst, 2).unwrap();
+/// let mut inp = audio::Bufs::new(&src, 2).unwrap();
+/// inp.interleaved_mut::<f32>().unwrap().copy_from_slice(&[0.5, -0.5]);
+/// conv.convert_bufs(&inp, &mut out).unwrap();
+/// assert_eq!(out.planar::<f32>(1).unwrap(), &[0.5, -0.5]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct PcmConverter {
+    src_asbd: StreamBasicDesc,
+    dst_asbd: StreamBasicDesc,
+    src: Layout,
+    dst: Layout,
+    matrix: Option<MixMatrix>,
+    dither: Dither,
+    seed: u32,
+}
+
+impl PcmConverter {
+    pub const MAX_CHANNELS: usize = 64;
+
+    /// Sample rates must be equal.
+    pub fn new(src: &StreamBasicDesc, dst: &StreamBasicDesc) -> os::Result<Self> {
+        if src.sample_rate != dst.sample_rate {
+            return Err(audio::err::UNIMPLEMENTED);
+        }
+        let src_layout = Layout::with_asbd(src)?;
+        let dst_layout = Layout::with_asbd(dst)?;
+        let matrix = if src_layout.channels == dst_layout.channels {
+            None
+        } else {
+            Some(MixMatrix::default_for(
+                src_layout.channels,
+                dst_layout.channels,
+            ))
+        };
+        Ok(Self {
+            src_asbd: *src,
+            dst_asbd: *dst,
+            src: src_layout,
+            dst: dst_layout,
+            matrix,
+            dither: Dither::None,
+            seed: 0x9E37_79B9,
+        })
+    }
+
+    #[inline]
+    pub fn src_asbd(&self) -> &StreamBasicDesc {
+        &self.src_asbd
+    }
+
+    #[inline]
+    pub fn dst_asbd(&self) -> &StreamBasicDesc {
+        &self.dst_asbd
+    }
+
+    #[inline]
+    pub fn matrix(&self) -> Option<&MixMatrix> {
+        self.matrix.as_ref()
+    }
+
+    /// Matrix must have source and destination channel counts.
+    pub fn set_matrix(&mut self, val: MixMatrix) -> os::Result {
+        if val.src_channels != self.src.channels || val.dst_channels != self.dst.channels {
+            return Err(audio::err::PARAM);
+        }
+        self.matrix = if val.is_identity() { None } else { Some(val) };
+        Ok(())
+    }
+
+    #[inline]
+    pub fn dither(&self) -> Dither {
+        self.dither
+    }
+
+    #[inline]
+    pub fn set_dither(&mut self, val: Dither) {
+        self.dither = val;
+    }
+
+    /// Dither is applied only if it makes sense for the formats.
+    fn needs_dither(&self) -> bool {
+        self.dither == Dither::Tpdf
+            && self.dst.is_int()
+            && (!self.src.is_int() || self.src.bits() > self.dst.bits() || self.matrix.is_some())
+    }
+
+    #[inline]
+    fn next_rand(&mut self) -> f64 {
+        // xorshift32
+        let mut x = self.seed;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.seed = x;
+        x as f64 / 4_294_967_296.0
+    }
+
+    /// # Safety
+    ///
+    /// Pointers must be valid for `frames` of corresponding layout.
+    unsafe fn convert_ptrs(&mut self, src: &[*const u8], dst: &[*mut u8], frames: usize) {
+        let mut inp = [0f64; Self::MAX_CHANNELS];
+        let mut out = [0f64; Self::MAX_CHANNELS];
+        let dither = self.needs_dither();
+        let src_l = self.src;
+        let dst_l = self.dst;
+
+        for f in 0..frames {
+            for (ch, v) in inp.iter_mut().enumerate().take(src_l.channels) {
+                let (b, off) = src_l.offset(ch, f);
+                *v = src_l.read(src[b].add(off));
+            }
+            match &self.matrix {
+                None => out[..dst_l.channels].copy_from_slice(&inp[..dst_l.channels]),
+                Some(m) => {
+                    for (d, v) in out.iter_mut().enumerate().take(dst_l.channels) {
+                        let row = &m.gains[d * m.src_channels..(d + 1) * m.src_channels];
+                        *v = row.iter().zip(inp.iter()).map(|(g, s)| *g as f64 * s).sum();
+                    }
+                }
+            }
+            for (ch, v) in out.iter().enumerate().take(dst_l.channels) {
+                let d = if dither {
+                    self.next_rand() - self.next_rand()
+                } else {
+                    0.0
+                };
+                let (b, off) = dst_l.offset(ch, f);
+                dst_l.write(dst[b].add(off), *v, d);
+            }
+        }
+    }
+
+    /// Converts `frames` from `src` into `dst` and sets `data_bytes_size` of `dst` buffers.
+    pub fn convert_list<const N: usize, const M: usize>(
+        &mut self,
+        src: &BufList<N>,
+        dst: &mut BufList<M>,
+        frames: u32,
+    ) -> os::Result {
+        let frames = frames as usize;
+        let src_size = frames * self.src.bytes_per_frame;
+        let dst_size = frames * self.dst.bytes_per_frame;
+        if N != self.src.buf_count()
+            || M != self.dst.buf_count()
+            || src
+                .as_slice()
+                .iter()
+                .any(|b| b.data.is_null() || (b.data_bytes_size as usize) < src_size)
+            || dst
+                .as_slice()
+                .iter()
+                .any(|b| b.data.is_null() || (b.data_bytes_size as usize) < dst_size)
+        {
+            return Err(audio::err::PARAM);
+        }
+        let mut src_ptrs = [std::ptr::null::<u8>(); Self::MAX_CHANNELS];
+        for (p, b) in src_ptrs.iter_mut().zip(src.as_slice()) {
+            *p = b.data;
+        }
+        let mut dst_ptrs = [std::ptr::null_mut::<u8>(); Self::MAX_CHANNELS];
+        for (p, b) in dst_ptrs.iter_mut().zip(dst.as_slice()) {
+            *p = b.data;
+        }
+        unsafe { self.convert_ptrs(&src_ptrs[..N], &dst_ptrs[..M], frames) };
+        for b in dst.as_mut_slice() {
+            b.data_bytes_size = dst_size as u32;
+        }
+        Ok(())
+    }
+
+    /// Converts all valid frames of `src`. Formats of the buffers must match the converter.
+    pub fn convert_bufs(&mut self, src: &Bufs, dst: &mut Bufs) -> os::Result {
+        if src.asbd() != &self.src_asbd || dst.asbd() != &self.dst_asbd {
+            return Err(audio::err::PARAM);
+        }
+        let frames = src.frames();
+        dst.set_frames(frames)?;
+
+        let mut src_ptrs = [std::ptr::null::<u8>(); Self::MAX_CHANNELS];
+        for (i, p) in src_ptrs.iter_mut().enumerate().take(src.buf_count()) {
+            *p = src.bytes(i).unwrap().as_ptr();
+        }
+        let mut dst_ptrs = [std::ptr::null_mut::<u8>(); Self::MAX_CHANNELS];
+        for (i, p) in dst_ptrs.iter_mut().enumerate().take(dst.buf_count()) {
+            *p = dst.bytes_mut(i).unwrap().as_mut_ptr();
+        }
+        unsafe {
+            self.convert_ptrs(
+                &src_ptrs[..src.buf_count()],
+                &dst_ptrs[..dst.buf_count()],
+                frames as usize,
+            )
+        };
+        Ok(())
+    }
+
+    /// Converts interleaved slices, returns number of converted frames.
+    pub fn convert_interleaved<S: Sample, D: Sample>(
+        &mut self,
+        src: &[S],
+        dst: &mut [D],
+    ) -> os::Result<usize> {
+        if !self.src.interleaved
+            || !self.dst.interleaved
+            || !S::matches(&self.src_asbd)
+            || !D::matches(&self.dst_asbd)
+        {
+            return Err(audio::err::PARAM);
+        }
+        let frames = (src.len() / self.src.channels).min(dst.len() / self.dst.channels);
+        unsafe {
+            self.convert_ptrs(
+                &[src.as_ptr() as *const u8],
+                &[dst.as_mut_ptr() as *mut u8],
+                frames,
+            )
+        };
+        Ok(frames)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cat::audio;
+
+    fn asbd(flags: audio::FormatFlags, bits: u32, bytes: u32, ch: u32) -> audio::StreamBasicDesc {
+        let interleaved = !flags.contains(audio::FormatFlags::IS_NON_INTERLEAVED);
+        let bytes_per_frame = if interleaved { bytes * ch } else { bytes };
+        audio::StreamBasicDesc {
+            sample_rate: 48_000.0,
+            format: audio::Format::LINEAR_PCM,
+            format_flags: flags,
+            bytes_per_packet: bytes_per_frame,
+            frames_per_packet: 1,
+            bytes_per_frame,
+            channels_per_frame: ch,
+            bits_per_channel: bits,
+            reserved: 0,
+        }
+    }
+
+    const I: audio::FormatFlags = audio::FormatFlags(
+        audio::FormatFlags::IS_SIGNED_INTEGER.0 | audio::FormatFlags::IS_PACKED.0,
+    );
+    const I_BE: audio::FormatFlags = audio::FormatFlags(I.0 | audio::FormatFlags::IS_BIG_ENDIAN.0);
+
+    #[test]
+    fn i16_to_f32_planar() {
+        let src = asbd(I, 16, 2, 2);
+        let dst = audio::StreamBasicDesc::common_f32(48_000.0, 2, false);
+        let mut conv = audio::PcmConverter::new(&src, &dst).unwrap();
+
+        let mut inp = audio::Bufs::new(&src, 2).unwrap();
+        inp.interleaved_mut::<i16>()
+            .unwrap()
+            .copy_from_slice(&[0, 16384, -32768, 32767]);
+        let mut out = audio::Bufs::new(&dst, 2).unwrap();
+        conv.convert_bufs(&inp, &mut out).unwrap();
+        assert_eq!(out.planar::<f32>(0).unwrap(), &[0.0, -1.0]);
+        assert_eq!(out.planar::<f32>(1).unwrap(), &[0.5, 32767.0 / 32768.0]);
+
+        // and back
+        let mut back = audio::PcmConverter::new(&dst, &src).unwrap();
+        let mut res = audio::Bufs::new(&src, 2).unwrap();
+        back.convert_bufs(&out, &mut res).unwrap();
+        assert_eq!(
+            res.interleaved::<i16>().unwrap(),
+            &[0, 16384, -32768, 32767]
+        );
+    }
+
+    #[test]
+    fn clipping_and_rounding() {
+        let src = audio::StreamBasicDesc::common_f32(48_000.0, 1, true);
+        let dst = asbd(I, 16, 2, 1);
+        let mut conv = audio::PcmConverter::new(&src, &dst).unwrap();
+        let mut out = [0i16; 5];
+        let n = conv
+            .convert_interleaved(&[1.5f32, -2.0, 0.5, 1.0 / 65536.0, -1.0], &mut out)
+            .unwrap();
+        assert_eq!(n, 5);
+        assert_eq!(out, [32767, -32768, 16384, 0, -32768]);
+
+        conv.set_dither(audio::Dither::Tpdf);
+        let inp: Vec<f32> = (0..1000).map(|i| (i as f32 / 1000.0) - 0.5).collect();
+        let mut plain = vec![0i16; 1000];
+        let mut dithered = vec![0i16; 1000];
+        let mut exact = audio::PcmConverter::new(&src, &dst).unwrap();
+        exact.convert_interleaved(&inp, &mut plain).unwrap();
+        conv.convert_interleaved(&inp, &mut dithered).unwrap();
+        assert!(plain
+            .iter()
+            .zip(dithered.iter())
+            .all(|(a, b)| (*a as i32 - *b as i32).abs() <= 1));
+        assert_ne!(plain, dithered);
+    }
+
+    #[test]
+    fn bit_depth_and_endianness() {
+        // 24 bit big endian packed to 32 bit little endian
+        let src = asbd(I_BE, 24, 3, 1);
+        let dst = asbd(I, 32, 4, 1);
+        let mut conv = audio::PcmConverter::new(&src, &dst).unwrap();
+        let mut inp = audio::Bufs::new(&src, 3).unwrap();
+        inp.bytes_mut(0)
+            .unwrap()
+            .copy_from_slice(&[0x7f, 0xff, 0xff, 0x80, 0x00, 0x00, 0x00, 0x00, 0x01]);
+        let mut out = audio::Bufs::new(&dst, 3).unwrap();
+        conv.convert_bufs(&inp, &mut out).unwrap();
+        assert_eq!(
+            out.interleaved::<i32>().unwrap(),
+            &[0x7fff_ff00, i32::MIN, 0x100]
+        );
+
+        // unsigned 8 bit to 16 bit
+        let src = asbd(audio::FormatFlags::IS_PACKED, 8, 1, 1);
+        let dst = asbd(I, 16, 2, 1);
+        let mut conv = audio::PcmConverter::new(&src, &dst).unwrap();
+        let mut out = [0i16; 3];
+        conv.convert_interleaved(&[0u8, 128, 255], &mut out)
+            .unwrap();
+        assert_eq!(out, [-32768, 0, 32512]);
+
+        // big endian 16 to native 16
+        let src = asbd(I_BE, 16, 2, 1);
+        let mut conv = audio::PcmConverter::new(&src, &dst).unwrap();
+        let mut inp = audio::Bufs::new(&src, 1).unwrap();
+        inp.bytes_mut(0).unwrap().copy_from_slice(&[0x12, 0x34]);
+        let mut out = audio::Bufs::new(&dst, 1).unwrap();
+        conv.convert_bufs(&inp, &mut out).unwrap();
+        assert_eq!(out.interleaved::<i16>().unwrap(), &[0x1234]);
+
+        let mut src = audio::StreamBasicDesc::common_f32(48_000.0, 1, true);
+        src.sample_rate = 44_100.0;
+        assert!(audio::PcmConverter::new(&src, &dst).is_err());
+
+        // unsigned samples over 8 bits
+        for (bits, bytes) in [(16, 2), (24, 3), (32, 4)] {
+            let src = asbd(audio::FormatFlags::IS_PACKED, bits, bytes, 1);
+            assert_eq!(
+                audio::PcmConverter::new(&src, &dst).err(),
+                Some(audio::err::UNIMPLEMENTED)
+            );
+        }
+    }
+
+    #[test]
+    fn mixing() {
+        let stereo = audio::StreamBasicDesc::common_f32(48_000.0, 2, true);
+        let mono = audio::StreamBasicDesc::common_f32(48_000.0, 1, true);
+
+        let mut down = audio::PcmConverter::new(&stereo, &mono).unwrap();
+        let mut out = [0f32; 2];
+        down.convert_interleaved(&[1.0f32, 0.0, 0.5, 0.5], &mut out)
+            .unwrap();
+        assert_eq!(out, [0.5, 0.5]);
+
+        let mut up = audio::PcmConverter::new(&mono, &stereo).unwrap();
+        let mut out = [0f32; 4];
+        up.convert_interleaved(&[0.25f32, -1.0], &mut out).unwrap();
+        assert_eq!(out, [0.25, 0.25, -1.0, -1.0]);
+
+        let l = audio::ChannelLabel::LEFT;
+        let r = audio::ChannelLabel::RIGHT;
+        let c = audio::ChannelLabel::CENTER;
+        let src = [
+            l,
+            r,
+            c,
+            audio::ChannelLabel::LFE_SCREEN,
+            audio::ChannelLabel::LEFT_SURROUND,
+            audio::ChannelLabel::RIGHT_SURROUND,
+        ];
+        let m = audio::MixMatrix::with_labels(&src, &[l, r]);
+        let h = std::f32::consts::FRAC_1_SQRT_2;
+        assert_eq!(m.gain(0, 0), 1.0);
+        assert_eq!(m.gain(0, 1), 0.0);
+        assert_eq!(m.gain(0, 2), h);
+        assert_eq!(m.gain(1, 2), h);
+        assert_eq!(m.gain(0, 3), 0.0);
+        assert_eq!(m.gain(0, 4), h);
+        assert_eq!(m.gain(1, 5), h);
+
+        let surround = asbd(audio::FormatFlags::NATIVE_FLOAT_PACKED, 32, 4, 6);
+        let mut conv = audio::PcmConverter::new(&surround, &stereo).unwrap();
+        assert!(conv.set_matrix(audio::MixMatrix::new(2, 2)).is_err());
+        conv.set_matrix(m).unwrap();
+        let mut out = [0f32; 2];
+        conv.convert_interleaved(&[0.0f32, 0.0, 1.0, 1.0, 0.0, 0.0], &mut out)
+            .unwrap();
+        assert_eq!(out, [h, h]);
+    }
+
+    #[cfg(feature = "at")]
+    #[test]
+    fn matches_audio_converter() {
+        use crate::at;
+
+        let src = asbd(I, 16, 2, 2);
+        let dst = audio::StreamBasicDesc::common_f32(48_000.0, 2, false);
+        let frames = 512u32;
+
+        let mut inp = audio::Bufs::new(&src, frames).unwrap();
+        for (i, s) in inp.interleaved_mut::<i16>().unwrap().iter_mut().enumerate() {
+            *s = (i as i32 * 7919 % 65536 - 32768) as i16;
+        }
+
+        let mut expected = audio::Bufs::new(&dst, frames).unwrap();
+        let apple = at::AudioConverterRef::with_formats(&src, &dst).unwrap();
+        apple
+            .convert_complex_buf(
+                frames,
+                inp.as_list::<1>().unwrap(),
+                expected.as_list_mut::<2>().unwrap(),
+            )
+            .unwrap();
+
+        let mut out = audio::Bufs::new(&dst, frames).unwrap();
+        let mut conv = audio::PcmConverter::new(&src, &dst).unwrap();
+        conv.convert_bufs(&inp, &mut out).unwrap();
+        for ch in 0..2 {
+            assert_eq!(out.planar::<f32>(ch), expected.planar::<f32>(ch));
+        }
+
+        // and back with rounding differences of 1 lsb at most
+        let apple = at::AudioConverterRef::with_formats(&dst, &src).unwrap();
+        let mut expected_back = audio::Bufs::new(&src, frames).unwrap();
+        apple
+            .convert_complex_buf(
+                frames,
+                out.as_list::<2>().unwrap(),
+                expected_back.as_list_mut::<1>().unwrap(),
+            )
+            .unwrap();
+        let mut back = audio::Bufs::new(&src, frames).unwrap();
+        audio::PcmConverter::new(&dst, &src)
+            .unwrap()
+            .convert_bufs(&out, &mut back)
+            .unwrap();
+        let a = back.interleaved::<i16>().unwrap();
+        let b = expected_back.interleaved::<i16>().unwrap();
+        assert!(a
+            .iter()
+            .zip(b)
+            .all(|(a, b)| (*a as i32 - *b as i32).abs() <= 1));
+    }
+}
@@ -0,0 +1,860 @@
+use std::cell::UnsafeCell;
+
+#[cfg(loom)]
+use loom::sync::{
+    atomic::{AtomicI64, AtomicU64, Ordering},
+    Arc,
+};
+#[cfg(not(loom))]
+use std::sync::{
+    atomic::{AtomicI64, AtomicU64, Ordering},
+    Arc,
+};
+
+use crate::{cat::audio, os};
+
+use super::{BufList, Bufs, StreamBasicDesc, TimeStamp, TimeStampFlags};
+
+/// Result of a write into the ring.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct WriteStatus {
+    /// Frames copied into the ring.
+    pub written: u32,
+    /// Silent frames inserted before the data to fill sample time gap.
+    pub gap: u64,
+    /// Frames skipped because their sample time was already written.
+    pub late: u32,
+    /// Frames dropped because the ring is full.
+    pub overrun: u32,
+    /// Sample time jumped back by more than the capacity, e.g. after device
+    /// restart, and the ring continues from the new time.
+    pub resynced: bool,
+}
+
+/// Result of a read from the ring.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct ReadStatus {
+    /// Frames copied from the ring.
+    pub read: u32,
+    /// Frames discarded to catch up with requested sample time.
+    pub skipped: u64,
+    /// Frames filled with silence because they are not in the ring.
+    pub underrun: u32,
+}
+
+struct Shared {
+    asbd: StreamBasicDesc,
+    buf_count: usize,
+    bytes_per_frame: usize,
+    /// power of two
+    capacity: u64,
+    data: Box<[UnsafeCell<u8>]>,
+    /// Frames written. Stored by producer only.
+    head: AtomicU64,
+    /// Frames read. Stored by consumer only.
+    tail: AtomicU64,
+    /// Sample time of the first frame. Published with the first `head` store.
+    base: AtomicI64,
+    /// One cell per frame so loom can see which side touches it.
+    #[cfg(loom)]
+    slots: Box<[loom::cell::UnsafeCell<()>]>,
+}
+
+// Producer and consumer access disjoint regions guarded by `head` and `tail`.
+unsafe impl Send for Shared {}
+unsafe impl Sync for Shared {}
+
+impl Shared {
+    #[inline]
+    fn buf_ptr(&self, i: usize) -> *mut u8 {
+        let ptr = UnsafeCell::raw_get(self.data.as_ptr());
+        unsafe { ptr.add(i * self.capacity as usize * self.bytes_per_frame) }
+    }
+
+    /// Splits `frames` at `pos` into two (offset, len) regions in bytes.
+    #[inline]
+    fn regions(&self, pos: u64, frames: u64) -> [(usize, usize); 2] {
+        let start = (pos & (self.capacity - 1)) as usize;
+        let first = frames.min(self.capacity - start as u64) as usize;
+        let bpf = self.bytes_per_frame;
+        [
+            (start * bpf, first * bpf),
+            (0, (frames as usize - first) * bpf),
+        ]
+    }
+
+    /// Reports access to frames at `pos` to loom, which fails the model if
+    /// producer and consumer race on the same frame.
+    #[cfg(loom)]
+    fn track(&self, pos: u64, frames: u64, write: bool) {
+        for p in pos..pos + frames {
+            let slot = &self.slots[(p & (self.capacity - 1)) as usize];
+            if write {
+                slot.with_mut(|_| ());
+            } else {
+                slot.with(|_| ());
+            }
+        }
+    }
+
+    /// # Safety
+    ///
+    /// Region must be owned by producer, `src` must be valid for `src_offset + frames`.
+    /// `None` writes silence.
+    unsafe fn copy_in(&self, pos: u64, src: Option<&[*const u8]>, src_offset: u64, frames: u64) {
+        #[cfg(loom)]
+        self.track(pos, frames, true);
+        let offset = src_offset as usize * self.bytes_per_frame;
+        for i in 0..self.buf_count {
+            let buf = self.buf_ptr(i);
+            let mut consumed = 0;
+            for (at, len) in self.regions(pos, frames) {
+                match src {
+                    Some(src) => std::ptr::copy_nonoverlapping(
+                        src[i].add(offset + consumed),
+                        buf.add(at),
+                        len,
+                    ),
+                    None => std::ptr::write_bytes(buf.add(at), 0, len),
+                }
+                consumed += len;
+            }
+        }
+    }
+
+    /// # Safety
+    ///
+    /// Region must be owned by consumer, `dst` must be valid for `dst_offset + frames`.
+    unsafe fn copy_out(&self, pos: u64, dst: &[*mut u8], dst_offset: u64, frames: u64) {
+        #[cfg(loom)]
+        self.track(pos, frames, false);
+        let offset = dst_offset as usize * self.bytes_per_frame;
+        for (i, dst) in dst.iter().enumerate() {
+            let buf = self.buf_ptr(i);
+            let mut produced = 0;
+            for (at, len) in self.regions(pos, frames) {
+                std::ptr::copy_nonoverlapping(buf.add(at), dst.add(offset + produced), len);
+                produced += len;
+            }
+        }
+    }
+
+    /// # Safety
+    ///
+    /// `dst` must be valid for `dst_offset + frames`.
+    unsafe fn silence(&self, dst: &[*mut u8], dst_offset: u64, frames: u64) {
+        let offset = dst_offset as usize * self.bytes_per_frame;
+        for dst in dst {
+            std::ptr::write_bytes(dst.add(offset), 0, frames as usize * self.bytes_per_frame);
+        }
+    }
+
+    fn check_list<const N: usize>(&self, list: &BufList<N>, frames: u32) -> os::Result {
+        let size = frames as usize * self.bytes_per_frame;
+        if N != self.buf_count
+            || list
+                .as_slice()
+                .iter()
+                .any(|b| b.data.is_null() || (b.data_bytes_size as usize) < size)
+        {
+            return Err(audio::err::PARAM);
+        }
+        Ok(())
+    }
+
+    fn check_bufs(&self, bufs: &Bufs) -> os::Result {
+        if bufs.asbd().bytes_per_frame as usize != self.bytes_per_frame
+            || bufs.buf_count() != self.buf_count
+        {
+            return Err(audio::err::PARAM);
+        }
+        Ok(())
+    }
+}
+
+#[inline]
+fn sample_time(ts: &TimeStamp) -> Option<i64> {
+    if ts.flags.0 & TimeStampFlags::SAMPLE_TIME_VALID.0 != 0 {
+        Some(ts.sample_time.round() as i64)
+    } else {
+        None
+    }
+}
+
+/// Wait-free single producer, single consumer ring of LPCM frames.
+///
+/// Frames are addressed by sample time: gaps in producer time stamps are filled
+/// with silence, and consumer may read at any sample time, getting silence for the
+/// frames which are not in the ring. Producer never overwrites unread frames,
+/// so a full ring drops incoming frames and reports overrun. Producer time going
+/// back by more than the capacity moves the whole ring to the new time line.
+///
+/// Neither side allocates or locks, so both can be used from render callbacks.
+///
+/// ```
+/// use cidre::at::audio;
+///
+/// let asbd = audio::StreamBasicDesc::common_f32(48_000.0, 2, false);
+/// let (mut producer, mut consumer) = audio::RingBuf::new(&asbd, 1024).unwrap().split();
+///
+/// let mut src = audio::Bufs::new(&asbd, 256).unwrap();
+/// src.planar_mut::<f32>(1).unwrap().fill(0.5);
+/// let status = producer.write_bufs(&src).unwrap();
+/// assert_eq!(status.written, 256);
+///
+/// let mut dst = audio::Bufs::new(&asbd, 512).unwrap();
+/// let status = consumer.read_bufs(&mut dst, 512).unwrap();
+/// assert_eq!(status.read, 256);
+/// assert_eq!(status.underrun, 256);
+/// assert_eq!(dst.planar::<f32>(1).unwrap()[255], 0.5);
+/// ```
+pub struct RingBuf {
+    shared: Arc<Shared>,
+}
+
+impl RingBuf {
+    pub const MAX_BUFS: usize = 64;
+
+    /// Capacity is rounded up to the power of two.
+    pub fn new(asbd: &StreamBasicDesc, capacity: u32) -> os::Result<Self> {
+        let buf_count = if asbd.is_interleaved() {
+            1
+        } else {
+            asbd.channels_per_frame as usize
+        };
+        if asbd.frames_per_packet != 1
+            || asbd.bytes_per_frame == 0
+            || capacity == 0
+            || buf_count == 0
+            || buf_count > Self::MAX_BUFS
+        {
+            return Err(audio::err::PARAM);
+        }
+        let capacity = (capacity as u64).next_power_of_two();
+        let bytes_per_frame = asbd.bytes_per_frame as usize;
+        let len = buf_count * capacity as usize * bytes_per_frame;
+        let data = (0..len).map(|_| UnsafeCell::new(0u8)).collect();
+        Ok(Self {
+            shared: Arc::new(Shared {
+                asbd: *asbd,
+                buf_count,
+                bytes_per_frame,
+                capacity,
+                data,
+                head: AtomicU64::new(0),
+                tail: AtomicU64::new(0),
+                base: AtomicI64::new(0),
+                #[cfg(loom)]
+                slots: (0..capacity)
+                    .map(|_| loom::cell::UnsafeCell::new(()))
+                    .collect(),
+            }),
+        })
+    }
+
+    #[inline]
+    pub fn asbd(&self) -> &StreamBasicDesc {
+        &self.shared.asbd
+    }
+
+    #[inline]
+    pub fn capacity(&self) -> u32 {
+        self.shared.capacity as u32
+    }
+
+    pub fn split(self) -> (Producer, Consumer) {
+        (
+            Producer {
+                shared: self.shared.clone(),
+                head: 0,
+                base: None,
+            },
+            Consumer {
+                shared: self.shared,
+                tail: 0,
+            },
+        )
+    }
+}
+
+/// Writing half of the [`RingBuf`].
+pub struct Producer {
+    shared: Arc<Shared>,
+    head: u64,
+    base: Option<i64>,
+}
+
+impl Producer {
+    #[inline]
+    pub fn asbd(&self) -> &StreamBasicDesc {
+        &self.shared.asbd
+    }
+
+    #[inline]
+    pub fn capacity(&self) -> u32 {
+        self.shared.capacity as u32
+    }
+
+    #[inline]
+    pub fn free_frames(&self) -> u32 {
+        let tail = self.shared.tail.load(Ordering::Acquire);
+        (self.shared.capacity - (self.head - tail)) as u32
+    }
+
+    /// Sample time of the next frame to write, `None` before the first write.
+    #[inline]
+    pub fn sample_time(&self) -> Option<f64> {
+        self.base.map(|base| (base + self.head as i64) as f64)
+    }
+
+    unsafe fn write_ptrs(
+        &mut self,
+        time: Option<i64>,
+        src: &[*const u8],
+        frames: u32,
+    ) -> WriteStatus {
+        let shared = &*self.shared;
+        let mut status = WriteStatus::default();
+        let mut base = *self.base.get_or_insert_with(|| {
+            let base = time.unwrap_or(0);
+            shared.base.store(base, Ordering::Relaxed);
+            base
+        });
+        let mut next = base + self.head as i64;
+        let time = time.unwrap_or(next);
+        if next.saturating_sub(time) > shared.capacity as i64 {
+            // unread frames are kept right before the new time, so both
+            // sides keep their positions
+            base = time - self.head as i64;
+            self.base = Some(base);
+            shared.base.store(base, Ordering::Relaxed);
+            next = time;
+            status.resynced = true;
+        }
+        let tail = shared.tail.load(Ordering::Acquire);
+        let mut free = shared.capacity - (self.head - tail);
+        let mut frames = frames as u64;
+        let mut skip = 0;
+
+        if time < next {
+            skip = ((next - time) as u64).min(frames);
+            frames -= skip;
+            status.late = skip as u32;
+        } else if time > next {
+            let gap = (time - next) as u64;
+            let zeros = gap.min(free);
+            shared.copy_in(self.head, None, 0, zeros);
+            self.head += zeros;
+            free -= zeros;
+            status.gap = zeros;
+            if zeros < gap {
+                // rest of the gap will be filled by the next write
+                status.overrun = frames as u32;
+                frames = 0;
+            }
+        }
+
+        let n = frames.min(free);
+        shared.copy_in(self.head, Some(src), skip, n);
+        self.head += n;
+        status.written = n as u32;
+        status.overrun += (frames - n) as u32;
+        shared.head.store(self.head, Ordering::Release);
+        status
+    }
+
+    fn write_list<const N: usize>(
+        &mut self,
+        time: Option<i64>,
+        list: &BufList<N>,
+        frames: u32,
+    ) -> os::Result<WriteStatus> {
+        self.shared.check_list(list, frames)?;
+        let mut ptrs = [std::ptr::null::<u8>(); RingBuf::MAX_BUFS];
+        for (p, b) in ptrs.iter_mut().zip(list.as_slice()) {
+            *p = b.data;
+        }
+        Ok(unsafe { self.write_ptrs(time, &ptrs[..N], frames) })
+    }
+
+    /// Writes `frames` right after previously written frames.
+    #[inline]
+    pub fn write<const N: usize>(
+        &mut self,
+        list: &BufList<N>,
+        frames: u32,
+    ) -> os::Result<WriteStatus> {
+        self.write_list(None, list, frames)
+    }
+
+    /// Writes `frames` at sample time of `ts`. Falls back to [`Self::write`] if sample time is not valid.
+    #[inline]
+    pub fn write_at<const N: usize>(
+        &mut self,
+        ts: &TimeStamp,
+        list: &BufList<N>,
+        frames: u32,
+    ) -> os::Result<WriteStatus> {
+        self.write_list(sample_time(ts), list, frames)
+    }
+
+    /// Writes all frames of `src` right after previously written frames.
+    pub fn write_bufs(&mut self, src: &Bufs) -> os::Result<WriteStatus> {
+        self.shared.check_bufs(src)?;
+        let mut ptrs = [std::ptr::null::<u8>(); RingBuf::MAX_BUFS];
+        for (i, p) in ptrs.iter_mut().enumerate().take(src.buf_count()) {
+            *p = src.bytes(i).unwrap().as_ptr();
+        }
+        Ok(unsafe { self.write_ptrs(None, &ptrs[..src.buf_count()], src.frames()) })
+    }
+}
+
+/// Reading half of the [`RingBuf`].
+pub struct Consumer {
+    shared: Arc<Shared>,
+    tail: u64,
+}
+
+impl Consumer {
+    #[inline]
+    pub fn asbd(&self) -> &StreamBasicDesc {
+        &self.shared.asbd
+    }
+
+    #[inline]
+    pub fn capacity(&self) -> u32 {
+        self.shared.capacity as u32
+    }
+
+    #[inline]
+    pub fn available_frames(&self) -> u32 {
+        (self.shared.head.load(Ordering::Acquire) - self.tail) as u32
+    }
+
+    /// Sample time of the next frame to read, `None` before the first write.
+    pub fn sample_time(&self) -> Option<f64> {
+        if self.shared.head.load(Ordering::Acquire) == 0 {
+            return None;
+        }
+        let base = self.shared.base.load(Ordering::Relaxed);
+        Some((base + self.tail as i64) as f64)
+    }
+
+    unsafe fn read_ptrs(&mut self, time: Option<i64>, dst: &[*mut u8], frames: u32) -> ReadStatus {
+        let shared = &*self.shared;
+        let head = shared.head.load(Ordering::Acquire);
+        let frames = frames as u64;
+        let mut status = ReadStatus::default();
+        if head == 0 {
+            shared.silence(dst, 0, frames);
+            status.underrun = frames as u32;
+            return status;
+        }
+        let base = shared.base.load(Ordering::Relaxed);
+        let start = base + self.tail as i64;
+        let end = base + head as i64;
+        let time = time.unwrap_or(start);
+
+        if time > start {
+            let skip = (time.min(end) - start) as u64;
+            self.tail += skip;
+            status.skipped = skip;
+        }
+        let start = base + self.tail as i64;
+        let prefix = if time < start {
+            ((start - time) as u64).min(frames)
+        } else {
+            0
+        };
+        let n = if end > start {
+            ((end - start) as u64).min(frames - prefix)
+        } else {
+            0
+        };
+        // frames after the prefix always start at `start` unless requested time is past `end`
+        let n = if time > start { 0 } else { n };
+        let suffix = frames - prefix - n;
+
+        shared.silence(dst, 0, prefix);
+        shared.copy_out(self.tail, dst, prefix, n);
+        shared.silence(dst, prefix + n, suffix);
+        self.tail += n;
+        shared.tail.store(self.tail, Ordering::Release);
+
+        status.read = n as u32;
+        status.underrun = (prefix + suffix) as u32;
+        status
+    }
+
+    fn read_list<const N: usize>(
+        &mut self,
+        time: Option<i64>,
+        list: &mut BufList<N>,
+        frames: u32,
+    ) -> os::Result<ReadStatus> {
+        self.shared.check_list(list, frames)?;
+        let mut ptrs = [std::ptr::null_mut::<u8>(); RingBuf::MAX_BUFS];
+        for (p, b) in ptrs.iter_mut().zip(list.as_slice()) {
+            *p = b.data;
+        }
+        let status = unsafe { self.read_ptrs(time, &ptrs[..N], frames) };
+        let size = frames * self.shared.bytes_per_frame as u32;
+        for b in list.as_mut_slice() {
+            b.data_bytes_size = size;
+        }
+        Ok(status)
+    }
+
+    /// Reads `frames` right after previously read frames.
+    #[inline]
+    pub fn read<const N: usize>(
+        &mut self,
+        list: &mut BufList<N>,
+        frames: u32,
+    ) -> os::Result<ReadStatus> {
+        self.read_list(None, list, frames)
+    }
+
+    /// Reads `frames` at sample time of `ts`, skipping older frames.
+    /// Falls back to [`Self::read`] if sample time is not valid.
+    #[inline]
+    pub fn read_at<const N: usize>(
+        &mut self,
+        ts: &TimeStamp,
+        list: &mut BufList<N>,
+        frames: u32,
+    ) -> os::Result<ReadStatus> {
+        self.read_list(sample_time(ts), list, frames)
+    }
+
+    /// Reads `frames` right after previously read frames and sets frames of `dst`.
+    pub fn read_bufs(&mut self, dst: &mut Bufs, frames: u32) -> os::Result<ReadStatus> {
+        self.shared.check_bufs(dst)?;
+        dst.set_frames(frames)?;
+        let count = dst.buf_count();
+        let mut ptrs = [std::ptr::null_mut::<u8>(); RingBuf::MAX_BUFS];
+        for (i, p) in ptrs.iter_mut().enumerate().take(count) {
+            *p = dst.bytes_mut(i).unwrap().as_mut_ptr();
+        }
+        Ok(unsafe { self.read_ptrs(None, &ptrs[..count], frames) })
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod tests {
+    use crate::cat::audio;
+
+    fn mono_i32() -> audio::StreamBasicDesc {
+        audio::StreamBasicDesc {
+            sample_rate: 48_000.0,
+            format: audio::Format::LINEAR_PCM,
+            format_flags: audio::FormatFlags(
+                audio::FormatFlags::IS_SIGNED_INTEGER.0 | audio::FormatFlags::IS_PACKED.0,
+            ),
+            bytes_per_packet: 4,
+            frames_per_packet: 1,
+            bytes_per_frame: 4,
+            channels_per_frame: 1,
+            bits_per_channel: 32,
+            reserved: 0,
+        }
+    }
+
+    #[test]
+    fn wrap_around() {
+        let asbd = audio::StreamBasicDesc::common_f32(48_000.0, 2, false);
+        let ring = audio::RingBuf::new(&asbd, 100).unwrap();
+        assert_eq!(ring.capacity(), 128);
+        let (mut producer, mut consumer) = ring.split();
+        assert_eq!(consumer.sample_time(), None);
+
+        let mut src = audio::Bufs::new(&asbd, 96).unwrap();
+        let mut dst = audio::Bufs::new(&asbd, 96).unwrap();
+        let mut value = 0.0;
+        for _ in 0..10 {
+            for ch in 0..2 {
+                for (i, s) in src.planar_mut::<f32>(ch).unwrap().iter_mut().enumerate() {
+                    *s = value + i as f32 + ch as f32 * 1000.0;
+                }
+            }
+            let status = producer.write_bufs(&src).unwrap();
+            assert_eq!(status.written, 96);
+            assert_eq!(consumer.available_frames(), 96);
+
+            let status = consumer.read_bufs(&mut dst, 96).unwrap();
+            assert_eq!(status.read, 96);
+            assert_eq!(src.planar::<f32>(0), dst.planar::<f32>(0));
+            assert_eq!(src.planar::<f32>(1), dst.planar::<f32>(1));
+            value += 96.0;
+        }
+        assert_eq!(consumer.sample_time(), Some(960.0));
+        assert_eq!(producer.sample_time(), Some(960.0));
+    }
+
+    #[test]
+    fn time_gaps() {
+        let asbd = mono_i32();
+        let (mut producer, mut consumer) = audio::RingBuf::new(&asbd, 16).unwrap().split();
+        let mut src = audio::Bufs::new(&asbd, 8).unwrap();
+        let mut dst = audio::Bufs::new(&asbd, 8).unwrap();
+        src.interleaved_mut::<i32>()
+            .unwrap()
+            .copy_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8]);
+
+        let ts = audio::TimeStamp::with_sample_time(100.0);
+        let status = producer
+            .write_at(&ts, src.as_list::<1>().unwrap(), 4)
+            .unwrap();
+        assert_eq!(status.written, 4);
+
+        // 2 frames gap
+        let ts = audio::TimeStamp::with_sample_time(106.0);
+        let status = producer
+            .write_at(&ts, src.as_list::<1>().unwrap(), 4)
+            .unwrap();
+        assert_eq!(status.gap, 2);
+        assert_eq!(status.written, 4);
+
+        // overlapping
+        let ts = audio::TimeStamp::with_sample_time(108.0);
+        let status = producer
+            .write_at(&ts, src.as_list::<1>().unwrap(), 4)
+            .unwrap();
+        assert_eq!(status.late, 2);
+        assert_eq!(status.written, 2);
+        assert_eq!(producer.sample_time(), Some(112.0));
+
+        // read earlier than ring start
+        let ts = audio::TimeStamp::with_sample_time(98.0);
+        let status = consumer
+            .read_at(&ts, dst.as_list_mut::<1>().unwrap(), 8)
+            .unwrap();
+        assert_eq!(status.underrun, 2);
+        assert_eq!(status.read, 6);
+        assert_eq!(dst.interleaved::<i32>().unwrap(), &[0, 0, 1, 2, 3, 4, 0, 0]);
+
+        // skip 2 frames
+        let ts = audio::TimeStamp::with_sample_time(108.0);
+        let status = consumer
+            .read_at(&ts, dst.as_list_mut::<1>().unwrap(), 8)
+            .unwrap();
+        assert_eq!(status.skipped, 2);
+        assert_eq!(status.read, 4);
+        assert_eq!(status.underrun, 4);
+        assert_eq!(dst.interleaved::<i32>().unwrap(), &[3, 4, 3, 4, 0, 0, 0, 0]);
+
+        // overrun
+        let status = producer.write(src.as_list::<1>().unwrap(), 8).unwrap();
+        assert_eq!(status.written, 8);
+        let status = producer.write(src.as_list::<1>().unwrap(), 8).unwrap();
+        assert_eq!(status.written, 8);
+        let ts = audio::TimeStamp::with_sample_time(128.0);
+        let status = producer
+            .write_at(&ts, src.as_list::<1>().unwrap(), 8)
+            .unwrap();
+        assert_eq!(status.overrun, 8);
+        assert_eq!(producer.free_frames(), 0);
+
+        // dropped frames become silence
+        consumer.read(dst.as_list_mut::<1>().unwrap(), 8).unwrap();
+        let ts = audio::TimeStamp::with_sample_time(136.0);
+        let status = producer
+            .write_at(&ts, src.as_list::<1>().unwrap(), 4)
+            .unwrap();
+        assert_eq!(status.gap, 8);
+        assert_eq!(status.overrun, 4);
+    }
+
+    #[test]
+    fn time_jump_back() {
+        let asbd = mono_i32();
+        let (mut producer, mut consumer) = audio::RingBuf::new(&asbd, 16).unwrap().split();
+        let mut src = audio::Bufs::new(&asbd, 8).unwrap();
+        let mut dst = audio::Bufs::new(&asbd, 8).unwrap();
+        src.interleaved_mut::<i32>()
+            .unwrap()
+            .copy_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8]);
+
+        let ts = audio::TimeStamp::with_sample_time(1000.0);
+        producer
+            .write_at(&ts, src.as_list::<1>().unwrap(), 4)
+            .unwrap();
+
+        // within the capacity frames are late
+        let ts = audio::TimeStamp::with_sample_time(990.0);
+        let status = producer
+            .write_at(&ts, src.as_list::<1>().unwrap(), 8)
+            .unwrap();
+        assert_eq!((status.late, status.written), (8, 0));
+        assert!(!status.resynced);
+
+        // restarted device
+        let ts = audio::TimeStamp::with_sample_time(0.0);
+        let status = producer
+            .write_at(&ts, src.as_list::<1>().unwrap(), 4)
+            .unwrap();
+        assert!(status.resynced);
+        assert_eq!((status.late, status.written), (0, 4));
+        assert_eq!(producer.sample_time(), Some(4.0));
+        assert_eq!(consumer.sample_time(), Some(-4.0));
+
+        let ts = audio::TimeStamp::with_sample_time(-2.0);
+        let status = consumer
+            .read_at(&ts, dst.as_list_mut::<1>().unwrap(), 8)
+            .unwrap();
+        assert_eq!((status.skipped, status.read, status.underrun), (2, 6, 2));
+        assert_eq!(dst.interleaved::<i32>().unwrap(), &[3, 4, 1, 2, 3, 4, 0, 0]);
+    }
+
+    #[test]
+    fn threads() {
+        const TOTAL: i32 = 200_000;
+        let asbd = mono_i32();
+        let (mut producer, mut consumer) = audio::RingBuf::new(&asbd, 256).unwrap().split();
+
+        let writer = std::thread::spawn(move || {
+            let mut src = audio::Bufs::new(&asbd, 61).unwrap();
+            let mut next = 0;
+            while next < TOTAL {
+                let free = producer.free_frames().min(61).min((TOTAL - next) as u32);
+                if free == 0 {
+                    std::thread::yield_now();
+                    continue;
+                }
+                src.set_frames(free).unwrap();
+                for s in src.interleaved_mut::<i32>().unwrap() {
+                    *s = next;
+                    next += 1;
+                }
+                let status = producer.write_bufs(&src).unwrap();
+                assert_eq!(status.written, free);
+            }
+        });
+
+        let mut dst = audio::Bufs::new(&asbd, 47).unwrap();
+        let mut expected = 0;
+        while expected < TOTAL {
+            let n = consumer.available_frames().min(47);
+            if n == 0 {
+                std::thread::yield_now();
+                continue;
+            }
+            let status = consumer.read_bufs(&mut dst, n).unwrap();
+            assert_eq!(status.read, n);
+            for s in dst.interleaved::<i32>().unwrap() {
+                assert_eq!(*s, expected);
+                expected += 1;
+            }
+        }
+        writer.join().unwrap();
+    }
+}
+
+/// Model checks of producer and consumer orderings.
+///
+/// `RUSTFLAGS="--cfg loom" cargo test --release --lib cat::audio::ring::loom_tests`
+#[cfg(all(test, loom))]
+mod loom_tests {
+    use loom::thread;
+
+    use crate::cat::audio;
+
+    fn mono_i32() -> audio::StreamBasicDesc {
+        audio::StreamBasicDesc {
+            sample_rate: 48_000.0,
+            format: audio::Format::LINEAR_PCM,
+            format_flags: audio::FormatFlags(
+                audio::FormatFlags::IS_SIGNED_INTEGER.0 | audio::FormatFlags::IS_PACKED.0,
+            ),
+            bytes_per_packet: 4,
+            frames_per_packet: 1,
+            bytes_per_frame: 4,
+            channels_per_frame: 1,
+            bits_per_channel: 32,
+            reserved: 0,
+        }
+    }
+
+    #[test]
+    fn push_pop_wrap_around() {
+        const TOTAL: i32 = 6;
+        loom::model(|| {
+            let asbd = mono_i32();
+            let (mut producer, mut consumer) = audio::RingBuf::new(&asbd, 4).unwrap().split();
+
+            let writer = thread::spawn(move || {
+                let mut src = audio::Bufs::new(&asbd, 2).unwrap();
+                let mut next = 0;
+                while next < TOTAL {
+                    let free = producer.free_frames().min(2);
+                    if free == 0 {
+                        thread::yield_now();
+                        continue;
+                    }
+                    src.set_frames(free).unwrap();
+                    for s in src.interleaved_mut::<i32>().unwrap() {
+                        *s = next;
+                        next += 1;
+                    }
+                    let status = producer.write_bufs(&src).unwrap();
+                    assert_eq!(status.written, free);
+                }
+            });
+
+            let mut dst = audio::Bufs::new(&asbd, 3).unwrap();
+            let mut expected = 0;
+            while expected < TOTAL {
+                let n = consumer.available_frames().min(3);
+                if n == 0 {
+                    thread::yield_now();
+                    continue;
+                }
+                let status = consumer.read_bufs(&mut dst, n).unwrap();
+                assert_eq!(status.read, n);
+                for s in dst.interleaved::<i32>().unwrap() {
+                    assert_eq!(*s, expected);
+                    expected += 1;
+                }
+            }
+            writer.join().unwrap();
+        });
+    }
+
+    #[test]
+    fn overrun_on_full_ring() {
+        loom::model(|| {
+            let asbd = mono_i32();
+            let (mut producer, mut consumer) = audio::RingBuf::new(&asbd, 2).unwrap().split();
+
+            let writer = thread::spawn(move || {
+                let mut src = audio::Bufs::new(&asbd, 2).unwrap();
+                let mut written = 0;
+                for round in 0..2 {
+                    src.interleaved_mut::<i32>()
+                        .unwrap()
+                        .copy_from_slice(&[round * 2 + 1, round * 2 + 2]);
+                    let status = producer.write_bufs(&src).unwrap();
+                    assert_eq!(status.written + status.overrun, 2);
+                    written += status.written;
+                }
+                written
+            });
+
+            let mut dst = audio::Bufs::new(&asbd, 2).unwrap();
+            let mut read = Vec::new();
+            for _ in 0..2 {
+                let n = consumer.available_frames();
+                if n == 0 {
+                    continue;
+                }
+                let status = consumer.read_bufs(&mut dst, n).unwrap();
+                assert_eq!(status.read, n);
+                read.extend_from_slice(dst.interleaved::<i32>().unwrap());
+            }
+            let written = writer.join().unwrap();
+            // frames come out in order and dropped ones are never visible
+            assert!(read.len() as u32 <= written);
+            assert_eq!(read, [1, 2, 3, 4][..read.len()]);
+        });
+    }
+}
@@ -42,6 +42,10 @@ pub use sample_buffer::Flags as SampleBufFlags;
 pub use sample_buffer::SampleBuf;
 pub use sample_buffer::SampleTimingInfo;
 
+mod sample_attachments;
+pub use sample_attachments::BufAttachments;
+pub use sample_attachments::SampleAttachments;
+
 pub mod attachment;
 pub use attachment::Bearer as AttachBearer;
 pub use attachment::Mode as AttachMode;
@@ -55,11 +59,20 @@ pub mod sync;
 pub use sync::Clock;
 pub use sync::ClockOrTimebase;
 pub use sync::Timebase;
+pub use sync::TimebaseControl;
+
+pub mod virtual_timebase;
+pub use virtual_timebase::VirtualClock;
+pub use virtual_timebase::VirtualTimebase;
 
 pub mod memory_pool;
 pub use memory_pool::keys as memory_pool_options;
 pub use memory_pool::MemPool;
 
+pub mod iso;
+pub mod timing;
+pub mod ts;
+
 pub mod simple_queue;
 pub use simple_queue::err as simple_queue_err;
 pub use simple_queue::SimpleQueue;
@@ -166,7 +166,7 @@ impl VideoCodec {
     #[doc(alias = "kCMVideoCodecType_AV1")]
     pub const AV1: Self = Self::from_be_bytes(b"av01");
 
-    const fn from_be_bytes(bytes: &[u8; 4]) -> Self {
+    pub const fn from_be_bytes(bytes: &[u8; 4]) -> Self {
         Self(FourCharCode::from_be_bytes(*bytes))
     }
 }
@@ -604,6 +604,19 @@ impl AudioFormatDesc {
     pub fn stream_basic_desc(&self) -> Option<&cat::audio::StreamBasicDesc> {
         unsafe { CMAudioFormatDescriptionGetStreamBasicDescription(self) }
     }
+
+    /// Codec specific data, e.g. ES descriptor of AAC.
+    #[doc(alias = "CMAudioFormatDescriptionGetMagicCookie")]
+    pub fn magic_cookie(&self) -> Option<&[u8]> {
+        let mut size = 0;
+        unsafe {
+            let bytes = CMAudioFormatDescriptionGetMagicCookie(self, &mut size);
+            if bytes.is_null() {
+                return None;
+            }
+            Some(&*std::ptr::slice_from_raw_parts(bytes.cast::<u8>(), size))
+        }
+    }
 }
 
 define_cf_type!(
@@ -988,6 +1001,12 @@ extern "C-unwind" {
         desc: &AudioFormatDesc,
     ) -> Option<&cat::audio::StreamBasicDesc>;
 
+    #[cfg(feature = "cat")]
+    fn CMAudioFormatDescriptionGetMagicCookie(
+        desc: &AudioFormatDesc,
+        size_out: *mut usize,
+    ) -> *const c_void;
+
     fn CMFormatDescriptionCreate(
         allocator: Option<&cf::Allocator>,
         media_type: MediaType,
@@ -0,0 +1,699 @@
+//! Pure Rust reader and writer of ISO base media file format boxes.
+//!
+//! Covers the boxes found in MP4 files and fMP4 segments: `ftyp`, the
+//! `moov` sample tables with `stsd` sample entries, `moof` fragments, `sidx`,
+//! `emsg` and `mdat`. [`BoxRef`] borrows boxes of a buffer, typed boxes
+//! implement [`IsoBox`] and are written back with [`Writer`].
+
+use std::fmt;
+
+use crate::os;
+
+mod boxes;
+pub use boxes::ChunkOffsets;
+pub use boxes::CompositionOffset;
+pub use boxes::Ctts;
+pub use boxes::Emsg;
+pub use boxes::EmsgTime;
+pub use boxes::Ftyp;
+pub use boxes::Hdlr;
+pub use boxes::Mdhd;
+pub use boxes::Mfhd;
+pub use boxes::Mvhd;
+pub use boxes::SampleFlags;
+pub use boxes::SampleToChunk;
+pub use boxes::Sidx;
+pub use boxes::SidxRef;
+pub use boxes::Stsc;
+pub use boxes::Stss;
+pub use boxes::Stsz;
+pub use boxes::Stts;
+pub use boxes::Styp;
+pub use boxes::Tfdt;
+pub use boxes::Tfhd;
+pub use boxes::TimeToSample;
+pub use boxes::Tkhd;
+pub use boxes::Trex;
+pub use boxes::Trun;
+pub use boxes::TrunEntry;
+
+mod sample_entry;
+pub use sample_entry::AudioSampleEntry;
+pub use sample_entry::AvcConfig;
+pub use sample_entry::HevcConfig;
+pub use sample_entry::NalArray;
+pub use sample_entry::OpusConfig;
+pub use sample_entry::SampleEntry;
+pub use sample_entry::Stsd;
+pub use sample_entry::VisualSampleEntry;
+
+mod movie;
+pub use movie::set_base_decode_time;
+pub use movie::set_sequence_number;
+pub use movie::Fragment;
+pub use movie::Movie;
+pub use movie::Sample;
+pub use movie::SampleTable;
+pub use movie::Segment;
+pub use movie::Track;
+pub use movie::TrackFragment;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// Box or field extends past the end of data.
+    Truncated,
+    /// Box is malformed.
+    InvalidBox(BoxType),
+    /// Required box is missing.
+    MissingBox(BoxType),
+    /// Value doesn't fit into the field being patched.
+    Overflow,
+    /// Core Media failed to create format description.
+    Os(os::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Truncated => f.write_str("truncated box"),
+            Self::InvalidBox(ty) => write!(f, "invalid '{ty}' box"),
+            Self::MissingBox(ty) => write!(f, "missing '{ty}' box"),
+            Self::Overflow => f.write_str("value doesn't fit into the field"),
+            Self::Os(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<os::Error> for Error {
+    fn from(value: os::Error) -> Self {
+        Self::Os(value)
+    }
+}
+
+pub type Result<T = ()> = std::result::Result<T, Error>;
+
+/// Four character code of a box.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BoxType(pub [u8; 4]);
+
+impl BoxType {
+    pub const FTYP: Self = Self(*b"ftyp");
+    pub const STYP: Self = Self(*b"styp");
+    pub const MOOV: Self = Self(*b"moov");
+    pub const MVHD: Self = Self(*b"mvhd");
+    pub const TRAK: Self = Self(*b"trak");
+    pub const TKHD: Self = Self(*b"tkhd");
+    pub const EDTS: Self = Self(*b"edts");
+    pub const MDIA: Self = Self(*b"mdia");
+    pub const MDHD: Self = Self(*b"mdhd");
+    pub const HDLR: Self = Self(*b"hdlr");
+    pub const MINF: Self = Self(*b"minf");
+    pub const DINF: Self = Self(*b"dinf");
+    pub const STBL: Self = Self(*b"stbl");
+    pub const STSD: Self = Self(*b"stsd");
+    pub const STTS: Self = Self(*b"stts");
+    pub const CTTS: Self = Self(*b"ctts");
+    pub const STSC: Self = Self(*b"stsc");
+    pub const STSZ: Self = Self(*b"stsz");
+    pub const STCO: Self = Self(*b"stco");
+    pub const CO64: Self = Self(*b"co64");
+    pub const STSS: Self = Self(*b"stss");
+    pub const MVEX: Self = Self(*b"mvex");
+    pub const TREX: Self = Self(*b"trex");
+    pub const MOOF: Self = Self(*b"moof");
+    pub const MFHD: Self = Self(*b"mfhd");
+    pub const TRAF: Self = Self(*b"traf");
+    pub const TFHD: Self = Self(*b"tfhd");
+    pub const TFDT: Self = Self(*b"tfdt");
+    pub const TRUN: Self = Self(*b"trun");
+    pub const MFRA: Self = Self(*b"mfra");
+    pub const SIDX: Self = Self(*b"sidx");
+    pub const EMSG: Self = Self(*b"emsg");
+    pub const MDAT: Self = Self(*b"mdat");
+    pub const FREE: Self = Self(*b"free");
+    pub const UDTA: Self = Self(*b"udta");
+    pub const UUID: Self = Self(*b"uuid");
+
+    pub const AVC1: Self = Self(*b"avc1");
+    pub const AVC3: Self = Self(*b"avc3");
+    pub const HVC1: Self = Self(*b"hvc1");
+    pub const HEV1: Self = Self(*b"hev1");
+    pub const MP4A: Self = Self(*b"mp4a");
+    pub const OPUS: Self = Self(*b"Opus");
+    pub const AVCC: Self = Self(*b"avcC");
+    pub const HVCC: Self = Self(*b"hvcC");
+    pub const ESDS: Self = Self(*b"esds");
+    pub const DOPS: Self = Self(*b"dOps");
+
+    /// Boxes which contain only other boxes.
+    pub fn is_container(self) -> bool {
+        matches!(
+            self,
+            Self::MOOV
+                | Self::TRAK
+                | Self::EDTS
+                | Self::MDIA
+                | Self::MINF
+                | Self::DINF
+                | Self::STBL
+                | Self::MVEX
+                | Self::MOOF
+                | Self::TRAF
+                | Self::MFRA
+                | Self::UDTA
+        )
+    }
+}
+
+impl fmt::Display for BoxType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.escape_ascii())
+    }
+}
+
+impl fmt::Debug for BoxType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "BoxType(\"{self}\")")
+    }
+}
+
+/// Box borrowed from a buffer.
+#[derive(Debug, Clone, Copy)]
+pub struct BoxRef<'a> {
+    pub ty: BoxType,
+    /// Offset of the box header in the buffer passed to [`Boxes::new`].
+    pub offset: usize,
+    pub header_len: usize,
+    data: &'a [u8],
+}
+
+impl<'a> BoxRef<'a> {
+    /// Whole box including header.
+    #[inline]
+    pub fn bytes(&self) -> &'a [u8] {
+        self.data
+    }
+
+    #[inline]
+    pub fn body(&self) -> &'a [u8] {
+        &self.data[self.header_len..]
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Offset of the body in the buffer passed to [`Boxes::new`].
+    #[inline]
+    pub fn body_offset(&self) -> usize {
+        self.offset + self.header_len
+    }
+
+    /// Child boxes of a container box.
+    #[inline]
+    pub fn children(&self) -> Boxes<'a> {
+        self.children_at(0)
+    }
+
+    /// Child boxes which follow `skip` bytes of fields in the body.
+    pub fn children_at(&self, skip: usize) -> Boxes<'a> {
+        let body = self.body();
+        let skip = skip.min(body.len());
+        Boxes {
+            data: &body[skip..],
+            pos: 0,
+            base: self.body_offset() + skip,
+        }
+    }
+
+    /// First child of type `ty`.
+    pub fn find(&self, ty: BoxType) -> Result<Option<BoxRef<'a>>> {
+        find(self.children(), ty)
+    }
+
+    /// First child of type `ty` or [`Error::MissingBox`].
+    pub fn child(&self, ty: BoxType) -> Result<BoxRef<'a>> {
+        self.find(ty)?.ok_or(Error::MissingBox(ty))
+    }
+
+    /// Parses this box as `T`.
+    pub fn parse<T: IsoBox>(&self) -> Result<T> {
+        if !T::accepts(self.ty) {
+            return Err(Error::InvalidBox(self.ty));
+        }
+        T::parse(self).map_err(|e| match e {
+            Error::Truncated => Error::InvalidBox(self.ty),
+            e => e,
+        })
+    }
+
+    /// Version and flags of a full box.
+    pub fn full_header(&self) -> Result<(u8, u32)> {
+        Reader::new(self.body()).full_header()
+    }
+
+    pub fn to_raw(&self) -> RawBox {
+        RawBox {
+            ty: self.ty,
+            body: self.body().to_vec(),
+        }
+    }
+}
+
+/// Iterator over consecutive boxes of a buffer.
+#[derive(Debug, Clone)]
+pub struct Boxes<'a> {
+    data: &'a [u8],
+    pos: usize,
+    base: usize,
+}
+
+impl<'a> Boxes<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            pos: 0,
+            base: 0,
+        }
+    }
+
+    /// First box of type `ty`.
+    pub fn find(self, ty: BoxType) -> Result<Option<BoxRef<'a>>> {
+        find(self, ty)
+    }
+}
+
+impl<'a> Iterator for Boxes<'a> {
+    type Item = Result<BoxRef<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = &self.data[self.pos..];
+        if rest.is_empty() {
+            return None;
+        }
+        match header(rest) {
+            Ok((ty, header_len, len)) => {
+                let res = BoxRef {
+                    ty,
+                    offset: self.base + self.pos,
+                    header_len,
+                    data: &rest[..len],
+                };
+                self.pos += len;
+                Some(Ok(res))
+            }
+            Err(e) => {
+                self.pos = self.data.len();
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+fn find(boxes: Boxes, ty: BoxType) -> Result<Option<BoxRef>> {
+    for b in boxes {
+        let b = b?;
+        if b.ty == ty {
+            return Ok(Some(b));
+        }
+    }
+    Ok(None)
+}
+
+/// Returns type, header length and total length of the box at the start of `data`.
+fn header(data: &[u8]) -> Result<(BoxType, usize, usize)> {
+    let mut r = Reader::new(data);
+    let size = r.u32()?;
+    let ty = BoxType(r.fourcc()?);
+    let len = match size {
+        0 => data.len(),
+        1 => usize::try_from(r.u64()?).map_err(|_| Error::Truncated)?,
+        n => n as usize,
+    };
+    if ty == BoxType::UUID {
+        r.skip(16)?;
+    }
+    let header_len = r.pos();
+    if len < header_len {
+        return Err(Error::InvalidBox(ty));
+    }
+    if len > data.len() {
+        return Err(Error::Truncated);
+    }
+    Ok((ty, header_len, len))
+}
+
+/// Typed box.
+pub trait IsoBox: Sized {
+    const TYPE: BoxType;
+
+    /// Box types the parser accepts, `TYPE` by default.
+    fn accepts(ty: BoxType) -> bool {
+        ty == Self::TYPE
+    }
+
+    fn parse(b: &BoxRef) -> Result<Self>;
+
+    fn write(&self, w: &mut Writer);
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut w = Writer::new();
+        self.write(&mut w);
+        w.into_inner()
+    }
+}
+
+/// Box kept as is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawBox {
+    pub ty: BoxType,
+    /// Body without header, including version and flags of a full box.
+    pub body: Vec<u8>,
+}
+
+impl RawBox {
+    pub fn new(ty: BoxType, body: Vec<u8>) -> Self {
+        Self { ty, body }
+    }
+
+    pub fn write(&self, w: &mut Writer) {
+        w.begin(self.ty);
+        w.bytes(&self.body);
+        w.end();
+    }
+}
+
+/// Box serializer.
+///
+/// Sizes of nested boxes are patched in [`Writer::end`]; boxes larger than
+/// 4 GiB get 64-bit size.
+#[derive(Debug, Default)]
+pub struct Writer {
+    buf: Vec<u8>,
+    open: Vec<usize>,
+}
+
+impl Writer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends to `buf`.
+    pub fn with_vec(buf: Vec<u8>) -> Self {
+        Self {
+            buf,
+            open: Vec::new(),
+        }
+    }
+
+    pub fn begin(&mut self, ty: BoxType) {
+        self.open.push(self.buf.len());
+        self.u32(0);
+        self.fourcc(ty.0);
+    }
+
+    pub fn begin_full(&mut self, ty: BoxType, version: u8, flags: u32) {
+        self.begin(ty);
+        self.u32((version as u32) << 24 | flags & 0xff_ffff);
+    }
+
+    /// Closes the last opened box.
+    pub fn end(&mut self) {
+        let start = self.open.pop().expect("no open box");
+        let size = self.buf.len() - start;
+        if let Ok(size) = u32::try_from(size) {
+            self.buf[start..start + 4].copy_from_slice(&size.to_be_bytes());
+        } else {
+            let size = size as u64 + 8;
+            self.buf[start..start + 4].copy_from_slice(&1u32.to_be_bytes());
+            let at = start + 8;
+            self.buf.splice(at..at, size.to_be_bytes());
+        }
+    }
+
+    pub fn write<T: IsoBox>(&mut self, b: &T) {
+        b.write(self);
+    }
+
+    #[inline]
+    pub fn u8(&mut self, val: u8) {
+        self.buf.push(val);
+    }
+
+    #[inline]
+    pub fn u16(&mut self, val: u16) {
+        self.bytes(&val.to_be_bytes());
+    }
+
+    #[inline]
+    pub fn u32(&mut self, val: u32) {
+        self.bytes(&val.to_be_bytes());
+    }
+
+    #[inline]
+    pub fn u64(&mut self, val: u64) {
+        self.bytes(&val.to_be_bytes());
+    }
+
+    #[inline]
+    pub fn i16(&mut self, val: i16) {
+        self.bytes(&val.to_be_bytes());
+    }
+
+    #[inline]
+    pub fn i32(&mut self, val: i32) {
+        self.bytes(&val.to_be_bytes());
+    }
+
+    #[inline]
+    pub fn fourcc(&mut self, val: [u8; 4]) {
+        self.bytes(&val);
+    }
+
+    #[inline]
+    pub fn bytes(&mut self, val: &[u8]) {
+        self.buf.extend_from_slice(val);
+    }
+
+    pub fn zeros(&mut self, n: usize) {
+        self.buf.resize(self.buf.len() + n, 0);
+    }
+
+    /// Null terminated string.
+    pub fn cstr(&mut self, val: &str) {
+        self.bytes(val.as_bytes());
+        self.u8(0);
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.buf.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    pub fn into_inner(self) -> Vec<u8> {
+        debug_assert!(self.open.is_empty(), "box is not closed");
+        self.buf
+    }
+}
+
+/// Big endian field reader.
+pub(crate) struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    #[inline]
+    pub fn pos(&self) -> usize {
+        self.pos
+    }
+
+    #[inline]
+    pub fn remaining(&self) -> usize {
+        self.data.len() - self.pos
+    }
+
+    pub fn bytes(&mut self, n: usize) -> Result<&'a [u8]> {
+        if self.remaining() < n {
+            return Err(Error::Truncated);
+        }
+        let res = &self.data[self.pos..self.pos + n];
+        self.pos += n;
+        Ok(res)
+    }
+
+    pub fn rest(&mut self) -> &'a [u8] {
+        let res = &self.data[self.pos..];
+        self.pos = self.data.len();
+        res
+    }
+
+    pub fn skip(&mut self, n: usize) -> Result {
+        self.bytes(n).map(|_| ())
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let mut res = [0u8; N];
+        res.copy_from_slice(self.bytes(N)?);
+        Ok(res)
+    }
+
+    pub fn u8(&mut self) -> Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    pub fn u16(&mut self) -> Result<u16> {
+        self.array().map(u16::from_be_bytes)
+    }
+
+    pub fn u32(&mut self) -> Result<u32> {
+        self.array().map(u32::from_be_bytes)
+    }
+
+    pub fn u64(&mut self) -> Result<u64> {
+        self.array().map(u64::from_be_bytes)
+    }
+
+    pub fn i16(&mut self) -> Result<i16> {
+        self.array().map(i16::from_be_bytes)
+    }
+
+    pub fn i32(&mut self) -> Result<i32> {
+        self.array().map(i32::from_be_bytes)
+    }
+
+    pub fn fourcc(&mut self) -> Result<[u8; 4]> {
+        self.array()
+    }
+
+    /// 32-bit field for version 0 and 64-bit for version 1.
+    pub fn u32_or_u64(&mut self, version: u8) -> Result<u64> {
+        if version == 0 {
+            self.u32().map(u64::from)
+        } else {
+            self.u64()
+        }
+    }
+
+    pub fn full_header(&mut self) -> Result<(u8, u32)> {
+        let v = self.u32()?;
+        Ok(((v >> 24) as u8, v & 0xff_ffff))
+    }
+
+    /// Null terminated string, the terminator may be missing at the end of data.
+    pub fn cstr(&mut self) -> Result<String> {
+        let rest = &self.data[self.pos..];
+        let len = rest.iter().position(|&b| b == 0);
+        let s = &rest[..len.unwrap_or(rest.len())];
+        self.pos += len.map_or(rest.len(), |len| len + 1);
+        Ok(String::from_utf8_lossy(s).into_owned())
+    }
+}
+
+/// Checks box structure and all known boxes of the buffer.
+///
+/// Movies and fragments are checked to resolve into samples.
+pub fn validate(data: &[u8]) -> Result {
+    for b in Boxes::new(data) {
+        let b = b?;
+        match b.ty {
+            BoxType::MOOV => {
+                for track in Movie::parse_moov(&b)?.tracks {
+                    track.samples_within(data.len() as u64)?;
+                }
+            }
+            BoxType::MOOF => {
+                Fragment::parse(&b)?;
+            }
+            _ => check(&b, 0)?,
+        }
+    }
+    Ok(())
+}
+
+/// Nesting limit of containers in [`validate`].
+const MAX_DEPTH: usize = 16;
+
+fn check(b: &BoxRef, depth: usize) -> Result {
+    match b.ty {
+        ty if ty.is_container() => {
+            if depth >= MAX_DEPTH {
+                return Err(Error::InvalidBox(ty));
+            }
+            for child in b.children() {
+                check(&child?, depth + 1)?;
+            }
+        }
+        BoxType::FTYP => _ = b.parse::<Ftyp>()?,
+        BoxType::STYP => _ = b.parse::<Styp>()?,
+        BoxType::SIDX => _ = b.parse::<Sidx>()?,
+        BoxType::EMSG => _ = b.parse::<Emsg>()?,
+        _ => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cm::iso::{self, BoxType, Boxes, Error, Writer};
+
+    #[test]
+    fn boxes() {
+        let mut w = Writer::new();
+        w.begin(BoxType::MOOV);
+        w.begin_full(BoxType::MFHD, 0, 0);
+        w.u32(3);
+        w.end();
+        w.begin(BoxType::FREE);
+        w.end();
+        w.end();
+        let data = w.into_inner();
+        assert_eq!(&data[..8], b"\0\0\0\x20moov");
+
+        let moov = Boxes::new(&data).next().unwrap().unwrap();
+        assert_eq!(moov.len(), data.len());
+        let kids: Vec<_> = moov.children().map(|b| b.unwrap()).collect();
+        assert_eq!(kids.len(), 2);
+        assert_eq!(kids[0].offset, 8);
+        assert_eq!(kids[0].full_header().unwrap(), (0, 0));
+        assert_eq!(kids[0].body()[4..], 3u32.to_be_bytes());
+        assert_eq!(kids[1].ty, BoxType::FREE);
+        assert!(moov.find(BoxType::MDAT).unwrap().is_none());
+        assert_eq!(
+            moov.child(BoxType::MDAT).unwrap_err(),
+            Error::MissingBox(BoxType::MDAT)
+        );
+
+        // size 0 extends to the end, size 1 has 64-bit size
+        let data = b"\0\0\0\0mdat\x01\x02";
+        let mdat = Boxes::new(data).next().unwrap().unwrap();
+        assert_eq!(mdat.body(), &[1, 2]);
+        let data = b"\0\0\0\x01mdat\0\0\0\0\0\0\0\x11\x07";
+        let mdat = Boxes::new(data).next().unwrap().unwrap();
+        assert_eq!(mdat.header_len, 16);
+        assert_eq!(mdat.body(), &[7]);
+
+        let data = b"\0\0\0\x10mdat\x01";
+        let mut it = Boxes::new(data);
+        assert_eq!(it.next().unwrap().unwrap_err(), Error::Truncated);
+        assert!(it.next().is_none());
+        assert_eq!(iso::validate(data).unwrap_err(), Error::Truncated);
+    }
+}
@@ -0,0 +1,1177 @@
+use super::{BoxRef, BoxType, Error, IsoBox, Reader, Result, Writer};
+
+const UNITY_MATRIX: [i32; 9] = [0x10000, 0, 0, 0, 0x10000, 0, 0, 0, 0x4000_0000];
+
+fn version_for(vals: &[u64]) -> u8 {
+    vals.iter().any(|&v| v > u32::MAX as u64) as u8
+}
+
+fn write_u32_or_u64(w: &mut Writer, version: u8, val: u64) {
+    if version == 0 {
+        w.u32(val as u32);
+    } else {
+        w.u64(val);
+    }
+}
+
+fn read_matrix(r: &mut Reader) -> Result<[i32; 9]> {
+    let mut res = [0; 9];
+    for v in res.iter_mut() {
+        *v = r.i32()?;
+    }
+    Ok(res)
+}
+
+fn write_matrix(w: &mut Writer, matrix: &[i32; 9]) {
+    for &v in matrix {
+        w.i32(v);
+    }
+}
+
+/// File type, `major_brand` and `compatible_brands`.
+#[doc(alias = "ftyp")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ftyp {
+    pub major_brand: [u8; 4],
+    pub minor_version: u32,
+    pub compatible_brands: Vec<[u8; 4]>,
+}
+
+impl IsoBox for Ftyp {
+    const TYPE: BoxType = BoxType::FTYP;
+
+    fn parse(b: &BoxRef) -> Result<Self> {
+        let mut r = Reader::new(b.body());
+        let major_brand = r.fourcc()?;
+        let minor_version = r.u32()?;
+        let mut compatible_brands = Vec::with_capacity(r.remaining() / 4);
+        while r.remaining() >= 4 {
+            compatible_brands.push(r.fourcc()?);
+        }
+        Ok(Self {
+            major_brand,
+            minor_version,
+            compatible_brands,
+        })
+    }
+
+    fn write(&self, w: &mut Writer) {
+        self.write_as(w, Self::TYPE);
+    }
+}
+
+impl Ftyp {
+    fn write_as(&self, w: &mut Writer, ty: BoxType) {
+        w.begin(ty);
+        w.fourcc(self.major_brand);
+        w.u32(self.minor_version);
+        for &brand in &self.compatible_brands {
+            w.fourcc(brand);
+        }
+        w.end();
+    }
+
+    pub fn is_compatible(&self, brand: &[u8; 4]) -> bool {
+        &self.major_brand == brand || self.compatible_brands.contains(brand)
+    }
+}
+
+/// Segment type, same layout as [`Ftyp`].
+#[doc(alias = "styp")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Styp(pub Ftyp);
+
+impl IsoBox for Styp {
+    const TYPE: BoxType = BoxType::STYP;
+
+    fn parse(b: &BoxRef) -> Result<Self> {
+        Ftyp::parse(b).map(Self)
+    }
+
+    fn write(&self, w: &mut Writer) {
+        self.0.write_as(w, Self::TYPE);
+    }
+}
+
+/// Movie header.
+#[doc(alias = "mvhd")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mvhd {
+    pub creation_time: u64,
+    pub modification_time: u64,
+    pub timescale: u32,
+    pub duration: u64,
+    /// Q16.16 playback rate.
+    pub rate: i32,
+    /// Q8.8 volume.
+    pub volume: i16,
+    pub matrix: [i32; 9],
+    pub next_track_id: u32,
+}
+
+impl Mvhd {
+    pub fn new(timescale: u32) -> Self {
+        Self {
+            creation_time: 0,
+            modification_time: 0,
+            timescale,
+            duration: 0,
+            rate: 0x10000,
+            volume: 0x100,
+            matrix: UNITY_MATRIX,
+            next_track_id: 1,
+        }
+    }
+}
+
+impl IsoBox for Mvhd {
+    const TYPE: BoxType = BoxType::MVHD;
+
+    fn parse(b: &BoxRef) -> Result<Self> {
+        let mut r = Reader::new(b.body());
+        let (version, _) = r.full_header()?;
+        let creation_time = r.u32_or_u64(version)?;
+        let modification_time = r.u32_or_u64(version)?;
+        let timescale = r.u32()?;
+        let duration = r.u32_or_u64(version)?;
+        let rate = r.i32()?;
+        let volume = r.i16()?;
+        r.skip(10)?;
+        let matrix = read_matrix(&mut r)?;
+        r.skip(24)?;
+        let next_track_id = r.u32()?;
+        Ok(Self {
+            creation_time,
+            modification_time,
+            timescale,
+            duration,
+            rate,
+            volume,
+            matrix,
+            next_track_id,
+        })
+    }
+
+    fn write(&self, w: &mut Writer) {
+        let v = version_for(&[self.creation_time, self.modification_time, self.duration]);
+        w.begin_full(Self::TYPE, v, 0);
+        write_u32_or_u64(w, v, self.creation_time);
+        write_u32_or_u64(w, v, self.modification_time);
+        w.u32(self.timescale);
+        write_u32_or_u64(w, v, self.duration);
+        w.i32(self.rate);
+        w.i16(self.volume);
+        w.zeros(10);
+        write_matrix(w, &self.matrix);
+        w.zeros(24);
+        w.u32(self.next_track_id);
+        w.end();
+    }
+}
+
+/// Track header.
+#[doc(alias = "tkhd")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tkhd {
+    /// 1 enabled, 2 in movie, 4 in preview.
+    pub flags: u32,
+    pub creation_time: u64,
+    pub modification_time: u64,
+    pub track_id: u32,
+    /// In movie timescale.
+    pub duration: u64,
+    pub layer: i16,
+    pub alternate_group: i16,
+    /// Q8.8 volume, 0 for video tracks.
+    pub volume: i16,
+    pub matrix: [i32; 9],
+    /// Q16.16 presentation width.
+    pub width: u32,
+    /// Q16.16 presentation height.
+    pub height: u32,
+}
+
+impl Tkhd {
+    pub fn new(track_id: u32) -> Self {
+        Self {
+            flags: 3,
+            creation_time: 0,
+            modification_time: 0,
+            track_id,
+            duration: 0,
+            layer: 0,
+            alternate_group: 0,
+            volume: 0,
+            matrix: UNITY_MATRIX,
+            width: 0,
+            height: 0,
+        }
+    }
+}
+
+impl IsoBox for Tkhd {
+    const TYPE: BoxType = BoxType::TKHD;
+
+    fn parse(b: &BoxRef) -> Result<Self> {
+        let mut r = Reader::new(b.body());
+        let (version, flags) = r.full_header()?;
+        let creation_time = r.u32_or_u64(version)?;
+        let modification_time = r.u32_or_u64(version)?;
+        let track_id = r.u32()?;
+        r.skip(4)?;
+        let duration = r.u32_or_u64(version)?;
+        r.skip(8)?;
+        let layer = r.i16()?;
+        let alternate_group = r.i16()?;
+        let volume = r.i16()?;
+        r.skip(2)?;
+        let matrix = read_matrix(&mut r)?;
+        Ok(Self {
+            flags,
+            creation_time,
+            modification_time,
+            track_id,
+            duration,
+            layer,
+            alternate_group,
+            volume,
+            matrix,
+            width: r.u32()?,
+            height: r.u32()?,
+        })
+    }
+
+    fn write(&self, w: &mut Writer) {
+        let v = version_for(&[self.creation_time, self.modification_time, self.duration]);
+        w.begin_full(Self::TYPE, v, self.flags);
+        write_u32_or_u64(w, v, self.creation_time);
+        write_u32_or_u64(w, v, self.modification_time);
+        w.u32(self.track_id);
+        w.zeros(4);
+        write_u32_or_u64(w, v, self.duration);
+        w.zeros(8);
+        w.i16(self.layer);
+        w.i16(self.alternate_group);
+        w.i16(self.volume);
+        w.zeros(2);
+        write_matrix(w, &self.matrix);
+        w.u32(self.width);
+        w.u32(self.height);
+        w.end();
+    }
+}
+
+/// Media header.
+#[doc(alias = "mdhd")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mdhd {
+    pub creation_time: u64,
+    pub modification_time: u64,
+    pub timescale: u32,
+    pub duration: u64,
+    /// ISO 639-2/T code, e.g. `b"und"`.
+    pub language: [u8; 3],
+}
+
+impl Mdhd {
+    pub fn new(timescale: u32) -> Self {
+        Self {
+            creation_time: 0,
+            modification_time: 0,
+            timescale,
+            duration: 0,
+            language: *b"und",
+        }
+    }
+}
+
+impl IsoBox for Mdhd {
+    const TYPE: BoxType = BoxType::MDHD;
+
+    fn parse(b: &BoxRef) -> Result<Self> {
+        let mut r = Reader::new(b.body());
+        let (version, _) = r.full_header()?;
+        let creation_time = r.u32_or_u64(version)?;
+        let modification_time = r.u32_or_u64(version)?;
+        let timescale = r.u32()?;
+        let duration = r.u32_or_u64(version)?;
+        let lang = r.u16()?;
+        let c = |shift: u16| ((lang >> shift) & 0x1f) as u8 + 0x60;
+        Ok(Self {
+            creation_time,
+            modification_time,
+            timescale,
+            duration,
+            language: [c(10), c(5), c(0)],
+        })
+    }
+
+    fn write(&self, w: &mut Writer) {
+        let v = version_for(&[self.creation_time, self.modification_time, self.duration]);
+        w.begin_full(Self::TYPE, v, 0);
+        write_u32_or_u64(w, v, self.creation_time);
+        write_u32_or_u64(w, v, self.modification_time);
+        w.u32(self.timescale);
+        write_u32_or_u64(w, v, self.duration);
+        let c = |i: usize| (self.language[i].wrapping_sub(0x60) & 0x1f) as u16;
+        w.u16(c(0) << 10 | c(1) << 5 | c(2));
+        w.u16(0);
+        w.end();
+    }
+}
+
+/// Handler reference, `b"vide"` or `b"soun"` for media tracks.
+#[doc(alias = "hdlr")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hdlr {
+    pub handler_type: [u8; 4],
+    pub name: String,
+}
+
+impl IsoBox for Hdlr {
+    const TYPE: BoxType = BoxType::HDLR;
+
+    fn parse(b: &BoxRef) -> Result<Self> {
+        let mut r = Reader::new(b.body());
+        r.full_header()?;
+        r.skip(4)?;
+        let handler_type = r.fourcc()?;
+        r.skip(12)?;
+        Ok(Self {
+            handler_type,
+            name: r.cstr()?,
+        })
+    }
+
+    fn write(&self, w: &mut Writer) {
+        w.begin_full(Self::TYPE, 0, 0);
+        w.zeros(4);
+        w.fourcc(self.handler_type);
+        w.zeros(12);
+        w.cstr(&self.name);
+        w.end();
+    }
+}
+
+/// Flags of a sample in movie fragments.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct SampleFlags(pub u32);
+
+impl SampleFlags {
+    /// Sync sample which doesn't depend on others.
+    pub const SYNC: Self = Self(0x0200_0000);
+    /// Non sync sample which depends on others.
+    pub const NON_SYNC: Self = Self(0x0101_0000);
+
+    #[inline]
+    pub fn is_sync(self) -> bool {
+        self.0 & 0x0001_0000 == 0
+    }
+
+    /// 1 depends on other samples, 2 doesn't, 0 unknown.
+    #[inline]
+    pub fn depends_on(self) -> u8 {
+        (self.0 >> 24 & 3) as u8
+    }
+
+    #[inline]
+    pub fn degradation_priority(self) -> u16 {
+        self.0 as u16
+    }
+}
+
+/// Track extends, defaults of track fragments.
+#[doc(alias = "trex")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trex {
+    pub track_id: u32,
+    pub default_sample_desc_index: u32,
+    pub default_sample_duration: u32,
+    pub default_sample_size: u32,
+    pub default_sample_flags: SampleFlags,
+}
+
+impl IsoBox for Trex {
+    const TYPE: BoxType = BoxType::TREX;
+
+    fn parse(b: &BoxRef) -> Result<Self> {
+        let mut r = Reader::new(b.body());
+        r.full_header()?;
+        Ok(Self {
+            track_id: r.u32()?,
+            default_sample_desc_index: r.u32()?,
+            default_sample_duration: r.u32()?,
+            default_sample_size: r.u32()?,
+            default_sample_flags: SampleFlags(r.u32()?),
+        })
+    }
+
+    fn write(&self, w: &mut Writer) {
+        w.begin_full(Self::TYPE, 0, 0);
+        w.u32(self.track_id);
+        w.u32(self.default_sample_desc_index);
+        w.u32(self.default_sample_duration);
+        w.u32(self.default_sample_size);
+        w.u32(self.default_sample_flags.0);
+        w.end();
+    }
+}
+
+/// Movie fragment header.
+#[doc(alias = "mfhd")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mfhd {
+    pub sequence_number: u32,
+}
+
+impl IsoBox for Mfhd {
+    const TYPE: BoxType = BoxType::MFHD;
+
+    fn parse(b: &BoxRef) -> Result<Self> {
+        let mut r = Reader::new(b.body());
+        r.full_header()?;
+        Ok(Self {
+            sequence_number: r.u32()?,
+        })
+    }
+
+    fn write(&self, w: &mut Writer) {
+        w.begin_full(Self::TYPE, 0, 0);
+        w.u32(self.sequence_number);
+        w.end();
+    }
+}
+
+/// Track fragment header, fields override [`Trex`] defaults.
+#[doc(alias = "tfhd")]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Tfhd {
+    pub track_id: u32,
+    pub base_data_offset: Option<u64>,
+    pub sample_desc_index: Option<u32>,
+    pub default_sample_duration: Option<u32>,
+    pub default_sample_size: Option<u32>,
+    pub default_sample_flags: Option<SampleFlags>,
+    pub duration_is_empty: bool,
+    /// Data offsets are relative to the enclosing `moof`.
+    pub default_base_is_moof: bool,
+}
+
+impl Tfhd {
+    const BASE_DATA_OFFSET: u32 = 0x1;
+    const SAMPLE_DESC_INDEX: u32 = 0x2;
+    const DEFAULT_DURATION: u32 = 0x8;
+    const DEFAULT_SIZE: u32 = 0x10;
+    const DEFAULT_FLAGS: u32 = 0x20;
+    const DURATION_IS_EMPTY: u32 = 0x1_0000;
+    const DEFAULT_BASE_IS_MOOF: u32 = 0x2_0000;
+}
+
+impl IsoBox for Tfhd {
+    const TYPE: BoxType = BoxType::TFHD;
+
+    fn parse(b: &BoxRef) -> Result<Self> {
+        let mut r = Reader::new(b.body());
+        let (_, flags) = r.full_header()?;
+        let track_id = r.u32()?;
+        let base_data_offset = if flags & Self::BASE_DATA_OFFSET != 0 {
+            Some(r.u64()?)
+        } else {
+            None
+        };
+        let mut opt = |flag: u32| (flags & flag != 0).then(|| r.u32()).transpose();
+        Ok(Self {
+            track_id,
+            base_data_offset,
+            sample_desc_index: opt(Self::SAMPLE_DESC_INDEX)?,
+            default_sample_duration: opt(Self::DEFAULT_DURATION)?,
+            default_sample_size: opt(Self::DEFAULT_SIZE)?,
+            default_sample_flags: opt(Self::DEFAULT_FLAGS)?.map(SampleFlags),
+            duration_is_empty: flags & Self::DURATION_IS_EMPTY != 0,
+            default_base_is_moof: flags & Self::DEFAULT_BASE_IS_MOOF != 0,
+        })
+    }
+
+    fn write(&self, w: &mut Writer) {
+        let flag = |set: bool, flag: u32| if set { flag } else { 0 };
+        let flags = flag(self.base_data_offset.is_some(), Self::BASE_DATA_OFFSET)
+            | flag(self.sample_desc_index.is_some(), Self::SAMPLE_DESC_INDEX)
+            | flag(
+                self.default_sample_duration.is_some(),
+                Self::DEFAULT_DURATION,
+            )
+            | flag(self.default_sample_size.is_some(), Self::DEFAULT_SIZE)
+            | flag(self.default_sample_flags.is_some(), Self::DEFAULT_FLAGS)
+            | flag(self.duration_is_empty, Self::DURATION_IS_EMPTY)
+            | flag(self.default_base_is_moof, Self::DEFAULT_BASE_IS_MOOF);
+        w.begin_full(Self::TYPE, 0, flags);
+        w.u32(self.track_id);
+        if let Some(v) = self.base_data_offset {
+            w.u64(v);
+        }
+        for v in [
+            self.sample_desc_index,
+            self.default_sample_duration,
+            self.default_sample_size,
+            self.default_sample_flags.map(|f| f.0),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            w.u32(v);
+        }
+        w.end();
+    }
+}
+
+/// Track fragment decode time.
+#[doc(alias = "tfdt")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tfdt {
+    /// Decode time of the first sample in media timescale.
+    pub base_media_decode_time: u64,
+}
+
+impl IsoBox for Tfdt {
+    const TYPE: BoxType = BoxType::TFDT;
+
+    fn parse(b: &BoxRef) -> Result<Self> {
+        let mut r = Reader::new(b.body());
+        let (version, _) = r.full_header()?;
+        Ok(Self {
+            base_media_decode_time: r.u32_or_u64(version)?,
+        })
+    }
+
+    fn write(&self, w: &mut Writer) {
+        let v = version_for(&[self.base_media_decode_time]);
+        w.begin_full(Self::TYPE, v, 0);
+        write_u32_or_u64(w, v, self.base_media_decode_time);
+        w.end();
+    }
+}
+
+/// Sample of [`Trun`], absent fields come from [`Tfhd`] or [`Trex`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TrunEntry {
+    pub duration: Option<u32>,
+    pub size: Option<u32>,
+    pub flags: Option<SampleFlags>,
+    pub cts_offset: Option<i32>,
+}
+
+/// Track fragment run.
+///
+/// All entries must have the same fields set, the first entry decides.
+#[doc(alias = "trun")]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Trun {
+    /// Offset of the first sample data from the base data offset.
+    pub data_offset: Option<i32>,
+    pub first_sample_flags: Option<SampleFlags>,
+    pub entries: Vec<TrunEntry>,
+}
+
+impl Trun {
+    const DATA_OFFSET: u32 = 0x1;
+    const FIRST_SAMPLE_FLAGS: u32 = 0x4;
+    const DURATION: u32 = 0x100;
+    const SIZE: u32 = 0x200;
+    const FLAGS: u32 = 0x400;
+    const CTS_OFFSET: u32 = 0x800;
+
+    /// Flags of the sample at `index`, if set in the run.
+    pub fn sample_flags(&self, index: usize) -> Option<SampleFlags> {
+        match (index, self.first_sample_flags) {
+            (0, Some(flags)) => Some(flags),
+            _ => self.entries.get(index)?.flags,
+        }
+    }
+}
+
+impl IsoBox for Trun {
+    const TYPE: BoxType = BoxType::TRUN;
+
+    fn parse(b: &BoxRef) -> Result<Self> {
+        let mut r = Reader::new(b.body());
+        let (_, flags) = r.full_header()?;
+        let count = r.u32()? as usize;
+        let data_offset = (flags & Self::DATA_OFFSET != 0)
+            .then(|| r.i32())
+            .transpose()?;
+        let first_sample_flags = (flags & Self::FIRST_SAMPLE_FLAGS != 0)
+            .then(|| r.u32().map(SampleFlags))
+            .transpose()?;
+        let per_sample = [Self::DURATION, Self::SIZE, Self::FLAGS, Self::CTS_OFFSET]
+            .iter()
+            .filter(|&&f| flags & f != 0)
+            .count();
+        if count.saturating_mul(per_sample * 4) > r.remaining() {
+            return Err(Error::InvalidBox(Self::TYPE));
+        }
+        let mut entries = Vec::with_capacity(count);
+        for _ in 0..count {
+            let mut opt = |flag: u32| (flags & flag != 0).then(|| r.u32()).transpose();
+            entries.push(TrunEntry {
+                duration: opt(Self::DURATION)?,
+                size: opt(Self::SIZE)?,
+                flags: opt(Self::FLAGS)?.map(SampleFlags),
+                // version 0 offsets are unsigned but negative ones are common
+                cts_offset: opt(Self::CTS_OFFSET)?.map(|v| v as i32),
+            });
+        }
+        Ok(Self {
+            data_offset,
+            first_sample_flags,
+            entries,
+        })
+    }
+
+    fn write(&self, w: &mut Writer) {
+        let first = self.entries.first().copied().unwrap_or_default();
+        let flag = |set: bool, flag: u32| if set { flag } else { 0 };
+        let flags = flag(self.data_offset.is_some(), Self::DATA_OFFSET)
+            | flag(self.first_sample_flags.is_some(), Self::FIRST_SAMPLE_FLAGS)
+            | flag(first.duration.is_some(), Self::DURATION)
+            | flag(first.size.is_some(), Self::SIZE)
+            | flag(first.flags.is_some(), Self::FLAGS)
+            | flag(first.cts_offset.is_some(), Self::CTS_OFFSET);
+        let negative = self.entries.iter().any(|e| e.cts_offset.unwrap_or(0) < 0);
+        w.begin_full(Self::TYPE, negative as u8, flags);
+        w.u32(self.entries.len() as u32);
+        if let Some(v) = self.data_offset {
+            w.i32(v);
+        }
+        if let Some(v) = self.first_sample_flags {
+            w.u32(v.0);
+        }
+        for e in &self.entries {
+            if flags & Self::DURATION != 0 {
+                w.u32(e.duration.unwrap_or_default());
+            }
+            if flags & Self::SIZE != 0 {
+                w.u32(e.size.unwrap_or_default());
+            }
+            if flags & Self::FLAGS != 0 {
+                w.u32(e.flags.unwrap_or_default().0);
+            }
+            if flags & Self::CTS_OFFSET != 0 {
+                w.i32(e.cts_offset.unwrap_or_default());
+            }
+        }
+        w.end();
+    }
+}
+
+/// Reference of [`Sidx`] to a subsegment or another `sidx`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SidxRef {
+    /// Reference is to another `sidx`.
+    pub is_index: bool,
+    pub size: u32,
+    pub duration: u32,
+    pub starts_with_sap: bool,
+    pub sap_type: u8,
+    pub sap_delta_time: u32,
+}
+
+/// Segment index.
+#[doc(alias = "sidx")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sidx {
+    pub reference_id: u32,
+    pub timescale: u32,
+    pub earliest_presentation_time: u64,
+    /// Distance from the end of this box to the first referenced byte.
+    pub first_offset: u64,
+    pub references: Vec<SidxRef>,
+}
+
+impl IsoBox for Sidx {
+    const TYPE: BoxType = BoxType::SIDX;
+
+    fn parse(b: &BoxRef) -> Result<Self> {
+        let mut r = Reader::new(b.body());
+        let (version, _) = r.full_header()?;
+        let reference_id = r.u32()?;
+        let timescale = r.u32()?;
+        let earliest_presentation_time = r.u32_or_u64(version)?;
+        let first_offset = r.u32_or_u64(version)?;
+        r.skip(2)?;
+        let count = r.u16()? as usize;
+        if count * 12 > r.remaining() {
+            return Err(Error::InvalidBox(Self::TYPE));
+        }
+        let mut references = Vec::with_capacity(count);
+        for _ in 0..count {
+            let size = r.u32()?;
+            let duration = r.u32()?;
+            let sap = r.u32()?;
+            references.push(SidxRef {
+                is_index: size >> 31 != 0,
+                size: size & 0x7fff_ffff,
+                duration,
+                starts_with_sap: sap >> 31 != 0,
+                sap_type: (sap >> 28 & 7) as u8,
+                sap_delta_time: sap & 0x0fff_ffff,
+            });
+        }
+        Ok(Self {
+            reference_id,
+            timescale,
+            earliest_presentation_time,
+            first_offset,
+            references,
+        })
+    }
+
+    fn write(&self, w: &mut Writer) {
+        let v = version_for(&[self.earliest_presentation_time, self.first_offset]);
+        w.begin_full(Self::TYPE, v, 0);
+        w.u32(self.reference_id);
+        w.u32(self.timescale);
+        write_u32_or_u64(w, v, self.earliest_presentation_time);
+        write_u32_or_u64(w, v, self.first_offset);
+        w.u16(0);
+        w.u16(self.references.len() as u16);
+        for r in &self.references {
+            w.u32((r.is_index as u32) << 31 | r.size & 0x7fff_ffff);
+            w.u32(r.duration);
+            w.u32(
+                (r.starts_with_sap as u32) << 31
+                    | (r.sap_type as u32 & 7) << 28
+                    | r.sap_delta_time & 0x0fff_ffff,
+            );
+        }
+        w.end();
+    }
+}
+
+/// Presentation time of [`Emsg`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmsgTime {
+    /// Version 0, relative to the earliest presentation time of the segment.
+    Delta(u32),
+    /// Version 1, in media timeline.
+    Absolute(u64),
+}
+
+/// Event message.
+#[doc(alias = "emsg")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Emsg {
+    pub scheme_id_uri: String,
+    pub value: String,
+    pub timescale: u32,
+    pub presentation_time: EmsgTime,
+    pub event_duration: u32,
+    pub id: u32,
+    pub message_data: Vec<u8>,
+}
+
+impl IsoBox for Emsg {
+    const TYPE: BoxType = BoxType::EMSG;
+
+    fn parse(b: &BoxRef) -> Result<Self> {
+        let mut r = Reader::new(b.body());
+        let (version, _) = r.full_header()?;
+        match version {
+            0 => {
+                let scheme_id_uri = r.cstr()?;
+                let value = r.cstr()?;
+                Ok(Self {
+                    scheme_id_uri,
+                    value,
+                    timescale: r.u32()?,
+                    presentation_time: EmsgTime::Delta(r.u32()?),
+                    event_duration: r.u32()?,
+                    id: r.u32()?,
+                    message_data: r.rest().to_vec(),
+                })
+            }
+            1 => {
+                let timescale = r.u32()?;
+                let presentation_time = EmsgTime::Absolute(r.u64()?);
+                let event_duration = r.u32()?;
+                let id = r.u32()?;
+                Ok(Self {
+                    scheme_id_uri: r.cstr()?,
+                    value: r.cstr()?,
+                    timescale,
+                    presentation_time,
+                    event_duration,
+                    id,
+                    message_data: r.rest().to_vec(),
+                })
+            }
+            _ => Err(Error::InvalidBox(Self::TYPE)),
+        }
+    }
+
+    fn write(&self, w: &mut Writer) {
+        match self.presentation_time {
+            EmsgTime::Delta(delta) => {
+                w.begin_full(Self::TYPE, 0, 0);
+                w.cstr(&self.scheme_id_uri);
+                w.cstr(&self.value);
+                w.u32(self.timescale);
+                w.u32(delta);
+                w.u32(self.event_duration);
+                w.u32(self.id);
+            }
+            EmsgTime::Absolute(time) => {
+                w.begin_full(Self::TYPE, 1, 0);
+                w.u32(self.timescale);
+                w.u64(time);
+                w.u32(self.event_duration);
+                w.u32(self.id);
+                w.cstr(&self.scheme_id_uri);
+                w.cstr(&self.value);
+            }
+        }
+        w.bytes(&self.message_data);
+        w.end();
+    }
+}
+
+/// Run of samples with the same duration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeToSample {
+    pub count: u32,
+    pub delta: u32,
+}
+
+/// Decoding time to sample.
+#[doc(alias = "stts")]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Stts {
+    pub entries: Vec<TimeToSample>,
+}
+
+/// Run of samples with the same composition offset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompositionOffset {
+    pub count: u32,
+    pub offset: i32,
+}
+
+/// Composition time to sample.
+#[doc(alias = "ctts")]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Ctts {
+    pub entries: Vec<CompositionOffset>,
+}
+
+/// Run of chunks with the same number of samples.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SampleToChunk {
+    /// 1-based index of the first chunk of the run.
+    pub first_chunk: u32,
+    pub samples_per_chunk: u32,
+    /// 1-based index into `stsd`.
+    pub sample_desc_index: u32,
+}
+
+/// Sample to chunk.
+#[doc(alias = "stsc")]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Stsc {
+    pub entries: Vec<SampleToChunk>,
+}
+
+/// Sync samples, all samples are sync if the box is absent.
+#[doc(alias = "stss")]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Stss {
+    /// 1-based sample numbers in ascending order.
+    pub sample_numbers: Vec<u32>,
+}
+
+/// Reads entry count and checks it against the remaining data.
+fn table<'a>(b: &BoxRef<'a>, entry_len: usize) -> Result<(Reader<'a>, usize)> {
+    let mut r = Reader::new(b.body());
+    r.full_header()?;
+    let count = r.u32()? as usize;
+    if count.saturating_mul(entry_len) > r.remaining() {
+        return Err(Error::InvalidBox(b.ty));
+    }
+    Ok((r, count))
+}
+
+impl IsoBox for Stts {
+    const TYPE: BoxType = BoxType::STTS;
+
+    fn parse(b: &BoxRef) -> Result<Self> {
+        let (mut r, count) = table(b, 8)?;
+        let mut entries = Vec::with_capacity(count);
+        for _ in 0..count {
+            entries.push(TimeToSample {
+                count: r.u32()?,
+                delta: r.u32()?,
+            });
+        }
+        Ok(Self { entries })
+    }
+
+    fn write(&self, w: &mut Writer) {
+        w.begin_full(Self::TYPE, 0, 0);
+        w.u32(self.entries.len() as u32);
+        for e in &self.entries {
+            w.u32(e.count);
+            w.u32(e.delta);
+        }
+        w.end();
+    }
+}
+
+impl IsoBox for Ctts {
+    const TYPE: BoxType = BoxType::CTTS;
+
+    fn parse(b: &BoxRef) -> Result<Self> {
+        let (mut r, count) = table(b, 8)?;
+        let mut entries = Vec::with_capacity(count);
+        for _ in 0..count {
+            entries.push(CompositionOffset {
+                count: r.u32()?,
+                offset: r.i32()?,
+            });
+        }
+        Ok(Self { entries })
+    }
+
+    fn write(&self, w: &mut Writer) {
+        let negative = self.entries.iter().any(|e| e.offset < 0);
+        w.begin_full(Self::TYPE, negative as u8, 0);
+        w.u32(self.entries.len() as u32);
+        for e in &self.entries {
+            w.u32(e.count);
+            w.i32(e.offset);
+        }
+        w.end();
+    }
+}
+
+impl IsoBox for Stsc {
+    const TYPE: BoxType = BoxType::STSC;
+
+    fn parse(b: &BoxRef) -> Result<Self> {
+        let (mut r, count) = table(b, 12)?;
+        let mut entries = Vec::with_capacity(count);
+        for _ in 0..count {
+            entries.push(SampleToChunk {
+                first_chunk: r.u32()?,
+                samples_per_chunk: r.u32()?,
+                sample_desc_index: r.u32()?,
+            });
+        }
+        Ok(Self { entries })
+    }
+
+    fn write(&self, w: &mut Writer) {
+        w.begin_full(Self::TYPE, 0, 0);
+        w.u32(self.entries.len() as u32);
+        for e in &self.entries {
+            w.u32(e.first_chunk);
+            w.u32(e.samples_per_chunk);
+            w.u32(e.sample_desc_index);
+        }
+        w.end();
+    }
+}
+
+impl IsoBox for Stss {
+    const TYPE: BoxType = BoxType::STSS;
+
+    fn parse(b: &BoxRef) -> Result<Self> {
+        let (mut r, count) = table(b, 4)?;
+        let mut sample_numbers = Vec::with_capacity(count);
+        for _ in 0..count {
+            sample_numbers.push(r.u32()?);
+        }
+        Ok(Self { sample_numbers })
+    }
+
+    fn write(&self, w: &mut Writer) {
+        w.begin_full(Self::TYPE, 0, 0);
+        w.u32(self.sample_numbers.len() as u32);
+        for &n in &self.sample_numbers {
+            w.u32(n);
+        }
+        w.end();
+    }
+}
+
+/// Sample sizes.
+#[doc(alias = "stsz")]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Stsz {
+    /// Size of every sample or 0 if `sizes` are listed.
+    pub sample_size: u32,
+    pub sample_count: u32,
+    pub sizes: Vec<u32>,
+}
+
+impl Stsz {
+    pub fn size(&self, index: usize) -> Option<u32> {
+        match self.sample_size {
+            0 => self.sizes.get(index).copied(),
+            size => (index < self.sample_count as usize).then_some(size),
+        }
+    }
+}
+
+impl IsoBox for Stsz {
+    const TYPE: BoxType = BoxType::STSZ;
+
+    fn parse(b: &BoxRef) -> Result<Self> {
+        let mut r = Reader::new(b.body());
+        r.full_header()?;
+        let sample_size = r.u32()?;
+        let sample_count = r.u32()?;
+        let mut sizes = Vec::new();
+        if sample_size == 0 {
+            if sample_count as usize > r.remaining() / 4 {
+                return Err(Error::InvalidBox(Self::TYPE));
+            }
+            sizes.reserve(sample_count as usize);
+            for _ in 0..sample_count {
+                sizes.push(r.u32()?);
+            }
+        }
+        Ok(Self {
+            sample_size,
+            sample_count,
+            sizes,
+        })
+    }
+
+    fn write(&self, w: &mut Writer) {
+        w.begin_full(Self::TYPE, 0, 0);
+        w.u32(self.sample_size);
+        w.u32(self.sample_count);
+        if self.sample_size == 0 {
+            for &size in &self.sizes {
+                w.u32(size);
+            }
+        }
+        w.end();
+    }
+}
+
+/// Chunk offsets of `stco` or `co64`.
+///
+/// Written as `co64` only if some offset doesn't fit 32 bits.
+#[doc(alias = "stco")]
+#[doc(alias = "co64")]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ChunkOffsets {
+    pub offsets: Vec<u64>,
+}
+
+impl ChunkOffsets {
+    /// Adds `delta` to all offsets, e.g. after moving `moov` before `mdat`.
+    pub fn shift(&mut self, delta: i64) -> Result {
+        for offset in self.offsets.iter_mut() {
+            *offset = offset.checked_add_signed(delta).ok_or(Error::Overflow)?;
+        }
+        Ok(())
+    }
+}
+
+impl IsoBox for ChunkOffsets {
+    const TYPE: BoxType = BoxType::STCO;
+
+    fn accepts(ty: BoxType) -> bool {
+        ty == BoxType::STCO || ty == BoxType::CO64
+    }
+
+    fn parse(b: &BoxRef) -> Result<Self> {
+        let large = b.ty == BoxType::CO64;
+        let (mut r, count) = table(b, if large { 8 } else { 4 })?;
+        let mut offsets = Vec::with_capacity(count);
+        for _ in 0..count {
+            offsets.push(if large { r.u64()? } else { r.u32()? as u64 });
+        }
+        Ok(Self { offsets })
+    }
+
+    fn write(&self, w: &mut Writer) {
+        let large = version_for(&self.offsets) != 0;
+        w.begin_full(if large { BoxType::CO64 } else { BoxType::STCO }, 0, 0);
+        w.u32(self.offsets.len() as u32);
+        for &offset in &self.offsets {
+            write_u32_or_u64(w, large as u8, offset);
+        }
+        w.end();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cm::iso::{
+        Boxes, ChunkOffsets, Emsg, EmsgTime, IsoBox, Mdhd, SampleFlags, Trun, TrunEntry,
+    };
+
+    fn round_trip<T: IsoBox + PartialEq + std::fmt::Debug>(val: &T) -> Vec<u8> {
+        let bytes = val.to_bytes();
+        let b = Boxes::new(&bytes).next().unwrap().unwrap();
+        assert_eq!(b.ty, T::TYPE);
+        assert_eq!(&b.parse::<T>().unwrap(), val);
+        bytes
+    }
+
+    #[test]
+    fn write_parse() {
+        let mut mdhd = Mdhd::new(90000);
+        mdhd.language = *b"eng";
+        let bytes = round_trip(&mdhd);
+        assert_eq!(bytes[8], 0);
+
+        mdhd.duration = u32::MAX as u64 + 1;
+        let bytes = round_trip(&mdhd);
+        assert_eq!(bytes[8], 1);
+
+        let trun = Trun {
+            data_offset: Some(100),
+            first_sample_flags: Some(SampleFlags::SYNC),
+            entries: vec![
+                TrunEntry {
+                    size: Some(10),
+                    cts_offset: Some(-1000),
+                    ..Default::default()
+                },
+                TrunEntry {
+                    size: Some(20),
+                    cts_offset: Some(2000),
+                    ..Default::default()
+                },
+            ],
+        };
+        let bytes = round_trip(&trun);
+        assert_eq!(bytes[8], 1);
+        assert!(trun.sample_flags(0).unwrap().is_sync());
+        assert!(trun.sample_flags(1).is_none());
+        assert!(!SampleFlags::NON_SYNC.is_sync());
+
+        let emsg = Emsg {
+            scheme_id_uri: "urn:test".to_string(),
+            value: "1".to_string(),
+            timescale: 1000,
+            presentation_time: EmsgTime::Delta(40),
+            event_duration: 0xffff_ffff,
+            id: 1,
+            message_data: b"hi".to_vec(),
+        };
+        round_trip(&emsg);
+
+        let mut offsets = ChunkOffsets {
+            offsets: vec![8, 1000],
+        };
+        assert_eq!(&round_trip(&offsets)[4..8], b"stco");
+        offsets.shift(u32::MAX as i64).unwrap();
+        let bytes = offsets.to_bytes();
+        assert_eq!(&bytes[4..8], b"co64");
+        assert!(offsets.shift(-(u32::MAX as i64) - 9).is_err());
+    }
+}
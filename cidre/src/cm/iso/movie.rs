@@ -0,0 +1,907 @@
+use std::ops::Range;
+
+use crate::cm;
+
+use super::{
+    BoxRef, BoxType, Boxes, ChunkOffsets, Ctts, Emsg, Error, Ftyp, Hdlr, Mdhd, Mfhd, Mvhd, Result,
+    Sidx, Stsc, Stsd, Stss, Stsz, Stts, Tfdt, Tfhd, Tkhd, Trex, Trun,
+};
+
+/// Sample resolved from sample tables or track fragment runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sample {
+    /// Offset of sample data in the file or segment.
+    pub offset: u64,
+    pub size: u32,
+    /// Decode timestamp in media timescale.
+    pub dts: u64,
+    /// Presentation timestamp minus decode timestamp.
+    pub cts_offset: i32,
+    pub duration: u32,
+    pub is_sync: bool,
+    /// 1-based index into `stsd`.
+    pub desc_index: u32,
+}
+
+impl Sample {
+    /// Presentation timestamp in media timescale.
+    #[inline]
+    pub fn pts(&self) -> i64 {
+        self.dts as i64 + self.cts_offset as i64
+    }
+
+    #[inline]
+    pub fn range(&self) -> Range<u64> {
+        self.offset..self.offset + self.size as u64
+    }
+
+    #[inline]
+    pub fn decode_time(&self, timescale: u32) -> cm::Time {
+        cm::Time::new(self.dts as i64, timescale as i32)
+    }
+
+    #[inline]
+    pub fn presentation_time(&self, timescale: u32) -> cm::Time {
+        cm::Time::new(self.pts(), timescale as i32)
+    }
+
+    #[inline]
+    pub fn duration_time(&self, timescale: u32) -> cm::Time {
+        cm::Time::new(self.duration as i64, timescale as i32)
+    }
+
+    pub fn timing_info(&self, timescale: u32) -> cm::SampleTimingInfo {
+        cm::SampleTimingInfo {
+            duration: self.duration_time(timescale),
+            pts: self.presentation_time(timescale),
+            dts: self.decode_time(timescale),
+        }
+    }
+}
+
+/// Tables of `stbl` except sample descriptions.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SampleTable {
+    pub stts: Stts,
+    pub ctts: Option<Ctts>,
+    pub stsc: Stsc,
+    pub stsz: Stsz,
+    pub chunk_offsets: ChunkOffsets,
+    pub stss: Option<Stss>,
+}
+
+impl SampleTable {
+    pub fn parse(stbl: &BoxRef) -> Result<Self> {
+        let mut chunk_offsets = None;
+        for b in stbl.children() {
+            let b = b?;
+            if b.ty == BoxType::STCO || b.ty == BoxType::CO64 {
+                chunk_offsets = Some(b.parse()?);
+            }
+        }
+        let ctts = stbl.find(BoxType::CTTS)?;
+        let stss = stbl.find(BoxType::STSS)?;
+        Ok(Self {
+            stts: stbl.child(BoxType::STTS)?.parse()?,
+            ctts: ctts.map(|b| b.parse()).transpose()?,
+            stsc: stbl.child(BoxType::STSC)?.parse()?,
+            stsz: stbl.child(BoxType::STSZ)?.parse()?,
+            chunk_offsets: chunk_offsets.ok_or(Error::MissingBox(BoxType::STCO))?,
+            stss: stss.map(|b| b.parse()).transpose()?,
+        })
+    }
+
+    pub fn sample_count(&self) -> usize {
+        self.stsz.sample_count as usize
+    }
+
+    /// Resolves all tables into samples in decode order.
+    pub fn samples(&self) -> Result<Vec<Sample>> {
+        self.samples_within(u64::MAX)
+    }
+
+    /// Resolves all tables into samples with data ending within `len` bytes,
+    /// e.g. length of the file.
+    pub fn samples_within(&self, len: u64) -> Result<Vec<Sample>> {
+        let count = self.sample_count();
+        let timed: u64 = self.stts.entries.iter().map(|e| e.count as u64).sum();
+        if timed != count as u64 {
+            return Err(Error::InvalidBox(BoxType::STTS));
+        }
+        match self.stsz.sample_size {
+            0 if self.stsz.sizes.len() != count => return Err(Error::InvalidBox(BoxType::STSZ)),
+            0 => {}
+            size if count as u64 * size as u64 > len => {
+                return Err(Error::InvalidBox(BoxType::STSZ))
+            }
+            _ => {}
+        }
+
+        // count of fixed size samples is not bounded by the box size
+        let mut res = Vec::with_capacity(self.stsz.sizes.len());
+        let offsets = &self.chunk_offsets.offsets;
+        let runs = &self.stsc.entries;
+        for (i, run) in runs.iter().enumerate() {
+            let last_chunk = match runs.get(i + 1) {
+                Some(next) => next.first_chunk,
+                None => offsets.len() as u32 + 1,
+            };
+            if run.first_chunk == 0 || last_chunk < run.first_chunk {
+                return Err(Error::InvalidBox(BoxType::STSC));
+            }
+            for chunk in run.first_chunk..last_chunk {
+                let Some(&chunk_offset) = offsets.get(chunk as usize - 1) else {
+                    return Err(Error::InvalidBox(BoxType::STSC));
+                };
+                let mut offset = chunk_offset;
+                for _ in 0..run.samples_per_chunk {
+                    let Some(size) = self.stsz.size(res.len()) else {
+                        break;
+                    };
+                    let end = offset
+                        .checked_add(size as u64)
+                        .filter(|&end| end <= len)
+                        .ok_or(Error::InvalidBox(BoxType::STCO))?;
+                    res.push(Sample {
+                        offset,
+                        size,
+                        dts: 0,
+                        cts_offset: 0,
+                        duration: 0,
+                        is_sync: self.stss.is_none(),
+                        desc_index: run.sample_desc_index,
+                    });
+                    offset = end;
+                }
+            }
+        }
+        if res.len() != count {
+            return Err(Error::InvalidBox(BoxType::STSC));
+        }
+
+        let mut samples = res.iter_mut();
+        let mut dts = 0u64;
+        for e in &self.stts.entries {
+            for s in samples.by_ref().take(e.count as usize) {
+                s.dts = dts;
+                s.duration = e.delta;
+                dts = dts
+                    .checked_add(e.delta as u64)
+                    .ok_or(Error::InvalidBox(BoxType::STTS))?;
+            }
+        }
+        if let Some(ctts) = &self.ctts {
+            let mut samples = res.iter_mut();
+            for e in &ctts.entries {
+                for s in samples.by_ref().take(e.count as usize) {
+                    s.cts_offset = e.offset;
+                }
+            }
+        }
+        if let Some(stss) = &self.stss {
+            for &n in &stss.sample_numbers {
+                let s = n
+                    .checked_sub(1)
+                    .and_then(|i| res.get_mut(i as usize))
+                    .ok_or(Error::InvalidBox(BoxType::STSS))?;
+                s.is_sync = true;
+            }
+        }
+        Ok(res)
+    }
+}
+
+/// Track of `moov`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Track {
+    pub tkhd: Tkhd,
+    pub mdhd: Mdhd,
+    pub hdlr: Hdlr,
+    pub stsd: Stsd,
+    pub table: SampleTable,
+}
+
+impl Track {
+    pub fn parse(trak: &BoxRef) -> Result<Self> {
+        let mdia = trak.child(BoxType::MDIA)?;
+        let stbl = mdia.child(BoxType::MINF)?.child(BoxType::STBL)?;
+        Ok(Self {
+            tkhd: trak.child(BoxType::TKHD)?.parse()?,
+            mdhd: mdia.child(BoxType::MDHD)?.parse()?,
+            hdlr: mdia.child(BoxType::HDLR)?.parse()?,
+            stsd: stbl.child(BoxType::STSD)?.parse()?,
+            table: SampleTable::parse(&stbl)?,
+        })
+    }
+
+    #[inline]
+    pub fn id(&self) -> u32 {
+        self.tkhd.track_id
+    }
+
+    #[inline]
+    pub fn timescale(&self) -> u32 {
+        self.mdhd.timescale
+    }
+
+    #[inline]
+    pub fn is_video(&self) -> bool {
+        &self.hdlr.handler_type == b"vide"
+    }
+
+    #[inline]
+    pub fn is_audio(&self) -> bool {
+        &self.hdlr.handler_type == b"soun"
+    }
+
+    /// Samples of the sample tables, empty for fragmented tracks.
+    pub fn samples(&self) -> Result<Vec<Sample>> {
+        self.table.samples()
+    }
+
+    /// Samples with data ending within `len` bytes, see [`SampleTable::samples_within`].
+    pub fn samples_within(&self, len: u64) -> Result<Vec<Sample>> {
+        self.table.samples_within(len)
+    }
+
+    #[inline]
+    pub fn duration(&self) -> cm::Time {
+        cm::Time::new(self.mdhd.duration as i64, self.mdhd.timescale as i32)
+    }
+}
+
+/// Movie of a progressive file or an init segment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Movie {
+    pub ftyp: Option<Ftyp>,
+    pub mvhd: Mvhd,
+    pub tracks: Vec<Track>,
+    /// Fragment defaults of `mvex`, empty for non fragmented movies.
+    pub trex: Vec<Trex>,
+}
+
+impl Movie {
+    /// Parses `ftyp` and `moov` of the file.
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        let mut ftyp = None;
+        for b in Boxes::new(data) {
+            let b = b?;
+            match b.ty {
+                BoxType::FTYP => ftyp = Some(b.parse()?),
+                BoxType::MOOV => {
+                    let mut res = Self::parse_moov(&b)?;
+                    res.ftyp = ftyp;
+                    return Ok(res);
+                }
+                _ => {}
+            }
+        }
+        Err(Error::MissingBox(BoxType::MOOV))
+    }
+
+    pub fn parse_moov(moov: &BoxRef) -> Result<Self> {
+        let mut tracks = Vec::new();
+        let mut trex = Vec::new();
+        for b in moov.children() {
+            let b = b?;
+            match b.ty {
+                BoxType::TRAK => tracks.push(Track::parse(&b)?),
+                BoxType::MVEX => {
+                    for b in b.children() {
+                        let b = b?;
+                        if b.ty == BoxType::TREX {
+                            trex.push(b.parse()?);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(Self {
+            ftyp: None,
+            mvhd: moov.child(BoxType::MVHD)?.parse()?,
+            tracks,
+            trex,
+        })
+    }
+
+    pub fn track(&self, id: u32) -> Option<&Track> {
+        self.tracks.iter().find(|t| t.id() == id)
+    }
+
+    pub fn trex(&self, track_id: u32) -> Option<&Trex> {
+        self.trex.iter().find(|t| t.track_id == track_id)
+    }
+
+    #[inline]
+    pub fn is_fragmented(&self) -> bool {
+        !self.trex.is_empty()
+    }
+}
+
+/// Track fragment of `traf`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrackFragment {
+    pub tfhd: Tfhd,
+    pub tfdt: Option<Tfdt>,
+    pub truns: Vec<Trun>,
+}
+
+impl TrackFragment {
+    pub fn parse(traf: &BoxRef) -> Result<Self> {
+        let mut truns = Vec::new();
+        for b in traf.children() {
+            let b = b?;
+            if b.ty == BoxType::TRUN {
+                truns.push(b.parse()?);
+            }
+        }
+        let tfdt = traf.find(BoxType::TFDT)?;
+        Ok(Self {
+            tfhd: traf.child(BoxType::TFHD)?.parse()?,
+            tfdt: tfdt.map(|b| b.parse()).transpose()?,
+            truns,
+        })
+    }
+
+    #[inline]
+    pub fn track_id(&self) -> u32 {
+        self.tfhd.track_id
+    }
+
+    /// Resolves runs into samples with defaults of `tfhd` and `trex`.
+    ///
+    /// Without base data offset in `tfhd` data offsets are relative to `moof_offset`.
+    /// Decode times start at `tfdt` or 0.
+    pub fn samples(&self, moof_offset: u64, trex: Option<&Trex>) -> Result<Vec<Sample>> {
+        let tfhd = &self.tfhd;
+        let base = tfhd.base_data_offset.unwrap_or(moof_offset);
+        let duration = tfhd
+            .default_sample_duration
+            .or(trex.map(|t| t.default_sample_duration));
+        let size = tfhd
+            .default_sample_size
+            .or(trex.map(|t| t.default_sample_size));
+        let flags = tfhd
+            .default_sample_flags
+            .or(trex.map(|t| t.default_sample_flags))
+            .unwrap_or_default();
+        let desc_index = tfhd
+            .sample_desc_index
+            .or(trex.map(|t| t.default_sample_desc_index))
+            .unwrap_or(1);
+
+        let mut res = Vec::new();
+        let mut dts = self.tfdt.as_ref().map_or(0, |t| t.base_media_decode_time);
+        let mut offset = base;
+        for trun in &self.truns {
+            if let Some(data_offset) = trun.data_offset {
+                offset = base
+                    .checked_add_signed(data_offset as i64)
+                    .ok_or(Error::InvalidBox(BoxType::TRUN))?;
+            }
+            for (i, e) in trun.entries.iter().enumerate() {
+                let duration = e
+                    .duration
+                    .or(duration)
+                    .ok_or(Error::MissingBox(BoxType::TREX))?;
+                let size = e.size.or(size).ok_or(Error::MissingBox(BoxType::TREX))?;
+                res.push(Sample {
+                    offset,
+                    size,
+                    dts,
+                    cts_offset: e.cts_offset.unwrap_or(0),
+                    duration,
+                    is_sync: trun.sample_flags(i).unwrap_or(flags).is_sync(),
+                    desc_index,
+                });
+                offset = offset
+                    .checked_add(size as u64)
+                    .ok_or(Error::InvalidBox(BoxType::TRUN))?;
+                dts = dts
+                    .checked_add(duration as u64)
+                    .ok_or(Error::InvalidBox(BoxType::TRUN))?;
+            }
+        }
+        Ok(res)
+    }
+}
+
+/// Movie fragment of `moof`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fragment {
+    /// Offset of `moof` in the parsed buffer.
+    pub offset: u64,
+    pub sequence_number: u32,
+    pub tracks: Vec<TrackFragment>,
+}
+
+impl Fragment {
+    pub fn parse(moof: &BoxRef) -> Result<Self> {
+        let mfhd: Mfhd = moof.child(BoxType::MFHD)?.parse()?;
+        let mut tracks = Vec::new();
+        for b in moof.children() {
+            let b = b?;
+            if b.ty == BoxType::TRAF {
+                tracks.push(TrackFragment::parse(&b)?);
+            }
+        }
+        Ok(Self {
+            offset: moof.offset as u64,
+            sequence_number: mfhd.sequence_number,
+            tracks,
+        })
+    }
+
+    pub fn track(&self, track_id: u32) -> Option<&TrackFragment> {
+        self.tracks.iter().find(|t| t.track_id() == track_id)
+    }
+
+    /// Samples of the track, see [`TrackFragment::samples`].
+    pub fn samples(&self, track_id: u32, trex: Option<&Trex>) -> Result<Vec<Sample>> {
+        match self.track(track_id) {
+            Some(t) => t.samples(self.offset, trex),
+            None => Ok(Vec::new()),
+        }
+    }
+}
+
+/// Media segment or fragmented file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment {
+    pub styp: Option<Ftyp>,
+    pub sidx: Vec<Sidx>,
+    pub emsg: Vec<Emsg>,
+    pub fragments: Vec<Fragment>,
+    /// Body ranges of `mdat` boxes.
+    pub mdat: Vec<Range<usize>>,
+}
+
+impl Segment {
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        let mut res = Self {
+            styp: None,
+            sidx: Vec::new(),
+            emsg: Vec::new(),
+            fragments: Vec::new(),
+            mdat: Vec::new(),
+        };
+        for b in Boxes::new(data) {
+            let b = b?;
+            match b.ty {
+                BoxType::STYP => res.styp = Some(b.parse::<super::Styp>()?.0),
+                BoxType::SIDX => res.sidx.push(b.parse()?),
+                BoxType::EMSG => res.emsg.push(b.parse()?),
+                BoxType::MOOF => res.fragments.push(Fragment::parse(&b)?),
+                BoxType::MDAT => res
+                    .mdat
+                    .push(b.body_offset()..b.body_offset() + b.body().len()),
+                _ => {}
+            }
+        }
+        Ok(res)
+    }
+
+    /// Samples of the track in all fragments.
+    pub fn samples(&self, track_id: u32, trex: Option<&Trex>) -> Result<Vec<Sample>> {
+        let mut res = Vec::new();
+        for f in &self.fragments {
+            res.extend(f.samples(track_id, trex)?);
+        }
+        Ok(res)
+    }
+}
+
+/// Visits boxes of `moof`s of the buffer.
+fn each_moof_child<'a>(
+    data: &'a [u8],
+    mut f: impl FnMut(&BoxRef<'a>, &BoxRef<'a>) -> Result,
+) -> Result {
+    for moof in Boxes::new(data) {
+        let moof = moof?;
+        if moof.ty != BoxType::MOOF {
+            continue;
+        }
+        for b in moof.children() {
+            f(&moof, &b?)?;
+        }
+    }
+    Ok(())
+}
+
+/// Shifts `tfdt` of the track in all fragments in place, so the first one is
+/// `time` and later ones keep their distance to it.
+///
+/// Returns number of patched boxes. Version 0 boxes can't hold times
+/// over 32 bits, nothing is patched in this case.
+pub fn set_base_decode_time(data: &mut [u8], track_id: u32, time: u64) -> Result<usize> {
+    let mut fields = Vec::new();
+    let mut first = None;
+    each_moof_child(data, |_, traf| {
+        if traf.ty != BoxType::TRAF {
+            return Ok(());
+        }
+        let tfhd: Tfhd = traf.child(BoxType::TFHD)?.parse()?;
+        if tfhd.track_id != track_id {
+            return Ok(());
+        }
+        if let Some(tfdt) = traf.find(BoxType::TFDT)? {
+            let (version, _) = tfdt.full_header()?;
+            let at = tfdt.body_offset() + 4;
+            let len = if version == 0 { 4 } else { 8 };
+            if tfdt.body().len() < 4 + len {
+                return Err(Error::InvalidBox(BoxType::TFDT));
+            }
+            let old = tfdt.body()[4..4 + len]
+                .iter()
+                .fold(0u64, |acc, b| acc << 8 | *b as u64);
+            let first = *first.get_or_insert(old);
+            let new: u64 = (old as i128 - first as i128 + time as i128)
+                .try_into()
+                .map_err(|_| Error::Overflow)?;
+            if version == 0 && new > u32::MAX as u64 {
+                return Err(Error::Overflow);
+            }
+            fields.push((at..at + len, new));
+        }
+        Ok(())
+    })?;
+    for (range, time) in &fields {
+        let len = range.len();
+        data[range.clone()].copy_from_slice(&time.to_be_bytes()[8 - len..]);
+    }
+    Ok(fields.len())
+}
+
+/// Numbers `mfhd` of all fragments in place starting from `first`.
+///
+/// Returns number of patched boxes.
+pub fn set_sequence_number(data: &mut [u8], first: u32) -> Result<usize> {
+    let mut fields = Vec::new();
+    each_moof_child(data, |_, mfhd| {
+        if mfhd.ty == BoxType::MFHD {
+            if mfhd.body().len() < 8 {
+                return Err(Error::InvalidBox(BoxType::MFHD));
+            }
+            fields.push(mfhd.body_offset() + 4);
+        }
+        Ok(())
+    })?;
+    for (i, &at) in fields.iter().enumerate() {
+        let n = first.wrapping_add(i as u32);
+        data[at..at + 4].copy_from_slice(&n.to_be_bytes());
+    }
+    Ok(fields.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cm::{
+        self,
+        iso::{self, BoxType, Boxes, EmsgTime, Error, IsoBox, Movie, SampleEntry, Segment},
+    };
+
+    const INIT: &[u8] = include_bytes!("../../../tests/fixtures/iso/init.mp4");
+    const SEGMENT: &[u8] = include_bytes!("../../../tests/fixtures/iso/segment.m4s");
+    const PROGRESSIVE: &[u8] = include_bytes!("../../../tests/fixtures/iso/progressive.mp4");
+
+    #[test]
+    fn init_segment() {
+        iso::validate(INIT).unwrap();
+        let movie = Movie::parse(INIT).unwrap();
+        let ftyp = movie.ftyp.as_ref().unwrap();
+        assert_eq!(&ftyp.major_brand, b"iso6");
+        assert!(ftyp.is_compatible(b"cmfc"));
+        assert!(movie.is_fragmented());
+        assert_eq!(movie.mvhd.timescale, 1000);
+        assert_eq!(movie.tracks.len(), 2);
+
+        let video = movie.track(1).unwrap();
+        assert!(video.is_video());
+        assert_eq!(video.timescale(), 90000);
+        assert_eq!(video.tkhd.width >> 16, 640);
+        assert!(video.samples().unwrap().is_empty());
+        let SampleEntry::Visual(avc1) = &video.stsd.entries[0] else {
+            panic!("visual entry expected");
+        };
+        assert_eq!(avc1.ty, BoxType::AVC1);
+        assert_eq!((avc1.width, avc1.height), (640, 360));
+        assert_eq!(avc1.compressor_name, "cidre video");
+        assert!(avc1.child(BoxType(*b"pasp")).is_some());
+        let avcc = avc1.avc_config().unwrap().unwrap();
+        assert_eq!((avcc.profile, avcc.level, avcc.nal_len), (100, 31, 4));
+        assert_eq!(avcc.sps.len(), 1);
+        assert_eq!(avcc.sps[0][0] & 0x1f, 7);
+        assert_eq!(avcc.pps, vec![vec![0x68, 0xeb, 0xe3, 0xcb, 0x22, 0xc0]]);
+        assert_eq!(avcc.to_bytes().unwrap(), avc1.child(BoxType::AVCC).unwrap());
+        let too_many = iso::AvcConfig {
+            sps: vec![avcc.sps[0].clone(); 32],
+            ..avcc.clone()
+        };
+        assert_eq!(too_many.to_bytes().unwrap_err(), Error::Overflow);
+
+        let audio = movie.track(2).unwrap();
+        assert!(audio.is_audio());
+        let SampleEntry::Audio(mp4a) = &audio.stsd.entries[0] else {
+            panic!("audio entry expected");
+        };
+        assert_eq!((mp4a.channels, mp4a.sample_rate), (2, 48000));
+        assert_eq!(mp4a.magic_cookie().unwrap()[0], 3);
+
+        let trex = movie.trex(1).unwrap();
+        assert_eq!(trex.default_sample_duration, 3000);
+        assert!(!trex.default_sample_flags.is_sync());
+
+        // typed boxes are written back as they are
+        let moov = Boxes::new(INIT).find(BoxType::MOOV).unwrap().unwrap();
+        let mut stack = vec![moov];
+        let mut checked = 0;
+        while let Some(b) = stack.pop() {
+            if b.ty.is_container() {
+                stack.extend(b.children().map(|b| b.unwrap()));
+                continue;
+            }
+            let bytes = match b.ty {
+                BoxType::MVHD => b.parse::<iso::Mvhd>().unwrap().to_bytes(),
+                BoxType::TKHD => b.parse::<iso::Tkhd>().unwrap().to_bytes(),
+                BoxType::MDHD => b.parse::<iso::Mdhd>().unwrap().to_bytes(),
+                BoxType::HDLR => b.parse::<iso::Hdlr>().unwrap().to_bytes(),
+                BoxType::STSD => b.parse::<iso::Stsd>().unwrap().to_bytes(),
+                BoxType::STTS => b.parse::<iso::Stts>().unwrap().to_bytes(),
+                BoxType::STSC => b.parse::<iso::Stsc>().unwrap().to_bytes(),
+                BoxType::STSZ => b.parse::<iso::Stsz>().unwrap().to_bytes(),
+                BoxType::STCO => b.parse::<iso::ChunkOffsets>().unwrap().to_bytes(),
+                BoxType::TREX => b.parse::<iso::Trex>().unwrap().to_bytes(),
+                _ => continue,
+            };
+            assert_eq!(bytes, b.bytes(), "{}", b.ty);
+            checked += 1;
+        }
+        assert_eq!(checked, 19);
+    }
+
+    #[test]
+    fn media_segment() {
+        // two CMAF chunks with I P B B reordering, see tests/fixtures/iso/gen.py
+        iso::validate(SEGMENT).unwrap();
+        let movie = Movie::parse(INIT).unwrap();
+        let seg = Segment::parse(SEGMENT).unwrap();
+        assert_eq!(&seg.styp.as_ref().unwrap().major_brand, b"msdh");
+
+        let sidx = &seg.sidx[0];
+        assert_eq!((sidx.reference_id, sidx.timescale), (1, 90000));
+        assert_eq!(sidx.earliest_presentation_time, 186000);
+        assert_eq!(sidx.references[0].duration, 24000);
+        assert!(sidx.references[0].starts_with_sap);
+        assert_eq!(sidx.references[0].sap_type, 1);
+
+        let emsg = &seg.emsg[0];
+        assert_eq!(emsg.scheme_id_uri, "urn:scte:scte35:2013:bin");
+        assert_eq!(emsg.value, "1");
+        assert_eq!(emsg.presentation_time, EmsgTime::Absolute(186000));
+        assert_eq!(emsg.message_data, [0xfc, 0x30]);
+
+        let seqs: Vec<_> = seg.fragments.iter().map(|f| f.sequence_number).collect();
+        assert_eq!(seqs, [7, 8]);
+        assert_eq!(
+            sidx.references[0].size as usize,
+            SEGMENT.len() - seg.fragments[0].offset as usize
+        );
+        let samples = seg.samples(1, movie.trex(1)).unwrap();
+        assert!(seg.samples(2, movie.trex(2)).unwrap().is_empty());
+        assert_eq!(samples.len(), 8);
+        let dts: Vec<_> = samples.iter().map(|s| s.dts).collect();
+        assert_eq!(
+            dts,
+            [180000, 183000, 186000, 189000, 192000, 195000, 198000, 201000]
+        );
+        let pts: Vec<_> = samples.iter().map(|s| s.pts()).collect();
+        assert_eq!(
+            pts,
+            [186000, 195000, 189000, 192000, 204000, 198000, 201000, 207000]
+        );
+        let min_pts = pts.iter().min().unwrap();
+        assert_eq!(*min_pts as u64, sidx.earliest_presentation_time);
+        let sync: Vec<_> = samples.iter().map(|s| s.is_sync).collect();
+        assert_eq!(
+            sync,
+            [true, false, false, false, false, false, false, false]
+        );
+        assert_eq!(seg.mdat.len(), 2);
+        assert_eq!(samples[0].offset, seg.mdat[0].start as u64);
+        assert_eq!(samples[3].range().end, seg.mdat[0].end as u64);
+        assert_eq!(samples[4].offset, seg.mdat[1].start as u64);
+        assert_eq!(samples[7].range().end, seg.mdat[1].end as u64);
+        for (i, s) in samples.iter().enumerate() {
+            let data = &SEGMENT[s.range().start as usize..s.range().end as usize];
+            assert!(data.iter().all(|&b| b == i as u8 + 1));
+        }
+        let timing = samples[1].timing_info(movie.track(1).unwrap().timescale());
+        assert_eq!(timing.dts, cm::Time::new(183000, 90000));
+        assert_eq!(timing.pts, cm::Time::new(195000, 90000));
+        assert_eq!(timing.duration, cm::Time::new(3000, 90000));
+    }
+
+    #[test]
+    fn progressive() {
+        iso::validate(PROGRESSIVE).unwrap();
+        let movie = Movie::parse(PROGRESSIVE).unwrap();
+        assert!(!movie.is_fragmented());
+
+        let opus = movie.track(1).unwrap();
+        let SampleEntry::Audio(entry) = &opus.stsd.entries[0] else {
+            panic!("audio entry expected");
+        };
+        assert_eq!(entry.ty, BoxType::OPUS);
+        let config = entry.opus_config().unwrap().unwrap();
+        assert_eq!((config.channels, config.pre_skip), (2, 312));
+        assert_eq!(config.to_bytes(), entry.child(BoxType::DOPS).unwrap());
+        assert_eq!(opus.duration(), cm::Time::new(2880, 48000));
+        let samples = opus.samples().unwrap();
+        let sizes: Vec<_> = samples.iter().map(|s| s.size).collect();
+        assert_eq!(sizes, [5, 6, 7]);
+        for (s, b) in samples.iter().zip([0xa0, 0xa1, 0xa2]) {
+            let data = &PROGRESSIVE[s.range().start as usize..s.range().end as usize];
+            assert!(data.iter().all(|&v| v == b));
+            assert!(s.is_sync);
+        }
+        assert_eq!(samples[2].dts, 1920);
+
+        let hevc = movie.track(2).unwrap();
+        let SampleEntry::Visual(entry) = &hevc.stsd.entries[0] else {
+            panic!("visual entry expected");
+        };
+        let hvcc = entry.hevc_config().unwrap().unwrap();
+        assert_eq!(hvcc.nal_len, 4);
+        let types: Vec<_> = hvcc.arrays.iter().map(|a| a.nal_type).collect();
+        assert_eq!(types, [32, 33, 34]);
+        assert_eq!(hvcc.param_sets().count(), 3);
+        let samples = hevc.samples().unwrap();
+        let sync: Vec<_> = samples.iter().map(|s| s.is_sync).collect();
+        assert_eq!(sync, [true, false, false]);
+        assert_eq!(
+            samples[1].presentation_time(hevc.timescale()),
+            cm::Time::new(3000, 30000)
+        );
+        for (s, b) in samples.iter().zip([0xb0, 0xb1, 0xb2]) {
+            assert_eq!(PROGRESSIVE[s.offset as usize], b);
+        }
+        assert!(hevc.table.chunk_offsets.offsets[0] < u32::MAX as u64);
+    }
+
+    #[test]
+    fn patch() {
+        let mut seg = SEGMENT.to_vec();
+        let time = u32::MAX as u64 * 2;
+        assert_eq!(iso::set_base_decode_time(&mut seg, 1, time).unwrap(), 2);
+        assert_eq!(iso::set_base_decode_time(&mut seg, 2, 0).unwrap(), 0);
+        assert_eq!(iso::set_sequence_number(&mut seg, 100).unwrap(), 2);
+        let parsed = Segment::parse(&seg).unwrap();
+        assert_eq!(parsed.fragments[0].sequence_number, 100);
+        let samples = parsed.samples(1, None).unwrap();
+        let dts: Vec<_> = samples.iter().map(|s| s.dts - time).collect();
+        assert_eq!(dts, [0, 3000, 6000, 9000, 12000, 15000, 18000, 21000]);
+        assert_eq!(seg.len(), SEGMENT.len());
+
+        // moving back keeps fragments apart too
+        assert_eq!(iso::set_base_decode_time(&mut seg, 1, 0).unwrap(), 2);
+        let parsed = Segment::parse(&seg).unwrap();
+        let tfdt: Vec<_> = parsed
+            .fragments
+            .iter()
+            .map(|f| f.tracks[0].tfdt.as_ref().unwrap().base_media_decode_time)
+            .collect();
+        assert_eq!(tfdt, [0, 12000]);
+
+        // version 0 tfdt
+        let mut w = iso::Writer::new();
+        w.begin(BoxType::MOOF);
+        iso::Mfhd { sequence_number: 1 }.write(&mut w);
+        w.begin(BoxType::TRAF);
+        iso::Tfhd {
+            track_id: 1,
+            ..Default::default()
+        }
+        .write(&mut w);
+        iso::Tfdt {
+            base_media_decode_time: 10,
+        }
+        .write(&mut w);
+        w.end();
+        w.end();
+        let mut moof = w.into_inner();
+        assert_eq!(
+            iso::set_base_decode_time(&mut moof, 1, time).unwrap_err(),
+            Error::Overflow
+        );
+        assert_eq!(iso::set_base_decode_time(&mut moof, 1, 20).unwrap(), 1);
+        let seg = Segment::parse(&moof).unwrap();
+        let tfdt = seg.fragments[0].tracks[0].tfdt.as_ref().unwrap();
+        assert_eq!(tfdt.base_media_decode_time, 20);
+    }
+
+    #[test]
+    fn invalid() {
+        let mut data = PROGRESSIVE.to_vec();
+        // sample count of stsz in the audio track
+        let moov = Boxes::new(PROGRESSIVE)
+            .find(BoxType::MOOV)
+            .unwrap()
+            .unwrap();
+        let at = moov.bytes().windows(4).position(|w| w == b"stsz").unwrap();
+        let at = moov.offset + at + 12;
+        data[at + 3] = 4;
+        assert_eq!(
+            iso::validate(&data).unwrap_err(),
+            Error::InvalidBox(BoxType::STSZ)
+        );
+        assert!(Movie::parse(&SEGMENT[..20]).is_err());
+
+        // fixed size samples are bounded by the data length
+        let mut table = iso::SampleTable {
+            stts: iso::Stts {
+                entries: vec![iso::TimeToSample {
+                    count: u32::MAX,
+                    delta: 1,
+                }],
+            },
+            stsc: iso::Stsc {
+                entries: vec![iso::SampleToChunk {
+                    first_chunk: 1,
+                    samples_per_chunk: u32::MAX,
+                    sample_desc_index: 1,
+                }],
+            },
+            chunk_offsets: iso::ChunkOffsets { offsets: vec![0] },
+            ..Default::default()
+        };
+        table.stsz.sample_size = 1;
+        table.stsz.sample_count = u32::MAX;
+        assert_eq!(
+            table.samples_within(100).unwrap_err(),
+            Error::InvalidBox(BoxType::STSZ)
+        );
+
+        // co64 offset past address space
+        table.stts.entries[0].count = 2;
+        table.stsz.sample_count = 2;
+        table.chunk_offsets.offsets[0] = u64::MAX - 1;
+        assert_eq!(
+            table.samples().unwrap_err(),
+            Error::InvalidBox(BoxType::STCO)
+        );
+
+        let traf = iso::TrackFragment {
+            tfhd: iso::Tfhd {
+                track_id: 1,
+                default_sample_duration: Some(1),
+                default_sample_size: Some(1),
+                ..Default::default()
+            },
+            tfdt: Some(iso::Tfdt {
+                base_media_decode_time: u64::MAX,
+            }),
+            truns: vec![iso::Trun {
+                entries: vec![Default::default(); 2],
+                ..Default::default()
+            }],
+        };
+        assert_eq!(
+            traf.samples(0, None).unwrap_err(),
+            Error::InvalidBox(BoxType::TRUN)
+        );
+
+        // nested containers
+        let mut w = iso::Writer::new();
+        for _ in 0..100 {
+            w.begin(BoxType::TRAK);
+        }
+        for _ in 0..100 {
+            w.end();
+        }
+        assert_eq!(
+            iso::validate(&w.into_inner()).unwrap_err(),
+            Error::InvalidBox(BoxType::TRAK)
+        );
+    }
+}
@@ -0,0 +1,632 @@
+use crate::{arc, cf, cm};
+
+#[cfg(feature = "cat")]
+use crate::cat;
+
+use super::{BoxRef, BoxType, Error, IsoBox, RawBox, Reader, Result, Writer};
+
+/// Sample descriptions of a track.
+#[doc(alias = "stsd")]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Stsd {
+    pub entries: Vec<SampleEntry>,
+}
+
+impl IsoBox for Stsd {
+    const TYPE: BoxType = BoxType::STSD;
+
+    fn parse(b: &BoxRef) -> Result<Self> {
+        let mut r = Reader::new(b.body());
+        r.full_header()?;
+        let count = r.u32()? as usize;
+        let mut entries = Vec::with_capacity(count.min(16));
+        for entry in b.children_at(8).take(count) {
+            entries.push(SampleEntry::parse(&entry?)?);
+        }
+        if entries.len() != count {
+            return Err(Error::InvalidBox(Self::TYPE));
+        }
+        Ok(Self { entries })
+    }
+
+    fn write(&self, w: &mut Writer) {
+        w.begin_full(Self::TYPE, 0, 0);
+        w.u32(self.entries.len() as u32);
+        for e in &self.entries {
+            e.write(w);
+        }
+        w.end();
+    }
+}
+
+/// Entry of [`Stsd`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SampleEntry {
+    Visual(VisualSampleEntry),
+    Audio(AudioSampleEntry),
+    /// Entry of other media or unknown codec.
+    Other(RawBox),
+}
+
+impl SampleEntry {
+    pub fn parse(b: &BoxRef) -> Result<Self> {
+        let res = match &b.ty.0 {
+            b"avc1" | b"avc3" | b"hvc1" | b"hev1" | b"dvh1" | b"dvhe" | b"av01" | b"vp09"
+            | b"mp4v" | b"encv" => Self::Visual(VisualSampleEntry::parse(b)?),
+            b"mp4a" | b"Opus" | b"fLaC" | b"alac" | b"ac-3" | b"ec-3" | b"ipcm" | b"fpcm"
+            | b"enca" => match AudioSampleEntry::parse(b) {
+                Ok(entry) => Self::Audio(entry),
+                // QuickTime version 2 entries are kept as is
+                Err(Error::InvalidBox(_)) if b.body().get(8..10) == Some(&[0, 2]) => {
+                    Self::Other(b.to_raw())
+                }
+                Err(e) => return Err(e),
+            },
+            _ => Self::Other(b.to_raw()),
+        };
+        Ok(res)
+    }
+
+    pub fn ty(&self) -> BoxType {
+        match self {
+            Self::Visual(e) => e.ty,
+            Self::Audio(e) => e.ty,
+            Self::Other(e) => e.ty,
+        }
+    }
+
+    pub fn write(&self, w: &mut Writer) {
+        match self {
+            Self::Visual(e) => e.write(w),
+            Self::Audio(e) => e.write(w),
+            Self::Other(e) => e.write(w),
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut w = Writer::new();
+        self.write(&mut w);
+        w.into_inner()
+    }
+
+    /// Parses `VerbatimISOSampleEntry` extension of the format description.
+    pub fn with_format_desc(desc: &cm::FormatDesc) -> Option<Result<Self>> {
+        let data = desc.verbatim_iso_sample_entry()?;
+        let b = match super::Boxes::new(data.as_slice()).next()? {
+            Ok(b) => b,
+            Err(e) => return Some(Err(e)),
+        };
+        Some(Self::parse(&b))
+    }
+}
+
+fn child(children: &[RawBox], ty: BoxType) -> Option<&[u8]> {
+    children
+        .iter()
+        .find(|b| b.ty == ty)
+        .map(|b| b.body.as_slice())
+}
+
+fn parse_children(b: &BoxRef, skip: usize) -> Result<Vec<RawBox>> {
+    b.children_at(skip).map(|c| Ok(c?.to_raw())).collect()
+}
+
+/// Video sample entry, e.g. `avc1` or `hvc1`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VisualSampleEntry {
+    pub ty: BoxType,
+    pub data_ref_index: u16,
+    pub width: u16,
+    pub height: u16,
+    /// Q16.16 pixels per inch.
+    pub horiz_resolution: u32,
+    /// Q16.16 pixels per inch.
+    pub vert_resolution: u32,
+    pub frame_count: u16,
+    pub compressor_name: String,
+    pub depth: u16,
+    /// Codec configuration and other extension boxes, e.g. `avcC` and `pasp`.
+    pub children: Vec<RawBox>,
+}
+
+impl VisualSampleEntry {
+    const FIELDS_LEN: usize = 78;
+
+    pub fn new(ty: BoxType, width: u16, height: u16) -> Self {
+        Self {
+            ty,
+            data_ref_index: 1,
+            width,
+            height,
+            horiz_resolution: 0x48_0000,
+            vert_resolution: 0x48_0000,
+            frame_count: 1,
+            compressor_name: String::new(),
+            depth: 0x18,
+            children: Vec::new(),
+        }
+    }
+
+    pub fn parse(b: &BoxRef) -> Result<Self> {
+        let mut r = Reader::new(b.body());
+        r.skip(6)?;
+        let data_ref_index = r.u16()?;
+        r.skip(16)?;
+        let width = r.u16()?;
+        let height = r.u16()?;
+        let horiz_resolution = r.u32()?;
+        let vert_resolution = r.u32()?;
+        r.skip(4)?;
+        let frame_count = r.u16()?;
+        let name = r.bytes(32).map_err(|_| Error::InvalidBox(b.ty))?;
+        let name = &name[1..1 + (name[0] as usize).min(31)];
+        let depth = r.u16()?;
+        r.skip(2)?;
+        Ok(Self {
+            ty: b.ty,
+            data_ref_index,
+            width,
+            height,
+            horiz_resolution,
+            vert_resolution,
+            frame_count,
+            compressor_name: String::from_utf8_lossy(name).into_owned(),
+            depth,
+            children: parse_children(b, Self::FIELDS_LEN)?,
+        })
+    }
+
+    pub fn write(&self, w: &mut Writer) {
+        w.begin(self.ty);
+        w.zeros(6);
+        w.u16(self.data_ref_index);
+        w.zeros(16);
+        w.u16(self.width);
+        w.u16(self.height);
+        w.u32(self.horiz_resolution);
+        w.u32(self.vert_resolution);
+        w.zeros(4);
+        w.u16(self.frame_count);
+        let name = self.compressor_name.as_bytes();
+        let len = name.len().min(31);
+        w.u8(len as u8);
+        w.bytes(&name[..len]);
+        w.zeros(31 - len);
+        w.u16(self.depth);
+        w.i16(-1);
+        for c in &self.children {
+            c.write(w);
+        }
+        w.end();
+    }
+
+    /// Body of the child box of type `ty`.
+    pub fn child(&self, ty: BoxType) -> Option<&[u8]> {
+        child(&self.children, ty)
+    }
+
+    pub fn avc_config(&self) -> Option<Result<AvcConfig>> {
+        self.child(BoxType::AVCC).map(AvcConfig::parse)
+    }
+
+    pub fn hevc_config(&self) -> Option<Result<HevcConfig>> {
+        self.child(BoxType::HVCC).map(HevcConfig::parse)
+    }
+
+    /// `SampleDescriptionExtensionAtoms` extension with all child boxes.
+    pub fn ext_atoms(&self) -> arc::R<cf::DictionaryMut> {
+        let mut atoms = cf::DictionaryMut::with_capacity(self.children.len());
+        for c in &self.children {
+            let key = cf::String::from_str(&c.ty.to_string());
+            let Some(val) = cf::Data::from_slice(&c.body) else {
+                continue;
+            };
+            atoms.insert(&key, val.as_type_ref());
+        }
+        atoms
+    }
+
+    /// Format description with codec type, dimensions and extension atoms of the entry.
+    pub fn format_desc(&self) -> Result<arc::R<cm::VideoFormatDesc>> {
+        let atoms = self.ext_atoms();
+        let ext = cf::DictionaryOf::with_keys_values(
+            &[cm::FormatDescExtKey::sample_desc_ext_atoms()],
+            &[atoms.as_type_ref()],
+        );
+        let desc = cm::VideoFormatDesc::video(
+            cm::VideoCodec::from_be_bytes(&self.ty.0),
+            self.width as i32,
+            self.height as i32,
+            Some(&ext),
+        )?;
+        Ok(desc)
+    }
+}
+
+/// Audio sample entry, e.g. `mp4a` or `Opus`.
+///
+/// QuickTime version 1 fields are kept in `qt_ext`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AudioSampleEntry {
+    pub ty: BoxType,
+    pub data_ref_index: u16,
+    /// QuickTime sound description version, 0 in ISO files.
+    pub version: u16,
+    pub channels: u16,
+    pub sample_size: u16,
+    /// Sample rate in Hz.
+    pub sample_rate: u16,
+    pub qt_ext: Vec<u8>,
+    /// Codec configuration and other extension boxes, e.g. `esds` and `dOps`.
+    pub children: Vec<RawBox>,
+}
+
+impl AudioSampleEntry {
+    pub fn new(ty: BoxType, channels: u16, sample_rate: u16) -> Self {
+        Self {
+            ty,
+            data_ref_index: 1,
+            version: 0,
+            channels,
+            sample_size: 16,
+            sample_rate,
+            qt_ext: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+
+    pub fn parse(b: &BoxRef) -> Result<Self> {
+        let mut r = Reader::new(b.body());
+        r.skip(6)?;
+        let data_ref_index = r.u16()?;
+        let version = r.u16()?;
+        r.skip(6)?;
+        let channels = r.u16()?;
+        let sample_size = r.u16()?;
+        r.skip(4)?;
+        let sample_rate = r.u16()?;
+        r.skip(2)?;
+        let qt_ext = match version {
+            0 => &[][..],
+            1 => r.bytes(16)?,
+            _ => return Err(Error::InvalidBox(b.ty)),
+        };
+        Ok(Self {
+            ty: b.ty,
+            data_ref_index,
+            version,
+            channels,
+            sample_size,
+            sample_rate,
+            qt_ext: qt_ext.to_vec(),
+            children: parse_children(b, r.pos())?,
+        })
+    }
+
+    pub fn write(&self, w: &mut Writer) {
+        w.begin(self.ty);
+        w.zeros(6);
+        w.u16(self.data_ref_index);
+        w.u16(self.version);
+        w.zeros(6);
+        w.u16(self.channels);
+        w.u16(self.sample_size);
+        w.zeros(4);
+        w.u16(self.sample_rate);
+        w.u16(0);
+        w.bytes(&self.qt_ext);
+        for c in &self.children {
+            c.write(w);
+        }
+        w.end();
+    }
+
+    /// Body of the child box of type `ty`.
+    pub fn child(&self, ty: BoxType) -> Option<&[u8]> {
+        child(&self.children, ty)
+    }
+
+    pub fn opus_config(&self) -> Option<Result<OpusConfig>> {
+        self.child(BoxType::DOPS).map(OpusConfig::parse)
+    }
+
+    /// ES descriptor of `esds` without version and flags, the AAC magic cookie.
+    pub fn magic_cookie(&self) -> Option<&[u8]> {
+        self.child(BoxType::ESDS)?.get(4..)
+    }
+
+    #[cfg(feature = "cat")]
+    pub fn esds(&self) -> Option<Result<cat::audio::aac::Esds>> {
+        let esds = self.child(BoxType::ESDS)?;
+        Some(cat::audio::aac::Esds::parse(esds).map_err(|_| Error::InvalidBox(BoxType::ESDS)))
+    }
+
+    /// Stream format of `mp4a` with AAC config and `Opus` entries.
+    ///
+    /// Opus packets are assumed to be 20 ms long.
+    #[cfg(feature = "cat")]
+    pub fn asbd(&self) -> Result<cat::audio::StreamBasicDesc> {
+        match self.ty {
+            BoxType::MP4A => {
+                let esds = self.esds().ok_or(Error::MissingBox(BoxType::ESDS))??;
+                let asc = cat::audio::aac::AudioSpecificConfig::parse(&esds.asc)
+                    .map_err(|_| Error::InvalidBox(BoxType::ESDS))?;
+                Ok(asc.to_asbd())
+            }
+            BoxType::OPUS => {
+                let config = self
+                    .opus_config()
+                    .ok_or(Error::MissingBox(BoxType::DOPS))??;
+                Ok(config.to_head().asbd(960))
+            }
+            ty => Err(Error::InvalidBox(ty)),
+        }
+    }
+
+    /// Format description with [`asbd`](Self::asbd) and magic cookie of the entry.
+    #[cfg(feature = "cat")]
+    pub fn format_desc(&self) -> Result<arc::R<cm::AudioFormatDesc>> {
+        let asbd = self.asbd()?;
+        let cookie = self.magic_cookie().unwrap_or_default();
+        let desc = unsafe {
+            crate::os::result_unchecked(|res| {
+                cm::AudioFormatDesc::audio_in(
+                    &asbd,
+                    0,
+                    None,
+                    cookie.len(),
+                    (!cookie.is_empty()).then(|| &*cookie.as_ptr().cast::<std::ffi::c_void>()),
+                    None,
+                    res,
+                    None,
+                )
+            })
+        }?;
+        Ok(desc)
+    }
+}
+
+/// AVC decoder configuration record of `avcC`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AvcConfig {
+    pub profile: u8,
+    pub compatibility: u8,
+    pub level: u8,
+    /// Length of NAL unit size prefix in bytes.
+    pub nal_len: u8,
+    pub sps: Vec<Vec<u8>>,
+    pub pps: Vec<Vec<u8>>,
+    /// Chroma format and SPS extensions of high profiles.
+    pub ext: Vec<u8>,
+}
+
+impl AvcConfig {
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        Self::read(data).map_err(|_| Error::InvalidBox(BoxType::AVCC))
+    }
+
+    fn read(data: &[u8]) -> Result<Self> {
+        let mut r = Reader::new(data);
+        if r.u8()? != 1 {
+            return Err(Error::Truncated);
+        }
+        let profile = r.u8()?;
+        let compatibility = r.u8()?;
+        let level = r.u8()?;
+        let nal_len = (r.u8()? & 3) + 1;
+        let count = r.u8()? & 0x1f;
+        let sps = read_nals(&mut r, count as usize)?;
+        let count = r.u8()?;
+        let pps = read_nals(&mut r, count as usize)?;
+        Ok(Self {
+            profile,
+            compatibility,
+            level,
+            nal_len,
+            sps,
+            pps,
+            ext: r.rest().to_vec(),
+        })
+    }
+
+    /// Fails with [`Error::Overflow`] for over 31 SPS, 255 PPS or NAL units
+    /// over 64 KiB.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        if self.sps.len() > 0x1f
+            || self.pps.len() > u8::MAX as usize
+            || self
+                .sps
+                .iter()
+                .chain(&self.pps)
+                .any(|nal| nal.len() > u16::MAX as usize)
+        {
+            return Err(Error::Overflow);
+        }
+        let mut w = Writer::new();
+        w.u8(1);
+        w.u8(self.profile);
+        w.u8(self.compatibility);
+        w.u8(self.level);
+        w.u8(0xfc | (self.nal_len.clamp(1, 4) - 1));
+        w.u8(0xe0 | self.sps.len() as u8);
+        for nal in &self.sps {
+            w.u16(nal.len() as u16);
+            w.bytes(nal);
+        }
+        w.u8(self.pps.len() as u8);
+        for nal in &self.pps {
+            w.u16(nal.len() as u16);
+            w.bytes(nal);
+        }
+        w.bytes(&self.ext);
+        Ok(w.into_inner())
+    }
+}
+
+/// NAL units with 16-bit length prefixes.
+fn read_nals(r: &mut Reader, count: usize) -> Result<Vec<Vec<u8>>> {
+    let mut res = Vec::with_capacity(count.min(16));
+    for _ in 0..count {
+        let len = r.u16()? as usize;
+        res.push(r.bytes(len)?.to_vec());
+    }
+    Ok(res)
+}
+
+/// NAL units of the same type in [`HevcConfig`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NalArray {
+    pub completeness: bool,
+    pub nal_type: u8,
+    pub nals: Vec<Vec<u8>>,
+}
+
+/// HEVC decoder configuration record of `hvcC`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HevcConfig {
+    pub general_profile_idc: u8,
+    pub general_level_idc: u8,
+    /// Length of NAL unit size prefix in bytes.
+    pub nal_len: u8,
+    pub arrays: Vec<NalArray>,
+}
+
+impl HevcConfig {
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        Self::read(data).map_err(|_| Error::InvalidBox(BoxType::HVCC))
+    }
+
+    fn read(data: &[u8]) -> Result<Self> {
+        let mut r = Reader::new(data);
+        let header = r.bytes(23)?;
+        if header[0] != 1 {
+            return Err(Error::Truncated);
+        }
+        let mut arrays = Vec::with_capacity(header[22] as usize);
+        for _ in 0..header[22] {
+            let ty = r.u8()?;
+            let count = r.u16()?;
+            let nals = read_nals(&mut r, count as usize)?;
+            arrays.push(NalArray {
+                completeness: ty & 0x80 != 0,
+                nal_type: ty & 0x3f,
+                nals,
+            });
+        }
+        Ok(Self {
+            general_profile_idc: header[1] & 0x1f,
+            general_level_idc: header[12],
+            nal_len: (header[21] & 3) + 1,
+            arrays,
+        })
+    }
+
+    /// All parameter sets in order, e.g. VPS, SPS and PPS.
+    pub fn param_sets(&self) -> impl Iterator<Item = &[u8]> {
+        self.arrays
+            .iter()
+            .flat_map(|a| a.nals.iter().map(Vec::as_slice))
+    }
+}
+
+/// Opus specific box `dOps`.
+///
+/// Same fields as Ogg `OpusHead`, but big endian.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpusConfig {
+    pub channels: u8,
+    pub pre_skip: u16,
+    pub input_sample_rate: u32,
+    /// Gain in Q7.8 dB.
+    pub output_gain: i16,
+    pub mapping_family: u8,
+    pub stream_count: u8,
+    pub coupled_count: u8,
+    /// Channel to stream mapping, empty for family 0.
+    pub mapping: Vec<u8>,
+}
+
+impl OpusConfig {
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        Self::read(data).map_err(|_| Error::InvalidBox(BoxType::DOPS))
+    }
+
+    fn read(data: &[u8]) -> Result<Self> {
+        let mut r = Reader::new(data);
+        if r.u8()? != 0 {
+            return Err(Error::Truncated);
+        }
+        let channels = r.u8()?;
+        let pre_skip = r.u16()?;
+        let input_sample_rate = r.u32()?;
+        let output_gain = r.i16()?;
+        let mapping_family = r.u8()?;
+        let (stream_count, coupled_count, mapping) = if mapping_family == 0 {
+            (1, channels.saturating_sub(1), Vec::new())
+        } else {
+            let stream_count = r.u8()?;
+            let coupled_count = r.u8()?;
+            (
+                stream_count,
+                coupled_count,
+                r.bytes(channels as usize)?.to_vec(),
+            )
+        };
+        Ok(Self {
+            channels,
+            pre_skip,
+            input_sample_rate,
+            output_gain,
+            mapping_family,
+            stream_count,
+            coupled_count,
+            mapping,
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut w = Writer::new();
+        w.u8(0);
+        w.u8(self.channels);
+        w.u16(self.pre_skip);
+        w.u32(self.input_sample_rate);
+        w.i16(self.output_gain);
+        w.u8(self.mapping_family);
+        if self.mapping_family != 0 {
+            w.u8(self.stream_count);
+            w.u8(self.coupled_count);
+            w.bytes(&self.mapping);
+        }
+        w.into_inner()
+    }
+
+    #[cfg(feature = "cat")]
+    pub fn to_head(&self) -> cat::audio::container::ogg::OpusHead {
+        cat::audio::container::ogg::OpusHead {
+            channels: self.channels,
+            pre_skip: self.pre_skip,
+            input_sample_rate: self.input_sample_rate,
+            output_gain: self.output_gain,
+            mapping_family: self.mapping_family,
+            stream_count: self.stream_count,
+            coupled_count: self.coupled_count,
+            mapping: self.mapping.clone(),
+        }
+    }
+}
+
+#[cfg(feature = "cat")]
+impl From<&cat::audio::container::ogg::OpusHead> for OpusConfig {
+    fn from(head: &cat::audio::container::ogg::OpusHead) -> Self {
+        Self {
+            channels: head.channels,
+            pre_skip: head.pre_skip,
+            input_sample_rate: head.input_sample_rate,
+            output_gain: head.output_gain,
+            mapping_family: head.mapping_family,
+            stream_count: head.stream_count,
+            coupled_count: head.coupled_count,
+            mapping: head.mapping.clone(),
+        }
+    }
+}
@@ -0,0 +1,384 @@
+use std::ops::Deref;
+
+use crate::{
+    arc, cf, cm,
+    cm::sample_buffer::{attach_keys as keys, buf_attach_keys as buf_keys},
+};
+
+/// Typed sample attachments of one sample of [`cm::SampleBuf`].
+///
+/// `None` means the key is absent.
+#[derive(Debug, Default, Clone)]
+pub struct SampleAttachments {
+    pub not_sync: Option<bool>,
+    pub partial_sync: Option<bool>,
+    pub has_redundant_coding: Option<bool>,
+    pub is_depended_on_by_others: Option<bool>,
+    pub depends_on_others: Option<bool>,
+    pub earlier_display_times_allowed: Option<bool>,
+    pub display_immediately: Option<bool>,
+    pub do_not_display: Option<bool>,
+    pub cryptor_subsample_auxiliary_data: Option<arc::R<cf::Data>>,
+    pub hdr10plus_per_frame_data: Option<arc::R<cf::Data>>,
+}
+
+impl SampleAttachments {
+    /// Reads known keys of the sample attachments dictionary, values of wrong types are skipped.
+    pub fn with_dictionary(dict: &cf::Dictionary) -> Self {
+        let b = |key: &cf::String| boolean(dict.value(key));
+        let d = |key: &cf::String| data(dict.value(key));
+        Self {
+            not_sync: b(keys::not_sync()),
+            partial_sync: b(keys::partial_sync()),
+            has_redundant_coding: b(keys::has_redundant_coding()),
+            is_depended_on_by_others: b(keys::is_depended_on_by_others()),
+            depends_on_others: b(keys::depends_on_others()),
+            earlier_display_times_allowed: b(keys::earlier_display_times_allowed()),
+            display_immediately: b(keys::display_immediately()),
+            do_not_display: b(keys::do_not_display()),
+            cryptor_subsample_auxiliary_data: d(keys::cryptor_subsample_auxiliary_data()),
+            hdr10plus_per_frame_data: d(keys::hdr10plus_per_frame_data()),
+        }
+    }
+
+    /// Sets known keys of `dict`, keys of `None` fields are removed. Other keys are kept.
+    pub fn write_to(&self, dict: &mut cf::DictionaryOfMut<cf::String, cf::Plist>) {
+        let bools = [
+            (keys::not_sync(), self.not_sync),
+            (keys::partial_sync(), self.partial_sync),
+            (keys::has_redundant_coding(), self.has_redundant_coding),
+            (
+                keys::is_depended_on_by_others(),
+                self.is_depended_on_by_others,
+            ),
+            (keys::depends_on_others(), self.depends_on_others),
+            (
+                keys::earlier_display_times_allowed(),
+                self.earlier_display_times_allowed,
+            ),
+            (keys::display_immediately(), self.display_immediately),
+            (keys::do_not_display(), self.do_not_display),
+        ];
+        for (key, val) in bools {
+            match val {
+                Some(val) => dict.insert(key, <&cf::Boolean>::from(val).into()),
+                None => dict.remove(key),
+            }
+        }
+        let datas = [
+            (
+                keys::cryptor_subsample_auxiliary_data(),
+                &self.cryptor_subsample_auxiliary_data,
+            ),
+            (
+                keys::hdr10plus_per_frame_data(),
+                &self.hdr10plus_per_frame_data,
+            ),
+        ];
+        for (key, val) in datas {
+            match val {
+                Some(val) => dict.insert(key, val.as_ref().into()),
+                None => dict.remove(key),
+            }
+        }
+    }
+
+    /// Absence of `not_sync` implies sync sample.
+    #[inline]
+    pub fn is_sync(&self) -> bool {
+        self.not_sync != Some(true)
+    }
+
+    /// A sample is droppable if and only if `is_depended_on_by_others` is present and `false`.
+    #[inline]
+    pub fn is_droppable(&self) -> bool {
+        self.is_depended_on_by_others == Some(false)
+    }
+}
+
+/// Typed buffer-level attachments of [`cm::SampleBuf`].
+///
+/// `None` means the key is absent.
+#[derive(Debug, Default, Clone)]
+pub struct BufAttachments {
+    pub reset_decoder_before_decoding: Option<bool>,
+    pub drain_after_decoding: Option<bool>,
+    pub post_notification_when_consumed: Option<arc::R<cf::Dictionary>>,
+    pub resume_output: Option<i64>,
+    pub transition_id: Option<arc::R<cf::Type>>,
+    pub trim_duration_at_start: Option<cm::Time>,
+    pub trim_duration_at_end: Option<cm::Time>,
+    pub reverse: Option<bool>,
+    pub fill_discontinuities_with_silence: Option<bool>,
+    pub empty_media: Option<bool>,
+    pub permanent_empty_media: Option<bool>,
+    pub display_empty_media_immediately: Option<bool>,
+    pub ends_previous_sample_duration: Option<bool>,
+    pub sample_reference_url: Option<arc::R<cf::Url>>,
+    pub sample_reference_byte_offset: Option<i64>,
+    pub dropped_frame_reason: Option<arc::R<cf::String>>,
+    pub dropped_frame_reason_info: Option<arc::R<cf::String>>,
+    pub still_image_lens_stabilization_info: Option<arc::R<cf::String>>,
+    pub camera_intrinsic_matrix: Option<arc::R<cf::Data>>,
+    pub force_key_frame: Option<bool>,
+}
+
+impl BufAttachments {
+    /// Reads known keys of the attachments dictionary, values of wrong types are skipped.
+    pub fn with_dictionary(dict: &cf::Dictionary) -> Self {
+        Self::read(|key| dict.value(key))
+    }
+
+    /// Dictionary with present fields only.
+    pub fn to_dictionary(&self) -> arc::R<cf::DictionaryMut> {
+        let mut dict = cf::DictionaryMut::with_capacity(0);
+        self.write(|key, val| {
+            if let Some(val) = val {
+                dict.insert(key, val);
+            }
+        });
+        dict
+    }
+
+    pub(crate) fn read<'a>(get: impl Fn(&cf::String) -> Option<&'a cf::Type>) -> Self {
+        let b = |key| boolean(get(key));
+        let s = |key| cast::<cf::String>(get(key), cf::String::type_id()).map(|v| v.retained());
+        Self {
+            reset_decoder_before_decoding: b(buf_keys::reset_decoder_before_decoding()),
+            drain_after_decoding: b(buf_keys::drain_after_decoding()),
+            post_notification_when_consumed: cast::<cf::Dictionary>(
+                get(buf_keys::post_notification_when_consumed()),
+                cf::Dictionary::type_id(),
+            )
+            .map(|v| v.retained()),
+            resume_output: number(get(buf_keys::resume_output())),
+            transition_id: get(buf_keys::transition_id()).map(arc::Retain::retained),
+            trim_duration_at_start: time(get(buf_keys::trim_duration_at_start())),
+            trim_duration_at_end: time(get(buf_keys::trim_duration_at_end())),
+            reverse: b(buf_keys::reverse()),
+            fill_discontinuities_with_silence: b(buf_keys::fill_discontinuities_with_silence()),
+            empty_media: b(buf_keys::empty_media()),
+            permanent_empty_media: b(buf_keys::permanent_empty_media()),
+            display_empty_media_immediately: b(buf_keys::display_empty_media_immediately()),
+            ends_previous_sample_duration: b(buf_keys::ends_previous_sample_duration()),
+            sample_reference_url: cast::<cf::Url>(
+                get(buf_keys::sample_reference_url()),
+                cf::Url::type_id(),
+            )
+            .map(|v| v.retained()),
+            sample_reference_byte_offset: number(get(buf_keys::sample_reference_byte_offset())),
+            dropped_frame_reason: s(buf_keys::dropped_frame_reason()),
+            dropped_frame_reason_info: s(buf_keys::dropped_frame_reason_info()),
+            still_image_lens_stabilization_info: s(buf_keys::still_image_lens_stabilization_info()),
+            camera_intrinsic_matrix: data(get(buf_keys::camera_intrinsic_matrix())),
+            force_key_frame: b(buf_keys::force_key_frame()),
+        }
+    }
+
+    /// Calls `set` for every known key with value of the field or `None` to remove the key.
+    pub(crate) fn write(&self, mut set: impl FnMut(&cf::String, Option<&cf::Type>)) {
+        let bools = [
+            (
+                buf_keys::reset_decoder_before_decoding(),
+                self.reset_decoder_before_decoding,
+            ),
+            (buf_keys::drain_after_decoding(), self.drain_after_decoding),
+            (buf_keys::reverse(), self.reverse),
+            (
+                buf_keys::fill_discontinuities_with_silence(),
+                self.fill_discontinuities_with_silence,
+            ),
+            (buf_keys::empty_media(), self.empty_media),
+            (
+                buf_keys::permanent_empty_media(),
+                self.permanent_empty_media,
+            ),
+            (
+                buf_keys::display_empty_media_immediately(),
+                self.display_empty_media_immediately,
+            ),
+            (
+                buf_keys::ends_previous_sample_duration(),
+                self.ends_previous_sample_duration,
+            ),
+            (buf_keys::force_key_frame(), self.force_key_frame),
+        ];
+        for (key, val) in bools {
+            set(key, val.map(|v| <&cf::Boolean>::from(v).deref()));
+        }
+
+        let numbers = [
+            (buf_keys::resume_output(), self.resume_output),
+            (
+                buf_keys::sample_reference_byte_offset(),
+                self.sample_reference_byte_offset,
+            ),
+        ];
+        for (key, val) in numbers {
+            set(key, val.map(cf::Number::from_i64).as_deref().map(as_type));
+        }
+
+        let times = [
+            (
+                buf_keys::trim_duration_at_start(),
+                self.trim_duration_at_start,
+            ),
+            (buf_keys::trim_duration_at_end(), self.trim_duration_at_end),
+        ];
+        for (key, val) in times {
+            let dict = val.and_then(cm::Time::as_dictionary);
+            set(key, dict.as_deref().map(as_type));
+        }
+
+        let strings = [
+            (buf_keys::dropped_frame_reason(), &self.dropped_frame_reason),
+            (
+                buf_keys::dropped_frame_reason_info(),
+                &self.dropped_frame_reason_info,
+            ),
+            (
+                buf_keys::still_image_lens_stabilization_info(),
+                &self.still_image_lens_stabilization_info,
+            ),
+        ];
+        for (key, val) in strings {
+            set(key, val.as_deref().map(as_type));
+        }
+
+        set(
+            buf_keys::post_notification_when_consumed(),
+            self.post_notification_when_consumed.as_deref().map(as_type),
+        );
+        set(buf_keys::transition_id(), self.transition_id.as_deref());
+        set(
+            buf_keys::sample_reference_url(),
+            self.sample_reference_url.as_deref().map(as_type),
+        );
+        set(
+            buf_keys::camera_intrinsic_matrix(),
+            self.camera_intrinsic_matrix.as_deref().map(as_type),
+        );
+    }
+}
+
+fn as_type<T: Deref<Target = cf::Type>>(val: &T) -> &cf::Type {
+    val
+}
+
+fn cast<T>(val: Option<&cf::Type>, type_id: cf::TypeId) -> Option<&T> {
+    let val = val?;
+    if val.get_type_id() == type_id {
+        Some(unsafe { &*(val as *const cf::Type as *const T) })
+    } else {
+        None
+    }
+}
+
+fn boolean(val: Option<&cf::Type>) -> Option<bool> {
+    cast::<cf::Boolean>(val, cf::Boolean::type_id()).map(cf::Boolean::value)
+}
+
+fn number(val: Option<&cf::Type>) -> Option<i64> {
+    cast::<cf::Number>(val, cf::Number::type_id()).and_then(cf::Number::to_i64)
+}
+
+fn data(val: Option<&cf::Type>) -> Option<arc::R<cf::Data>> {
+    cast::<cf::Data>(val, cf::Data::type_id()).map(|v| v.retained())
+}
+
+fn time(val: Option<&cf::Type>) -> Option<cm::Time> {
+    let dict = cast::<cf::Dictionary>(val, cf::Dictionary::type_id())?;
+    let time = cm::Time::with_dictionary(dict);
+    time.is_valid().then_some(time)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        cf,
+        cm::{
+            self,
+            sample_buffer::{attach_keys, buf_attach_keys},
+        },
+    };
+
+    #[test]
+    fn sample() {
+        let hdr = cf::Data::from_slice(&[0xb5, 0, 0x3c]).unwrap();
+        let dict = cf::DictionaryOf::with_keys_values(
+            &[
+                attach_keys::not_sync(),
+                attach_keys::is_depended_on_by_others(),
+                attach_keys::display_immediately(),
+                attach_keys::hdr10plus_per_frame_data(),
+            ],
+            &[
+                cf::Boolean::value_true().as_prop_list(),
+                cf::Boolean::value_false().as_prop_list(),
+                cf::Number::tagged_i8(1).into(),
+                hdr.as_ref().into(),
+            ],
+        );
+        let attaches = cm::SampleAttachments::with_dictionary(&dict);
+        assert_eq!(attaches.not_sync, Some(true));
+        assert_eq!(attaches.is_depended_on_by_others, Some(false));
+        // number is not a boolean
+        assert_eq!(attaches.display_immediately, None);
+        assert_eq!(attaches.partial_sync, None);
+        assert_eq!(
+            attaches
+                .hdr10plus_per_frame_data
+                .as_ref()
+                .unwrap()
+                .as_slice(),
+            &[0xb5, 0, 0x3c]
+        );
+        assert!(!attaches.is_sync());
+        assert!(attaches.is_droppable());
+
+        let mut copy = dict.copy_mut().unwrap();
+        let attaches = cm::SampleAttachments {
+            depends_on_others: Some(true),
+            ..Default::default()
+        };
+        attaches.write_to(&mut copy);
+        assert_eq!(copy.len(), 2);
+        assert!(copy.contains_key(attach_keys::display_immediately()));
+        let attaches = cm::SampleAttachments::with_dictionary(&copy);
+        assert_eq!(attaches.depends_on_others, Some(true));
+        assert!(attaches.is_sync());
+        assert!(!attaches.is_droppable());
+
+        assert!(cm::SampleAttachments::default().is_sync());
+    }
+
+    #[test]
+    fn buf() {
+        let attaches = cm::BufAttachments {
+            drain_after_decoding: Some(true),
+            resume_output: Some(7),
+            trim_duration_at_start: Some(cm::Time::new(1024, 44_100)),
+            dropped_frame_reason: Some(cf::String::from_str("FrameWasLate")),
+            ..Default::default()
+        };
+        let dict = attaches.to_dictionary();
+        assert_eq!(dict.len(), 4);
+        assert!(dict.contains_key(buf_attach_keys::trim_duration_at_start()));
+
+        let attaches = cm::BufAttachments::with_dictionary(&dict);
+        assert_eq!(attaches.drain_after_decoding, Some(true));
+        assert_eq!(attaches.reset_decoder_before_decoding, None);
+        assert_eq!(attaches.resume_output, Some(7));
+        assert_eq!(
+            attaches.trim_duration_at_start,
+            Some(cm::Time::new(1024, 44_100))
+        );
+        assert_eq!(attaches.trim_duration_at_end, None);
+        assert_eq!(
+            attaches.dropped_frame_reason.unwrap().to_string(),
+            "FrameWasLate"
+        );
+
+        assert!(cm::BufAttachments::default().to_dictionary().is_empty());
+    }
+}
@@ -369,29 +369,62 @@ impl SampleBuf {
         unsafe { CMSampleBufferGetSampleAttachmentsArray(self, create_if_necessary) }
     }
 
-    #[inline]
-    pub fn is_key_frame(&self) -> bool {
+    /// Returns typed attachments of the sample at `index`.
+    ///
+    /// Samples without attachments yield defaults.
+    pub fn sample_attachments(&self, index: usize) -> cm::SampleAttachments {
         match self.attaches(false) {
-            Some(arr) => {
-                if arr.is_empty() {
-                    true
-                } else {
-                    let dict = &arr[0];
-                    match dict.get(attach_keys::not_sync()) {
-                        None => true,
-                        Some(not_sync) => unsafe {
-                            // in theory we don't need check actual value here.
-                            // there is unsafe [`contains_not_sync()`] for faster
-                            // check
-                            not_sync.as_type_ptr() == cf::Boolean::value_false().as_type_ptr()
-                        },
-                    }
-                }
+            Some(arr) if index < arr.len() => cm::SampleAttachments::with_dictionary(&arr[index]),
+            _ => Default::default(),
+        }
+    }
+
+    /// Replaces known attachments of the sample at `index`, attachments of `None` fields are removed.
+    pub fn set_sample_attachments(
+        &mut self,
+        index: usize,
+        val: &cm::SampleAttachments,
+    ) -> os::Result {
+        match self.attaches_mut(true) {
+            Some(arr) if index < arr.len() => {
+                val.write_to(&mut arr[index]);
+                Ok(())
             }
-            None => true,
+            _ => Err(err::SAMPLE_INDEX_OUT_OF_RANGE),
         }
     }
 
+    /// Returns typed buffer-level attachments.
+    pub fn buf_attachments(&self) -> cm::BufAttachments {
+        cm::BufAttachments::read(|key| self.attach(key, std::ptr::null_mut()))
+    }
+
+    /// Replaces known buffer-level attachments, attachments of `None` fields are removed.
+    pub fn set_buf_attachments(&mut self, val: &cm::BufAttachments, mode: cm::AttachMode) {
+        val.write(|key, v| match v {
+            Some(v) => self.set_attach(key, v, mode),
+            None => self.remove_attach(key),
+        })
+    }
+
+    /// Whether the first sample is a sync sample.
+    ///
+    /// Buffers without sample attachments are sync.
+    #[inline]
+    pub fn is_key_frame(&self) -> bool {
+        self.is_sample_key_frame(0)
+    }
+
+    #[inline]
+    pub fn is_sample_key_frame(&self, index: usize) -> bool {
+        self.sample_attachments(index).is_sync()
+    }
+
+    #[inline]
+    pub fn is_sample_droppable(&self, index: usize) -> bool {
+        self.sample_attachments(index).is_droppable()
+    }
+
     #[inline]
     pub unsafe fn contains_not_sync(&self) -> bool {
         let arr = self.attaches(true).unwrap_unchecked();
@@ -1051,6 +1084,27 @@ mod tests {
 
         assert!(sample_buf.is_key_frame());
 
+        let mut attaches = sample_buf.sample_attachments(0);
+        // number is not a boolean
+        assert_eq!(attaches.do_not_display, None);
+        attaches.not_sync = Some(true);
+        sample_buf.set_sample_attachments(0, &attaches).unwrap();
+        assert!(!sample_buf.is_key_frame());
+        assert!(!sample_buf.is_sample_droppable(0));
+        assert_eq!(
+            sample_buf.set_sample_attachments(1, &attaches),
+            Err(cm::sample_buf_err::SAMPLE_INDEX_OUT_OF_RANGE)
+        );
+
+        let attaches = cm::BufAttachments {
+            drain_after_decoding: Some(true),
+            ..Default::default()
+        };
+        sample_buf.set_buf_attachments(&attaches, cm::AttachMode::NotPropagate);
+        let attaches = sample_buf.buf_attachments();
+        assert_eq!(attaches.drain_after_decoding, Some(true));
+        assert_eq!(attaches.force_key_frame, None);
+
         let err = sample_buf
             .audio_stream_packet_descs()
             .expect_err("It is video format");
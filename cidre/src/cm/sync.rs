@@ -567,6 +567,86 @@ impl Timebase {
     }
 }
 
+/// Time and rate control of a timebase.
+///
+/// Implemented by [`cm::Timebase`] and by [`cm::VirtualTimebase`] which is
+/// driven by a manually advanced clock.
+pub trait TimebaseControl {
+    /// Current time of the timebase.
+    fn time(&self) -> cm::Time;
+
+    /// Rate relative to the immediate source.
+    fn rate(&self) -> f64;
+
+    /// Rate relative to the ultimate source clock.
+    fn effective_rate(&self) -> f64;
+
+    fn set_time(&mut self, val: cm::Time) -> os::Result;
+
+    fn set_rate(&mut self, val: f64) -> os::Result;
+
+    /// Sets the time of the timebase at a particular source time.
+    fn set_anchor_time(
+        &mut self,
+        timebase_time: cm::Time,
+        immediate_src_time: cm::Time,
+    ) -> os::Result;
+
+    /// Sets the time of the timebase at a particular source time and changes the rate at exactly that time.
+    fn set_rate_and_anchor_time(
+        &mut self,
+        rate: f64,
+        timebase_time: cm::Time,
+        immediate_src_time: cm::Time,
+    ) -> os::Result;
+}
+
+impl TimebaseControl for Timebase {
+    #[inline]
+    fn time(&self) -> cm::Time {
+        Timebase::time(self)
+    }
+
+    #[inline]
+    fn rate(&self) -> f64 {
+        Timebase::rate(self)
+    }
+
+    #[inline]
+    fn effective_rate(&self) -> f64 {
+        Timebase::effective_rate(self)
+    }
+
+    #[inline]
+    fn set_time(&mut self, val: cm::Time) -> os::Result {
+        Timebase::set_time(self, val)
+    }
+
+    #[inline]
+    fn set_rate(&mut self, val: f64) -> os::Result {
+        Timebase::set_rate(self, val)
+    }
+
+    #[inline]
+    fn set_anchor_time(
+        &mut self,
+        timebase_time: cm::Time,
+        immediate_src_time: cm::Time,
+    ) -> os::Result {
+        self.set_achor_time(timebase_time, immediate_src_time)
+    }
+
+    #[inline]
+    fn set_rate_and_anchor_time(
+        &mut self,
+        rate: f64,
+        timebase_time: cm::Time,
+        immediate_src_time: cm::Time,
+    ) -> os::Result {
+        Timebase::set_rate_and_anchor_time(self, rate, timebase_time, immediate_src_time)
+    }
+}
+
 #[link(name = "CoreMedia", kind = "framework")]
 extern "C-unwind" {
     fn CMTimebaseGetTypeID() -> cf::TypeId;
@@ -113,6 +113,31 @@ impl Time {
         unsafe { CMTimeCopyDescription(None, self) }
     }
 
+    /// Returns a cf::Dictionary version of a Time.
+    #[doc(alias = "CMTimeCopyAsDictionary")]
+    #[inline]
+    pub fn as_dictionary_in(
+        self,
+        allocator: Option<&cf::Allocator>,
+    ) -> Option<arc::R<cf::Dictionary>> {
+        unsafe { CMTimeCopyAsDictionary(self, allocator) }
+    }
+
+    #[doc(alias = "CMTimeCopyAsDictionary")]
+    #[inline]
+    pub fn as_dictionary(self) -> Option<arc::R<cf::Dictionary>> {
+        unsafe { CMTimeCopyAsDictionary(self, None) }
+    }
+
+    /// Reconstitutes a Time from a cf::Dictionary previously created by [`Time::as_dictionary`].
+    ///
+    /// Returns invalid time for malformed dictionaries.
+    #[doc(alias = "CMTimeMakeFromDictionary")]
+    #[inline]
+    pub fn with_dictionary(dict: &cf::Dictionary) -> Time {
+        unsafe { CMTimeMakeFromDictionary(Some(dict)) }
+    }
+
     /// Converts a Time to seconds.
     #[inline]
     pub fn as_secs(self) -> f64 {
@@ -394,6 +419,12 @@ extern "C-unwind" {
     fn CMTimeMaximum(time1: Time, time2: Time) -> Time;
     fn CMTimeMinimum(time1: Time, time2: Time) -> Time;
 
+    fn CMTimeCopyAsDictionary(
+        time: Time,
+        allocator: Option<&cf::Allocator>,
+    ) -> Option<arc::R<cf::Dictionary>>;
+    fn CMTimeMakeFromDictionary(dict: Option<&cf::Dictionary>) -> Time;
+
     fn CMTimeCopyDescription(
         allocator: Option<&cf::Allocator>,
         time: Time,
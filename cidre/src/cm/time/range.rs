@@ -9,6 +9,18 @@ pub struct Range {
 }
 
 impl Range {
+    #[doc(alias = "CMTimeRangeMake")]
+    #[inline]
+    pub const fn new(start: cm::Time, duration: cm::Time) -> Self {
+        Self { start, duration }
+    }
+
+    #[doc(alias = "CMTimeRangeGetEnd")]
+    #[inline]
+    pub fn end(&self) -> cm::Time {
+        unsafe { CMTimeRangeGetEnd(*self) }
+    }
+
     #[doc(alias = "CMTIMERANGE_IS_INVALID")]
     #[inline]
     pub const fn is_valid(&self) -> bool {
@@ -47,6 +59,9 @@ impl Range {
     }
 }
 
+#[doc(alias = "CMTimeMapping")]
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[repr(C)]
 pub struct Mapping {
     pub source: cm::TimeRange,
     pub target: cm::TimeRange,
@@ -58,6 +73,7 @@ extern "C-unwind" {
     static kCMTimeRangeInvalid: Range;
 
     fn CMTimeRangeContainsTime(range: Range, time: cm::Time) -> bool;
+    fn CMTimeRangeGetEnd(range: Range) -> cm::Time;
 }
 
 #[cfg(test)]
@@ -70,6 +86,9 @@ mod tests {
         assert!(range.is_valid());
         assert!(range.is_empty());
 
+        let range = cm::TimeRange::new(cm::Time::new(1, 2), cm::Time::new(3, 2));
+        assert_eq!(range.end(), cm::Time::new(2, 1));
+
         let range = cm::TimeRange::invalid();
         assert!(!range.is_valid());
         assert!(!range.is_empty());
@@ -0,0 +1,574 @@
+//! Pure Rust decode and presentation order analysis of encoded streams.
+//!
+//! [`Sequence`] takes sample timings with sync flags in the order they come out
+//! of `vt::CompressionSession` or `av::AssetReaderTrackOutput`, derives missing
+//! decode timestamps, reorder depth, composition offsets, edit start offset and
+//! frame duration, and reports timestamp [`Issue`]s. All times are integer ticks
+//! of a single timescale, so results are frame accurate.
+
+use std::fmt;
+
+use crate::cm::{self, iso};
+
+/// Converts `time` into ticks of `timescale`, rounding to the nearest tick.
+///
+/// Returns `None` for non-numeric times and out of range results.
+pub fn ticks(time: cm::Time, timescale: cm::TimeScale) -> Option<i64> {
+    if !time.is_numeric() || time.scale <= 0 || timescale <= 0 {
+        return None;
+    }
+    let n = time.value as i128 * timescale as i128;
+    let d = time.scale as i128;
+    i64::try_from((2 * n + d).div_euclid(2 * d)).ok()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sample {
+    pub pts: i64,
+    /// `None` for streams that only carry presentation timestamps.
+    pub dts: Option<i64>,
+    pub duration: Option<i64>,
+    pub is_sync: bool,
+}
+
+impl Sample {
+    #[inline]
+    pub const fn new(pts: i64, is_sync: bool) -> Self {
+        Self {
+            pts,
+            dts: None,
+            duration: None,
+            is_sync,
+        }
+    }
+
+    #[inline]
+    pub const fn with_dts(mut self, dts: i64) -> Self {
+        self.dts = Some(dts);
+        self
+    }
+
+    #[inline]
+    pub const fn with_duration(mut self, duration: i64) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+
+    /// Invalid decode time and duration are treated as missing.
+    ///
+    /// Returns `None` if presentation time is not numeric.
+    pub fn with_timing(
+        info: &cm::SampleTimingInfo,
+        timescale: cm::TimeScale,
+        is_sync: bool,
+    ) -> Option<Self> {
+        Some(Self {
+            pts: ticks(info.pts, timescale)?,
+            dts: ticks(info.dts, timescale),
+            duration: ticks(info.duration, timescale).filter(|d| *d > 0),
+            is_sync,
+        })
+    }
+}
+
+/// Timestamp problem, `sample` is an index into [`Sequence::samples`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Issue {
+    /// Presentation time is not numeric.
+    InvalidPts {
+        sample: usize,
+    },
+    /// Some samples carry decode time and this one doesn't, all are derived.
+    MissingDts {
+        sample: usize,
+    },
+    /// Decode time is less than decode time of the previous sample.
+    NonMonotonicDts {
+        sample: usize,
+    },
+    DuplicateDts {
+        sample: usize,
+        other: usize,
+    },
+    DuplicatePts {
+        sample: usize,
+        other: usize,
+    },
+    /// Sample is presented before it is decoded.
+    PtsBeforeDts {
+        sample: usize,
+    },
+    /// The first decoded sample is not a sync sample.
+    NonSyncStart {
+        sample: usize,
+    },
+    /// Presentation gap of `count` frames after the sample.
+    Dropped {
+        after: usize,
+        count: u32,
+    },
+    /// Count of sync flags differs from count of samples.
+    SyncMismatch {
+        samples: usize,
+        sync: usize,
+    },
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::InvalidPts { sample } => write!(f, "sample {sample} has invalid pts"),
+            Self::MissingDts { sample } => write!(f, "sample {sample} has no dts"),
+            Self::NonMonotonicDts { sample } => {
+                write!(f, "sample {sample} dts goes backwards")
+            }
+            Self::DuplicateDts { sample, other } => {
+                write!(f, "samples {other} and {sample} have the same dts")
+            }
+            Self::DuplicatePts { sample, other } => {
+                write!(f, "samples {other} and {sample} have the same pts")
+            }
+            Self::PtsBeforeDts { sample } => write!(f, "sample {sample} pts is before dts"),
+            Self::NonSyncStart { sample } => {
+                write!(f, "stream starts with non-sync sample {sample}")
+            }
+            Self::Dropped { after, count } => {
+                write!(f, "{count} frames dropped after sample {after}")
+            }
+            Self::SyncMismatch { samples, sync } => {
+                write!(f, "{sync} sync flags for {samples} samples")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Issue {}
+
+/// Analyzed timing of a stream.
+///
+/// Samples are expected in decode order. When they carry decode timestamps,
+/// decode order follows them instead.
+#[derive(Debug, Clone)]
+pub struct Sequence {
+    timescale: cm::TimeScale,
+    samples: Vec<Sample>,
+    dts: Vec<i64>,
+    derived_dts: bool,
+    decode_order: Vec<usize>,
+    presentation_order: Vec<usize>,
+    /// Position of each sample in presentation order.
+    rank: Vec<usize>,
+    reorder_depth: usize,
+    frame_duration: Option<i64>,
+    issues: Vec<Issue>,
+}
+
+impl Sequence {
+    pub fn new(timescale: cm::TimeScale, samples: Vec<Sample>) -> Self {
+        let n = samples.len();
+        let mut issues = Vec::new();
+
+        let missing: Vec<usize> = (0..n).filter(|&i| samples[i].dts.is_none()).collect();
+        let derived_dts = !missing.is_empty();
+        if missing.len() < n {
+            issues.extend(missing.iter().map(|&sample| Issue::MissingDts { sample }));
+        }
+
+        let mut presentation_order: Vec<usize> = (0..n).collect();
+        presentation_order.sort_by_key(|&i| samples[i].pts);
+
+        let (dts, decode_order) = if derived_dts {
+            // sorted pts shifted back just enough to keep every dts <= pts
+            let shift = (0..n)
+                .map(|k| samples[presentation_order[k]].pts - samples[k].pts)
+                .fold(0, i64::max);
+            let dts = (0..n)
+                .map(|k| samples[presentation_order[k]].pts - shift)
+                .collect();
+            (dts, (0..n).collect())
+        } else {
+            let dts: Vec<i64> = samples.iter().map(|s| s.dts.unwrap_or_default()).collect();
+            for i in 1..n {
+                if dts[i] < dts[i - 1] {
+                    issues.push(Issue::NonMonotonicDts { sample: i });
+                }
+            }
+            for (i, s) in samples.iter().enumerate() {
+                if s.pts < dts[i] {
+                    issues.push(Issue::PtsBeforeDts { sample: i });
+                }
+            }
+            let mut order: Vec<usize> = (0..n).collect();
+            order.sort_by_key(|&i| dts[i]);
+            for w in order.windows(2) {
+                if dts[w[0]] == dts[w[1]] {
+                    issues.push(Issue::DuplicateDts {
+                        sample: w[1],
+                        other: w[0],
+                    });
+                }
+            }
+            (dts, order)
+        };
+
+        for w in presentation_order.windows(2) {
+            if samples[w[0]].pts == samples[w[1]].pts {
+                issues.push(Issue::DuplicatePts {
+                    sample: w[1],
+                    other: w[0],
+                });
+            }
+        }
+        if let Some(&first) = decode_order.first() {
+            if !samples[first].is_sync {
+                issues.push(Issue::NonSyncStart { sample: first });
+            }
+        }
+
+        let mut rank = vec![0; n];
+        for (r, &i) in presentation_order.iter().enumerate() {
+            rank[i] = r;
+        }
+        let reorder_depth = decode_order
+            .iter()
+            .enumerate()
+            .map(|(k, &i)| k.saturating_sub(rank[i]))
+            .max()
+            .unwrap_or(0);
+
+        let mut deltas: Vec<i64> = presentation_order
+            .windows(2)
+            .map(|w| samples[w[1]].pts - samples[w[0]].pts)
+            .filter(|d| *d > 0)
+            .collect();
+        deltas.sort_unstable();
+        let frame_duration = deltas.get(deltas.len() / 2).copied();
+
+        if let Some(frame) = frame_duration {
+            for w in presentation_order.windows(2) {
+                let delta = samples[w[1]].pts - samples[w[0]].pts;
+                if 2 * delta >= 3 * frame {
+                    let frames = (2 * delta + frame) / (2 * frame);
+                    issues.push(Issue::Dropped {
+                        after: w[0],
+                        count: u32::try_from(frames - 1).unwrap_or(u32::MAX),
+                    });
+                }
+            }
+        }
+
+        Self {
+            timescale,
+            samples,
+            dts,
+            derived_dts,
+            decode_order,
+            presentation_order,
+            rank,
+            reorder_depth,
+            frame_duration,
+            issues,
+        }
+    }
+
+    /// Converts timing infos with sync flags of the same length.
+    ///
+    /// Fails with [`Issue::SyncMismatch`] if lengths differ.
+    pub fn with_timing_infos(
+        timescale: cm::TimeScale,
+        infos: &[cm::SampleTimingInfo],
+        sync: &[bool],
+    ) -> Result<Self, Issue> {
+        if infos.len() != sync.len() {
+            return Err(Issue::SyncMismatch {
+                samples: infos.len(),
+                sync: sync.len(),
+            });
+        }
+        let samples = infos
+            .iter()
+            .zip(sync)
+            .enumerate()
+            .map(|(i, (info, &is_sync))| {
+                Sample::with_timing(info, timescale, is_sync).ok_or(Issue::InvalidPts { sample: i })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self::new(timescale, samples))
+    }
+
+    #[inline]
+    pub fn timescale(&self) -> cm::TimeScale {
+        self.timescale
+    }
+
+    #[inline]
+    pub fn samples(&self) -> &[Sample] {
+        &self.samples
+    }
+
+    #[inline]
+    pub fn issues(&self) -> &[Issue] {
+        &self.issues
+    }
+
+    #[inline]
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Decode time of the sample, given or derived.
+    #[inline]
+    pub fn dts(&self, sample: usize) -> i64 {
+        self.dts[sample]
+    }
+
+    /// Decode timestamps were derived from presentation timestamps.
+    #[inline]
+    pub fn is_dts_derived(&self) -> bool {
+        self.derived_dts
+    }
+
+    /// Composition offset of the sample, pts - dts.
+    #[inline]
+    pub fn cts_offset(&self, sample: usize) -> i64 {
+        self.samples[sample].pts - self.dts[sample]
+    }
+
+    /// Sample indices in decode order.
+    #[inline]
+    pub fn decode_order(&self) -> &[usize] {
+        &self.decode_order
+    }
+
+    /// Sample indices in presentation order.
+    #[inline]
+    pub fn presentation_order(&self) -> &[usize] {
+        &self.presentation_order
+    }
+
+    /// Max number of frames decoded ahead of presentation, 0 without B-frames.
+    #[inline]
+    pub fn reorder_depth(&self) -> usize {
+        self.reorder_depth
+    }
+
+    /// Median presentation delta in ticks.
+    #[inline]
+    pub fn frame_duration(&self) -> Option<i64> {
+        self.frame_duration
+    }
+
+    /// Frames per second from [`Self::frame_duration`].
+    pub fn frame_rate(&self) -> Option<f64> {
+        self.frame_duration
+            .map(|d| self.timescale as f64 / d as f64)
+    }
+
+    /// Duration of the sample, given or up to the next presented sample.
+    pub fn duration(&self, sample: usize) -> i64 {
+        if let Some(duration) = self.samples[sample].duration {
+            return duration;
+        }
+        match self.presentation_order.get(self.rank[sample] + 1) {
+            Some(&next) => self.samples[next].pts - self.samples[sample].pts,
+            None => self.frame_duration.unwrap_or(0),
+        }
+    }
+
+    /// Media time of the first presented sample relative to the first decoded one,
+    /// the edit list start when decode timeline begins at zero.
+    pub fn start_offset(&self) -> i64 {
+        match (self.presentation_order.first(), self.decode_order.first()) {
+            (Some(&p), Some(&d)) => self.samples[p].pts - self.dts[d],
+            _ => 0,
+        }
+    }
+
+    /// Composition offsets in decode order.
+    ///
+    /// Returns `None` if an offset doesn't fit `ctts`.
+    pub fn ctts(&self) -> Option<iso::Ctts> {
+        let mut entries: Vec<iso::CompositionOffset> = Vec::new();
+        for &i in &self.decode_order {
+            let offset = i32::try_from(self.cts_offset(i)).ok()?;
+            match entries.last_mut() {
+                Some(last) if last.offset == offset => last.count += 1,
+                _ => entries.push(iso::CompositionOffset { count: 1, offset }),
+            }
+        }
+        Some(iso::Ctts { entries })
+    }
+
+    /// Timing infos in decode order.
+    pub fn timing_infos(&self) -> Vec<cm::SampleTimingInfo> {
+        let scale = self.timescale;
+        self.decode_order
+            .iter()
+            .map(|&i| cm::SampleTimingInfo {
+                duration: cm::Time::new(self.duration(i), scale),
+                pts: cm::Time::new(self.samples[i].pts, scale),
+                dts: cm::Time::new(self.dts[i], scale),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cm::{
+        self, iso,
+        timing::{ticks, Issue, Sample, Sequence},
+    };
+
+    #[test]
+    fn derived_dts() {
+        // I0 P3 B1 B2 P6 B4 B5 with 1001/30000 frames
+        let pts = [0, 3, 1, 2, 6, 4, 5];
+        let samples = pts.iter().map(|&p| Sample::new(p * 1001, p == 0)).collect();
+        let seq = Sequence::new(30_000, samples);
+        assert!(seq.is_valid(), "{:?}", seq.issues());
+        assert!(seq.is_dts_derived());
+        assert_eq!(seq.reorder_depth(), 1);
+        assert_eq!(seq.presentation_order(), &[0, 2, 3, 1, 5, 6, 4]);
+        assert_eq!(seq.frame_duration(), Some(1001));
+        assert!((seq.frame_rate().unwrap() - 29.97).abs() < 0.01);
+        assert_eq!(seq.start_offset(), 1001);
+        for i in 0..pts.len() {
+            assert!(seq.dts(i) <= seq.samples()[i].pts);
+            assert_eq!(seq.duration(i), 1001);
+        }
+        assert_eq!(seq.dts(0), -1001);
+        assert_eq!(
+            seq.ctts().unwrap().entries,
+            [
+                iso::CompositionOffset {
+                    count: 1,
+                    offset: 1001
+                },
+                iso::CompositionOffset {
+                    count: 1,
+                    offset: 3003
+                },
+                iso::CompositionOffset {
+                    count: 2,
+                    offset: 0
+                },
+                iso::CompositionOffset {
+                    count: 1,
+                    offset: 3003
+                },
+                iso::CompositionOffset {
+                    count: 2,
+                    offset: 0
+                },
+            ]
+        );
+
+        // b-pyramid I0 P4 B2 b1 b3
+        let samples = [0, 4, 2, 1, 3]
+            .iter()
+            .map(|&p| Sample::new(p, p == 0))
+            .collect();
+        let seq = Sequence::new(1, samples);
+        assert_eq!(seq.reorder_depth(), 2);
+        assert_eq!(seq.start_offset(), 2);
+    }
+
+    #[test]
+    fn issues() {
+        let samples = vec![
+            Sample::new(2, false).with_dts(0),
+            Sample::new(1, false).with_dts(2),
+            Sample::new(6, false).with_dts(1),
+            Sample::new(6, false).with_dts(1),
+            Sample::new(7, false),
+        ];
+        let seq = Sequence::new(1, samples);
+        assert!(seq.is_dts_derived());
+        assert_eq!(
+            seq.issues(),
+            &[
+                Issue::MissingDts { sample: 4 },
+                Issue::DuplicatePts {
+                    sample: 3,
+                    other: 2
+                },
+                Issue::NonSyncStart { sample: 0 },
+                Issue::Dropped { after: 0, count: 3 },
+            ]
+        );
+
+        let samples = vec![
+            Sample::new(0, true).with_dts(0),
+            Sample::new(2, false).with_dts(2),
+            Sample::new(1, false).with_dts(1),
+            Sample::new(3, false).with_dts(3).with_duration(5),
+            Sample::new(4, false).with_dts(3),
+        ];
+        let seq = Sequence::new(1, samples);
+        assert!(!seq.is_dts_derived());
+        assert_eq!(seq.decode_order(), &[0, 2, 1, 3, 4]);
+        assert_eq!(seq.duration(3), 5);
+        assert_eq!(
+            seq.issues(),
+            &[
+                Issue::NonMonotonicDts { sample: 2 },
+                Issue::DuplicateDts {
+                    sample: 4,
+                    other: 3
+                },
+            ]
+        );
+        assert_eq!(
+            Sequence::new(1, vec![Sample::new(0, true).with_dts(1)]).issues(),
+            &[Issue::PtsBeforeDts { sample: 0 }]
+        );
+    }
+
+    #[test]
+    fn timing_infos() {
+        assert_eq!(ticks(cm::Time::new(1, 3), 90_000), Some(30_000));
+        assert_eq!(ticks(cm::Time::new(1001, 30_000), 1000), Some(33));
+        assert_eq!(ticks(cm::Time::new(-1, 2), 1), Some(0));
+        assert_eq!(ticks(cm::Time::invalid(), 1000), None);
+
+        let infos = [
+            cm::SampleTimingInfo {
+                duration: cm::Time::invalid(),
+                pts: cm::Time::new(0, 600),
+                dts: cm::Time::invalid(),
+            },
+            cm::SampleTimingInfo {
+                duration: cm::Time::invalid(),
+                pts: cm::Time::new(40, 600),
+                dts: cm::Time::invalid(),
+            },
+            cm::SampleTimingInfo {
+                duration: cm::Time::invalid(),
+                pts: cm::Time::new(20, 600),
+                dts: cm::Time::invalid(),
+            },
+        ];
+        let seq = Sequence::with_timing_infos(1200, &infos, &[true, false, false]).unwrap();
+        assert!(seq.is_valid());
+        let out = seq.timing_infos();
+        assert_eq!(out.len(), 3);
+        assert_eq!(out[0].dts, cm::Time::new(-40, 1200));
+        assert_eq!(out[1].pts, cm::Time::new(80, 1200));
+        assert_eq!(out[2].duration, cm::Time::new(40, 1200));
+
+        let mut infos = infos;
+        infos[1].pts = cm::Time::invalid();
+        assert_eq!(
+            Sequence::with_timing_infos(1200, &infos, &[true, false, false]).unwrap_err(),
+            Issue::InvalidPts { sample: 1 }
+        );
+        assert_eq!(
+            Sequence::with_timing_infos(1200, &infos, &[true]).unwrap_err(),
+            Issue::SyncMismatch {
+                samples: 3,
+                sync: 1
+            }
+        );
+    }
+}
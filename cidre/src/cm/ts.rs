@@ -0,0 +1,65 @@
+//! MPEG-2 transport stream muxer.
+//!
+//! [`Muxer`] packetizes Annex-B video and ADTS audio frames with 90 kHz
+//! timestamps into 188-byte packets with PAT/PMT, PCR and continuity counters.
+//! [`SampleBufMuxer`] feeds it with H.264/HEVC and AAC [`cm::SampleBuf`]s.
+//!
+//! [`cm::SampleBuf`]: crate::cm::SampleBuf
+
+use std::fmt;
+
+use crate::os;
+
+mod annex_b;
+pub use annex_b::to_annex_b;
+pub use annex_b::VideoCodec;
+pub use annex_b::VideoConfig;
+
+mod mux;
+pub use mux::Frame;
+pub use mux::Muxer;
+pub use mux::StreamType;
+pub use mux::CLOCK_RATE;
+pub use mux::PACKET_LEN;
+
+mod sample_buf;
+pub use sample_buf::ticks;
+pub use sample_buf::SampleBufMuxer;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// No stream with the PID.
+    UnknownStream(u16),
+    /// Codec configuration is malformed.
+    InvalidConfig,
+    /// Sample data doesn't match codec configuration.
+    InvalidSample,
+    /// Codec can't be carried in transport stream.
+    Unsupported,
+    /// Program can't describe more streams.
+    TooManyStreams,
+    Os(os::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownStream(pid) => write!(f, "unknown stream {pid:#x}"),
+            Self::InvalidConfig => f.write_str("invalid codec configuration"),
+            Self::InvalidSample => f.write_str("invalid sample data"),
+            Self::Unsupported => f.write_str("codec is not supported"),
+            Self::TooManyStreams => f.write_str("too many streams"),
+            Self::Os(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<os::Error> for Error {
+    fn from(value: os::Error) -> Self {
+        Self::Os(value)
+    }
+}
+
+pub type Result<T = ()> = std::result::Result<T, Error>;
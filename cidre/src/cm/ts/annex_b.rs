@@ -0,0 +1,179 @@
+use crate::cm::iso;
+
+use super::{Error, Result, StreamType};
+
+const START_CODE: [u8; 4] = [0, 0, 0, 1];
+
+/// Appends NAL units of `data` with `nal_len` bytes size prefixes to `out` with start codes.
+pub fn to_annex_b(data: &[u8], nal_len: usize, out: &mut Vec<u8>) -> Result {
+    for nal in nals(data, nal_len) {
+        out.extend_from_slice(&START_CODE);
+        out.extend_from_slice(nal?);
+    }
+    Ok(())
+}
+
+fn nals(mut data: &[u8], nal_len: usize) -> impl Iterator<Item = Result<&[u8]>> {
+    std::iter::from_fn(move || {
+        if data.is_empty() {
+            return None;
+        }
+        if !(1..=4).contains(&nal_len) || data.len() < nal_len {
+            data = &[];
+            return Some(Err(Error::InvalidSample));
+        }
+        let len = data[..nal_len]
+            .iter()
+            .fold(0usize, |len, &b| len << 8 | b as usize);
+        let Some(nal) = data.get(nal_len..nal_len + len) else {
+            data = &[];
+            return Some(Err(Error::InvalidSample));
+        };
+        data = &data[nal_len + len..];
+        Some(Ok(nal))
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VideoCodec {
+    H264,
+    Hevc,
+}
+
+impl VideoCodec {
+    pub fn stream_type(self) -> StreamType {
+        match self {
+            Self::H264 => StreamType::H264,
+            Self::Hevc => StreamType::HEVC,
+        }
+    }
+
+    fn nal_type(self, nal: &[u8]) -> u8 {
+        match self {
+            Self::H264 => nal[0] & 0x1f,
+            Self::Hevc => nal[0] >> 1 & 0x3f,
+        }
+    }
+
+    fn aud(self) -> &'static [u8] {
+        match self {
+            Self::H264 => &[0x09, 0xf0],
+            Self::Hevc => &[0x46, 0x01, 0x50],
+        }
+    }
+}
+
+/// Converts length prefixed samples into Annex-B access units.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VideoConfig {
+    pub codec: VideoCodec,
+    /// Length of NAL unit size prefix in bytes.
+    pub nal_len: usize,
+    /// Parameter sets inserted before key frames.
+    pub param_sets: Vec<Vec<u8>>,
+}
+
+impl VideoConfig {
+    /// Config of `avcC` decoder configuration record.
+    pub fn with_avcc(avcc: &[u8]) -> Result<Self> {
+        let config = iso::AvcConfig::parse(avcc).map_err(|_| Error::InvalidConfig)?;
+        Ok(Self {
+            codec: VideoCodec::H264,
+            nal_len: config.nal_len as usize,
+            param_sets: config.sps.into_iter().chain(config.pps).collect(),
+        })
+    }
+
+    /// Config of `hvcC` decoder configuration record.
+    pub fn with_hvcc(hvcc: &[u8]) -> Result<Self> {
+        let config = iso::HevcConfig::parse(hvcc).map_err(|_| Error::InvalidConfig)?;
+        Ok(Self {
+            codec: VideoCodec::Hevc,
+            nal_len: config.nal_len as usize,
+            param_sets: config.param_sets().map(<[u8]>::to_vec).collect(),
+        })
+    }
+
+    /// Appends access unit delimiter, parameter sets for key frames and
+    /// NAL units of `sample` to `out`.
+    ///
+    /// Delimiters of the sample are dropped.
+    pub fn access_unit(&self, sample: &[u8], is_key: bool, out: &mut Vec<u8>) -> Result {
+        let aud_type = self.codec.nal_type(self.codec.aud());
+        out.extend_from_slice(&START_CODE);
+        out.extend_from_slice(self.codec.aud());
+        if is_key {
+            for ps in &self.param_sets {
+                out.extend_from_slice(&START_CODE);
+                out.extend_from_slice(ps);
+            }
+        }
+        for nal in nals(sample, self.nal_len) {
+            let nal = nal?;
+            if nal.is_empty() || self.codec.nal_type(nal) == aud_type {
+                continue;
+            }
+            out.extend_from_slice(&START_CODE);
+            out.extend_from_slice(nal);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cm::ts::{self, Error, VideoCodec, VideoConfig};
+
+    #[test]
+    fn annex_b() {
+        let mut out = Vec::new();
+        ts::to_annex_b(&[0, 2, 0x65, 1, 0, 1, 0x41], 2, &mut out).unwrap();
+        assert_eq!(out, [0, 0, 0, 1, 0x65, 1, 0, 0, 0, 1, 0x41]);
+        assert_eq!(
+            ts::to_annex_b(&[0, 0, 0, 5, 1], 4, &mut out),
+            Err(Error::InvalidSample)
+        );
+        assert_eq!(ts::to_annex_b(&[0], 0, &mut out), Err(Error::InvalidSample));
+    }
+
+    #[test]
+    fn access_unit() {
+        let avcc = [
+            1, 0x64, 0, 0x1f, 0xff, 0xe1, 0, 2, 0x67, 0x64, 1, 0, 2, 0x68, 0xeb,
+        ];
+        let config = VideoConfig::with_avcc(&avcc).unwrap();
+        assert_eq!(config.codec, VideoCodec::H264);
+        assert_eq!(config.nal_len, 4);
+        assert_eq!(config.param_sets, [vec![0x67, 0x64], vec![0x68, 0xeb]]);
+
+        let sample = [0, 0, 0, 2, 0x09, 0x10, 0, 0, 0, 2, 0x65, 0x88];
+        let mut out = Vec::new();
+        config.access_unit(&sample, true, &mut out).unwrap();
+        assert_eq!(
+            out,
+            [
+                0, 0, 0, 1, 0x09, 0xf0, 0, 0, 0, 1, 0x67, 0x64, 0, 0, 0, 1, 0x68, 0xeb, 0, 0, 0, 1,
+                0x65, 0x88
+            ]
+        );
+        out.clear();
+        config.access_unit(&sample[6..], false, &mut out).unwrap();
+        assert_eq!(out, [0, 0, 0, 1, 0x09, 0xf0, 0, 0, 0, 1, 0x65, 0x88]);
+
+        assert_eq!(
+            VideoConfig::with_avcc(&avcc[..8]),
+            Err(Error::InvalidConfig)
+        );
+        let mut hvcc = vec![
+            1, 1, 0x60, 0, 0, 0, 0xb0, 0, 0, 0, 0, 0, 0x5d, 0xf0, 0, 0xfc,
+        ];
+        hvcc.extend_from_slice(&[0xfd, 0xf8, 0xf8, 0, 0, 0x0f, 1, 0xa0, 0, 1, 0, 2, 0x40, 1]);
+        let config = VideoConfig::with_hvcc(&hvcc).unwrap();
+        assert_eq!(config.codec.stream_type(), ts::StreamType::HEVC);
+        assert_eq!(config.param_sets, [vec![0x40, 1]]);
+        out.clear();
+        let sample = [0, 0, 0, 3, 0x46, 1, 0x50, 0, 0, 0, 2, 0x26, 1];
+        config.access_unit(&sample, false, &mut out).unwrap();
+        assert_eq!(out, [0, 0, 0, 1, 0x46, 1, 0x50, 0, 0, 0, 1, 0x26, 1]);
+    }
+}
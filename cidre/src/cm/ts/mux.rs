@@ -0,0 +1,672 @@
+use super::{Error, Result};
+
+pub const PACKET_LEN: usize = 188;
+
+/// Clock rate of PTS, DTS and PCR base.
+pub const CLOCK_RATE: u64 = 90_000;
+
+const SYNC_BYTE: u8 = 0x47;
+const PAT_PID: u16 = 0;
+const PAYLOAD_LEN: usize = PACKET_LEN - 4;
+const TIMESTAMP_MASK: u64 = (1 << 33) - 1;
+/// Max section length of PMT is 1021 bytes, 13 of them are fixed fields and CRC.
+const MAX_STREAMS: usize = (1021 - 13) / 5;
+
+/// Stream type of PMT.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamType(pub u8);
+
+impl StreamType {
+    /// AAC with ADTS framing.
+    pub const AAC_ADTS: Self = Self(0x0f);
+    pub const H264: Self = Self(0x1b);
+    pub const HEVC: Self = Self(0x24);
+
+    #[inline]
+    pub fn is_video(self) -> bool {
+        self == Self::H264 || self == Self::HEVC
+    }
+
+    fn stream_id(self) -> u8 {
+        if self.is_video() {
+            0xe0
+        } else {
+            0xc0
+        }
+    }
+}
+
+/// Access unit with timestamps in [`CLOCK_RATE`] units.
+#[derive(Debug, Clone, Copy)]
+pub struct Frame<'a> {
+    /// Annex-B access unit or ADTS frames.
+    pub data: &'a [u8],
+    pub pts: u64,
+    /// Decode timestamp if it differs from `pts`.
+    pub dts: Option<u64>,
+    /// Random access point, sets random access indicator.
+    pub is_key: bool,
+}
+
+impl Frame<'_> {
+    #[inline]
+    fn dts(&self) -> u64 {
+        self.dts.unwrap_or(self.pts)
+    }
+}
+
+#[derive(Debug)]
+struct Stream {
+    pid: u16,
+    ty: StreamType,
+    cc: u8,
+}
+
+/// Single program transport stream muxer.
+///
+/// PAT and PMT are written before the first frame and then repeated before key frames
+/// of the PCR stream at most every [`Muxer::set_table_interval`], so segments cut at
+/// video key frames are decodable on their own. Call [`Muxer::write_tables`] at
+/// segment boundaries to force them, e.g. for audio only streams.
+/// PCR is carried by the first video stream unless set with [`Muxer::set_pcr_pid`].
+#[derive(Debug)]
+pub struct Muxer {
+    transport_stream_id: u16,
+    program_number: u16,
+    pmt_pid: u16,
+    next_pid: u16,
+    streams: Vec<Stream>,
+    pcr_pid: Option<u16>,
+    pcr_interval: u64,
+    pcr_delay: u64,
+    last_pcr: Option<u64>,
+    table_interval: u64,
+    last_tables: Option<u64>,
+    pat_cc: u8,
+    pmt_cc: u8,
+    version: u8,
+    tables_written: bool,
+    tables_pending: bool,
+}
+
+impl Default for Muxer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Muxer {
+    pub fn new() -> Self {
+        Self {
+            transport_stream_id: 1,
+            program_number: 1,
+            pmt_pid: 0x1000,
+            next_pid: 0x100,
+            streams: Vec::new(),
+            pcr_pid: None,
+            pcr_interval: CLOCK_RATE / 25,
+            pcr_delay: CLOCK_RATE * 7 / 10,
+            last_pcr: None,
+            table_interval: CLOCK_RATE / 10,
+            last_tables: None,
+            pat_cc: 0,
+            pmt_cc: 0,
+            version: 0,
+            tables_written: false,
+            tables_pending: true,
+        }
+    }
+
+    /// Adds elementary stream and returns its PID.
+    ///
+    /// Fails with [`Error::TooManyStreams`] if PMT can't describe one more stream.
+    pub fn add_stream(&mut self, ty: StreamType) -> Result<u16> {
+        if self.streams.len() >= MAX_STREAMS {
+            return Err(Error::TooManyStreams);
+        }
+        let pid = self.next_pid;
+        self.next_pid += 1;
+        self.streams.push(Stream { pid, ty, cc: 0 });
+        self.tables_changed();
+        Ok(pid)
+    }
+
+    /// Bumps version of written tables, so receivers pick up the change.
+    fn tables_changed(&mut self) {
+        if self.tables_written && !self.tables_pending {
+            self.version = (self.version + 1) & 0x1f;
+        }
+        self.tables_pending = true;
+    }
+
+    pub fn stream_type(&self, pid: u16) -> Option<StreamType> {
+        self.streams.iter().find(|s| s.pid == pid).map(|s| s.ty)
+    }
+
+    #[inline]
+    pub fn pmt_pid(&self) -> u16 {
+        self.pmt_pid
+    }
+
+    pub fn pcr_pid(&self) -> Option<u16> {
+        self.pcr_pid.or_else(|| {
+            let s = self.streams.iter().find(|s| s.ty.is_video());
+            s.or(self.streams.first()).map(|s| s.pid)
+        })
+    }
+
+    pub fn set_pcr_pid(&mut self, pid: u16) -> Result {
+        if self.stream_type(pid).is_none() {
+            return Err(Error::UnknownStream(pid));
+        }
+        let changed = self.pcr_pid() != Some(pid);
+        self.pcr_pid = Some(pid);
+        if changed {
+            self.tables_changed();
+        }
+        Ok(())
+    }
+
+    /// Max distance between PCRs, 40 ms by default.
+    pub fn set_pcr_interval(&mut self, ticks: u64) {
+        self.pcr_interval = ticks;
+    }
+
+    /// Min distance between repeated PAT and PMT, 100 ms by default.
+    pub fn set_table_interval(&mut self, ticks: u64) {
+        self.table_interval = ticks;
+    }
+
+    /// How much PCR precedes DTS, 700 ms by default like in ffmpeg.
+    ///
+    /// Gives decoders time to buffer frames before they are due.
+    pub fn set_pcr_delay(&mut self, ticks: u64) {
+        self.pcr_delay = ticks;
+    }
+
+    /// Writes PAT and PMT packets.
+    pub fn write_tables(&mut self, out: &mut Vec<u8>) {
+        let mut pat = Vec::with_capacity(16);
+        pat.push(0x00);
+        section_header(&mut pat, 13, self.transport_stream_id, self.version);
+        pat.extend_from_slice(&self.program_number.to_be_bytes());
+        pat.extend_from_slice(&(0xe000 | self.pmt_pid).to_be_bytes());
+        finish_section(&mut pat);
+        write_section(out, PAT_PID, &mut self.pat_cc, &pat);
+
+        let pcr_pid = self.pcr_pid().unwrap_or(0x1fff);
+        let mut pmt = Vec::with_capacity(16 + 5 * self.streams.len());
+        pmt.push(0x02);
+        let len = 13 + 5 * self.streams.len();
+        section_header(&mut pmt, len, self.program_number, self.version);
+        pmt.extend_from_slice(&(0xe000 | pcr_pid).to_be_bytes());
+        pmt.extend_from_slice(&[0xf0, 0x00]);
+        for s in &self.streams {
+            pmt.push(s.ty.0);
+            pmt.extend_from_slice(&(0xe000 | s.pid).to_be_bytes());
+            pmt.extend_from_slice(&[0xf0, 0x00]);
+        }
+        finish_section(&mut pmt);
+        write_section(out, self.pmt_pid, &mut self.pmt_cc, &pmt);
+
+        self.tables_written = true;
+        self.tables_pending = false;
+    }
+
+    /// Writes frame as PES packet of the stream.
+    pub fn write_frame(&mut self, pid: u16, frame: &Frame, out: &mut Vec<u8>) -> Result {
+        let Some(index) = self.streams.iter().position(|s| s.pid == pid) else {
+            return Err(Error::UnknownStream(pid));
+        };
+        let is_pcr = self.pcr_pid() == Some(pid);
+        let dts = frame.dts();
+        let repeat = is_pcr
+            && frame.is_key
+            && match self.last_tables {
+                Some(last) => dts.wrapping_sub(last) >= self.table_interval,
+                None => true,
+            };
+        if self.tables_pending || repeat {
+            self.write_tables(out);
+            self.last_tables = Some(dts);
+        }
+
+        let pcr = if is_pcr
+            && (frame.is_key
+                || match self.last_pcr {
+                    Some(last) => dts.wrapping_sub(last) >= self.pcr_interval,
+                    None => true,
+                }) {
+            self.last_pcr = Some(dts);
+            Some(dts.wrapping_sub(self.pcr_delay))
+        } else {
+            None
+        };
+
+        let stream = &mut self.streams[index];
+        let header = pes_header(stream.ty, frame);
+        let mut payload = header.iter().chain(frame.data).copied();
+        let mut remaining = header.len() + frame.data.len();
+        let mut first = true;
+        while remaining > 0 {
+            let af = if first {
+                AdaptationField {
+                    random_access: frame.is_key,
+                    pcr,
+                }
+            } else {
+                AdaptationField::default()
+            };
+            let len = remaining.min(PAYLOAD_LEN - af.min_len());
+            let start = out.len();
+            write_header(out, pid, first, &mut stream.cc, len < PAYLOAD_LEN);
+            if len < PAYLOAD_LEN {
+                af.write(out, PAYLOAD_LEN - len);
+            }
+            out.extend(payload.by_ref().take(len));
+            debug_assert_eq!(out.len() - start, PACKET_LEN);
+            remaining -= len;
+            first = false;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+struct AdaptationField {
+    random_access: bool,
+    pcr: Option<u64>,
+}
+
+impl AdaptationField {
+    /// Length of the field with its flags, 0 if there is nothing to signal.
+    fn min_len(&self) -> usize {
+        match (self.random_access, self.pcr) {
+            (false, None) => 0,
+            (_, None) => 2,
+            (_, Some(_)) => 8,
+        }
+    }
+
+    /// Writes field of `len` bytes with stuffing.
+    fn write(&self, out: &mut Vec<u8>, len: usize) {
+        debug_assert!(len >= self.min_len().max(1));
+        let start = out.len();
+        out.push((len - 1) as u8);
+        if len == 1 {
+            return;
+        }
+        let mut flags = 0;
+        if self.random_access {
+            flags |= 0x40;
+        }
+        if self.pcr.is_some() {
+            flags |= 0x10;
+        }
+        out.push(flags);
+        if let Some(pcr) = self.pcr {
+            let base = pcr & TIMESTAMP_MASK;
+            out.extend_from_slice(&[
+                (base >> 25) as u8,
+                (base >> 17) as u8,
+                (base >> 9) as u8,
+                (base >> 1) as u8,
+                ((base & 1) << 7) as u8 | 0x7e,
+                0,
+            ]);
+        }
+        out.resize(start + len, 0xff);
+    }
+}
+
+fn write_header(out: &mut Vec<u8>, pid: u16, start: bool, cc: &mut u8, has_af: bool) {
+    out.push(SYNC_BYTE);
+    out.push((start as u8) << 6 | (pid >> 8) as u8 & 0x1f);
+    out.push(pid as u8);
+    out.push(if has_af { 0x30 } else { 0x10 } | *cc);
+    *cc = (*cc + 1) & 0x0f;
+}
+
+fn pes_header(ty: StreamType, frame: &Frame) -> Vec<u8> {
+    let dts = frame.dts.filter(|&dts| dts != frame.pts);
+    let header_len = if dts.is_some() { 10 } else { 5 };
+    let len = 3 + header_len + frame.data.len();
+    // unbounded length is allowed for video only
+    let len = if ty.is_video() || len > u16::MAX as usize {
+        0
+    } else {
+        len as u16
+    };
+    let mut res = Vec::with_capacity(9 + header_len);
+    res.extend_from_slice(&[0, 0, 1, ty.stream_id()]);
+    res.extend_from_slice(&len.to_be_bytes());
+    // marker bits and data alignment indicator
+    res.push(0x84);
+    res.push(if dts.is_some() { 0xc0 } else { 0x80 });
+    res.push(header_len as u8);
+    match dts {
+        Some(dts) => {
+            write_timestamp(&mut res, 0x3, frame.pts);
+            write_timestamp(&mut res, 0x1, dts);
+        }
+        None => write_timestamp(&mut res, 0x2, frame.pts),
+    }
+    res
+}
+
+fn write_timestamp(out: &mut Vec<u8>, prefix: u8, ts: u64) {
+    let ts = ts & TIMESTAMP_MASK;
+    out.extend_from_slice(&[
+        prefix << 4 | (ts >> 29) as u8 & 0x0e | 1,
+        (ts >> 22) as u8,
+        (ts >> 14) as u8 | 1,
+        (ts >> 7) as u8,
+        (ts << 1) as u8 | 1,
+    ]);
+}
+
+/// Writes section length and syntax fields, `len` counts bytes after length including CRC.
+fn section_header(out: &mut Vec<u8>, len: usize, id: u16, version: u8) {
+    out.extend_from_slice(&(0xb000 | len as u16).to_be_bytes());
+    out.extend_from_slice(&id.to_be_bytes());
+    out.push(0xc1 | version << 1);
+    out.extend_from_slice(&[0, 0]);
+}
+
+fn finish_section(section: &mut Vec<u8>) {
+    let crc = crc32(section);
+    section.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// Writes section with pointer field, continues it in following packets if it doesn't fit.
+fn write_section(out: &mut Vec<u8>, pid: u16, cc: &mut u8, section: &[u8]) {
+    let mut rest = section;
+    let mut first = true;
+    while first || !rest.is_empty() {
+        let start = out.len();
+        write_header(out, pid, first, cc, false);
+        if first {
+            out.push(0);
+        }
+        let len = rest.len().min(start + PACKET_LEN - out.len());
+        out.extend_from_slice(&rest[..len]);
+        out.resize(start + PACKET_LEN, 0xff);
+        rest = &rest[len..];
+        first = false;
+    }
+}
+
+/// CRC-32/MPEG-2 of PSI sections.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = u32::MAX;
+    for &b in data {
+        crc ^= (b as u32) << 24;
+        for _ in 0..8 {
+            crc = if crc & 0x8000_0000 != 0 {
+                crc << 1 ^ 0x04c1_1db7
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cm::ts::{Error, Frame, Muxer, StreamType, PACKET_LEN};
+
+    use super::crc32;
+
+    struct Packet<'a> {
+        pid: u16,
+        start: bool,
+        cc: u8,
+        af: &'a [u8],
+        payload: &'a [u8],
+    }
+
+    fn packets(data: &[u8]) -> Vec<Packet<'_>> {
+        assert_eq!(data.len() % PACKET_LEN, 0);
+        data.chunks(PACKET_LEN)
+            .map(|p| {
+                assert_eq!(p[0], 0x47);
+                let has_af = p[3] & 0x20 != 0;
+                let af_len = if has_af { p[4] as usize + 1 } else { 0 };
+                Packet {
+                    pid: u16::from_be_bytes([p[1] & 0x1f, p[2]]),
+                    start: p[1] & 0x40 != 0,
+                    cc: p[3] & 0x0f,
+                    af: &p[4..4 + af_len],
+                    payload: &p[4 + af_len..],
+                }
+            })
+            .collect()
+    }
+
+    fn timestamp(b: &[u8]) -> u64 {
+        (b[0] as u64 >> 1 & 7) << 30
+            | (b[1] as u64) << 22
+            | (b[2] as u64 >> 1) << 15
+            | (b[3] as u64) << 7
+            | b[4] as u64 >> 1
+    }
+
+    fn pcr_base(af: &[u8]) -> u64 {
+        let pcr = &af[2..8];
+        (pcr[0] as u64) << 25
+            | (pcr[1] as u64) << 17
+            | (pcr[2] as u64) << 9
+            | (pcr[3] as u64) << 1
+            | pcr[4] as u64 >> 7
+    }
+
+    #[test]
+    fn tables() {
+        let mut mux = Muxer::new();
+        let mut out = Vec::new();
+        mux.write_tables(&mut out);
+        // PAT of ffmpeg with the same ids
+        assert_eq!(
+            &out[..21],
+            &[
+                0x47, 0x40, 0x00, 0x10, 0x00, 0x00, 0xb0, 0x0d, 0x00, 0x01, 0xc1, 0x00, 0x00, 0x00,
+                0x01, 0xf0, 0x00, 0x2a, 0xb1, 0x04, 0xb2
+            ]
+        );
+        assert!(out[21..PACKET_LEN].iter().all(|&b| b == 0xff));
+
+        let audio = mux.add_stream(StreamType::AAC_ADTS).unwrap();
+        let video = mux.add_stream(StreamType::H264).unwrap();
+        assert_eq!(mux.pcr_pid(), Some(video));
+        out.clear();
+        mux.write_tables(&mut out);
+        let ps = packets(&out);
+        assert_eq!(ps[0].cc, 1);
+        let pmt = &ps[1].payload[1..];
+        assert_eq!(ps[1].pid, mux.pmt_pid());
+        let len = (u16::from_be_bytes([pmt[1], pmt[2]]) & 0xfff) as usize;
+        assert_eq!(len, 23);
+        assert_eq!(crc32(&pmt[..3 + len]), 0);
+        // version is bumped after the change
+        assert_eq!(pmt[5], 0xc3);
+        assert_eq!(u16::from_be_bytes([pmt[8], pmt[9]]) & 0x1fff, video);
+        assert_eq!(pmt[12], 0x0f);
+        assert_eq!(u16::from_be_bytes([pmt[13], pmt[14]]) & 0x1fff, audio);
+        assert_eq!(pmt[17], 0x1b);
+
+        mux.set_pcr_pid(audio).unwrap();
+        assert_eq!(mux.pcr_pid(), Some(audio));
+        assert_eq!(mux.set_pcr_pid(7), Err(Error::UnknownStream(7)));
+        out.clear();
+        mux.write_tables(&mut out);
+        let pmt = &packets(&out)[1].payload[1..];
+        assert_eq!(pmt[5], 0xc5);
+        assert_eq!(u16::from_be_bytes([pmt[8], pmt[9]]) & 0x1fff, audio);
+
+        // same PCR stream keeps the version
+        mux.set_pcr_pid(audio).unwrap();
+        out.clear();
+        mux.write_tables(&mut out);
+        assert_eq!(packets(&out)[1].payload[1..][5], 0xc5);
+    }
+
+    #[test]
+    fn video() {
+        let mut mux = Muxer::new();
+        let pid = mux.add_stream(StreamType::H264).unwrap();
+        mux.set_pcr_delay(9000);
+        let data: Vec<u8> = (0..400).map(|i| i as u8).collect();
+        let mut out = Vec::new();
+        let frame = Frame {
+            data: &data,
+            pts: 93000,
+            dts: Some(90000),
+            is_key: true,
+        };
+        mux.write_frame(pid, &frame, &mut out).unwrap();
+        let ps = packets(&out);
+        assert_eq!(ps.len(), 2 + 3);
+        assert_eq!((ps[0].pid, ps[1].pid), (0, mux.pmt_pid()));
+
+        let first = &ps[2];
+        assert!(first.start);
+        assert_eq!(first.af[1], 0x50);
+        assert_eq!(pcr_base(first.af), 81000);
+
+        let pes: Vec<u8> = ps[2..].iter().flat_map(|p| p.payload).copied().collect();
+        assert_eq!(&pes[..4], &[0, 0, 1, 0xe0]);
+        assert_eq!(&pes[4..6], &[0, 0]);
+        assert_eq!(pes[7], 0xc0);
+        assert_eq!(pes[8], 10);
+        assert_eq!(pes[9] >> 4, 3);
+        assert_eq!(timestamp(&pes[9..]), 93000);
+        assert_eq!(pes[14] >> 4, 1);
+        assert_eq!(timestamp(&pes[14..]), 90000);
+        assert_eq!(&pes[19..], &data);
+        for (i, p) in ps[2..].iter().enumerate() {
+            assert_eq!(p.pid, pid);
+            assert_eq!(p.cc, i as u8);
+            assert_eq!(p.start, i == 0);
+        }
+        // last packet is stuffed
+        assert!(ps[4].af.len() > 1);
+        assert_eq!(ps[4].af[1], 0);
+
+        // no tables and PCR for non key frame within the interval
+        out.clear();
+        let frame = Frame {
+            data: &data[..10],
+            pts: 96000,
+            dts: Some(93000),
+            is_key: false,
+        };
+        mux.write_frame(pid, &frame, &mut out).unwrap();
+        let ps = packets(&out);
+        assert_eq!(ps.len(), 1);
+        assert_eq!(ps[0].cc, 3);
+        assert_eq!(ps[0].af[1], 0);
+        assert_eq!(
+            mux.write_frame(1, &frame, &mut out),
+            Err(Error::UnknownStream(1))
+        );
+
+        // default delay of 700 ms wraps around like timestamps
+        let mut mux = Muxer::new();
+        let pid = mux.add_stream(StreamType::H264).unwrap();
+        out.clear();
+        let frame = Frame {
+            data: &data[..10],
+            pts: 0,
+            dts: None,
+            is_key: true,
+        };
+        mux.write_frame(pid, &frame, &mut out).unwrap();
+        assert_eq!(pcr_base(packets(&out)[2].af), (1 << 33) - 63000);
+    }
+
+    #[test]
+    fn audio() {
+        let mut mux = Muxer::new();
+        let video = mux.add_stream(StreamType::HEVC).unwrap();
+        let audio = mux.add_stream(StreamType::AAC_ADTS).unwrap();
+        let mut out = Vec::new();
+        for i in 0..20u64 {
+            let data = [i as u8; 167];
+            let frame = Frame {
+                data: &data,
+                pts: (1 << 33) - 1024 + i * 1920,
+                dts: None,
+                is_key: true,
+            };
+            mux.write_frame(audio, &frame, &mut out).unwrap();
+        }
+        let ps = packets(&out);
+        assert_eq!(ps.len(), 2 + 20);
+        for (i, p) in ps[2..].iter().enumerate() {
+            assert_eq!(p.pid, audio);
+            assert_eq!(p.cc, i as u8 & 0x0f);
+            // random access flag and single stuffing byte
+            assert_eq!(p.af, &[2, 0x40, 0xff]);
+            assert_eq!(&p.payload[..4], &[0, 0, 1, 0xc0]);
+            assert_eq!(u16::from_be_bytes([p.payload[4], p.payload[5]]), 175);
+            let pts = ((1u64 << 33) - 1024 + i as u64 * 1920) & ((1 << 33) - 1);
+            assert_eq!(timestamp(&p.payload[9..]), pts);
+        }
+        // PCR of video stream only
+        assert_eq!(mux.pcr_pid(), Some(video));
+
+        // tables are repeated every 100 ms in audio only stream
+        let mut mux = Muxer::new();
+        let audio = mux.add_stream(StreamType::AAC_ADTS).unwrap();
+        out.clear();
+        for i in 0..20u64 {
+            let frame = Frame {
+                data: &[0; 10],
+                pts: i * 1920,
+                dts: None,
+                is_key: true,
+            };
+            mux.write_frame(audio, &frame, &mut out).unwrap();
+        }
+        let ps = packets(&out);
+        let pats: Vec<_> = ps.iter().enumerate().filter(|(_, p)| p.pid == 0).collect();
+        assert_eq!(pats.len(), 4);
+        assert_eq!(ps.len(), 20 + 2 * 4);
+        // frames 0, 5, 10 and 15
+        assert_eq!(pats[1].0, 5 + 2);
+    }
+
+    #[test]
+    fn many_streams() {
+        let mut mux = Muxer::new();
+        for _ in 0..201 {
+            mux.add_stream(StreamType::AAC_ADTS).unwrap();
+        }
+        assert_eq!(
+            mux.add_stream(StreamType::AAC_ADTS),
+            Err(Error::TooManyStreams)
+        );
+        let mut out = Vec::new();
+        mux.write_tables(&mut out);
+        let ps = packets(&out);
+        assert_eq!(ps.len(), 1 + 6);
+        assert!(ps[1..].iter().all(|p| p.pid == mux.pmt_pid()));
+        assert!(ps[1].start);
+        assert!(ps[2..].iter().all(|p| !p.start));
+        for (i, p) in ps[1..].iter().enumerate() {
+            assert_eq!(p.cc, i as u8);
+        }
+        let pmt: Vec<u8> = ps[1..].iter().flat_map(|p| p.payload).copied().collect();
+        let pmt = &pmt[1..];
+        let len = (u16::from_be_bytes([pmt[1], pmt[2]]) & 0xfff) as usize;
+        assert_eq!(len, 1018);
+        assert_eq!(crc32(&pmt[..3 + len]), 0);
+        assert!(pmt[3 + len..].iter().all(|&b| b == 0xff));
+    }
+
+    #[test]
+    fn crc() {
+        assert_eq!(crc32(b"123456789"), 0x0376_e6e7);
+    }
+}
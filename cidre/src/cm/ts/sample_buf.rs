@@ -0,0 +1,148 @@
+use crate::cm;
+
+#[cfg(feature = "cat")]
+use crate::cat::audio::aac;
+
+use super::{Error, Frame, Muxer, Result, VideoConfig, CLOCK_RATE};
+
+/// Converts `time` into 33-bit 90 kHz clock ticks.
+///
+/// Returns `None` for invalid times.
+pub fn ticks(time: cm::Time) -> Option<u64> {
+    if !time.is_valid() || time.scale <= 0 {
+        return None;
+    }
+    let ticks = (time.value as i128 * CLOCK_RATE as i128).div_euclid(time.scale as i128);
+    Some(ticks.rem_euclid(1 << 33) as u64)
+}
+
+enum Codec {
+    Video(VideoConfig),
+    #[cfg(feature = "cat")]
+    Aac(aac::AudioSpecificConfig),
+}
+
+struct Input {
+    pid: u16,
+    codec: Codec,
+}
+
+/// Transport stream muxer of H.264/HEVC and AAC sample buffers.
+#[derive(Default)]
+pub struct SampleBufMuxer {
+    mux: Muxer,
+    inputs: Vec<Input>,
+    data: Vec<u8>,
+    frame: Vec<u8>,
+}
+
+impl SampleBufMuxer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn muxer(&self) -> &Muxer {
+        &self.mux
+    }
+
+    pub fn muxer_mut(&mut self) -> &mut Muxer {
+        &mut self.mux
+    }
+
+    /// Adds stream for `avc1`, `hvc1`/`hev1` or `aac ` format description.
+    /// Returns PID of the stream.
+    pub fn add_stream(&mut self, desc: &cm::FormatDesc) -> Result<u16> {
+        let codec = match &desc.media_sub_type().to_be_bytes() {
+            b"avc1" => {
+                let avcc = desc.avcc().ok_or(Error::InvalidConfig)?;
+                Codec::Video(VideoConfig::with_avcc(&avcc)?)
+            }
+            b"hvc1" | b"hev1" => {
+                let hvcc = desc.hvcc().ok_or(Error::InvalidConfig)?;
+                Codec::Video(VideoConfig::with_hvcc(&hvcc)?)
+            }
+            #[cfg(feature = "cat")]
+            b"aac " => {
+                let cookie = desc.magic_cookie().ok_or(Error::InvalidConfig)?;
+                let asc = aac::AudioSpecificConfig::with_magic_cookie(cookie)
+                    .map_err(|_| Error::InvalidConfig)?;
+                Codec::Aac(asc)
+            }
+            _ => return Err(Error::Unsupported),
+        };
+        let ty = match &codec {
+            Codec::Video(config) => config.codec.stream_type(),
+            #[cfg(feature = "cat")]
+            Codec::Aac(_) => super::StreamType::AAC_ADTS,
+        };
+        let pid = self.mux.add_stream(ty)?;
+        self.inputs.push(Input { pid, codec });
+        Ok(pid)
+    }
+
+    /// Appends packets of `buf` of stream `pid` to `out`.
+    pub fn write(&mut self, pid: u16, buf: &cm::SampleBuf, out: &mut Vec<u8>) -> Result {
+        let input = self
+            .inputs
+            .iter()
+            .find(|i| i.pid == pid)
+            .ok_or(Error::UnknownStream(pid))?;
+        let block = buf.data_buf().ok_or(Error::InvalidSample)?;
+        self.data.clear();
+        let mut offset = 0;
+        while offset < block.data_len() {
+            let (chunk, _) = block.data_ptr_at(offset)?;
+            if chunk.is_empty() {
+                return Err(Error::InvalidSample);
+            }
+            self.data.extend_from_slice(chunk);
+            offset += chunk.len();
+        }
+        let pts = ticks(buf.pts()).ok_or(Error::InvalidSample)?;
+        self.frame.clear();
+        let frame = match &input.codec {
+            Codec::Video(config) => {
+                let is_key = buf.is_key_frame();
+                config.access_unit(&self.data, is_key, &mut self.frame)?;
+                let dts = ticks(buf.dts()).filter(|&dts| dts != pts);
+                Frame {
+                    data: &self.frame,
+                    pts,
+                    dts,
+                    is_key,
+                }
+            }
+            #[cfg(feature = "cat")]
+            Codec::Aac(asc) => {
+                let res = match buf.audio_stream_packet_descs()? {
+                    Some(packets) => aac::add_adts(asc, &self.data, packets, &mut self.frame),
+                    None => aac::AdtsHeader::with_asc(asc, self.data.len()).map(|header| {
+                        self.frame.extend_from_slice(&header.to_bytes());
+                        self.frame.extend_from_slice(&self.data);
+                    }),
+                };
+                res.map_err(|_| Error::InvalidSample)?;
+                Frame {
+                    data: &self.frame,
+                    pts,
+                    dts: None,
+                    is_key: true,
+                }
+            }
+        };
+        self.mux.write_frame(pid, &frame, out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cm::{self, ts};
+
+    #[test]
+    fn ticks() {
+        assert_eq!(ts::ticks(cm::Time::new(1, 30)), Some(3000));
+        assert_eq!(ts::ticks(cm::Time::new(1001, 30_000)), Some(3003));
+        assert_eq!(ts::ticks(cm::Time::new(-1, 90_000)), Some((1 << 33) - 1));
+        assert_eq!(ts::ticks(cm::Time::invalid()), None);
+    }
+}
@@ -0,0 +1,561 @@
+//! Deterministic clock and timebases for testing playback and sync logic.
+//!
+//! [`VirtualClock`] only moves when advanced. [`VirtualTimebase`]s follow
+//! the clock or other timebases like [`cm::Timebase`] does and post
+//! [`Notification`]s that are collected by the clock instead of the
+//! notification center.
+
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{cm, cm::sync::timebase_err, os};
+
+const NANOS: i64 = 1_000_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TimebaseId(usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TimerId(usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    /// Time of the timebase jumped, directly or by one of its sources.
+    TimeJumped,
+    /// Effective rate of the timebase changed, directly or by one of its sources.
+    EffectiveRateChanged,
+    /// Time of the timebase passed fire time of the timer.
+    TimerFired(TimerId),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Notification {
+    pub timebase: TimebaseId,
+    pub event: Event,
+    /// Clock time when the notification was posted.
+    pub clock_time: cm::Time,
+}
+
+struct Node {
+    /// `None` for timebases driven by the clock.
+    src: Option<usize>,
+    rate: f64,
+    anchor_time: i64,
+    anchor_src_time: i64,
+}
+
+struct Timer {
+    timebase: usize,
+    fire_time: Option<i64>,
+    removed: bool,
+}
+
+#[derive(Default)]
+struct Inner {
+    now: i64,
+    nodes: Vec<Node>,
+    timers: Vec<Timer>,
+    notifications: Vec<Notification>,
+}
+
+impl Inner {
+    fn src_time(&self, id: usize) -> i64 {
+        match self.nodes[id].src {
+            Some(src) => self.time(src),
+            None => self.now,
+        }
+    }
+
+    /// Saturates at the ends of the timeline, so far anchors can't overflow.
+    fn time(&self, id: usize) -> i64 {
+        let node = &self.nodes[id];
+        let elapsed = self.src_time(id).saturating_sub(node.anchor_src_time);
+        let scaled = (elapsed as f64 * node.rate).round() as i64;
+        node.anchor_time.saturating_add(scaled)
+    }
+
+    fn effective_rate(&self, id: usize) -> f64 {
+        let node = &self.nodes[id];
+        match node.src {
+            Some(src) => node.rate * self.effective_rate(src),
+            None => node.rate,
+        }
+    }
+
+    /// The timebase and all timebases driven by it.
+    fn subtree(&self, id: usize) -> Vec<usize> {
+        // sources are always created before timebases they drive
+        let mut ids = vec![id];
+        for (i, node) in self.nodes.iter().enumerate().skip(id + 1) {
+            if node.src.is_some_and(|src| ids.contains(&src)) {
+                ids.push(i);
+            }
+        }
+        ids
+    }
+
+    fn post(&mut self, id: usize, event: Event) {
+        self.notifications.push(Notification {
+            timebase: TimebaseId(id),
+            event,
+            clock_time: time(self.now),
+        });
+    }
+
+    fn anchor(&mut self, id: usize, rate: f64, time: i64, src_time: i64) {
+        let ids = self.subtree(id);
+        let rates: Vec<f64> = ids.iter().map(|&i| self.effective_rate(i)).collect();
+        let node = &mut self.nodes[id];
+        node.rate = rate;
+        node.anchor_time = time;
+        node.anchor_src_time = src_time;
+        for (&i, &r) in ids.iter().zip(&rates) {
+            if self.effective_rate(i) != r {
+                self.post(i, Event::EffectiveRateChanged);
+            }
+        }
+    }
+
+    fn jumped(&mut self, id: usize) {
+        for i in self.subtree(id) {
+            self.post(i, Event::TimeJumped);
+        }
+    }
+
+    /// Clock time delta after which the timer is due, `Some(0)` if it is due now.
+    fn due_in(&self, timer: &Timer) -> Option<i64> {
+        let fire_time = timer.fire_time.filter(|_| !timer.removed)?;
+        let rate = self.effective_rate(timer.timebase);
+        if rate == 0.0 {
+            return None;
+        }
+        let left = fire_time.saturating_sub(self.time(timer.timebase)) as f64 / rate;
+        Some(left.ceil().max(0.0) as i64)
+    }
+
+    fn fire_due(&mut self) {
+        for i in 0..self.timers.len() {
+            if self.due_in(&self.timers[i]) == Some(0) {
+                let timer = &mut self.timers[i];
+                timer.fire_time = None;
+                let timebase = timer.timebase;
+                self.post(timebase, Event::TimerFired(TimerId(i)));
+            }
+        }
+    }
+
+    fn advance(&mut self, delta: i64) {
+        let end = self.now.saturating_add(delta);
+        loop {
+            self.fire_due();
+            let next = self.timers.iter().filter_map(|t| self.due_in(t)).min();
+            match next {
+                Some(next) if self.now < end && next <= end - self.now => self.now += next.max(1),
+                _ => break,
+            }
+        }
+        self.now = end;
+        self.fire_due();
+    }
+}
+
+fn nanos(time: cm::Time) -> os::Result<i64> {
+    if !time.is_numeric() || time.scale <= 0 {
+        return Err(timebase_err::INVALID_PARAMETER);
+    }
+    let nanos = (time.value as i128 * NANOS as i128).div_euclid(time.scale as i128);
+    i64::try_from(nanos).map_err(|_| timebase_err::INVALID_PARAMETER)
+}
+
+fn time(nanos: i64) -> cm::Time {
+    cm::Time {
+        value: nanos,
+        scale: NANOS as i32,
+        flags: cm::TimeFlags::VALID,
+        epoch: 0,
+    }
+}
+
+fn check_rate(rate: f64) -> os::Result {
+    if rate.is_finite() {
+        Ok(())
+    } else {
+        Err(timebase_err::INVALID_PARAMETER)
+    }
+}
+
+/// Clock which time changes only by [`VirtualClock::advance`].
+///
+/// Clones share the same clock.
+#[derive(Clone, Default)]
+pub struct VirtualClock {
+    inner: Rc<RefCell<Inner>>,
+}
+
+impl VirtualClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn time(&self) -> cm::Time {
+        time(self.inner.borrow().now)
+    }
+
+    /// Moves the clock forward by `delta`, firing timers of driven timebases in order.
+    pub fn advance(&self, delta: cm::Time) -> os::Result {
+        let delta = nanos(delta)?;
+        if delta < 0 {
+            return Err(timebase_err::INVALID_PARAMETER);
+        }
+        self.inner.borrow_mut().advance(delta);
+        Ok(())
+    }
+
+    /// Returns notifications posted since the last call.
+    pub fn take_notifications(&self) -> Vec<Notification> {
+        std::mem::take(&mut self.inner.borrow_mut().notifications)
+    }
+}
+
+/// Timebase driven by [`VirtualClock`] or other virtual timebase.
+pub struct VirtualTimebase {
+    inner: Rc<RefCell<Inner>>,
+    id: usize,
+}
+
+impl VirtualTimebase {
+    fn with_src(inner: &Rc<RefCell<Inner>>, src: Option<usize>) -> Self {
+        let mut state = inner.borrow_mut();
+        let anchor_src_time = match src {
+            Some(src) => state.time(src),
+            None => state.now,
+        };
+        state.nodes.push(Node {
+            src,
+            rate: 0.0,
+            anchor_time: 0,
+            anchor_src_time,
+        });
+        Self {
+            inner: inner.clone(),
+            id: state.nodes.len() - 1,
+        }
+    }
+
+    /// Creates a timebase with rate zero and time zero driven by the clock.
+    pub fn with_src_clock(src_clock: &VirtualClock) -> Self {
+        Self::with_src(&src_clock.inner, None)
+    }
+
+    /// Creates a timebase with rate zero and time zero driven by the timebase.
+    pub fn with_src_timebase(src_timebase: &VirtualTimebase) -> Self {
+        Self::with_src(&src_timebase.inner, Some(src_timebase.id))
+    }
+
+    #[inline]
+    pub fn id(&self) -> TimebaseId {
+        TimebaseId(self.id)
+    }
+
+    /// The ultimate source clock.
+    pub fn clock(&self) -> VirtualClock {
+        VirtualClock {
+            inner: self.inner.clone(),
+        }
+    }
+
+    /// Adds timer which isn't scheduled until [`VirtualTimebase::set_timer_next_fire_time`].
+    pub fn add_timer(&mut self) -> TimerId {
+        let mut inner = self.inner.borrow_mut();
+        inner.timers.push(Timer {
+            timebase: self.id,
+            fire_time: None,
+            removed: false,
+        });
+        TimerId(inner.timers.len() - 1)
+    }
+
+    pub fn remove_timer(&mut self, timer: TimerId) -> os::Result {
+        self.with_timer(timer, |t| t.removed = true)
+    }
+
+    /// Sets the time on the timebase's timeline at which the timer should fire once.
+    ///
+    /// Non-numeric `fire_time` unschedules the timer.
+    pub fn set_timer_next_fire_time(&mut self, timer: TimerId, fire_time: cm::Time) -> os::Result {
+        let fire_time = nanos(fire_time).ok();
+        self.with_timer(timer, |t| t.fire_time = fire_time)?;
+        self.inner.borrow_mut().fire_due();
+        Ok(())
+    }
+
+    pub fn set_timer_to_fire_immediately(&mut self, timer: TimerId) -> os::Result {
+        self.with_timer(timer, |t| t.fire_time = None)?;
+        self.inner
+            .borrow_mut()
+            .post(self.id, Event::TimerFired(timer));
+        Ok(())
+    }
+
+    fn with_timer(&mut self, timer: TimerId, f: impl FnOnce(&mut Timer)) -> os::Result {
+        let mut inner = self.inner.borrow_mut();
+        match inner.timers.get_mut(timer.0) {
+            Some(t) if t.timebase == self.id && !t.removed => {
+                f(t);
+                Ok(())
+            }
+            _ => Err(timebase_err::INVALID_PARAMETER),
+        }
+    }
+}
+
+impl cm::TimebaseControl for VirtualTimebase {
+    fn time(&self) -> cm::Time {
+        time(self.inner.borrow().time(self.id))
+    }
+
+    fn rate(&self) -> f64 {
+        self.inner.borrow().nodes[self.id].rate
+    }
+
+    fn effective_rate(&self) -> f64 {
+        self.inner.borrow().effective_rate(self.id)
+    }
+
+    fn set_time(&mut self, val: cm::Time) -> os::Result {
+        let val = nanos(val)?;
+        let mut inner = self.inner.borrow_mut();
+        let rate = inner.nodes[self.id].rate;
+        let src_time = inner.src_time(self.id);
+        inner.anchor(self.id, rate, val, src_time);
+        inner.jumped(self.id);
+        inner.fire_due();
+        Ok(())
+    }
+
+    fn set_rate(&mut self, val: f64) -> os::Result {
+        check_rate(val)?;
+        let mut inner = self.inner.borrow_mut();
+        let time = inner.time(self.id);
+        let src_time = inner.src_time(self.id);
+        inner.anchor(self.id, val, time, src_time);
+        inner.fire_due();
+        Ok(())
+    }
+
+    fn set_anchor_time(
+        &mut self,
+        timebase_time: cm::Time,
+        immediate_src_time: cm::Time,
+    ) -> os::Result {
+        let rate = cm::TimebaseControl::rate(self);
+        cm::TimebaseControl::set_rate_and_anchor_time(self, rate, timebase_time, immediate_src_time)
+    }
+
+    fn set_rate_and_anchor_time(
+        &mut self,
+        rate: f64,
+        timebase_time: cm::Time,
+        immediate_src_time: cm::Time,
+    ) -> os::Result {
+        check_rate(rate)?;
+        let timebase_time = nanos(timebase_time)?;
+        let src_time = nanos(immediate_src_time)?;
+        let mut inner = self.inner.borrow_mut();
+        inner.anchor(self.id, rate, timebase_time, src_time);
+        inner.jumped(self.id);
+        inner.fire_due();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cm::{
+        self,
+        virtual_timebase::{Event, TimebaseId},
+        TimebaseControl,
+    };
+
+    fn ms(ms: i64) -> cm::Time {
+        cm::Time {
+            value: ms,
+            scale: 1000,
+            flags: cm::TimeFlags::VALID,
+            epoch: 0,
+        }
+    }
+
+    fn as_ms(time: cm::Time) -> i64 {
+        time.value * 1000 / time.scale as i64
+    }
+
+    fn events(clock: &cm::VirtualClock) -> Vec<(TimebaseId, Event, i64)> {
+        clock
+            .take_notifications()
+            .into_iter()
+            .map(|n| (n.timebase, n.event, as_ms(n.clock_time)))
+            .collect()
+    }
+
+    fn play<T: TimebaseControl>(timebase: &mut T) {
+        timebase.set_time(ms(1000)).unwrap();
+        timebase.set_rate(1.0).unwrap();
+    }
+
+    #[test]
+    fn rate_and_time() {
+        let clock = cm::VirtualClock::new();
+        let mut tb = cm::VirtualTimebase::with_src_clock(&clock);
+        assert_eq!(as_ms(tb.time()), 0);
+        assert_eq!(tb.rate(), 0.0);
+
+        clock.advance(ms(500)).unwrap();
+        assert_eq!(as_ms(tb.time()), 0);
+
+        play(&mut tb);
+        assert_eq!(
+            events(&clock),
+            [
+                (tb.id(), Event::TimeJumped, 500),
+                (tb.id(), Event::EffectiveRateChanged, 500)
+            ]
+        );
+        clock.advance(ms(250)).unwrap();
+        assert_eq!(as_ms(tb.time()), 1250);
+
+        tb.set_rate(-2.0).unwrap();
+        clock.advance(ms(100)).unwrap();
+        assert_eq!(as_ms(tb.time()), 1050);
+
+        // same rate posts nothing
+        tb.set_rate(-2.0).unwrap();
+        assert_eq!(
+            events(&clock),
+            [(tb.id(), Event::EffectiveRateChanged, 750)]
+        );
+
+        tb.set_rate_and_anchor_time(1.0, ms(0), ms(1000)).unwrap();
+        assert_eq!(as_ms(tb.time()), -150);
+        assert_eq!(as_ms(clock.time()), 850);
+
+        assert!(clock.advance(ms(-1)).is_err());
+        assert!(tb.set_rate(f64::NAN).is_err());
+        assert!(tb.set_time(cm::Time::invalid()).is_err());
+    }
+
+    #[test]
+    fn far_anchors() {
+        let clock = cm::VirtualClock::new();
+        let mut tb = cm::VirtualTimebase::with_src_clock(&clock);
+        let max = cm::Time {
+            value: i64::MAX,
+            scale: 1_000_000_000,
+            flags: cm::TimeFlags::VALID,
+            epoch: 0,
+        };
+        tb.set_rate_and_anchor_time(1.0, max, ms(0)).unwrap();
+        clock.advance(ms(1000)).unwrap();
+        assert_eq!(tb.time().value, i64::MAX);
+
+        let min = cm::Time {
+            value: -i64::MAX,
+            ..max
+        };
+        tb.set_rate_and_anchor_time(-2.0, ms(0), min).unwrap();
+        assert_eq!(tb.time().value, i64::MIN);
+
+        let timer = tb.add_timer();
+        tb.set_timer_next_fire_time(timer, max).unwrap();
+        clock.advance(max).unwrap();
+        clock.advance(max).unwrap();
+        assert_eq!(clock.time().value, i64::MAX);
+    }
+
+    #[test]
+    fn nested() {
+        let clock = cm::VirtualClock::new();
+        let mut parent = cm::VirtualTimebase::with_src_clock(&clock);
+        let mut child = cm::VirtualTimebase::with_src_timebase(&parent);
+        let other = cm::VirtualTimebase::with_src_clock(&clock);
+        child.set_rate(0.5).unwrap();
+        assert_eq!(child.effective_rate(), 0.0);
+        clock.take_notifications();
+
+        parent.set_rate(2.0).unwrap();
+        assert_eq!(child.effective_rate(), 1.0);
+        clock.advance(ms(1000)).unwrap();
+        assert_eq!(as_ms(parent.time()), 2000);
+        assert_eq!(as_ms(child.time()), 1000);
+
+        parent.set_time(ms(0)).unwrap();
+        assert_eq!(as_ms(child.time()), 0);
+        clock.advance(ms(500)).unwrap();
+        assert_eq!(as_ms(child.time()), 500);
+
+        parent.set_rate(0.0).unwrap();
+        assert_eq!(
+            events(&clock),
+            [
+                (parent.id(), Event::EffectiveRateChanged, 0),
+                (child.id(), Event::EffectiveRateChanged, 0),
+                (parent.id(), Event::TimeJumped, 1000),
+                (child.id(), Event::TimeJumped, 1000),
+                (parent.id(), Event::EffectiveRateChanged, 1500),
+                (child.id(), Event::EffectiveRateChanged, 1500),
+            ]
+        );
+        assert_eq!(other.effective_rate(), 0.0);
+    }
+
+    #[test]
+    fn timers() {
+        let clock = cm::VirtualClock::new();
+        let mut tb = cm::VirtualTimebase::with_src_clock(&clock);
+        let a = tb.add_timer();
+        let b = tb.add_timer();
+        tb.set_timer_next_fire_time(b, ms(100)).unwrap();
+        tb.set_timer_next_fire_time(a, ms(300)).unwrap();
+        // not moving
+        clock.advance(ms(1000)).unwrap();
+        assert!(events(&clock).is_empty());
+
+        tb.set_rate(2.0).unwrap();
+        clock.take_notifications();
+        clock.advance(ms(1000)).unwrap();
+        assert_eq!(
+            events(&clock),
+            [
+                (tb.id(), Event::TimerFired(b), 1050),
+                (tb.id(), Event::TimerFired(a), 1150)
+            ]
+        );
+        // timers fire once
+        clock.advance(ms(1000)).unwrap();
+        assert!(events(&clock).is_empty());
+
+        // fire time passed by jump
+        tb.set_timer_next_fire_time(a, ms(10_000)).unwrap();
+        tb.set_time(ms(20_000)).unwrap();
+        assert_eq!(
+            events(&clock),
+            [
+                (tb.id(), Event::TimeJumped, 3000),
+                (tb.id(), Event::TimerFired(a), 3000)
+            ]
+        );
+
+        tb.set_rate(-1.0).unwrap();
+        tb.set_timer_next_fire_time(b, ms(19_000)).unwrap();
+        clock.take_notifications();
+        clock.advance(ms(2000)).unwrap();
+        assert_eq!(events(&clock), [(tb.id(), Event::TimerFired(b), 4000)]);
+
+        tb.set_timer_to_fire_immediately(a).unwrap();
+        assert_eq!(events(&clock), [(tb.id(), Event::TimerFired(a), 5000)]);
+
+        tb.remove_timer(a).unwrap();
+        assert!(tb.set_timer_next_fire_time(a, ms(0)).is_err());
+        let mut other = cm::VirtualTimebase::with_src_clock(&clock);
+        assert!(other.remove_timer(b).is_err());
+    }
+}
@@ -28,3 +28,12 @@ pub mod hardware_tapping;
 pub use hardware_tapping::Tap;
 #[cfg(feature = "macos_14_2")]
 pub use hardware_tapping::TapGuard;
+
+#[cfg(feature = "macos_14_2")]
+mod tap_capture;
+#[cfg(feature = "macos_14_2")]
+pub use tap_capture::tap_aggregate_desc;
+#[cfg(feature = "macos_14_2")]
+pub use tap_capture::TapBuf;
+#[cfg(feature = "macos_14_2")]
+pub use tap_capture::TapCapture;
@@ -0,0 +1,428 @@
+use std::sync::{
+    atomic::{AtomicBool, AtomicU32, Ordering},
+    Arc, Mutex,
+};
+
+use crate::{
+    arc, cat, cf,
+    core_audio::{
+        aggregate_device_keys as agg_keys, device_start, hardware::sub_tap_keys,
+        hardware::StartedDevice, sub_device_keys, AggregateDevice, Device, Obj, Process, PropAddr,
+        PropListenerFn, PropSelector, System, TapDesc, TapGuard,
+    },
+    ns, os,
+};
+
+/// Input buffers of the tap passed to [`TapCapture`] handler.
+pub struct TapBuf<'a> {
+    bufs: &'a [cat::AudioBuf],
+    asbd: &'a cat::AudioBasicStreamDesc,
+    time: &'a cat::AudioTimeStamp,
+}
+
+impl<'a> TapBuf<'a> {
+    /// Current format of the tap.
+    #[inline]
+    pub fn asbd(&self) -> &cat::AudioBasicStreamDesc {
+        self.asbd
+    }
+
+    /// Input time stamp of the IO cycle.
+    #[inline]
+    pub fn time(&self) -> &cat::AudioTimeStamp {
+        self.time
+    }
+
+    pub fn host_time(&self) -> Option<u64> {
+        let valid = self.time.flags.0 & cat::AudioTimeStampFlags::HOST_TIME_VALID.0 != 0;
+        valid.then_some(self.time.host_time)
+    }
+
+    pub fn sample_time(&self) -> Option<f64> {
+        let valid = self.time.flags.0 & cat::AudioTimeStampFlags::SAMPLE_TIME_VALID.0 != 0;
+        valid.then_some(self.time.sample_time)
+    }
+
+    #[inline]
+    pub fn buf_count(&self) -> usize {
+        self.bufs.len()
+    }
+
+    pub fn frames(&self) -> u32 {
+        match (self.bufs.first(), self.asbd.bytes_per_frame) {
+            (Some(buf), bpf) if bpf > 0 => buf.data_bytes_size / bpf,
+            _ => 0,
+        }
+    }
+
+    pub fn bytes(&self, i: usize) -> Option<&'a [u8]> {
+        let buf = self.bufs.get(i)?;
+        if buf.data.is_null() {
+            return None;
+        }
+        Some(unsafe { std::slice::from_raw_parts(buf.data, buf.data_bytes_size as usize) })
+    }
+
+    /// Typed samples of buffer `i`. `None` if `T` doesn't match the format.
+    pub fn samples<T: cat::AudioSample>(&self, i: usize) -> Option<&'a [T]> {
+        if !T::matches(self.asbd) {
+            return None;
+        }
+        let bytes = self.bytes(i)?;
+        let len = bytes.len() / std::mem::size_of::<T>();
+        Some(unsafe { std::slice::from_raw_parts(bytes.as_ptr() as *const T, len) })
+    }
+}
+
+/// State shared with property listeners.
+struct Shared {
+    asbd: Mutex<cat::AudioBasicStreamDesc>,
+    format_gen: AtomicU32,
+    device_changed: AtomicBool,
+}
+
+/// State owned by the IO proc.
+struct ProcState<F> {
+    handler: F,
+    shared: Arc<Shared>,
+    format_gen: u32,
+    asbd: cat::AudioBasicStreamDesc,
+}
+
+extern "C" fn io_proc<F: FnMut(&TapBuf)>(
+    _device: Device,
+    _now: &cat::AudioTimeStamp,
+    input_data: &cat::AudioBufList<1>,
+    input_time: &cat::AudioTimeStamp,
+    _output_data: &mut cat::AudioBufList<1>,
+    _output_time: &cat::AudioTimeStamp,
+    state: Option<&mut ProcState<F>>,
+) -> os::Status {
+    let Some(state) = state else {
+        return os::Status::NO_ERR;
+    };
+    let gen = state.shared.format_gen.load(Ordering::Acquire);
+    if gen != state.format_gen {
+        // never block IO thread, pick new format on the next cycle
+        if let Ok(asbd) = state.shared.asbd.try_lock() {
+            state.asbd = *asbd;
+            state.format_gen = gen;
+        }
+    }
+    let n = input_data.number_buffers as usize;
+    let bufs = unsafe { std::slice::from_raw_parts(input_data.buffers.as_ptr(), n) };
+    (state.handler)(&TapBuf {
+        bufs,
+        asbd: &state.asbd,
+        time: input_time,
+    });
+    os::Status::NO_ERR
+}
+
+extern "C-unwind" fn listener(
+    obj: Obj,
+    number_addresses: u32,
+    addresses: *const PropAddr,
+    shared: *mut Shared,
+) -> os::Status {
+    let shared = unsafe { &*shared };
+    let addresses = unsafe { std::slice::from_raw_parts(addresses, number_addresses as usize) };
+    for addr in addresses {
+        if addr.selector == PropSelector::TAP_FORMAT {
+            if let Ok(asbd) = obj.prop(&PropSelector::TAP_FORMAT.global_addr()) {
+                *shared.asbd.lock().unwrap() = asbd;
+                shared.format_gen.fetch_add(1, Ordering::Release);
+            }
+        } else {
+            shared.device_changed.store(true, Ordering::Release);
+        }
+    }
+    os::Status::NO_ERR
+}
+
+const LISTENER: PropListenerFn<Shared> = listener;
+
+/// Description of private aggregate device with the tap and `main_device_uid` as clock source.
+pub fn tap_aggregate_desc(
+    name: &cf::String,
+    uid: &cf::String,
+    main_device_uid: &cf::String,
+    tap_uid: &cf::String,
+) -> arc::R<cf::DictionaryOf<cf::String, cf::Type>> {
+    let sub_device =
+        cf::DictionaryOf::with_keys_values(&[sub_device_keys::uid()], &[main_device_uid]);
+    let sub_devices = cf::ArrayOf::from_slice(&[&*sub_device]);
+    let tap = cf::DictionaryOf::<cf::String, cf::Type>::with_keys_values(
+        &[sub_tap_keys::uid(), sub_tap_keys::drift_compensation()],
+        &[tap_uid.as_type_ref(), cf::Boolean::value_true()],
+    );
+    let taps = cf::ArrayOf::from_slice(&[&*tap]);
+    cf::DictionaryOf::with_keys_values(
+        &[
+            agg_keys::name(),
+            agg_keys::uid(),
+            agg_keys::main_sub_device(),
+            agg_keys::is_private(),
+            agg_keys::is_stacked(),
+            agg_keys::tap_auto_start(),
+            agg_keys::sub_device_list(),
+            agg_keys::tap_list(),
+        ],
+        &[
+            name.as_type_ref(),
+            uid,
+            main_device_uid,
+            cf::Boolean::value_true(),
+            cf::Boolean::value_false(),
+            cf::Boolean::value_false(),
+            &sub_devices,
+            &taps,
+        ],
+    )
+}
+
+fn obj_ids(processes: &[Process]) -> arc::R<ns::Array<ns::Number>> {
+    let ids: Vec<_> = processes
+        .iter()
+        .map(|p| ns::Number::with_u32(p.0 .0))
+        .collect();
+    ns::Array::from_slice_retained(&ids)
+}
+
+/// Captures output audio of processes through a process tap.
+///
+/// Creates the tap, a private aggregate device with the tap and the default output device
+/// as clock source and runs the handler on the IO thread of the aggregate device.
+/// Everything is destroyed on drop.
+///
+/// Format changes of the tap are picked up automatically. If the default output
+/// device changes or dies [`Self::is_device_changed`] returns `true`,
+/// call [`Self::rebuild`] to recreate the aggregate device.
+pub struct TapCapture<F> {
+    started: Option<StartedDevice<AggregateDevice>>,
+    main_device: Option<Device>,
+    proc: Box<ProcState<F>>,
+    shared: Arc<Shared>,
+    tap_uid: arc::R<cf::String>,
+    tap: TapGuard,
+}
+
+impl<F> TapCapture<F>
+where
+    F: FnMut(&TapBuf) + Send + 'static,
+{
+    /// Captures stereo mix of `processes`.
+    pub fn with_processes(processes: &[Process], handler: F) -> os::Result<Self> {
+        let mut desc = TapDesc::with_stereo_mixdown_of_processes(&obj_ids(processes));
+        desc.set_private(true);
+        Self::with_desc(&desc, handler)
+    }
+
+    /// Captures stereo mix of all processes except `processes`.
+    pub fn excluding_processes(processes: &[Process], handler: F) -> os::Result<Self> {
+        let mut desc = TapDesc::with_stereo_global_tap_excluding_processes(&obj_ids(processes));
+        desc.set_private(true);
+        Self::with_desc(&desc, handler)
+    }
+
+    pub fn with_desc(desc: &TapDesc, handler: F) -> os::Result<Self> {
+        let tap = desc.create_process_tap()?;
+        let tap_uid = tap.uid()?;
+        let asbd = tap.asbd()?;
+        let shared = Arc::new(Shared {
+            asbd: Mutex::new(asbd),
+            format_gen: AtomicU32::new(0),
+            device_changed: AtomicBool::new(false),
+        });
+        let proc = Box::new(ProcState {
+            handler,
+            shared: shared.clone(),
+            format_gen: 0,
+            asbd,
+        });
+        let mut res = Self {
+            started: None,
+            main_device: None,
+            proc,
+            shared,
+            tap_uid,
+            tap,
+        };
+        let client_data = res.client_data();
+        res.tap.add_prop_listener(
+            &PropSelector::TAP_FORMAT.global_addr(),
+            LISTENER,
+            client_data,
+        )?;
+        System::OBJ.add_prop_listener(
+            &PropSelector::HARDWARE_DEFAULT_OUTPUT_DEVICE.global_addr(),
+            LISTENER,
+            client_data,
+        )?;
+        res.build()?;
+        Ok(res)
+    }
+}
+
+impl<F> TapCapture<F> {
+    fn client_data(&self) -> *mut Shared {
+        Arc::as_ptr(&self.shared) as *mut Shared
+    }
+
+    fn build(&mut self) -> os::Result
+    where
+        F: FnMut(&TapBuf),
+    {
+        let output = System::default_output_device()?;
+        let output_uid = output.uid()?;
+        let uid = cf::Uuid::new().to_cf_string();
+        let desc = tap_aggregate_desc(cf::str!(c"Tap Capture"), &uid, &output_uid, &self.tap_uid);
+        let device = AggregateDevice::with_desc(&desc)?;
+        let proc_id = device.create_io_proc_id(io_proc::<F>, Some(self.proc.as_mut()))?;
+
+        output.add_prop_listener(
+            &PropSelector::DEVICE_IS_ALIVE.global_addr(),
+            LISTENER,
+            self.client_data(),
+        )?;
+        self.main_device = Some(output);
+        self.started = Some(device_start(device, Some(proc_id))?);
+        Ok(())
+    }
+
+    fn teardown(&mut self) {
+        // stops IO and destroys aggregate device with its IO proc
+        self.started = None;
+        if let Some(device) = self.main_device.take() {
+            let _ = device.remove_prop_listener(
+                &PropSelector::DEVICE_IS_ALIVE.global_addr(),
+                LISTENER,
+                self.client_data(),
+            );
+        }
+    }
+
+    /// Current format of the tap.
+    pub fn asbd(&self) -> cat::AudioBasicStreamDesc {
+        *self.shared.asbd.lock().unwrap()
+    }
+
+    #[inline]
+    pub fn tap(&self) -> &TapGuard {
+        &self.tap
+    }
+
+    #[inline]
+    pub fn is_running(&self) -> bool {
+        self.started.is_some()
+    }
+
+    /// Default output device changed or main sub device is gone.
+    #[inline]
+    pub fn is_device_changed(&self) -> bool {
+        self.shared.device_changed.load(Ordering::Acquire)
+    }
+
+    /// Recreates aggregate device with the current default output device.
+    pub fn rebuild(&mut self) -> os::Result
+    where
+        F: FnMut(&TapBuf),
+    {
+        self.teardown();
+        self.shared.device_changed.store(false, Ordering::Release);
+        self.build()
+    }
+}
+
+impl<F> Drop for TapCapture<F> {
+    fn drop(&mut self) {
+        self.teardown();
+        let client_data = self.client_data();
+        let _ = System::OBJ.remove_prop_listener(
+            &PropSelector::HARDWARE_DEFAULT_OUTPUT_DEVICE.global_addr(),
+            LISTENER,
+            client_data,
+        );
+        let _ = self.tap.remove_prop_listener(
+            &PropSelector::TAP_FORMAT.global_addr(),
+            LISTENER,
+            client_data,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    };
+
+    use crate::{
+        cf,
+        core_audio::{
+            aggregate_device_keys as agg_keys, hardware::sub_tap_keys, sub_device_keys,
+            tap_aggregate_desc, TapCapture,
+        },
+    };
+
+    #[test]
+    fn aggregate_desc() {
+        let desc = tap_aggregate_desc(
+            cf::str!(c"name"),
+            cf::str!(c"uid"),
+            cf::str!(c"output"),
+            cf::str!(c"tap"),
+        );
+        assert_eq!(desc.len(), 8);
+        assert!(desc.get(agg_keys::name()).unwrap().equal(cf::str!(c"name")));
+        assert!(desc.get(agg_keys::uid()).unwrap().equal(cf::str!(c"uid")));
+        assert!(desc
+            .get(agg_keys::main_sub_device())
+            .unwrap()
+            .equal(cf::str!(c"output")));
+        assert!(desc
+            .get(agg_keys::is_private())
+            .unwrap()
+            .equal(cf::Boolean::value_true()));
+        assert!(desc
+            .get(agg_keys::tap_auto_start())
+            .unwrap()
+            .equal(cf::Boolean::value_false()));
+
+        let tap = cf::DictionaryOf::<cf::String, cf::Type>::with_keys_values(
+            &[sub_tap_keys::uid(), sub_tap_keys::drift_compensation()],
+            &[cf::str!(c"tap").as_type_ref(), cf::Boolean::value_true()],
+        );
+        let taps = cf::ArrayOf::from_slice(&[&*tap]);
+        assert!(desc.get(agg_keys::tap_list()).unwrap().equal(&taps));
+
+        let sub_device =
+            cf::DictionaryOf::with_keys_values(&[sub_device_keys::uid()], &[cf::str!(c"output")]);
+        let sub_devices = cf::ArrayOf::from_slice(&[&*sub_device]);
+        assert!(desc
+            .get(agg_keys::sub_device_list())
+            .unwrap()
+            .equal(&sub_devices));
+    }
+
+    #[test]
+    fn capture() {
+        let cycles = Arc::new(AtomicU32::new(0));
+        let counter = cycles.clone();
+        let capture = TapCapture::excluding_processes(&[], move |buf| {
+            assert!(buf.host_time().is_some());
+            assert!(buf.samples::<f32>(0).is_some());
+            counter.fetch_add(1, Ordering::Relaxed);
+        })
+        .unwrap();
+        assert!(capture.is_running());
+        assert_eq!(
+            capture.asbd().sample_rate,
+            capture.tap().asbd().unwrap().sample_rate
+        );
+        std::thread::sleep(std::time::Duration::from_millis(200));
+        drop(capture);
+        assert!(cycles.load(Ordering::Relaxed) > 0);
+    }
+}
@@ -30,6 +30,16 @@ pub mod pixel_format_description;
 pub use pixel_format_description::all_pixel_formats as pixel_format_desc_array_with_all_pixel_formats;
 pub use pixel_format_description::create as pixel_format_desc_create;
 
+pub mod ycbcr;
+pub use ycbcr::Converter as YCbCrConverter;
+
+pub mod snapshot;
+pub use snapshot::Snapshot;
+
+pub mod frame;
+pub use frame::y4m;
+pub use frame::Frame;
+
 #[cfg(feature = "mtl")]
 pub mod metal;
 #[cfg(feature = "mtl")]
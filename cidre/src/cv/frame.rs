@@ -0,0 +1,728 @@
+//! Raw YCbCr frames for feeding encoders and dumping decoded pictures.
+//!
+//! [`Frame`] keeps tightly packed planes in the layout of the matching
+//! [`cv::PixelFormat`], so raw `.yuv` files (I420, NV12, P010...) are read and
+//! written as is and copies to and from locked [`cv::PixelBuf`] only deal with
+//! row padding. Samples wider than 8 bits are 16-bit little endian words, low
+//! bits aligned in planar layouts (like `yuv420p10le`) and high bits aligned in
+//! bi-planar ones (like `x420` or P010). [`y4m`] reads and writes YUV4MPEG2
+//! streams of planar frames.
+
+use std::{
+    borrow::Cow,
+    fmt,
+    io::{self, Read, Write},
+};
+
+use crate::{arc, cf, cv, cv::ycbcr::Range};
+
+pub mod y4m;
+
+#[derive(Debug)]
+pub enum Error {
+    /// Pixel format has no frame layout.
+    PixelFormat(cv::PixelFormat),
+    /// Layout differs in chroma sampling or bit depth or has no pixel format.
+    Layout(Layout),
+    /// Frame dimensions or plane sizes don't match.
+    SizeMismatch,
+    /// Malformed or unsupported YUV4MPEG2 header.
+    InvalidHeader(String),
+    Cv(cv::Return),
+    Io(io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::PixelFormat(format) => write!(f, "unsupported pixel format {format:?}"),
+            Self::Layout(layout) => write!(f, "unsupported frame layout {layout:?}"),
+            Self::SizeMismatch => f.write_str("frame sizes differ"),
+            Self::InvalidHeader(msg) => write!(f, "invalid y4m header: {msg}"),
+            Self::Cv(res) => write!(f, "core video error {res:?}"),
+            Self::Io(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<cv::Return> for Error {
+    fn from(value: cv::Return) -> Self {
+        Self::Cv(value)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sampling {
+    /// Luma only.
+    Mono,
+    C420,
+    C422,
+    C444,
+}
+
+impl Sampling {
+    /// Horizontal and vertical chroma subsampling shifts.
+    #[inline]
+    const fn shifts(self) -> (u32, u32) {
+        match self {
+            Self::C420 => (1, 1),
+            Self::C422 => (1, 0),
+            Self::Mono | Self::C444 => (0, 0),
+        }
+    }
+}
+
+/// Plane arrangement of a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Layout {
+    sampling: Sampling,
+    depth: u8,
+    bi_planar: bool,
+}
+
+impl Layout {
+    /// `yuv420p`, `y420`.
+    pub const I420: Self = Self::planar(Sampling::C420, 8);
+    /// `420v`, `420f`.
+    pub const NV12: Self = Self::bi_planar(Sampling::C420, 8);
+    /// `x420`, `xf20`.
+    pub const P010: Self = Self::bi_planar(Sampling::C420, 10);
+
+    /// Separate luma, Cb and Cr planes of `8..=16` bit samples.
+    pub const fn planar(sampling: Sampling, depth: u8) -> Self {
+        assert!(depth >= 8 && depth <= 16, "bit depth must be 8..=16");
+        Self {
+            sampling,
+            depth,
+            bi_planar: false,
+        }
+    }
+
+    /// Luma plane and interleaved CbCr plane of `8..=16` bit samples.
+    pub const fn bi_planar(sampling: Sampling, depth: u8) -> Self {
+        assert!(depth >= 8 && depth <= 16, "bit depth must be 8..=16");
+        assert!(
+            !matches!(sampling, Sampling::Mono),
+            "mono has no chroma plane"
+        );
+        Self {
+            sampling,
+            depth,
+            bi_planar: true,
+        }
+    }
+
+    #[inline]
+    pub const fn sampling(self) -> Sampling {
+        self.sampling
+    }
+
+    #[inline]
+    pub const fn depth(self) -> u8 {
+        self.depth
+    }
+
+    #[inline]
+    pub const fn is_bi_planar(self) -> bool {
+        self.bi_planar
+    }
+
+    /// Same sampling and depth with separate chroma planes.
+    pub const fn to_planar(self) -> Self {
+        Self::planar(self.sampling, self.depth)
+    }
+
+    /// Same sampling and depth with interleaved chroma, mono stays planar.
+    pub const fn to_bi_planar(self) -> Self {
+        match self.sampling {
+            Sampling::Mono => self,
+            sampling => Self::bi_planar(sampling, self.depth),
+        }
+    }
+
+    pub fn with_pixel_format(format: cv::PixelFormat) -> Option<(Self, Range)> {
+        use cv::PixelFormat as F;
+        use Sampling::*;
+        Some(match format {
+            F::ONE_COMPONENT_8 => (Self::planar(Mono, 8), Range::Full),
+            F::_420_YP_CB_CR_8_PLANAR => (Self::I420, Range::Video),
+            F::_420_YP_CB_CR_8_PLANAR_FULL_RANGE => (Self::I420, Range::Full),
+            F::_420V => (Self::NV12, Range::Video),
+            F::_420F => (Self::NV12, Range::Full),
+            F::_422_YP_CB_CR_8_BI_PLANAR_VIDEO_RANGE => (Self::bi_planar(C422, 8), Range::Video),
+            F::_422_YP_CB_CR_8_BI_PLANAR_FULL_RANGE => (Self::bi_planar(C422, 8), Range::Full),
+            F::_444_YP_CB_CR_8_BI_PLANAR_VIDEO_RANGE => (Self::bi_planar(C444, 8), Range::Video),
+            F::_444_YP_CB_CR_8_BI_PLANAR_FULL_RANGE => (Self::bi_planar(C444, 8), Range::Full),
+            F::_420_YP_CB_CR_10_BI_PLANAR_VIDEO_RANGE => (Self::P010, Range::Video),
+            F::_420_YP_CB_CR_10_BI_PLANAR_FULL_RANGE => (Self::P010, Range::Full),
+            F::_422_YP_CB_CR_10_BI_PLANAR_VIDEO_RANGE => (Self::bi_planar(C422, 10), Range::Video),
+            F::_422_YP_CB_CR_10_BI_PLANAR_FULL_RANGE => (Self::bi_planar(C422, 10), Range::Full),
+            F::_444_YP_CB_CR_10_BI_PLANAR_VIDEO_RANGE => (Self::bi_planar(C444, 10), Range::Video),
+            F::_444_YP_CB_CR_10_BI_PLANAR_FULL_RANGE => (Self::bi_planar(C444, 10), Range::Full),
+            _ => return None,
+        })
+    }
+
+    /// `L008` is used for mono in either range.
+    pub fn pixel_format(self, range: Range) -> Option<cv::PixelFormat> {
+        use cv::PixelFormat as F;
+        use Sampling::*;
+        let video = range == Range::Video;
+        Some(match (self.sampling, self.depth, self.bi_planar) {
+            (Mono, 8, _) => F::ONE_COMPONENT_8,
+            (C420, 8, false) if video => F::_420_YP_CB_CR_8_PLANAR,
+            (C420, 8, false) => F::_420_YP_CB_CR_8_PLANAR_FULL_RANGE,
+            (C420, 8, true) if video => F::_420V,
+            (C420, 8, true) => F::_420F,
+            (C422, 8, true) if video => F::_422_YP_CB_CR_8_BI_PLANAR_VIDEO_RANGE,
+            (C422, 8, true) => F::_422_YP_CB_CR_8_BI_PLANAR_FULL_RANGE,
+            (C444, 8, true) if video => F::_444_YP_CB_CR_8_BI_PLANAR_VIDEO_RANGE,
+            (C444, 8, true) => F::_444_YP_CB_CR_8_BI_PLANAR_FULL_RANGE,
+            (C420, 10, true) if video => F::_420_YP_CB_CR_10_BI_PLANAR_VIDEO_RANGE,
+            (C420, 10, true) => F::_420_YP_CB_CR_10_BI_PLANAR_FULL_RANGE,
+            (C422, 10, true) if video => F::_422_YP_CB_CR_10_BI_PLANAR_VIDEO_RANGE,
+            (C422, 10, true) => F::_422_YP_CB_CR_10_BI_PLANAR_FULL_RANGE,
+            (C444, 10, true) if video => F::_444_YP_CB_CR_10_BI_PLANAR_VIDEO_RANGE,
+            (C444, 10, true) => F::_444_YP_CB_CR_10_BI_PLANAR_FULL_RANGE,
+            _ => return None,
+        })
+    }
+
+    #[inline]
+    pub const fn plane_count(self) -> usize {
+        match (self.sampling, self.bi_planar) {
+            (Sampling::Mono, _) => 1,
+            (_, true) => 2,
+            (_, false) => 3,
+        }
+    }
+
+    #[inline]
+    pub const fn bytes_per_sample(self) -> usize {
+        if self.depth > 8 {
+            2
+        } else {
+            1
+        }
+    }
+
+    /// Samples in a row of `plane`, both Cb and Cr of interleaved plane.
+    pub const fn plane_width(self, plane: usize, width: usize) -> usize {
+        if plane == 0 {
+            return width;
+        }
+        let cw = (width + (1 << self.sampling.shifts().0) - 1) >> self.sampling.shifts().0;
+        if self.bi_planar {
+            cw * 2
+        } else {
+            cw
+        }
+    }
+
+    pub const fn plane_height(self, plane: usize, height: usize) -> usize {
+        if plane == 0 {
+            return height;
+        }
+        (height + (1 << self.sampling.shifts().1) - 1) >> self.sampling.shifts().1
+    }
+
+    /// Bytes of a single tightly packed row of `plane`.
+    #[inline]
+    pub const fn plane_bytes_per_row(self, plane: usize, width: usize) -> usize {
+        self.plane_width(plane, width) * self.bytes_per_sample()
+    }
+
+    #[inline]
+    pub const fn plane_len(self, plane: usize, width: usize, height: usize) -> usize {
+        self.plane_bytes_per_row(plane, width) * self.plane_height(plane, height)
+    }
+
+    /// Bytes of a whole raw frame.
+    pub const fn frame_len(self, width: usize, height: usize) -> usize {
+        let mut len = 0;
+        let mut i = 0;
+        while i < self.plane_count() {
+            len += self.plane_len(i, width, height);
+            i += 1;
+        }
+        len
+    }
+
+    /// [`Self::plane_len`] or `None` if it overflows `usize`.
+    pub fn checked_plane_len(self, plane: usize, width: usize, height: usize) -> Option<usize> {
+        let (sw, sh) = self.sampling.shifts();
+        let (mut w, mut h) = (width, height);
+        if plane != 0 {
+            w = width.checked_add((1 << sw) - 1)? >> sw;
+            h = height.checked_add((1 << sh) - 1)? >> sh;
+            if self.bi_planar {
+                w = w.checked_mul(2)?;
+            }
+        }
+        w.checked_mul(self.bytes_per_sample())?.checked_mul(h)
+    }
+
+    /// [`Self::frame_len`] or `None` if it overflows `usize`.
+    pub fn checked_frame_len(self, width: usize, height: usize) -> Option<usize> {
+        (0..self.plane_count()).try_fold(0usize, |len, i| {
+            len.checked_add(self.checked_plane_len(i, width, height)?)
+        })
+    }
+
+    /// Bits samples are shifted up in 16-bit words.
+    #[inline]
+    const fn msb_shift(self) -> u32 {
+        if self.bi_planar && self.depth > 8 {
+            16 - self.depth as u32
+        } else {
+            0
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq)]
+pub struct Frame {
+    layout: Layout,
+    width: usize,
+    height: usize,
+    planes: Vec<Vec<u8>>,
+}
+
+impl fmt::Debug for Frame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Frame")
+            .field("layout", &self.layout)
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .finish()
+    }
+}
+
+impl Frame {
+    /// Frame with zeroed planes.
+    ///
+    /// Panics if the frame size overflows `usize`.
+    pub fn new(layout: Layout, width: usize, height: usize) -> Self {
+        let planes = (0..layout.plane_count())
+            .map(|i| {
+                let len = layout.checked_plane_len(i, width, height);
+                vec![0; len.expect("frame size overflows usize")]
+            })
+            .collect();
+        Self {
+            layout,
+            width,
+            height,
+            planes,
+        }
+    }
+
+    /// Tightly packed `planes`, see [`Layout::plane_len`].
+    pub fn with_planes(
+        layout: Layout,
+        width: usize,
+        height: usize,
+        planes: Vec<Vec<u8>>,
+    ) -> Result<Self, Error> {
+        if planes.len() != layout.plane_count()
+            || planes
+                .iter()
+                .enumerate()
+                .any(|(i, p)| p.len() != layout.plane_len(i, width, height))
+        {
+            return Err(Error::SizeMismatch);
+        }
+        Ok(Self {
+            layout,
+            width,
+            height,
+            planes,
+        })
+    }
+
+    #[inline]
+    pub fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    #[inline]
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    #[inline]
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    #[inline]
+    pub fn plane(&self, index: usize) -> &[u8] {
+        &self.planes[index]
+    }
+
+    #[inline]
+    pub fn plane_mut(&mut self, index: usize) -> &mut [u8] {
+        &mut self.planes[index]
+    }
+
+    #[inline]
+    pub fn plane_bytes_per_row(&self, index: usize) -> usize {
+        self.layout.plane_bytes_per_row(index, self.width)
+    }
+
+    #[inline]
+    pub fn plane_height(&self, index: usize) -> usize {
+        self.layout.plane_height(index, self.height)
+    }
+
+    #[inline]
+    pub fn into_planes(self) -> Vec<Vec<u8>> {
+        self.planes
+    }
+
+    /// Converts between planar and bi-planar layouts of the same sampling and depth.
+    pub fn to_layout(&self, layout: Layout) -> Result<Self, Error> {
+        if layout.sampling != self.layout.sampling || layout.depth != self.layout.depth {
+            return Err(Error::Layout(layout));
+        }
+        if layout == self.layout {
+            return Ok(self.clone());
+        }
+        let mut res = Self::new(layout, self.width, self.height);
+        let wide = layout.depth > 8;
+        // samples are shifted up going to bi-planar and down going back
+        let up = layout.msb_shift() as i32 - self.layout.msb_shift() as i32;
+        let conv = |v: u16| {
+            if up >= 0 {
+                v << up
+            } else {
+                v >> -up
+            }
+        };
+        for (i, v) in samples(&self.planes[0], wide).enumerate() {
+            put(&mut res.planes[0], i, wide, conv(v));
+        }
+        if self.layout.plane_count() == 1 {
+            return Ok(res);
+        }
+        if layout.bi_planar {
+            let (cb, cr) = (&self.planes[1], &self.planes[2]);
+            let cbcr = &mut res.planes[1];
+            for (i, (b, r)) in samples(cb, wide).zip(samples(cr, wide)).enumerate() {
+                put(cbcr, i * 2, wide, conv(b));
+                put(cbcr, i * 2 + 1, wide, conv(r));
+            }
+        } else {
+            let (dst, rest) = res.planes.split_at_mut(2);
+            for (i, v) in samples(&self.planes[1], wide).enumerate() {
+                let plane = if i % 2 == 0 {
+                    &mut dst[1]
+                } else {
+                    &mut rest[0]
+                };
+                put(plane, i / 2, wide, conv(v));
+            }
+        }
+        Ok(res)
+    }
+
+    /// Reads raw frame of `layout`, `None` at the end of `reader`.
+    ///
+    /// Planes grow with the data read, so sizes of untrusted headers don't
+    /// allocate the whole frame up front.
+    pub fn read_raw<R: Read>(
+        reader: &mut R,
+        layout: Layout,
+        width: usize,
+        height: usize,
+    ) -> Result<Option<Self>, Error> {
+        if layout.checked_frame_len(width, height).is_none() {
+            return Err(Error::SizeMismatch);
+        }
+        let mut planes = Vec::with_capacity(layout.plane_count());
+        for i in 0..layout.plane_count() {
+            let len = layout.plane_len(i, width, height);
+            let mut plane = Vec::new();
+            reader.by_ref().take(len as u64).read_to_end(&mut plane)?;
+            if plane.is_empty() && i == 0 {
+                return Ok(None);
+            }
+            if plane.len() < len {
+                return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+            }
+            planes.push(plane);
+        }
+        Ok(Some(Self {
+            layout,
+            width,
+            height,
+            planes,
+        }))
+    }
+
+    /// Writes planes one after another.
+    pub fn write_raw<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
+        for plane in &self.planes {
+            writer.write_all(plane)?;
+        }
+        Ok(())
+    }
+
+    /// Copies locked planes of `buf` with [`Layout::with_pixel_format`] layout.
+    pub fn with_pixel_buf(buf: &cv::PixelBuf) -> Result<Self, Error> {
+        let format = buf.pixel_format();
+        let (layout, _) = Layout::with_pixel_format(format).ok_or(Error::PixelFormat(format))?;
+        let mut res = Self::new(layout, buf.width(), buf.height());
+        let lock = buf.base_address_lock(cv::pixel_buffer::LockFlags::READ_ONLY)?;
+        for i in 0..layout.plane_count() {
+            let (src, src_bpr) = if buf.is_planar() {
+                (lock.plane_bytes(i), buf.plane_bytes_per_row(i))
+            } else {
+                (lock.bytes(), buf.bytes_per_row())
+            };
+            let bpr = res.plane_bytes_per_row(i);
+            let rows = res.plane_height(i);
+            copy_rows(src, src_bpr, &mut res.planes[i], bpr, rows, bpr)?;
+        }
+        Ok(res)
+    }
+
+    /// Copies planes into `buf` of the same size and layout.
+    pub fn copy_to_pixel_buf(&self, buf: &mut cv::PixelBuf) -> Result<(), Error> {
+        let format = buf.pixel_format();
+        match Layout::with_pixel_format(format) {
+            Some((layout, _)) if layout == self.layout => {}
+            _ => return Err(Error::PixelFormat(format)),
+        }
+        if buf.width() != self.width || buf.height() != self.height {
+            return Err(Error::SizeMismatch);
+        }
+        let planar = buf.is_planar();
+        let mut lock = buf.base_address_lock_mut(cv::pixel_buffer::LockFlags::DEFAULT)?;
+        for (i, plane) in self.planes.iter().enumerate() {
+            let (dst, dst_bpr) = if planar {
+                let bpr = lock.pixel_buf().plane_bytes_per_row(i);
+                (lock.plane_bytes_mut(i).unwrap(), bpr)
+            } else {
+                let bpr = lock.pixel_buf().bytes_per_row();
+                (lock.bytes_mut().unwrap(), bpr)
+            };
+            let bpr = self.plane_bytes_per_row(i);
+            copy_rows(plane, bpr, dst, dst_bpr, self.plane_height(i), bpr)?;
+        }
+        Ok(())
+    }
+
+    /// New pixel buffer with a copy of the frame.
+    ///
+    /// Planar layouts without pixel format, like 10-bit Y4M frames, are
+    /// interleaved into the bi-planar one first.
+    pub fn to_pixel_buf(
+        &self,
+        range: Range,
+        attrs: Option<&cf::Dictionary>,
+    ) -> Result<arc::R<cv::PixelBuf>, Error> {
+        let layout = match self.layout.pixel_format(range) {
+            Some(_) => self.layout,
+            None => self.layout.to_bi_planar(),
+        };
+        let format = layout
+            .pixel_format(range)
+            .ok_or(Error::Layout(self.layout))?;
+        let frame = if layout == self.layout {
+            Cow::Borrowed(self)
+        } else {
+            Cow::Owned(self.to_layout(layout)?)
+        };
+        let mut buf = cv::PixelBuf::new(self.width, self.height, format, attrs)?;
+        frame.copy_to_pixel_buf(&mut buf)?;
+        Ok(buf)
+    }
+}
+
+fn samples(plane: &[u8], wide: bool) -> impl Iterator<Item = u16> + '_ {
+    let step = if wide { 2 } else { 1 };
+    plane.chunks_exact(step).map(move |s| {
+        if wide {
+            u16::from_le_bytes([s[0], s[1]])
+        } else {
+            s[0] as u16
+        }
+    })
+}
+
+#[inline]
+fn put(plane: &mut [u8], index: usize, wide: bool, val: u16) {
+    if wide {
+        plane[index * 2..index * 2 + 2].copy_from_slice(&val.to_le_bytes());
+    } else {
+        plane[index] = val as u8;
+    }
+}
+
+/// Copies `rows` rows of `len` bytes between strided planes.
+fn copy_rows(
+    src: &[u8],
+    src_bpr: usize,
+    dst: &mut [u8],
+    dst_bpr: usize,
+    rows: usize,
+    len: usize,
+) -> Result<(), Error> {
+    let fits = |data_len: usize, bpr: usize| {
+        rows == 0 || (bpr >= len && data_len >= bpr * (rows - 1) + len)
+    };
+    if !fits(src.len(), src_bpr) || !fits(dst.len(), dst_bpr) {
+        return Err(Error::SizeMismatch);
+    }
+    for y in 0..rows {
+        dst[y * dst_bpr..y * dst_bpr + len].copy_from_slice(&src[y * src_bpr..y * src_bpr + len]);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cv::{
+        self,
+        frame::{Error, Frame, Layout, Sampling},
+        ycbcr::Range,
+    };
+
+    #[test]
+    fn layout() {
+        let l = Layout::I420;
+        assert_eq!(l.plane_count(), 3);
+        assert_eq!(l.plane_bytes_per_row(1, 5), 3);
+        assert_eq!(l.plane_height(2, 3), 2);
+        assert_eq!(l.frame_len(5, 3), 15 + 6 * 2);
+        assert_eq!(Layout::P010.frame_len(4, 2), 16 + 8);
+        assert_eq!(l.checked_frame_len(5, 3), Some(l.frame_len(5, 3)));
+        assert_eq!(
+            Layout::P010.checked_frame_len(5, 3),
+            Some(Layout::P010.frame_len(5, 3))
+        );
+        assert_eq!(l.checked_frame_len(usize::MAX, 2), None);
+        assert_eq!(l.checked_frame_len(1 << 32, 1 << 32), None);
+
+        // sizes of the header don't allocate before data arrives
+        let res = Frame::read_raw(&mut &[0u8; 4][..], l, 1 << 20, 1 << 20);
+        assert!(matches!(res, Err(Error::Io(_))));
+        let res = Frame::read_raw(&mut &[0u8; 4][..], l, usize::MAX, 1);
+        assert!(matches!(res, Err(Error::SizeMismatch)));
+
+        let l = Layout::bi_planar(Sampling::C422, 10);
+        assert_eq!(l.plane_bytes_per_row(1, 5), 12);
+        assert_eq!(l.plane_height(1, 3), 3);
+        assert_eq!(l.to_planar(), Layout::planar(Sampling::C422, 10));
+        assert_eq!(
+            Layout::planar(Sampling::Mono, 8)
+                .to_bi_planar()
+                .plane_count(),
+            1
+        );
+
+        for range in [Range::Video, Range::Full] {
+            for layout in [
+                Layout::I420,
+                Layout::NV12,
+                Layout::P010,
+                Layout::bi_planar(Sampling::C422, 8),
+                Layout::bi_planar(Sampling::C444, 10),
+            ] {
+                let format = layout.pixel_format(range).unwrap();
+                assert_eq!(Layout::with_pixel_format(format), Some((layout, range)));
+            }
+        }
+        assert_eq!(
+            Layout::planar(Sampling::C420, 10).pixel_format(Range::Video),
+            None
+        );
+        assert_eq!(Layout::with_pixel_format(cv::PixelFormat::_32_BGRA), None);
+    }
+
+    #[test]
+    fn to_layout() {
+        let l = Layout::planar(Sampling::C420, 10);
+        let planes = vec![
+            [1u16, 2, 3, 4]
+                .iter()
+                .flat_map(|v| v.to_le_bytes())
+                .collect(),
+            1023u16.to_le_bytes().to_vec(),
+            512u16.to_le_bytes().to_vec(),
+        ];
+        let frame = Frame::with_planes(l, 2, 2, planes).unwrap();
+        let p010 = frame.to_layout(Layout::P010).unwrap();
+        assert_eq!(&p010.plane(0)[..4], &[64, 0, 128, 0]);
+        assert_eq!(p010.plane(1), &[0xc0, 0xff, 0, 0x80]);
+        assert_eq!(p010.to_layout(l).unwrap(), frame);
+
+        let i420 = Frame::with_planes(Layout::I420, 2, 1, vec![vec![1, 2], vec![3], vec![4]]);
+        let nv12 = i420.unwrap().to_layout(Layout::NV12).unwrap();
+        assert_eq!(nv12.plane(1), &[3, 4]);
+
+        assert!(matches!(
+            frame.to_layout(Layout::NV12),
+            Err(Error::Layout(_))
+        ));
+        assert!(matches!(
+            Frame::with_planes(Layout::I420, 2, 2, vec![vec![0; 4]]),
+            Err(Error::SizeMismatch)
+        ));
+    }
+
+    #[test]
+    fn raw() {
+        let mut frame = Frame::new(Layout::NV12, 3, 3);
+        frame
+            .plane_mut(0)
+            .iter_mut()
+            .enumerate()
+            .for_each(|(i, v)| *v = i as u8);
+        frame.plane_mut(1).fill(128);
+
+        let mut data = Vec::new();
+        frame.write_raw(&mut data).unwrap();
+        frame.write_raw(&mut data).unwrap();
+        assert_eq!(data.len(), 2 * Layout::NV12.frame_len(3, 3));
+
+        let mut reader = &data[..];
+        let read = |r: &mut &[u8]| Frame::read_raw(r, Layout::NV12, 3, 3);
+        assert_eq!(read(&mut reader).unwrap().unwrap(), frame);
+        assert_eq!(read(&mut reader).unwrap().unwrap(), frame);
+        assert!(read(&mut reader).unwrap().is_none());
+
+        let mut truncated = &data[..12];
+        assert!(matches!(read(&mut truncated), Err(Error::Io(_))));
+    }
+
+    #[test]
+    fn pixel_buf() {
+        let mut frame = Frame::new(Layout::planar(Sampling::C420, 10), 33, 17);
+        frame.plane_mut(0).fill(2);
+        frame.plane_mut(2).fill(1);
+        let buf = frame.to_pixel_buf(Range::Video, None).unwrap();
+        assert_eq!(
+            buf.pixel_format(),
+            cv::PixelFormat::_420_YP_CB_CR_10_BI_PLANAR_VIDEO_RANGE
+        );
+        let copy = Frame::with_pixel_buf(&buf).unwrap();
+        assert_eq!(copy.layout(), Layout::P010);
+        assert_eq!(copy.to_layout(frame.layout()).unwrap(), frame);
+
+        let mut bgra = cv::PixelBuf::new(33, 17, cv::PixelFormat::_32_BGRA, None).unwrap();
+        assert!(matches!(
+            copy.copy_to_pixel_buf(&mut bgra),
+            Err(Error::PixelFormat(_))
+        ));
+    }
+}
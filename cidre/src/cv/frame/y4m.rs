@@ -0,0 +1,468 @@
+//! YUV4MPEG2 streams as written by ffmpeg, x264 and mjpegtools.
+//!
+//! Frames are planar with 16-bit little endian samples over 8 bits. Writer
+//! accepts bi-planar frames, e.g. of a decoded [`crate::cv::PixelBuf`], and
+//! deinterleaves them. Per-frame parameters are skipped.
+
+use std::{
+    borrow::Cow,
+    fmt,
+    io::{self, Read, Write},
+};
+
+use super::{Error, Frame, Layout, Sampling};
+use crate::cv::ycbcr::Range;
+
+const SIGNATURE: &str = "YUV4MPEG2";
+const FRAME: &[u8] = b"FRAME";
+const COLOR_RANGE: &str = "COLORRANGE=";
+const MAX_LINE: usize = 64 * 1024;
+/// Limit of width and height, 16K video is 15360 wide.
+const MAX_DIMENSION: usize = 1 << 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ratio {
+    pub num: u32,
+    pub den: u32,
+}
+
+impl Ratio {
+    #[inline]
+    pub const fn new(num: u32, den: u32) -> Self {
+        Self { num, den }
+    }
+
+    fn parse(val: &str) -> Option<Self> {
+        let (num, den) = val.split_once(':')?;
+        Some(Self::new(num.parse().ok()?, den.parse().ok()?))
+    }
+}
+
+impl fmt::Display for Ratio {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.num, self.den)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Interlace {
+    #[default]
+    Progressive,
+    TopFirst,
+    BottomFirst,
+    /// Set per frame.
+    Mixed,
+}
+
+/// Chroma position of 8-bit 4:2:0 streams.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Siting {
+    /// `420jpeg`, between luma samples, the default.
+    #[default]
+    Center,
+    /// `420mpeg2`, at even luma columns between rows.
+    Left,
+    /// `420paldv`, Cr and Cb on alternating lines.
+    PalDv,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Header {
+    pub width: usize,
+    pub height: usize,
+    pub frame_rate: Ratio,
+    pub interlace: Interlace,
+    /// Pixel aspect ratio, `None` if unknown.
+    pub aspect: Option<Ratio>,
+    /// Planar layout of frames.
+    pub layout: Layout,
+    /// Only used with 8-bit 4:2:0 layout.
+    pub siting: Siting,
+    /// From ffmpeg `XCOLORRANGE` tag.
+    pub range: Option<Range>,
+    /// Other `X` tags without the prefix.
+    pub extensions: Vec<String>,
+}
+
+impl Header {
+    /// Progressive stream of `layout` made planar.
+    pub fn new(width: usize, height: usize, frame_rate: Ratio, layout: Layout) -> Self {
+        Self {
+            width,
+            height,
+            frame_rate,
+            interlace: Interlace::Progressive,
+            aspect: None,
+            layout: layout.to_planar(),
+            siting: Siting::Center,
+            range: None,
+            extensions: Vec::new(),
+        }
+    }
+
+    /// Bytes of frame data without the `FRAME` line.
+    #[inline]
+    pub fn frame_len(&self) -> usize {
+        self.layout.frame_len(self.width, self.height)
+    }
+
+    fn parse(line: &str) -> Result<Self, Error> {
+        let invalid = |msg: &str| Error::InvalidHeader(msg.to_string());
+        let mut tokens = line.split_ascii_whitespace();
+        if tokens.next() != Some(SIGNATURE) {
+            return Err(invalid("missing YUV4MPEG2 signature"));
+        }
+        let (mut width, mut height, mut frame_rate) = (None, None, None);
+        let mut res = Self::new(0, 0, Ratio::new(0, 0), Layout::I420);
+        for token in tokens {
+            let mut chars = token.chars();
+            let tag = chars.next();
+            let val = chars.as_str();
+            match tag {
+                Some('W') => width = val.parse().ok(),
+                Some('H') => height = val.parse().ok(),
+                Some('F') => frame_rate = Ratio::parse(val),
+                Some('I') => {
+                    res.interlace = match val {
+                        "p" | "?" => Interlace::Progressive,
+                        "t" => Interlace::TopFirst,
+                        "b" => Interlace::BottomFirst,
+                        "m" => Interlace::Mixed,
+                        _ => return Err(Error::InvalidHeader(format!("interlacing {val}"))),
+                    }
+                }
+                Some('A') => {
+                    let aspect = Ratio::parse(val).ok_or_else(|| invalid("aspect"))?;
+                    res.aspect = Some(aspect).filter(|a| a.num != 0 && a.den != 0);
+                }
+                Some('C') => {
+                    (res.layout, res.siting) = parse_colorspace(val)
+                        .ok_or_else(|| Error::InvalidHeader(format!("colorspace {val}")))?;
+                }
+                Some('X') => match val.strip_prefix(COLOR_RANGE) {
+                    Some("FULL") => res.range = Some(Range::Full),
+                    Some("LIMITED") => res.range = Some(Range::Video),
+                    _ => res.extensions.push(val.to_string()),
+                },
+                _ => {}
+            }
+        }
+        let dimension = |v: &usize| (1..=MAX_DIMENSION).contains(v);
+        res.width = width.filter(dimension).ok_or_else(|| invalid("width"))?;
+        res.height = height.filter(dimension).ok_or_else(|| invalid("height"))?;
+        if res
+            .layout
+            .checked_frame_len(res.width, res.height)
+            .is_none()
+        {
+            return Err(invalid("frame size"));
+        }
+        res.frame_rate = frame_rate
+            .filter(|r| r.num != 0 && r.den != 0)
+            .ok_or_else(|| invalid("frame rate"))?;
+        Ok(res)
+    }
+}
+
+/// Header line without the newline.
+impl fmt::Display for Header {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let interlace = match self.interlace {
+            Interlace::Progressive => 'p',
+            Interlace::TopFirst => 't',
+            Interlace::BottomFirst => 'b',
+            Interlace::Mixed => 'm',
+        };
+        write!(
+            f,
+            "{SIGNATURE} W{} H{} F{} I{interlace} A{} C",
+            self.width,
+            self.height,
+            self.frame_rate,
+            self.aspect.unwrap_or(Ratio::new(0, 0)),
+        )?;
+        let (sampling, depth) = (self.layout.sampling(), self.layout.depth());
+        let name = match sampling {
+            Sampling::Mono => "mono",
+            Sampling::C420 => "420",
+            Sampling::C422 => "422",
+            Sampling::C444 => "444",
+        };
+        match (sampling, depth) {
+            (Sampling::C420, 8) => f.write_str(match self.siting {
+                Siting::Center => "420jpeg",
+                Siting::Left => "420mpeg2",
+                Siting::PalDv => "420paldv",
+            })?,
+            (_, 8) => f.write_str(name)?,
+            (Sampling::Mono, _) => write!(f, "mono{depth}")?,
+            _ => write!(f, "{name}p{depth}")?,
+        }
+        match self.range {
+            Some(Range::Full) => write!(f, " X{COLOR_RANGE}FULL")?,
+            Some(Range::Video) => write!(f, " X{COLOR_RANGE}LIMITED")?,
+            None => {}
+        }
+        for ext in &self.extensions {
+            write!(f, " X{ext}")?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_colorspace(val: &str) -> Option<(Layout, Siting)> {
+    let planar = |sampling, depth| Some((Layout::planar(sampling, depth), Siting::Center));
+    match val {
+        "420" | "420jpeg" => return planar(Sampling::C420, 8),
+        "420mpeg2" => return Some((Layout::I420, Siting::Left)),
+        "420paldv" => return Some((Layout::I420, Siting::PalDv)),
+        "422" => return planar(Sampling::C422, 8),
+        "444" => return planar(Sampling::C444, 8),
+        "mono" => return planar(Sampling::Mono, 8),
+        _ => {}
+    }
+    let (sampling, depth) = if let Some(depth) = val.strip_prefix("mono") {
+        (Sampling::Mono, depth)
+    } else {
+        let (sampling, depth) = val.split_once('p')?;
+        let sampling = match sampling {
+            "420" => Sampling::C420,
+            "422" => Sampling::C422,
+            "444" => Sampling::C444,
+            _ => return None,
+        };
+        (sampling, depth)
+    };
+    match depth.parse() {
+        Ok(depth @ 9..=16) => planar(sampling, depth),
+        _ => None,
+    }
+}
+
+/// Reads one line without `\n`, `None` at the end of `reader`.
+///
+/// Reads a byte at a time, so `reader` should be buffered.
+fn read_line<R: Read>(reader: &mut R) -> Result<Option<Vec<u8>>, Error> {
+    let mut line = Vec::new();
+    let mut b = [0u8];
+    loop {
+        match reader.read(&mut b) {
+            Ok(0) if line.is_empty() => return Ok(None),
+            Ok(0) => return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+            Ok(_) if b[0] == b'\n' => return Ok(Some(line)),
+            Ok(_) if line.len() == MAX_LINE => {
+                return Err(Error::InvalidHeader("line is too long".to_string()))
+            }
+            Ok(_) => line.push(b[0]),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+/// Reads frames of a stream, wrap files into [`io::BufReader`].
+pub struct Reader<R> {
+    inner: R,
+    header: Header,
+}
+
+impl<R: Read> Reader<R> {
+    pub fn new(mut inner: R) -> Result<Self, Error> {
+        let line = read_line(&mut inner)?
+            .ok_or_else(|| Error::InvalidHeader("empty stream".to_string()))?;
+        let line =
+            String::from_utf8(line).map_err(|_| Error::InvalidHeader("not utf-8".to_string()))?;
+        let header = Header::parse(&line)?;
+        Ok(Self { inner, header })
+    }
+
+    #[inline]
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// Next planar frame, `None` at the end of stream.
+    pub fn read_frame(&mut self) -> Result<Option<Frame>, Error> {
+        let Some(line) = read_line(&mut self.inner)? else {
+            return Ok(None);
+        };
+        if !line.starts_with(FRAME) {
+            return Err(Error::InvalidHeader("missing FRAME marker".to_string()));
+        }
+        let h = &self.header;
+        match Frame::read_raw(&mut self.inner, h.layout, h.width, h.height)? {
+            Some(frame) => Ok(Some(frame)),
+            None => Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+        }
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read> Iterator for Reader<R> {
+    type Item = Result<Frame, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_frame().transpose()
+    }
+}
+
+pub struct Writer<W> {
+    inner: W,
+    header: Header,
+}
+
+impl<W: Write> Writer<W> {
+    /// Writes the header, [`Header::layout`] has to be planar.
+    pub fn new(mut inner: W, header: Header) -> Result<Self, Error> {
+        if header.layout.is_bi_planar() {
+            return Err(Error::Layout(header.layout));
+        }
+        writeln!(inner, "{header}")?;
+        Ok(Self { inner, header })
+    }
+
+    #[inline]
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// Writes frame of the header size, sampling and depth.
+    pub fn write_frame(&mut self, frame: &Frame) -> Result<(), Error> {
+        if frame.width() != self.header.width || frame.height() != self.header.height {
+            return Err(Error::SizeMismatch);
+        }
+        let frame = if frame.layout() == self.header.layout {
+            Cow::Borrowed(frame)
+        } else {
+            Cow::Owned(frame.to_layout(self.header.layout)?)
+        };
+        self.inner.write_all(FRAME)?;
+        self.inner.write_all(b"\n")?;
+        frame.write_raw(&mut self.inner)
+    }
+
+    pub fn flush(&mut self) -> Result<(), Error> {
+        Ok(self.inner.flush()?)
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cv::{
+        frame::{
+            y4m::{Header, Interlace, Ratio, Reader, Siting, Writer},
+            Error, Frame, Layout, Sampling,
+        },
+        ycbcr::Range,
+    };
+
+    #[test]
+    fn header() {
+        let line = "YUV4MPEG2 W352 H288 F30000:1001 It A128:117 C420mpeg2 XCOLORRANGE=FULL XYSCSS=420MPEG2";
+        let h = Header::parse(line).unwrap();
+        assert_eq!((h.width, h.height), (352, 288));
+        assert_eq!(h.frame_rate, Ratio::new(30000, 1001));
+        assert_eq!(h.interlace, Interlace::TopFirst);
+        assert_eq!(h.aspect, Some(Ratio::new(128, 117)));
+        assert_eq!((h.layout, h.siting), (Layout::I420, Siting::Left));
+        assert_eq!(h.range, Some(Range::Full));
+        assert_eq!(h.extensions, ["YSCSS=420MPEG2"]);
+        assert_eq!(h.to_string(), line);
+
+        let h = Header::parse("YUV4MPEG2 W2 H2 F25:1 A0:0").unwrap();
+        assert_eq!(
+            (h.layout, h.siting, h.aspect),
+            (Layout::I420, Siting::Center, None)
+        );
+        assert_eq!(h.to_string(), "YUV4MPEG2 W2 H2 F25:1 Ip A0:0 C420jpeg");
+
+        for (tag, sampling, depth) in [
+            ("420p10", Sampling::C420, 10),
+            ("422", Sampling::C422, 8),
+            ("444p12", Sampling::C444, 12),
+            ("mono", Sampling::Mono, 8),
+            ("mono16", Sampling::Mono, 16),
+        ] {
+            let line = format!("YUV4MPEG2 W2 H2 F25:1 Ip A1:1 C{tag}");
+            let h = Header::parse(&line).unwrap();
+            assert_eq!(h.layout, Layout::planar(sampling, depth));
+            assert_eq!(h.to_string(), line);
+        }
+
+        for line in [
+            "YUV4MPEG W2 H2 F25:1",
+            "YUV4MPEG2 H2 F25:1",
+            "YUV4MPEG2 W2 H2 F25:0",
+            "YUV4MPEG2 W2 H2 F25:1 C411",
+            "YUV4MPEG2 W2 H2 F25:1 C420p7",
+            "YUV4MPEG2 W2 H2 F25:1 Ix",
+            "YUV4MPEG2 W4294967296 H4294967296 F1:1",
+            "YUV4MPEG2 W18446744073709551615 H2 F1:1",
+        ] {
+            assert!(
+                matches!(Header::parse(line), Err(Error::InvalidHeader(_))),
+                "{line}"
+            );
+        }
+    }
+
+    #[test]
+    fn stream() {
+        let layout = Layout::planar(Sampling::C422, 10);
+        let mut header = Header::new(3, 2, Ratio::new(60, 1), layout);
+        header.range = Some(Range::Video);
+
+        let mut frame = Frame::new(layout, 3, 2);
+        frame.plane_mut(0)[..2].copy_from_slice(&1023u16.to_le_bytes());
+        frame.plane_mut(2)[2..4].copy_from_slice(&7u16.to_le_bytes());
+        let bi_planar = frame.to_layout(layout.to_bi_planar()).unwrap();
+
+        let mut w = Writer::new(Vec::new(), header.clone()).unwrap();
+        w.write_frame(&frame).unwrap();
+        w.write_frame(&bi_planar).unwrap();
+        assert!(matches!(
+            w.write_frame(&Frame::new(layout, 2, 2)),
+            Err(Error::SizeMismatch)
+        ));
+        let data = w.into_inner();
+        let header_len = "YUV4MPEG2 W3 H2 F60:1 Ip A0:0 C422p10 XCOLORRANGE=LIMITED\n".len();
+        assert_eq!(data.len(), header_len + 2 * (6 + header.frame_len()));
+
+        let mut r = Reader::new(&data[..]).unwrap();
+        assert_eq!(r.header(), &header);
+        assert_eq!(r.read_frame().unwrap().unwrap(), frame);
+        assert_eq!(r.next().unwrap().unwrap(), frame);
+        assert!(r.next().is_none());
+
+        let truncated = &data[..data.len() - 1];
+        let res: Result<Vec<_>, _> = Reader::new(truncated).unwrap().collect();
+        assert!(matches!(res, Err(Error::Io(_))));
+
+        // per-frame parameters
+        let data = b"YUV4MPEG2 W1 H1 F1:1 Cmono\nFRAME Ib\n\x10";
+        let frame = Reader::new(&data[..])
+            .unwrap()
+            .read_frame()
+            .unwrap()
+            .unwrap();
+        assert_eq!(frame.plane(0), &[0x10]);
+
+        let bi_planar = Header::new(2, 2, Ratio::new(1, 1), Layout::NV12);
+        assert_eq!(bi_planar.layout, Layout::I420);
+        let mut header = bi_planar;
+        header.layout = Layout::NV12;
+        assert!(matches!(
+            Writer::new(Vec::new(), header),
+            Err(Error::Layout(_))
+        ));
+    }
+}
@@ -55,6 +55,38 @@ impl PixelBuf {
         unsafe { CVPixelBufferGetHeightOfPlane(self, plane_index) }
     }
 
+    #[doc(alias = "CVPixelBufferIsPlanar")]
+    #[inline]
+    pub fn is_planar(&self) -> bool {
+        unsafe { CVPixelBufferIsPlanar(self) }
+    }
+
+    #[doc(alias = "CVPixelBufferGetBytesPerRow")]
+    #[inline]
+    pub fn bytes_per_row(&self) -> usize {
+        unsafe { CVPixelBufferGetBytesPerRow(self) }
+    }
+
+    #[doc(alias = "CVPixelBufferGetBytesPerRowOfPlane")]
+    #[inline]
+    pub fn plane_bytes_per_row(&self, plane_index: usize) -> usize {
+        unsafe { CVPixelBufferGetBytesPerRowOfPlane(self, plane_index) }
+    }
+
+    /// Valid only while base address is locked.
+    #[doc(alias = "CVPixelBufferGetBaseAddress")]
+    #[inline]
+    pub unsafe fn base_addr(&self) -> *mut u8 {
+        CVPixelBufferGetBaseAddress(self)
+    }
+
+    /// Valid only while base address is locked.
+    #[doc(alias = "CVPixelBufferGetBaseAddressOfPlane")]
+    #[inline]
+    pub unsafe fn plane_base_addr(&self, plane_index: usize) -> *mut u8 {
+        CVPixelBufferGetBaseAddressOfPlane(self, plane_index)
+    }
+
     /// ```
     /// use cidre::{cv, cg};
     ///
@@ -181,13 +213,24 @@ impl PixelBuf {
         unsafe {
             let res = self.lock_base_addr(flags);
             if res.is_ok() {
-                Ok(BaseAddrLockGuard(self, flags))
+                Ok(BaseAddrLockGuard(self, flags, false))
             } else {
                 Err(res)
             }
         }
     }
 
+    /// Lock with mutable access to the bytes unless `flags` are [`LockFlags::READ_ONLY`].
+    #[inline]
+    pub fn base_address_lock_mut(
+        &mut self,
+        flags: LockFlags,
+    ) -> Result<BaseAddrLockGuard, cv::Return> {
+        let mut lock = self.base_address_lock(flags)?;
+        lock.2 = !flags.contains(LockFlags::READ_ONLY);
+        Ok(lock)
+    }
+
     #[cfg(feature = "io")]
     #[inline]
     pub fn io_surf(&self) -> Option<&io::Surf> {
@@ -230,7 +273,67 @@ impl PixelBuf {
     }
 }
 
-pub struct BaseAddrLockGuard<'a>(&'a PixelBuf, LockFlags);
+/// Holds base address lock, the last field is set for exclusive locks
+/// of [`PixelBuf::base_address_lock_mut`].
+pub struct BaseAddrLockGuard<'a>(&'a PixelBuf, LockFlags, bool);
+
+impl<'a> BaseAddrLockGuard<'a> {
+    #[inline]
+    pub fn pixel_buf(&self) -> &PixelBuf {
+        self.0
+    }
+
+    #[inline]
+    pub fn flags(&self) -> LockFlags {
+        self.1
+    }
+
+    /// Rows of non planar buffer, `bytes_per_row * height` bytes.
+    pub fn bytes(&self) -> &[u8] {
+        let len = self.0.bytes_per_row() * self.0.height();
+        unsafe { slice(self.0.base_addr(), len) }
+    }
+
+    /// `None` unless buffer is locked with [`PixelBuf::base_address_lock_mut`]
+    /// without [`LockFlags::READ_ONLY`].
+    pub fn bytes_mut(&mut self) -> Option<&mut [u8]> {
+        if !self.2 {
+            return None;
+        }
+        let len = self.0.bytes_per_row() * self.0.height();
+        Some(unsafe { slice_mut(self.0.base_addr(), len) })
+    }
+
+    /// Rows of the plane, `plane_bytes_per_row * plane_height` bytes.
+    pub fn plane_bytes(&self, plane_index: usize) -> &[u8] {
+        let len = self.0.plane_bytes_per_row(plane_index) * self.0.plane_height(plane_index);
+        unsafe { slice(self.0.plane_base_addr(plane_index), len) }
+    }
+
+    /// `None` unless buffer is locked with [`PixelBuf::base_address_lock_mut`]
+    /// without [`LockFlags::READ_ONLY`].
+    pub fn plane_bytes_mut(&mut self, plane_index: usize) -> Option<&mut [u8]> {
+        if !self.2 {
+            return None;
+        }
+        let len = self.0.plane_bytes_per_row(plane_index) * self.0.plane_height(plane_index);
+        Some(unsafe { slice_mut(self.0.plane_base_addr(plane_index), len) })
+    }
+}
+
+unsafe fn slice<'a>(ptr: *const u8, len: usize) -> &'a [u8] {
+    if ptr.is_null() {
+        return &[];
+    }
+    std::slice::from_raw_parts(ptr, len)
+}
+
+unsafe fn slice_mut<'a>(ptr: *mut u8, len: usize) -> &'a mut [u8] {
+    if ptr.is_null() {
+        return &mut [];
+    }
+    std::slice::from_raw_parts_mut(ptr, len)
+}
 
 impl<'a> Drop for BaseAddrLockGuard<'a> {
     #[inline]
@@ -394,6 +497,10 @@ impl PixelFormat {
     #[doc(alias = "kCVPixelFormatType_444YpCbCr10")]
     pub const _444_YP_CB_CR_10: Self = Self(os::Type::from_be_bytes(*b"v410"));
 
+    /// Planar Component Y'CbCr 8-bit 4:2:0.  baseAddr points to a big-endian CVPlanarPixelBufferInfo_YCbCrPlanar struct
+    #[doc(alias = "kCVPixelFormatType_420YpCbCr8Planar")]
+    pub const _420_YP_CB_CR_8_PLANAR: Self = Self(os::Type::from_be_bytes(*b"y420"));
+
     /// Planar Component Y'CbCr 8-bit 4:2:0, full range.  baseAddr points to a big-endian CVPlanarPixelBufferInfo_YCbCrPlanar struct
     #[doc(alias = "kCVPixelFormatType_420YpCbCr8PlanarFullRange")]
     pub const _420_YP_CB_CR_8_PLANAR_FULL_RANGE: Self = Self(os::Type::from_be_bytes(*b"f420"));
@@ -414,6 +521,22 @@ impl PixelFormat {
     #[doc(alias = "kCVPixelFormatType_420YpCbCr8BiPlanarFullRange")]
     pub const _420F: Self = Self::_420_YP_CB_CR_8_BI_PLANAR_FULL_RANGE;
 
+    /// Bi-Planar Component Y'CbCr 8-bit 4:2:2, video-range (luma=\[16,235\] chroma=\[16,240\]).
+    #[doc(alias = "kCVPixelFormatType_422YpCbCr8BiPlanarVideoRange")]
+    pub const _422_YP_CB_CR_8_BI_PLANAR_VIDEO_RANGE: Self = Self(os::Type::from_be_bytes(*b"422v"));
+
+    /// Bi-Planar Component Y'CbCr 8-bit 4:2:2, full-range (luma=\[0,255\] chroma=\[1,255\]).
+    #[doc(alias = "kCVPixelFormatType_422YpCbCr8BiPlanarFullRange")]
+    pub const _422_YP_CB_CR_8_BI_PLANAR_FULL_RANGE: Self = Self(os::Type::from_be_bytes(*b"422f"));
+
+    /// Bi-Planar Component Y'CbCr 8-bit 4:4:4, video-range (luma=\[16,235\] chroma=\[16,240\]).
+    #[doc(alias = "kCVPixelFormatType_444YpCbCr8BiPlanarVideoRange")]
+    pub const _444_YP_CB_CR_8_BI_PLANAR_VIDEO_RANGE: Self = Self(os::Type::from_be_bytes(*b"444v"));
+
+    /// Bi-Planar Component Y'CbCr 8-bit 4:4:4, full-range (luma=\[0,255\] chroma=\[1,255\]).
+    #[doc(alias = "kCVPixelFormatType_444YpCbCr8BiPlanarFullRange")]
+    pub const _444_YP_CB_CR_8_BI_PLANAR_FULL_RANGE: Self = Self(os::Type::from_be_bytes(*b"444f"));
+
     /// 2 plane YCbCr10 4:2:0, each 10 bits in the MSBs of 16bits, video-range (luma=\[64,940\] chroma=\[64,960\])
     #[doc(alias = "kCVPixelFormatType_420YpCbCr10BiPlanarVideoRange")]
     pub const _420_YP_CB_CR_10_BI_PLANAR_VIDEO_RANGE: Self =
@@ -659,6 +782,11 @@ extern "C-unwind" {
     fn CVPixelBufferGetPlaneCount(pixel_buffer: &PixelBuf) -> usize;
     fn CVPixelBufferGetWidthOfPlane(pixel_buffer: &PixelBuf, plane_index: usize) -> usize;
     fn CVPixelBufferGetHeightOfPlane(pixel_buffer: &PixelBuf, plane_index: usize) -> usize;
+    fn CVPixelBufferIsPlanar(pixel_buffer: &PixelBuf) -> bool;
+    fn CVPixelBufferGetBytesPerRow(pixel_buffer: &PixelBuf) -> usize;
+    fn CVPixelBufferGetBytesPerRowOfPlane(pixel_buffer: &PixelBuf, plane_index: usize) -> usize;
+    fn CVPixelBufferGetBaseAddress(pixel_buffer: &PixelBuf) -> *mut u8;
+    fn CVPixelBufferGetBaseAddressOfPlane(pixel_buffer: &PixelBuf, plane_index: usize) -> *mut u8;
 
     fn CVPixelBufferLockBaseAddress(pixel_buffer: &PixelBuf, lock_flags: LockFlags) -> cv::Return;
     fn CVPixelBufferUnlockBaseAddress(pixel_buffer: &PixelBuf, lock_flags: LockFlags)
@@ -744,7 +872,7 @@ pub mod keys {
 
 #[cfg(test)]
 mod tests {
-    use crate::cv::PixelFormat;
+    use crate::cv::{self, pixel_buffer::LockFlags, PixelFormat};
 
     #[test]
     fn basics() {
@@ -753,6 +881,24 @@ mod tests {
         number.show();
     }
 
+    #[test]
+    fn lock() {
+        let mut buf = cv::PixelBuf::new(4, 2, PixelFormat::_420V, None).unwrap();
+        let mut lock = buf.base_address_lock_mut(LockFlags::READ_ONLY).unwrap();
+        assert!(lock.plane_bytes_mut(0).is_none());
+        assert!(lock.bytes_mut().is_none());
+        drop(lock);
+        // shared locks don't hand out mutable bytes
+        let mut lock = buf.base_address_lock(LockFlags::DEFAULT).unwrap();
+        assert!(lock.plane_bytes_mut(0).is_none());
+        drop(lock);
+        let mut lock = buf.base_address_lock_mut(LockFlags::DEFAULT).unwrap();
+        assert_eq!(
+            lock.plane_bytes_mut(0).unwrap().len(),
+            lock.plane_bytes(0).len()
+        );
+    }
+
     #[test]
     fn compressed() {
         assert!(PixelFormat::LOSSY_32_BGRA.is_compressed_avaliable());
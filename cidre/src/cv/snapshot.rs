@@ -0,0 +1,674 @@
+//! RGBA snapshots of pixel buffers for golden image tests.
+//!
+//! [`Snapshot`] copies a [`cv::PixelBuf`] or `io::Surf` of any supported pixel
+//! format into tightly packed 8-bit RGBA, honoring planes and row padding.
+//! PNG and PPM are encoded and decoded in pure Rust, TIFF, HEIF and JPEG go
+//! through ImageIO with the `iio` feature. [`Snapshot::compare`] reports PSNR
+//! and perceptual mismatches against a reference and renders a diff image.
+
+use std::{fmt, path::Path};
+
+use crate::{cf, cv, cv::ycbcr};
+
+#[cfg(feature = "iio")]
+use crate::cg;
+
+#[cfg(feature = "io")]
+use crate::{io, mach};
+
+mod compare;
+pub use compare::Comparison;
+pub use compare::Tolerance;
+
+mod png;
+
+#[derive(Debug)]
+pub enum Error {
+    /// Pixel format can't be converted to RGBA.
+    PixelFormat(cv::PixelFormat),
+    /// Image file format isn't available in this build.
+    Format(Format),
+    /// Image data is malformed or uses unsupported features.
+    InvalidData,
+    /// Snapshots have different dimensions.
+    SizeMismatch,
+    Cv(cv::Return),
+    #[cfg(feature = "io")]
+    Lock(mach::KernReturn),
+    Io(std::io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::PixelFormat(format) => write!(f, "unsupported pixel format {format:?}"),
+            Self::Format(format) => write!(f, "{format:?} is not available"),
+            Self::InvalidData => f.write_str("invalid image data"),
+            Self::SizeMismatch => f.write_str("snapshot sizes differ"),
+            Self::Cv(res) => write!(f, "core video error {res:?}"),
+            #[cfg(feature = "io")]
+            Self::Lock(res) => write!(f, "surface lock failed {res:?}"),
+            Self::Io(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<cv::Return> for Error {
+    fn from(value: cv::Return) -> Self {
+        Self::Cv(value)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Png,
+    /// Binary `P6`, alpha is dropped.
+    Ppm,
+    /// Binary `P5` of luma, alpha is dropped.
+    Pgm,
+    Tiff,
+    Heif,
+    Jpeg,
+}
+
+impl Format {
+    pub fn with_extension(ext: &str) -> Option<Self> {
+        match ext.to_ascii_lowercase().as_str() {
+            "png" => Some(Self::Png),
+            "ppm" => Some(Self::Ppm),
+            "pgm" => Some(Self::Pgm),
+            "tif" | "tiff" => Some(Self::Tiff),
+            "heic" | "heif" => Some(Self::Heif),
+            "jpg" | "jpeg" => Some(Self::Jpeg),
+            _ => None,
+        }
+    }
+
+    /// Uniform type identifier used with ImageIO, `None` for netpbm formats
+    /// which are written without it.
+    pub fn uti(self) -> Option<&'static str> {
+        match self {
+            Self::Png => Some("public.png"),
+            Self::Ppm | Self::Pgm => None,
+            Self::Tiff => Some("public.tiff"),
+            Self::Heif => Some("public.heic"),
+            Self::Jpeg => Some("public.jpeg"),
+        }
+    }
+}
+
+/// Pixel layouts of packed formats.
+#[derive(Clone, Copy)]
+enum Packed {
+    /// Byte indices of r, g, b and a.
+    Rgba8([usize; 4]),
+    Rgb8([usize; 3]),
+    Gray8,
+    GrayF16,
+    GrayF32,
+    RgbaF16,
+    RgbaF32,
+}
+
+impl Packed {
+    fn with_pixel_format(format: cv::PixelFormat) -> Option<Self> {
+        use cv::PixelFormat as F;
+        Some(match format {
+            F::_32_BGRA => Self::Rgba8([2, 1, 0, 3]),
+            F::_32_RGBA => Self::Rgba8([0, 1, 2, 3]),
+            F::_32_ARGB => Self::Rgba8([1, 2, 3, 0]),
+            F::_32_ABGR => Self::Rgba8([3, 2, 1, 0]),
+            F::_24_RGB => Self::Rgb8([0, 1, 2]),
+            F::_24_BGR => Self::Rgb8([2, 1, 0]),
+            F::ONE_COMPONENT_8 => Self::Gray8,
+            F::ONE_COMPONENT_16_HALF => Self::GrayF16,
+            F::ONE_COMPONENT_32_FLOAT => Self::GrayF32,
+            F::_64_RGBA_HALF => Self::RgbaF16,
+            F::_128_RGBA_FLOAT => Self::RgbaF32,
+            _ => return None,
+        })
+    }
+
+    const fn bytes_per_pixel(self) -> usize {
+        match self {
+            Self::Rgba8(_) | Self::GrayF32 => 4,
+            Self::Rgb8(_) => 3,
+            Self::Gray8 => 1,
+            Self::GrayF16 => 2,
+            Self::RgbaF16 => 8,
+            Self::RgbaF32 => 16,
+        }
+    }
+
+    fn convert(self, src: &[u8], dst: &mut [u8]) {
+        let f16 = |i: usize| unorm(half(u16::from_le_bytes([src[i], src[i + 1]])));
+        let f32 = |i: usize| {
+            unorm(f32::from_le_bytes([
+                src[i],
+                src[i + 1],
+                src[i + 2],
+                src[i + 3],
+            ]))
+        };
+        let px = match self {
+            Self::Rgba8([r, g, b, a]) => [src[r], src[g], src[b], src[a]],
+            Self::Rgb8([r, g, b]) => [src[r], src[g], src[b], 255],
+            Self::Gray8 => [src[0], src[0], src[0], 255],
+            Self::GrayF16 => {
+                let v = f16(0);
+                [v, v, v, 255]
+            }
+            Self::GrayF32 => {
+                let v = f32(0);
+                [v, v, v, 255]
+            }
+            Self::RgbaF16 => [f16(0), f16(2), f16(4), f16(6)],
+            Self::RgbaF32 => [f32(0), f32(4), f32(8), f32(12)],
+        };
+        dst.copy_from_slice(&px);
+    }
+}
+
+/// Maps `0.0..=1.0` to `0..=255`, clamping out of range values and NaN.
+#[inline]
+fn unorm(v: f32) -> u8 {
+    (v.clamp(0.0, 1.0) * 255.0 + 0.5) as u8
+}
+
+fn half(bits: u16) -> f32 {
+    let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exp = (bits >> 10) & 0x1f;
+    let mant = (bits & 0x3ff) as f32;
+    sign * match exp {
+        0 => mant * (-24f32).exp2(),
+        0x1f if mant == 0.0 => f32::INFINITY,
+        0x1f => f32::NAN,
+        _ => (1.0 + mant / 1024.0) * (exp as f32 - 15.0).exp2(),
+    }
+}
+
+/// Tightly packed 8-bit RGBA image.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    width: usize,
+    height: usize,
+    rgba: Vec<u8>,
+}
+
+impl fmt::Debug for Snapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Snapshot")
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .finish()
+    }
+}
+
+impl Snapshot {
+    /// Transparent black image.
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            rgba: vec![0; width * height * 4],
+        }
+    }
+
+    /// Returns `None` if `rgba` is not `width * height * 4` bytes.
+    pub fn with_rgba(width: usize, height: usize, rgba: Vec<u8>) -> Option<Self> {
+        (rgba.len() == width * height * 4).then_some(Self {
+            width,
+            height,
+            rgba,
+        })
+    }
+
+    /// Converts planes of a `format` image, a single plane for packed formats.
+    ///
+    /// `matrix` is used for YCbCr formats only.
+    pub fn with_planes(
+        format: cv::PixelFormat,
+        width: usize,
+        height: usize,
+        planes: &[ycbcr::Plane],
+        matrix: ycbcr::Matrix,
+    ) -> Result<Self, Error> {
+        let mut res = Self::new(width, height);
+        if let Some(packed) = Packed::with_pixel_format(format) {
+            let [plane] = planes else {
+                return Err(Error::Cv(cv::Return::INVALID_ARGUMENT));
+            };
+            let bpp = packed.bytes_per_pixel();
+            let len = width * bpp;
+            if width == 0 || height == 0 {
+                return Ok(res);
+            }
+            if plane.bytes_per_row < len
+                || plane.data.len() < plane.bytes_per_row * (height - 1) + len
+            {
+                return Err(Error::Cv(cv::Return::INVALID_ARGUMENT));
+            }
+            for (y, dst) in res.rgba.chunks_exact_mut(width * 4).enumerate() {
+                let src = &plane.data[y * plane.bytes_per_row..][..len];
+                for (s, d) in src.chunks_exact(bpp).zip(dst.chunks_exact_mut(4)) {
+                    packed.convert(s, d);
+                }
+            }
+            return Ok(res);
+        }
+        let mut conv = ycbcr::Converter::with_pixel_format(format, matrix)
+            .map_err(|_| Error::PixelFormat(format))?;
+        conv.set_rgb_order(ycbcr::RgbOrder::Rgba);
+        conv.convert_to_rgb(
+            width,
+            height,
+            planes,
+            ycbcr::PlaneMut::new(&mut res.rgba, width * 4),
+        )?;
+        Ok(res)
+    }
+
+    /// Copies locked pixel buffer, YCbCr matrix comes from attachments.
+    pub fn with_pixel_buf(buf: &cv::PixelBuf) -> Result<Self, Error> {
+        let matrix = buf
+            .copy_attaches(cv::AttachMode::ShouldPropagate)
+            .and_then(|attaches| {
+                let val = attaches.value(cv::image_buf_attachment::keys::ycbcr_matrix())?;
+                if val.get_type_id() != cf::String::type_id() {
+                    return None;
+                }
+                let val: &cf::String = unsafe { std::mem::transmute(val) };
+                ycbcr::Matrix::from_cf_string(val)
+            })
+            .unwrap_or_default();
+        let lock = buf.base_address_lock(cv::pixel_buffer::LockFlags::READ_ONLY)?;
+        let planes: Vec<_> = if buf.is_planar() {
+            (0..buf.plane_count())
+                .map(|i| ycbcr::Plane::new(lock.plane_bytes(i), buf.plane_bytes_per_row(i)))
+                .collect()
+        } else {
+            vec![ycbcr::Plane::new(lock.bytes(), buf.bytes_per_row())]
+        };
+        Self::with_planes(
+            buf.pixel_format(),
+            buf.width(),
+            buf.height(),
+            &planes,
+            matrix,
+        )
+    }
+
+    /// Copies locked surface, YCbCr surfaces use the default matrix.
+    #[cfg(feature = "io")]
+    pub fn with_io_surf(surf: &io::Surf) -> Result<Self, Error> {
+        let lock = surf
+            .base_addr_lock(io::SurfLockOpts::READ_ONLY)
+            .map_err(Error::Lock)?;
+        let planes: Vec<_> = if surf.plane_count() > 0 {
+            (0..surf.plane_count())
+                .map(|i| ycbcr::Plane::new(lock.plane_bytes(i), surf.plane_bytes_per_row(i)))
+                .collect()
+        } else {
+            vec![ycbcr::Plane::new(lock.bytes(), surf.bytes_per_row())]
+        };
+        Self::with_planes(
+            cv::PixelFormat(surf.pixel_format()),
+            surf.width(),
+            surf.height(),
+            &planes,
+            Default::default(),
+        )
+    }
+
+    #[inline]
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    #[inline]
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    #[inline]
+    pub fn rgba(&self) -> &[u8] {
+        &self.rgba
+    }
+
+    #[inline]
+    pub fn rgba_mut(&mut self) -> &mut [u8] {
+        &mut self.rgba
+    }
+
+    #[inline]
+    pub fn into_rgba(self) -> Vec<u8> {
+        self.rgba
+    }
+
+    #[inline]
+    pub fn pixel(&self, x: usize, y: usize) -> [u8; 4] {
+        let i = (y * self.width + x) * 4;
+        [
+            self.rgba[i],
+            self.rgba[i + 1],
+            self.rgba[i + 2],
+            self.rgba[i + 3],
+        ]
+    }
+
+    #[inline]
+    pub fn set_pixel(&mut self, x: usize, y: usize, px: [u8; 4]) {
+        let i = (y * self.width + x) * 4;
+        self.rgba[i..i + 4].copy_from_slice(&px);
+    }
+
+    pub fn to_png(&self) -> Vec<u8> {
+        png::encode(self.width, self.height, &self.rgba)
+    }
+
+    pub fn from_png(data: &[u8]) -> Result<Self, Error> {
+        let (width, height, rgba) = png::decode(data)?;
+        Ok(Self {
+            width,
+            height,
+            rgba,
+        })
+    }
+
+    pub fn to_ppm(&self) -> Vec<u8> {
+        let mut out = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+        for px in self.rgba.chunks_exact(4) {
+            out.extend_from_slice(&px[..3]);
+        }
+        out
+    }
+
+    /// Writes BT.601 luma.
+    pub fn to_pgm(&self) -> Vec<u8> {
+        let mut out = format!("P5\n{} {}\n255\n", self.width, self.height).into_bytes();
+        for px in self.rgba.chunks_exact(4) {
+            let [r, g, b] = [px[0], px[1], px[2]].map(|c| c as u32);
+            out.push(((r * 77 + g * 150 + b * 29 + 128) >> 8) as u8);
+        }
+        out
+    }
+
+    /// Reads binary `P5` and `P6`, 16-bit samples are truncated.
+    pub fn from_ppm(data: &[u8]) -> Result<Self, Error> {
+        let mut pos = 0;
+        let mut fields = [0usize; 4];
+        for (i, field) in fields.iter_mut().enumerate() {
+            loop {
+                match data.get(pos) {
+                    Some(b'#') => {
+                        while data.get(pos).is_some_and(|b| *b != b'\n') {
+                            pos += 1;
+                        }
+                    }
+                    Some(b) if b.is_ascii_whitespace() => pos += 1,
+                    Some(_) => break,
+                    None => return Err(Error::InvalidData),
+                }
+            }
+            let start = pos;
+            while data.get(pos).is_some_and(|b| !b.is_ascii_whitespace()) {
+                pos += 1;
+            }
+            let token = std::str::from_utf8(&data[start..pos]).map_err(|_| Error::InvalidData)?;
+            *field = match (i, token) {
+                (0, "P5") => 1,
+                (0, "P6") => 3,
+                (0, _) => return Err(Error::InvalidData),
+                _ => token.parse().map_err(|_| Error::InvalidData)?,
+            };
+        }
+        // single whitespace before samples
+        pos += 1;
+        let [channels, width, height, max] = fields;
+        if max == 0 || max > 0xffff {
+            return Err(Error::InvalidData);
+        }
+        let sample_len = if max > 255 { 2 } else { 1 };
+        let len = width
+            .checked_mul(height)
+            .and_then(|n| n.checked_mul(channels * sample_len))
+            .and_then(|n| n.checked_add(pos))
+            .ok_or(Error::InvalidData)?;
+        let samples = data.get(pos..len).ok_or(Error::InvalidData)?;
+        let mut res = Self::new(width, height);
+        for (src, dst) in samples
+            .chunks_exact(channels * sample_len)
+            .zip(res.rgba.chunks_exact_mut(4))
+        {
+            let s = |c: usize| {
+                let v = if sample_len == 2 {
+                    u16::from_be_bytes([src[c * 2], src[c * 2 + 1]]) as usize
+                } else {
+                    src[c] as usize
+                };
+                (v * 255 / max) as u8
+            };
+            let px = if channels == 1 {
+                [s(0), s(0), s(0), 255]
+            } else {
+                [s(0), s(1), s(2), 255]
+            };
+            dst.copy_from_slice(&px);
+        }
+        Ok(res)
+    }
+
+    pub fn encode(&self, format: Format) -> Result<Vec<u8>, Error> {
+        match format {
+            Format::Png => Ok(self.to_png()),
+            Format::Ppm => Ok(self.to_ppm()),
+            Format::Pgm => Ok(self.to_pgm()),
+            #[cfg(feature = "iio")]
+            _ => self.encode_iio(format),
+            #[cfg(not(feature = "iio"))]
+            _ => Err(Error::Format(format)),
+        }
+    }
+
+    /// Decodes PNG and PPM, other formats with ImageIO.
+    pub fn decode(data: &[u8]) -> Result<Self, Error> {
+        if png::is_png(data) {
+            Self::from_png(data)
+        } else if data.starts_with(b"P5") || data.starts_with(b"P6") {
+            Self::from_ppm(data)
+        } else {
+            Self::decode_iio(data)
+        }
+    }
+
+    /// Writes the image in format of path extension.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let path = path.as_ref();
+        let format = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(Format::with_extension)
+            .ok_or(Error::InvalidData)?;
+        std::fs::write(path, self.encode(format)?)?;
+        Ok(())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Self::decode(&std::fs::read(path)?)
+    }
+
+    /// Transcodes PNG with ImageIO, as there is no bitmap context yet.
+    #[cfg(feature = "iio")]
+    fn encode_iio(&self, format: Format) -> Result<Vec<u8>, Error> {
+        let image = Self::iio_image(&self.to_png())?;
+        let mut data = cf::DataMut::with_capacity(0);
+        let ty = cf::String::from_str(format.uti().ok_or(Error::Format(format))?);
+        let mut dst = cg::ImageDst::with_data(&mut data, &ty, 1).ok_or(Error::Format(format))?;
+        dst.add_image(&image, None);
+        if !dst.finalize() {
+            return Err(Error::Format(format));
+        }
+        Ok(data.as_slice().to_vec())
+    }
+
+    #[cfg(feature = "iio")]
+    fn decode_iio(data: &[u8]) -> Result<Self, Error> {
+        let image = Self::iio_image(data)?;
+        let mut png = cf::DataMut::with_capacity(0);
+        let ty = cf::String::from_str("public.png");
+        let mut dst = cg::ImageDst::with_data(&mut png, &ty, 1).ok_or(Error::InvalidData)?;
+        dst.add_image(&image, None);
+        if !dst.finalize() {
+            return Err(Error::InvalidData);
+        }
+        Self::from_png(png.as_slice())
+    }
+
+    #[cfg(not(feature = "iio"))]
+    fn decode_iio(_data: &[u8]) -> Result<Self, Error> {
+        Err(Error::InvalidData)
+    }
+
+    #[cfg(feature = "iio")]
+    fn iio_image(data: &[u8]) -> Result<crate::arc::R<cg::Image>, Error> {
+        let data = cf::Data::from_slice(data).ok_or(Error::InvalidData)?;
+        cg::ImageSrc::with_data(&data, None)
+            .and_then(|src| src.image_at(0, None))
+            .ok_or(Error::InvalidData)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cv::{
+        self,
+        snapshot::{Error, Format, Snapshot},
+        ycbcr,
+    };
+
+    #[test]
+    fn planes() {
+        // 2x2 bgra with row padding
+        let bgra = [
+            1, 2, 3, 255, 4, 5, 6, 128, 0, 0, //
+            7, 8, 9, 0, 10, 11, 12, 255, 0, 0,
+        ];
+        let snap = Snapshot::with_planes(
+            cv::PixelFormat::_32_BGRA,
+            2,
+            2,
+            &[ycbcr::Plane::new(&bgra[..18], 10)],
+            Default::default(),
+        )
+        .unwrap();
+        assert_eq!(snap.pixel(1, 0), [6, 5, 4, 128]);
+        assert_eq!(snap.pixel(0, 1), [9, 8, 7, 0]);
+
+        let mask = [0f32, 0.5, 1.0, 2.0].map(f32::to_le_bytes).concat();
+        let snap = Snapshot::with_planes(
+            cv::PixelFormat::ONE_COMPONENT_32_FLOAT,
+            4,
+            1,
+            &[ycbcr::Plane::new(&mask, 16)],
+            Default::default(),
+        )
+        .unwrap();
+        assert_eq!(snap.rgba()[..8], [0, 0, 0, 255, 128, 128, 128, 255]);
+        assert_eq!(snap.pixel(3, 0), [255, 255, 255, 255]);
+
+        // 1.0 and -2.0 halfs
+        let rgba = [0x3c00u16, 0xc000, 0x3800, 0x3c00]
+            .map(u16::to_le_bytes)
+            .concat();
+        let snap = Snapshot::with_planes(
+            cv::PixelFormat::_64_RGBA_HALF,
+            1,
+            1,
+            &[ycbcr::Plane::new(&rgba, 8)],
+            Default::default(),
+        )
+        .unwrap();
+        assert_eq!(snap.pixel(0, 0), [255, 0, 128, 255]);
+
+        let luma = [126u8; 4];
+        let chroma = [128u8; 2];
+        let snap = Snapshot::with_planes(
+            cv::PixelFormat::_420V,
+            2,
+            2,
+            &[ycbcr::Plane::new(&luma, 2), ycbcr::Plane::new(&chroma, 2)],
+            ycbcr::Matrix::Bt709,
+        )
+        .unwrap();
+        assert_eq!(snap.pixel(1, 1), [128, 128, 128, 255]);
+
+        assert!(matches!(
+            Snapshot::with_planes(
+                cv::PixelFormat::_30_RGB,
+                1,
+                1,
+                &[ycbcr::Plane::new(&[0; 4], 4)],
+                Default::default()
+            ),
+            Err(Error::PixelFormat(cv::PixelFormat::_30_RGB))
+        ));
+        assert!(matches!(
+            Snapshot::with_planes(
+                cv::PixelFormat::_32_BGRA,
+                2,
+                2,
+                &[ycbcr::Plane::new(&bgra[..17], 10)],
+                Default::default()
+            ),
+            Err(Error::Cv(cv::Return::INVALID_ARGUMENT))
+        ));
+    }
+
+    #[test]
+    fn files() {
+        let mut snap = Snapshot::new(3, 2);
+        snap.set_pixel(0, 0, [255, 0, 0, 255]);
+        snap.set_pixel(2, 1, [0, 10, 20, 30]);
+        let png = snap.encode(Format::Png).unwrap();
+        assert_eq!(Snapshot::decode(&png).unwrap(), snap);
+
+        let ppm = snap.to_ppm();
+        assert!(ppm.starts_with(b"P6\n3 2\n255\n"));
+        let back = Snapshot::decode(&ppm).unwrap();
+        assert_eq!(back.pixel(2, 1), [0, 10, 20, 255]);
+        let pgm = b"P5 # gray\n2 1\n65535\n\xff\xff\x80\x00";
+        let back = Snapshot::from_ppm(pgm).unwrap();
+        assert_eq!(back.rgba(), [255, 255, 255, 255, 127, 127, 127, 255]);
+        assert!(Snapshot::from_ppm(b"P6\n2 1\n255\n\0").is_err());
+        let huge = b"P6\n18446744073709551615 18446744073709551615\n255\n\0";
+        assert!(matches!(Snapshot::from_ppm(huge), Err(Error::InvalidData)));
+
+        let pgm = snap.encode(Format::Pgm).unwrap();
+        assert!(pgm.starts_with(b"P5\n3 2\n255\n"));
+        assert_eq!(pgm[pgm.len() - 6..], [77, 0, 0, 0, 0, 8]);
+        let back = Snapshot::decode(&pgm).unwrap();
+        assert_eq!(back.pixel(0, 0), [77, 77, 77, 255]);
+
+        assert_eq!(Format::with_extension("JPG"), Some(Format::Jpeg));
+        assert_eq!(Format::with_extension("pgm"), Some(Format::Pgm));
+        assert_eq!(Format::Ppm.uti(), None);
+        assert_eq!(Format::with_extension("bmp"), None);
+    }
+
+    #[cfg(feature = "iio")]
+    #[test]
+    fn iio() {
+        let snap = Snapshot::with_rgba(8, 8, [200, 100, 50, 255].repeat(64)).unwrap();
+        let tiff = snap.encode(Format::Tiff).unwrap();
+        assert!(tiff.starts_with(b"MM") || tiff.starts_with(b"II"));
+        assert_eq!(Snapshot::decode(&tiff).unwrap(), snap);
+    }
+}
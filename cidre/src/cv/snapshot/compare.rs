@@ -0,0 +1,193 @@
+use std::path::Path;
+
+use super::{Error, Snapshot};
+
+/// Max YIQ difference of black and white.
+const MAX_DELTA: f64 = 35215.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tolerance {
+    /// Perceptual color difference a pixel may have, `0.0` to `1.0`.
+    pub threshold: f64,
+    /// Fraction of pixels allowed over the threshold.
+    pub max_mismatch: f64,
+    /// Lowest acceptable PSNR in dB.
+    pub min_psnr: Option<f64>,
+}
+
+impl Tolerance {
+    pub const EXACT: Self = Self {
+        threshold: 0.0,
+        max_mismatch: 0.0,
+        min_psnr: None,
+    };
+}
+
+impl Default for Tolerance {
+    /// Tolerates small color shifts, e.g. of different GPUs or YCbCr round trips.
+    fn default() -> Self {
+        Self {
+            threshold: 0.1,
+            max_mismatch: 0.0,
+            min_psnr: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Comparison {
+    pub tolerance: Tolerance,
+    /// Mean squared error over all channels.
+    pub mse: f64,
+    /// Infinite for identical images.
+    pub psnr: f64,
+    pub max_channel_diff: u8,
+    /// Pixels over the perceptual threshold.
+    pub mismatched: usize,
+    /// Reference faded to light gray, with mismatched pixels in red and
+    /// differing pixels within the threshold in yellow.
+    pub diff: Snapshot,
+}
+
+impl Comparison {
+    pub fn mismatch_ratio(&self) -> f64 {
+        let total = self.diff.width() * self.diff.height();
+        if total == 0 {
+            0.0
+        } else {
+            self.mismatched as f64 / total as f64
+        }
+    }
+
+    pub fn is_match(&self) -> bool {
+        self.mismatch_ratio() <= self.tolerance.max_mismatch
+            && !self.tolerance.min_psnr.is_some_and(|min| self.psnr < min)
+    }
+
+    /// Writes the diff image, e.g. next to a failed golden file.
+    pub fn save_diff<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        self.diff.save(path)
+    }
+}
+
+/// Blends the pixel over white and converts to YIQ.
+fn yiq(px: &[u8]) -> [f64; 3] {
+    let a = px[3] as f64 / 255.0;
+    let [r, g, b] = [0, 1, 2].map(|i| 255.0 + (px[i] as f64 - 255.0) * a);
+    [
+        r * 0.29889531 + g * 0.58662247 + b * 0.11448223,
+        r * 0.59597799 - g * 0.27417610 - b * 0.32180189,
+        r * 0.21147017 - g * 0.52261711 + b * 0.31114694,
+    ]
+}
+
+impl Snapshot {
+    /// Compares with `reference` of the same size.
+    ///
+    /// Perceptual difference is the weighted YIQ distance of pixels blended over white.
+    pub fn compare(&self, reference: &Snapshot, tolerance: Tolerance) -> Result<Comparison, Error> {
+        if self.width() != reference.width() || self.height() != reference.height() {
+            return Err(Error::SizeMismatch);
+        }
+        let limit = MAX_DELTA * tolerance.threshold * tolerance.threshold;
+        let mut diff = Snapshot::new(self.width(), self.height());
+        let mut sum = 0u64;
+        let mut max_channel_diff = 0;
+        let mut mismatched = 0;
+        for ((a, b), d) in self
+            .rgba()
+            .chunks_exact(4)
+            .zip(reference.rgba().chunks_exact(4))
+            .zip(diff.rgba_mut().chunks_exact_mut(4))
+        {
+            for (x, y) in a.iter().zip(b) {
+                let delta = x.abs_diff(*y);
+                sum += delta as u64 * delta as u64;
+                max_channel_diff = max_channel_diff.max(delta);
+            }
+            let px = if a == b {
+                let luma = yiq(b)[0];
+                let faded = (255.0 + (luma - 255.0) * 0.1) as u8;
+                [faded, faded, faded, 255]
+            } else {
+                let (p, q) = (yiq(a), yiq(b));
+                let delta = 0.5053 * (p[0] - q[0]).powi(2)
+                    + 0.299 * (p[1] - q[1]).powi(2)
+                    + 0.1957 * (p[2] - q[2]).powi(2);
+                if delta > limit {
+                    mismatched += 1;
+                    [255, 0, 0, 255]
+                } else {
+                    [255, 255, 0, 255]
+                }
+            };
+            d.copy_from_slice(&px);
+        }
+        let samples = self.rgba().len().max(1) as f64;
+        let mse = sum as f64 / samples;
+        let psnr = if mse == 0.0 {
+            f64::INFINITY
+        } else {
+            10.0 * (255.0 * 255.0 / mse).log10()
+        };
+        Ok(Comparison {
+            tolerance,
+            mse,
+            psnr,
+            max_channel_diff,
+            mismatched,
+            diff,
+        })
+    }
+
+    /// Compares with reference image file, see [`Snapshot::compare`].
+    pub fn compare_with_file<P: AsRef<Path>>(
+        &self,
+        path: P,
+        tolerance: Tolerance,
+    ) -> Result<Comparison, Error> {
+        self.compare(&Snapshot::load(path)?, tolerance)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cv::snapshot::{Error, Snapshot, Tolerance};
+
+    #[test]
+    fn compare() {
+        let reference = Snapshot::with_rgba(4, 1, [100, 150, 200, 255].repeat(4)).unwrap();
+        let res = reference.compare(&reference, Tolerance::EXACT).unwrap();
+        assert!(res.is_match());
+        assert_eq!(res.psnr, f64::INFINITY);
+        assert_eq!(res.diff.pixel(0, 0)[3], 255);
+
+        let mut snap = reference.clone();
+        snap.set_pixel(1, 0, [101, 150, 200, 255]);
+        snap.set_pixel(3, 0, [0, 0, 0, 255]);
+        let res = snap.compare(&reference, Tolerance::default()).unwrap();
+        assert_eq!(res.mismatched, 1);
+        assert_eq!(res.max_channel_diff, 200);
+        assert_eq!(res.diff.pixel(1, 0), [255, 255, 0, 255]);
+        assert_eq!(res.diff.pixel(3, 0), [255, 0, 0, 255]);
+        assert!((res.psnr - 11.57).abs() < 0.01, "{}", res.psnr);
+        assert!(!res.is_match());
+
+        let tolerance = Tolerance {
+            max_mismatch: 0.25,
+            min_psnr: Some(11.0),
+            ..Default::default()
+        };
+        assert!(snap.compare(&reference, tolerance).unwrap().is_match());
+        let tolerance = Tolerance {
+            min_psnr: Some(20.0),
+            ..tolerance
+        };
+        assert!(!snap.compare(&reference, tolerance).unwrap().is_match());
+
+        assert!(matches!(
+            snap.compare(&Snapshot::new(1, 1), tolerance),
+            Err(Error::SizeMismatch)
+        ));
+    }
+}
@@ -0,0 +1,693 @@
+//! Minimal PNG codec with its own deflate, so snapshots don't need ImageIO.
+//!
+//! Encoder writes 8-bit gray, RGB, gray alpha or RGBA with adaptive row filters
+//! and fixed Huffman codes. Decoder reads any non-interlaced PNG.
+
+use super::Error;
+
+const SIGNATURE: &[u8; 8] = b"\x89PNG\r\n\x1a\n";
+
+const LEN_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LEN_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+const WINDOW: usize = 1 << 15;
+
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut c = i as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 {
+                0xedb88320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+            k += 1;
+        }
+        table[i] = c;
+        i += 1;
+    }
+    table
+};
+
+fn crc32(parts: &[&[u8]]) -> u32 {
+    let mut c = !0u32;
+    for part in parts {
+        for b in part.iter() {
+            c = CRC_TABLE[((c ^ *b as u32) & 0xff) as usize] ^ (c >> 8);
+        }
+    }
+    !c
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    // largest run before `b` may overflow
+    for chunk in data.chunks(5552) {
+        for x in chunk {
+            a += *x as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
+
+#[derive(Default)]
+struct BitWriter {
+    out: Vec<u8>,
+    acc: u64,
+    n: u32,
+}
+
+impl BitWriter {
+    fn bits(&mut self, val: u32, count: u32) {
+        self.acc |= (val as u64) << self.n;
+        self.n += count;
+        while self.n >= 8 {
+            self.out.push(self.acc as u8);
+            self.acc >>= 8;
+            self.n -= 8;
+        }
+    }
+
+    /// Huffman codes go most significant bit first.
+    fn code(&mut self, code: u32, len: u32) {
+        self.bits(code.reverse_bits() >> (32 - len), len);
+    }
+
+    fn literal(&mut self, sym: u32) {
+        match sym {
+            0..=143 => self.code(0x30 + sym, 8),
+            144..=255 => self.code(0x190 + sym - 144, 9),
+            256..=279 => self.code(sym - 256, 7),
+            _ => self.code(0xc0 + sym - 280, 8),
+        }
+    }
+
+    fn length(&mut self, len: usize) {
+        let i = LEN_BASE.iter().rposition(|b| *b as usize <= len).unwrap();
+        self.literal(257 + i as u32);
+        self.bits((len - LEN_BASE[i] as usize) as u32, LEN_EXTRA[i] as u32);
+    }
+
+    fn distance(&mut self, dist: usize) {
+        let i = DIST_BASE.iter().rposition(|b| *b as usize <= dist).unwrap();
+        self.code(i as u32, 5);
+        self.bits((dist - DIST_BASE[i] as usize) as u32, DIST_EXTRA[i] as u32);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.n > 0 {
+            self.out.push(self.acc as u8);
+        }
+        self.out
+    }
+}
+
+#[inline]
+fn hash(b: &[u8]) -> usize {
+    ((b[0] as usize) << 10 ^ (b[1] as usize) << 5 ^ b[2] as usize) & (WINDOW - 1)
+}
+
+/// Single fixed Huffman block with greedy LZ77 matching.
+fn deflate(data: &[u8]) -> Vec<u8> {
+    let mut w = BitWriter::default();
+    w.bits(1, 1);
+    w.bits(1, 2);
+    let mut head = vec![usize::MAX; WINDOW];
+    let mut prev = vec![usize::MAX; WINDOW];
+    let insert = |pos: usize, head: &mut [usize], prev: &mut [usize]| {
+        if pos + 3 <= data.len() {
+            let h = hash(&data[pos..]);
+            prev[pos % WINDOW] = head[h];
+            head[h] = pos;
+        }
+    };
+    let mut i = 0;
+    while i < data.len() {
+        let max = (data.len() - i).min(258);
+        let (mut best_len, mut best_dist) = (0, 0);
+        if max >= 3 {
+            let mut cand = head[hash(&data[i..])];
+            let mut chain = 64;
+            while cand != usize::MAX && i - cand <= WINDOW && chain > 0 {
+                let len = data[cand..]
+                    .iter()
+                    .zip(&data[i..i + max])
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                if len > best_len {
+                    (best_len, best_dist) = (len, i - cand);
+                    if len == max {
+                        break;
+                    }
+                }
+                cand = prev[cand % WINDOW];
+                chain -= 1;
+            }
+        }
+        if best_len >= 3 {
+            w.length(best_len);
+            w.distance(best_dist);
+            for pos in i..i + best_len {
+                insert(pos, &mut head, &mut prev);
+            }
+            i += best_len;
+        } else {
+            w.literal(data[i] as u32);
+            insert(i, &mut head, &mut prev);
+            i += 1;
+        }
+    }
+    w.literal(256);
+    w.finish()
+}
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    acc: u32,
+    n: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn bits(&mut self, count: u32) -> Result<u32, Error> {
+        while self.n < count {
+            let b = *self.data.get(self.pos).ok_or(Error::InvalidData)?;
+            self.pos += 1;
+            self.acc |= (b as u32) << self.n;
+            self.n += 8;
+        }
+        let val = self.acc & ((1 << count) - 1);
+        self.acc >>= count;
+        self.n -= count;
+        Ok(val)
+    }
+
+    fn align(&mut self) {
+        self.acc = 0;
+        self.n = 0;
+    }
+}
+
+/// Canonical Huffman code decoded a bit at a time.
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Result<Self, Error> {
+        let mut counts = [0u16; 16];
+        for len in lengths {
+            counts[*len as usize] += 1;
+        }
+        let mut left = 1i32;
+        for count in &counts[1..] {
+            left = (left << 1) - *count as i32;
+            if left < 0 {
+                return Err(Error::InvalidData);
+            }
+        }
+        let mut offsets = [0u16; 16];
+        for len in 1..15 {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+        let mut symbols = vec![0; lengths.len()];
+        for (sym, len) in lengths.iter().enumerate() {
+            if *len != 0 {
+                symbols[offsets[*len as usize] as usize] = sym as u16;
+                offsets[*len as usize] += 1;
+            }
+        }
+        Ok(Self { counts, symbols })
+    }
+
+    fn decode(&self, r: &mut BitReader) -> Result<u16, Error> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for count in &self.counts[1..] {
+            code |= r.bits(1)? as i32;
+            let count = *count as i32;
+            if code - count < first {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(Error::InvalidData)
+    }
+}
+
+fn fixed_codes() -> (Huffman, Huffman) {
+    let mut lengths = [8u8; 288];
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    (
+        Huffman::new(&lengths).unwrap(),
+        Huffman::new(&[5; 30]).unwrap(),
+    )
+}
+
+fn dynamic_codes(r: &mut BitReader) -> Result<(Huffman, Huffman), Error> {
+    const ORDER: [usize; 19] = [
+        16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+    ];
+    let lit_count = r.bits(5)? as usize + 257;
+    let dist_count = r.bits(5)? as usize + 1;
+    let code_count = r.bits(4)? as usize + 4;
+    let mut lengths = [0u8; 19];
+    for i in &ORDER[..code_count] {
+        lengths[*i] = r.bits(3)? as u8;
+    }
+    let codes = Huffman::new(&lengths)?;
+
+    let mut lengths = vec![0u8; lit_count + dist_count];
+    let mut i = 0;
+    while i < lengths.len() {
+        let sym = codes.decode(r)?;
+        let (val, repeat) = match sym {
+            0..=15 => (sym as u8, 1),
+            16 if i > 0 => (lengths[i - 1], 3 + r.bits(2)? as usize),
+            17 => (0, 3 + r.bits(3)? as usize),
+            18 => (0, 11 + r.bits(7)? as usize),
+            _ => return Err(Error::InvalidData),
+        };
+        let end = i + repeat;
+        lengths.get_mut(i..end).ok_or(Error::InvalidData)?.fill(val);
+        i = end;
+    }
+    if lengths[256] == 0 {
+        return Err(Error::InvalidData);
+    }
+    Ok((
+        Huffman::new(&lengths[..lit_count])?,
+        Huffman::new(&lengths[lit_count..])?,
+    ))
+}
+
+/// Fails if output grows over `limit` bytes.
+fn inflate(data: &[u8], limit: usize) -> Result<Vec<u8>, Error> {
+    let mut r = BitReader {
+        data,
+        pos: 0,
+        acc: 0,
+        n: 0,
+    };
+    let mut out = Vec::with_capacity((data.len() * 4).min(limit));
+    loop {
+        let last = r.bits(1)? == 1;
+        match r.bits(2)? {
+            0 => {
+                r.align();
+                let header = data.get(r.pos..r.pos + 4).ok_or(Error::InvalidData)?;
+                let len = u16::from_le_bytes([header[0], header[1]]);
+                if len != !u16::from_le_bytes([header[2], header[3]]) {
+                    return Err(Error::InvalidData);
+                }
+                let start = r.pos + 4;
+                let end = start + len as usize;
+                if out.len() + len as usize > limit {
+                    return Err(Error::InvalidData);
+                }
+                out.extend_from_slice(data.get(start..end).ok_or(Error::InvalidData)?);
+                r.pos = end;
+            }
+            ty @ (1 | 2) => {
+                let (lit, dist) = if ty == 1 {
+                    fixed_codes()
+                } else {
+                    dynamic_codes(&mut r)?
+                };
+                loop {
+                    if out.len() > limit {
+                        return Err(Error::InvalidData);
+                    }
+                    let sym = lit.decode(&mut r)? as usize;
+                    if sym < 256 {
+                        out.push(sym as u8);
+                        continue;
+                    }
+                    if sym == 256 {
+                        break;
+                    }
+                    let i = sym - 257;
+                    if i >= LEN_BASE.len() {
+                        return Err(Error::InvalidData);
+                    }
+                    let len = LEN_BASE[i] as usize + r.bits(LEN_EXTRA[i] as u32)? as usize;
+                    let i = dist.decode(&mut r)? as usize;
+                    if i >= DIST_BASE.len() {
+                        return Err(Error::InvalidData);
+                    }
+                    let d = DIST_BASE[i] as usize + r.bits(DIST_EXTRA[i] as u32)? as usize;
+                    if d > out.len() {
+                        return Err(Error::InvalidData);
+                    }
+                    let start = out.len() - d;
+                    for k in 0..len {
+                        out.push(out[start + k]);
+                    }
+                }
+            }
+            _ => return Err(Error::InvalidData),
+        }
+        if last {
+            return Ok(out);
+        }
+    }
+}
+
+#[inline]
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = (
+        (p - a as i16).abs(),
+        (p - b as i16).abs(),
+        (p - c as i16).abs(),
+    );
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+/// Filters row with each filter type and keeps the one with the smallest sum.
+fn filter_row(row: &[u8], prev: &[u8], bpp: usize, out: &mut Vec<u8>) {
+    let mut best = Vec::new();
+    let mut best_sum = u64::MAX;
+    let mut cur = Vec::with_capacity(row.len() + 1);
+    for ty in 0..5u8 {
+        cur.clear();
+        cur.push(ty);
+        for i in 0..row.len() {
+            let a = if i >= bpp { row[i - bpp] } else { 0 };
+            let b = prev[i];
+            let c = if i >= bpp { prev[i - bpp] } else { 0 };
+            let pred = match ty {
+                0 => 0,
+                1 => a,
+                2 => b,
+                3 => ((a as u16 + b as u16) / 2) as u8,
+                _ => paeth(a, b, c),
+            };
+            cur.push(row[i].wrapping_sub(pred));
+        }
+        let sum = cur[1..]
+            .iter()
+            .map(|x| (*x as i8).unsigned_abs() as u64)
+            .sum();
+        if sum < best_sum {
+            best_sum = sum;
+            std::mem::swap(&mut best, &mut cur);
+        }
+    }
+    out.extend_from_slice(&best);
+}
+
+fn unfilter_row(ty: u8, row: &mut [u8], prev: &[u8], bpp: usize) -> Result<(), Error> {
+    for i in 0..row.len() {
+        let a = if i >= bpp { row[i - bpp] } else { 0 };
+        let b = prev[i];
+        let c = if i >= bpp { prev[i - bpp] } else { 0 };
+        let pred = match ty {
+            0 => 0,
+            1 => a,
+            2 => b,
+            3 => ((a as u16 + b as u16) / 2) as u8,
+            4 => paeth(a, b, c),
+            _ => return Err(Error::InvalidData),
+        };
+        row[i] = row[i].wrapping_add(pred);
+    }
+    Ok(())
+}
+
+fn chunk(out: &mut Vec<u8>, ty: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    out.extend_from_slice(ty);
+    out.extend_from_slice(data);
+    out.extend_from_slice(&crc32(&[ty, data]).to_be_bytes());
+}
+
+/// Encodes RGBA pixels, dropping color and alpha channels that carry nothing.
+pub(super) fn encode(width: usize, height: usize, rgba: &[u8]) -> Vec<u8> {
+    let px = || rgba.chunks_exact(4);
+    let opaque = px().all(|p| p[3] == 255);
+    let gray = px().all(|p| p[0] == p[1] && p[1] == p[2]);
+    let (color_type, channels): (u8, &[usize]) = match (gray, opaque) {
+        (true, true) => (0, &[0]),
+        (false, true) => (2, &[0, 1, 2]),
+        (true, false) => (4, &[0, 3]),
+        (false, false) => (6, &[0, 1, 2, 3]),
+    };
+    let bpp = channels.len();
+    let stride = width * bpp;
+
+    let mut raw = Vec::with_capacity((stride + 1) * height);
+    let mut prev = vec![0u8; stride];
+    let mut row = Vec::with_capacity(stride);
+    for y in 0..height {
+        row.clear();
+        for p in rgba[y * width * 4..][..width * 4].chunks_exact(4) {
+            row.extend(channels.iter().map(|c| p[*c]));
+        }
+        filter_row(&row, &prev, bpp, &mut raw);
+        std::mem::swap(&mut prev, &mut row);
+    }
+
+    let mut zlib = vec![0x78, 0x9c];
+    zlib.extend_from_slice(&deflate(&raw));
+    zlib.extend_from_slice(&adler32(&raw).to_be_bytes());
+
+    let mut ihdr = Vec::with_capacity(13);
+    ihdr.extend_from_slice(&(width as u32).to_be_bytes());
+    ihdr.extend_from_slice(&(height as u32).to_be_bytes());
+    ihdr.extend_from_slice(&[8, color_type, 0, 0, 0]);
+
+    let mut out = SIGNATURE.to_vec();
+    chunk(&mut out, b"IHDR", &ihdr);
+    chunk(&mut out, b"IDAT", &zlib);
+    chunk(&mut out, b"IEND", &[]);
+    out
+}
+
+#[inline]
+pub(super) fn is_png(data: &[u8]) -> bool {
+    data.starts_with(SIGNATURE)
+}
+
+/// Decodes to width, height and 8-bit RGBA, 16-bit samples are truncated.
+pub(super) fn decode(data: &[u8]) -> Result<(usize, usize, Vec<u8>), Error> {
+    if !is_png(data) {
+        return Err(Error::InvalidData);
+    }
+    let mut pos = SIGNATURE.len();
+    let mut header = None;
+    let mut palette: &[u8] = &[];
+    let mut trns: &[u8] = &[];
+    let mut zlib = Vec::new();
+    loop {
+        let len = data.get(pos..pos + 4).ok_or(Error::InvalidData)?;
+        let len = u32::from_be_bytes([len[0], len[1], len[2], len[3]]) as usize;
+        let ty = data.get(pos + 4..pos + 8).ok_or(Error::InvalidData)?;
+        let end = (pos + 8).checked_add(len).ok_or(Error::InvalidData)?;
+        let body = data.get(pos + 8..end).ok_or(Error::InvalidData)?;
+        let crc = data.get(end..end + 4).ok_or(Error::InvalidData)?;
+        if crc32(&[ty, body]).to_be_bytes() != crc {
+            return Err(Error::InvalidData);
+        }
+        pos = end + 4;
+        match ty {
+            b"IHDR" if body.len() == 13 => header = Some(body),
+            b"PLTE" => palette = body,
+            b"tRNS" => trns = body,
+            b"IDAT" => zlib.extend_from_slice(body),
+            b"IEND" => break,
+            _ => {}
+        }
+    }
+    let h = header.ok_or(Error::InvalidData)?;
+    let width = u32::from_be_bytes([h[0], h[1], h[2], h[3]]) as usize;
+    let height = u32::from_be_bytes([h[4], h[5], h[6], h[7]]) as usize;
+    let (depth, color_type) = (h[8] as usize, h[9]);
+    let channels = match (color_type, depth) {
+        (0, 1 | 2 | 4 | 8 | 16) => 1,
+        (3, 1 | 2 | 4 | 8) => 1,
+        (2 | 6 | 4, 8 | 16) => [0, 0, 3, 0, 2, 0, 4][color_type as usize],
+        _ => return Err(Error::InvalidData),
+    };
+    // interlacing isn't supported
+    if h[10] != 0 || h[11] != 0 || h[12] != 0 {
+        return Err(Error::InvalidData);
+    }
+
+    if zlib.len() < 6 || zlib[0] & 0x0f != 8 || u16::from_be_bytes([zlib[0], zlib[1]]) % 31 != 0 {
+        return Err(Error::InvalidData);
+    }
+    // sizes come from the header, so check them before allocating
+    let stride = width
+        .checked_mul(channels * depth)
+        .ok_or(Error::InvalidData)?
+        .div_ceil(8);
+    let raw_len = (stride + 1).checked_mul(height).ok_or(Error::InvalidData)?;
+    let rgba_len = width
+        .checked_mul(height)
+        .and_then(|n| n.checked_mul(4))
+        .ok_or(Error::InvalidData)?;
+    let mut raw = inflate(&zlib[2..], raw_len)?;
+    if raw.len() < raw_len {
+        return Err(Error::InvalidData);
+    }
+    let bpp = (channels * depth / 8).max(1);
+    let mut prev = vec![0u8; stride];
+    for y in 0..height {
+        let row = &mut raw[y * (stride + 1)..(y + 1) * (stride + 1)];
+        let (ty, row) = row.split_first_mut().unwrap();
+        unfilter_row(*ty, row, &prev, bpp)?;
+        prev.copy_from_slice(row);
+    }
+
+    // sample `i` of the row at bit depth
+    let sample = |row: &[u8], i: usize| -> u16 {
+        match depth {
+            16 => u16::from_be_bytes([row[i * 2], row[i * 2 + 1]]),
+            8 => row[i] as u16,
+            _ => {
+                let bit = i * depth;
+                ((row[bit / 8] >> (8 - depth - bit % 8)) & ((1 << depth) - 1) as u8) as u16
+            }
+        }
+    };
+    let to8 = |v: u16| -> u8 {
+        match depth {
+            16 => (v >> 8) as u8,
+            8 => v as u8,
+            _ => (v as usize * 255 / ((1 << depth) - 1)) as u8,
+        }
+    };
+    let key = |i: usize| -> Option<u16> {
+        trns.get(i * 2..i * 2 + 2)
+            .map(|b| u16::from_be_bytes([b[0], b[1]]))
+    };
+
+    let mut rgba = Vec::with_capacity(rgba_len);
+    for y in 0..height {
+        let row = &raw[y * (stride + 1) + 1..(y + 1) * (stride + 1)];
+        for x in 0..width {
+            let s = |c: usize| sample(row, x * channels + c);
+            let px = match color_type {
+                0 => {
+                    let v = s(0);
+                    let g = to8(v);
+                    let a = if key(0) == Some(v) { 0 } else { 255 };
+                    [g, g, g, a]
+                }
+                2 => {
+                    let (r, g, b) = (s(0), s(1), s(2));
+                    let transparent = key(0) == Some(r) && key(1) == Some(g) && key(2) == Some(b);
+                    [to8(r), to8(g), to8(b), if transparent { 0 } else { 255 }]
+                }
+                3 => {
+                    let i = s(0) as usize;
+                    let rgb = palette.get(i * 3..i * 3 + 3).ok_or(Error::InvalidData)?;
+                    [rgb[0], rgb[1], rgb[2], trns.get(i).copied().unwrap_or(255)]
+                }
+                4 => {
+                    let g = to8(s(0));
+                    [g, g, g, to8(s(1))]
+                }
+                _ => [to8(s(0)), to8(s(1)), to8(s(2)), to8(s(3))],
+            };
+            rgba.extend_from_slice(&px);
+        }
+    }
+    Ok((width, height, rgba))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{adler32, crc32, decode, deflate, encode, inflate};
+
+    #[test]
+    fn deflate_roundtrip() {
+        assert_eq!(crc32(&[b"IEND"]), 0xae426082);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e60398);
+
+        let mut data = b"abcabcabcabcabcabcxyz".repeat(100);
+        data.extend((0..5000u32).map(|i| (i * 7 % 251) as u8));
+        let z = deflate(&data);
+        assert!(z.len() < data.len() / 2);
+        assert_eq!(inflate(&z, usize::MAX).unwrap(), data);
+        assert_eq!(inflate(&deflate(&[]), usize::MAX).unwrap(), []);
+
+        // stored block
+        assert_eq!(
+            inflate(b"\x01\x03\x00\xfc\xffabc", usize::MAX).unwrap(),
+            b"abc"
+        );
+        // fixed and dynamic blocks from zlib
+        let z = b"\x4b\x4c\x4a\x4e\x44\x42\x5c\x00";
+        assert_eq!(inflate(z, usize::MAX).unwrap(), b"abcabcabcabcabc\n");
+        let z = b"\x0d\xc8\x41\x0d\x00\x00\x0c\x83\x40\xff\xce\xe8\x30\x35\xee\x43\
+            \xc2\x38\xcc\xb0\xe2\xad\x11\x27\x6b\x3f";
+        let data = [
+            0xc9, 0xc8, 0xca, 0xc8, 0xcb, 0xcb, 0xcb, 0xcb, 0xc9, 0xc8, 0xcb, 0xc8, 0xcb, 0xcb,
+            0xc8, 0xcb, 0xca, 0xc9, 0xc8, 0xca, 0xc8, 0xc8, 0xc8, 0xc8, 0xcb, 0xc9, 0xcb, 0xc8,
+            0xc9, 0xcb, 0xcb, 0xc9,
+        ];
+        assert_eq!(inflate(z, usize::MAX).unwrap(), data);
+    }
+
+    #[test]
+    fn png_roundtrip() {
+        let rgba: Vec<u8> = (0..6 * 5)
+            .flat_map(|i| [(i * 8) as u8, (255 - i * 3) as u8, (i * i) as u8, 255])
+            .collect();
+        let png = encode(6, 5, &rgba);
+        assert_eq!(png[25], 2);
+        assert_eq!(decode(&png).unwrap(), (6, 5, rgba.clone()));
+
+        let mut rgba = rgba;
+        rgba[3] = 7;
+        assert_eq!(decode(&encode(6, 5, &rgba)).unwrap().2, rgba);
+
+        let gray = [9, 9, 9, 255].repeat(4);
+        let png = encode(2, 2, &gray);
+        assert_eq!(png[25], 0);
+        assert_eq!(decode(&png).unwrap().2, gray);
+
+        let mut bad = png.clone();
+        bad[30] ^= 1;
+        assert!(decode(&bad).is_err());
+        assert!(decode(&png[..png.len() - 12]).is_err());
+
+        // header sizes can't overflow or allocate
+        let mut huge = encode(1, 1, &gray[..4]);
+        huge[16..24].copy_from_slice(&[0xff; 8]);
+        let crc = crc32(&[&huge[12..16], &huge[16..29]]);
+        huge[29..33].copy_from_slice(&crc.to_be_bytes());
+        assert!(decode(&huge).is_err());
+        assert!(inflate(&deflate(&[0; 100]), 99).is_err());
+    }
+}
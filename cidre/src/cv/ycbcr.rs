@@ -0,0 +1,889 @@
+//! YCbCr <-> RGB conversion of pixel buffer planes.
+//!
+//! Kernels work on plane byte slices and don't call CoreVideo, so thumbnails
+//! don't need `vt::PixelTransferSession` and conversion is testable anywhere.
+//! [`Converter::pixel_buf_to_rgb`] and [`Converter::rgb_to_pixel_buf`] take planes
+//! of locked [`cv::PixelBuf`].
+//!
+//! Chroma is sited at even luma columns and between luma rows (MPEG-2 "left" siting).
+
+use crate::{cf, cv};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Matrix {
+    #[doc(alias = "kCVImageBufferYCbCrMatrix_ITU_R_601_4")]
+    #[default]
+    Bt601,
+
+    #[doc(alias = "kCVImageBufferYCbCrMatrix_ITU_R_709_2")]
+    Bt709,
+
+    #[doc(alias = "kCVImageBufferYCbCrMatrix_ITU_R_2020")]
+    Bt2020,
+}
+
+impl Matrix {
+    /// Kr and Kb luma coefficients.
+    pub const fn kr_kb(self) -> (f32, f32) {
+        match self {
+            Self::Bt601 => (0.299, 0.114),
+            Self::Bt709 => (0.2126, 0.0722),
+            Self::Bt2020 => (0.2627, 0.0593),
+        }
+    }
+
+    /// Matrix of `kCVImageBufferYCbCrMatrixKey` attachment value.
+    pub fn from_cf_string(val: &cf::String) -> Option<Self> {
+        use cv::image_buf_attachment::ycbcr_matrix;
+        if val.equal(ycbcr_matrix::itu_r_601_4()) {
+            Some(Self::Bt601)
+        } else if val.equal(ycbcr_matrix::itu_r_709_2()) {
+            Some(Self::Bt709)
+        } else if val.equal(ycbcr_matrix::itu_r_2020()) {
+            Some(Self::Bt2020)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Range {
+    /// Luma in `16..=235`, chroma in `16..=240` scaled to bit depth.
+    Video,
+    Full,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChromaFilter {
+    Nearest,
+    #[default]
+    Bilinear,
+}
+
+/// Byte order of 32-bit RGB pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RgbOrder {
+    #[default]
+    Bgra,
+    Rgba,
+}
+
+impl RgbOrder {
+    #[inline]
+    pub fn pixel_format(self) -> cv::PixelFormat {
+        match self {
+            Self::Bgra => cv::PixelFormat::_32_BGRA,
+            Self::Rgba => cv::PixelFormat::_32_RGBA,
+        }
+    }
+
+    /// Indices of r, g, b and a bytes.
+    #[inline]
+    const fn indices(self) -> [usize; 4] {
+        match self {
+            Self::Bgra => [2, 1, 0, 3],
+            Self::Rgba => [0, 1, 2, 3],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+    /// `420v`, `420f`: 8-bit luma plane and interleaved CbCr plane of half size.
+    BiPlanar420,
+    /// `x420`, `xf20`: [`Self::BiPlanar420`] with 10-bit samples in high bits
+    /// of 16-bit little endian words.
+    BiPlanar420_10,
+    /// `2vuy`: 8-bit Cb Y0 Cr Y1 macropixels.
+    Packed422,
+}
+
+impl Layout {
+    pub fn with_pixel_format(format: cv::PixelFormat) -> Option<(Self, Range)> {
+        use cv::PixelFormat as F;
+        match format {
+            F::_420V => Some((Self::BiPlanar420, Range::Video)),
+            F::_420F => Some((Self::BiPlanar420, Range::Full)),
+            F::_420_YP_CB_CR_10_BI_PLANAR_VIDEO_RANGE => Some((Self::BiPlanar420_10, Range::Video)),
+            F::_420_YP_CB_CR_10_BI_PLANAR_FULL_RANGE => Some((Self::BiPlanar420_10, Range::Full)),
+            F::_2VUY => Some((Self::Packed422, Range::Video)),
+            _ => None,
+        }
+    }
+
+    /// `2vuy` is video range only.
+    pub fn pixel_format(self, range: Range) -> Option<cv::PixelFormat> {
+        use cv::PixelFormat as F;
+        match (self, range) {
+            (Self::BiPlanar420, Range::Video) => Some(F::_420V),
+            (Self::BiPlanar420, Range::Full) => Some(F::_420F),
+            (Self::BiPlanar420_10, Range::Video) => Some(F::_420_YP_CB_CR_10_BI_PLANAR_VIDEO_RANGE),
+            (Self::BiPlanar420_10, Range::Full) => Some(F::_420_YP_CB_CR_10_BI_PLANAR_FULL_RANGE),
+            (Self::Packed422, Range::Video) => Some(F::_2VUY),
+            (Self::Packed422, Range::Full) => None,
+        }
+    }
+
+    #[inline]
+    pub const fn plane_count(self) -> usize {
+        match self {
+            Self::BiPlanar420 | Self::BiPlanar420_10 => 2,
+            Self::Packed422 => 1,
+        }
+    }
+
+    #[inline]
+    pub const fn bit_depth(self) -> u32 {
+        match self {
+            Self::BiPlanar420_10 => 10,
+            _ => 8,
+        }
+    }
+
+    /// Bytes of a single row of `plane` for image `width`.
+    pub const fn min_bytes_per_row(self, plane: usize, width: usize) -> usize {
+        let cw = width.div_ceil(2);
+        match (self, plane) {
+            (Self::BiPlanar420, 0) => width,
+            (Self::BiPlanar420, _) => cw * 2,
+            (Self::BiPlanar420_10, 0) => width * 2,
+            (Self::BiPlanar420_10, _) => cw * 4,
+            (Self::Packed422, _) => cw * 4,
+        }
+    }
+
+    pub const fn plane_height(self, plane: usize, height: usize) -> usize {
+        match (self, plane) {
+            (Self::Packed422, _) | (_, 0) => height,
+            _ => height.div_ceil(2),
+        }
+    }
+
+    #[inline]
+    const fn chroma_rows_shift(self) -> u32 {
+        match self {
+            Self::Packed422 => 0,
+            _ => 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Plane<'a> {
+    pub data: &'a [u8],
+    pub bytes_per_row: usize,
+}
+
+impl<'a> Plane<'a> {
+    #[inline]
+    pub fn new(data: &'a [u8], bytes_per_row: usize) -> Self {
+        Self {
+            data,
+            bytes_per_row,
+        }
+    }
+
+    #[inline]
+    fn row(&self, y: usize, len: usize) -> &'a [u8] {
+        let start = y * self.bytes_per_row;
+        &self.data[start..start + len]
+    }
+}
+
+#[derive(Debug)]
+pub struct PlaneMut<'a> {
+    pub data: &'a mut [u8],
+    pub bytes_per_row: usize,
+}
+
+impl<'a> PlaneMut<'a> {
+    #[inline]
+    pub fn new(data: &'a mut [u8], bytes_per_row: usize) -> Self {
+        Self {
+            data,
+            bytes_per_row,
+        }
+    }
+
+    #[inline]
+    fn row_mut(&mut self, y: usize, len: usize) -> &mut [u8] {
+        let start = y * self.bytes_per_row;
+        &mut self.data[start..start + len]
+    }
+}
+
+/// Checks that `rows` rows of `len` bytes fit into plane.
+fn check_plane(data_len: usize, bytes_per_row: usize, rows: usize, len: usize) -> bool {
+    rows == 0 || (bytes_per_row >= len && data_len >= bytes_per_row * (rows - 1) + len)
+}
+
+/// Offsets and scales of samples of the bit depth and range.
+#[derive(Debug, Clone, Copy)]
+struct Levels {
+    y_off: f32,
+    y_range: f32,
+    c_off: f32,
+    c_range: f32,
+    max: f32,
+}
+
+impl Levels {
+    fn new(bit_depth: u32, range: Range) -> Self {
+        let max = ((1 << bit_depth) - 1) as f32;
+        let scale = (1 << (bit_depth - 8)) as f32;
+        match range {
+            Range::Video => Self {
+                y_off: 16.0 * scale,
+                y_range: 219.0 * scale,
+                c_off: 128.0 * scale,
+                c_range: 224.0 * scale,
+                max,
+            },
+            Range::Full => Self {
+                y_off: 0.0,
+                y_range: max,
+                c_off: (1 << (bit_depth - 1)) as f32,
+                c_range: max,
+                max,
+            },
+        }
+    }
+}
+
+/// Converts between YCbCr layouts and 32-bit RGB.
+///
+/// ```
+/// use cidre::cv::{self, ycbcr};
+///
+/// let conv = cv::YCbCrConverter::new(
+///     ycbcr::Layout::BiPlanar420,
+///     ycbcr::Range::Video,
+///     ycbcr::Matrix::Bt709,
+/// );
+///
+/// // 2x2 mid grey
+/// let luma = [126u8; 4];
+/// let chroma = [128u8; 2];
+/// let mut bgra = [0u8; 16];
+/// conv.convert_to_rgb(
+///     2,
+///     2,
+///     &[ycbcr::Plane::new(&luma, 2), ycbcr::Plane::new(&chroma, 2)],
+///     ycbcr::PlaneMut::new(&mut bgra, 8),
+/// )
+/// .unwrap();
+/// assert_eq!(&bgra[..4], &[128, 128, 128, 255]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Converter {
+    layout: Layout,
+    range: Range,
+    matrix: Matrix,
+    chroma_filter: ChromaFilter,
+    rgb_order: RgbOrder,
+}
+
+impl Converter {
+    pub fn new(layout: Layout, range: Range, matrix: Matrix) -> Self {
+        Self {
+            layout,
+            range,
+            matrix,
+            chroma_filter: Default::default(),
+            rgb_order: Default::default(),
+        }
+    }
+
+    pub fn with_pixel_format(format: cv::PixelFormat, matrix: Matrix) -> Result<Self, cv::Return> {
+        let (layout, range) =
+            Layout::with_pixel_format(format).ok_or(cv::Return::INVALID_PIXEL_FORMAT)?;
+        Ok(Self::new(layout, range, matrix))
+    }
+
+    #[inline]
+    pub fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    #[inline]
+    pub fn range(&self) -> Range {
+        self.range
+    }
+
+    #[inline]
+    pub fn matrix(&self) -> Matrix {
+        self.matrix
+    }
+
+    #[inline]
+    pub fn chroma_filter(&self) -> ChromaFilter {
+        self.chroma_filter
+    }
+
+    /// Filter of chroma upsampling in [`Self::convert_to_rgb`].
+    #[inline]
+    pub fn set_chroma_filter(&mut self, val: ChromaFilter) {
+        self.chroma_filter = val;
+    }
+
+    #[inline]
+    pub fn rgb_order(&self) -> RgbOrder {
+        self.rgb_order
+    }
+
+    #[inline]
+    pub fn set_rgb_order(&mut self, val: RgbOrder) {
+        self.rgb_order = val;
+    }
+
+    fn check_ycbcr(&self, width: usize, height: usize, planes: &[(usize, usize)]) -> bool {
+        planes.len() == self.layout.plane_count()
+            && planes.iter().enumerate().all(|(i, &(len, bpr))| {
+                check_plane(
+                    len,
+                    bpr,
+                    self.layout.plane_height(i, height),
+                    self.layout.min_bytes_per_row(i, width),
+                )
+            })
+    }
+
+    /// Reads luma row `y` as samples of layout bit depth.
+    fn read_luma(&self, planes: &[Plane], width: usize, y: usize, out: &mut [i32]) {
+        match self.layout {
+            Layout::BiPlanar420 => {
+                let row = planes[0].row(y, width);
+                for (o, s) in out.iter_mut().zip(row) {
+                    *o = *s as i32;
+                }
+            }
+            Layout::BiPlanar420_10 => {
+                let row = planes[0].row(y, width * 2);
+                for (o, s) in out.iter_mut().zip(row.chunks_exact(2)) {
+                    *o = (u16::from_le_bytes([s[0], s[1]]) >> 6) as i32;
+                }
+            }
+            Layout::Packed422 => {
+                let row = planes[0].row(y, width.div_ceil(2) * 4);
+                for (x, o) in out.iter_mut().enumerate() {
+                    *o = row[x / 2 * 4 + 1 + (x & 1) * 2] as i32;
+                }
+            }
+        }
+    }
+
+    /// Reads chroma row `j` at chroma resolution.
+    fn read_chroma(
+        &self,
+        planes: &[Plane],
+        width: usize,
+        j: usize,
+        cb: &mut [i32],
+        cr: &mut [i32],
+    ) {
+        let cw = width.div_ceil(2);
+        let (plane, step) = match self.layout {
+            Layout::BiPlanar420 => (planes[1].row(j, cw * 2), 2),
+            Layout::BiPlanar420_10 => (planes[1].row(j, cw * 4), 4),
+            Layout::Packed422 => (planes[0].row(j, cw * 4), 4),
+        };
+        for (i, px) in plane.chunks_exact(step).enumerate() {
+            (cb[i], cr[i]) = match self.layout {
+                Layout::BiPlanar420 => (px[0] as i32, px[1] as i32),
+                Layout::BiPlanar420_10 => (
+                    (u16::from_le_bytes([px[0], px[1]]) >> 6) as i32,
+                    (u16::from_le_bytes([px[2], px[3]]) >> 6) as i32,
+                ),
+                Layout::Packed422 => (px[0] as i32, px[2] as i32),
+            };
+        }
+    }
+
+    /// Converts YCbCr `src` planes to 32-bit RGB `dst`.
+    pub fn convert_to_rgb(
+        &self,
+        width: usize,
+        height: usize,
+        src: &[Plane],
+        mut dst: PlaneMut,
+    ) -> Result<(), cv::Return> {
+        let src_planes: Vec<_> = src
+            .iter()
+            .map(|p| (p.data.len(), p.bytes_per_row))
+            .collect();
+        if !self.check_ycbcr(width, height, &src_planes)
+            || !check_plane(dst.data.len(), dst.bytes_per_row, height, width * 4)
+        {
+            return Err(cv::Return::INVALID_ARGUMENT);
+        }
+        if width == 0 || height == 0 {
+            return Ok(());
+        }
+
+        let lv = Levels::new(self.layout.bit_depth(), self.range);
+        let (kr, kb) = self.matrix.kr_kb();
+        let kg = 1.0 - kr - kb;
+        // chroma is accumulated 16 times by the upsampling filter
+        let ys = 255.0 / lv.y_range;
+        let cs = 255.0 / lv.c_range / 16.0;
+        let c_off = lv.c_off * 16.0;
+        let r_cr = 2.0 * (1.0 - kr) * cs;
+        let g_cb = 2.0 * kb * (1.0 - kb) / kg * cs;
+        let g_cr = 2.0 * kr * (1.0 - kr) / kg * cs;
+        let b_cb = 2.0 * (1.0 - kb) * cs;
+        let [ri, gi, bi, ai] = self.rgb_order.indices();
+
+        let cw = width.div_ceil(2);
+        let shift = self.layout.chroma_rows_shift();
+        let ch = self.layout.plane_height(1, height);
+        let mut luma = vec![0i32; width];
+        let mut c0 = [vec![0i32; cw], vec![0i32; cw]];
+        let mut c1 = [vec![0i32; cw], vec![0i32; cw]];
+        // vertically filtered chroma, 4 times accumulated
+        let mut v = [vec![0i32; cw], vec![0i32; cw]];
+        // upsampled chroma, 16 times accumulated
+        let mut up = [vec![0i32; width], vec![0i32; width]];
+
+        for y in 0..height {
+            self.read_luma(src, width, y, &mut luma);
+
+            let j0 = y >> shift;
+            let [cb0, cr0] = &mut c0;
+            self.read_chroma(src, width, j0, cb0, cr0);
+            let j1 = if shift == 0 || self.chroma_filter == ChromaFilter::Nearest {
+                j0
+            } else if y & 1 == 0 {
+                j0.saturating_sub(1)
+            } else {
+                (j0 + 1).min(ch - 1)
+            };
+            if j1 == j0 {
+                for c in 0..2 {
+                    for (v, s) in v[c].iter_mut().zip(&c0[c]) {
+                        *v = s * 4;
+                    }
+                }
+            } else {
+                let [cb1, cr1] = &mut c1;
+                self.read_chroma(src, width, j1, cb1, cr1);
+                for c in 0..2 {
+                    for ((v, s0), s1) in v[c].iter_mut().zip(&c0[c]).zip(&c1[c]) {
+                        *v = s0 * 3 + s1;
+                    }
+                }
+            }
+
+            for c in 0..2 {
+                let (v, up) = (&v[c], &mut up[c]);
+                for (x, u) in up.iter_mut().enumerate() {
+                    let i = x / 2;
+                    *u = if x & 1 == 0 || self.chroma_filter == ChromaFilter::Nearest {
+                        v[i] * 4
+                    } else {
+                        (v[i] + v[(i + 1).min(cw - 1)]) * 2
+                    };
+                }
+            }
+
+            let row = dst.row_mut(y, width * 4);
+            for (((px, &l), &cb), &cr) in row.chunks_exact_mut(4).zip(&luma).zip(&up[0]).zip(&up[1])
+            {
+                let l = (l as f32 - lv.y_off) * ys;
+                let cb = cb as f32 - c_off;
+                let cr = cr as f32 - c_off;
+                let r = l + r_cr * cr;
+                let g = l - g_cb * cb - g_cr * cr;
+                let b = l + b_cb * cb;
+                px[ri] = (r.clamp(0.0, 255.0) + 0.5) as u8;
+                px[gi] = (g.clamp(0.0, 255.0) + 0.5) as u8;
+                px[bi] = (b.clamp(0.0, 255.0) + 0.5) as u8;
+                px[ai] = 255;
+            }
+        }
+        Ok(())
+    }
+
+    /// Converts 32-bit RGB `src` to YCbCr `dst` planes.
+    ///
+    /// Chroma is averaged over the luma pixels it covers, alpha is ignored.
+    pub fn convert_from_rgb(
+        &self,
+        width: usize,
+        height: usize,
+        src: Plane,
+        dst: &mut [PlaneMut],
+    ) -> Result<(), cv::Return> {
+        let dst_planes: Vec<_> = dst
+            .iter()
+            .map(|p| (p.data.len(), p.bytes_per_row))
+            .collect();
+        if !self.check_ycbcr(width, height, &dst_planes)
+            || !check_plane(src.data.len(), src.bytes_per_row, height, width * 4)
+        {
+            return Err(cv::Return::INVALID_ARGUMENT);
+        }
+        if width == 0 || height == 0 {
+            return Ok(());
+        }
+
+        let lv = Levels::new(self.layout.bit_depth(), self.range);
+        let (kr, kb) = self.matrix.kr_kb();
+        let kg = 1.0 - kr - kb;
+        let [ri, gi, bi, _] = self.rgb_order.indices();
+        let cb_s = 1.0 / (2.0 * (1.0 - kb));
+        let cr_s = 1.0 / (2.0 * (1.0 - kr));
+
+        let cw = width.div_ceil(2);
+        let rows = 1usize << self.layout.chroma_rows_shift();
+        let mut luma = vec![0f32; width];
+        let mut cb = vec![0f32; width];
+        let mut cr = vec![0f32; width];
+        let mut out_cb = vec![0f32; cw];
+        let mut out_cr = vec![0f32; cw];
+
+        for j in 0..self.layout.plane_height(1, height) {
+            cb.fill(0.0);
+            cr.fill(0.0);
+            let y0 = j * rows;
+            let y1 = (y0 + rows).min(height);
+            for y in y0..y1 {
+                let row = src.row(y, width * 4);
+                for (x, px) in row.chunks_exact(4).enumerate() {
+                    let r = px[ri] as f32 / 255.0;
+                    let g = px[gi] as f32 / 255.0;
+                    let b = px[bi] as f32 / 255.0;
+                    let l = kr * r + kg * g + kb * b;
+                    luma[x] = l;
+                    cb[x] += (b - l) * cb_s;
+                    cr[x] += (r - l) * cr_s;
+                }
+                self.write_luma(dst, width, y, &luma, &lv);
+            }
+            let n = (y1 - y0) as f32;
+            for i in 0..cw {
+                // [1 2 1] taps around even column
+                let x = i * 2;
+                let l = x.saturating_sub(1);
+                let r = (x + 1).min(width - 1);
+                out_cb[i] = (cb[l] + 2.0 * cb[x] + cb[r]) / (4.0 * n);
+                out_cr[i] = (cr[l] + 2.0 * cr[x] + cr[r]) / (4.0 * n);
+            }
+            self.write_chroma(dst, width, j, &out_cb, &out_cr, &lv);
+        }
+        Ok(())
+    }
+
+    fn write_luma(
+        &self,
+        planes: &mut [PlaneMut],
+        width: usize,
+        y: usize,
+        luma: &[f32],
+        lv: &Levels,
+    ) {
+        let q = |l: f32| (l * lv.y_range + lv.y_off + 0.5).clamp(0.0, lv.max) as u16;
+        match self.layout {
+            Layout::BiPlanar420 => {
+                for (o, l) in planes[0].row_mut(y, width).iter_mut().zip(luma) {
+                    *o = q(*l) as u8;
+                }
+            }
+            Layout::BiPlanar420_10 => {
+                let row = planes[0].row_mut(y, width * 2);
+                for (o, l) in row.chunks_exact_mut(2).zip(luma) {
+                    o.copy_from_slice(&(q(*l) << 6).to_le_bytes());
+                }
+            }
+            Layout::Packed422 => {
+                let row = planes[0].row_mut(y, width.div_ceil(2) * 4);
+                for (x, l) in luma.iter().enumerate() {
+                    row[x / 2 * 4 + 1 + (x & 1) * 2] = q(*l) as u8;
+                }
+                if width & 1 == 1 {
+                    // repeat last luma in padding of the last macropixel
+                    let last = row[(width - 1) / 2 * 4 + 1];
+                    row[(width - 1) / 2 * 4 + 3] = last;
+                }
+            }
+        }
+    }
+
+    fn write_chroma(
+        &self,
+        planes: &mut [PlaneMut],
+        width: usize,
+        j: usize,
+        cb: &[f32],
+        cr: &[f32],
+        lv: &Levels,
+    ) {
+        let cw = width.div_ceil(2);
+        let q = |c: f32| (c * lv.c_range + lv.c_off + 0.5).clamp(0.0, lv.max) as u16;
+        let (row, step) = match self.layout {
+            Layout::BiPlanar420 => (planes[1].row_mut(j, cw * 2), 2),
+            Layout::BiPlanar420_10 => (planes[1].row_mut(j, cw * 4), 4),
+            Layout::Packed422 => (planes[0].row_mut(j, cw * 4), 4),
+        };
+        for ((px, cb), cr) in row.chunks_exact_mut(step).zip(cb).zip(cr) {
+            match self.layout {
+                Layout::BiPlanar420 => {
+                    px[0] = q(*cb) as u8;
+                    px[1] = q(*cr) as u8;
+                }
+                Layout::BiPlanar420_10 => {
+                    px[0..2].copy_from_slice(&(q(*cb) << 6).to_le_bytes());
+                    px[2..4].copy_from_slice(&(q(*cr) << 6).to_le_bytes());
+                }
+                Layout::Packed422 => {
+                    px[0] = q(*cb) as u8;
+                    px[2] = q(*cr) as u8;
+                }
+            }
+        }
+    }
+
+    /// Converts locked planes of `buf` to 32-bit RGB `dst`.
+    pub fn pixel_buf_to_rgb(&self, buf: &cv::PixelBuf, dst: PlaneMut) -> Result<(), cv::Return> {
+        self.check_pixel_format(buf)?;
+        let lock = buf.base_address_lock(cv::pixel_buffer::LockFlags::READ_ONLY)?;
+        let planes = if self.layout.plane_count() == 1 {
+            vec![Plane::new(lock.bytes(), buf.bytes_per_row())]
+        } else {
+            (0..self.layout.plane_count())
+                .map(|i| Plane::new(lock.plane_bytes(i), buf.plane_bytes_per_row(i)))
+                .collect()
+        };
+        self.convert_to_rgb(buf.width(), buf.height(), &planes, dst)
+    }
+
+    /// Converts 32-bit RGB `src` into locked planes of `buf`.
+    pub fn rgb_to_pixel_buf(&self, src: Plane, buf: &mut cv::PixelBuf) -> Result<(), cv::Return> {
+        self.check_pixel_format(buf)?;
+        let _lock = buf.base_address_lock(cv::pixel_buffer::LockFlags::DEFAULT)?;
+        // planes are disjoint, so they are borrowed mutably at once
+        let mut planes: Vec<_> = if self.layout.plane_count() == 1 {
+            let len = buf.bytes_per_row() * buf.height();
+            let data = unsafe { slice_mut(buf.base_addr(), len) };
+            vec![PlaneMut::new(data, buf.bytes_per_row())]
+        } else {
+            (0..self.layout.plane_count())
+                .map(|i| {
+                    let bpr = buf.plane_bytes_per_row(i);
+                    let len = bpr * buf.plane_height(i);
+                    PlaneMut::new(unsafe { slice_mut(buf.plane_base_addr(i), len) }, bpr)
+                })
+                .collect()
+        };
+        self.convert_from_rgb(buf.width(), buf.height(), src, &mut planes)
+    }
+
+    fn check_pixel_format(&self, buf: &cv::PixelBuf) -> Result<(), cv::Return> {
+        match Layout::with_pixel_format(buf.pixel_format()) {
+            Some((layout, range)) if layout == self.layout && range == self.range => Ok(()),
+            _ => Err(cv::Return::INVALID_PIXEL_FORMAT),
+        }
+    }
+}
+
+unsafe fn slice_mut<'a>(ptr: *mut u8, len: usize) -> &'a mut [u8] {
+    if ptr.is_null() {
+        return &mut [];
+    }
+    std::slice::from_raw_parts_mut(ptr, len)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cv::{
+        self,
+        ycbcr::{ChromaFilter, Layout, Matrix, Plane, PlaneMut, Range, RgbOrder},
+    };
+
+    fn to_rgb(conv: &cv::YCbCrConverter, w: usize, h: usize, planes: &[&[u8]]) -> Vec<u8> {
+        let bprs: Vec<_> = (0..planes.len())
+            .map(|i| conv.layout().min_bytes_per_row(i, w))
+            .collect();
+        let src: Vec<_> = planes
+            .iter()
+            .zip(&bprs)
+            .map(|(p, bpr)| Plane::new(p, *bpr))
+            .collect();
+        let mut out = vec![0u8; w * h * 4];
+        conv.convert_to_rgb(w, h, &src, PlaneMut::new(&mut out, w * 4))
+            .unwrap();
+        out
+    }
+
+    #[test]
+    fn golden_420v() {
+        // video range white, black, red of BT.601 and BT.709
+        let cases = [
+            (Matrix::Bt601, [235, 128, 128], [255, 255, 255]),
+            (Matrix::Bt601, [16, 128, 128], [0, 0, 0]),
+            (Matrix::Bt601, [81, 90, 240], [255, 0, 0]),
+            (Matrix::Bt709, [63, 102, 240], [255, 0, 0]),
+            (Matrix::Bt709, [173, 42, 26], [0, 255, 0]),
+            (Matrix::Bt2020, [74, 97, 240], [255, 0, 0]),
+        ];
+        for (matrix, [y, cb, cr], rgb) in cases {
+            let mut conv = cv::YCbCrConverter::new(Layout::BiPlanar420, Range::Video, matrix);
+            conv.set_rgb_order(RgbOrder::Rgba);
+            let out = to_rgb(&conv, 2, 2, &[&[y; 4], &[cb, cr]]);
+            for px in out.chunks(4) {
+                for c in 0..3 {
+                    assert!(
+                        (px[c] as i32 - rgb[c]).abs() <= 1,
+                        "{matrix:?} {px:?} {rgb:?}"
+                    );
+                }
+                assert_eq!(px[3], 255);
+            }
+        }
+    }
+
+    #[test]
+    fn full_range_and_order() {
+        let conv =
+            cv::YCbCrConverter::with_pixel_format(cv::PixelFormat::_420F, Matrix::Bt601).unwrap();
+        assert_eq!(conv.range(), Range::Full);
+        // full range Y=76 Cb=85 Cr=255 is red, chroma tops out at 127/255
+        let out = to_rgb(&conv, 2, 2, &[&[76; 4], &[85, 255]]);
+        assert_eq!(&out[..4], &[0, 0, 254, 255]);
+
+        assert!(
+            cv::YCbCrConverter::with_pixel_format(cv::PixelFormat::_32_BGRA, Matrix::Bt601)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn bit_depth_10() {
+        let conv = cv::YCbCrConverter::new(Layout::BiPlanar420_10, Range::Video, Matrix::Bt709);
+        let word = |v: u16| (v << 6).to_le_bytes();
+        let luma: Vec<u8> = (0..4).flat_map(|_| word(940)).collect();
+        let chroma: Vec<u8> = [word(512), word(512)].concat();
+        let out = to_rgb(&conv, 2, 2, &[&luma, &chroma]);
+        assert_eq!(out, [255; 16]);
+    }
+
+    #[test]
+    fn chroma_upsampling() {
+        // 4x2 frame, bluish chroma on the left, reddish on the right
+        let luma = [128u8; 8];
+        let chroma = [160, 100, 100, 160];
+        let mut conv = cv::YCbCrConverter::new(Layout::BiPlanar420, Range::Full, Matrix::Bt601);
+
+        conv.set_chroma_filter(ChromaFilter::Nearest);
+        let nearest = to_rgb(&conv, 4, 2, &[&luma, &chroma]);
+        assert_eq!(&nearest[4..8], &nearest[0..4]);
+        assert_ne!(&nearest[8..12], &nearest[4..8]);
+
+        conv.set_chroma_filter(ChromaFilter::Bilinear);
+        let bilinear = to_rgb(&conv, 4, 2, &[&luma, &chroma]);
+        // even columns are sited on chroma samples
+        assert_eq!(&bilinear[0..4], &nearest[0..4]);
+        assert_eq!(&bilinear[8..12], &nearest[8..12]);
+        // odd column in between is the average
+        let mid: Vec<_> = (0..4)
+            .map(|c| (nearest[c] as i32 + nearest[8 + c] as i32) / 2)
+            .collect();
+        for c in 0..3 {
+            assert!((bilinear[4 + c] as i32 - mid[c]).abs() <= 1);
+        }
+    }
+
+    #[test]
+    fn packed_422() {
+        let conv =
+            cv::YCbCrConverter::with_pixel_format(cv::PixelFormat::_2VUY, Matrix::Bt601).unwrap();
+        // Cb Y0 Cr Y1: white and black pixels
+        let out = to_rgb(&conv, 2, 1, &[&[128, 235, 128, 16]]);
+        assert_eq!(out, [255, 255, 255, 255, 0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn round_trip() {
+        let (w, h) = (7, 5);
+        let rgba: Vec<u8> = (0..w * h)
+            .flat_map(|i| {
+                let x = (i % w) as u8;
+                let y = (i / w) as u8;
+                [40 + x * 8, 60 + y * 6, 200 - x * 4, 255]
+            })
+            .collect();
+
+        for layout in [
+            Layout::BiPlanar420,
+            Layout::BiPlanar420_10,
+            Layout::Packed422,
+        ] {
+            for range in [Range::Video, Range::Full] {
+                if layout.pixel_format(range).is_none() {
+                    continue;
+                }
+                let mut conv = cv::YCbCrConverter::new(layout, range, Matrix::Bt709);
+                conv.set_rgb_order(RgbOrder::Rgba);
+                let mut planes: Vec<Vec<u8>> = (0..layout.plane_count())
+                    .map(|i| vec![0u8; layout.min_bytes_per_row(i, w) * layout.plane_height(i, h)])
+                    .collect();
+                let mut dst: Vec<_> = planes
+                    .iter_mut()
+                    .enumerate()
+                    .map(|(i, p)| PlaneMut::new(p, layout.min_bytes_per_row(i, w)))
+                    .collect();
+                conv.convert_from_rgb(w, h, Plane::new(&rgba, w * 4), &mut dst)
+                    .unwrap();
+
+                let planes: Vec<&[u8]> = planes.iter().map(|p| &p[..]).collect();
+                let out = to_rgb(&conv, w, h, &planes);
+                for (a, b) in out.iter().zip(&rgba) {
+                    // smooth gradient survives chroma subsampling,
+                    // border rows clamp chroma instead of extrapolating
+                    assert!((*a as i32 - *b as i32).abs() <= 6, "{layout:?} {a} {b}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_planes() {
+        let conv = cv::YCbCrConverter::new(Layout::BiPlanar420, Range::Video, Matrix::Bt601);
+        let luma = [0u8; 4];
+        let mut out = [0u8; 16];
+        let res = conv.convert_to_rgb(2, 2, &[Plane::new(&luma, 2)], PlaneMut::new(&mut out, 8));
+        assert_eq!(res, Err(cv::Return::INVALID_ARGUMENT));
+        let res = conv.convert_to_rgb(
+            2,
+            2,
+            &[Plane::new(&luma, 2), Plane::new(&luma[..1], 2)],
+            PlaneMut::new(&mut out, 8),
+        );
+        assert_eq!(res, Err(cv::Return::INVALID_ARGUMENT));
+    }
+
+    #[test]
+    fn pixel_buf() {
+        let (w, h) = (16, 8);
+        let mut buf = cv::PixelBuf::new(w, h, cv::PixelFormat::_420V, None).unwrap();
+        let conv =
+            cv::YCbCrConverter::with_pixel_format(buf.pixel_format(), Matrix::Bt709).unwrap();
+        let bgra = [30u8, 120, 210, 255].repeat(w * h);
+        conv.rgb_to_pixel_buf(Plane::new(&bgra, w * 4), &mut buf)
+            .unwrap();
+
+        let mut out = vec![0u8; w * h * 4];
+        conv.pixel_buf_to_rgb(&buf, PlaneMut::new(&mut out, w * 4))
+            .unwrap();
+        for (a, b) in out.iter().zip(&bgra) {
+            assert!((*a as i32 - *b as i32).abs() <= 2);
+        }
+
+        let full =
+            cv::YCbCrConverter::with_pixel_format(cv::PixelFormat::_420F, Matrix::Bt709).unwrap();
+        assert_eq!(
+            full.pixel_buf_to_rgb(&buf, PlaneMut::new(&mut out, w * 4)),
+            Err(cv::Return::INVALID_PIXEL_FORMAT)
+        );
+    }
+}
@@ -49,6 +49,33 @@ pub use work_item::WorkItem;
 pub mod block;
 pub use block::Flags as BlockFlags;
 
+#[cfg(feature = "async")]
+pub mod executor;
+#[cfg(feature = "async")]
+pub use executor::block_on;
+#[cfg(feature = "async")]
+pub use executor::interval;
+#[cfg(feature = "async")]
+pub use executor::interval_at;
+#[cfg(feature = "async")]
+pub use executor::sleep;
+#[cfg(feature = "async")]
+pub use executor::sleep_until;
+#[cfg(feature = "async")]
+pub use executor::spawn;
+#[cfg(feature = "async")]
+pub use executor::spawn_main;
+#[cfg(feature = "async")]
+pub use executor::Cancelled;
+#[cfg(feature = "async")]
+pub use executor::Executor;
+#[cfg(feature = "async")]
+pub use executor::Interval;
+#[cfg(feature = "async")]
+pub use executor::JoinHandle;
+#[cfg(feature = "async")]
+pub use executor::Sleep;
+
 #[cfg(feature = "blocks")]
 use crate::blocks;
 
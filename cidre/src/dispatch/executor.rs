@@ -0,0 +1,579 @@
+//! Minimal futures runtime on top of Grand Central Dispatch.
+//!
+//! Tasks are polled on a dispatch queue. Waking a task re-enqueues it with
+//! `dispatch_async_f`, so serial queues give single threaded executors and
+//! concurrent queues give work stealing for free.
+//!
+//! ```no_run
+//! use cidre::dispatch;
+//!
+//! let ex = dispatch::Executor::global(dispatch::QosClass::UTILITY);
+//! let handle = ex.spawn(async {
+//!     dispatch::sleep(std::time::Duration::from_millis(10)).await;
+//!     42
+//! });
+//!
+//! assert_eq!(dispatch::block_on(handle), Ok(42));
+//! ```
+
+use std::{
+    any::Any,
+    future::Future,
+    panic::{self, AssertUnwindSafe},
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, AtomicU8, Ordering},
+        Arc,
+    },
+    task::{Context, Poll, Wake, Waker},
+    time::Duration,
+};
+
+use parking_lot::Mutex;
+
+use crate::{arc, dispatch};
+
+type BoxFuture = Pin<Box<dyn Future<Output = ()> + Send + 'static>>;
+
+/// Spawns futures onto a dispatch queue.
+#[derive(Debug, Clone)]
+pub struct Executor {
+    queue: arc::R<dispatch::Queue>,
+}
+
+impl Executor {
+    /// Executor polling tasks on the given queue.
+    ///
+    /// Serial queue polls one task at a time, concurrent queue
+    /// may poll different tasks in parallel.
+    #[inline]
+    pub fn with_queue(queue: &dispatch::Queue) -> Self {
+        Self {
+            queue: queue.retained(),
+        }
+    }
+
+    /// Executor on a new serial queue.
+    #[inline]
+    pub fn serial() -> Self {
+        Self {
+            queue: dispatch::Queue::new(),
+        }
+    }
+
+    /// Executor on a new concurrent queue.
+    #[inline]
+    pub fn concurrent() -> Self {
+        Self {
+            queue: dispatch::Queue::concurrent(),
+        }
+    }
+
+    /// Executor on the global concurrent queue with specified QoS.
+    #[inline]
+    pub fn global(qos: dispatch::QosClass) -> Self {
+        let queue =
+            dispatch::Queue::global_with_qos(qos).expect("global queue for qos class should exist");
+        Self::with_queue(queue)
+    }
+
+    /// Executor on the main queue.
+    ///
+    /// Tasks are polled on the main thread, so you have to run main loop
+    /// (`dispatch::main()`, `ns::App::run()`, `cf::RunLoop::run()`).
+    #[inline]
+    pub fn main() -> Self {
+        Self::with_queue(dispatch::Queue::main())
+    }
+
+    #[inline]
+    pub fn queue(&self) -> &dispatch::Queue {
+        &self.queue
+    }
+
+    /// Spawns future onto the queue.
+    ///
+    /// Dropping `JoinHandle` detaches the task, use `JoinHandle::cancel` to stop it.
+    /// Panic of the future is resumed where `JoinHandle` is awaited.
+    pub fn spawn<F>(&self, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let join = Arc::new(Mutex::new(JoinState {
+            result: None,
+            panic: None,
+            waker: None,
+        }));
+
+        let state = join.clone();
+        let future = async move {
+            // panics must not unwind through dispatch
+            let mut future = std::pin::pin!(future);
+            let res = std::future::poll_fn(|cx| {
+                match panic::catch_unwind(AssertUnwindSafe(|| future.as_mut().poll(cx))) {
+                    Ok(poll) => poll.map(Ok),
+                    Err(payload) => Poll::Ready(Err(payload)),
+                }
+            })
+            .await;
+            let mut state = state.lock();
+            match res {
+                Ok(res) => state.result = Some(Ok(res)),
+                Err(payload) => state.panic = Some(payload),
+            }
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+        };
+
+        let task = Arc::new(Task {
+            state: AtomicU8::new(Task::IDLE),
+            cancelled: AtomicBool::new(false),
+            future: Mutex::new(Some(Box::pin(future))),
+            queue: self.queue.clone(),
+            on_cancel: {
+                let join = join.clone();
+                Box::new(move || {
+                    let mut state = join.lock();
+                    if state.result.is_none() && state.panic.is_none() {
+                        state.result = Some(Err(Cancelled));
+                    }
+                    if let Some(waker) = state.waker.take() {
+                        waker.wake();
+                    }
+                })
+            },
+        });
+
+        task.clone().schedule();
+
+        JoinHandle { task, state: join }
+    }
+}
+
+/// Spawns future onto the global queue with default QoS.
+#[inline]
+pub fn spawn<F>(future: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    Executor::global(dispatch::QosClass::DEFAULT).spawn(future)
+}
+
+/// Spawns future onto the main queue.
+#[inline]
+pub fn spawn_main<F>(future: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    Executor::main().spawn(future)
+}
+
+struct Task {
+    state: AtomicU8,
+    cancelled: AtomicBool,
+    future: Mutex<Option<BoxFuture>>,
+    queue: arc::R<dispatch::Queue>,
+    on_cancel: Box<dyn Fn() + Send + Sync>,
+}
+
+impl Task {
+    const IDLE: u8 = 0;
+    const SCHEDULED: u8 = 1;
+    const RUNNING: u8 = 2;
+    const NOTIFIED: u8 = 3;
+    const DONE: u8 = 4;
+
+    fn schedule(self: Arc<Self>) {
+        let mut state = self.state.load(Ordering::Acquire);
+        loop {
+            let next = match state {
+                Self::IDLE => Self::SCHEDULED,
+                Self::RUNNING => Self::NOTIFIED,
+                _ => return,
+            };
+            match self
+                .state
+                .compare_exchange_weak(state, next, Ordering::AcqRel, Ordering::Acquire)
+            {
+                Ok(_) if next == Self::SCHEDULED => break,
+                Ok(_) => return,
+                Err(actual) => state = actual,
+            }
+        }
+        self.enqueue();
+    }
+
+    #[inline]
+    fn enqueue(self: Arc<Self>) {
+        let queue = self.queue.clone();
+        queue.async_f(Arc::into_raw(self) as *mut Self, Self::run);
+    }
+
+    extern "C-unwind" fn run(ctx: *mut Self) {
+        let task = unsafe { Arc::from_raw(ctx as *const Self) };
+        task.state.store(Self::RUNNING, Ordering::Release);
+
+        let mut slot = task.future.lock();
+
+        if task.cancelled.load(Ordering::Acquire) {
+            let _ = slot.take();
+            task.state.store(Self::DONE, Ordering::Release);
+            drop(slot);
+            (task.on_cancel)();
+            return;
+        }
+
+        let Some(future) = slot.as_mut() else {
+            task.state.store(Self::DONE, Ordering::Release);
+            return;
+        };
+
+        let waker = Waker::from(task.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        if future.as_mut().poll(&mut cx).is_ready() {
+            let _ = slot.take();
+            task.state.store(Self::DONE, Ordering::Release);
+            return;
+        }
+        drop(slot);
+
+        if task
+            .state
+            .compare_exchange(
+                Self::RUNNING,
+                Self::IDLE,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            )
+            .is_err()
+        {
+            // woken while polling
+            task.state.store(Self::SCHEDULED, Ordering::Release);
+            task.enqueue();
+        }
+    }
+}
+
+impl Wake for Task {
+    #[inline]
+    fn wake(self: Arc<Self>) {
+        self.schedule();
+    }
+
+    #[inline]
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.clone().schedule();
+    }
+}
+
+struct JoinState<T> {
+    result: Option<Result<T, Cancelled>>,
+    panic: Option<Box<dyn Any + Send>>,
+    waker: Option<Waker>,
+}
+
+/// Task was cancelled before it completed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl std::fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("task was cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// Owned permission to join on a task (await its termination).
+pub struct JoinHandle<T> {
+    task: Arc<Task>,
+    state: Arc<Mutex<JoinState<T>>>,
+}
+
+impl<T> JoinHandle<T> {
+    /// Cancels the task.
+    ///
+    /// The future is dropped on its queue next time the task is polled.
+    /// Awaiting the handle after cancellation yields `Err(Cancelled)`
+    /// unless the task has already completed.
+    pub fn cancel(&self) {
+        if !self.task.cancelled.swap(true, Ordering::AcqRel) {
+            self.task.clone().schedule();
+        }
+    }
+
+    #[inline]
+    pub fn is_finished(&self) -> bool {
+        self.task.state.load(Ordering::Acquire) == Task::DONE
+    }
+}
+
+impl<T> Future for JoinHandle<T> {
+    type Output = Result<T, Cancelled>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.state.lock();
+        if let Some(payload) = state.panic.take() {
+            drop(state);
+            panic::resume_unwind(payload)
+        }
+        if let Some(res) = state.result.take() {
+            Poll::Ready(res)
+        } else {
+            state.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+struct SemaWaker(arc::R<dispatch::Semaphore>);
+
+impl Wake for SemaWaker {
+    #[inline]
+    fn wake(self: Arc<Self>) {
+        self.0.signal();
+    }
+
+    #[inline]
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.signal();
+    }
+}
+
+/// Runs future to completion on the current thread.
+///
+/// Do not call it on the main thread if the future waits for
+/// tasks on the main queue.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = std::pin::pin!(future);
+    let sema = dispatch::Semaphore::new(0);
+    let waker = Waker::from(Arc::new(SemaWaker(sema.clone())));
+    let mut cx = Context::from_waker(&waker);
+    loop {
+        if let Poll::Ready(res) = future.as_mut().poll(&mut cx) {
+            return res;
+        }
+        sema.wait_forever();
+    }
+}
+
+#[derive(Default)]
+struct Fired {
+    fired: bool,
+    waker: Option<Waker>,
+}
+
+/// Future completing after specified duration.
+///
+/// Created by [`sleep`]
+pub struct Sleep {
+    deadline: dispatch::Time,
+    shared: Option<Arc<Mutex<Fired>>>,
+}
+
+/// Waits until duration has elapsed.
+#[inline]
+pub fn sleep(duration: Duration) -> Sleep {
+    sleep_until(dispatch::Time::with_delta(duration))
+}
+
+/// Waits until deadline is reached.
+#[inline]
+pub fn sleep_until(deadline: dispatch::Time) -> Sleep {
+    Sleep {
+        deadline,
+        shared: None,
+    }
+}
+
+impl Sleep {
+    extern "C-unwind" fn fire(ctx: *mut Mutex<Fired>) {
+        let shared = unsafe { Arc::from_raw(ctx as *const Mutex<Fired>) };
+        let mut lock = shared.lock();
+        lock.fired = true;
+        if let Some(waker) = lock.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let deadline = self.deadline;
+        let shared = self.shared.get_or_insert_with(|| {
+            let shared = Arc::new(Mutex::new(Fired::default()));
+            let queue = dispatch::Queue::global_with_qos(dispatch::QosClass::DEFAULT).unwrap();
+            let ctx = Arc::into_raw(shared.clone()) as *mut Mutex<Fired>;
+            queue.after_f(deadline, ctx, Self::fire);
+            shared
+        });
+        let mut lock = shared.lock();
+        if lock.fired {
+            Poll::Ready(())
+        } else {
+            lock.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+#[derive(Default)]
+struct Ticks {
+    count: usize,
+    waker: Option<Waker>,
+}
+
+/// Periodic timer backed by `dispatch::TimerSrc`.
+///
+/// Created by [`interval`]
+pub struct Interval {
+    src: arc::R<dispatch::TimerSrc>,
+    shared: Arc<Mutex<Ticks>>,
+}
+
+/// Creates timer firing every `period` starting after the first `period`.
+pub fn interval(period: Duration) -> Interval {
+    interval_at(dispatch::Time::with_delta(period), period, Duration::ZERO)
+}
+
+/// Creates timer firing every `period` starting at `start`.
+pub fn interval_at(start: dispatch::Time, period: Duration, leeway: Duration) -> Interval {
+    let shared = Arc::new(Mutex::new(Ticks::default()));
+    let mut src = dispatch::Src::new_timer(Default::default(), None).unwrap();
+    src.set_context(Arc::into_raw(shared.clone()) as _);
+    src.set_event_handler_f(Some(Interval::on_event as dispatch::Fn<Mutex<Ticks>>));
+    src.set_cancel_handler_f(Some(Interval::on_cancel as dispatch::Fn<Mutex<Ticks>>));
+    src.set(start, period, leeway);
+    src.activate();
+    Interval { src, shared }
+}
+
+impl Interval {
+    extern "C-unwind" fn on_event(ctx: *mut Mutex<Ticks>) {
+        let shared = unsafe { &*ctx };
+        let mut lock = shared.lock();
+        lock.count += 1;
+        if let Some(waker) = lock.waker.take() {
+            waker.wake();
+        }
+    }
+
+    extern "C-unwind" fn on_cancel(ctx: *mut Mutex<Ticks>) {
+        let _ = unsafe { Arc::from_raw(ctx as *const Mutex<Ticks>) };
+    }
+
+    /// Completes on the next tick. Returns number of ticks since previous call.
+    #[inline]
+    pub fn tick(&mut self) -> Tick<'_> {
+        Tick { interval: self }
+    }
+
+    pub fn poll_tick(&mut self, cx: &mut Context<'_>) -> Poll<usize> {
+        let mut lock = self.shared.lock();
+        if lock.count > 0 {
+            Poll::Ready(std::mem::take(&mut lock.count))
+        } else {
+            lock.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+impl Drop for Interval {
+    fn drop(&mut self) {
+        self.src.cancel();
+    }
+}
+
+/// Future returned by [`Interval::tick`]
+pub struct Tick<'a> {
+    interval: &'a mut Interval,
+}
+
+impl<'a> Future for Tick<'a> {
+    type Output = usize;
+
+    #[inline]
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.interval.poll_tick(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use crate::dispatch;
+
+    #[test]
+    fn spawn_and_join() {
+        let ex = dispatch::Executor::serial();
+        let handle = ex.spawn(async { 10 });
+        assert_eq!(dispatch::block_on(handle), Ok(10));
+
+        let ex = dispatch::Executor::global(dispatch::QosClass::UTILITY);
+        let handles: Vec<_> = (0..100).map(|i| ex.spawn(async move { i * 2 })).collect();
+        let sum = dispatch::block_on(async {
+            let mut sum = 0;
+            for h in handles {
+                sum += h.await.unwrap();
+            }
+            sum
+        });
+        assert_eq!(sum, 9900);
+    }
+
+    #[test]
+    fn panic() {
+        let ex = dispatch::Executor::serial();
+        let handle = ex.spawn(async { panic!("boom") });
+        let res = std::panic::catch_unwind(|| dispatch::block_on(handle));
+        let payload = res.unwrap_err();
+        assert_eq!(payload.downcast_ref::<&str>(), Some(&"boom"));
+
+        // queue keeps running tasks
+        assert_eq!(dispatch::block_on(ex.spawn(async { 1 })), Ok(1));
+    }
+
+    #[test]
+    fn cancel() {
+        let ex = dispatch::Executor::concurrent();
+        let counter = Arc::new(AtomicUsize::new(0));
+        let c = counter.clone();
+        let handle = ex.spawn(async move {
+            dispatch::sleep(Duration::from_secs(10)).await;
+            c.fetch_add(1, Ordering::SeqCst);
+        });
+        handle.cancel();
+        assert_eq!(dispatch::block_on(handle), Err(dispatch::Cancelled));
+        assert_eq!(counter.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn timers() {
+        dispatch::block_on(async {
+            let start = std::time::Instant::now();
+            dispatch::sleep(Duration::from_millis(20)).await;
+            assert!(start.elapsed() >= Duration::from_millis(20));
+
+            let mut interval = dispatch::interval(Duration::from_millis(5));
+            let mut ticks = 0;
+            while ticks < 3 {
+                ticks += interval.tick().await;
+            }
+        });
+    }
+}
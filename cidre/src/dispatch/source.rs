@@ -233,12 +233,12 @@ impl Src {
     }
 
     #[inline]
-    pub fn set_event_handler_f<T>(&mut self, handler: Option<&dispatch::Fn<T>>) {
+    pub fn set_event_handler_f<T>(&mut self, handler: Option<dispatch::Fn<T>>) {
         unsafe { dispatch_source_set_event_handler_f(self, transmute(handler)) }
     }
 
     #[inline]
-    pub fn set_cancel_handler_f<T>(&mut self, handler: Option<&dispatch::Fn<T>>) {
+    pub fn set_cancel_handler_f<T>(&mut self, handler: Option<dispatch::Fn<T>>) {
         unsafe { dispatch_source_set_cancel_handler_f(self, transmute(handler)) }
     }
 
@@ -288,13 +288,10 @@ extern "C" {
     fn dispatch_source_get_mask(source: &Src) -> c_ulong;
     fn dispatch_source_get_data(source: &Src) -> c_ulong;
     fn dispatch_source_merge_data(source: &Src, value: c_ulong) -> c_ulong;
-    fn dispatch_source_set_event_handler_f(
-        source: &mut Src,
-        handler: Option<&dispatch::Fn<c_void>>,
-    );
+    fn dispatch_source_set_event_handler_f(source: &mut Src, handler: Option<dispatch::Fn<c_void>>);
     fn dispatch_source_set_cancel_handler_f(
         source: &mut Src,
-        handler: Option<&dispatch::Fn<c_void>>,
+        handler: Option<dispatch::Fn<c_void>>,
     );
 
     fn dispatch_source_set_timer(
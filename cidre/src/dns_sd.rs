@@ -2,6 +2,10 @@ use std::ffi::c_void;
 
 use crate::define_opts;
 
+mod txt_record;
+pub use txt_record::Error as TxtRecordError;
+pub use txt_record::TxtRecord;
+
 pub type Sock = i32;
 
 #[repr(transparent)]
@@ -0,0 +1,273 @@
+/// Error produced while building or parsing a DNS-SD TXT record.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Error {
+    /// Key is empty, contains `=` or is not printable US-ASCII.
+    InvalidKey,
+
+    /// Encoded `key=value` string is longer than 255 bytes.
+    TooLong,
+
+    /// Length byte points past the end of the record.
+    Truncated,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::InvalidKey => "invalid txt record key",
+            Self::TooLong => "txt record entry is longer than 255 bytes",
+            Self::Truncated => "txt record is truncated",
+        };
+        f.write_str(s)
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// DNS-SD TXT record as described in RFC 6763, section 6.
+///
+/// Entries keep insertion order. Keys are compared case-insensitively.
+/// A `None` value is a boolean attribute (`key` without `=`),
+/// `Some(&[])` is an attribute with empty value (`key=`).
+///
+/// Bytes from [`TxtRecord::to_bytes`] can be passed directly as `txtRecord`
+/// to `DNSServiceRegister` or to [`crate::nw::AdvertiseDesc::set_txt_record`].
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct TxtRecord {
+    entries: Vec<(String, Option<Vec<u8>>)>,
+}
+
+impl TxtRecord {
+    /// Maximum length of a single `key=value` string.
+    pub const MAX_ENTRY_LEN: usize = 255;
+
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses wire format.
+    ///
+    /// Following the RFC, empty strings, strings with empty keys and all but the first
+    /// occurrence of the same key are silently ignored.
+    pub fn parse(bytes: &[u8]) -> Result<Self, Error> {
+        let mut res = Self::new();
+        let mut rest = bytes;
+        while let Some((&len, tail)) = rest.split_first() {
+            let len = len as usize;
+            if len > tail.len() {
+                return Err(Error::Truncated);
+            }
+            let (entry, tail) = tail.split_at(len);
+            rest = tail;
+
+            let (key, val) = match entry.iter().position(|&b| b == b'=') {
+                Some(i) => (&entry[..i], Some(&entry[i + 1..])),
+                None => (entry, None),
+            };
+            if !is_valid_key(key) {
+                continue;
+            }
+            // validated as ascii above
+            let key = unsafe { std::str::from_utf8_unchecked(key) };
+            if res.position(key).is_some() {
+                continue;
+            }
+            res.entries.push((key.to_string(), val.map(<[u8]>::to_vec)));
+        }
+        Ok(res)
+    }
+
+    /// Number of attributes.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    #[inline]
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.position(key).is_some()
+    }
+
+    /// Returns `None` if key is not present, `Some(None)` for boolean attribute.
+    pub fn get(&self, key: &str) -> Option<Option<&[u8]>> {
+        self.position(key).map(|i| self.entries[i].1.as_deref())
+    }
+
+    /// Value as utf8 string. Boolean attributes and non utf8 values are `None`.
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        self.get(key)?.and_then(|v| std::str::from_utf8(v).ok())
+    }
+
+    /// Inserts or replaces attribute with value.
+    pub fn set(&mut self, key: &str, val: &[u8]) -> Result<(), Error> {
+        self.insert(key, Some(val))
+    }
+
+    #[inline]
+    pub fn set_str(&mut self, key: &str, val: &str) -> Result<(), Error> {
+        self.set(key, val.as_bytes())
+    }
+
+    /// Inserts or replaces boolean attribute (key without value).
+    #[inline]
+    pub fn set_bool(&mut self, key: &str) -> Result<(), Error> {
+        self.insert(key, None)
+    }
+
+    /// Returns `true` if attribute was present.
+    pub fn remove(&mut self, key: &str) -> bool {
+        if let Some(i) = self.position(key) {
+            self.entries.remove(i);
+            true
+        } else {
+            false
+        }
+    }
+
+    #[inline]
+    pub fn clear(&mut self) {
+        self.entries.clear()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, Option<&[u8]>)> {
+        self.entries.iter().map(|(k, v)| (k.as_str(), v.as_deref()))
+    }
+
+    /// Length of wire format.
+    pub fn encoded_len(&self) -> usize {
+        if self.entries.is_empty() {
+            return 1;
+        }
+        self.entries
+            .iter()
+            .map(|(k, v)| 1 + entry_len(k, v.as_deref()))
+            .sum()
+    }
+
+    /// Wire format. Empty record is encoded as single empty string
+    /// since TXT record with no strings is not allowed.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(self.encoded_len());
+        if self.entries.is_empty() {
+            buf.push(0);
+            return buf;
+        }
+        for (k, v) in self.entries.iter() {
+            buf.push(entry_len(k, v.as_deref()) as u8);
+            buf.extend_from_slice(k.as_bytes());
+            if let Some(v) = v {
+                buf.push(b'=');
+                buf.extend_from_slice(v);
+            }
+        }
+        buf
+    }
+
+    fn insert(&mut self, key: &str, val: Option<&[u8]>) -> Result<(), Error> {
+        if !is_valid_key(key.as_bytes()) {
+            return Err(Error::InvalidKey);
+        }
+        if entry_len(key, val) > Self::MAX_ENTRY_LEN {
+            return Err(Error::TooLong);
+        }
+        let val = val.map(<[u8]>::to_vec);
+        match self.position(key) {
+            Some(i) => self.entries[i] = (key.to_string(), val),
+            None => self.entries.push((key.to_string(), val)),
+        }
+        Ok(())
+    }
+
+    fn position(&self, key: &str) -> Option<usize> {
+        self.entries
+            .iter()
+            .position(|(k, _)| k.eq_ignore_ascii_case(key))
+    }
+}
+
+impl TryFrom<&[u8]> for TxtRecord {
+    type Error = Error;
+
+    #[inline]
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        Self::parse(value)
+    }
+}
+
+fn is_valid_key(key: &[u8]) -> bool {
+    !key.is_empty() && key.iter().all(|&b| (0x20..=0x7e).contains(&b) && b != b'=')
+}
+
+fn entry_len(key: &str, val: Option<&[u8]>) -> usize {
+    key.len() + val.map_or(0, |v| 1 + v.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dns_sd;
+
+    #[test]
+    fn basics() {
+        let mut txt = dns_sd::TxtRecord::new();
+        assert_eq!(txt.to_bytes(), [0]);
+        assert!(dns_sd::TxtRecord::parse(&[0]).unwrap().is_empty());
+        assert!(dns_sd::TxtRecord::parse(&[]).unwrap().is_empty());
+
+        txt.set_str("txtvers", "1").unwrap();
+        txt.set_bool("Secure").unwrap();
+        txt.set("path", b"").unwrap();
+        txt.set_str("TXTVERS", "2").unwrap();
+
+        assert_eq!(txt.len(), 3);
+        assert_eq!(txt.get_str("txtVers"), Some("2"));
+        assert_eq!(txt.get("secure"), Some(None));
+        assert_eq!(txt.get("path"), Some(Some(&b""[..])));
+        assert_eq!(txt.get("missing"), None);
+
+        let bytes = txt.to_bytes();
+        assert_eq!(bytes.len(), txt.encoded_len());
+        assert_eq!(&bytes, b"\x09TXTVERS=2\x06Secure\x05path=");
+        assert_eq!(dns_sd::TxtRecord::parse(&bytes).unwrap(), txt);
+
+        assert!(txt.remove("SECURE"));
+        assert!(!txt.remove("secure"));
+        assert_eq!(txt.iter().count(), 2);
+    }
+
+    #[test]
+    fn limits() {
+        let mut txt = dns_sd::TxtRecord::new();
+        assert_eq!(txt.set_bool(""), Err(dns_sd::TxtRecordError::InvalidKey));
+        assert_eq!(txt.set_bool("a=b"), Err(dns_sd::TxtRecordError::InvalidKey));
+        assert_eq!(txt.set_bool("\n"), Err(dns_sd::TxtRecordError::InvalidKey));
+
+        let val = [b'x'; 253];
+        txt.set("k", &val).unwrap();
+        assert_eq!(
+            txt.set("k", &[b'x'; 254]),
+            Err(dns_sd::TxtRecordError::TooLong)
+        );
+        assert_eq!(txt.get("k"), Some(Some(&val[..])));
+        assert_eq!(txt.to_bytes()[0], 255);
+    }
+
+    #[test]
+    fn parse_quirks() {
+        let txt = dns_sd::TxtRecord::parse(b"\x03a=1\x00\x02=x\x03A=2\x01b\x05c=a=b").unwrap();
+        assert_eq!(txt.len(), 3);
+        assert_eq!(txt.get_str("a"), Some("1"));
+        assert_eq!(txt.get("b"), Some(None));
+        assert_eq!(txt.get_str("c"), Some("a=b"));
+
+        assert_eq!(
+            dns_sd::TxtRecord::parse(b"\x05ab"),
+            Err(dns_sd::TxtRecordError::Truncated)
+        );
+    }
+}
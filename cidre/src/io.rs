@@ -1,5 +1,6 @@
 pub mod surface;
 
+pub use surface::BaseAddrLockGuard as SurfBaseAddrLockGuard;
 pub use surface::ComponentName as SurfComponentName;
 pub use surface::ComponentRange as SurfComponentRange;
 pub use surface::LockOpts as SurfLockOpts;
@@ -1,4 +1,4 @@
-use crate::{arc, cf, define_cf_type, define_opts, os, sys::_types::MachPort};
+use crate::{arc, cf, define_cf_type, define_opts, mach, os, sys::_types::MachPort};
 
 #[doc(alias = "SurfaceID")]
 pub type SurfId = u32;
@@ -260,6 +260,84 @@ impl Surf {
     pub fn pixel_format(&self) -> os::Type {
         unsafe { IOSurfaceGetPixelFormat(self) }
     }
+
+    #[doc(alias = "IOSurfaceGetBytesPerRowOfPlane")]
+    #[inline]
+    pub fn plane_bytes_per_row(&self, plane_index: usize) -> usize {
+        unsafe { IOSurfaceGetBytesPerRowOfPlane(self, plane_index) }
+    }
+
+    /// Valid only while the surface is locked.
+    #[doc(alias = "IOSurfaceGetBaseAddress")]
+    #[inline]
+    pub unsafe fn base_addr(&self) -> *mut u8 {
+        IOSurfaceGetBaseAddress(self)
+    }
+
+    /// Valid only while the surface is locked.
+    #[doc(alias = "IOSurfaceGetBaseAddressOfPlane")]
+    #[inline]
+    pub unsafe fn plane_base_addr(&self, plane_index: usize) -> *mut u8 {
+        IOSurfaceGetBaseAddressOfPlane(self, plane_index)
+    }
+
+    #[doc(alias = "IOSurfaceLock")]
+    #[inline]
+    pub unsafe fn lock(&self, options: LockOpts, seed: *mut u32) -> mach::KernReturn {
+        IOSurfaceLock(self, options, seed)
+    }
+
+    #[doc(alias = "IOSurfaceUnlock")]
+    #[inline]
+    pub unsafe fn unlock(&self, options: LockOpts, seed: *mut u32) -> mach::KernReturn {
+        IOSurfaceUnlock(self, options, seed)
+    }
+
+    /// Locks the surface until the guard is dropped.
+    pub fn base_addr_lock(&self, options: LockOpts) -> Result<BaseAddrLockGuard, mach::KernReturn> {
+        let res = unsafe { self.lock(options, std::ptr::null_mut()) };
+        if res.is_ok() {
+            Ok(BaseAddrLockGuard(self, options))
+        } else {
+            Err(res)
+        }
+    }
+}
+
+pub struct BaseAddrLockGuard<'a>(&'a Surf, LockOpts);
+
+impl<'a> BaseAddrLockGuard<'a> {
+    #[inline]
+    pub fn surf(&self) -> &Surf {
+        self.0
+    }
+
+    /// Rows of non planar surface, `bytes_per_row * height` bytes.
+    pub fn bytes(&self) -> &[u8] {
+        let len = self.0.bytes_per_row() * self.0.height();
+        unsafe { slice(self.0.base_addr(), len) }
+    }
+
+    /// Rows of the plane, `plane_bytes_per_row * plane_height` bytes.
+    pub fn plane_bytes(&self, plane_index: usize) -> &[u8] {
+        let len = self.0.plane_bytes_per_row(plane_index) * self.0.plane_height(plane_index);
+        unsafe { slice(self.0.plane_base_addr(plane_index), len) }
+    }
+}
+
+unsafe fn slice<'a>(ptr: *const u8, len: usize) -> &'a [u8] {
+    if ptr.is_null() {
+        return &[];
+    }
+    std::slice::from_raw_parts(ptr, len)
+}
+
+impl<'a> Drop for BaseAddrLockGuard<'a> {
+    #[inline]
+    fn drop(&mut self) {
+        let res = unsafe { self.0.unlock(self.1, std::ptr::null_mut()) };
+        debug_assert!(res.is_ok());
+    }
 }
 
 extern "C" {
@@ -294,6 +372,11 @@ extern "C" {
 
     fn IOSurfaceGetBytesPerRow(buffer: &Surf) -> usize;
     fn IOSurfaceGetPixelFormat(buffer: &Surf) -> os::Type;
+    fn IOSurfaceGetBytesPerRowOfPlane(buffer: &Surf, plane_index: usize) -> usize;
+    fn IOSurfaceGetBaseAddress(buffer: &Surf) -> *mut u8;
+    fn IOSurfaceGetBaseAddressOfPlane(buffer: &Surf, plane_index: usize) -> *mut u8;
+    fn IOSurfaceLock(buffer: &Surf, options: LockOpts, seed: *mut u32) -> mach::KernReturn;
+    fn IOSurfaceUnlock(buffer: &Surf, options: LockOpts, seed: *mut u32) -> mach::KernReturn;
 
 }
 
@@ -546,6 +629,9 @@ mod test {
         assert_eq!(false, surf2.is_in_use());
         let vals = surf2.all_values().unwrap();
         vals.show();
+
+        let lock = surf.base_addr_lock(io::SurfLockOpts::READ_ONLY).unwrap();
+        assert_eq!(lock.bytes().len(), surf.bytes_per_row() * 200);
     }
 
     #[cfg(not(feature = "macos_15_0"))]
@@ -164,6 +164,10 @@ pub mod ut;
 #[cfg(feature = "un")]
 pub mod un;
 
+/// usbmuxd and lockdownd clients
+#[cfg(all(unix, feature = "usbmux"))]
+pub mod usbmux;
+
 pub mod time;
 
 pub mod dns_sd;
@@ -28,10 +28,23 @@ pub use connection_group::ConnectionGroup;
 pub mod content_context;
 pub use content_context::ContentCtx;
 
+#[cfg(feature = "async")]
+pub mod stream;
+#[cfg(feature = "async")]
+pub use stream::Messages;
+#[cfg(feature = "async")]
+pub use stream::Msg;
+#[cfg(feature = "async")]
+pub use stream::Stream;
+
 mod txt_record;
+pub use txt_record::AccessBytes as TxtRecordAccessBytes;
+pub use txt_record::AccessKey as TxtRecordAccessKey;
+pub use txt_record::FindKey as TxtRecordFindKey;
 pub use txt_record::TxtRecord;
 
 mod endpoint;
+pub use endpoint::BonjourService;
 pub use endpoint::Endpoint;
 pub use endpoint::EndpointType;
 
@@ -67,6 +80,23 @@ mod protocol_options;
 pub use protocol_options::ProtocolDefinition;
 pub use protocol_options::ProtocolMetadata;
 pub use protocol_options::ProtocolOpts;
+pub use protocol_options::SecProtocolOpts;
+pub use protocol_options::TcpMetadata;
+pub use protocol_options::TcpOpts;
+pub use protocol_options::TlsOpts;
+pub use protocol_options::TlsVersion;
+pub use protocol_options::UdpOpts;
+pub use protocol_options::WsCloseCode;
+pub use protocol_options::WsMetadata;
+pub use protocol_options::WsOpcode;
+pub use protocol_options::WsOpts;
+pub use protocol_options::WsVersion;
+
+pub mod framer;
+pub use framer::Framer;
+pub use framer::FramerCreateFlags;
+pub use framer::FramerMsg;
+pub use framer::FramerStartResult;
 
 mod privacy_context;
 pub use privacy_context::PrivacyContext;
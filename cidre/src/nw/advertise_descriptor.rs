@@ -1,6 +1,6 @@
 use std::ffi::{c_char, c_void, CStr};
 
-use crate::{arc, define_obj_type, ns, nw};
+use crate::{arc, define_obj_type, dns_sd, ns, nw};
 
 define_obj_type!(
     #[doc(alias = "nw_advertise_descriptor")]
@@ -31,6 +31,12 @@ impl AdvertiseDesc {
         unsafe { nw_advertise_descriptor_set_txt_record(self, val.as_ptr() as _, val.len()) }
     }
 
+    #[doc(alias = "nw_advertise_descriptor_set_txt_record")]
+    #[inline]
+    pub fn set_txt(&mut self, val: &dns_sd::TxtRecord) {
+        self.set_txt_record(&val.to_bytes())
+    }
+
     #[doc(alias = "nw_advertise_descriptor_set_no_auto_rename")]
     #[inline]
     pub fn set_no_auto_rename(&mut self, val: bool) {
@@ -1,4 +1,4 @@
-use crate::{arc, blocks, define_obj_type, define_opts, ns, nw};
+use crate::{arc, blocks, define_obj_type, define_opts, dns_sd, ns, nw};
 
 #[doc(alias = "nw_browse_result_enumerate_interface_t")]
 pub type EnumerateIface<Attr> = blocks::Block<fn(&nw::Iface) -> bool, Attr>;
@@ -69,7 +69,35 @@ impl BrowseResult {
         unsafe { nw_browse_result_copy_txt_record_object(self) }
     }
 
-    #[doc(alias = "nw_browse_result_copy_txt_record_object")]
+    /// TXT record parsed into pure Rust representation.
+    ///
+    /// Malformed records are `None`.
+    pub fn txt_record(&self) -> Option<dns_sd::TxtRecord> {
+        self.txt_record_obj()?.to_dns_sd().ok()
+    }
+
+    /// Name, type and domain of the discovered Bonjour service.
+    #[inline]
+    pub fn bonjour_service(&self) -> Option<nw::BonjourService> {
+        self.endpoint().bonjour_service()
+    }
+
+    #[inline]
+    pub fn service_name(&self) -> Option<String> {
+        self.bonjour_service().map(|s| s.name)
+    }
+
+    #[inline]
+    pub fn service_type(&self) -> Option<String> {
+        self.bonjour_service().map(|s| s.type_)
+    }
+
+    #[inline]
+    pub fn service_domain(&self) -> Option<String> {
+        self.bonjour_service().map(|s| s.domain)
+    }
+
+    #[doc(alias = "nw_browse_result_enumerate_interfaces")]
     #[inline]
     pub fn enumerate_ifaces<'a>(&self, enumerator: &mut EnumerateIface<blocks::NoEsc>) {
         unsafe { nw_browse_result_enumerate_interfaces(self, enumerator) }
@@ -1,4 +1,6 @@
-use crate::{define_obj_type, ns};
+use std::ffi::{c_char, CStr};
+
+use crate::{arc, define_obj_type, ns, nw};
 
 define_obj_type!(
     #[doc(alias = "nw_content_context")]
@@ -6,7 +8,78 @@ define_obj_type!(
     pub ContentCtx(ns::Id)
 );
 
+unsafe impl Send for ContentCtx {}
+unsafe impl Sync for ContentCtx {}
+
 impl ContentCtx {
+    #[doc(alias = "nw_content_context_create")]
+    #[inline]
+    pub fn with_id(id: &CStr) -> arc::R<Self> {
+        unsafe { nw_content_context_create(id.as_ptr()) }
+    }
+
+    #[doc(alias = "nw_content_context_get_identifier")]
+    #[inline]
+    pub fn id(&self) -> &CStr {
+        unsafe { CStr::from_ptr(nw_content_context_get_identifier(self)) }
+    }
+
+    /// Returns true if the content context is the final context
+    /// for the connection. When receiving, this is set when the peer
+    /// closed its side of the connection (half-close).
+    #[doc(alias = "nw_content_context_get_is_final")]
+    #[inline]
+    pub fn is_final(&self) -> bool {
+        unsafe { nw_content_context_get_is_final(self) }
+    }
+
+    /// Set the final flag on a content context. When sending, marking
+    /// the context as final closes the write side of the connection.
+    #[doc(alias = "nw_content_context_set_is_final")]
+    #[inline]
+    pub fn set_is_final(&mut self, val: bool) {
+        unsafe { nw_content_context_set_is_final(self, val) }
+    }
+
+    #[doc(alias = "nw_content_context_get_expiration_milliseconds")]
+    #[inline]
+    pub fn expiration_ms(&self) -> u64 {
+        unsafe { nw_content_context_get_expiration_milliseconds(self) }
+    }
+
+    #[doc(alias = "nw_content_context_set_expiration_milliseconds")]
+    #[inline]
+    pub fn set_expiration_ms(&mut self, val: u64) {
+        unsafe { nw_content_context_set_expiration_milliseconds(self, val) }
+    }
+
+    #[doc(alias = "nw_content_context_get_relative_priority")]
+    #[inline]
+    pub fn relative_priority(&self) -> f64 {
+        unsafe { nw_content_context_get_relative_priority(self) }
+    }
+
+    #[doc(alias = "nw_content_context_set_relative_priority")]
+    #[inline]
+    pub fn set_relative_priority(&mut self, val: f64) {
+        unsafe { nw_content_context_set_relative_priority(self, val) }
+    }
+
+    #[doc(alias = "nw_content_context_copy_protocol_metadata")]
+    #[inline]
+    pub fn protocol_metadata(
+        &self,
+        protocol: &nw::ProtocolDefinition,
+    ) -> Option<arc::R<nw::ProtocolMetadata>> {
+        unsafe { nw_content_context_copy_protocol_metadata(self, protocol) }
+    }
+
+    #[doc(alias = "nw_content_context_set_metadata_for_protocol")]
+    #[inline]
+    pub fn set_protocol_metadata(&mut self, val: &nw::ProtocolMetadata) {
+        unsafe { nw_content_context_set_metadata_for_protocol(self, val) }
+    }
+
     /// A send callback override that causes the write call to
     /// be treated as idempotent. Idempotent content is allowed to be sent
     /// before the connection is ready, and may be replayed across parallel connection
@@ -49,4 +122,24 @@ extern "C" {
     static _nw_content_context_default_message: &'static ContentCtx;
     static _nw_content_context_final_send: &'static ContentCtx;
     static _nw_content_context_default_stream: &'static ContentCtx;
+
+    fn nw_content_context_create(context_identifier: *const c_char) -> arc::R<ContentCtx>;
+    fn nw_content_context_get_identifier(context: &ContentCtx) -> *const c_char;
+    fn nw_content_context_get_is_final(context: &ContentCtx) -> bool;
+    fn nw_content_context_set_is_final(context: &mut ContentCtx, is_final: bool);
+    fn nw_content_context_get_expiration_milliseconds(context: &ContentCtx) -> u64;
+    fn nw_content_context_set_expiration_milliseconds(
+        context: &mut ContentCtx,
+        expiration_milliseconds: u64,
+    );
+    fn nw_content_context_get_relative_priority(context: &ContentCtx) -> f64;
+    fn nw_content_context_set_relative_priority(context: &mut ContentCtx, relative_priority: f64);
+    fn nw_content_context_copy_protocol_metadata(
+        context: &ContentCtx,
+        protocol: &nw::ProtocolDefinition,
+    ) -> Option<arc::R<nw::ProtocolMetadata>>;
+    fn nw_content_context_set_metadata_for_protocol(
+        context: &mut ContentCtx,
+        protocol_metadata: &nw::ProtocolMetadata,
+    );
 }
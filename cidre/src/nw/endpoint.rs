@@ -1,6 +1,6 @@
 use std::ffi::{c_char, CStr, CString};
 
-use crate::{arc, define_obj_type, ns};
+use crate::{arc, define_obj_type, ns, nw};
 
 define_obj_type!(
     #[doc(alias = "nw_endpoint")]
@@ -141,6 +141,29 @@ impl Endpoint {
     }
 }
 
+/// Owned name, type and domain of a Bonjour service endpoint.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct BonjourService {
+    pub name: String,
+    pub type_: String,
+    pub domain: String,
+}
+
+impl Endpoint {
+    /// `None` if endpoint is not a Bonjour service.
+    pub fn bonjour_service(&self) -> Option<BonjourService> {
+        if self.type_() != EndpointType::BonjourService {
+            return None;
+        }
+        let to_string = |s: Option<&CStr>| s.map(|s| s.to_string_lossy().into_owned());
+        Some(BonjourService {
+            name: to_string(self.bonjour_service_name())?,
+            type_: to_string(self.bonjour_service_type())?,
+            domain: to_string(self.bonjour_service_domain()).unwrap_or_default(),
+        })
+    }
+}
+
 #[link(name = "Network", kind = "framework")]
 extern "C" {
     fn nw_endpoint_create_bonjour_service(
@@ -177,15 +200,8 @@ impl Endpoint {
 
     #[doc(alias = "nw_endpoint_copy_txt_record")]
     #[inline]
-    pub fn txt_record(&self) -> Option<CString> {
-        unsafe {
-            let ptr = nw_endpoint_copy_txt_record(self);
-            if ptr.is_null() {
-                None
-            } else {
-                Some(CString::from_raw(ptr))
-            }
-        }
+    pub fn txt_record(&self) -> Option<arc::R<nw::TxtRecord>> {
+        unsafe { nw_endpoint_copy_txt_record(self) }
     }
 
     #[doc(alias = "nw_endpoint_get_signature")]
@@ -207,7 +223,7 @@ impl Endpoint {
 extern "C" {
     fn nw_endpoint_create_url(url: *const c_char) -> Option<arc::R<Endpoint>>;
     fn nw_endpoint_get_url(endpoint: &Endpoint) -> *const c_char;
-    fn nw_endpoint_copy_txt_record(endpoint: &Endpoint) -> *mut c_char;
+    fn nw_endpoint_copy_txt_record(endpoint: &Endpoint) -> Option<arc::R<nw::TxtRecord>>;
     fn nw_endpoint_get_signature(
         endpoint: &Endpoint,
         out_signature_length: &mut usize,
@@ -247,6 +263,11 @@ mod tests {
             endpoint.bonjour_service_domain().unwrap(),
             domain.as_c_str()
         );
+
+        let service = endpoint.bonjour_service().unwrap();
+        assert_eq!(service.name, "example");
+        assert_eq!(service.type_, "_what._udp");
+        assert_eq!(service.domain, "local");
     }
 
     #[test]
@@ -258,5 +279,6 @@ mod tests {
 
         assert!(endpoint.txt_record().is_none());
         assert!(endpoint.signature().is_none());
+        assert!(endpoint.bonjour_service().is_none());
     }
 }
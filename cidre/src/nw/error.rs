@@ -7,6 +7,9 @@ define_obj_type!(
     pub Error(ns::Id)
 );
 
+unsafe impl Send for Error {}
+unsafe impl Sync for Error {}
+
 #[doc(alias = "nw_error_domain_t")]
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 #[repr(i32)]
@@ -46,6 +49,15 @@ impl Error {
     }
 }
 
+impl From<&Error> for std::io::Error {
+    fn from(value: &Error) -> Self {
+        match value.domain() {
+            ErrorDomain::Posix => std::io::Error::from_raw_os_error(value.code()),
+            domain => std::io::Error::other(format!("{domain:?} error {}", value.code())),
+        }
+    }
+}
+
 #[link(name = "Network", kind = "framework")]
 extern "C" {
     fn nw_error_get_error_domain(error: &Error) -> ErrorDomain;
@@ -0,0 +1,623 @@
+use std::ffi::{c_char, c_int, CStr};
+
+use crate::{arc, blocks, define_obj_type, define_opts, dispatch, ns, nw};
+
+define_obj_type!(
+    /// A instance of a framer protocol within a connection.
+    ///
+    /// Framer is passed to the handlers and must not be used outside of them
+    /// except via `Framer::async_`.
+    #[doc(alias = "nw_framer")]
+    #[doc(alias = "nw_framer_t")]
+    pub Framer(ns::Id)
+);
+
+define_obj_type!(
+    /// Message with framer specific key/value pairs.
+    ///
+    /// Attach it to `nw::ContentCtx` with `set_protocol_metadata`
+    /// to pass values from sender to the framer output handler.
+    #[doc(alias = "nw_framer_message")]
+    #[doc(alias = "nw_framer_message_t")]
+    pub FramerMsg(nw::ProtocolMetadata)
+);
+
+unsafe impl Send for FramerMsg {}
+unsafe impl Sync for FramerMsg {}
+
+define_opts!(
+    #[doc(alias = "nw_framer_create_flags_t")]
+    pub FramerCreateFlags(u32)
+);
+
+impl FramerCreateFlags {
+    #[doc(alias = "NW_FRAMER_CREATE_FLAGS_DEFAULT")]
+    pub const DEFAULT: Self = Self(0x00);
+}
+
+#[doc(alias = "nw_framer_start_result_t")]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[repr(i32)]
+pub enum FramerStartResult {
+    /// The framer is ready to handle data
+    #[doc(alias = "nw_framer_start_result_ready")]
+    Ready = 1,
+
+    /// The framer will call `Framer::mark_ready` once handshake is complete
+    #[doc(alias = "nw_framer_start_result_will_mark_ready")]
+    WillMarkReady = 2,
+}
+
+#[doc(alias = "nw_framer_start_handler_t")]
+pub type FramerStartHandler = blocks::SyncBlock<fn(&mut Framer) -> FramerStartResult>;
+
+/// Returns hint of how many bytes the framer needs to be able to continue parsing
+#[doc(alias = "nw_framer_input_handler_t")]
+pub type FramerInputHandler = blocks::SyncBlock<fn(&mut Framer) -> usize>;
+
+#[doc(alias = "nw_framer_output_handler_t")]
+pub type FramerOutputHandler = blocks::SyncBlock<
+    fn(
+        &mut Framer,
+        /* message */ &mut FramerMsg,
+        /* message_length */ usize,
+        /* is_complete */ bool,
+    ),
+>;
+
+#[doc(alias = "nw_framer_wakeup_handler_t")]
+pub type FramerWakeupHandler = blocks::SyncBlock<fn(&mut Framer)>;
+
+/// Returns `true` if the framer is done and can be released
+#[doc(alias = "nw_framer_stop_handler_t")]
+pub type FramerStopHandler = blocks::SyncBlock<fn(&mut Framer) -> bool>;
+
+#[doc(alias = "nw_framer_cleanup_handler_t")]
+pub type FramerCleanupHandler = blocks::SyncBlock<fn(&mut Framer)>;
+
+#[doc(alias = "nw_framer_parse_completion_t")]
+pub type FramerParseCompletion =
+    blocks::NoEscBlock<fn(/* buffer */ *mut u8, /* buffer_length */ usize, bool) -> usize>;
+
+#[doc(alias = "nw_framer_block_t")]
+pub type FramerBlock = blocks::SyncBlock<fn()>;
+
+impl nw::ProtocolDefinition {
+    #[doc(alias = "nw_framer_create_definition")]
+    #[inline]
+    pub fn with_framer_block(
+        id: &CStr,
+        flags: FramerCreateFlags,
+        start_handler: &mut FramerStartHandler,
+    ) -> arc::R<Self> {
+        unsafe { nw_framer_create_definition(id.as_ptr(), flags, start_handler) }
+    }
+
+    /// Defines custom framer protocol.
+    ///
+    /// `start` is called for every new connection using the protocol.
+    /// Set input and output handlers there.
+    #[doc(alias = "nw_framer_create_definition")]
+    #[inline]
+    pub fn with_framer(
+        id: &CStr,
+        flags: FramerCreateFlags,
+        start: impl FnMut(&mut Framer) -> FramerStartResult + 'static + std::marker::Sync,
+    ) -> arc::R<Self> {
+        let mut block = FramerStartHandler::new1(start);
+        Self::with_framer_block(id, flags, &mut block)
+    }
+}
+
+impl nw::ProtocolOpts {
+    /// Options to add framer to the `nw::ProtocolStack`
+    #[doc(alias = "nw_framer_create_options")]
+    #[inline]
+    pub fn with_framer(framer_definition: &nw::ProtocolDefinition) -> arc::R<Self> {
+        unsafe { nw_framer_create_options(framer_definition) }
+    }
+}
+
+impl Framer {
+    #[doc(alias = "NW_FRAMER_WAKEUP_TIME_FOREVER")]
+    pub const WAKEUP_TIME_FOREVER: u64 = u64::MAX;
+
+    #[doc(alias = "nw_framer_set_input_handler")]
+    #[inline]
+    pub fn set_input_handler_block(&mut self, val: &mut FramerInputHandler) {
+        unsafe { nw_framer_set_input_handler(self, val) }
+    }
+
+    /// Handler is called when new data arrives. Parse it with `parse_input` and
+    /// hand messages to the connection with `deliver_input`.
+    #[doc(alias = "nw_framer_set_input_handler")]
+    #[inline]
+    pub fn set_input_handler(
+        &mut self,
+        val: impl FnMut(&mut Framer) -> usize + 'static + std::marker::Sync,
+    ) {
+        let mut block = FramerInputHandler::new1(val);
+        self.set_input_handler_block(&mut block);
+    }
+
+    #[doc(alias = "nw_framer_set_output_handler")]
+    #[inline]
+    pub fn set_output_handler_block(&mut self, val: &mut FramerOutputHandler) {
+        unsafe { nw_framer_set_output_handler(self, val) }
+    }
+
+    /// Handler is called for every message sent on the connection.
+    /// Write headers with `write_output` and payload with `write_output_no_copy`.
+    #[doc(alias = "nw_framer_set_output_handler")]
+    #[inline]
+    pub fn set_output_handler(
+        &mut self,
+        val: impl FnMut(&mut Framer, &mut FramerMsg, usize, bool) + 'static + std::marker::Sync,
+    ) {
+        let mut block = FramerOutputHandler::new4(val);
+        self.set_output_handler_block(&mut block);
+    }
+
+    #[doc(alias = "nw_framer_set_wakeup_handler")]
+    #[inline]
+    pub fn set_wakeup_handler_block(&mut self, val: &mut FramerWakeupHandler) {
+        unsafe { nw_framer_set_wakeup_handler(self, val) }
+    }
+
+    #[doc(alias = "nw_framer_set_wakeup_handler")]
+    #[inline]
+    pub fn set_wakeup_handler(
+        &mut self,
+        val: impl FnMut(&mut Framer) + 'static + std::marker::Sync,
+    ) {
+        let mut block = FramerWakeupHandler::new1(val);
+        self.set_wakeup_handler_block(&mut block);
+    }
+
+    #[doc(alias = "nw_framer_set_stop_handler")]
+    #[inline]
+    pub fn set_stop_handler_block(&mut self, val: &mut FramerStopHandler) {
+        unsafe { nw_framer_set_stop_handler(self, val) }
+    }
+
+    #[doc(alias = "nw_framer_set_stop_handler")]
+    #[inline]
+    pub fn set_stop_handler(
+        &mut self,
+        val: impl FnMut(&mut Framer) -> bool + 'static + std::marker::Sync,
+    ) {
+        let mut block = FramerStopHandler::new1(val);
+        self.set_stop_handler_block(&mut block);
+    }
+
+    #[doc(alias = "nw_framer_set_cleanup_handler")]
+    #[inline]
+    pub fn set_cleanup_handler_block(&mut self, val: &mut FramerCleanupHandler) {
+        unsafe { nw_framer_set_cleanup_handler(self, val) }
+    }
+
+    #[doc(alias = "nw_framer_set_cleanup_handler")]
+    #[inline]
+    pub fn set_cleanup_handler(
+        &mut self,
+        val: impl FnMut(&mut Framer) + 'static + std::marker::Sync,
+    ) {
+        let mut block = FramerCleanupHandler::new1(val);
+        self.set_cleanup_handler_block(&mut block);
+    }
+
+    #[doc(alias = "nw_framer_mark_ready")]
+    #[inline]
+    pub fn mark_ready(&mut self) {
+        unsafe { nw_framer_mark_ready(self) }
+    }
+
+    #[doc(alias = "nw_framer_mark_failed_with_error")]
+    #[inline]
+    pub fn mark_failed_with_err(&mut self, error_code: c_int) {
+        unsafe { nw_framer_mark_failed_with_error(self, error_code) }
+    }
+
+    #[doc(alias = "nw_framer_prepend_application_protocol")]
+    #[inline]
+    pub fn prepend_app_protocol(&mut self, protocol_opts: &nw::ProtocolOpts) -> bool {
+        unsafe { nw_framer_prepend_application_protocol(self, protocol_opts) }
+    }
+
+    /// Inspects up to `max_len` bytes of input without consuming them.
+    ///
+    /// `parse` receives available bytes (at least `min_incomplete_len` unless input is complete)
+    /// and returns number of bytes to consume.
+    /// Returns `false` if not enough bytes were available.
+    #[doc(alias = "nw_framer_parse_input")]
+    pub fn parse_input(
+        &mut self,
+        min_incomplete_len: usize,
+        max_len: usize,
+        mut parse: impl FnMut(&[u8], bool) -> usize,
+    ) -> bool {
+        let mut f = |ptr: *mut u8, len: usize, is_complete: bool| {
+            let buf = if ptr.is_null() {
+                &[][..]
+            } else {
+                unsafe { std::slice::from_raw_parts(ptr, len) }
+            };
+            parse(buf, is_complete)
+        };
+        let mut block = unsafe { FramerParseCompletion::stack3(&mut f) };
+        unsafe {
+            nw_framer_parse_input(
+                self,
+                min_incomplete_len,
+                max_len,
+                std::ptr::null_mut(),
+                &mut block,
+            )
+        }
+    }
+
+    #[doc(alias = "nw_framer_deliver_input")]
+    #[inline]
+    pub fn deliver_input(&mut self, input: &[u8], msg: &FramerMsg, is_complete: bool) {
+        unsafe { nw_framer_deliver_input(self, input.as_ptr(), input.len(), msg, is_complete) }
+    }
+
+    /// Delivers next `input_len` bytes of input to the connection without copying.
+    /// Returns `false` if the bytes are not available yet.
+    #[doc(alias = "nw_framer_deliver_input_no_copy")]
+    #[inline]
+    pub fn deliver_input_no_copy(
+        &mut self,
+        input_len: usize,
+        msg: &FramerMsg,
+        is_complete: bool,
+    ) -> bool {
+        unsafe { nw_framer_deliver_input_no_copy(self, input_len, msg, is_complete) }
+    }
+
+    #[doc(alias = "nw_framer_pass_through_input")]
+    #[inline]
+    pub fn pass_through_input(&mut self) {
+        unsafe { nw_framer_pass_through_input(self) }
+    }
+
+    /// Inspects output message bytes in output handler.
+    #[doc(alias = "nw_framer_parse_output")]
+    pub fn parse_output(
+        &mut self,
+        min_incomplete_len: usize,
+        max_len: usize,
+        mut parse: impl FnMut(&[u8], bool) -> usize,
+    ) -> bool {
+        let mut f = |ptr: *mut u8, len: usize, is_complete: bool| {
+            let buf = if ptr.is_null() {
+                &[][..]
+            } else {
+                unsafe { std::slice::from_raw_parts(ptr, len) }
+            };
+            parse(buf, is_complete)
+        };
+        let mut block = unsafe { FramerParseCompletion::stack3(&mut f) };
+        unsafe {
+            nw_framer_parse_output(
+                self,
+                min_incomplete_len,
+                max_len,
+                std::ptr::null_mut(),
+                &mut block,
+            )
+        }
+    }
+
+    #[doc(alias = "nw_framer_write_output")]
+    #[inline]
+    pub fn write_output(&mut self, output: &[u8]) {
+        unsafe { nw_framer_write_output(self, output.as_ptr(), output.len()) }
+    }
+
+    #[doc(alias = "nw_framer_write_output_data")]
+    #[inline]
+    pub fn write_output_data(&mut self, output: &dispatch::Data) {
+        unsafe { nw_framer_write_output_data(self, output) }
+    }
+
+    /// Writes next `output_len` bytes of the message being sent without copying.
+    #[doc(alias = "nw_framer_write_output_no_copy")]
+    #[inline]
+    pub fn write_output_no_copy(&mut self, output_len: usize) -> bool {
+        unsafe { nw_framer_write_output_no_copy(self, output_len) }
+    }
+
+    #[doc(alias = "nw_framer_pass_through_output")]
+    #[inline]
+    pub fn pass_through_output(&mut self) {
+        unsafe { nw_framer_pass_through_output(self) }
+    }
+
+    #[doc(alias = "nw_framer_schedule_wakeup")]
+    #[inline]
+    pub fn schedule_wakeup(&mut self, milliseconds: u64) {
+        unsafe { nw_framer_schedule_wakeup(self, milliseconds) }
+    }
+
+    #[doc(alias = "nw_framer_async")]
+    #[inline]
+    pub fn async_block(&mut self, block: &mut FramerBlock) {
+        unsafe { nw_framer_async(self, block) }
+    }
+
+    /// Schedules block on the framer's queue. Use it to call framer methods
+    /// from outside of the handlers.
+    #[doc(alias = "nw_framer_async")]
+    #[inline]
+    pub fn async_(&mut self, block: impl FnMut() + 'static + std::marker::Sync) {
+        let mut block = FramerBlock::new0(block);
+        self.async_block(&mut block);
+    }
+
+    #[doc(alias = "nw_framer_copy_remote_endpoint")]
+    #[inline]
+    pub fn remote_endpoint(&self) -> arc::R<nw::Endpoint> {
+        unsafe { nw_framer_copy_remote_endpoint(self) }
+    }
+
+    #[doc(alias = "nw_framer_copy_local_endpoint")]
+    #[inline]
+    pub fn local_endpoint(&self) -> arc::R<nw::Endpoint> {
+        unsafe { nw_framer_copy_local_endpoint(self) }
+    }
+
+    #[doc(alias = "nw_framer_copy_parameters")]
+    #[inline]
+    pub fn params(&self) -> arc::R<nw::Params> {
+        unsafe { nw_framer_copy_parameters(self) }
+    }
+}
+
+impl FramerMsg {
+    #[doc(alias = "nw_framer_message_create")]
+    #[inline]
+    pub fn new(framer: &Framer) -> arc::R<Self> {
+        unsafe { nw_framer_message_create(framer) }
+    }
+
+    /// Message to send with `nw::ContentCtx::set_protocol_metadata`
+    #[doc(alias = "nw_framer_protocol_create_message")]
+    #[inline]
+    pub fn with_definition(definition: &nw::ProtocolDefinition) -> arc::R<Self> {
+        unsafe { nw_framer_protocol_create_message(definition) }
+    }
+
+    #[doc(alias = "nw_framer_message_set_object_value")]
+    #[inline]
+    pub fn set_obj_value(&mut self, key: &CStr, val: Option<&ns::Id>) {
+        unsafe { nw_framer_message_set_object_value(self, key.as_ptr(), val) }
+    }
+
+    #[doc(alias = "nw_framer_message_copy_object_value")]
+    #[inline]
+    pub fn obj_value(&self, key: &CStr) -> Option<arc::R<ns::Id>> {
+        unsafe { nw_framer_message_copy_object_value(self, key.as_ptr()) }
+    }
+}
+
+#[link(name = "Network", kind = "framework")]
+extern "C" {
+    fn nw_framer_create_definition(
+        identifier: *const c_char,
+        flags: FramerCreateFlags,
+        start_handler: &mut FramerStartHandler,
+    ) -> arc::R<nw::ProtocolDefinition>;
+    fn nw_framer_create_options(
+        framer_definition: &nw::ProtocolDefinition,
+    ) -> arc::R<nw::ProtocolOpts>;
+
+    fn nw_framer_set_input_handler(framer: &mut Framer, input_handler: &mut FramerInputHandler);
+    fn nw_framer_set_output_handler(framer: &mut Framer, output_handler: &mut FramerOutputHandler);
+    fn nw_framer_set_wakeup_handler(framer: &mut Framer, wakeup_handler: &mut FramerWakeupHandler);
+    fn nw_framer_set_stop_handler(framer: &mut Framer, stop_handler: &mut FramerStopHandler);
+    fn nw_framer_set_cleanup_handler(
+        framer: &mut Framer,
+        cleanup_handler: &mut FramerCleanupHandler,
+    );
+
+    fn nw_framer_mark_ready(framer: &mut Framer);
+    fn nw_framer_mark_failed_with_error(framer: &mut Framer, error_code: c_int);
+    fn nw_framer_prepend_application_protocol(
+        framer: &mut Framer,
+        protocol_options: &nw::ProtocolOpts,
+    ) -> bool;
+
+    fn nw_framer_parse_input(
+        framer: &mut Framer,
+        minimum_incomplete_length: usize,
+        maximum_length: usize,
+        temp_buffer: *mut u8,
+        parse: &mut FramerParseCompletion,
+    ) -> bool;
+    fn nw_framer_deliver_input(
+        framer: &mut Framer,
+        input_buffer: *const u8,
+        input_length: usize,
+        message: &FramerMsg,
+        is_complete: bool,
+    );
+    fn nw_framer_deliver_input_no_copy(
+        framer: &mut Framer,
+        input_length: usize,
+        message: &FramerMsg,
+        is_complete: bool,
+    ) -> bool;
+    fn nw_framer_pass_through_input(framer: &mut Framer);
+
+    fn nw_framer_parse_output(
+        framer: &mut Framer,
+        minimum_incomplete_length: usize,
+        maximum_length: usize,
+        temp_buffer: *mut u8,
+        parse: &mut FramerParseCompletion,
+    ) -> bool;
+    fn nw_framer_write_output(framer: &mut Framer, output_buffer: *const u8, output_length: usize);
+    fn nw_framer_write_output_data(framer: &mut Framer, output_data: &dispatch::Data);
+    fn nw_framer_write_output_no_copy(framer: &mut Framer, output_length: usize) -> bool;
+    fn nw_framer_pass_through_output(framer: &mut Framer);
+
+    fn nw_framer_schedule_wakeup(framer: &mut Framer, milliseconds: u64);
+    fn nw_framer_async(framer: &mut Framer, async_block: &mut FramerBlock);
+
+    fn nw_framer_copy_remote_endpoint(framer: &Framer) -> arc::R<nw::Endpoint>;
+    fn nw_framer_copy_local_endpoint(framer: &Framer) -> arc::R<nw::Endpoint>;
+    fn nw_framer_copy_parameters(framer: &Framer) -> arc::R<nw::Params>;
+
+    fn nw_framer_message_create(framer: &Framer) -> arc::R<FramerMsg>;
+    fn nw_framer_protocol_create_message(definition: &nw::ProtocolDefinition) -> arc::R<FramerMsg>;
+    fn nw_framer_message_set_object_value(
+        message: &mut FramerMsg,
+        key: *const c_char,
+        value: Option<&ns::Id>,
+    );
+    fn nw_framer_message_copy_object_value(
+        message: &FramerMsg,
+        key: *const c_char,
+    ) -> Option<arc::R<ns::Id>>;
+}
+
+#[cfg(all(test, feature = "async"))]
+mod tests {
+    use std::{ffi::CString, sync::mpsc};
+
+    use crate::{arc, dispatch, nw};
+
+    /// 4 bytes big endian length followed by payload
+    fn length_prefixed() -> arc::R<nw::ProtocolDefinition> {
+        nw::ProtocolDefinition::with_framer(
+            c"length-prefixed",
+            nw::FramerCreateFlags::DEFAULT,
+            |framer| {
+                framer.set_input_handler(|framer| loop {
+                    let mut len = 0usize;
+                    let parsed = framer.parse_input(4, 4, |buf, _is_complete| {
+                        if buf.len() < 4 {
+                            return 0;
+                        }
+                        len = u32::from_be_bytes([buf[0], buf[1], buf[2], buf[3]]) as usize;
+                        4
+                    });
+                    if !parsed {
+                        return 4;
+                    }
+                    let msg = nw::FramerMsg::new(framer);
+                    if !framer.deliver_input_no_copy(len, &msg, true) {
+                        return 0;
+                    }
+                });
+                framer.set_output_handler(|framer, _msg, len, _is_complete| {
+                    framer.write_output(&(len as u32).to_be_bytes());
+                    framer.write_output_no_copy(len);
+                });
+                nw::FramerStartResult::Ready
+            },
+        )
+    }
+
+    fn params(app_protocol: &nw::ProtocolOpts) -> arc::R<nw::Params> {
+        let params = nw::Params::tcp();
+        params
+            .default_protocol_stack()
+            .prepend_app_protocol(app_protocol);
+        params
+    }
+
+    fn client_server(
+        params: &nw::Params,
+        queue: &dispatch::Queue,
+    ) -> (arc::R<nw::Listener>, nw::Messages, nw::Messages) {
+        let (tx, rx) = mpsc::channel();
+        let mut listener = nw::Listener::with_port(c"0", params).unwrap();
+        let mut on_conn = nw::ListenerNewConnectionHandler::new1(move |conn: &nw::Connection| {
+            tx.send(conn.retained()).unwrap();
+        });
+        listener.set_new_connection_handler(Some(&mut on_conn));
+        let sema = dispatch::Semaphore::new(0);
+        let signal = sema.clone();
+        let mut on_state = nw::ListenerStateChangedHandler::new2(
+            move |state: nw::ListenerState, _err: Option<&nw::Error>| {
+                if let nw::ListenerState::Ready = state {
+                    signal.signal();
+                }
+            },
+        );
+        listener.set_state_changed_handler(Some(&mut on_state));
+        listener.start(queue);
+        sema.wait_forever();
+
+        let port = CString::new(listener.port().to_string()).unwrap();
+        let endpoint = nw::Endpoint::with_host(c"127.0.0.1", &port).unwrap();
+        let conn = nw::Connection::with_endpoint(&endpoint, params).unwrap();
+        let client = dispatch::block_on(nw::Messages::start(conn, queue)).unwrap();
+        let server = dispatch::block_on(nw::Messages::start(rx.recv().unwrap(), queue)).unwrap();
+        (listener, client, server)
+    }
+
+    #[test]
+    fn framer() {
+        let queue = dispatch::Queue::new();
+        let definition = length_prefixed();
+        let opts = nw::ProtocolOpts::with_framer(&definition);
+        let (mut listener, client, server) = client_server(&params(&opts), &queue);
+
+        dispatch::block_on(async {
+            let mut ctx = nw::ContentCtx::with_id(c"msg");
+            ctx.set_protocol_metadata(&nw::FramerMsg::with_definition(&definition));
+            for payload in [&b"hello"[..], b"", &[7u8; 100_000]] {
+                let data = dispatch::Data::copy_from_slice(payload);
+                client.send_with_ctx(Some(&data), &ctx, true).await.unwrap();
+            }
+
+            let msg = server.recv().await.unwrap();
+            assert!(msg.is_complete);
+            assert_eq!(msg.to_vec(), b"hello");
+
+            let msg = server.recv().await.unwrap();
+            assert!(msg.is_empty());
+
+            let msg = server.recv().await.unwrap();
+            assert_eq!(msg.len(), 100_000);
+            assert!(msg
+                .ctx
+                .unwrap()
+                .protocol_metadata(&definition)
+                .unwrap()
+                .is_framer_msg());
+        });
+
+        listener.cancel();
+    }
+
+    #[test]
+    fn websocket() {
+        let queue = dispatch::Queue::new();
+        let mut opts = nw::WsOpts::new();
+        opts.set_auto_reply_ping(true);
+        opts.add_subprotocol(c"chat");
+        let (mut listener, client, server) = client_server(&params(&opts), &queue);
+
+        dispatch::block_on(async {
+            let mut ctx = nw::ContentCtx::with_id(c"text");
+            ctx.set_protocol_metadata(&nw::WsMetadata::with_opcode(nw::WsOpcode::Text));
+            let data = dispatch::Data::copy_from_slice(b"hi");
+            client.send_with_ctx(Some(&data), &ctx, true).await.unwrap();
+
+            let msg = server.recv().await.unwrap();
+            assert_eq!(msg.to_vec(), b"hi");
+            let meta = msg
+                .ctx
+                .unwrap()
+                .protocol_metadata(&nw::ProtocolDefinition::ws())
+                .unwrap();
+            assert_eq!(meta.as_ws().unwrap().opcode(), nw::WsOpcode::Text);
+        });
+
+        listener.cancel();
+    }
+}
@@ -38,6 +38,22 @@ impl Params {
         unsafe { Self::create_secure_tcp(cfg1, cfg2).unwrap_unchecked() }
     }
 
+    /// TCP without TLS
+    #[inline]
+    pub fn tcp() -> arc::R<Self> {
+        let tls = Self::disable_protocol();
+        let tcp = Self::default_cfg();
+        unsafe { Self::create_secure_tcp(tls, tcp).unwrap_unchecked() }
+    }
+
+    /// UDP without DTLS
+    #[inline]
+    pub fn udp() -> arc::R<Self> {
+        let dtls = Self::disable_protocol();
+        let udp = Self::default_cfg();
+        unsafe { Self::create_secure_udp(dtls, udp).unwrap_unchecked() }
+    }
+
     #[doc(alias = "nw_parameters_create_secure_udp")]
     #[inline]
     pub fn create_secure_udp(
@@ -68,6 +84,12 @@ impl Params {
         unsafe { nw_parameters_create_application_service() }
     }
 
+    #[doc(alias = "nw_parameters_copy_default_protocol_stack")]
+    #[inline]
+    pub fn default_protocol_stack(&self) -> arc::R<ProtocolStack> {
+        unsafe { nw_parameters_copy_default_protocol_stack(self) }
+    }
+
     #[doc(alias = "NW_PARAMETERS_DEFAULT_CONFIGURATION")]
     #[inline]
     pub fn default_cfg() -> &'static mut ParamsCfgProtocolBlock {
@@ -87,6 +109,39 @@ define_obj_type!(
     pub ProtocolStack(ns::Id)
 );
 
+impl ProtocolStack {
+    /// Prepends application protocol (framer, WebSocket, TLS) on top of the stack
+    #[doc(alias = "nw_protocol_stack_prepend_application_protocol")]
+    #[inline]
+    pub fn prepend_app_protocol(&mut self, protocol: &nw::ProtocolOpts) {
+        unsafe { nw_protocol_stack_prepend_application_protocol(self, protocol) }
+    }
+
+    #[doc(alias = "nw_protocol_stack_clear_application_protocols")]
+    #[inline]
+    pub fn clear_app_protocols(&mut self) {
+        unsafe { nw_protocol_stack_clear_application_protocols(self) }
+    }
+
+    #[doc(alias = "nw_protocol_stack_copy_transport_protocol")]
+    #[inline]
+    pub fn transport_protocol(&self) -> Option<arc::R<nw::ProtocolOpts>> {
+        unsafe { nw_protocol_stack_copy_transport_protocol(self) }
+    }
+
+    #[doc(alias = "nw_protocol_stack_set_transport_protocol")]
+    #[inline]
+    pub fn set_transport_protocol(&mut self, val: &nw::ProtocolOpts) {
+        unsafe { nw_protocol_stack_set_transport_protocol(self, val) }
+    }
+
+    #[doc(alias = "nw_protocol_stack_copy_internet_protocol")]
+    #[inline]
+    pub fn internet_protocol(&self) -> Option<arc::R<nw::ProtocolOpts>> {
+        unsafe { nw_protocol_stack_copy_internet_protocol(self) }
+    }
+}
+
 #[link(name = "Network", kind = "framework")]
 extern "C" {
     fn nw_parameters_create() -> arc::R<Params>;
@@ -113,6 +168,23 @@ extern "C" {
 
     fn nw_parameters_create_application_service() -> arc::R<Params>;
 
+    fn nw_parameters_copy_default_protocol_stack(parameters: &Params) -> arc::R<ProtocolStack>;
+    fn nw_protocol_stack_prepend_application_protocol(
+        stack: &mut ProtocolStack,
+        protocol: &nw::ProtocolOpts,
+    );
+    fn nw_protocol_stack_clear_application_protocols(stack: &mut ProtocolStack);
+    fn nw_protocol_stack_copy_transport_protocol(
+        stack: &ProtocolStack,
+    ) -> Option<arc::R<nw::ProtocolOpts>>;
+    fn nw_protocol_stack_set_transport_protocol(
+        stack: &mut ProtocolStack,
+        protocol: &nw::ProtocolOpts,
+    );
+    fn nw_protocol_stack_copy_internet_protocol(
+        stack: &ProtocolStack,
+    ) -> Option<arc::R<nw::ProtocolOpts>>;
+
     static mut _nw_parameters_configure_protocol_default_configuration:
         &'static mut ParamsCfgProtocolBlock;
     static mut _nw_parameters_configure_protocol_disable: &'static mut ParamsCfgProtocolBlock;
@@ -1,4 +1,6 @@
-use crate::{define_obj_type, ns};
+use std::ffi::{c_char, CStr};
+
+use crate::{arc, define_obj_type, ns};
 
 define_obj_type!(
     #[doc(alias = "nw_protocol_definition")]
@@ -6,6 +8,9 @@ define_obj_type!(
     pub ProtocolDefinition(ns::Id)
 );
 
+unsafe impl Send for ProtocolDefinition {}
+unsafe impl Sync for ProtocolDefinition {}
+
 define_obj_type!(
     #[doc(alias = "nw_protocol_options")]
     #[doc(alias = "nw_protocol_options_t")]
@@ -17,3 +22,659 @@ define_obj_type!(
     #[doc(alias = "nw_protocol_metadata_t")]
     pub ProtocolMetadata(ns::Id)
 );
+
+impl ProtocolDefinition {
+    #[doc(alias = "nw_protocol_definition_is_equal")]
+    #[inline]
+    pub fn is_equal(&self, other: &Self) -> bool {
+        unsafe { nw_protocol_definition_is_equal(self, other) }
+    }
+
+    #[doc(alias = "nw_protocol_copy_tcp_definition")]
+    #[inline]
+    pub fn tcp() -> arc::R<Self> {
+        unsafe { nw_protocol_copy_tcp_definition() }
+    }
+
+    #[doc(alias = "nw_protocol_copy_udp_definition")]
+    #[inline]
+    pub fn udp() -> arc::R<Self> {
+        unsafe { nw_protocol_copy_udp_definition() }
+    }
+
+    #[doc(alias = "nw_protocol_copy_tls_definition")]
+    #[inline]
+    pub fn tls() -> arc::R<Self> {
+        unsafe { nw_protocol_copy_tls_definition() }
+    }
+
+    #[doc(alias = "nw_protocol_copy_ws_definition")]
+    #[inline]
+    pub fn ws() -> arc::R<Self> {
+        unsafe { nw_protocol_copy_ws_definition() }
+    }
+}
+
+impl ProtocolOpts {
+    #[doc(alias = "nw_protocol_options_copy_definition")]
+    #[inline]
+    pub fn definition(&self) -> arc::R<ProtocolDefinition> {
+        unsafe { nw_protocol_options_copy_definition(self) }
+    }
+}
+
+impl ProtocolMetadata {
+    #[doc(alias = "nw_protocol_metadata_copy_definition")]
+    #[inline]
+    pub fn definition(&self) -> arc::R<ProtocolDefinition> {
+        unsafe { nw_protocol_metadata_copy_definition(self) }
+    }
+
+    #[doc(alias = "nw_protocol_metadata_is_tcp")]
+    #[inline]
+    pub fn is_tcp(&self) -> bool {
+        unsafe { nw_protocol_metadata_is_tcp(self) }
+    }
+
+    #[doc(alias = "nw_protocol_metadata_is_udp")]
+    #[inline]
+    pub fn is_udp(&self) -> bool {
+        unsafe { nw_protocol_metadata_is_udp(self) }
+    }
+
+    #[doc(alias = "nw_protocol_metadata_is_tls")]
+    #[inline]
+    pub fn is_tls(&self) -> bool {
+        unsafe { nw_protocol_metadata_is_tls(self) }
+    }
+
+    #[doc(alias = "nw_protocol_metadata_is_ws")]
+    #[inline]
+    pub fn is_ws(&self) -> bool {
+        unsafe { nw_protocol_metadata_is_ws(self) }
+    }
+
+    #[doc(alias = "nw_protocol_metadata_is_framer_message")]
+    #[inline]
+    pub fn is_framer_msg(&self) -> bool {
+        unsafe { nw_protocol_metadata_is_framer_message(self) }
+    }
+
+    #[inline]
+    pub fn as_tcp(&self) -> Option<&TcpMetadata> {
+        if self.is_tcp() {
+            Some(unsafe { std::mem::transmute(self) })
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    pub fn as_ws(&self) -> Option<&WsMetadata> {
+        if self.is_ws() {
+            Some(unsafe { std::mem::transmute(self) })
+        } else {
+            None
+        }
+    }
+}
+
+define_obj_type!(
+    #[doc(alias = "nw_tcp_options")]
+    pub TcpOpts(ProtocolOpts)
+);
+
+impl TcpOpts {
+    #[doc(alias = "nw_tcp_create_options")]
+    #[inline]
+    pub fn new() -> arc::R<Self> {
+        unsafe { nw_tcp_create_options() }
+    }
+
+    /// Options obtained in `nw::ParamsCfgProtocolBlock` for TCP.
+    ///
+    /// # Safety
+    ///
+    /// `opts` must be TCP options.
+    #[inline]
+    pub unsafe fn with_opts_mut(opts: &mut ProtocolOpts) -> &mut Self {
+        std::mem::transmute(opts)
+    }
+
+    #[doc(alias = "nw_tcp_options_set_no_delay")]
+    #[inline]
+    pub fn set_no_delay(&mut self, val: bool) {
+        unsafe { nw_tcp_options_set_no_delay(self, val) }
+    }
+
+    #[doc(alias = "nw_tcp_options_set_no_push")]
+    #[inline]
+    pub fn set_no_push(&mut self, val: bool) {
+        unsafe { nw_tcp_options_set_no_push(self, val) }
+    }
+
+    #[doc(alias = "nw_tcp_options_set_no_options")]
+    #[inline]
+    pub fn set_no_opts(&mut self, val: bool) {
+        unsafe { nw_tcp_options_set_no_options(self, val) }
+    }
+
+    #[doc(alias = "nw_tcp_options_set_enable_keepalive")]
+    #[inline]
+    pub fn set_keepalive(&mut self, val: bool) {
+        unsafe { nw_tcp_options_set_enable_keepalive(self, val) }
+    }
+
+    #[doc(alias = "nw_tcp_options_set_keepalive_count")]
+    #[inline]
+    pub fn set_keepalive_count(&mut self, val: u32) {
+        unsafe { nw_tcp_options_set_keepalive_count(self, val) }
+    }
+
+    /// Idle time in seconds
+    #[doc(alias = "nw_tcp_options_set_keepalive_idle_time")]
+    #[inline]
+    pub fn set_keepalive_idle_time(&mut self, val: u32) {
+        unsafe { nw_tcp_options_set_keepalive_idle_time(self, val) }
+    }
+
+    /// Interval in seconds
+    #[doc(alias = "nw_tcp_options_set_keepalive_interval")]
+    #[inline]
+    pub fn set_keepalive_interval(&mut self, val: u32) {
+        unsafe { nw_tcp_options_set_keepalive_interval(self, val) }
+    }
+
+    #[doc(alias = "nw_tcp_options_set_maximum_segment_size")]
+    #[inline]
+    pub fn set_max_segment_size(&mut self, val: u32) {
+        unsafe { nw_tcp_options_set_maximum_segment_size(self, val) }
+    }
+
+    /// Timeout in seconds
+    #[doc(alias = "nw_tcp_options_set_connection_timeout")]
+    #[inline]
+    pub fn set_connection_timeout(&mut self, val: u32) {
+        unsafe { nw_tcp_options_set_connection_timeout(self, val) }
+    }
+
+    #[doc(alias = "nw_tcp_options_set_persist_timeout")]
+    #[inline]
+    pub fn set_persist_timeout(&mut self, val: u32) {
+        unsafe { nw_tcp_options_set_persist_timeout(self, val) }
+    }
+
+    #[doc(alias = "nw_tcp_options_set_retransmit_connection_drop_time")]
+    #[inline]
+    pub fn set_retransmit_connection_drop_time(&mut self, val: u32) {
+        unsafe { nw_tcp_options_set_retransmit_connection_drop_time(self, val) }
+    }
+
+    #[doc(alias = "nw_tcp_options_set_retransmit_fin_drop")]
+    #[inline]
+    pub fn set_retransmit_fin_drop(&mut self, val: bool) {
+        unsafe { nw_tcp_options_set_retransmit_fin_drop(self, val) }
+    }
+
+    #[doc(alias = "nw_tcp_options_set_disable_ack_stretching")]
+    #[inline]
+    pub fn set_disable_ack_stretching(&mut self, val: bool) {
+        unsafe { nw_tcp_options_set_disable_ack_stretching(self, val) }
+    }
+
+    #[doc(alias = "nw_tcp_options_set_enable_fast_open")]
+    #[inline]
+    pub fn set_fast_open(&mut self, val: bool) {
+        unsafe { nw_tcp_options_set_enable_fast_open(self, val) }
+    }
+
+    #[doc(alias = "nw_tcp_options_set_disable_ecn")]
+    #[inline]
+    pub fn set_disable_ecn(&mut self, val: bool) {
+        unsafe { nw_tcp_options_set_disable_ecn(self, val) }
+    }
+}
+
+define_obj_type!(
+    #[doc(alias = "nw_tcp_metadata")]
+    pub TcpMetadata(ProtocolMetadata)
+);
+
+impl TcpMetadata {
+    #[doc(alias = "nw_tcp_get_available_receive_buffer")]
+    #[inline]
+    pub fn available_recv_buf(&self) -> u32 {
+        unsafe { nw_tcp_get_available_receive_buffer(self) }
+    }
+
+    #[doc(alias = "nw_tcp_get_available_send_buffer")]
+    #[inline]
+    pub fn available_send_buf(&self) -> u32 {
+        unsafe { nw_tcp_get_available_send_buffer(self) }
+    }
+}
+
+define_obj_type!(
+    #[doc(alias = "nw_udp_options")]
+    pub UdpOpts(ProtocolOpts)
+);
+
+impl UdpOpts {
+    #[doc(alias = "nw_udp_create_options")]
+    #[inline]
+    pub fn new() -> arc::R<Self> {
+        unsafe { nw_udp_create_options() }
+    }
+
+    /// Options obtained in `nw::ParamsCfgProtocolBlock` for UDP.
+    ///
+    /// # Safety
+    ///
+    /// `opts` must be UDP options.
+    #[inline]
+    pub unsafe fn with_opts_mut(opts: &mut ProtocolOpts) -> &mut Self {
+        std::mem::transmute(opts)
+    }
+
+    #[doc(alias = "nw_udp_options_set_prefer_no_checksum")]
+    #[inline]
+    pub fn set_prefer_no_checksum(&mut self, val: bool) {
+        unsafe { nw_udp_options_set_prefer_no_checksum(self, val) }
+    }
+}
+
+#[doc(alias = "tls_protocol_version_t")]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
+#[repr(transparent)]
+pub struct TlsVersion(pub u16);
+
+impl TlsVersion {
+    #[doc(alias = "tls_protocol_version_TLSv10")]
+    pub const V10: Self = Self(0x0301);
+
+    #[doc(alias = "tls_protocol_version_TLSv11")]
+    pub const V11: Self = Self(0x0302);
+
+    #[doc(alias = "tls_protocol_version_TLSv12")]
+    pub const V12: Self = Self(0x0303);
+
+    #[doc(alias = "tls_protocol_version_TLSv13")]
+    pub const V13: Self = Self(0x0304);
+
+    #[doc(alias = "tls_protocol_version_DTLSv10")]
+    pub const DTLS_V10: Self = Self(0xfeff);
+
+    #[doc(alias = "tls_protocol_version_DTLSv12")]
+    pub const DTLS_V12: Self = Self(0xfefd);
+}
+
+define_obj_type!(
+    #[doc(alias = "sec_protocol_options")]
+    #[doc(alias = "sec_protocol_options_t")]
+    pub SecProtocolOpts(ns::Id)
+);
+
+impl SecProtocolOpts {
+    /// Adds ALPN value
+    #[doc(alias = "sec_protocol_options_add_tls_application_protocol")]
+    #[inline]
+    pub fn add_tls_app_protocol(&mut self, val: &CStr) {
+        unsafe { sec_protocol_options_add_tls_application_protocol(self, val.as_ptr()) }
+    }
+
+    #[doc(alias = "sec_protocol_options_set_min_tls_protocol_version")]
+    #[inline]
+    pub fn set_min_tls_version(&mut self, val: TlsVersion) {
+        unsafe { sec_protocol_options_set_min_tls_protocol_version(self, val) }
+    }
+
+    #[doc(alias = "sec_protocol_options_set_max_tls_protocol_version")]
+    #[inline]
+    pub fn set_max_tls_version(&mut self, val: TlsVersion) {
+        unsafe { sec_protocol_options_set_max_tls_protocol_version(self, val) }
+    }
+
+    #[doc(alias = "sec_protocol_options_set_tls_server_name")]
+    #[inline]
+    pub fn set_tls_server_name(&mut self, val: &CStr) {
+        unsafe { sec_protocol_options_set_tls_server_name(self, val.as_ptr()) }
+    }
+
+    #[doc(alias = "sec_protocol_options_set_peer_authentication_required")]
+    #[inline]
+    pub fn set_peer_authentication_required(&mut self, val: bool) {
+        unsafe { sec_protocol_options_set_peer_authentication_required(self, val) }
+    }
+
+    #[doc(alias = "sec_protocol_options_set_tls_resumption_enabled")]
+    #[inline]
+    pub fn set_tls_resumption(&mut self, val: bool) {
+        unsafe { sec_protocol_options_set_tls_resumption_enabled(self, val) }
+    }
+
+    #[doc(alias = "sec_protocol_options_set_tls_tickets_enabled")]
+    #[inline]
+    pub fn set_tls_tickets(&mut self, val: bool) {
+        unsafe { sec_protocol_options_set_tls_tickets_enabled(self, val) }
+    }
+
+    #[doc(alias = "sec_protocol_options_set_tls_false_start_enabled")]
+    #[inline]
+    pub fn set_tls_false_start(&mut self, val: bool) {
+        unsafe { sec_protocol_options_set_tls_false_start_enabled(self, val) }
+    }
+}
+
+define_obj_type!(
+    #[doc(alias = "nw_tls_options")]
+    pub TlsOpts(ProtocolOpts)
+);
+
+impl TlsOpts {
+    #[doc(alias = "nw_tls_create_options")]
+    #[inline]
+    pub fn new() -> arc::R<Self> {
+        unsafe { nw_tls_create_options() }
+    }
+
+    /// Options obtained in `nw::ParamsCfgProtocolBlock` for TLS.
+    ///
+    /// # Safety
+    ///
+    /// `opts` must be TLS options.
+    #[inline]
+    pub unsafe fn with_opts_mut(opts: &mut ProtocolOpts) -> &mut Self {
+        std::mem::transmute(opts)
+    }
+
+    #[doc(alias = "nw_tls_copy_sec_protocol_options")]
+    #[inline]
+    pub fn sec_protocol_opts(&self) -> arc::R<SecProtocolOpts> {
+        unsafe { nw_tls_copy_sec_protocol_options(self) }
+    }
+}
+
+#[doc(alias = "nw_ws_version_t")]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[repr(i32)]
+pub enum WsVersion {
+    #[doc(alias = "nw_ws_version_invalid")]
+    Invalid = 0,
+    #[doc(alias = "nw_ws_version_13")]
+    V13 = 1,
+}
+
+#[doc(alias = "nw_ws_opcode_t")]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[repr(i32)]
+pub enum WsOpcode {
+    #[doc(alias = "nw_ws_opcode_invalid")]
+    Invalid = -1,
+    #[doc(alias = "nw_ws_opcode_cont")]
+    Cont = 0x0,
+    #[doc(alias = "nw_ws_opcode_text")]
+    Text = 0x1,
+    #[doc(alias = "nw_ws_opcode_binary")]
+    Binary = 0x2,
+    #[doc(alias = "nw_ws_opcode_close")]
+    Close = 0x8,
+    #[doc(alias = "nw_ws_opcode_ping")]
+    Ping = 0x9,
+    #[doc(alias = "nw_ws_opcode_pong")]
+    Pong = 0xA,
+}
+
+#[doc(alias = "nw_ws_close_code_t")]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[repr(transparent)]
+pub struct WsCloseCode(pub i32);
+
+impl WsCloseCode {
+    #[doc(alias = "nw_ws_close_code_normal_closure")]
+    pub const NORMAL_CLOSURE: Self = Self(1000);
+    #[doc(alias = "nw_ws_close_code_going_away")]
+    pub const GOING_AWAY: Self = Self(1001);
+    #[doc(alias = "nw_ws_close_code_protocol_error")]
+    pub const PROTOCOL_ERROR: Self = Self(1002);
+    #[doc(alias = "nw_ws_close_code_unsupported_data")]
+    pub const UNSUPPORTED_DATA: Self = Self(1003);
+    #[doc(alias = "nw_ws_close_code_no_status_received")]
+    pub const NO_STATUS_RECEIVED: Self = Self(1005);
+    #[doc(alias = "nw_ws_close_code_abnormal_closure")]
+    pub const ABNORMAL_CLOSURE: Self = Self(1006);
+    #[doc(alias = "nw_ws_close_code_invalid_frame_payload_data")]
+    pub const INVALID_FRAME_PAYLOAD_DATA: Self = Self(1007);
+    #[doc(alias = "nw_ws_close_code_policy_violation")]
+    pub const POLICY_VIOLATION: Self = Self(1008);
+    #[doc(alias = "nw_ws_close_code_message_too_big")]
+    pub const MESSAGE_TOO_BIG: Self = Self(1009);
+    #[doc(alias = "nw_ws_close_code_mandatory_extension")]
+    pub const MANDATORY_EXTENSION: Self = Self(1010);
+    #[doc(alias = "nw_ws_close_code_internal_server_error")]
+    pub const INTERNAL_SERVER_ERROR: Self = Self(1011);
+    #[doc(alias = "nw_ws_close_code_tls_handshake")]
+    pub const TLS_HANDSHAKE: Self = Self(1015);
+}
+
+define_obj_type!(
+    #[doc(alias = "nw_ws_options")]
+    pub WsOpts(ProtocolOpts)
+);
+
+impl WsOpts {
+    #[doc(alias = "nw_ws_create_options")]
+    #[inline]
+    pub fn with_version(version: WsVersion) -> arc::R<Self> {
+        unsafe { nw_ws_create_options(version) }
+    }
+
+    #[inline]
+    pub fn new() -> arc::R<Self> {
+        Self::with_version(WsVersion::V13)
+    }
+
+    #[doc(alias = "nw_ws_options_add_additional_header")]
+    #[inline]
+    pub fn add_additional_header(&mut self, name: &CStr, value: &CStr) {
+        unsafe { nw_ws_options_add_additional_header(self, name.as_ptr(), value.as_ptr()) }
+    }
+
+    #[doc(alias = "nw_ws_options_add_subprotocol")]
+    #[inline]
+    pub fn add_subprotocol(&mut self, val: &CStr) {
+        unsafe { nw_ws_options_add_subprotocol(self, val.as_ptr()) }
+    }
+
+    /// Automatically reply to pings with pongs
+    #[doc(alias = "nw_ws_options_set_auto_reply_ping")]
+    #[inline]
+    pub fn set_auto_reply_ping(&mut self, val: bool) {
+        unsafe { nw_ws_options_set_auto_reply_ping(self, val) }
+    }
+
+    #[doc(alias = "nw_ws_options_set_maximum_message_size")]
+    #[inline]
+    pub fn set_max_msg_size(&mut self, val: usize) {
+        unsafe { nw_ws_options_set_maximum_message_size(self, val) }
+    }
+
+    #[doc(alias = "nw_ws_options_set_skip_handshake")]
+    #[inline]
+    pub fn set_skip_handshake(&mut self, val: bool) {
+        unsafe { nw_ws_options_set_skip_handshake(self, val) }
+    }
+}
+
+define_obj_type!(
+    #[doc(alias = "nw_ws_metadata")]
+    pub WsMetadata(ProtocolMetadata)
+);
+
+unsafe impl Send for WsMetadata {}
+unsafe impl Sync for WsMetadata {}
+
+impl WsMetadata {
+    #[doc(alias = "nw_ws_create_metadata")]
+    #[inline]
+    pub fn with_opcode(opcode: WsOpcode) -> arc::R<Self> {
+        unsafe { nw_ws_create_metadata(opcode) }
+    }
+
+    #[doc(alias = "nw_ws_metadata_get_opcode")]
+    #[inline]
+    pub fn opcode(&self) -> WsOpcode {
+        unsafe { nw_ws_metadata_get_opcode(self) }
+    }
+
+    #[doc(alias = "nw_ws_metadata_get_close_code")]
+    #[inline]
+    pub fn close_code(&self) -> WsCloseCode {
+        unsafe { nw_ws_metadata_get_close_code(self) }
+    }
+
+    #[doc(alias = "nw_ws_metadata_set_close_code")]
+    #[inline]
+    pub fn set_close_code(&mut self, val: WsCloseCode) {
+        unsafe { nw_ws_metadata_set_close_code(self, val) }
+    }
+}
+
+#[link(name = "Network", kind = "framework")]
+extern "C" {
+    fn nw_protocol_definition_is_equal(
+        definition1: &ProtocolDefinition,
+        definition2: &ProtocolDefinition,
+    ) -> bool;
+    fn nw_protocol_copy_tcp_definition() -> arc::R<ProtocolDefinition>;
+    fn nw_protocol_copy_udp_definition() -> arc::R<ProtocolDefinition>;
+    fn nw_protocol_copy_tls_definition() -> arc::R<ProtocolDefinition>;
+    fn nw_protocol_copy_ws_definition() -> arc::R<ProtocolDefinition>;
+
+    fn nw_protocol_options_copy_definition(options: &ProtocolOpts) -> arc::R<ProtocolDefinition>;
+    fn nw_protocol_metadata_copy_definition(
+        metadata: &ProtocolMetadata,
+    ) -> arc::R<ProtocolDefinition>;
+
+    fn nw_protocol_metadata_is_tcp(metadata: &ProtocolMetadata) -> bool;
+    fn nw_protocol_metadata_is_udp(metadata: &ProtocolMetadata) -> bool;
+    fn nw_protocol_metadata_is_tls(metadata: &ProtocolMetadata) -> bool;
+    fn nw_protocol_metadata_is_ws(metadata: &ProtocolMetadata) -> bool;
+    fn nw_protocol_metadata_is_framer_message(metadata: &ProtocolMetadata) -> bool;
+
+    fn nw_tcp_create_options() -> arc::R<TcpOpts>;
+    fn nw_tcp_options_set_no_delay(options: &mut TcpOpts, no_delay: bool);
+    fn nw_tcp_options_set_no_push(options: &mut TcpOpts, no_push: bool);
+    fn nw_tcp_options_set_no_options(options: &mut TcpOpts, no_options: bool);
+    fn nw_tcp_options_set_enable_keepalive(options: &mut TcpOpts, enable_keepalive: bool);
+    fn nw_tcp_options_set_keepalive_count(options: &mut TcpOpts, keepalive_count: u32);
+    fn nw_tcp_options_set_keepalive_idle_time(options: &mut TcpOpts, keepalive_idle_time: u32);
+    fn nw_tcp_options_set_keepalive_interval(options: &mut TcpOpts, keepalive_interval: u32);
+    fn nw_tcp_options_set_maximum_segment_size(options: &mut TcpOpts, maximum_segment_size: u32);
+    fn nw_tcp_options_set_connection_timeout(options: &mut TcpOpts, connection_timeout: u32);
+    fn nw_tcp_options_set_persist_timeout(options: &mut TcpOpts, persist_timeout: u32);
+    fn nw_tcp_options_set_retransmit_connection_drop_time(
+        options: &mut TcpOpts,
+        retransmit_connection_drop_time: u32,
+    );
+    fn nw_tcp_options_set_retransmit_fin_drop(options: &mut TcpOpts, retransmit_fin_drop: bool);
+    fn nw_tcp_options_set_disable_ack_stretching(
+        options: &mut TcpOpts,
+        disable_ack_stretching: bool,
+    );
+    fn nw_tcp_options_set_enable_fast_open(options: &mut TcpOpts, enable_fast_open: bool);
+    fn nw_tcp_options_set_disable_ecn(options: &mut TcpOpts, disable_ecn: bool);
+    fn nw_tcp_get_available_receive_buffer(metadata: &TcpMetadata) -> u32;
+    fn nw_tcp_get_available_send_buffer(metadata: &TcpMetadata) -> u32;
+
+    fn nw_udp_create_options() -> arc::R<UdpOpts>;
+    fn nw_udp_options_set_prefer_no_checksum(options: &mut UdpOpts, prefer_no_checksum: bool);
+
+    fn nw_tls_create_options() -> arc::R<TlsOpts>;
+    fn nw_tls_copy_sec_protocol_options(options: &TlsOpts) -> arc::R<SecProtocolOpts>;
+
+    fn nw_ws_create_options(version: WsVersion) -> arc::R<WsOpts>;
+    fn nw_ws_options_add_additional_header(
+        options: &mut WsOpts,
+        name: *const c_char,
+        value: *const c_char,
+    );
+    fn nw_ws_options_add_subprotocol(options: &mut WsOpts, subprotocol: *const c_char);
+    fn nw_ws_options_set_auto_reply_ping(options: &mut WsOpts, auto_reply_ping: bool);
+    fn nw_ws_options_set_maximum_message_size(options: &mut WsOpts, maximum_message_size: usize);
+    fn nw_ws_options_set_skip_handshake(options: &mut WsOpts, skip_handshake: bool);
+
+    fn nw_ws_create_metadata(opcode: WsOpcode) -> arc::R<WsMetadata>;
+    fn nw_ws_metadata_get_opcode(metadata: &WsMetadata) -> WsOpcode;
+    fn nw_ws_metadata_get_close_code(metadata: &WsMetadata) -> WsCloseCode;
+    fn nw_ws_metadata_set_close_code(metadata: &mut WsMetadata, close_code: WsCloseCode);
+}
+
+#[link(name = "Security", kind = "framework")]
+extern "C" {
+    fn sec_protocol_options_add_tls_application_protocol(
+        options: &mut SecProtocolOpts,
+        application_protocol: *const c_char,
+    );
+    fn sec_protocol_options_set_min_tls_protocol_version(
+        options: &mut SecProtocolOpts,
+        version: TlsVersion,
+    );
+    fn sec_protocol_options_set_max_tls_protocol_version(
+        options: &mut SecProtocolOpts,
+        version: TlsVersion,
+    );
+    fn sec_protocol_options_set_tls_server_name(
+        options: &mut SecProtocolOpts,
+        server_name: *const c_char,
+    );
+    fn sec_protocol_options_set_peer_authentication_required(
+        options: &mut SecProtocolOpts,
+        peer_authentication_required: bool,
+    );
+    fn sec_protocol_options_set_tls_resumption_enabled(
+        options: &mut SecProtocolOpts,
+        tls_resumption_enabled: bool,
+    );
+    fn sec_protocol_options_set_tls_tickets_enabled(
+        options: &mut SecProtocolOpts,
+        tickets_enabled: bool,
+    );
+    fn sec_protocol_options_set_tls_false_start_enabled(
+        options: &mut SecProtocolOpts,
+        false_start_enabled: bool,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::nw;
+
+    #[test]
+    fn basics() {
+        let mut tcp = nw::TcpOpts::new();
+        tcp.set_no_delay(true);
+        tcp.set_keepalive(true);
+        tcp.set_keepalive_idle_time(10);
+        assert!(tcp.definition().is_equal(&nw::ProtocolDefinition::tcp()));
+
+        let mut udp = nw::UdpOpts::new();
+        udp.set_prefer_no_checksum(false);
+        assert!(udp.definition().is_equal(&nw::ProtocolDefinition::udp()));
+
+        let tls = nw::TlsOpts::new();
+        let mut sec = tls.sec_protocol_opts();
+        sec.add_tls_app_protocol(c"h2");
+        sec.set_min_tls_version(nw::TlsVersion::V12);
+
+        let mut ws = nw::WsOpts::new();
+        ws.add_subprotocol(c"chat");
+        ws.set_auto_reply_ping(true);
+        assert!(ws.definition().is_equal(&nw::ProtocolDefinition::ws()));
+
+        let mut meta = nw::WsMetadata::with_opcode(nw::WsOpcode::Close);
+        meta.set_close_code(nw::WsCloseCode::GOING_AWAY);
+        assert!(meta.is_ws());
+        assert_eq!(meta.opcode(), nw::WsOpcode::Close);
+        assert_eq!(meta.close_code(), nw::WsCloseCode::GOING_AWAY);
+    }
+}
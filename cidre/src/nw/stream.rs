@@ -0,0 +1,627 @@
+//! Async adapters over `nw::Connection`.
+//!
+//! [`Stream`] is a byte stream (TCP, TLS, QUIC stream) implementing
+//! `futures_io::AsyncRead`/`AsyncWrite` (and tokio's traits with `tokio` feature).
+//! [`Messages`] preserves message boundaries (UDP datagrams, framed protocols, QUIC streams).
+
+use std::{
+    future::poll_fn,
+    io,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll, Waker},
+};
+
+use parking_lot::Mutex;
+
+use crate::{arc, blocks, dispatch, nw};
+
+/// Starts the connection on the queue and waits until it is ready.
+async fn start(conn: &mut nw::Connection, queue: &dispatch::Queue) -> io::Result<()> {
+    let shared = blocks::Shared::new();
+    let comp = blocks::Completion::new(shared.clone());
+    let mut shared = Some(shared);
+    conn.set_state_changed_handler(move |state, err| {
+        let res = match state {
+            nw::ConnectionState::Ready => Ok(()),
+            nw::ConnectionState::Failed | nw::ConnectionState::Cancelled => Err(err
+                .map(io::Error::from)
+                .unwrap_or_else(|| io::ErrorKind::NotConnected.into())),
+            _ => return,
+        };
+        if let Some(shared) = shared.take() {
+            shared.lock().ready(res);
+        }
+    });
+    conn.start(queue);
+    comp.await
+}
+
+#[derive(Default)]
+struct ReadState {
+    buf: Vec<u8>,
+    pos: usize,
+    pending: bool,
+    eof: bool,
+    err: Option<io::Error>,
+    waker: Option<Waker>,
+}
+
+#[derive(Default)]
+struct WriteState {
+    in_flight: usize,
+    closing: bool,
+    closed: bool,
+    /// First send error, kept so every later write fails too.
+    err: Option<arc::R<nw::Error>>,
+    waker: Option<Waker>,
+}
+
+/// Byte stream over `nw::Connection`.
+///
+/// Reads complete with `Ok(0)` once the peer closed its write side
+/// (receive completion with `is_complete` and final context).
+/// Closing the stream sends final context, so the peer observes half-close
+/// while reading is still possible.
+///
+/// Writes are accepted while the amount of data not yet consumed
+/// by the network stack is below [`Stream::high_water_mark`].
+pub struct Stream {
+    conn: arc::R<nw::Connection>,
+    read: Arc<Mutex<ReadState>>,
+    write: Arc<Mutex<WriteState>>,
+    max_recv_len: u32,
+    high_water_mark: usize,
+}
+
+impl Stream {
+    pub const DEFAULT_MAX_RECV_LEN: u32 = 64 * 1024;
+    pub const DEFAULT_HIGH_WATER_MARK: usize = 256 * 1024;
+
+    /// Wraps already started and ready connection.
+    pub fn with_ready(conn: arc::R<nw::Connection>) -> Self {
+        Self {
+            conn,
+            read: Default::default(),
+            write: Default::default(),
+            max_recv_len: Self::DEFAULT_MAX_RECV_LEN,
+            high_water_mark: Self::DEFAULT_HIGH_WATER_MARK,
+        }
+    }
+
+    /// Starts connection on the queue and waits it to become ready.
+    ///
+    /// Use it for connections from `nw::Listener` too.
+    pub async fn start(
+        mut conn: arc::R<nw::Connection>,
+        queue: &dispatch::Queue,
+    ) -> io::Result<Self> {
+        start(&mut conn, queue).await?;
+        Ok(Self::with_ready(conn))
+    }
+
+    pub async fn connect(
+        endpoint: &nw::Endpoint,
+        params: &nw::Params,
+        queue: &dispatch::Queue,
+    ) -> io::Result<Self> {
+        let Some(conn) = nw::Connection::with_endpoint(endpoint, params) else {
+            return Err(io::ErrorKind::InvalidInput.into());
+        };
+        Self::start(conn, queue).await
+    }
+
+    #[inline]
+    pub fn conn(&self) -> &nw::Connection {
+        &self.conn
+    }
+
+    /// Max bytes requested from the connection with one receive call.
+    #[inline]
+    pub fn max_recv_len(&self) -> u32 {
+        self.max_recv_len
+    }
+
+    #[inline]
+    pub fn set_max_recv_len(&mut self, val: u32) {
+        self.max_recv_len = val.max(1);
+    }
+
+    /// Max bytes sent but not yet processed by the connection.
+    #[inline]
+    pub fn high_water_mark(&self) -> usize {
+        self.high_water_mark
+    }
+
+    #[inline]
+    pub fn set_high_water_mark(&mut self, val: usize) {
+        self.high_water_mark = val.max(1);
+    }
+
+    fn recv_more(&self) {
+        let read = self.read.clone();
+        self.conn.recv(
+            1,
+            self.max_recv_len,
+            move |content, ctx, is_complete, err| {
+                let mut st = read.lock();
+                st.pending = false;
+                if let Some(content) = content {
+                    st.buf.extend_from_slice(content.map().as_slice());
+                }
+                if let Some(err) = err {
+                    st.err = Some(err.into());
+                } else if is_complete && ctx.map_or(true, nw::ContentCtx::is_final) {
+                    st.eof = true;
+                }
+                if let Some(waker) = st.waker.take() {
+                    waker.wake();
+                }
+            },
+        );
+    }
+
+    fn poll_read_priv(&self, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        let mut st = self.read.lock();
+        if st.pos < st.buf.len() {
+            let n = buf.len().min(st.buf.len() - st.pos);
+            buf[..n].copy_from_slice(&st.buf[st.pos..st.pos + n]);
+            st.pos += n;
+            if st.pos == st.buf.len() {
+                st.buf.clear();
+                st.pos = 0;
+            }
+            return Poll::Ready(Ok(n));
+        }
+        if let Some(err) = st.err.take() {
+            return Poll::Ready(Err(err));
+        }
+        if st.eof || buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+        st.waker = Some(cx.waker().clone());
+        if !st.pending {
+            st.pending = true;
+            drop(st);
+            self.recv_more();
+        }
+        Poll::Pending
+    }
+
+    fn poll_write_priv(&self, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        let mut st = self.write.lock();
+        if let Some(err) = &st.err {
+            return Poll::Ready(Err(err.as_ref().into()));
+        }
+        if st.closing {
+            return Poll::Ready(Err(io::ErrorKind::BrokenPipe.into()));
+        }
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+        if st.in_flight >= self.high_water_mark {
+            st.waker = Some(cx.waker().clone());
+            return Poll::Pending;
+        }
+        let n = buf.len().min(self.high_water_mark - st.in_flight);
+        st.in_flight += n;
+        drop(st);
+
+        let data = dispatch::Data::copy_from_slice(&buf[..n]);
+        let write = self.write.clone();
+        self.conn.send(
+            Some(&data),
+            nw::ContentCtx::default_stream(),
+            false,
+            move |err| {
+                let mut st = write.lock();
+                st.in_flight -= n;
+                if let Some(err) = err {
+                    st.err.get_or_insert_with(|| err.retained());
+                }
+                if let Some(waker) = st.waker.take() {
+                    waker.wake();
+                }
+            },
+        );
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush_priv(&self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let mut st = self.write.lock();
+        if let Some(err) = &st.err {
+            return Poll::Ready(Err(err.as_ref().into()));
+        }
+        if st.in_flight == 0 {
+            return Poll::Ready(Ok(()));
+        }
+        st.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+
+    fn poll_close_priv(&self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        if self.poll_flush_priv(cx)?.is_pending() {
+            return Poll::Pending;
+        }
+        let mut st = self.write.lock();
+        if st.closed {
+            return Poll::Ready(match &st.err {
+                Some(err) => Err(err.as_ref().into()),
+                None => Ok(()),
+            });
+        }
+        st.waker = Some(cx.waker().clone());
+        if !st.closing {
+            st.closing = true;
+            drop(st);
+            let write = self.write.clone();
+            self.conn
+                .send(None, nw::ContentCtx::final_msg_send(), true, move |err| {
+                    let mut st = write.lock();
+                    st.closed = true;
+                    if let Some(err) = err {
+                        st.err.get_or_insert_with(|| err.retained());
+                    }
+                    if let Some(waker) = st.waker.take() {
+                        waker.wake();
+                    }
+                });
+        }
+        Poll::Pending
+    }
+
+    /// Reads some bytes. `Ok(0)` means end of stream.
+    pub async fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        poll_fn(|cx| self.poll_read_priv(cx, buf)).await
+    }
+
+    pub async fn read_exact(&mut self, mut buf: &mut [u8]) -> io::Result<()> {
+        while !buf.is_empty() {
+            match self.read(buf).await? {
+                0 => return Err(io::ErrorKind::UnexpectedEof.into()),
+                n => buf = &mut buf[n..],
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        poll_fn(|cx| self.poll_write_priv(cx, buf)).await
+    }
+
+    pub async fn write_all(&mut self, mut buf: &[u8]) -> io::Result<()> {
+        while !buf.is_empty() {
+            let n = self.write(buf).await?;
+            buf = &buf[n..];
+        }
+        Ok(())
+    }
+
+    /// Waits until all written bytes are processed by the connection.
+    pub async fn flush(&mut self) -> io::Result<()> {
+        poll_fn(|cx| self.poll_flush_priv(cx)).await
+    }
+
+    /// Flushes and closes write side of the stream.
+    pub async fn close(&mut self) -> io::Result<()> {
+        poll_fn(|cx| self.poll_close_priv(cx)).await
+    }
+}
+
+impl futures_io::AsyncRead for Stream {
+    #[inline]
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        self.poll_read_priv(cx, buf)
+    }
+}
+
+impl futures_io::AsyncWrite for Stream {
+    #[inline]
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.poll_write_priv(cx, buf)
+    }
+
+    #[inline]
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.poll_flush_priv(cx)
+    }
+
+    #[inline]
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.poll_close_priv(cx)
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl tokio::io::AsyncRead for Stream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let n = std::task::ready!(self.poll_read_priv(cx, buf.initialize_unfilled()))?;
+        buf.advance(n);
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl tokio::io::AsyncWrite for Stream {
+    #[inline]
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.poll_write_priv(cx, buf)
+    }
+
+    #[inline]
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.poll_flush_priv(cx)
+    }
+
+    #[inline]
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.poll_close_priv(cx)
+    }
+}
+
+/// Received message.
+pub struct Msg {
+    pub content: Option<arc::R<dispatch::Data>>,
+    pub ctx: Option<arc::R<nw::ContentCtx>>,
+    pub is_complete: bool,
+}
+
+impl Msg {
+    /// Peer will not send more messages.
+    ///
+    /// Complete receive without context is final too, like end of [`Stream`].
+    #[inline]
+    pub fn is_final(&self) -> bool {
+        self.is_complete && self.ctx.as_ref().map_or(true, |ctx| ctx.is_final())
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.content.as_ref().map_or(0, |c| c.len())
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    #[inline]
+    pub fn to_vec(&self) -> Vec<u8> {
+        self.content
+            .as_ref()
+            .map_or_else(Vec::new, |c| c.map().as_slice().to_vec())
+    }
+}
+
+/// Message oriented API over `nw::Connection`.
+///
+/// Each send is delivered as a separate datagram on UDP or
+/// as a complete message on framed protocols.
+pub struct Messages {
+    conn: arc::R<nw::Connection>,
+}
+
+impl Messages {
+    /// Wraps already started and ready connection.
+    #[inline]
+    pub fn with_ready(conn: arc::R<nw::Connection>) -> Self {
+        Self { conn }
+    }
+
+    pub async fn start(
+        mut conn: arc::R<nw::Connection>,
+        queue: &dispatch::Queue,
+    ) -> io::Result<Self> {
+        start(&mut conn, queue).await?;
+        Ok(Self::with_ready(conn))
+    }
+
+    #[inline]
+    pub fn conn(&self) -> &nw::Connection {
+        &self.conn
+    }
+
+    #[inline]
+    pub fn max_datagram_size(&self) -> u32 {
+        self.conn.maximum_datagram_size()
+    }
+
+    /// Receives complete message.
+    pub async fn recv(&self) -> io::Result<Msg> {
+        let shared = blocks::Shared::new();
+        let comp = blocks::Completion::new(shared.clone());
+        let mut block = nw::connection::RecvCompletion::new4(
+            move |content: Option<&dispatch::Data>,
+                  ctx: Option<&nw::ContentCtx>,
+                  is_complete: bool,
+                  err: Option<&nw::Error>| {
+                let res = match err {
+                    Some(err) => Err(err.into()),
+                    None => Ok(Msg {
+                        content: content.map(|c| c.retained()),
+                        ctx: ctx.map(|c| c.retained()),
+                        is_complete,
+                    }),
+                };
+                shared.lock().ready(res);
+            },
+        );
+        self.conn.recv_msg_ch(&mut block);
+        comp.await
+    }
+
+    /// Sends complete message with default message context.
+    pub async fn send(&self, content: &[u8]) -> io::Result<()> {
+        let data = dispatch::Data::copy_from_slice(content);
+        self.send_with_ctx(Some(&data), nw::ContentCtx::default_msg(), true)
+            .await
+    }
+
+    pub async fn send_with_ctx(
+        &self,
+        content: Option<&dispatch::Data>,
+        ctx: &nw::ContentCtx,
+        is_complete: bool,
+    ) -> io::Result<()> {
+        let shared = blocks::Shared::new();
+        let comp = blocks::Completion::new(shared.clone());
+        self.conn.send(content, ctx, is_complete, move |err| {
+            shared.lock().ready(match err {
+                Some(err) => Err(err.into()),
+                None => Ok(()),
+            });
+        });
+        comp.await
+    }
+
+    /// Marks the end of sending (closes write side of QUIC stream).
+    pub async fn send_final(&self) -> io::Result<()> {
+        self.send_with_ctx(None, nw::ContentCtx::final_msg_send(), true)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{ffi::CString, sync::mpsc};
+
+    use crate::{arc, dispatch, nw};
+
+    fn listener(
+        params: &nw::Params,
+        queue: &dispatch::Queue,
+    ) -> (arc::R<nw::Listener>, mpsc::Receiver<arc::R<nw::Connection>>) {
+        let (tx, rx) = mpsc::channel();
+        let mut listener = nw::Listener::with_port(c"0", params).unwrap();
+        let mut on_conn = nw::ListenerNewConnectionHandler::new1(move |conn: &nw::Connection| {
+            tx.send(conn.retained()).unwrap();
+        });
+        listener.set_new_connection_handler(Some(&mut on_conn));
+
+        let sema = dispatch::Semaphore::new(0);
+        let signal = sema.clone();
+        let mut on_state = nw::ListenerStateChangedHandler::new2(
+            move |state: nw::ListenerState, _err: Option<&nw::Error>| {
+                if let nw::ListenerState::Ready = state {
+                    signal.signal();
+                }
+            },
+        );
+        listener.set_state_changed_handler(Some(&mut on_state));
+        listener.start(queue);
+        sema.wait_forever();
+        (listener, rx)
+    }
+
+    fn loopback(port: u16) -> arc::R<nw::Endpoint> {
+        let port = CString::new(port.to_string()).unwrap();
+        nw::Endpoint::with_host(c"127.0.0.1", &port).unwrap()
+    }
+
+    #[test]
+    fn stream_partial_reads_and_backpressure() {
+        let queue = dispatch::Queue::new();
+        let (mut listener, rx) = listener(&nw::Params::tcp(), &queue);
+        let endpoint = loopback(listener.port());
+
+        let payload: Vec<u8> = (0..1024 * 1024).map(|i| (i % 251) as u8).collect();
+        let expected = payload.clone();
+
+        let mut client =
+            dispatch::block_on(nw::Stream::connect(&endpoint, &nw::Params::tcp(), &queue)).unwrap();
+        client.set_high_water_mark(4096);
+
+        let server = rx.recv().unwrap();
+        let mut server = dispatch::block_on(nw::Stream::start(server, &queue)).unwrap();
+        server.set_max_recv_len(1000);
+
+        let writer = dispatch::Executor::serial().spawn(async move {
+            client.write_all(&payload).await.unwrap();
+            client.close().await.unwrap();
+            client
+        });
+
+        let received = dispatch::block_on(async {
+            let mut received = Vec::new();
+            let mut buf = [0u8; 777];
+            loop {
+                let n = server.read(&mut buf).await.unwrap();
+                if n == 0 {
+                    break;
+                }
+                assert!(n <= buf.len());
+                received.extend_from_slice(&buf[..n]);
+            }
+            received
+        });
+        assert_eq!(received.len(), expected.len());
+        assert!(received == expected);
+
+        // half-close: server can still write back after client closed
+        let mut client = dispatch::block_on(writer).unwrap();
+        dispatch::block_on(async {
+            server.write_all(b"bye").await.unwrap();
+            server.close().await.unwrap();
+            let mut buf = [0u8; 3];
+            client.read_exact(&mut buf).await.unwrap();
+            assert_eq!(&buf, b"bye");
+            assert_eq!(client.read(&mut buf).await.unwrap(), 0);
+        });
+
+        listener.cancel();
+    }
+
+    #[test]
+    fn datagrams() {
+        let queue = dispatch::Queue::new();
+        let (mut listener, rx) = listener(&nw::Params::udp(), &queue);
+        let endpoint = loopback(listener.port());
+
+        let conn = nw::Connection::with_endpoint(&endpoint, &nw::Params::udp()).unwrap();
+        let client = dispatch::block_on(nw::Messages::start(conn, &queue)).unwrap();
+        dispatch::block_on(client.send(b"hello")).unwrap();
+
+        let server = rx.recv().unwrap();
+        let server = dispatch::block_on(nw::Messages::start(server, &queue)).unwrap();
+        let msg = dispatch::block_on(server.recv()).unwrap();
+        assert!(msg.is_complete);
+        assert_eq!(msg.to_vec(), b"hello");
+
+        dispatch::block_on(server.send(b"world")).unwrap();
+        let msg = dispatch::block_on(client.recv()).unwrap();
+        assert_eq!(msg.to_vec(), b"world");
+
+        listener.cancel();
+    }
+
+    #[test]
+    fn final_msg() {
+        let msg = nw::Msg {
+            content: None,
+            ctx: None,
+            is_complete: true,
+        };
+        assert!(msg.is_final());
+        let msg = nw::Msg {
+            is_complete: false,
+            ..msg
+        };
+        assert!(!msg.is_final());
+    }
+}
@@ -1,7 +1,276 @@
-use crate::{define_obj_type, ns};
+use std::ffi::{c_char, CStr, CString};
+
+use crate::{arc, blocks, define_obj_type, dns_sd, ns};
 
 define_obj_type!(
     #[doc(alias = "nw_txt_record")]
     #[doc(alias = "nw_txt_record_t")]
     pub TxtRecord(ns::Id)
 );
+
+unsafe impl Send for TxtRecord {}
+unsafe impl Sync for TxtRecord {}
+
+#[doc(alias = "nw_txt_record_find_key_t")]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[repr(i32)]
+pub enum FindKey {
+    /// The key is invalid.
+    #[doc(alias = "nw_txt_record_find_key_invalid")]
+    Invalid = 0,
+
+    /// The key is not present in the TXT record.
+    #[doc(alias = "nw_txt_record_find_key_not_present")]
+    NotPresent = 1,
+
+    /// The key has no associated value (boolean attribute).
+    #[doc(alias = "nw_txt_record_find_key_no_value")]
+    NoValue = 2,
+
+    /// The key has an associated empty value.
+    #[doc(alias = "nw_txt_record_find_key_empty_value")]
+    EmptyValue = 3,
+
+    /// The key has an associated non-empty value.
+    #[doc(alias = "nw_txt_record_find_key_non_empty_value")]
+    NonEmptyValue = 4,
+}
+
+#[doc(alias = "nw_txt_record_access_bytes_t")]
+pub type AccessBytes = blocks::NoEscBlock<fn(*const u8, usize) -> bool>;
+
+#[doc(alias = "nw_txt_record_access_key_t")]
+pub type AccessKey = blocks::NoEscBlock<fn(*const c_char, FindKey, *const u8, usize) -> bool>;
+
+impl TxtRecord {
+    #[doc(alias = "nw_txt_record_create_with_bytes")]
+    #[inline]
+    pub fn with_bytes(bytes: &[u8]) -> Option<arc::R<Self>> {
+        unsafe { nw_txt_record_create_with_bytes(bytes.as_ptr(), bytes.len()) }
+    }
+
+    #[doc(alias = "nw_txt_record_create_dictionary")]
+    #[inline]
+    pub fn new_dictionary() -> arc::R<Self> {
+        unsafe { nw_txt_record_create_dictionary() }
+    }
+
+    /// Builds dictionary TXT record from the pure Rust representation.
+    pub fn with_dns_sd(txt: &dns_sd::TxtRecord) -> arc::R<Self> {
+        let mut res = Self::new_dictionary();
+        for (key, val) in txt.iter() {
+            // keys are validated by dns_sd::TxtRecord
+            let key = CString::new(key).unwrap();
+            res.set_key(&key, val);
+        }
+        res
+    }
+
+    #[doc(alias = "nw_txt_record_copy")]
+    #[inline]
+    pub fn copy(&self) -> Option<arc::R<Self>> {
+        unsafe { nw_txt_record_copy(Some(self)) }
+    }
+
+    #[doc(alias = "nw_txt_record_find_key")]
+    #[inline]
+    pub fn find_key(&self, key: &CStr) -> FindKey {
+        unsafe { nw_txt_record_find_key(self, key.as_ptr()) }
+    }
+
+    /// Calls `f` with value of the key. `None` for missing key or key without value.
+    #[doc(alias = "nw_txt_record_access_key")]
+    pub fn access_key<R>(&self, key: &CStr, f: impl FnOnce(FindKey, Option<&[u8]>) -> R) -> R {
+        let mut f = Some(f);
+        let mut res = None;
+        let mut access = |_key: *const c_char, found: FindKey, ptr: *const u8, len: usize| {
+            let val = if ptr.is_null() {
+                None
+            } else {
+                Some(unsafe { std::slice::from_raw_parts(ptr, len) })
+            };
+            if let Some(f) = f.take() {
+                res = Some(f(found, val));
+            }
+            true
+        };
+        let mut block = unsafe { AccessKey::stack4(&mut access) };
+        unsafe { nw_txt_record_access_key(self, key.as_ptr(), &mut block) };
+        match res {
+            Some(res) => res,
+            None => (f.take().unwrap())(FindKey::Invalid, None),
+        }
+    }
+
+    /// Value of the key. `Some(None)` if key is present without value.
+    pub fn value(&self, key: &CStr) -> Option<Option<Vec<u8>>> {
+        self.access_key(key, |found, val| match found {
+            FindKey::Invalid | FindKey::NotPresent => None,
+            FindKey::NoValue => Some(None),
+            FindKey::EmptyValue => Some(Some(Vec::new())),
+            FindKey::NonEmptyValue => Some(val.map(<[u8]>::to_vec)),
+        })
+    }
+
+    /// Sets key with value. `None` sets key without value.
+    #[doc(alias = "nw_txt_record_set_key")]
+    #[inline]
+    pub fn set_key(&mut self, key: &CStr, val: Option<&[u8]>) -> bool {
+        unsafe {
+            match val {
+                Some(val) => nw_txt_record_set_key(self, key.as_ptr(), val.as_ptr(), val.len()),
+                None => nw_txt_record_set_key(self, key.as_ptr(), std::ptr::null(), 0),
+            }
+        }
+    }
+
+    #[doc(alias = "nw_txt_record_remove_key")]
+    #[inline]
+    pub fn remove_key(&mut self, key: &CStr) -> bool {
+        unsafe { nw_txt_record_remove_key(self, key.as_ptr()) }
+    }
+
+    #[doc(alias = "nw_txt_record_get_key_count")]
+    #[inline]
+    pub fn key_count(&self) -> usize {
+        unsafe { nw_txt_record_get_key_count(self) }
+    }
+
+    #[doc(alias = "nw_txt_record_access_bytes")]
+    #[inline]
+    pub fn access_bytes_block(&self, block: &mut AccessBytes) -> bool {
+        unsafe { nw_txt_record_access_bytes(self, block) }
+    }
+
+    /// Raw TXT record bytes suitable for `DNSServiceRegister`.
+    #[doc(alias = "nw_txt_record_access_bytes")]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut res = Vec::new();
+        let mut access = |ptr: *const u8, len: usize| {
+            if !ptr.is_null() {
+                res.extend_from_slice(unsafe { std::slice::from_raw_parts(ptr, len) });
+            }
+            true
+        };
+        let mut block = unsafe { AccessBytes::stack2(&mut access) };
+        self.access_bytes_block(&mut block);
+        res
+    }
+
+    #[inline]
+    pub fn to_dns_sd(&self) -> Result<dns_sd::TxtRecord, dns_sd::TxtRecordError> {
+        dns_sd::TxtRecord::parse(&self.to_bytes())
+    }
+
+    #[doc(alias = "nw_txt_record_apply")]
+    #[inline]
+    pub fn apply_block(&self, block: &mut AccessKey) -> bool {
+        unsafe { nw_txt_record_apply(self, block) }
+    }
+
+    /// Iterates keys, stops when `f` returns `false`.
+    #[doc(alias = "nw_txt_record_apply")]
+    pub fn apply(&self, mut f: impl FnMut(&CStr, FindKey, Option<&[u8]>) -> bool) -> bool {
+        let mut access = |key: *const c_char, found: FindKey, ptr: *const u8, len: usize| {
+            let key = unsafe { CStr::from_ptr(key) };
+            let val = if ptr.is_null() {
+                None
+            } else {
+                Some(unsafe { std::slice::from_raw_parts(ptr, len) })
+            };
+            f(key, found, val)
+        };
+        let mut block = unsafe { AccessKey::stack4(&mut access) };
+        self.apply_block(&mut block)
+    }
+
+    #[doc(alias = "nw_txt_record_is_equal")]
+    #[inline]
+    pub fn is_equal(&self, other: &Self) -> bool {
+        unsafe { nw_txt_record_is_equal(Some(self), Some(other)) }
+    }
+
+    /// Returns `true` if TXT record follows RFC 6763 and can be treated as dictionary.
+    #[doc(alias = "nw_txt_record_is_dictionary")]
+    #[inline]
+    pub fn is_dictionary(&self) -> bool {
+        unsafe { nw_txt_record_is_dictionary(self) }
+    }
+}
+
+#[link(name = "Network", kind = "framework")]
+extern "C" {
+    fn nw_txt_record_create_with_bytes(
+        txt_bytes: *const u8,
+        txt_len: usize,
+    ) -> Option<arc::R<TxtRecord>>;
+    fn nw_txt_record_create_dictionary() -> arc::R<TxtRecord>;
+    fn nw_txt_record_copy(txt_record: Option<&TxtRecord>) -> Option<arc::R<TxtRecord>>;
+    fn nw_txt_record_find_key(txt_record: &TxtRecord, key: *const c_char) -> FindKey;
+    fn nw_txt_record_access_key(
+        txt_record: &TxtRecord,
+        key: *const c_char,
+        access_value: &mut AccessKey,
+    ) -> bool;
+    fn nw_txt_record_set_key(
+        txt_record: &mut TxtRecord,
+        key: *const c_char,
+        value: *const u8,
+        value_len: usize,
+    ) -> bool;
+    fn nw_txt_record_remove_key(txt_record: &mut TxtRecord, key: *const c_char) -> bool;
+    fn nw_txt_record_get_key_count(txt_record: &TxtRecord) -> usize;
+    fn nw_txt_record_access_bytes(txt_record: &TxtRecord, access_bytes: &mut AccessBytes) -> bool;
+    fn nw_txt_record_apply(txt_record: &TxtRecord, applier: &mut AccessKey) -> bool;
+    fn nw_txt_record_is_equal(left: Option<&TxtRecord>, right: Option<&TxtRecord>) -> bool;
+    fn nw_txt_record_is_dictionary(txt_record: &TxtRecord) -> bool;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CString;
+
+    use crate::{dns_sd, nw};
+
+    #[test]
+    fn basics() {
+        let mut txt = dns_sd::TxtRecord::new();
+        txt.set_str("txtvers", "1").unwrap();
+        txt.set_bool("secure").unwrap();
+        txt.set("path", b"").unwrap();
+
+        let record = nw::TxtRecord::with_dns_sd(&txt);
+        assert!(record.is_dictionary());
+        assert_eq!(record.key_count(), 3);
+
+        let key = CString::new("txtvers").unwrap();
+        assert_eq!(record.find_key(&key), nw::TxtRecordFindKey::NonEmptyValue);
+        assert_eq!(record.value(&key), Some(Some(b"1".to_vec())));
+
+        let key = CString::new("secure").unwrap();
+        assert_eq!(record.find_key(&key), nw::TxtRecordFindKey::NoValue);
+        assert_eq!(record.value(&key), Some(None));
+
+        let key = CString::new("path").unwrap();
+        assert_eq!(record.find_key(&key), nw::TxtRecordFindKey::EmptyValue);
+
+        let key = CString::new("missing").unwrap();
+        assert_eq!(record.value(&key), None);
+
+        let parsed = record.to_dns_sd().unwrap();
+        assert_eq!(parsed.len(), 3);
+        assert_eq!(parsed.get_str("TXTVERS"), Some("1"));
+        assert_eq!(parsed.get("secure"), Some(None));
+
+        let raw = nw::TxtRecord::with_bytes(&txt.to_bytes()).unwrap();
+        assert!(raw.is_equal(&raw.copy().unwrap()));
+        assert_eq!(raw.key_count(), 3);
+
+        let mut count = 0;
+        record.apply(|_key, _found, _val| {
+            count += 1;
+            true
+        });
+        assert_eq!(count, 3);
+    }
+}
@@ -0,0 +1,82 @@
+//! Pure Rust clients of `usbmuxd` and `lockdownd`.
+//!
+//! [`Client`] speaks the plist flavour of the usbmuxd socket protocol:
+//! device listing, attach/detach events and tunneling to a device port.
+//! [`LockdownClient`] speaks the lockdown protocol over such a tunnel.
+//! Both this backend and `am::device::Session` implement [`Lockdown`].
+
+use std::{fmt, io};
+
+pub mod plist;
+pub use plist::Dict;
+pub use plist::Value;
+
+mod client;
+pub use client::Client;
+pub use client::ConnectionType;
+pub use client::Device;
+pub use client::Event;
+pub use client::Events;
+pub use client::SOCKET_PATH;
+
+pub mod lockdown;
+pub use lockdown::Client as LockdownClient;
+pub use lockdown::Lockdown;
+pub use lockdown::PairRecord;
+pub use lockdown::ServiceInfo;
+pub use lockdown::Stream;
+pub use lockdown::TlsConnector;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+
+    /// Malformed property list.
+    Plist,
+
+    /// Message is not what the protocol expects.
+    UnexpectedResponse,
+
+    /// usbmuxd replied with non zero result code.
+    Mux(i64),
+
+    /// lockdownd replied with an error.
+    Lockdown(String),
+
+    /// Device requested TLS but no [`TlsConnector`] is provided.
+    TlsRequired,
+
+    /// Error code of the backend, e.g. `am::device::Error`.
+    Backend(i32),
+}
+
+pub type Result<T = ()> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => e.fmt(f),
+            Error::Plist => f.write_str("malformed property list"),
+            Error::UnexpectedResponse => f.write_str("unexpected response"),
+            Error::Mux(code) => write!(f, "usbmuxd error {code}"),
+            Error::Lockdown(e) => write!(f, "lockdown error {e}"),
+            Error::TlsRequired => f.write_str("tls session required"),
+            Error::Backend(code) => write!(f, "backend error {code:#x}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(value: io::Error) -> Self {
+        Error::Io(value)
+    }
+}
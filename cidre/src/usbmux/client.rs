@@ -0,0 +1,404 @@
+use std::{
+    io::{Read, Write},
+    os::unix::net::UnixStream,
+    path::Path,
+};
+
+use super::{lockdown::PairRecord, Dict, Error, Result, Value};
+
+/// Default path of the usbmuxd socket.
+pub const SOCKET_PATH: &str = "/var/run/usbmuxd";
+
+const HEADER_LEN: usize = 16;
+const PLIST_VERSION: u32 = 1;
+const PLIST_MESSAGE: u32 = 8;
+const MAX_MESSAGE_LEN: usize = 16 << 20;
+const PROG_NAME: &str = "cidre";
+const CLIENT_VERSION: &str = concat!("cidre-", env!("CARGO_PKG_VERSION"));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionType {
+    Usb,
+    Network,
+    Unknown,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Device {
+    /// usbmuxd assigned id, valid until device detaches.
+    pub id: u32,
+    /// UDID of the device.
+    pub serial_number: String,
+    pub connection_type: ConnectionType,
+    /// All properties as reported by usbmuxd.
+    pub properties: Dict,
+}
+
+impl Device {
+    fn from_value(value: &Value) -> Result<Self> {
+        let props = value
+            .get("Properties")
+            .and_then(Value::as_dict)
+            .ok_or(Error::UnexpectedResponse)?;
+        let id = props
+            .get("DeviceID")
+            .or_else(|| value.get("DeviceID"))
+            .and_then(Value::as_i64)
+            .ok_or(Error::UnexpectedResponse)?;
+        let serial_number = props
+            .get("SerialNumber")
+            .and_then(Value::as_str)
+            .ok_or(Error::UnexpectedResponse)?;
+        let connection_type = match props.get("ConnectionType").and_then(Value::as_str) {
+            Some("USB") => ConnectionType::Usb,
+            Some("Network") => ConnectionType::Network,
+            _ => ConnectionType::Unknown,
+        };
+        Ok(Self {
+            id: id as u32,
+            serial_number: serial_number.to_string(),
+            connection_type,
+            properties: props.clone(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    Attached(Device),
+    /// Id of detached device.
+    Detached(u32),
+    /// Id of device paired with this host.
+    Paired(u32),
+}
+
+/// usbmuxd client.
+///
+/// Each client is a single connection to usbmuxd. [`Self::listen`] and
+/// [`Self::connect_port`] consume it, since the connection is dedicated afterwards.
+pub struct Client<S = UnixStream> {
+    stream: S,
+    tag: u32,
+}
+
+impl Client<UnixStream> {
+    /// Connects to the usbmuxd at [`SOCKET_PATH`].
+    pub fn connect() -> Result<Self> {
+        Self::connect_to(SOCKET_PATH)
+    }
+
+    pub fn connect_to<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Ok(Self::with_stream(UnixStream::connect(path)?))
+    }
+}
+
+impl<S: Read + Write> Client<S> {
+    pub fn with_stream(stream: S) -> Self {
+        Self { stream, tag: 0 }
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+
+    fn send(&mut self, msg_type: &str, mut msg: Dict) -> Result<u32> {
+        msg.insert("MessageType".into(), msg_type.into());
+        msg.insert("ProgName".into(), PROG_NAME.into());
+        msg.insert("ClientVersionString".into(), CLIENT_VERSION.into());
+        msg.insert("kLibUSBMuxVersion".into(), 3i64.into());
+        let body = Value::Dict(msg).to_xml();
+
+        self.tag = self.tag.wrapping_add(1);
+        let mut header = [0u8; HEADER_LEN];
+        header[0..4].copy_from_slice(&((HEADER_LEN + body.len()) as u32).to_le_bytes());
+        header[4..8].copy_from_slice(&PLIST_VERSION.to_le_bytes());
+        header[8..12].copy_from_slice(&PLIST_MESSAGE.to_le_bytes());
+        header[12..16].copy_from_slice(&self.tag.to_le_bytes());
+        self.stream.write_all(&header)?;
+        self.stream.write_all(&body)?;
+        self.stream.flush()?;
+        Ok(self.tag)
+    }
+
+    fn recv(&mut self) -> Result<(u32, Dict)> {
+        let mut header = [0u8; HEADER_LEN];
+        self.stream.read_exact(&mut header)?;
+        let field = |i: usize| u32::from_le_bytes(header[i * 4..i * 4 + 4].try_into().unwrap());
+        let len = field(0) as usize;
+        if field(1) != PLIST_VERSION
+            || field(2) != PLIST_MESSAGE
+            || !(HEADER_LEN..=MAX_MESSAGE_LEN).contains(&len)
+        {
+            return Err(Error::UnexpectedResponse);
+        }
+        let mut body = vec![0u8; len - HEADER_LEN];
+        self.stream.read_exact(&mut body)?;
+        let msg = Value::from_xml(&body)?
+            .into_dict()
+            .ok_or(Error::UnexpectedResponse)?;
+        Ok((field(3), msg))
+    }
+
+    fn request(&mut self, msg_type: &str, msg: Dict) -> Result<Dict> {
+        let tag = self.send(msg_type, msg)?;
+        loop {
+            let (reply_tag, reply) = self.recv()?;
+            // skip events of listening connections
+            if reply_tag == tag {
+                return Ok(reply);
+            }
+        }
+    }
+
+    /// Sends request answered with `Result` message.
+    fn request_result(&mut self, msg_type: &str, msg: Dict) -> Result {
+        let reply = self.request(msg_type, msg)?;
+        if reply.get("MessageType").and_then(Value::as_str) != Some("Result") {
+            return Err(Error::UnexpectedResponse);
+        }
+        match reply.get("Number").and_then(Value::as_i64) {
+            Some(0) => Ok(()),
+            Some(code) => Err(Error::Mux(code)),
+            None => Err(Error::UnexpectedResponse),
+        }
+    }
+
+    /// Currently attached devices.
+    pub fn devices(&mut self) -> Result<Vec<Device>> {
+        let reply = self.request("ListDevices", Dict::new())?;
+        let Some(list) = reply.get("DeviceList").and_then(Value::as_array) else {
+            return Err(Error::UnexpectedResponse);
+        };
+        list.iter().map(Device::from_value).collect()
+    }
+
+    /// System BUID of this host.
+    pub fn buid(&mut self) -> Result<String> {
+        let reply = self.request("ReadBUID", Dict::new())?;
+        match reply.get("BUID") {
+            Some(Value::String(buid)) => Ok(buid.clone()),
+            _ => Err(Error::UnexpectedResponse),
+        }
+    }
+
+    /// Pairing record of the device with `udid`.
+    pub fn pair_record(&mut self, udid: &str) -> Result<PairRecord> {
+        let mut msg = Dict::new();
+        msg.insert("PairRecordID".into(), udid.into());
+        let reply = self.request("ReadPairRecord", msg)?;
+        if let Some(code) = reply.get("Number").and_then(Value::as_i64) {
+            return Err(Error::Mux(code));
+        }
+        let Some(data) = reply.get("PairRecordData").and_then(Value::as_data) else {
+            return Err(Error::UnexpectedResponse);
+        };
+        PairRecord::from_value(&Value::from_bytes(data)?)
+    }
+
+    /// Turns this connection into a stream of attach and detach events.
+    ///
+    /// Currently attached devices are reported first.
+    pub fn listen(mut self) -> Result<Events<S>> {
+        self.request_result("Listen", Dict::new())?;
+        Ok(Events { client: self })
+    }
+
+    /// Turns this connection into a tunnel to TCP `port` of the device.
+    pub fn connect_port(mut self, device_id: u32, port: u16) -> Result<S> {
+        let mut msg = Dict::new();
+        msg.insert("DeviceID".into(), device_id.into());
+        // port is in network byte order
+        msg.insert("PortNumber".into(), (port.to_be() as u32).into());
+        self.request_result("Connect", msg)?;
+        Ok(self.stream)
+    }
+}
+
+/// Iterator of device events.
+pub struct Events<S = UnixStream> {
+    client: Client<S>,
+}
+
+impl<S: Read + Write> Iterator for Events<S> {
+    type Item = Result<Event>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let msg = match self.client.recv() {
+            Ok((_, msg)) => Value::Dict(msg),
+            Err(Error::Io(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => return None,
+            Err(e) => return Some(Err(e)),
+        };
+        let id = || {
+            msg.get("DeviceID")
+                .and_then(Value::as_i64)
+                .map(|id| id as u32)
+                .ok_or(Error::UnexpectedResponse)
+        };
+        let res = match msg.get("MessageType").and_then(Value::as_str) {
+            Some("Attached") => Device::from_value(&msg).map(Event::Attached),
+            Some("Detached") => id().map(Event::Detached),
+            Some("Paired") => id().map(Event::Paired),
+            _ => Err(Error::UnexpectedResponse),
+        };
+        Some(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        os::unix::net::{UnixListener, UnixStream},
+        thread,
+    };
+
+    use crate::usbmux::{self, ConnectionType, Dict, Event, Value};
+
+    fn read_msg(stream: &mut UnixStream) -> (u32, Value) {
+        let mut header = [0u8; 16];
+        stream.read_exact(&mut header).unwrap();
+        let len = u32::from_le_bytes(header[0..4].try_into().unwrap()) as usize;
+        let tag = u32::from_le_bytes(header[12..16].try_into().unwrap());
+        let mut body = vec![0u8; len - 16];
+        stream.read_exact(&mut body).unwrap();
+        (tag, Value::from_xml(&body).unwrap())
+    }
+
+    fn write_msg(stream: &mut UnixStream, tag: u32, msg: Dict) {
+        let body = Value::Dict(msg).to_xml();
+        let mut header = Vec::new();
+        for v in [16 + body.len() as u32, 1, 8, tag] {
+            header.extend_from_slice(&v.to_le_bytes());
+        }
+        stream.write_all(&header).unwrap();
+        stream.write_all(&body).unwrap();
+    }
+
+    fn attached(id: u32) -> Dict {
+        let mut props = Dict::new();
+        props.insert("DeviceID".into(), id.into());
+        props.insert("SerialNumber".into(), format!("udid-{id}").into());
+        props.insert("ConnectionType".into(), "USB".into());
+        let mut msg = Dict::new();
+        msg.insert("MessageType".into(), "Attached".into());
+        msg.insert("DeviceID".into(), id.into());
+        msg.insert("Properties".into(), props.into());
+        msg
+    }
+
+    fn result(code: i64) -> Dict {
+        let mut msg = Dict::new();
+        msg.insert("MessageType".into(), "Result".into());
+        msg.insert("Number".into(), code.into());
+        msg
+    }
+
+    /// Stand-in usbmuxd serving single request per connection.
+    fn serve(mut stream: UnixStream) {
+        let (tag, msg) = read_msg(&mut stream);
+        assert_eq!(msg.get("ProgName").unwrap().as_str(), Some("cidre"));
+        match msg.get("MessageType").unwrap().as_str().unwrap() {
+            "ListDevices" => {
+                let mut reply = Dict::new();
+                reply.insert(
+                    "DeviceList".into(),
+                    Value::Array(vec![attached(1).into(), attached(2).into()]),
+                );
+                write_msg(&mut stream, tag, reply);
+            }
+            "ReadPairRecord" => {
+                assert_eq!(msg.get("PairRecordID").unwrap().as_str(), Some("udid-1"));
+                let mut record = Dict::new();
+                record.insert("HostID".into(), "HOST".into());
+                record.insert("SystemBUID".into(), "BUID".into());
+                record.insert("HostCertificate".into(), vec![1u8].into());
+                record.insert("HostPrivateKey".into(), vec![2u8].into());
+                record.insert("RootCertificate".into(), vec![3u8].into());
+                record.insert("DeviceCertificate".into(), vec![4u8].into());
+                let mut reply = Dict::new();
+                reply.insert("PairRecordData".into(), Value::Dict(record).to_xml().into());
+                write_msg(&mut stream, tag, reply);
+            }
+            "Listen" => {
+                write_msg(&mut stream, tag, result(0));
+                write_msg(&mut stream, 0, attached(3));
+                let mut detached = Dict::new();
+                detached.insert("MessageType".into(), "Detached".into());
+                detached.insert("DeviceID".into(), 3u32.into());
+                write_msg(&mut stream, 0, detached);
+            }
+            "Connect" => {
+                let port = msg.get("PortNumber").unwrap().as_i64().unwrap() as u16;
+                if msg.get("DeviceID").unwrap().as_i64() != Some(1) {
+                    write_msg(&mut stream, tag, result(2));
+                    return;
+                }
+                assert_eq!(u16::from_be(port), 62078);
+                write_msg(&mut stream, tag, result(0));
+                // tunneled connection, echo
+                let mut buf = [0u8; 4];
+                stream.read_exact(&mut buf).unwrap();
+                stream.write_all(&buf).unwrap();
+            }
+            t => panic!("unexpected {t}"),
+        }
+    }
+
+    #[test]
+    fn stand_in_server() {
+        let dir = std::env::temp_dir().join(format!("cidre-usbmux-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("usbmuxd");
+        _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+        let server = thread::spawn(move || {
+            for stream in listener.incoming().take(5) {
+                serve(stream.unwrap());
+            }
+        });
+
+        let devices = usbmux::Client::connect_to(&path)
+            .unwrap()
+            .devices()
+            .unwrap();
+        assert_eq!(devices.len(), 2);
+        assert_eq!(devices[0].id, 1);
+        assert_eq!(devices[1].serial_number, "udid-2");
+        assert_eq!(devices[0].connection_type, ConnectionType::Usb);
+
+        let record = usbmux::Client::connect_to(&path)
+            .unwrap()
+            .pair_record("udid-1")
+            .unwrap();
+        assert_eq!(record.host_id, "HOST");
+        assert_eq!(record.device_certificate, [4]);
+
+        let mut events = usbmux::Client::connect_to(&path).unwrap().listen().unwrap();
+        match events.next().unwrap().unwrap() {
+            Event::Attached(device) => assert_eq!(device.id, 3),
+            e => panic!("unexpected {e:?}"),
+        }
+        assert_eq!(events.next().unwrap().unwrap(), Event::Detached(3));
+        assert!(events.next().is_none());
+
+        let mut stream = usbmux::Client::connect_to(&path)
+            .unwrap()
+            .connect_port(1, 62078)
+            .unwrap();
+        stream.write_all(b"ping").unwrap();
+        let mut buf = [0u8; 4];
+        stream.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"ping");
+
+        let err = usbmux::Client::connect_to(&path)
+            .unwrap()
+            .connect_port(7, 62078)
+            .err()
+            .unwrap();
+        assert!(matches!(err, usbmux::Error::Mux(2)));
+
+        server.join().unwrap();
+        _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,433 @@
+//! lockdownd protocol.
+//!
+//! Messages are XML property lists prefixed with big endian 32-bit length.
+
+use std::io::{self, Read, Write};
+
+use super::{Dict, Error, Result, Value};
+
+/// Device port of lockdownd.
+pub const PORT: u16 = 62078;
+
+/// Reply of `QueryType` of lockdownd.
+pub const SERVICE_TYPE: &str = "com.apple.mobile.lockdown";
+
+const LABEL: &str = "cidre";
+const MAX_MESSAGE_LEN: usize = 16 << 20;
+
+/// Byte stream lockdown client runs on.
+pub trait Stream: Read + Write + Send {}
+
+impl<T: Read + Write + Send> Stream for T {}
+
+/// TLS client provider.
+///
+/// There is no TLS implementation in this crate. Sessions and services
+/// with SSL enabled need one from the outside.
+pub trait TlsConnector {
+    /// Starts TLS on `stream` authenticated with host certificate and key of `record`.
+    fn connect(&self, stream: Box<dyn Stream>, record: &PairRecord) -> io::Result<Box<dyn Stream>>;
+}
+
+/// Pairing record of the device with this host.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PairRecord {
+    pub host_id: String,
+    pub system_buid: String,
+    /// PEM encoded.
+    pub host_certificate: Vec<u8>,
+    /// PEM encoded.
+    pub host_private_key: Vec<u8>,
+    /// PEM encoded.
+    pub root_certificate: Vec<u8>,
+    /// PEM encoded.
+    pub device_certificate: Vec<u8>,
+}
+
+impl PairRecord {
+    pub fn from_value(value: &Value) -> Result<Self> {
+        let string = |key| {
+            value
+                .get(key)
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .ok_or(Error::UnexpectedResponse)
+        };
+        let data = |key| {
+            value
+                .get(key)
+                .and_then(Value::as_data)
+                .map(<[u8]>::to_vec)
+                .ok_or(Error::UnexpectedResponse)
+        };
+        Ok(Self {
+            host_id: string("HostID")?,
+            system_buid: string("SystemBUID")?,
+            host_certificate: data("HostCertificate")?,
+            host_private_key: data("HostPrivateKey")?,
+            root_certificate: data("RootCertificate")?,
+            device_certificate: data("DeviceCertificate")?,
+        })
+    }
+}
+
+/// Port of the started service.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ServiceInfo {
+    pub port: u16,
+    /// Connection to `port` must be wrapped in TLS.
+    pub ssl: bool,
+}
+
+/// Lockdown queries shared by this crate backends.
+pub trait Lockdown {
+    /// Handle of the started service.
+    type Service;
+
+    fn query_type(&mut self) -> Result<String>;
+
+    /// Value for `key` in `domain`, `None` key returns whole domain.
+    fn value(&mut self, domain: Option<&str>, key: Option<&str>) -> Result<Value>;
+
+    fn start_service(&mut self, name: &str) -> Result<Self::Service>;
+
+    fn string_value(&mut self, key: &str) -> Result<String> {
+        match self.value(None, Some(key))? {
+            Value::String(s) => Ok(s),
+            _ => Err(Error::UnexpectedResponse),
+        }
+    }
+
+    #[inline]
+    fn device_name(&mut self) -> Result<String> {
+        self.string_value("DeviceName")
+    }
+
+    #[inline]
+    fn product_version(&mut self) -> Result<String> {
+        self.string_value("ProductVersion")
+    }
+
+    #[inline]
+    fn unique_device_id(&mut self) -> Result<String> {
+        self.string_value("UniqueDeviceID")
+    }
+
+    /// Battery charge in percents.
+    fn battery_level(&mut self) -> Result<i64> {
+        self.value(
+            Some("com.apple.mobile.battery"),
+            Some("BatteryCurrentCapacity"),
+        )?
+        .as_i64()
+        .ok_or(Error::UnexpectedResponse)
+    }
+}
+
+/// lockdownd client over usbmuxd tunnel or any other [`Stream`].
+pub struct Client {
+    stream: Option<Box<dyn Stream>>,
+    session_id: Option<String>,
+}
+
+impl Client {
+    pub fn new<S: Stream + 'static>(stream: S) -> Self {
+        Self {
+            stream: Some(Box::new(stream)),
+            session_id: None,
+        }
+    }
+
+    #[inline]
+    pub fn session_id(&self) -> Option<&str> {
+        self.session_id.as_deref()
+    }
+
+    fn stream(&mut self) -> Result<&mut Box<dyn Stream>> {
+        self.stream
+            .as_mut()
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotConnected).into())
+    }
+
+    fn send(&mut self, msg: Dict) -> Result {
+        let body = Value::Dict(msg).to_xml();
+        let stream = self.stream()?;
+        stream.write_all(&(body.len() as u32).to_be_bytes())?;
+        stream.write_all(&body)?;
+        stream.flush()?;
+        Ok(())
+    }
+
+    fn recv(&mut self) -> Result<Dict> {
+        let stream = self.stream()?;
+        let mut len = [0u8; 4];
+        stream.read_exact(&mut len)?;
+        let len = u32::from_be_bytes(len) as usize;
+        if len > MAX_MESSAGE_LEN {
+            return Err(Error::UnexpectedResponse);
+        }
+        let mut body = vec![0u8; len];
+        stream.read_exact(&mut body)?;
+        Value::from_xml(&body)?
+            .into_dict()
+            .ok_or(Error::UnexpectedResponse)
+    }
+
+    fn request(&mut self, request: &str, mut msg: Dict) -> Result<Dict> {
+        msg.insert("Request".into(), request.into());
+        msg.insert("Label".into(), LABEL.into());
+        self.send(msg)?;
+        let reply = self.recv()?;
+        if let Some(err) = reply.get("Error") {
+            let err = err.as_str().unwrap_or_default();
+            return Err(Error::Lockdown(err.to_string()));
+        }
+        if reply.get("Request").and_then(Value::as_str) != Some(request) {
+            return Err(Error::UnexpectedResponse);
+        }
+        Ok(reply)
+    }
+
+    /// Starts session with host identity of `record`.
+    ///
+    /// Fails with [`Error::TlsRequired`] if device enables SSL and `tls` is `None`.
+    /// The device expects a TLS handshake right after its reply, so the connection
+    /// is closed in that case and the client can't be used anymore.
+    pub fn start_session(
+        &mut self,
+        record: &PairRecord,
+        tls: Option<&dyn TlsConnector>,
+    ) -> Result<&str> {
+        let mut msg = Dict::new();
+        msg.insert("HostID".into(), record.host_id.as_str().into());
+        msg.insert("SystemBUID".into(), record.system_buid.as_str().into());
+        let reply = self.request("StartSession", msg)?;
+        let Some(id) = reply.get("SessionID").and_then(Value::as_str) else {
+            return Err(Error::UnexpectedResponse);
+        };
+
+        if reply.get("EnableSessionSSL").and_then(Value::as_bool) == Some(true) {
+            let Some(tls) = tls else {
+                self.stream = None;
+                return Err(Error::TlsRequired);
+            };
+            let stream = self.stream.take().ok_or(Error::UnexpectedResponse)?;
+            self.stream = Some(tls.connect(stream, record)?);
+        }
+        Ok(self.session_id.insert(id.to_string()))
+    }
+
+    pub fn stop_session(&mut self) -> Result {
+        let Some(id) = self.session_id.take() else {
+            return Ok(());
+        };
+        let mut msg = Dict::new();
+        msg.insert("SessionID".into(), id.into());
+        self.request("StopSession", msg)?;
+        Ok(())
+    }
+}
+
+impl Lockdown for Client {
+    type Service = ServiceInfo;
+
+    fn query_type(&mut self) -> Result<String> {
+        let reply = self.request("QueryType", Dict::new())?;
+        match reply.get("Type") {
+            Some(Value::String(t)) => Ok(t.clone()),
+            _ => Err(Error::UnexpectedResponse),
+        }
+    }
+
+    fn value(&mut self, domain: Option<&str>, key: Option<&str>) -> Result<Value> {
+        let mut msg = Dict::new();
+        if let Some(domain) = domain {
+            msg.insert("Domain".into(), domain.into());
+        }
+        if let Some(key) = key {
+            msg.insert("Key".into(), key.into());
+        }
+        let mut reply = self.request("GetValue", msg)?;
+        reply.remove("Value").ok_or(Error::UnexpectedResponse)
+    }
+
+    fn start_service(&mut self, name: &str) -> Result<ServiceInfo> {
+        let mut msg = Dict::new();
+        msg.insert("Service".into(), name.into());
+        let reply = self.request("StartService", msg)?;
+        let Some(port) = reply.get("Port").and_then(Value::as_i64) else {
+            return Err(Error::UnexpectedResponse);
+        };
+        let port = u16::try_from(port).map_err(|_| Error::UnexpectedResponse)?;
+        let ssl = reply.get("EnableServiceSSL").and_then(Value::as_bool);
+        Ok(ServiceInfo {
+            port,
+            ssl: ssl.unwrap_or(false),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{self, Read, Write},
+        os::unix::net::UnixStream,
+        sync::atomic::{AtomicUsize, Ordering},
+        thread,
+    };
+
+    use crate::usbmux::{self, lockdown, Dict, Lockdown, PairRecord, ServiceInfo, Value};
+
+    fn read_msg(stream: &mut UnixStream) -> Option<Dict> {
+        let mut len = [0u8; 4];
+        stream.read_exact(&mut len).ok()?;
+        let mut body = vec![0u8; u32::from_be_bytes(len) as usize];
+        stream.read_exact(&mut body).unwrap();
+        Value::from_xml(&body).unwrap().into_dict()
+    }
+
+    fn write_msg(stream: &mut UnixStream, msg: Dict) {
+        let body = Value::Dict(msg).to_xml();
+        stream
+            .write_all(&(body.len() as u32).to_be_bytes())
+            .unwrap();
+        stream.write_all(&body).unwrap();
+    }
+
+    /// Stand-in lockdownd.
+    fn serve(mut stream: UnixStream, ssl: bool) {
+        while let Some(msg) = read_msg(&mut stream) {
+            assert_eq!(msg.get("Label").unwrap().as_str(), Some("cidre"));
+            let request = msg.get("Request").unwrap().as_str().unwrap();
+            let mut reply = Dict::new();
+            reply.insert("Request".into(), request.into());
+            match request {
+                "QueryType" => {
+                    reply.insert("Type".into(), lockdown::SERVICE_TYPE.into());
+                }
+                "GetValue" => {
+                    let domain = msg.get("Domain").and_then(Value::as_str);
+                    let key = msg.get("Key").and_then(Value::as_str);
+                    match (domain, key) {
+                        (None, Some("DeviceName")) => {
+                            reply.insert("Value".into(), "iPhone".into());
+                        }
+                        (None, Some("ProductVersion")) => {
+                            reply.insert("Value".into(), "18.0".into());
+                        }
+                        (Some("com.apple.mobile.battery"), Some("BatteryCurrentCapacity")) => {
+                            reply.insert("Value".into(), 87i64.into());
+                        }
+                        _ => {
+                            reply.insert("Error".into(), "MissingValue".into());
+                        }
+                    }
+                }
+                "StartSession" => {
+                    assert_eq!(msg.get("HostID").unwrap().as_str(), Some("HOST"));
+                    reply.insert("SessionID".into(), "SESSION".into());
+                    reply.insert("EnableSessionSSL".into(), ssl.into());
+                }
+                "StartService" => {
+                    let name = msg.get("Service").unwrap().as_str().unwrap();
+                    if name == "com.apple.afc" {
+                        reply.insert("Port".into(), 49152i64.into());
+                        reply.insert("EnableServiceSSL".into(), true.into());
+                    } else {
+                        reply.insert("Error".into(), "InvalidService".into());
+                    }
+                }
+                "StopSession" => {
+                    assert_eq!(msg.get("SessionID").unwrap().as_str(), Some("SESSION"));
+                }
+                r => panic!("unexpected {r}"),
+            }
+            write_msg(&mut stream, reply);
+        }
+    }
+
+    fn record() -> PairRecord {
+        PairRecord {
+            host_id: "HOST".into(),
+            system_buid: "BUID".into(),
+            host_certificate: vec![],
+            host_private_key: vec![],
+            root_certificate: vec![],
+            device_certificate: vec![],
+        }
+    }
+
+    /// Passes stream through, counting handshakes.
+    struct PlainTls(AtomicUsize);
+
+    impl usbmux::TlsConnector for PlainTls {
+        fn connect(
+            &self,
+            stream: Box<dyn usbmux::Stream>,
+            _record: &PairRecord,
+        ) -> io::Result<Box<dyn usbmux::Stream>> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Ok(stream)
+        }
+    }
+
+    #[test]
+    fn stand_in_server() {
+        let (a, b) = UnixStream::pair().unwrap();
+        let server = thread::spawn(move || serve(b, false));
+
+        let mut client = usbmux::LockdownClient::new(a);
+        assert_eq!(client.query_type().unwrap(), lockdown::SERVICE_TYPE);
+        assert_eq!(client.device_name().unwrap(), "iPhone");
+        assert_eq!(client.product_version().unwrap(), "18.0");
+        assert_eq!(client.battery_level().unwrap(), 87);
+        match client.value(None, Some("Nope")) {
+            Err(usbmux::Error::Lockdown(e)) => assert_eq!(e, "MissingValue"),
+            r => panic!("unexpected {r:?}"),
+        }
+
+        assert_eq!(client.start_session(&record(), None).unwrap(), "SESSION");
+        assert_eq!(
+            client.start_service("com.apple.afc").unwrap(),
+            ServiceInfo {
+                port: 49152,
+                ssl: true
+            }
+        );
+        assert!(client.start_service("com.apple.nope").is_err());
+        client.stop_session().unwrap();
+        assert!(client.session_id().is_none());
+
+        drop(client);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn tls_session() {
+        let (a, b) = UnixStream::pair().unwrap();
+        let server = thread::spawn(move || serve(b, true));
+
+        let mut client = usbmux::LockdownClient::new(a);
+        assert!(matches!(
+            client.start_session(&record(), None),
+            Err(usbmux::Error::TlsRequired)
+        ));
+        assert!(client.session_id().is_none());
+        match client.device_name() {
+            Err(usbmux::Error::Io(e)) => assert_eq!(e.kind(), io::ErrorKind::NotConnected),
+            r => panic!("unexpected {r:?}"),
+        }
+        server.join().unwrap();
+
+        let (a, b) = UnixStream::pair().unwrap();
+        let server = thread::spawn(move || serve(b, true));
+        let mut client = usbmux::LockdownClient::new(a);
+        let tls = PlainTls(AtomicUsize::new(0));
+        client.start_session(&record(), Some(&tls)).unwrap();
+        assert_eq!(tls.0.load(Ordering::SeqCst), 1);
+        assert_eq!(client.device_name().unwrap(), "iPhone");
+
+        drop(client);
+        server.join().unwrap();
+    }
+}
@@ -0,0 +1,814 @@
+//! Minimal property list used by usbmuxd and lockdownd messages.
+//!
+//! Messages are XML, pairing records may come as binary property lists.
+
+use std::{cell::Cell, collections::BTreeMap, fmt::Write};
+
+use super::{Error, Result};
+
+pub type Dict = BTreeMap<String, Value>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    Integer(i64),
+    Real(f64),
+    String(String),
+    Data(Vec<u8>),
+    /// ISO 8601 date as is.
+    Date(String),
+    Array(Vec<Value>),
+    Dict(Dict),
+}
+
+impl Value {
+    #[inline]
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Value::Integer(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// Real or integer value.
+    #[inline]
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Real(v) => Some(*v),
+            Value::Integer(v) => Some(*v as f64),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn as_data(&self) -> Option<&[u8]> {
+        match self {
+            Value::Data(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Value::Array(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn as_dict(&self) -> Option<&Dict> {
+        match self {
+            Value::Dict(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn into_dict(self) -> Option<Dict> {
+        match self {
+            Value::Dict(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Value for `key` if `self` is a dictionary.
+    #[inline]
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.as_dict()?.get(key)
+    }
+
+    /// XML document with `self` as the root object.
+    pub fn to_xml(&self) -> Vec<u8> {
+        let mut out = String::from(HEADER);
+        write_value(&mut out, self, 0);
+        out.push_str("</plist>\n");
+        out.into_bytes()
+    }
+
+    /// Parses binary `bplist00` or XML property list.
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        if data.starts_with(BINARY_MAGIC) {
+            Self::from_binary(data)
+        } else {
+            Self::from_xml(data)
+        }
+    }
+
+    /// Parses `bplist00` property list. Dates are converted to ISO 8601 strings.
+    pub fn from_binary(data: &[u8]) -> Result<Self> {
+        if !data.starts_with(BINARY_MAGIC) || data.len() < BINARY_MAGIC.len() + 32 {
+            return Err(Error::Plist);
+        }
+        let trailer = &data[data.len() - 32..];
+        let be = |b: &[u8]| b.iter().fold(0u64, |n, b| n << 8 | *b as u64);
+        let reader = BinaryReader {
+            data,
+            offset_size: trailer[6] as usize,
+            ref_size: trailer[7] as usize,
+            num_objects: be(&trailer[8..16]),
+            offsets: be(&trailer[24..32]) as usize,
+            decoded: Cell::new(0),
+        };
+        if !(1..=8).contains(&reader.offset_size) || !(1..=8).contains(&reader.ref_size) {
+            return Err(Error::Plist);
+        }
+        reader.object(be(&trailer[16..24]), 0)
+    }
+
+    pub fn from_xml(xml: &[u8]) -> Result<Self> {
+        let s = std::str::from_utf8(xml).map_err(|_| Error::Plist)?;
+        let mut parser = Parser {
+            s,
+            pos: 0,
+            depth: 0,
+        };
+        let mut tag = parser.tag()?;
+        let wrapped = tag.name == "plist";
+        if wrapped {
+            if tag.kind != Kind::Open {
+                return Err(Error::Plist);
+            }
+            tag = parser.tag()?;
+        }
+        let res = parser.value(tag)?;
+        if wrapped {
+            parser.close("plist")?;
+        }
+        Ok(res)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Bool(value)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::Integer(value)
+    }
+}
+
+impl From<u32> for Value {
+    fn from(value: u32) -> Self {
+        Value::Integer(value as i64)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::Real(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::String(value.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::String(value)
+    }
+}
+
+impl From<Vec<u8>> for Value {
+    fn from(value: Vec<u8>) -> Self {
+        Value::Data(value)
+    }
+}
+
+impl From<Vec<Value>> for Value {
+    fn from(value: Vec<Value>) -> Self {
+        Value::Array(value)
+    }
+}
+
+impl From<Dict> for Value {
+    fn from(value: Dict) -> Self {
+        Value::Dict(value)
+    }
+}
+
+const HEADER: &str = concat!(
+    "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+    "<!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" ",
+    "\"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n",
+    "<plist version=\"1.0\">\n"
+);
+
+fn write_value(out: &mut String, value: &Value, depth: usize) {
+    indent(out, depth);
+    match value {
+        Value::Bool(true) => out.push_str("<true/>"),
+        Value::Bool(false) => out.push_str("<false/>"),
+        Value::Integer(v) => _ = write!(out, "<integer>{v}</integer>"),
+        Value::Real(v) => _ = write!(out, "<real>{v}</real>"),
+        Value::String(v) => {
+            out.push_str("<string>");
+            escape(out, v);
+            out.push_str("</string>");
+        }
+        Value::Data(v) => {
+            out.push_str("<data>");
+            base64_encode(out, v);
+            out.push_str("</data>");
+        }
+        Value::Date(v) => {
+            out.push_str("<date>");
+            escape(out, v);
+            out.push_str("</date>");
+        }
+        Value::Array(v) if v.is_empty() => out.push_str("<array/>"),
+        Value::Array(v) => {
+            out.push_str("<array>\n");
+            for item in v {
+                write_value(out, item, depth + 1);
+            }
+            indent(out, depth);
+            out.push_str("</array>");
+        }
+        Value::Dict(v) if v.is_empty() => out.push_str("<dict/>"),
+        Value::Dict(v) => {
+            out.push_str("<dict>\n");
+            for (key, item) in v {
+                indent(out, depth + 1);
+                out.push_str("<key>");
+                escape(out, key);
+                out.push_str("</key>\n");
+                write_value(out, item, depth + 1);
+            }
+            indent(out, depth);
+            out.push_str("</dict>");
+        }
+    }
+    out.push('\n');
+}
+
+fn indent(out: &mut String, depth: usize) {
+    for _ in 0..depth {
+        out.push('\t');
+    }
+}
+
+fn escape(out: &mut String, s: &str) {
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            c => out.push(c),
+        }
+    }
+}
+
+fn unescape(s: &str) -> Result<String> {
+    let mut res = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find('&') {
+        res.push_str(&rest[..start]);
+        rest = &rest[start + 1..];
+        let end = rest.find(';').ok_or(Error::Plist)?;
+        let c = match &rest[..end] {
+            "amp" => '&',
+            "lt" => '<',
+            "gt" => '>',
+            "quot" => '"',
+            "apos" => '\'',
+            e => {
+                let code = if let Some(hex) = e.strip_prefix("#x") {
+                    u32::from_str_radix(hex, 16)
+                } else if let Some(dec) = e.strip_prefix('#') {
+                    dec.parse()
+                } else {
+                    return Err(Error::Plist);
+                };
+                code.ok().and_then(char::from_u32).ok_or(Error::Plist)?
+            }
+        };
+        res.push(c);
+        rest = &rest[end + 1..];
+    }
+    res.push_str(rest);
+    Ok(res)
+}
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64_encode(out: &mut String, data: &[u8]) {
+    for chunk in data.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = u32::from_be_bytes([0, b[0], b[1], b[2]]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+}
+
+fn base64_decode(s: &str) -> Result<Vec<u8>> {
+    let mut res = Vec::with_capacity(s.len() / 4 * 3);
+    let mut n = 0u32;
+    let mut bits = 0;
+    for c in s.bytes() {
+        let v = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' => break,
+            c if c.is_ascii_whitespace() => continue,
+            _ => return Err(Error::Plist),
+        };
+        n = n << 6 | v as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            res.push((n >> bits) as u8);
+        }
+    }
+    Ok(res)
+}
+
+const BINARY_MAGIC: &[u8] = b"bplist00";
+
+/// Nesting limit, guards against reference cycles and deeply nested XML.
+const MAX_DEPTH: usize = 64;
+
+/// Limit of decoded objects, shared references are decoded each time they
+/// are met, so small plists could expand exponentially.
+const MAX_OBJECTS: usize = 1 << 20;
+
+struct BinaryReader<'a> {
+    data: &'a [u8],
+    offset_size: usize,
+    ref_size: usize,
+    num_objects: u64,
+    offsets: usize,
+    decoded: Cell<usize>,
+}
+
+impl BinaryReader<'_> {
+    fn bytes(&self, pos: usize, len: usize) -> Result<&[u8]> {
+        let end = pos.checked_add(len).ok_or(Error::Plist)?;
+        self.data.get(pos..end).ok_or(Error::Plist)
+    }
+
+    fn uint(&self, pos: usize, len: usize) -> Result<u64> {
+        let bytes = self.bytes(pos, len)?;
+        Ok(bytes.iter().fold(0u64, |n, b| n << 8 | *b as u64))
+    }
+
+    fn ref_at(&self, pos: usize, i: usize) -> Result<u64> {
+        let at = i
+            .checked_mul(self.ref_size)
+            .and_then(|n| n.checked_add(pos))
+            .ok_or(Error::Plist)?;
+        self.uint(at, self.ref_size)
+    }
+
+    /// Length of the object with `marker` at `pos` and position of its payload.
+    fn len(&self, marker: u8, pos: usize) -> Result<(usize, usize)> {
+        if marker & 0x0f != 0x0f {
+            return Ok(((marker & 0x0f) as usize, pos + 1));
+        }
+        let int_marker = *self.data.get(pos + 1).ok_or(Error::Plist)?;
+        if int_marker >> 4 != 0x1 || int_marker & 0x0f > 3 {
+            return Err(Error::Plist);
+        }
+        let size = 1 << (int_marker & 0x0f);
+        let len = self.uint(pos + 2, size)?;
+        Ok((len as usize, pos + 2 + size))
+    }
+
+    fn object(&self, index: u64, depth: usize) -> Result<Value> {
+        if index >= self.num_objects || depth > MAX_DEPTH || self.decoded.get() >= MAX_OBJECTS {
+            return Err(Error::Plist);
+        }
+        self.decoded.set(self.decoded.get() + 1);
+        let at = usize::try_from(index)
+            .ok()
+            .and_then(|i| i.checked_mul(self.offset_size))
+            .and_then(|n| n.checked_add(self.offsets))
+            .ok_or(Error::Plist)?;
+        let pos = self.uint(at, self.offset_size)?;
+        let pos = pos as usize;
+        let marker = *self.data.get(pos).ok_or(Error::Plist)?;
+        let low = (marker & 0x0f) as usize;
+        let res = match marker >> 4 {
+            0x0 if marker == 0x08 => Value::Bool(false),
+            0x0 if marker == 0x09 => Value::Bool(true),
+            0x1 if low <= 3 => {
+                let size = 1 << low;
+                let n = self.uint(pos + 1, size)?;
+                // 8 byte integers are signed, shorter ones are not
+                Value::Integer(n as i64)
+            }
+            0x2 if low == 2 => {
+                let bits = self.uint(pos + 1, 4)? as u32;
+                Value::Real(f32::from_bits(bits) as f64)
+            }
+            0x2 if low == 3 => Value::Real(f64::from_bits(self.uint(pos + 1, 8)?)),
+            0x3 if marker == 0x33 => {
+                let secs = f64::from_bits(self.uint(pos + 1, 8)?);
+                Value::Date(iso_date(secs)?)
+            }
+            0x4 => {
+                let (len, pos) = self.len(marker, pos)?;
+                Value::Data(self.bytes(pos, len)?.to_vec())
+            }
+            0x5 => {
+                let (len, pos) = self.len(marker, pos)?;
+                let s = std::str::from_utf8(self.bytes(pos, len)?).map_err(|_| Error::Plist)?;
+                Value::String(s.to_string())
+            }
+            0x6 => {
+                let (len, pos) = self.len(marker, pos)?;
+                let bytes = self.bytes(pos, len.checked_mul(2).ok_or(Error::Plist)?)?;
+                let units = bytes
+                    .chunks_exact(2)
+                    .map(|c| u16::from_be_bytes([c[0], c[1]]));
+                let s: std::result::Result<String, _> = char::decode_utf16(units).collect();
+                Value::String(s.map_err(|_| Error::Plist)?)
+            }
+            0x8 => Value::Integer(self.uint(pos + 1, low + 1)? as i64),
+            0xa => {
+                let (len, pos) = self.len(marker, pos)?;
+                // `len` comes from the file, grow as refs are actually decoded
+                let mut items = Vec::new();
+                for i in 0..len {
+                    items.push(self.object(self.ref_at(pos, i)?, depth + 1)?);
+                }
+                Value::Array(items)
+            }
+            0xd => {
+                let (len, pos) = self.len(marker, pos)?;
+                let mut dict = Dict::new();
+                for i in 0..len {
+                    let Value::String(key) = self.object(self.ref_at(pos, i)?, depth + 1)? else {
+                        return Err(Error::Plist);
+                    };
+                    let value = self.object(self.ref_at(pos, len + i)?, depth + 1)?;
+                    dict.insert(key, value);
+                }
+                Value::Dict(dict)
+            }
+            _ => return Err(Error::Plist),
+        };
+        Ok(res)
+    }
+}
+
+/// Formats seconds since 2001-01-01 as `YYYY-MM-DDTHH:MM:SSZ`.
+fn iso_date(secs: f64) -> Result<String> {
+    if !secs.is_finite() {
+        return Err(Error::Plist);
+    }
+    let secs = (secs.floor() as i64)
+        .checked_add(978_307_200)
+        .ok_or(Error::Plist)?;
+    let (days, rem) = (secs.div_euclid(86_400), secs.rem_euclid(86_400));
+    // civil from days, http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    Ok(format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        rem / 3600,
+        rem / 60 % 60,
+        rem % 60
+    ))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Open,
+    Close,
+    Empty,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Tag<'a> {
+    name: &'a str,
+    kind: Kind,
+}
+
+struct Parser<'a> {
+    s: &'a str,
+    pos: usize,
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    /// Skips whitespace, declarations, doctype and comments.
+    fn skip_misc(&mut self) -> Result {
+        loop {
+            let rest = self.s[self.pos..].trim_start();
+            self.pos = self.s.len() - rest.len();
+            let end = if rest.starts_with("<?") {
+                rest.find("?>").map(|i| i + 2)
+            } else if rest.starts_with("<!--") {
+                rest.find("-->").map(|i| i + 3)
+            } else if rest.starts_with("<!") {
+                rest.find('>').map(|i| i + 1)
+            } else {
+                return Ok(());
+            };
+            self.pos += end.ok_or(Error::Plist)?;
+        }
+    }
+
+    fn tag(&mut self) -> Result<Tag<'a>> {
+        self.skip_misc()?;
+        let rest = &self.s[self.pos..];
+        if !rest.starts_with('<') {
+            return Err(Error::Plist);
+        }
+        let end = rest.find('>').ok_or(Error::Plist)?;
+        let inner = &rest[1..end];
+        self.pos += end + 1;
+        let (kind, inner) = if let Some(name) = inner.strip_prefix('/') {
+            (Kind::Close, name)
+        } else if let Some(name) = inner.strip_suffix('/') {
+            (Kind::Empty, name)
+        } else {
+            (Kind::Open, inner)
+        };
+        let name = inner.split_ascii_whitespace().next().ok_or(Error::Plist)?;
+        Ok(Tag { name, kind })
+    }
+
+    fn close(&mut self, name: &str) -> Result {
+        let tag = self.tag()?;
+        if tag.kind != Kind::Close || tag.name != name {
+            return Err(Error::Plist);
+        }
+        Ok(())
+    }
+
+    fn text(&mut self, tag: Tag) -> Result<String> {
+        if tag.kind == Kind::Empty {
+            return Ok(String::new());
+        }
+        let rest = &self.s[self.pos..];
+        let end = rest.find('<').ok_or(Error::Plist)?;
+        self.pos += end;
+        self.close(tag.name)?;
+        unescape(&rest[..end])
+    }
+
+    fn value(&mut self, tag: Tag) -> Result<Value> {
+        if tag.kind == Kind::Close || self.depth >= MAX_DEPTH {
+            return Err(Error::Plist);
+        }
+        self.depth += 1;
+        let res = self.nested(tag);
+        self.depth -= 1;
+        res
+    }
+
+    fn nested(&mut self, tag: Tag) -> Result<Value> {
+        let res = match tag.name {
+            "true" | "false" => {
+                if tag.kind == Kind::Open {
+                    self.close(tag.name)?;
+                }
+                Value::Bool(tag.name == "true")
+            }
+            "integer" => {
+                let text = self.text(tag)?;
+                Value::Integer(text.trim().parse().map_err(|_| Error::Plist)?)
+            }
+            "real" => {
+                let text = self.text(tag)?;
+                Value::Real(text.trim().parse().map_err(|_| Error::Plist)?)
+            }
+            "string" => Value::String(self.text(tag)?),
+            "date" => Value::Date(self.text(tag)?.trim().to_string()),
+            "data" => Value::Data(base64_decode(&self.text(tag)?)?),
+            "array" => {
+                let mut items = Vec::new();
+                if tag.kind == Kind::Open {
+                    loop {
+                        let tag = self.tag()?;
+                        if tag.kind == Kind::Close && tag.name == "array" {
+                            break;
+                        }
+                        items.push(self.value(tag)?);
+                    }
+                }
+                Value::Array(items)
+            }
+            "dict" => {
+                let mut dict = Dict::new();
+                if tag.kind == Kind::Open {
+                    loop {
+                        let tag = self.tag()?;
+                        if tag.kind == Kind::Close && tag.name == "dict" {
+                            break;
+                        }
+                        if tag.name != "key" {
+                            return Err(Error::Plist);
+                        }
+                        let key = self.text(tag)?;
+                        let tag = self.tag()?;
+                        dict.insert(key, self.value(tag)?);
+                    }
+                }
+                Value::Dict(dict)
+            }
+            _ => return Err(Error::Plist),
+        };
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::usbmux::{Dict, Value};
+
+    #[test]
+    fn round_trip() {
+        let mut dict = Dict::new();
+        dict.insert("Bool".into(), true.into());
+        dict.insert("Integer".into(), (-42i64).into());
+        dict.insert("Real".into(), 0.5.into());
+        dict.insert("String".into(), "a < b & c".into());
+        dict.insert("Empty".into(), "".into());
+        dict.insert("Data".into(), vec![0u8, 1, 2, 3, 254, 255, 7].into());
+        dict.insert(
+            "Array".into(),
+            vec![Value::Dict(Dict::new()), Value::Array(vec![])].into(),
+        );
+        let value = Value::Dict(dict);
+
+        let xml = value.to_xml();
+        let s = std::str::from_utf8(&xml).unwrap();
+        assert!(s.contains("<string>a &lt; b &amp; c</string>"));
+        assert!(s.contains("<data>AAECA/7/Bw==</data>"));
+        assert_eq!(Value::from_xml(&xml).unwrap(), value);
+    }
+
+    #[test]
+    fn apple_style() {
+        let xml = br#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<!-- comment -->
+<dict>
+	<key>DeviceName</key>
+	<string>Yury&#x2019;s iPhone</string>
+	<key>Data</key>
+	<data>
+	aGVs
+	bG8=
+	</data>
+	<key>Flag</key>
+	<false/>
+	<key>Nothing</key>
+	<string/>
+</dict>
+</plist>
+"#;
+        let value = Value::from_xml(xml).unwrap();
+        assert_eq!(
+            value.get("DeviceName").unwrap().as_str(),
+            Some("Yury\u{2019}s iPhone")
+        );
+        assert_eq!(value.get("Data").unwrap().as_data(), Some(&b"hello"[..]));
+        assert_eq!(value.get("Flag").unwrap().as_bool(), Some(false));
+        assert_eq!(value.get("Nothing").unwrap().as_str(), Some(""));
+
+        assert!(Value::from_xml(b"<plist><dict><key>a</key></dict></plist>").is_err());
+        assert!(Value::from_xml(b"<plist><integer>x</integer></plist>").is_err());
+    }
+
+    #[test]
+    fn binary() {
+        // plistlib.dumps(..., fmt=FMT_BINARY)
+        let hex = concat!(
+            "62706c6973743030d80102030405060708090c0d0e0f10111251415144544461",
+            "746556486f73744944514e534e656751545155a20a0b5178233ff80000000000",
+            "004201023341c5c858b8000000514811012c13fffffffffffffffe096200e920",
+            "1908191b1d22292b2f3133363841444d4f525b5c000000000000010100000000",
+            "0000001300000000000000000000000000000061",
+        );
+        let data: Vec<u8> = (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect();
+
+        let value = Value::from_bytes(&data).unwrap();
+        assert_eq!(value.get("HostID").unwrap().as_str(), Some("H"));
+        assert_eq!(value.get("N").unwrap().as_i64(), Some(300));
+        assert_eq!(value.get("Neg").unwrap().as_i64(), Some(-2));
+        assert_eq!(value.get("D").unwrap().as_data(), Some(&[1u8, 2][..]));
+        assert_eq!(value.get("T").unwrap().as_bool(), Some(true));
+        assert_eq!(
+            value.get("A").unwrap().as_array().unwrap(),
+            &[Value::String("x".into()), Value::Real(1.5)]
+        );
+        assert_eq!(value.get("U").unwrap().as_str(), Some("\u{e9}\u{2019}"));
+        assert_eq!(
+            value.get("Date"),
+            Some(&Value::Date("2024-02-29T12:34:56Z".into()))
+        );
+
+        assert!(Value::from_bytes(&data[..data.len() - 1]).is_err());
+    }
+
+    /// Binary plist of encoded `objects` with 1 byte offsets and refs.
+    fn bplist(objects: &[Vec<u8>], top: u64) -> Vec<u8> {
+        let mut data = b"bplist00".to_vec();
+        let mut offsets = Vec::new();
+        for o in objects {
+            offsets.push(data.len() as u8);
+            data.extend_from_slice(o);
+        }
+        let table = data.len() as u64;
+        data.extend_from_slice(&offsets);
+        data.extend_from_slice(&[0, 0, 0, 0, 0, 0, 1, 1]);
+        data.extend_from_slice(&(objects.len() as u64).to_be_bytes());
+        data.extend_from_slice(&top.to_be_bytes());
+        data.extend_from_slice(&table.to_be_bytes());
+        data
+    }
+
+    /// Arrays referencing the next one twice, `2^n` strings when expanded.
+    fn chain(n: u8) -> Vec<Vec<u8>> {
+        let mut objects: Vec<_> = (1..=n).map(|next| vec![0xa2, next, next]).collect();
+        objects.push(vec![0x51, b'x']);
+        objects
+    }
+
+    #[test]
+    fn binary_limits() {
+        let value = Value::from_bytes(&bplist(&chain(3), 0)).unwrap();
+        let leaf = &value.as_array().unwrap()[1].as_array().unwrap()[0];
+        assert_eq!(leaf.as_array().unwrap()[1].as_str(), Some("x"));
+
+        // shared references don't expand without bound
+        assert!(Value::from_bytes(&bplist(&chain(40), 0)).is_err());
+
+        // offset table past address space
+        let mut data = bplist(&chain(1), 1);
+        let len = data.len();
+        data[len - 8..].copy_from_slice(&u64::MAX.to_be_bytes());
+        assert!(Value::from_bytes(&data).is_err());
+
+        // array claiming 2^40 entries
+        let mut array = vec![0xaf, 0x13];
+        array.extend_from_slice(&(1u64 << 40).to_be_bytes());
+        assert!(Value::from_bytes(&bplist(&[array], 0)).is_err());
+
+        let mut date = vec![0x33];
+        date.extend_from_slice(&1e300f64.to_be_bytes());
+        assert!(Value::from_bytes(&bplist(&[date.clone()], 0)).is_err());
+        date[1..].copy_from_slice(&f64::NAN.to_be_bytes());
+        assert!(Value::from_bytes(&bplist(&[date], 0)).is_err());
+    }
+
+    #[test]
+    fn xml_depth() {
+        let nested = |n| {
+            let mut xml = "<array>".repeat(n);
+            xml.push_str(&"</array>".repeat(n));
+            xml
+        };
+        assert!(Value::from_xml(nested(8).as_bytes()).is_ok());
+        assert!(Value::from_xml(nested(200_000).as_bytes()).is_err());
+    }
+}
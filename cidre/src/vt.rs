@@ -8,6 +8,7 @@ pub use session::Session;
 
 pub mod compression;
 pub use compression::properties as compression_properties;
+pub use compression::EncoderConfig;
 pub use compression::Session as CompressionSession;
 
 pub mod decompression;
@@ -2,11 +2,15 @@ pub mod session;
 pub use session::Session;
 
 pub mod properties;
+pub use properties::alpha_channel_mode;
 pub use properties::h264_entropy_mode;
 pub use properties::hdr_metadata_insertion_mode;
 pub use properties::keys;
 pub use properties::profile_level;
 
+pub mod config;
+pub use config::EncoderConfig;
+
 #[cfg(test)]
 mod tests {
     use std::ffi::c_void;
@@ -0,0 +1,954 @@
+//! Typed compression session configuration.
+//!
+//! [`EncoderConfig`] collects codec specific settings, checks combinations
+//! the encoders reject and turns them into compression properties.
+
+use std::fmt;
+
+use crate::{cm, os};
+
+mod props;
+pub use props::supported_keys;
+
+/// Compression property set by [`EncoderConfig`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Key {
+    RealTime,
+    AllowFrameReordering,
+    AllowTemporalCompression,
+    AllowOpenGop,
+    MaxKeyFrameInterval,
+    MaxKeyFrameIntervalDuration,
+    AverageBitRate,
+    DataRateLimits,
+    ConstantBitRate,
+    Quality,
+    TargetQualityForAlpha,
+    ExpectedFrameRate,
+    MaxFrameDelayCount,
+    PrioritizeEncodingSpeedOverQuality,
+    MaximizePowerEfficiency,
+    ProfileLevel,
+    H264EntropyMode,
+    MaxH264SliceBytes,
+    OutputBitDepth,
+    AlphaChannelMode,
+}
+
+impl Key {
+    pub const ALL: [Self; 20] = [
+        Self::RealTime,
+        Self::AllowFrameReordering,
+        Self::AllowTemporalCompression,
+        Self::AllowOpenGop,
+        Self::MaxKeyFrameInterval,
+        Self::MaxKeyFrameIntervalDuration,
+        Self::AverageBitRate,
+        Self::DataRateLimits,
+        Self::ConstantBitRate,
+        Self::Quality,
+        Self::TargetQualityForAlpha,
+        Self::ExpectedFrameRate,
+        Self::MaxFrameDelayCount,
+        Self::PrioritizeEncodingSpeedOverQuality,
+        Self::MaximizePowerEfficiency,
+        Self::ProfileLevel,
+        Self::H264EntropyMode,
+        Self::MaxH264SliceBytes,
+        Self::OutputBitDepth,
+        Self::AlphaChannelMode,
+    ];
+
+    /// Name without `kVTCompressionPropertyKey_` prefix.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::RealTime => "RealTime",
+            Self::AllowFrameReordering => "AllowFrameReordering",
+            Self::AllowTemporalCompression => "AllowTemporalCompression",
+            Self::AllowOpenGop => "AllowOpenGOP",
+            Self::MaxKeyFrameInterval => "MaxKeyFrameInterval",
+            Self::MaxKeyFrameIntervalDuration => "MaxKeyFrameIntervalDuration",
+            Self::AverageBitRate => "AverageBitRate",
+            Self::DataRateLimits => "DataRateLimits",
+            Self::ConstantBitRate => "ConstantBitRate",
+            Self::Quality => "Quality",
+            Self::TargetQualityForAlpha => "TargetQualityForAlpha",
+            Self::ExpectedFrameRate => "ExpectedFrameRate",
+            Self::MaxFrameDelayCount => "MaxFrameDelayCount",
+            Self::PrioritizeEncodingSpeedOverQuality => "PrioritizeEncodingSpeedOverQuality",
+            Self::MaximizePowerEfficiency => "MaximizePowerEfficiency",
+            Self::ProfileLevel => "ProfileLevel",
+            Self::H264EntropyMode => "H264EntropyMode",
+            Self::MaxH264SliceBytes => "MaxH264SliceBytes",
+            Self::OutputBitDepth => "OutputBitDepth",
+            Self::AlphaChannelMode => "AlphaChannelMode",
+        }
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "kVTCompressionPropertyKey_{}", self.name())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum H264Profile {
+    Baseline,
+    ConstrainedBaseline,
+    Main,
+    Extended,
+    High,
+    ConstrainedHigh,
+}
+
+impl H264Profile {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Baseline => "Baseline",
+            Self::ConstrainedBaseline => "ConstrainedBaseline",
+            Self::Main => "Main",
+            Self::Extended => "Extended",
+            Self::High => "High",
+            Self::ConstrainedHigh => "ConstrainedHigh",
+        }
+    }
+
+    /// Levels with `kVTProfileLevel_H264_*` constant for the profile.
+    pub fn levels(&self) -> &'static [H264Level] {
+        use H264Level::*;
+        match self {
+            Self::Baseline => &[
+                Auto, L1_3, L3_0, L3_1, L3_2, L4_0, L4_1, L4_2, L5_0, L5_1, L5_2,
+            ],
+            Self::Main | Self::High => {
+                &[Auto, L3_0, L3_1, L3_2, L4_0, L4_1, L4_2, L5_0, L5_1, L5_2]
+            }
+            Self::Extended => &[Auto, L5_0],
+            Self::ConstrainedBaseline | Self::ConstrainedHigh => &[Auto],
+        }
+    }
+
+    /// CABAC is defined for Main and High profiles only.
+    pub fn supports_cabac(&self) -> bool {
+        matches!(self, Self::Main | Self::High | Self::ConstrainedHigh)
+    }
+
+    /// Constrained profiles and Baseline have no B-frames.
+    pub fn supports_frame_reordering(&self) -> bool {
+        matches!(self, Self::Main | Self::Extended | Self::High)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum H264Level {
+    #[default]
+    Auto,
+    L1_3,
+    L3_0,
+    L3_1,
+    L3_2,
+    L4_0,
+    L4_1,
+    L4_2,
+    L5_0,
+    L5_1,
+    L5_2,
+}
+
+impl H264Level {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Auto => "AutoLevel",
+            Self::L1_3 => "1_3",
+            Self::L3_0 => "3_0",
+            Self::L3_1 => "3_1",
+            Self::L3_2 => "3_2",
+            Self::L4_0 => "4_0",
+            Self::L4_1 => "4_1",
+            Self::L4_2 => "4_2",
+            Self::L5_0 => "5_0",
+            Self::L5_1 => "5_1",
+            Self::L5_2 => "5_2",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HevcProfile {
+    Main,
+    Main10,
+    Main42210,
+}
+
+impl HevcProfile {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Main => "Main",
+            Self::Main10 => "Main10",
+            Self::Main42210 => "Main42210",
+        }
+    }
+
+    /// Output bit depths the profile can carry.
+    pub fn bit_depths(&self) -> &'static [i32] {
+        match self {
+            Self::Main => &[8],
+            Self::Main10 | Self::Main42210 => &[8, 10],
+        }
+    }
+}
+
+/// `kVTProfileLevel_*` value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProfileLevel {
+    H264(H264Profile, H264Level),
+    Hevc(HevcProfile),
+}
+
+impl fmt::Display for ProfileLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::H264(p, l) => write!(f, "H264_{}_{}", p.name(), l.name()),
+            Self::Hevc(p) => write!(f, "HEVC_{}_AutoLevel", p.name()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EntropyMode {
+    Cavlc,
+    Cabac,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum AlphaChannelMode {
+    #[default]
+    Straight,
+    Premultiplied,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProResFlavor {
+    Proxy,
+    Lt,
+    Standard,
+    Hq,
+    _4444,
+    _4444Xq,
+}
+
+impl ProResFlavor {
+    pub fn video_codec(&self) -> cm::VideoCodec {
+        match self {
+            Self::Proxy => cm::VideoCodec::APPLE_PRO_RES422_PROXY,
+            Self::Lt => cm::VideoCodec::APPLE_PRO_RES422_LT,
+            Self::Standard => cm::VideoCodec::APPLE_PRO_RES422,
+            Self::Hq => cm::VideoCodec::APPLE_PRO_RES422_HQ,
+            Self::_4444 => cm::VideoCodec::APPLE_PRO_RES4444,
+            Self::_4444Xq => cm::VideoCodec::APPLE_PRO_RES4444_XQ,
+        }
+    }
+}
+
+/// Byte limit over a window of `secs` seconds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DataRateLimit {
+    pub bytes: i64,
+    pub secs: f64,
+}
+
+impl DataRateLimit {
+    pub fn bits_per_sec(&self) -> f64 {
+        self.bytes as f64 * 8.0 / self.secs
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct H264Config {
+    pub profile: H264Profile,
+    pub level: H264Level,
+    pub entropy_mode: Option<EntropyMode>,
+    pub max_slice_bytes: Option<i32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HevcConfig {
+    pub profile: HevcProfile,
+    pub output_bit_depth: Option<i32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Codec {
+    H264(H264Config),
+    Hevc(HevcConfig),
+    HevcWithAlpha {
+        hevc: HevcConfig,
+        alpha_channel_mode: AlphaChannelMode,
+        target_quality_for_alpha: Option<f64>,
+    },
+    ProRes(ProResFlavor),
+}
+
+impl Codec {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::H264(_) => "H.264",
+            Self::Hevc(_) => "HEVC",
+            Self::HevcWithAlpha { .. } => "HEVC with alpha",
+            Self::ProRes(_) => "ProRes",
+        }
+    }
+
+    pub fn video_codec(&self) -> cm::VideoCodec {
+        match self {
+            Self::H264(_) => cm::VideoCodec::H264,
+            Self::Hevc(_) => cm::VideoCodec::HEVC,
+            Self::HevcWithAlpha { .. } => cm::VideoCodec::HEVC_WITH_ALPHA,
+            Self::ProRes(flavor) => flavor.video_codec(),
+        }
+    }
+
+    /// Whether the codec encoders accept `key`.
+    pub fn accepts(&self, key: Key) -> bool {
+        use Key::*;
+        match self {
+            Self::H264(_) => !matches!(
+                key,
+                AllowOpenGop | TargetQualityForAlpha | OutputBitDepth | AlphaChannelMode
+            ),
+            Self::Hevc(_) => !matches!(
+                key,
+                TargetQualityForAlpha | H264EntropyMode | MaxH264SliceBytes | AlphaChannelMode
+            ),
+            Self::HevcWithAlpha { .. } => !matches!(key, H264EntropyMode | MaxH264SliceBytes),
+            // intra only, rate is defined by flavor
+            Self::ProRes(_) => matches!(
+                key,
+                RealTime
+                    | Quality
+                    | ExpectedFrameRate
+                    | PrioritizeEncodingSpeedOverQuality
+                    | MaximizePowerEfficiency
+            ),
+        }
+    }
+
+    fn hevc(&self) -> Option<&HevcConfig> {
+        match self {
+            Self::Hevc(hevc) | Self::HevcWithAlpha { hevc, .. } => Some(hevc),
+            _ => None,
+        }
+    }
+}
+
+/// Typed value of [`Key`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    I32(i32),
+    I64(i64),
+    F64(f64),
+    DataRateLimits(Vec<DataRateLimit>),
+    ProfileLevel(ProfileLevel),
+    EntropyMode(EntropyMode),
+    AlphaChannelMode(AlphaChannelMode),
+}
+
+/// Reason of config rejection.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigError {
+    /// The codec has no such setting.
+    NotApplicable {
+        key: Key,
+        codec: &'static str,
+    },
+    OutOfRange(Key),
+    LevelUnavailable(H264Profile, H264Level),
+    CabacRequiresMainOrHigh(H264Profile),
+    FrameReorderingUnsupported(H264Profile),
+    FrameReorderingInRealTime,
+    FrameReorderingWithoutDelay,
+    /// Constant bit rate is set together with the key.
+    ConstantBitRateConflict(Key),
+    DuplicateDataRateWindow(f64),
+    /// Limit in bits per second is below the average bit rate.
+    DataRateLimitBelowAverage {
+        limit: f64,
+        average: i64,
+    },
+    BitDepthUnsupported {
+        profile: HevcProfile,
+        depth: i32,
+    },
+    /// The encoder doesn't list the key in its supported properties.
+    Unsupported(Key),
+    Os(os::Error),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotApplicable { key, codec } => write!(f, "{key} is not applicable to {codec}"),
+            Self::OutOfRange(key) => write!(f, "{key} is out of range"),
+            Self::LevelUnavailable(p, l) => write!(
+                f,
+                "level {} is not available for H.264 {} profile",
+                l.name(),
+                p.name()
+            ),
+            Self::CabacRequiresMainOrHigh(p) => write!(
+                f,
+                "CABAC requires Main or High profile, {} supports CAVLC only",
+                p.name()
+            ),
+            Self::FrameReorderingUnsupported(p) => {
+                write!(f, "H.264 {} profile has no B-frames", p.name())
+            }
+            Self::FrameReorderingInRealTime => {
+                f.write_str("frame reordering adds latency and conflicts with real-time encoding")
+            }
+            Self::FrameReorderingWithoutDelay => {
+                f.write_str("frame reordering requires max frame delay count above zero")
+            }
+            Self::ConstantBitRateConflict(key) => {
+                write!(f, "constant bit rate can't be combined with {key}")
+            }
+            Self::DuplicateDataRateWindow(secs) => {
+                write!(f, "data rate limit window of {secs}s is set twice")
+            }
+            Self::DataRateLimitBelowAverage { limit, average } => write!(
+                f,
+                "data rate limit of {limit} bps is below average bit rate of {average} bps"
+            ),
+            Self::BitDepthUnsupported { profile, depth } => write!(
+                f,
+                "HEVC {} profile doesn't support {depth}-bit output",
+                profile.name()
+            ),
+            Self::Unsupported(key) => write!(f, "{key} is not supported by the encoder"),
+            Self::Os(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl From<os::Error> for ConfigError {
+    fn from(value: os::Error) -> Self {
+        Self::Os(value)
+    }
+}
+
+pub type Result<T = ()> = std::result::Result<T, Vec<ConfigError>>;
+
+/// Compression session settings, unset fields keep encoder defaults.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EncoderConfig {
+    pub codec: Codec,
+    pub real_time: Option<bool>,
+    pub allow_frame_reordering: Option<bool>,
+    pub allow_temporal_compression: Option<bool>,
+    pub allow_open_gop: Option<bool>,
+    pub max_key_frame_interval: Option<i32>,
+    /// Seconds, `0` for unlimited.
+    pub max_key_frame_interval_duration: Option<f64>,
+    /// Bits per second.
+    pub average_bit_rate: Option<i64>,
+    pub data_rate_limits: Vec<DataRateLimit>,
+    pub constant_bit_rate: Option<i64>,
+    pub quality: Option<f64>,
+    pub expected_frame_rate: Option<f64>,
+    /// `-1` for unlimited.
+    pub max_frame_delay_count: Option<i32>,
+    pub prioritize_encoding_speed_over_quality: Option<bool>,
+    pub maximize_power_efficiency: Option<bool>,
+}
+
+impl EncoderConfig {
+    pub fn new(codec: Codec) -> Self {
+        Self {
+            codec,
+            real_time: None,
+            allow_frame_reordering: None,
+            allow_temporal_compression: None,
+            allow_open_gop: None,
+            max_key_frame_interval: None,
+            max_key_frame_interval_duration: None,
+            average_bit_rate: None,
+            data_rate_limits: Vec::new(),
+            constant_bit_rate: None,
+            quality: None,
+            expected_frame_rate: None,
+            max_frame_delay_count: None,
+            prioritize_encoding_speed_over_quality: None,
+            maximize_power_efficiency: None,
+        }
+    }
+
+    pub fn h264(profile: H264Profile, level: H264Level) -> Self {
+        Self::new(Codec::H264(H264Config {
+            profile,
+            level,
+            entropy_mode: None,
+            max_slice_bytes: None,
+        }))
+    }
+
+    pub fn hevc(profile: HevcProfile) -> Self {
+        Self::new(Codec::Hevc(HevcConfig {
+            profile,
+            output_bit_depth: None,
+        }))
+    }
+
+    pub fn hevc_with_alpha(profile: HevcProfile, alpha_channel_mode: AlphaChannelMode) -> Self {
+        Self::new(Codec::HevcWithAlpha {
+            hevc: HevcConfig {
+                profile,
+                output_bit_depth: None,
+            },
+            alpha_channel_mode,
+            target_quality_for_alpha: None,
+        })
+    }
+
+    pub fn pro_res(flavor: ProResFlavor) -> Self {
+        Self::new(Codec::ProRes(flavor))
+    }
+
+    pub fn h264_mut(&mut self) -> Option<&mut H264Config> {
+        match &mut self.codec {
+            Codec::H264(h264) => Some(h264),
+            _ => None,
+        }
+    }
+
+    pub fn hevc_mut(&mut self) -> Option<&mut HevcConfig> {
+        match &mut self.codec {
+            Codec::Hevc(hevc) | Codec::HevcWithAlpha { hevc, .. } => Some(hevc),
+            _ => None,
+        }
+    }
+
+    /// Properties to set, in [`Key::ALL`] order.
+    pub fn entries(&self) -> Vec<(Key, Value)> {
+        let mut res = Vec::new();
+        let mut push = |key, val: Option<Value>| {
+            if let Some(val) = val {
+                res.push((key, val));
+            }
+        };
+        let profile_level = match &self.codec {
+            Codec::H264(h264) => Some(ProfileLevel::H264(h264.profile, h264.level)),
+            Codec::Hevc(hevc) | Codec::HevcWithAlpha { hevc, .. } => {
+                Some(ProfileLevel::Hevc(hevc.profile))
+            }
+            Codec::ProRes(_) => None,
+        };
+        let (entropy_mode, max_slice_bytes) = match &self.codec {
+            Codec::H264(h264) => (h264.entropy_mode, h264.max_slice_bytes),
+            _ => (None, None),
+        };
+        let (alpha_channel_mode, target_quality_for_alpha) = match &self.codec {
+            Codec::HevcWithAlpha {
+                alpha_channel_mode,
+                target_quality_for_alpha,
+                ..
+            } => (Some(*alpha_channel_mode), *target_quality_for_alpha),
+            _ => (None, None),
+        };
+        let limits = (!self.data_rate_limits.is_empty())
+            .then(|| Value::DataRateLimits(self.data_rate_limits.clone()));
+
+        push(Key::RealTime, self.real_time.map(Value::Bool));
+        push(
+            Key::AllowFrameReordering,
+            self.allow_frame_reordering.map(Value::Bool),
+        );
+        push(
+            Key::AllowTemporalCompression,
+            self.allow_temporal_compression.map(Value::Bool),
+        );
+        push(Key::AllowOpenGop, self.allow_open_gop.map(Value::Bool));
+        push(
+            Key::MaxKeyFrameInterval,
+            self.max_key_frame_interval.map(Value::I32),
+        );
+        push(
+            Key::MaxKeyFrameIntervalDuration,
+            self.max_key_frame_interval_duration.map(Value::F64),
+        );
+        push(Key::AverageBitRate, self.average_bit_rate.map(Value::I64));
+        push(Key::DataRateLimits, limits);
+        push(Key::ConstantBitRate, self.constant_bit_rate.map(Value::I64));
+        push(Key::Quality, self.quality.map(Value::F64));
+        push(
+            Key::TargetQualityForAlpha,
+            target_quality_for_alpha.map(Value::F64),
+        );
+        push(
+            Key::ExpectedFrameRate,
+            self.expected_frame_rate.map(Value::F64),
+        );
+        push(
+            Key::MaxFrameDelayCount,
+            self.max_frame_delay_count.map(Value::I32),
+        );
+        push(
+            Key::PrioritizeEncodingSpeedOverQuality,
+            self.prioritize_encoding_speed_over_quality.map(Value::Bool),
+        );
+        push(
+            Key::MaximizePowerEfficiency,
+            self.maximize_power_efficiency.map(Value::Bool),
+        );
+        push(Key::ProfileLevel, profile_level.map(Value::ProfileLevel));
+        push(Key::H264EntropyMode, entropy_mode.map(Value::EntropyMode));
+        push(Key::MaxH264SliceBytes, max_slice_bytes.map(Value::I32));
+        push(
+            Key::OutputBitDepth,
+            self.codec
+                .hevc()
+                .and_then(|h| h.output_bit_depth)
+                .map(Value::I32),
+        );
+        push(
+            Key::AlphaChannelMode,
+            alpha_channel_mode.map(Value::AlphaChannelMode),
+        );
+        res
+    }
+
+    /// Checks settings and their combinations, collecting all rejections.
+    pub fn validate(&self) -> Result {
+        let mut errs = Vec::new();
+
+        for (key, _) in self.entries() {
+            if !self.codec.accepts(key) {
+                errs.push(ConfigError::NotApplicable {
+                    key,
+                    codec: self.codec.name(),
+                });
+            }
+        }
+
+        let unit = |v: f64| (0.0..=1.0).contains(&v);
+        let positive = |v: f64| v.is_finite() && v > 0.0;
+        let invalid = [
+            (Key::Quality, self.quality.is_some_and(|v| !unit(v))),
+            (
+                Key::ExpectedFrameRate,
+                self.expected_frame_rate.is_some_and(|v| !positive(v)),
+            ),
+            (
+                Key::AverageBitRate,
+                self.average_bit_rate.is_some_and(|v| v <= 0),
+            ),
+            (
+                Key::ConstantBitRate,
+                self.constant_bit_rate.is_some_and(|v| v <= 0),
+            ),
+            (
+                Key::MaxKeyFrameInterval,
+                self.max_key_frame_interval.is_some_and(|v| v < 0),
+            ),
+            (
+                Key::MaxKeyFrameIntervalDuration,
+                self.max_key_frame_interval_duration
+                    .is_some_and(|v| !v.is_finite() || v < 0.0),
+            ),
+            (
+                Key::MaxFrameDelayCount,
+                self.max_frame_delay_count.is_some_and(|v| v < -1),
+            ),
+            (
+                Key::DataRateLimits,
+                self.data_rate_limits
+                    .iter()
+                    .any(|l| l.bytes <= 0 || !positive(l.secs)),
+            ),
+        ];
+        for (key, invalid) in invalid {
+            if invalid {
+                errs.push(ConfigError::OutOfRange(key));
+            }
+        }
+
+        if self.real_time == Some(true) && self.allow_frame_reordering == Some(true) {
+            errs.push(ConfigError::FrameReorderingInRealTime);
+        }
+        if self.allow_frame_reordering == Some(true) && self.max_frame_delay_count == Some(0) {
+            errs.push(ConfigError::FrameReorderingWithoutDelay);
+        }
+
+        if self.constant_bit_rate.is_some() {
+            if self.average_bit_rate.is_some() {
+                errs.push(ConfigError::ConstantBitRateConflict(Key::AverageBitRate));
+            }
+            if !self.data_rate_limits.is_empty() {
+                errs.push(ConfigError::ConstantBitRateConflict(Key::DataRateLimits));
+            }
+        }
+
+        for (i, limit) in self.data_rate_limits.iter().enumerate() {
+            if self.data_rate_limits[..i]
+                .iter()
+                .any(|l| l.secs == limit.secs)
+            {
+                errs.push(ConfigError::DuplicateDataRateWindow(limit.secs));
+            }
+            if let Some(average) = self.average_bit_rate {
+                let bps = limit.bits_per_sec();
+                if limit.secs > 0.0 && bps < average as f64 {
+                    errs.push(ConfigError::DataRateLimitBelowAverage {
+                        limit: bps,
+                        average,
+                    });
+                }
+            }
+        }
+
+        if let Codec::H264(h264) = &self.codec {
+            if !h264.profile.levels().contains(&h264.level) {
+                errs.push(ConfigError::LevelUnavailable(h264.profile, h264.level));
+            }
+            if h264.entropy_mode == Some(EntropyMode::Cabac) && !h264.profile.supports_cabac() {
+                errs.push(ConfigError::CabacRequiresMainOrHigh(h264.profile));
+            }
+            if self.allow_frame_reordering == Some(true)
+                && !h264.profile.supports_frame_reordering()
+            {
+                errs.push(ConfigError::FrameReorderingUnsupported(h264.profile));
+            }
+            if h264.max_slice_bytes.is_some_and(|v| v <= 0) {
+                errs.push(ConfigError::OutOfRange(Key::MaxH264SliceBytes));
+            }
+        }
+
+        if let Some(hevc) = self.codec.hevc() {
+            if let Some(depth) = hevc.output_bit_depth {
+                if depth != 8 && depth != 10 {
+                    errs.push(ConfigError::OutOfRange(Key::OutputBitDepth));
+                } else if !hevc.profile.bit_depths().contains(&depth) {
+                    errs.push(ConfigError::BitDepthUnsupported {
+                        profile: hevc.profile,
+                        depth,
+                    });
+                }
+            }
+        }
+        if let Codec::HevcWithAlpha {
+            target_quality_for_alpha: Some(q),
+            ..
+        } = &self.codec
+        {
+            if !unit(*q) {
+                errs.push(ConfigError::OutOfRange(Key::TargetQualityForAlpha));
+            }
+        }
+
+        if errs.is_empty() {
+            Ok(())
+        } else {
+            Err(errs)
+        }
+    }
+
+    /// Rejects keys the encoder doesn't support.
+    ///
+    /// Use [`supported_keys`] to get supported keys of a session.
+    pub fn check_supported(&self, is_supported: impl Fn(Key) -> bool) -> Result {
+        let errs: Vec<_> = self
+            .entries()
+            .into_iter()
+            .filter(|(key, _)| !is_supported(*key))
+            .map(|(key, _)| ConfigError::Unsupported(key))
+            .collect();
+        if errs.is_empty() {
+            Ok(())
+        } else {
+            Err(errs)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::vt::compression::config::*;
+
+    #[test]
+    fn h264() {
+        let mut config = EncoderConfig::h264(H264Profile::High, H264Level::L4_1);
+        config.real_time = Some(true);
+        config.average_bit_rate = Some(4_000_000);
+        config.data_rate_limits.push(DataRateLimit {
+            bytes: 1_000_000,
+            secs: 1.0,
+        });
+        config.h264_mut().unwrap().entropy_mode = Some(EntropyMode::Cabac);
+        config.validate().unwrap();
+
+        let keys: Vec<_> = config.entries().into_iter().map(|(k, _)| k).collect();
+        assert_eq!(
+            keys,
+            [
+                Key::RealTime,
+                Key::AverageBitRate,
+                Key::DataRateLimits,
+                Key::ProfileLevel,
+                Key::H264EntropyMode
+            ]
+        );
+        assert_eq!(
+            ProfileLevel::H264(H264Profile::High, H264Level::L4_1).to_string(),
+            "H264_High_4_1"
+        );
+
+        let h264 = config.h264_mut().unwrap();
+        h264.profile = H264Profile::Baseline;
+        h264.level = H264Level::L1_3;
+        config.allow_frame_reordering = Some(true);
+        config.average_bit_rate = Some(10_000_000);
+        let errs = config.validate().unwrap_err();
+        assert_eq!(
+            errs,
+            [
+                ConfigError::FrameReorderingInRealTime,
+                ConfigError::DataRateLimitBelowAverage {
+                    limit: 8_000_000.0,
+                    average: 10_000_000
+                },
+                ConfigError::CabacRequiresMainOrHigh(H264Profile::Baseline),
+                ConfigError::FrameReorderingUnsupported(H264Profile::Baseline),
+            ]
+        );
+
+        let mut config = EncoderConfig::h264(H264Profile::Main, H264Level::L1_3);
+        config.allow_open_gop = Some(false);
+        assert_eq!(
+            config.validate().unwrap_err(),
+            [
+                ConfigError::NotApplicable {
+                    key: Key::AllowOpenGop,
+                    codec: "H.264"
+                },
+                ConfigError::LevelUnavailable(H264Profile::Main, H264Level::L1_3),
+            ]
+        );
+    }
+
+    #[test]
+    fn rates() {
+        let mut config = EncoderConfig::hevc(HevcProfile::Main);
+        config.quality = Some(1.5);
+        config.constant_bit_rate = Some(2_000_000);
+        config.average_bit_rate = Some(2_000_000);
+        config.data_rate_limits = vec![
+            DataRateLimit {
+                bytes: 500_000,
+                secs: 1.0,
+            },
+            DataRateLimit {
+                bytes: 600_000,
+                secs: 1.0,
+            },
+        ];
+        let errs = config.validate().unwrap_err();
+        assert_eq!(
+            errs,
+            [
+                ConfigError::OutOfRange(Key::Quality),
+                ConfigError::ConstantBitRateConflict(Key::AverageBitRate),
+                ConfigError::ConstantBitRateConflict(Key::DataRateLimits),
+                ConfigError::DuplicateDataRateWindow(1.0),
+            ]
+        );
+        assert_eq!(
+            errs[1].to_string(),
+            "constant bit rate can't be combined with kVTCompressionPropertyKey_AverageBitRate"
+        );
+
+        let mut config = EncoderConfig::hevc(HevcProfile::Main);
+        config.expected_frame_rate = Some(f64::NAN);
+        config.max_key_frame_interval_duration = Some(f64::INFINITY);
+        config.data_rate_limits = vec![DataRateLimit {
+            bytes: 500_000,
+            secs: f64::NAN,
+        }];
+        assert_eq!(
+            config.validate().unwrap_err(),
+            [
+                ConfigError::OutOfRange(Key::ExpectedFrameRate),
+                ConfigError::OutOfRange(Key::MaxKeyFrameIntervalDuration),
+                ConfigError::OutOfRange(Key::DataRateLimits),
+            ]
+        );
+
+        config.expected_frame_rate = Some(30.0);
+        config.max_key_frame_interval_duration = Some(0.0);
+        config.data_rate_limits[0].secs = 1.0;
+        assert_eq!(config.validate(), Ok(()));
+    }
+
+    #[test]
+    fn hevc() {
+        let mut config = EncoderConfig::hevc(HevcProfile::Main);
+        config.hevc_mut().unwrap().output_bit_depth = Some(10);
+        assert_eq!(
+            config.validate().unwrap_err(),
+            [ConfigError::BitDepthUnsupported {
+                profile: HevcProfile::Main,
+                depth: 10
+            }]
+        );
+        config.hevc_mut().unwrap().profile = HevcProfile::Main10;
+        config.validate().unwrap();
+
+        let mut config =
+            EncoderConfig::hevc_with_alpha(HevcProfile::Main, AlphaChannelMode::Premultiplied);
+        if let Codec::HevcWithAlpha {
+            target_quality_for_alpha,
+            ..
+        } = &mut config.codec
+        {
+            *target_quality_for_alpha = Some(0.75);
+        }
+        config.validate().unwrap();
+        assert_eq!(config.codec.video_codec(), cm::VideoCodec::HEVC_WITH_ALPHA);
+        assert_eq!(
+            config.entries().last(),
+            Some(&(
+                Key::AlphaChannelMode,
+                Value::AlphaChannelMode(AlphaChannelMode::Premultiplied)
+            ))
+        );
+
+        let res = config.check_supported(|key| key != Key::TargetQualityForAlpha);
+        assert_eq!(
+            res.unwrap_err(),
+            [ConfigError::Unsupported(Key::TargetQualityForAlpha)]
+        );
+    }
+
+    #[test]
+    fn pro_res() {
+        let mut config = EncoderConfig::pro_res(ProResFlavor::Hq);
+        config.real_time = Some(false);
+        config.quality = Some(0.9);
+        config.validate().unwrap();
+        assert!(config
+            .entries()
+            .iter()
+            .all(|(k, _)| *k != Key::ProfileLevel));
+
+        config.average_bit_rate = Some(100_000_000);
+        config.max_key_frame_interval = Some(1);
+        let errs = config.validate().unwrap_err();
+        assert_eq!(errs.len(), 2);
+        assert_eq!(
+            errs[0].to_string(),
+            "kVTCompressionPropertyKey_MaxKeyFrameInterval is not applicable to ProRes"
+        );
+    }
+}
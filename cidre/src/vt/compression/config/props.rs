@@ -0,0 +1,178 @@
+use crate::{
+    arc, cf, vt,
+    vt::compression::{alpha_channel_mode, h264_entropy_mode, keys, profile_level},
+};
+
+use super::{
+    AlphaChannelMode, ConfigError, EncoderConfig, EntropyMode, H264Level, H264Profile, HevcProfile,
+    Key, ProfileLevel, Result, Value,
+};
+
+impl Key {
+    pub fn cf_key(&self) -> &'static cf::String {
+        match self {
+            Self::RealTime => keys::real_time(),
+            Self::AllowFrameReordering => keys::allow_frame_reordering(),
+            Self::AllowTemporalCompression => keys::allow_temporal_compression(),
+            Self::AllowOpenGop => keys::allow_open_gop(),
+            Self::MaxKeyFrameInterval => keys::max_key_frame_interval(),
+            Self::MaxKeyFrameIntervalDuration => keys::max_key_frame_interval_duration(),
+            Self::AverageBitRate => keys::avarage_bit_rate(),
+            Self::DataRateLimits => keys::data_rate_limits(),
+            Self::ConstantBitRate => keys::constant_bit_rate(),
+            Self::Quality => keys::quality(),
+            Self::TargetQualityForAlpha => keys::target_quality_for_alpha(),
+            Self::ExpectedFrameRate => keys::expected_frame_rate(),
+            Self::MaxFrameDelayCount => keys::max_frame_delay_count(),
+            Self::PrioritizeEncodingSpeedOverQuality => {
+                keys::prioritize_encoding_speed_over_quality()
+            }
+            Self::MaximizePowerEfficiency => keys::maximize_power_efficiecy(),
+            Self::ProfileLevel => keys::profile_lvl(),
+            Self::H264EntropyMode => keys::h264_entropy_mode(),
+            Self::MaxH264SliceBytes => keys::max_h264_slice_bytes(),
+            Self::OutputBitDepth => keys::output_bit_depth(),
+            Self::AlphaChannelMode => keys::alpha_channel_mode(),
+        }
+    }
+}
+
+impl ProfileLevel {
+    /// `kVTProfileLevel_*` constant, `None` if profile has no such level.
+    pub fn cf_value(&self) -> Option<&'static cf::String> {
+        use profile_level::{h264, hevc};
+        use H264Level as L;
+        use H264Profile as P;
+        let val = match self {
+            Self::Hevc(HevcProfile::Main) => hevc::main_auto_lvl(),
+            Self::Hevc(HevcProfile::Main10) => hevc::main10_auto_lvl(),
+            Self::Hevc(HevcProfile::Main42210) => hevc::main42210_auto_lvl(),
+            Self::H264(P::Baseline, l) => match l {
+                L::Auto => h264::baseline_auto_lvl(),
+                L::L1_3 => h264::baseline_1_3(),
+                L::L3_0 => h264::baseline_3_0(),
+                L::L3_1 => h264::baseline_3_1(),
+                L::L3_2 => h264::baseline_3_2(),
+                L::L4_0 => h264::baseline_4_0(),
+                L::L4_1 => h264::baseline_4_1(),
+                L::L4_2 => h264::baseline_4_2(),
+                L::L5_0 => h264::baseline_5_0(),
+                L::L5_1 => h264::baseline_5_1(),
+                L::L5_2 => h264::baseline_5_2(),
+            },
+            Self::H264(P::Main, l) => match l {
+                L::Auto => h264::main_auto_lvl(),
+                L::L3_0 => h264::main_3_0(),
+                L::L3_1 => h264::main_3_1(),
+                L::L3_2 => h264::main_3_2(),
+                L::L4_0 => h264::main_4_0(),
+                L::L4_1 => h264::main_4_1(),
+                L::L4_2 => h264::main_4_2(),
+                L::L5_0 => h264::main_5_0(),
+                L::L5_1 => h264::main_5_1(),
+                L::L5_2 => h264::main_5_2(),
+                L::L1_3 => return None,
+            },
+            Self::H264(P::High, l) => match l {
+                L::Auto => h264::high_auto_lvl(),
+                L::L3_0 => h264::high_3_0(),
+                L::L3_1 => h264::high_3_1(),
+                L::L3_2 => h264::high_3_2(),
+                L::L4_0 => h264::high_4_0(),
+                L::L4_1 => h264::high_4_1(),
+                L::L4_2 => h264::high_4_2(),
+                L::L5_0 => h264::high_5_0(),
+                L::L5_1 => h264::high_5_1(),
+                L::L5_2 => h264::high_5_2(),
+                L::L1_3 => return None,
+            },
+            Self::H264(P::Extended, L::Auto) => h264::extended_auto_lvl(),
+            Self::H264(P::Extended, L::L5_0) => h264::extended_5_0(),
+            Self::H264(P::ConstrainedBaseline, L::Auto) => h264::constrained_baseline_auto_lvl(),
+            Self::H264(P::ConstrainedHigh, L::Auto) => h264::constrained_high_auto_lvl(),
+            Self::H264(..) => return None,
+        };
+        Some(val)
+    }
+}
+
+impl Value {
+    /// Property value, `None` for unavailable profile level.
+    pub fn to_cf(&self) -> Option<arc::R<cf::Type>> {
+        use arc::Retain;
+        let val = match self {
+            Self::Bool(v) => <&cf::Boolean>::from(*v).as_type_ref().retained(),
+            Self::I32(v) => cf::Number::from_i32(*v).as_type_ref().retained(),
+            Self::I64(v) => cf::Number::from_i64(*v).as_type_ref().retained(),
+            Self::F64(v) => cf::Number::from_f64(*v).as_type_ref().retained(),
+            Self::DataRateLimits(limits) => {
+                // [bytes, secs, bytes, secs, ...]
+                let nums: Vec<_> = limits
+                    .iter()
+                    .flat_map(|l| [cf::Number::from_i64(l.bytes), cf::Number::from_f64(l.secs)])
+                    .collect();
+                cf::ArrayOf::from_retained_slice(&nums)?
+                    .as_type_ref()
+                    .retained()
+            }
+            Self::ProfileLevel(pl) => pl.cf_value()?.as_type_ref().retained(),
+            Self::EntropyMode(EntropyMode::Cavlc) => {
+                h264_entropy_mode::cavlc().as_type_ref().retained()
+            }
+            Self::EntropyMode(EntropyMode::Cabac) => {
+                h264_entropy_mode::cabac().as_type_ref().retained()
+            }
+            Self::AlphaChannelMode(AlphaChannelMode::Straight) => {
+                alpha_channel_mode::straight_alpha()
+                    .as_type_ref()
+                    .retained()
+            }
+            Self::AlphaChannelMode(AlphaChannelMode::Premultiplied) => {
+                alpha_channel_mode::premultiplied_alpha()
+                    .as_type_ref()
+                    .retained()
+            }
+        };
+        Some(val)
+    }
+}
+
+/// Keys listed in `supported_props` of a compression session.
+pub fn supported_keys(supported: &cf::Dictionary) -> Vec<Key> {
+    Key::ALL
+        .into_iter()
+        .filter(|key| supported.contains_key(key.cf_key()))
+        .collect()
+}
+
+impl EncoderConfig {
+    /// Validates and serializes the config into a properties dictionary.
+    pub fn to_props(&self) -> Result<arc::R<cf::DictionaryMut>> {
+        self.validate()?;
+        let entries = self.entries();
+        let mut props = cf::DictionaryMut::with_capacity(entries.len());
+        for (key, val) in entries {
+            let Some(val) = val.to_cf() else {
+                return Err(vec![ConfigError::OutOfRange(key)]);
+            };
+            props.insert(key.cf_key(), &val);
+        }
+        Ok(props)
+    }
+
+    /// Rejects keys absent in `supported` dictionary of [`vt::Session::supported_props`].
+    pub fn check_supported_props(&self, supported: &cf::Dictionary) -> Result {
+        let keys = supported_keys(supported);
+        self.check_supported(|key| keys.contains(&key))
+    }
+}
+
+impl vt::CompressionSession {
+    /// Validates `config` against the session encoder and sets its properties.
+    pub fn set_config(&mut self, config: &EncoderConfig) -> Result {
+        let supported = self.supported_props().map_err(|e| vec![e.into()])?;
+        config.check_supported_props(&supported)?;
+        let props = config.to_props()?;
+        self.set_props(&props).map_err(|e| vec![e.into()])
+    }
+}
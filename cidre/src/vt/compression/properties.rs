@@ -672,6 +672,28 @@ pub mod hdr_metadata_insertion_mode {
     }
 }
 
+pub mod alpha_channel_mode {
+    use crate::cf;
+
+    #[doc(alias = "kVTAlphaChannelMode_StraightAlpha")]
+    #[inline]
+    pub fn straight_alpha() -> &'static cf::String {
+        unsafe { kVTAlphaChannelMode_StraightAlpha }
+    }
+
+    #[doc(alias = "kVTAlphaChannelMode_PremultipliedAlpha")]
+    #[inline]
+    pub fn premultiplied_alpha() -> &'static cf::String {
+        unsafe { kVTAlphaChannelMode_PremultipliedAlpha }
+    }
+
+    #[link(name = "VideoToolbox", kind = "framework")]
+    extern "C" {
+        static kVTAlphaChannelMode_StraightAlpha: &'static cf::String;
+        static kVTAlphaChannelMode_PremultipliedAlpha: &'static cf::String;
+    }
+}
+
 pub mod profile_level {
 
     pub mod hevc {
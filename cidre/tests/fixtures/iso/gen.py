@@ -0,0 +1,118 @@
+"""Generates ISO BMFF test fixtures: `python3 gen.py` in this directory.
+
+Files are synthetic but follow the layout of real encoder output:
+`init.mp4` and `segment.m4s` mimic a CMAF H.264/AAC stream as written by
+AVAssetWriter or `ffmpeg -f mp4 -movflags cmaf`, `progressive.mp4` a
+non fragmented HEVC/Opus file with `co64`, `ctts` and `stss` tables.
+Sample payloads are filled with their index so tests can check offsets.
+"""
+import struct
+def box(t, body): return struct.pack('>I', 8 + len(body)) + t + body
+def full(t, v, f, body): return box(t, struct.pack('>I', (v << 24) | f) + body)
+u8=lambda v: struct.pack('>B', v); u16=lambda v: struct.pack('>H', v); u32=lambda v: struct.pack('>I', v)
+i32=lambda v: struct.pack('>i', v); u64=lambda v: struct.pack('>Q', v)
+MATRIX = b''.join(u32(x) for x in [0x10000,0,0,0,0x10000,0,0,0,0x40000000])
+def mvhd(ts, dur, next_id):
+    return full(b'mvhd', 0, 0, u32(0)+u32(0)+u32(ts)+u32(dur)+u32(0x10000)+u16(0x100)+b'\0'*10+MATRIX+b'\0'*24+u32(next_id))
+def tkhd(tid, dur, w, h, vol):
+    return full(b'tkhd', 0, 3, u32(0)+u32(0)+u32(tid)+u32(0)+u32(dur)+b'\0'*8+u16(0)+u16(0)+u16(vol)+u16(0)+MATRIX+u32(w<<16)+u32(h<<16))
+def mdhd(ts, dur): return full(b'mdhd', 0, 0, u32(0)+u32(0)+u32(ts)+u32(dur)+u16(0x55c4)+u16(0))
+def hdlr(t, name): return full(b'hdlr', 0, 0, u32(0)+t+b'\0'*12+name+b'\0')
+def dinf(): return box(b'dinf', full(b'dref', 0, 0, u32(1)+full(b'url ', 0, 1, b'')))
+def stsd(*entries): return full(b'stsd', 0, 0, u32(len(entries))+b''.join(entries))
+def visual(t, w, h, *children):
+    name = b'\x0bcidre video'.ljust(32, b'\0')
+    return box(t, b'\0'*6+u16(1)+b'\0'*16+u16(w)+u16(h)+u32(0x480000)+u32(0x480000)+u32(0)+u16(1)+name+u16(0x18)+struct.pack('>h',-1)+b''.join(children))
+def audio(t, ch, rate, *children):
+    return box(t, b'\0'*6+u16(1)+b'\0'*8+u16(ch)+u16(16)+u16(0)+u16(0)+u32(rate<<16)+b''.join(children))
+SPS = bytes.fromhex('6764001facd9405005bb0110000003001000000303c0f1831960')
+PPS = bytes.fromhex('68ebe3cb22c0')
+avcc = box(b'avcC', bytes([1, 0x64, 0, 0x1f, 0xff, 0xe1])+u16(len(SPS))+SPS+u8(1)+u16(len(PPS))+PPS)
+pasp = box(b'pasp', u32(1)+u32(1))
+def descr(tag, body): return bytes([tag, len(body)]) + body
+ASC = bytes([0x11, 0x90])
+dec_cfg = descr(4, bytes([0x40, 0x15, 0, 0, 0])+u32(128000)+u32(128000)+descr(5, ASC))
+esds = full(b'esds', 0, 0, descr(3, u16(0)+u8(0)+dec_cfg+descr(6, b'\x02')))
+def empty_tables():
+    return full(b'stts',0,0,u32(0))+full(b'stsc',0,0,u32(0))+full(b'stsz',0,0,u32(0)+u32(0))+full(b'stco',0,0,u32(0))
+def trak(tid, handler, ts, dur, entry, tables, w=0, h=0, vol=0):
+    mh = full(b'vmhd',0,1,b'\0'*8) if handler==b'vide' else full(b'smhd',0,0,b'\0'*4)
+    stbl = box(b'stbl', stsd(entry)+tables)
+    minf = box(b'minf', mh+dinf()+stbl)
+    mdia = box(b'mdia', mdhd(ts, dur)+hdlr(handler, b'cidre '+handler)+minf)
+    return box(b'trak', tkhd(tid, dur, w, h, vol)+mdia)
+def trex(tid, dur, size, flags): return full(b'trex',0,0,u32(tid)+u32(1)+u32(dur)+u32(size)+u32(flags))
+
+# init segment
+ftyp = box(b'ftyp', b'iso6'+u32(0)+b'iso6mp41dashcmfc')
+moov = box(b'moov', mvhd(1000, 0, 3)
+    + trak(1, b'vide', 90000, 0, visual(b'avc1', 640, 360, avcc, pasp), empty_tables(), 640, 360)
+    + trak(2, b'soun', 48000, 0, audio(b'mp4a', 2, 48000, esds), empty_tables(), vol=0x100)
+    + box(b'mvex', trex(1, 3000, 0, 0x10000) + trex(2, 1024, 0, 0)))
+open('init.mp4','wb').write(ftyp+moov)
+
+# media segment of track 1: two CMAF chunks (moof + mdat) of 4 frames each,
+# 30 fps at 90 kHz, IDR first, I P B B reordering with 2 frames of delay
+# like x264 with default B-frames, so presentation times are unique
+sizes = [40, 12, 8, 8, 14, 8, 8, 12]
+decode_to_frame = [0, 3, 1, 2, 6, 4, 5, 7]
+cts = [6000 + 3000 * (f - d) for d, f in enumerate(decode_to_frame)]
+def chunk(seq, first, data_offset):
+    n = range(first, first + 4)
+    mfhd = full(b'mfhd',0,0,u32(seq))
+    tfhd = full(b'tfhd',0,0x020000|0x8|0x20, u32(1)+u32(3000)+u32(0x10000))
+    tfdt = full(b'tfdt',1,0,u64(180000 + 3000 * first))
+    flags = 0x1|0x200|0x800
+    trun_body = u32(4)+i32(data_offset)
+    if first == 0:
+        # sync first sample, defaults mark others as non sync
+        flags |= 0x4
+        trun_body += u32(0x02000000)
+    for i in n:
+        trun_body += u32(sizes[i])+i32(cts[i])
+    trun = full(b'trun',1,flags, trun_body)
+    return box(b'moof', mfhd+box(b'traf', tfhd+tfdt+trun))
+def chunk_with_mdat(seq, first):
+    moof = chunk(seq, first, 0)
+    moof = chunk(seq, first, len(moof)+8)
+    payload = b''.join(bytes([i+1])*sizes[i] for i in range(first, first + 4))
+    return moof + box(b'mdat', payload)
+chunks = chunk_with_mdat(7, 0) + chunk_with_mdat(8, 4)
+styp = box(b'styp', b'msdh'+u32(0)+b'msdhmsix')
+sidx = full(b'sidx',1,0,u32(1)+u32(90000)+u64(186000)+u64(0)+u16(0)+u16(1)+u32(len(chunks))+u32(24000)+u32(0x90000000))
+emsg = full(b'emsg',1,0,u32(90000)+u64(186000)+u32(3000)+u32(42)+b'urn:scte:scte35:2013:bin\0'+b'1\0'+b'\xfc\x30')
+open('segment.m4s','wb').write(styp+sidx+emsg+chunks)
+
+# progressive file: Opus audio + HEVC video, sample tables, co64, ctts, stss
+VPS = bytes([0x40,1,0x0c,1,0xff,0xff,1,0x60,0,0,3,0,0xb0,0,0,3,0,0,3,0,0x5d,0x95,0x98,0x09])
+HSPS = bytes([0x42,1,1,1,0x60,0,0,3,0,0xb0,0,0,3,0,0,3,0,0x5d,0xa0,2,0x80,0x80,0x2d,0x16,0x59,0x59,0xa4,0x93,0x2b,0xc0,0x5a])
+HPPS = bytes([0x44,1,0xc1,0x72,0xb4,0x62,0x40])
+hvcc = box(b'hvcC', bytes([1, 1, 0x60,0,0,0, 0xb0,0,0,0,0,0, 0x5d, 0xf0,0, 0xfc, 0xfd, 0xf8, 0xf8, 0,0, 0x0f, 3])
+    + b''.join(bytes([0x80|t])+u16(1)+u16(len(n))+n for t, n in [(32, VPS), (33, HSPS), (34, HPPS)]))
+dops = box(b'dOps', u8(0)+u8(2)+u16(312)+u32(48000)+struct.pack('>h', 0)+u8(0))
+asizes = [5, 6, 7]
+vsizes = [30, 10, 11]
+# chunk layout in mdat: audio chunk0 (2 samples), video chunk0 (3 samples), audio chunk1 (1 sample)
+def build(mdat_start):
+    a0 = mdat_start + 8
+    v0 = a0 + asizes[0] + asizes[1]
+    a1 = v0 + sum(vsizes)
+    atables = (full(b'stts',0,0,u32(1)+u32(3)+u32(960))
+        + full(b'stsc',0,0,u32(2)+u32(1)+u32(2)+u32(1)+u32(2)+u32(1)+u32(1))
+        + full(b'stsz',0,0,u32(0)+u32(3)+b''.join(u32(s) for s in asizes))
+        + full(b'stco',0,0,u32(2)+u32(a0)+u32(a1)))
+    vtables = (full(b'stts',0,0,u32(1)+u32(3)+u32(1000))
+        + full(b'ctts',0,0,u32(3)+u32(1)+u32(1000)+u32(1)+u32(2000)+u32(1)+u32(0))
+        + full(b'stss',0,0,u32(1)+u32(1))
+        + full(b'stsc',0,0,u32(1)+u32(1)+u32(3)+u32(1))
+        + full(b'stsz',0,0,u32(0)+u32(3)+b''.join(u32(s) for s in vsizes))
+        + full(b'co64',0,0,u32(1)+u64(v0)))
+    moov = box(b'moov', mvhd(1000, 60, 3)
+        + trak(1, b'soun', 48000, 2880, audio(b'Opus', 2, 48000, dops), atables, vol=0x100)
+        + trak(2, b'vide', 30000, 3000, visual(b'hvc1', 320, 240, hvcc), vtables, 320, 240))
+    return moov
+ftyp = box(b'ftyp', b'isom'+u32(0x200)+b'isomiso2mp41')
+moov = build(0)
+moov = build(len(ftyp)+len(moov))
+data = bytes([0xa0]*5 + [0xa1]*6) + bytes([0xb0]*30+[0xb1]*10+[0xb2]*11) + bytes([0xa2]*7)
+open('progressive.mp4','wb').write(ftyp+moov+box(b'mdat', data))